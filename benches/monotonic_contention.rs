@@ -0,0 +1,67 @@
+//! Throughput of `TimeBase::now_ms()` under thread contention, comparing the
+//! global and per-shard monotonic clamps.
+//!
+//! Run with `cargo bench --bench monotonic_contention`; set `BENCH_THREADS`
+//! (default: available cores) and `BENCH_SECS` (default: 2) to adjust.
+
+use ntp_time_json_api::config::MonotonicScope;
+use ntp_time_json_api::timebase::TimeBase;
+use std::hint::black_box;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+fn env_usize(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+fn run(scope: MonotonicScope, threads: usize, duration: Duration) -> f64 {
+    let timebase = TimeBase::new(true).with_monotonic_scope(scope);
+    timebase.set_manual(1_700_000_000_000, 3600);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let total = Arc::new(AtomicU64::new(0));
+    let handles: Vec<_> = (0..threads)
+        .map(|_| {
+            let timebase = timebase.clone();
+            let stop = stop.clone();
+            let total = total.clone();
+            std::thread::spawn(move || {
+                let mut ops = 0u64;
+                while !stop.load(Ordering::Relaxed) {
+                    for _ in 0..1024 {
+                        black_box(timebase.now_ms());
+                    }
+                    ops += 1024;
+                }
+                total.fetch_add(ops, Ordering::Relaxed);
+            })
+        })
+        .collect();
+
+    let started = Instant::now();
+    std::thread::sleep(duration);
+    stop.store(true, Ordering::Relaxed);
+    for handle in handles {
+        handle.join().expect("bench thread panicked");
+    }
+    total.load(Ordering::Relaxed) as f64 / started.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let threads = env_usize("BENCH_THREADS", cores);
+    let duration = Duration::from_secs(env_usize("BENCH_SECS", 2) as u64);
+
+    println!("now_ms() throughput, {threads} threads, {duration:?} per scope");
+    for (name, scope) in [
+        ("global", MonotonicScope::Global),
+        ("shard", MonotonicScope::Shard),
+    ] {
+        let ops = run(scope, threads, duration);
+        println!("{name:>7}: {:>14.0} ops/sec", ops);
+    }
+}
@@ -0,0 +1,17 @@
+//! Generates the gRPC service from `proto/time_service.proto` and the HTTP
+//! protobuf bodies from `proto/time.proto`.  protoc comes from
+//! `protoc-bin-vendored`, so building needs no system protobuf compiler.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if std::env::var_os("PROTOC").is_none() {
+        // SAFETY: build scripts are single-threaded.
+        unsafe { std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?) };
+    }
+    tonic_prost_build::configure()
+        .build_client(true)
+        .compile_protos(
+            &["proto/time_service.proto", "proto/time.proto"],
+            &["proto"],
+        )?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Shadow comparison of the host clock against NTP time.
+//!
+//! Every system-clock check appends (NTP time, host − NTP divergence) to a
+//! bounded series covering `SYSTEM_CLOCK_SHADOW_WINDOW_SECS`.  `GET
+//! /report/clock-drift` summarises it as min/max/mean and percentiles, which
+//! is the evidence needed when a host clock, not NTP, is what's wrong.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Samples retained per check interval across the window, plus slack for
+/// ticker catch-up bursts.
+const CAPACITY_SLACK: usize = 16;
+
+/// Bounded time series of host-clock divergence samples.
+#[derive(Debug)]
+pub struct ClockDriftSeries {
+    window_ms: i64,
+    capacity: usize,
+    /// (NTP epoch ms, host − NTP ms), oldest first.
+    samples: VecDeque<(i64, i64)>,
+}
+
+/// Distribution of signed or absolute divergence values (ms).
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DivergenceStats {
+    pub min: i64,
+    pub max: i64,
+    pub mean: f64,
+    pub p50: i64,
+    pub p90: i64,
+    pub p99: i64,
+}
+
+/// Summary served by `/report/clock-drift`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClockDriftReport {
+    pub enabled: bool,
+    pub window_secs: u64,
+    pub samples: usize,
+    /// NTP time (ms) of the oldest and newest sample in the window.
+    pub first_sample_ms: Option<i64>,
+    pub last_sample_ms: Option<i64>,
+    /// Host − NTP (ms); positive = host clock ahead.
+    pub divergence_ms: Option<DivergenceStats>,
+    /// |host − NTP| (ms), for "how far off is it" regardless of direction.
+    pub abs_divergence_ms: Option<DivergenceStats>,
+    /// `SYSTEM_CLOCK_DIVERGENCE_ALERT_MS`; 0 when alerting is disabled.
+    pub alert_threshold_ms: u64,
+    /// Samples whose absolute divergence exceeded the alert threshold.
+    pub samples_over_threshold: usize,
+}
+
+impl ClockDriftSeries {
+    /// A series covering `window_secs` of checks taken every
+    /// `check_interval_secs`.  A zero window disables recording.
+    pub fn new(window_secs: u64, check_interval_secs: u64) -> Self {
+        let capacity = if window_secs == 0 {
+            0
+        } else {
+            (window_secs / check_interval_secs.max(1)) as usize + CAPACITY_SLACK
+        };
+        Self {
+            window_ms: window_secs as i64 * 1000,
+            capacity,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Append a sample taken at NTP time `at_ms`, evicting samples older
+    /// than the window and, if still full, the oldest one.
+    pub fn record(&mut self, at_ms: i64, divergence_ms: i64) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.evict_before(at_ms - self.window_ms);
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((at_ms, divergence_ms));
+    }
+
+    /// Summarise samples within the window ending at NTP time `now_ms`.
+    pub fn report(&self, now_ms: i64, alert_threshold_ms: u64) -> ClockDriftReport {
+        let cutoff = now_ms - self.window_ms;
+        let in_window: Vec<(i64, i64)> = self
+            .samples
+            .iter()
+            .copied()
+            .filter(|(at, _)| *at >= cutoff)
+            .collect();
+        let signed: Vec<i64> = in_window.iter().map(|(_, d)| *d).collect();
+        let absolute: Vec<i64> = signed.iter().map(|d| d.abs()).collect();
+        let samples_over_threshold = if alert_threshold_ms == 0 {
+            0
+        } else {
+            absolute
+                .iter()
+                .filter(|d| d.unsigned_abs() > alert_threshold_ms)
+                .count()
+        };
+
+        ClockDriftReport {
+            enabled: self.is_enabled(),
+            window_secs: (self.window_ms / 1000) as u64,
+            samples: in_window.len(),
+            first_sample_ms: in_window.first().map(|(at, _)| *at),
+            last_sample_ms: in_window.last().map(|(at, _)| *at),
+            divergence_ms: stats(signed),
+            abs_divergence_ms: stats(absolute),
+            alert_threshold_ms,
+            samples_over_threshold,
+        }
+    }
+
+    fn evict_before(&mut self, cutoff_ms: i64) {
+        while self.samples.front().is_some_and(|(at, _)| *at < cutoff_ms) {
+            self.samples.pop_front();
+        }
+    }
+}
+
+/// Nearest-rank distribution of `values`; `None` when empty.
+fn stats(mut values: Vec<i64>) -> Option<DivergenceStats> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let rank = |p: f64| {
+        let idx = ((p * values.len() as f64).ceil() as usize).clamp(1, values.len()) - 1;
+        values[idx]
+    };
+    Some(DivergenceStats {
+        min: values[0],
+        max: values[values.len() - 1],
+        mean: values.iter().sum::<i64>() as f64 / values.len() as f64,
+        p50: rank(0.50),
+        p90: rank(0.90),
+        p99: rank(0.99),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarises_window_and_evicts_old_samples() {
+        let mut series = ClockDriftSeries::new(100, 1);
+        let start = 1_700_000_000_000;
+        for i in 0..100 {
+            // Host clock drifts from 1 ms behind to 100 ms ahead; odd samples negative.
+            let divergence = if i % 2 == 0 { i + 1 } else { -(i + 1) };
+            series.record(start + i * 1000, divergence);
+        }
+
+        let report = series.report(start + 99_000, 50);
+        assert_eq!(report.samples, 100);
+        let signed = report.divergence_ms.unwrap();
+        assert_eq!((signed.min, signed.max), (-100, 99));
+        let absolute = report.abs_divergence_ms.unwrap();
+        assert_eq!(absolute.p50, 50);
+        assert_eq!(absolute.p90, 90);
+        assert_eq!(absolute.p99, 99);
+        assert_eq!(report.samples_over_threshold, 50);
+
+        // 150 s later only the newest 50 samples are inside the window.
+        series.record(start + 150_000, 0);
+        let report = series.report(start + 150_000, 0);
+        assert_eq!(report.first_sample_ms, Some(start + 50_000));
+        assert_eq!(report.samples, 51);
+        assert_eq!(report.samples_over_threshold, 0);
+    }
+
+    #[test]
+    fn zero_window_disables_recording() {
+        let mut series = ClockDriftSeries::new(0, 10);
+        series.record(1_000, 5);
+        let report = series.report(1_000, 0);
+        assert!(!report.enabled);
+        assert_eq!(report.samples, 0);
+        assert!(report.divergence_ms.is_none());
+    }
+}
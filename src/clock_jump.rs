@@ -0,0 +1,145 @@
+//! Suspend/resume detection.
+//!
+//! The time base projects NTP time with `Instant` (`CLOCK_MONOTONIC`), which
+//! stops while the host is suspended and may stall across a VM pause or live
+//! migration.  Afterwards the projection lags true time by the gap, and the
+//! last sync still looks fresh because its age is measured on the same clock.
+//!
+//! [`ClockJumpDetector`] samples `Instant` and `CLOCK_BOOTTIME` (which keeps
+//! counting through suspend) every `CLOCK_JUMP_CHECK_INTERVAL_SECS`.  When
+//! boot time advanced more than `CLOCK_JUMP_THRESHOLD_MS` beyond monotonic
+//! time between two samples, the monotonic clock missed time: the caller
+//! marks served time stale and resyncs at once.  The wall clock is not
+//! compared: the time base never reads it, so a host-side step is not ours
+//! to correct.
+
+use std::time::Instant;
+
+/// A detected suspend: how far `CLOCK_BOOTTIME` moved beyond `Instant` (ms).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockJump {
+    pub gap_ms: i64,
+}
+
+/// Readings of both clocks taken together.
+#[derive(Debug, Clone, Copy)]
+pub struct ClockSample {
+    monotonic: Instant,
+    /// `CLOCK_BOOTTIME` in ms; `None` where it cannot be read.
+    boot_ms: Option<i64>,
+}
+
+impl ClockSample {
+    pub fn now() -> Self {
+        Self {
+            monotonic: Instant::now(),
+            boot_ms: boot_time_ms(),
+        }
+    }
+}
+
+/// Compares successive [`ClockSample`]s.
+#[derive(Debug)]
+pub struct ClockJumpDetector {
+    threshold_ms: u64,
+    last: ClockSample,
+}
+
+impl ClockJumpDetector {
+    pub fn new(threshold_ms: u64) -> Self {
+        Self {
+            threshold_ms,
+            last: ClockSample::now(),
+        }
+    }
+
+    /// Take a sample and compare it with the previous one.
+    pub fn check(&mut self) -> Option<ClockJump> {
+        self.observe(ClockSample::now())
+    }
+
+    /// Compare `sample` with the previous one and keep it for the next call.
+    fn observe(&mut self, sample: ClockSample) -> Option<ClockJump> {
+        let last = std::mem::replace(&mut self.last, sample);
+        let monotonic_ms = sample.monotonic.duration_since(last.monotonic).as_millis() as i64;
+        let gap_ms = sample.boot_ms? - last.boot_ms? - monotonic_ms;
+        (gap_ms > self.threshold_ms as i64).then_some(ClockJump { gap_ms })
+    }
+}
+
+/// `CLOCK_BOOTTIME` in ms.
+#[cfg(target_os = "linux")]
+fn boot_time_ms() -> Option<i64> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid, writable timespec for the duration of the call.
+    if unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut ts) } != 0 {
+        return None;
+    }
+    // `time_t` and `c_long` are 32-bit on some targets.
+    #[allow(clippy::unnecessary_cast)]
+    Some(ts.tv_sec as i64 * 1000 + ts.tv_nsec as i64 / 1_000_000)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn boot_time_ms() -> Option<i64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn sample(monotonic: Instant, boot_ms: Option<i64>) -> ClockSample {
+        ClockSample { monotonic, boot_ms }
+    }
+
+    fn detector(first: ClockSample) -> ClockJumpDetector {
+        ClockJumpDetector {
+            threshold_ms: 1_000,
+            last: first,
+        }
+    }
+
+    #[test]
+    fn clocks_in_step_are_not_a_jump() {
+        let t0 = Instant::now();
+        let mut detector = detector(sample(t0, Some(5_000)));
+        let t1 = t0 + Duration::from_secs(1);
+        assert_eq!(detector.observe(sample(t1, Some(6_010))), None);
+    }
+
+    #[test]
+    fn suspend_shows_as_boot_time_running_ahead() {
+        let t0 = Instant::now();
+        let mut detector = detector(sample(t0, Some(5_000)));
+        // One monotonic second, but a minute of boot time.
+        let t1 = t0 + Duration::from_secs(1);
+        assert_eq!(
+            detector.observe(sample(t1, Some(66_000))),
+            Some(ClockJump { gap_ms: 60_000 })
+        );
+        // The next interval compares against the post-resume sample.
+        let t2 = t1 + Duration::from_secs(1);
+        assert_eq!(detector.observe(sample(t2, Some(67_000))), None);
+    }
+
+    #[test]
+    fn nothing_is_reported_without_boot_time() {
+        let t0 = Instant::now();
+        let mut detector = detector(sample(t0, None));
+        let t1 = t0 + Duration::from_secs(1);
+        assert_eq!(detector.observe(sample(t1, None)), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn boot_time_is_read_from_the_kernel() {
+        let first = boot_time_ms().expect("CLOCK_BOOTTIME");
+        assert!(first > 0);
+        assert!(boot_time_ms().unwrap() >= first);
+    }
+}
@@ -1,20 +1,29 @@
 use anyhow::{Context, Result};
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::net::SocketAddr;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     pub http: HttpConfig,
     pub ntp: NtpConfig,
     pub ntp_server: NtpServerConfig,
     pub quality: QualityConfig,
     pub persist: PersistConfig,
+    pub roughtime: RoughtimeConfig,
     pub ws: WsConfig,
+    pub grpc: GrpcConfig,
     pub logging: LoggingConfig,
     pub messages: MessageConfig,
     pub admin: AdminConfig,
+    pub metrics_auth: MetricsAuthConfig,
+    pub jwt: JwtConfig,
     pub replica: ReplicaConfig,
 }
 
@@ -28,9 +37,9 @@ pub struct Config {
 /// 1. `REPLICA_ID` env var (explicit)
 /// 2. `HOSTNAME` env var (set automatically inside a Kubernetes pod)
 /// 3. `replica-<pid>` (process-local fallback)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ReplicaConfig {
-    /// Non-empty, max 128 characters.
+    /// Non-empty, max 128 characters. Set via `REPLICA_ID`.
     pub replica_id: String,
 }
 
@@ -38,28 +47,105 @@ pub struct ReplicaConfig {
 ///
 /// All admin endpoints are only registered when `enabled = true`.
 /// Enabling without setting `token` is a startup error.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AdminConfig {
-    /// Whether the admin API is enabled. Default: false.
+    /// Whether the admin API is enabled. Set via `ADMIN_API_ENABLED`. Default: false.
     pub enabled: bool,
-    /// Bearer token required for all admin endpoints.
+    /// Bearer token required for all admin endpoints. Set via
+    /// `ADMIN_API_TOKEN` or `ADMIN_API_TOKEN_FILE`.
     /// Must be non-empty when `enabled = true`. Never logged.
     pub token: String,
-    /// Maximum TTL (seconds) for a manual time override. Default: 300.
+    /// Maximum TTL (seconds) for a manual time override. Set via `MANUAL_OVERRIDE_MAX_TTL_SECS`.
+    /// Default: 300.
     pub max_ttl_secs: u32,
-    /// Maximum epoch_ms jump (ms) allowed from current NTP time. Default: 5000.
+    /// Maximum epoch_ms jump (ms) allowed from current NTP time. Set via
+    /// `MANUAL_OVERRIDE_MAX_JUMP_MS`. Default: 5000.
     pub max_jump_ms: u64,
     /// Whether `force=true` in POST /admin/time/override is allowed.
     /// Set via `MANUAL_OVERRIDE_ALLOW_FORCE=true`. Default: false.
     /// When false, any request with `force=true` is rejected 400.
     /// When true, `force=true` bypasses the jump check (monotonic clamp still applies).
     pub allow_force: bool,
-    /// Base root_dispersion (ms) advertised by the UDP NTP server in MANU mode. Default: 1000.
+    /// Base root_dispersion (ms) advertised by the UDP NTP server in MANU mode. Set via
+    /// `MANUAL_OVERRIDE_DISPERSION_MS`. Default: 1000.
     pub dispersion_ms: u64,
 }
 
+/// Optional credentials for the observability endpoints (`/metrics`,
+/// `/performance`, `/debug/*`), independent of the admin API token so
+/// scrapers can use a dedicated credential.
+///
+/// When both are empty (default) the endpoints are unauthenticated.  When
+/// either is set, a request must present a matching bearer token *or*
+/// matching Basic credentials.  Never logged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct MetricsAuthConfig {
+    /// Bearer token. Set via `METRICS_AUTH_TOKEN` or `METRICS_AUTH_TOKEN_FILE`.
+    pub token: String,
+    /// HTTP Basic credentials as `user:password`. Set via `METRICS_AUTH_BASIC`
+    /// or `METRICS_AUTH_BASIC_FILE`.
+    pub basic: String,
+}
+
+impl MetricsAuthConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.token.is_empty() || !self.basic.is_empty()
+    }
+}
+
+/// JWT bearer-token authentication for `/stream` and the admin API, so the
+/// service can sit behind existing OIDC infrastructure.
+///
+/// Enabled when an HS256 secret or a JWKS URL (RS256) is configured.  Tokens
+/// must carry `exp`; `iss` and `aud` are checked when configured.  Admin
+/// endpoints accept either the static `ADMIN_API_TOKEN` or a JWT whose
+/// `scope` (or `scp`) includes `admin_scope`.  Secrets are never logged.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JwtConfig {
+    /// Shared secret for HS256 tokens. Set via `JWT_HS256_SECRET` or
+    /// `JWT_HS256_SECRET_FILE`.
+    #[serde(skip_serializing)]
+    pub hs256_secret: String,
+    /// JWKS document with the RS256 signing keys, e.g. an OIDC provider's
+    /// `jwks_uri`. Set via `JWT_JWKS_URL`. Default: none.
+    pub jwks_url: Option<String>,
+    /// How often the JWKS is re-fetched. Set via `JWT_JWKS_REFRESH_SECS`. Default: 300.
+    pub jwks_refresh_secs: u64,
+    /// Required `iss` claim. Set via `JWT_ISSUER`. Default: not checked.
+    pub issuer: Option<String>,
+    /// Required `aud` claim (string or array). Set via `JWT_AUDIENCE`. Default: not checked.
+    pub audience: Option<String>,
+    /// Clock skew tolerated on `exp`/`nbf`. Set via `JWT_LEEWAY_SECS`. Default: 60.
+    pub leeway_secs: u64,
+    /// Scope that grants the admin API. Set via `JWT_ADMIN_SCOPE`. Default: `ntp:admin`.
+    pub admin_scope: String,
+    /// Require a JWT on `/stream`. Set via `JWT_PROTECT_STREAM`. Default: true.
+    pub protect_stream: bool,
+}
+
+impl JwtConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.hs256_secret.is_empty() || self.jwks_url.is_some()
+    }
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            hs256_secret: String::new(),
+            jwks_url: None,
+            jwks_refresh_secs: 300,
+            issuer: None,
+            audience: None,
+            leeway_secs: 60,
+            admin_scope: "ntp:admin".to_string(),
+            protect_stream: true,
+        }
+    }
+}
+
 /// Serve/stop SLA thresholds for the time-quality envelope.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct QualityConfig {
     /// When `false` (default), the service is holdover-first: after any seed
     /// (NTP, manual, or persisted), `/time` always returns HTTP 200 and reports
@@ -70,100 +156,651 @@ pub struct QualityConfig {
     /// returns 503 exactly as in the pre-v1.1 behaviour:
     ///   uncertainty > `serve_ok_max_uncertainty_ms` + `ALLOW_DEGRADED=false` → 503
     ///   uncertainty > `serve_degraded_max_uncertainty_ms` → 503 always
+    /// Set via `STRICT_SLA_MODE`. Default: false.
     pub strict_sla_mode: bool,
     /// In strict mode only: allow uncertainty in the degraded band to return 200.
-    /// Ignored when `strict_sla_mode=false`.
+    /// Ignored when `strict_sla_mode=false`. Set via `ALLOW_DEGRADED`.
     pub allow_degraded: bool,
-    /// Max uncertainty (ms) to report `serve_state="ok"`. Default 50 ms.
+    /// Max uncertainty (ms) to report `serve_state="ok"`. Set via `SERVE_OK_MAX_UNCERTAINTY_MS`.
+    /// Default 50 ms.
     pub serve_ok_max_uncertainty_ms: f64,
-    /// Max uncertainty (ms) for the degraded band (strict mode). Default 250 ms.
+    /// Max uncertainty (ms) for the degraded band (strict mode). Set via
+    /// `SERVE_DEGRADED_MAX_UNCERTAINTY_MS`. Default 250 ms.
     pub serve_degraded_max_uncertainty_ms: f64,
-    /// Max uncertainty (ms) for `/readyz` to return 200 after first sync.
+    /// Max uncertainty (ms) for `/readyz` to return 200 after first sync. Set via
+    /// `READINESS_MAX_UNCERTAINTY_MS`.
     pub readiness_max_uncertainty_ms: f64,
+    /// How often the host clock (`SystemTime::now()`) is compared against
+    /// NTP time. Set via `SYSTEM_CLOCK_CHECK_INTERVAL_SECS`. Default: 10.
+    pub system_clock_check_interval_secs: u64,
+    /// Host-clock divergence (ms, absolute) that raises the system-clock
+    /// alert; 0 disables alerting (the divergence is still reported).
+    /// Set via `SYSTEM_CLOCK_DIVERGENCE_ALERT_MS`. Default: 1000.
+    pub system_clock_alert_ms: u64,
+    /// How much host-clock divergence history `/report/clock-drift` keeps;
+    /// 0 disables the shadow series.  Set via
+    /// `SYSTEM_CLOCK_SHADOW_WINDOW_SECS`. Default: 86400 (24 h).
+    pub system_clock_shadow_window_secs: u64,
+    /// How far `CLOCK_BOOTTIME` may run ahead of the monotonic clock between
+    /// two checks before it is treated as a suspend/resume (time marked
+    /// stale, immediate resync that steps the base); 0 disables detection. Set via `CLOCK_JUMP_THRESHOLD_MS`. Default: 1000.
+    pub clock_jump_threshold_ms: u64,
+    /// How often the clocks are compared for jumps. Set via
+    /// `CLOCK_JUMP_CHECK_INTERVAL_SECS`. Default: 1.
+    pub clock_jump_check_interval_secs: u64,
+    /// Minimum spacing between live probes run by `/health/deep`; requests in
+    /// between get the previous result. Set via `DEEP_HEALTH_MIN_INTERVAL_SECS`.
+    /// Default: 10.
+    pub deep_health_min_interval_secs: u64,
+}
+
+/// Roughtime cross-check of the NTP-derived time.
+///
+/// Roughtime replies are signed, so a served time that falls outside a
+/// server's signed bound by more than `max_disagreement_ms` is evidence of
+/// NTP spoofing (or a broken upstream) and is flagged.  Disabled when
+/// `servers` is empty.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RoughtimeConfig {
+    /// Set via `ROUGHTIME_SERVERS`. Default: none.
+    pub servers: Vec<RoughtimeServerConfig>,
+    /// Seconds between cross-checks. Set via `ROUGHTIME_INTERVAL_SECS`. Default: 300.
+    pub interval_secs: u64,
+    /// Tolerated distance (ms) between NTP time and a signed bound.
+    /// Set via `ROUGHTIME_MAX_DISAGREEMENT_MS`. Default: 1000.
+    pub max_disagreement_ms: u64,
+}
+
+/// One `ROUGHTIME_SERVERS` entry: `host:port;key=BASE64_ED25519_PUBLIC_KEY`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RoughtimeServerConfig {
+    pub address: String,
+    /// Long-term Ed25519 public key that signs the server's delegations.
+    pub public_key: [u8; 32],
 }
 
+impl RoughtimeServerConfig {
+    /// Parse one entry, e.g. `roughtime.example.com:2002;key=...`.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let (address, key) = raw
+            .trim()
+            .split_once(";key=")
+            .with_context(|| format!("ROUGHTIME_SERVERS entry needs ;key=: {}", raw.trim()))?;
+        if address.is_empty() || !address.contains(':') {
+            anyhow::bail!("Invalid ROUGHTIME_SERVERS address: {}", address);
+        }
+        let public_key = BASE64
+            .decode(key.trim())
+            .ok()
+            .and_then(|k| <[u8; 32]>::try_from(k).ok())
+            .with_context(|| format!("Invalid ROUGHTIME_SERVERS key: {}", key.trim()))?;
+        Ok(Self {
+            address: address.to_string(),
+            public_key,
+        })
+    }
+}
+
+/// Standard base64, padded or not, as Roughtime public keys are published.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
 /// Persisted last-good state for restart recovery.
 ///
 /// When `enabled=true`, the service writes a JSON snapshot to `file_path`
-/// after every successful NTP sync.  On the next startup, if NTP is
-/// unreachable, the snapshot is read and used to seed the `TimeBase` so
-/// the service can serve time in holdover mode until NTP recovers.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// after every successful NTP sync, every `interval_secs` and on shutdown.
+/// On the next startup, if NTP is unreachable, the snapshot is read and used
+/// to seed the `TimeBase` so the service can serve time in holdover mode
+/// until NTP recovers.  The snapshot also carries the last served epoch_ms,
+/// restored as the monotonic floor so a restart never serves an earlier one.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PersistConfig {
     /// Set `TIME_STATE_PERSIST_ENABLED=true` to enable. Default: false.
     pub enabled: bool,
-    /// Path to the JSON state file. Default: `/var/lib/ntp-time-json-api/state.json`.
+    /// Path to the JSON state file. Set via `TIME_STATE_FILE`. Default:
+    /// `/var/lib/ntp-time-json-api/state.json`.
     pub file_path: String,
+    /// How often the snapshot is rewritten between syncs.  Set via
+    /// `TIME_STATE_PERSIST_INTERVAL_SECS`. Default: 5.
+    pub interval_secs: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HttpConfig {
-    pub addr: SocketAddr,
+    /// Addresses the router is served on, each a TCP `host:port` or a
+    /// `unix:/path` socket with optional per-address socket options.
+    /// Set via `ADDR` (comma-separated). Default: `0.0.0.0:8080`.
+    pub listen: Vec<ListenAddr>,
+    /// Optional second listener for probes, `/metrics`, `/performance`,
+    /// `/debug/*` and `/admin/*`; when set, those routes leave `listen`.
+    /// Same syntax as one `ADDR` entry. Set via `OPS_ADDR`. Default: none.
+    pub ops_listen: Option<ListenAddr>,
+    /// Per-request timeout. Set via `REQUEST_TIMEOUT`. Default: 5.
     pub request_timeout_secs: u64,
+    /// Largest accepted request body. Set via `BODY_LIMIT_BYTES`. Default: 1024.
     pub body_limit_bytes: usize,
+    /// Disable Nagle on accepted sockets. Set via `TCP_NODELAY`. Default: true.
     pub tcp_nodelay: bool,
+    /// TCP keepalive idle time. Set via `TCP_KEEPALIVE_SECS`. Default: none (0 disables).
     pub tcp_keepalive_secs: Option<u64>,
+    /// Accept-queue length passed to `listen(2)`. Set via `LISTEN_BACKLOG`.
+    /// The kernel caps it at `net.core.somaxconn`. Default: 1024.
+    pub listen_backlog: i32,
+    /// Stamp/propagate `x-request-id` on the `/time` fast path, as every
+    /// other route always does. Set `FAST_PATH_REQUEST_ID=false` to skip the
+    /// per-request UUID on the hot path. Default: true.
+    pub fast_path_request_id: bool,
+    /// Opt-in: record `http_requests_total` / duration for the `/time` fast
+    /// path. Set via `FAST_PATH_METRICS=true`. Default: false.
+    pub fast_path_metrics: bool,
     /// When `true`, skip `GovernorLayer` rate limiting. Set via
     /// `DISABLE_RATE_LIMITING=true`. Useful for local dev/smoke-testing
-    /// where no real peer IP is available to `PeerIpKeyExtractor`.
+    /// where no real peer IP is available to the rate limiter.
     pub disable_rate_limiting: bool,
+    /// Sustained requests per second allowed per client IP. Set via
+    /// `RATE_LIMIT_PER_SECOND`. Default: 1000.
+    pub rate_limit_per_second: u32,
+    /// Requests a client may burst above the sustained rate. Set via
+    /// `RATE_LIMIT_BURST`. Default: 100.
+    pub rate_limit_burst: u32,
+    /// Reverse proxies (IPs or CIDRs) whose `X-Forwarded-For` / `X-Real-IP`
+    /// headers name the client for rate limiting. Set via `TRUSTED_PROXIES`
+    /// (comma-separated). Default: none (the TCP peer is the client).
+    pub trusted_proxies: Vec<String>,
+    /// Requests allowed in flight at once across all routes; beyond it new
+    /// requests are shed with 503. Set via `MAX_INFLIGHT_REQUESTS`.
+    /// Default: 0 (no limit).
+    pub max_inflight_requests: usize,
+    /// Client IPs or CIDRs admitted; empty admits everyone not denied.
+    /// Set via `IP_ALLOWLIST` (comma-separated). Default: none.
+    pub ip_allowlist: Vec<String>,
+    /// Client IPs or CIDRs rejected with 403, even when allowlisted.
+    /// Set via `IP_DENYLIST` (comma-separated). Default: none.
+    pub ip_denylist: Vec<String>,
+    /// Routes the IP lists apply to. Set via `IP_FILTER_SCOPE`
+    /// (`all` or `ops`). Default: all.
+    pub ip_filter_scope: IpFilterScope,
+    /// On shutdown, how long `/readyz` reports 503 while the listeners keep
+    /// accepting, so load balancers see the failure before connections are
+    /// refused. Set via `SHUTDOWN_READINESS_DELAY_SECS`. Default: 5.
+    pub shutdown_readiness_delay_secs: u64,
+    /// On shutdown, how long in-flight requests and streams may run after
+    /// the listeners close before background tasks are stopped anyway.
+    /// Set via `SHUTDOWN_DRAIN_SECS`. Default: 20.
+    pub shutdown_drain_secs: u64,
+    /// Emit epoch values (`data`, `epoch_ms`) as JSON strings so JavaScript
+    /// clients don't round them past 2^53. Set via `EPOCH_AS_STRING`;
+    /// `/time?epoch_as_string=` overrides per request. Default: false.
+    pub epoch_as_string: bool,
+    /// Honour `/time?callback=` (JSONP) for legacy clients that cannot make
+    /// CORS requests. Set via `JSONP_ENABLED`. Default: false.
+    pub jsonp_enabled: bool,
+    /// gzip/brotli-compress slow-path responses (`/metrics`, `/status`, ...)
+    /// for clients sending `Accept-Encoding`. The `/time` fast path is never
+    /// compressed. Set via `HTTP_COMPRESSION`. Default: true.
+    pub compression_enabled: bool,
+    /// PEM certificate chain; with `tls_key_file`, serves HTTPS directly.
+    /// Set via `TLS_CERT_FILE`. Default: none (plain HTTP).
+    pub tls_cert_file: Option<String>,
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1). Set via `TLS_KEY_FILE`.
+    pub tls_key_file: Option<String>,
+    /// How often the certificate and key files are checked for changes and
+    /// reloaded. Set via `TLS_RELOAD_INTERVAL_SECS`. Default: 60.
+    pub tls_reload_interval_secs: u64,
+    /// Accept HTTP/2: h2c (prior knowledge) in plaintext, ALPN `h2` over TLS.
+    /// Set via `HTTP2_ENABLED`. Default: true.
+    pub http2_enabled: bool,
+    /// Concurrent streams a client may open per HTTP/2 connection.
+    /// Set via `HTTP2_MAX_CONCURRENT_STREAMS`. Default: 200.
+    pub http2_max_concurrent_streams: u32,
+    /// Initial HTTP/2 flow-control window per stream (bytes).
+    /// Set via `HTTP2_INITIAL_STREAM_WINDOW_SIZE`. Default: 1 MiB.
+    pub http2_initial_stream_window_size: u32,
+    /// Initial HTTP/2 flow-control window per connection (bytes).
+    /// Set via `HTTP2_INITIAL_CONNECTION_WINDOW_SIZE`. Default: 1 MiB.
+    pub http2_initial_connection_window_size: u32,
+}
+
+/// Which routes `IP_ALLOWLIST` / `IP_DENYLIST` guard.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IpFilterScope {
+    /// Every route.
+    All,
+    /// Admin and observability routes (`/admin/*`, `/metrics`,
+    /// `/performance`, `/debug/*`) only.
+    Ops,
+}
+
+/// Where an HTTP listener is bound.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ListenTarget {
+    Tcp(SocketAddr),
+    /// Unix domain socket path; a stale socket file is replaced on bind.
+    Unix(PathBuf),
+}
+
+/// One `ADDR` entry: `<host:port | unix:/path>[?option=value&...]`.
+///
+/// Options override the global socket settings for this listener only:
+/// `backlog` (`LISTEN_BACKLOG`), `nodelay` (`TCP_NODELAY`), `keepalive`
+/// (`TCP_KEEPALIVE_SECS`, 0 disables) and, for Unix sockets, `mode` (octal
+/// file permissions).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ListenAddr {
+    pub target: ListenTarget,
+    pub backlog: Option<i32>,
+    pub tcp_nodelay: Option<bool>,
+    pub tcp_keepalive_secs: Option<u64>,
+    pub unix_mode: Option<u32>,
+}
+
+impl ListenAddr {
+    pub fn tcp(addr: SocketAddr) -> Self {
+        Self {
+            target: ListenTarget::Tcp(addr),
+            backlog: None,
+            tcp_nodelay: None,
+            tcp_keepalive_secs: None,
+            unix_mode: None,
+        }
+    }
+
+    pub fn parse(raw: &str) -> Result<Self> {
+        let raw = raw.trim();
+        let (addr, options) = raw.split_once('?').unwrap_or((raw, ""));
+        let target = match addr.strip_prefix("unix:") {
+            Some("") => anyhow::bail!("ADDR entry {raw:?} has an empty socket path"),
+            Some(path) => ListenTarget::Unix(PathBuf::from(path)),
+            None => ListenTarget::Tcp(
+                addr.parse()
+                    .with_context(|| format!("Failed to parse ADDR entry {raw:?}"))?,
+            ),
+        };
+        let mut listen = Self {
+            target,
+            ..Self::tcp(SocketAddr::from(([0, 0, 0, 0], 0)))
+        };
+        for option in options.split('&').filter(|o| !o.is_empty()) {
+            let (key, value) = option.split_once('=').unwrap_or((option, ""));
+            let invalid = || anyhow::anyhow!("ADDR entry {raw:?}: invalid {key} {value:?}");
+            match key {
+                "backlog" => listen.backlog = Some(value.parse().map_err(|_| invalid())?),
+                "nodelay" => listen.tcp_nodelay = Some(value.parse().map_err(|_| invalid())?),
+                "keepalive" => {
+                    listen.tcp_keepalive_secs = Some(value.parse().map_err(|_| invalid())?)
+                }
+                "mode" => {
+                    listen.unix_mode = Some(u32::from_str_radix(value, 8).map_err(|_| invalid())?)
+                }
+                other => anyhow::bail!(
+                    "ADDR entry {raw:?}: unknown option {other:?} (expected backlog, nodelay, keepalive or mode)"
+                ),
+            }
+        }
+        Ok(listen)
+    }
+}
+
+impl std::fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.target {
+            ListenTarget::Tcp(addr) => write!(f, "{addr}"),
+            ListenTarget::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Socket settings for one listener, after per-address overrides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketOptions {
+    pub backlog: i32,
+    pub tcp_nodelay: bool,
+    pub tcp_keepalive_secs: Option<u64>,
+    pub unix_mode: Option<u32>,
+}
+
+/// Largest HTTP/2 flow-control window (2^31 - 1, RFC 9113 §6.9.1).
+pub const MAX_HTTP2_WINDOW: u32 = (1 << 31) - 1;
+
+impl HttpConfig {
+    /// Socket settings for `listen`: its own options, else the globals.
+    pub fn socket_options(&self, listen: &ListenAddr) -> SocketOptions {
+        SocketOptions {
+            backlog: listen.backlog.unwrap_or(self.listen_backlog),
+            tcp_nodelay: listen.tcp_nodelay.unwrap_or(self.tcp_nodelay),
+            tcp_keepalive_secs: match listen.tcp_keepalive_secs {
+                Some(0) => None,
+                Some(secs) => Some(secs),
+                None => self.tcp_keepalive_secs,
+            },
+            unix_mode: listen.unix_mode,
+        }
+    }
+
+    /// Whether the listener terminates TLS itself.
+    pub fn tls_enabled(&self) -> bool {
+        self.tls_cert_file.is_some() && self.tls_key_file.is_some()
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct NtpConfig {
-    pub servers: Vec<String>,
-    pub timeout_secs: u64,
+    /// Upstream servers with their per-server options. Set via `NTP_SERVERS`
+    /// or `NTP_SERVERS_FILE`.
+    pub servers: Vec<ServerConfig>,
+    /// Per-query upstream timeout (ms). Set via `NTP_TIMEOUT`. Default: 2s.
+    pub timeout_ms: u64,
+    /// Minimum (and initial) sync interval. Set via `SYNC_INTERVAL`.
     pub sync_interval_secs: u64,
+    /// Ceiling for the adaptive sync interval; equal to `sync_interval_secs`
+    /// disables adaptation. Set via `SYNC_INTERVAL_MAX`. Default: `SYNC_INTERVAL`.
+    pub sync_interval_max_secs: u64,
+    /// Largest sync-to-sync offset change (ms) that counts as stable for the
+    /// adaptive interval. Set via `POLL_STABLE_OFFSET_MS`. Default: 5.
+    pub poll_stable_offset_ms: u64,
+    /// Panic threshold: a sync that would move served time by more than this
+    /// (ms) relative to the current time base is rejected. 0 disables.
+    /// Set via `MAX_STEP_MS`. Default: 0.
+    pub max_step_ms: u64,
+    /// Consecutive rejections, agreeing to within `max_step_ms`, after which
+    /// the step is accepted anyway. 0 rejects forever.
+    /// Set via `MAX_STEP_STEPOUT`. Default: 3.
+    pub max_step_stepout: u32,
+    /// Shortest probe interval while unsynchronized. Set via `PROBE_MIN_INTERVAL`. Default: 10.
     pub probe_min_interval_secs: u64,
+    /// Longest probe interval while unsynchronized. Set via `PROBE_MAX_INTERVAL`. Default: 20.
     pub probe_max_interval_secs: u64,
+    /// Age after which the last sync no longer counts. Set via `MAX_STALENESS`. Default: 120.
     pub max_staleness_secs: u64,
+    /// Refuse to serve time before the first sync. Set via `REQUIRE_SYNC`. Default: true.
     pub require_sync: bool,
-    /// Deprecated: accepted for backwards compat but has no effect since P1-6.
-    pub selection_strategy: SelectionStrategy,
+    /// Never let served time go backwards. Set via `MONOTONIC_OUTPUT`. Default: true.
     pub monotonic_output: bool,
+    /// Scope of the `monotonic_output` guarantee. Set via `MONOTONIC_SCOPE`.
+    /// Default: global.
+    pub monotonic_scope: MonotonicScope,
+    /// Rate (ppm) at which sync corrections are slewed into served time; 0
+    /// steps the time base on every sync. Set via `SLEW_RATE_PPM`. Default: 0.
+    pub slew_rate_ppm: u64,
+    /// Corrections larger than this (ms) are stepped even when slewing.
+    /// Set via `SLEW_MAX_OFFSET_MS`. Default: 128.
+    pub slew_max_offset_ms: u64,
+    /// Estimate the local clock's frequency error from successive syncs and
+    /// correct served time for it, notably during holdover.
+    /// Set via `DRIFT_COMPENSATION`. Default: false.
+    pub drift_compensation: bool,
+    /// `leap-seconds.list` replacing the bundled leap-second table at
+    /// startup. Set via `LEAP_SECONDS_FILE`. Default: none.
+    pub leap_seconds_file: Option<String>,
+    /// Fixed offset correction. Set via `OFFSET_BIAS_MS`. Default: 0.
     pub offset_bias_ms: i64,
+    /// Fixed path-asymmetry correction. Set via `ASYMMETRY_BIAS_MS`. Default: 0.
     pub asymmetry_bias_ms: i64,
+    /// Failed syncs before the syncer reports unhealthy. Set via `MAX_CONSECUTIVE_FAILURES`.
+    /// Default: 10.
     pub max_consecutive_failures: u32,
+    /// IP family used for upstream queries when a name resolves to both.
+    /// Set via `NTP_ADDRESS_FAMILY`. Default: any.
+    pub address_family: AddressFamily,
+    /// Startup lookup of the server names, see [`Config::check_dns`]. Set
+    /// via `NTP_DNS_CHECK` (`fail`, `warn` or `off`); `--skip-dns-check`
+    /// forces `off`. Default: warn.
+    pub dns_check: DnsCheck,
+    /// Local address/interface upstream NTP and NTS-KE sockets bind to.
+    pub source: SourceBinding,
     /// P1-6 uncertainty-aware weighted-median selection configuration.
     pub selection: SelectionConfig,
+    /// Per-sync query scheduling (stagger, concurrency cap).
+    pub query: QueryConfig,
+    /// Automatic per-server offset-bias calibration.
+    pub calibration: CalibrationConfig,
+    /// Symmetric keys loaded from `NTP_KEYS_FILE`, by key id; referenced by
+    /// `NTP_SERVERS` entries with `;key=ID`. Never serialized.
+    #[serde(skip)]
+    pub keys: HashMap<u32, NtpKey>,
+}
+
+/// Automatic per-server path-asymmetry calibration (see `ntp::calibration`).
+///
+/// Disabled by default; the static `OFFSET_BIAS_MS` / `ASYMMETRY_BIAS_MS`
+/// values are applied either way.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CalibrationConfig {
+    /// Set `NTP_BIAS_CALIBRATION_ENABLED=true` to learn and apply per-server
+    /// corrections. Default: false.
+    pub enabled: bool,
+    /// Trusted reference server (`host:port`, as in `NTP_SERVERS`); residuals
+    /// are measured against it instead of the selected consensus. Set via
+    /// `NTP_BIAS_CALIBRATION_REFERENCE`. Default: none (consensus).
+    pub reference_server: Option<String>,
+    /// EWMA smoothing factor in (0, 1]. Set via `NTP_BIAS_CALIBRATION_ALPHA`. Default: 0.05.
+    pub alpha: f64,
+    /// Residuals required before a correction is applied. Set via
+    /// `NTP_BIAS_CALIBRATION_MIN_SAMPLES`. Default: 20.
+    pub min_samples: u32,
+    /// Largest correction ever applied to a server (ms). Set via
+    /// `NTP_BIAS_CALIBRATION_MAX_MS`. Default: 50.
+    pub max_correction_ms: u64,
+    /// Estimate each server's delay-dependent asymmetry from how its offset
+    /// moves with RTT (huff-n-puff) and correct samples for it; capped at
+    /// `max_correction_ms`. Set via `NTP_ASYMMETRY_ESTIMATION_ENABLED`.
+    /// Default: false.
+    pub asymmetry_enabled: bool,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reference_server: None,
+            alpha: 0.05,
+            min_samples: 20,
+            max_correction_ms: 50,
+            asymmetry_enabled: false,
+        }
+    }
+}
+
+/// How the per-sync fan-out of NTP queries is scheduled and retried.
+///
+/// By default every server is queried at once, exactly once.  Large fleets
+/// can spread the burst so thousands of instances do not trip
+/// upstream/firewall UDP rate rules, and lossy paths can retry so a single
+/// dropped packet is not counted as a server failure.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct QueryConfig {
+    /// Spread query start times evenly over this window (ms): server *i* of
+    /// *n* starts at `i × window / n`. `0` = no stagger. Set via
+    /// `NTP_QUERY_STAGGER_MS`. Default: 0.
+    pub stagger_window_ms: u64,
+    /// Maximum NTP queries in flight at once per sync. `0` = unlimited. Set
+    /// via `NTP_QUERY_MAX_IN_FLIGHT`. Default: 0.
+    pub max_in_flight: usize,
+    /// Extra attempts per server before the query counts as a failure. Set
+    /// via `NTP_QUERY_RETRIES`. Default: 0.
+    pub retries: u32,
+    /// Per-server overrides of `retries`.
+    /// Format: `"server1=2,server2=0"` via `NTP_QUERY_RETRIES_OVERRIDES`.
+    pub retries_by_server: HashMap<String, u32>,
+    /// Base delay before a retry (ms); the actual delay is drawn uniformly
+    /// from `[delay/2, 3·delay/2)`. Set via `NTP_QUERY_RETRY_DELAY_MS`. Default: 500.
+    pub retry_delay_ms: u64,
+    /// Random extra time added to `NTP_TIMEOUT` on every attempt (ms) so
+    /// retries from many instances do not expire in lockstep. Set via
+    /// `NTP_QUERY_TIMEOUT_JITTER_MS`. Default: 0.
+    pub timeout_jitter_ms: u64,
+    /// Expand each hostname into its A/AAAA records and query (and track
+    /// stats for) every address as its own server, so a pool such as
+    /// `pool.ntp.org` contributes several independent samples. Set via
+    /// `NTP_POOL_EXPANSION`. Default: false.
+    pub expand_pools: bool,
+    /// Re-resolve expanded hostnames this often (seconds) so pool rotation
+    /// is honoured. Set via `NTP_POOL_RESOLVE_INTERVAL_SECS`. Default: 300.
+    pub pool_resolve_interval_secs: u64,
+    /// Cap on addresses kept per expanded hostname. Set via
+    /// `NTP_POOL_MAX_ADDRESSES`. Default: 4.
+    pub pool_max_addresses: usize,
+    /// Pass each server's minimum-delay sample of its last eight, rather
+    /// than the latest one, to selection (see `ntp::filter`). Set via
+    /// `NTP_CLOCK_FILTER_ENABLED`. Default: true.
+    pub clock_filter: bool,
+}
+
+impl QueryConfig {
+    /// Retry budget for `server`, honouring per-server overrides.
+    pub fn retries_for(&self, server: &str) -> u32 {
+        self.retries_by_server
+            .get(server)
+            .copied()
+            .unwrap_or(self.retries)
+    }
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            stagger_window_ms: 0,
+            max_in_flight: 0,
+            retries: 0,
+            retries_by_server: HashMap::new(),
+            retry_delay_ms: 500,
+            timeout_jitter_ms: 0,
+            expand_pools: false,
+            pool_resolve_interval_secs: 300,
+            pool_max_addresses: 4,
+            clock_filter: true,
+        }
+    }
 }
 
-/// NTP server selection strategy.
+/// How the consensus offset is formed and which agreer is served.
 ///
-/// **Deprecated** — kept for backwards-compatible env-var parsing only.
-/// P1-6 replaced the algorithm with uncertainty-aware weighted median + quorum;
-/// the `SELECTION_STRATEGY` env var is accepted but has no effect.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+/// Every strategy runs the same hard gates, interval intersection, agreer
+/// classification and quorum check; they differ only in the final estimate.
+/// Set via `SELECTION_STRATEGY`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum SelectionStrategy {
-    /// Historical alias for the old accuracy-first algorithm.
-    /// Accepted for backwards compat; ignored by the P1-6 selection.
+    /// λ-weighted median consensus; serve the lowest-λ agreer's own sample
+    /// (RTT tiebreaker).  `rtt_min` is a backwards-compatible alias.
     AccuracyFirst,
+    /// Unweighted median of candidate offsets as the consensus, and serve
+    /// that median offset rather than any single server's.  Most robust to a
+    /// minority of bad servers; ignores per-server uncertainty.
+    OffsetMedian,
+    /// Serve the mean of agreer offsets weighted by 1 / (RTT + 1 ms).
+    /// Averages out per-server noise; low-latency paths dominate.
+    Weighted,
+    /// Serve the lowest-stratum agreer, λ then RTT as tiebreakers.  Prefers
+    /// servers closest to a reference clock over nearer, deeper ones.
+    StratumFirst,
+}
+
+/// How widely `MONOTONIC_OUTPUT` guarantees non-decreasing timestamps.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MonotonicScope {
+    /// One process-wide last-served value: every response is ordered after
+    /// every earlier one, at the cost of a cache line shared by all cores.
+    Global,
+    /// One last-served value per shard (worker threads are spread across
+    /// shards): each thread's responses are monotonic and shards no longer
+    /// contend, but two requests on different threads may be reordered by
+    /// up to a millisecond.
+    Shard,
+}
+
+/// IP family for upstream NTP (and NTS-KE) connections.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFamily {
+    /// Whatever the resolver returns first.
+    #[default]
+    Any,
+    /// IPv4 only; a name with no A record fails to resolve.
+    Ipv4,
+    /// IPv6 only; a name with no AAAA record fails to resolve.
+    Ipv6,
+}
+
+impl AddressFamily {
+    pub fn matches(self, addr: &SocketAddr) -> bool {
+        match self {
+            AddressFamily::Any => true,
+            AddressFamily::Ipv4 => addr.is_ipv4(),
+            AddressFamily::Ipv6 => addr.is_ipv6(),
+        }
+    }
+}
+
+/// What startup does when no configured server name resolves.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsCheck {
+    /// Refuse to start.
+    Fail,
+    /// Log a warning and start anyway, so a DNS outage at rollout does not
+    /// stop holdover or a persisted seed from serving.
+    #[default]
+    Warn,
+    /// Skip the lookups.
+    Off,
+}
+
+/// Local end of upstream sockets, for multi-homed hosts and VPN setups.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct SourceBinding {
+    /// Source IP of outgoing queries; implies `NTP_ADDRESS_FAMILY` of its
+    /// family. Set via `NTP_BIND_ADDR`. Default: chosen by the kernel.
+    pub addr: Option<IpAddr>,
+    /// Interface outgoing queries are pinned to (`SO_BINDTODEVICE`, Linux
+    /// only). Set via `NTP_BIND_INTERFACE`. Default: none.
+    pub interface: Option<String>,
 }
 
 /// Configuration for the P1-6 uncertainty-aware weighted-median NTP selection
 /// algorithm.  All fields are read from environment variables at startup.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SelectionConfig {
-    /// Maximum upstream stratum accepted (hard gate). Default: 4.
+    /// Final-estimate strategy. Moved from `NtpConfig`. Set via
+    /// `SELECTION_STRATEGY`. Default: accuracy_first.
+    pub strategy: SelectionStrategy,
+    /// Per-server weight/priority keyed by address, filled by the syncer
+    /// from `NtpConfig::servers` for each sync.  Servers absent from the map
+    /// use weight 1 and priority 0.
+    #[serde(skip)]
+    pub server_options: HashMap<String, ServerConfig>,
+    /// Maximum upstream stratum accepted (hard gate). Set via `MAX_STRATUM`. Default: 4.
     pub max_stratum: u8,
     /// Minimum number of agreeing servers required for a valid selection.
-    /// A production deployment should use ≥ 2 NTP sources. Default: 2.
+    /// A production deployment should use ≥ 2 NTP sources. Set via `MIN_QUORUM`. Default: 2.
     pub min_quorum: usize,
-    /// Hard-gate samples with `leap = 3` (LI_ALARM / unsynchronised). Default: true.
+    /// Hard-gate samples with `leap = 3` (LI_ALARM / unsynchronised). Set via `REJECT_LEAP_ALARM`.
+    /// Default: true.
     pub reject_leap_alarm: bool,
-    /// Hard-gate samples whose root-distance λ exceeds this (ms). Default: 500.0.
+    /// Hard-gate samples whose root-distance λ exceeds this (ms). Set via `MAX_ROOT_DISTANCE_MS`.
+    /// Default: 500.0.
     pub max_root_distance_ms: f64,
-    /// Hard-gate samples older than this (seconds). Default: 60 (= 2 × default sync interval).
+    /// Hard-gate samples whose upstream `root_dispersion` alone exceeds this
+    /// (ms), however close the server is. Set via `MAX_ROOT_DISPERSION_MS`. Default: 250.
+    pub max_root_dispersion_ms: u32,
+    /// Hard-gate samples older than this (seconds). Set via `MAX_SAMPLE_AGE_SECS`. Default: 60 (= 2
+    /// × default sync interval).
     pub max_sample_age_secs: u64,
     /// Provider-group cap: if one provider group holds more than this fraction
-    /// of the agreers, `single_provider=true` and uncertainty is doubled. Default: 0.5.
+    /// of the agreers, `single_provider=true` and uncertainty is doubled. Set via
+    /// `PROVIDER_GROUP_MAX_FRACTION`. Default: 0.5.
     pub provider_group_max_fraction: f64,
     /// Optional per-server provider-group overrides.
     /// Format: `"server1=group1,server2=group2"` via `NTP_PROVIDER_GROUPS`.
     pub provider_groups: HashMap<String, String>,
     /// Maximum offset deviation from the weighted median for a server to be
-    /// considered an agreer.  Moved from `NtpConfig` for P1-6.  Default: 1000 ms.
+    /// considered an agreer.  Moved from `NtpConfig` for P1-6.  Set via `MAX_OFFSET_SKEW_MS`.
+    /// Default: 1000 ms.
     pub max_offset_skew_ms: i64,
     /// P1F-12: enable Marzullo/interval-intersection pre-filter before the weighted
     /// median step.  When true, candidates whose uncertainty intervals do not
@@ -175,10 +812,13 @@ pub struct SelectionConfig {
 impl Default for SelectionConfig {
     fn default() -> Self {
         Self {
+            strategy: SelectionStrategy::AccuracyFirst,
+            server_options: HashMap::new(),
             max_stratum: 4,
             min_quorum: 2,
             reject_leap_alarm: true,
             max_root_distance_ms: 500.0,
+            max_root_dispersion_ms: 250,
             max_sample_age_secs: 60,
             provider_group_max_fraction: 0.5,
             provider_groups: HashMap::new(),
@@ -188,14 +828,307 @@ impl Default for SelectionConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl SelectionConfig {
+    /// Consensus vote multiplier for `server`.
+    pub fn server_weight(&self, server: &str) -> f64 {
+        self.server_options.get(server).map_or(1.0, |s| s.weight)
+    }
+
+    /// Selection priority for `server`; higher wins.
+    pub fn server_priority(&self, server: &str) -> u8 {
+        self.server_options.get(server).map_or(0, |s| s.priority)
+    }
+}
+
+/// One upstream server: an `NTP_SERVERS` entry
+/// `host[:port][;weight=W][;priority=P][;key=ID][;timeout=T][;samples=N][;tier=TIER]`
+/// or an `NTP_SERVERS_FILE` block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ServerConfig {
+    /// Normalised upstream address, as used everywhere else to key the server.
+    pub address: String,
+    /// Multiplier on the server's vote in the consensus offset (weighted
+    /// median, `weighted` strategy mean). Default: 1.
+    pub weight: f64,
+    /// The served agreer is picked from the highest-priority servers that
+    /// pass the gates and agree with the consensus; lower priorities are only
+    /// used when none do. Default: 0.
+    pub priority: u8,
+    /// `NTP_KEYS_FILE` key that authenticates exchanges with this server.
+    /// Default: none (unauthenticated).
+    pub key_id: Option<u32>,
+    /// Per-query timeout (ms) overriding `NTP_TIMEOUT`. Default: none.
+    pub timeout_ms: Option<u64>,
+    /// Exchanges per sync; the lowest-delay one is used, like ntpd's
+    /// `burst`. Default: 1.
+    pub samples: u32,
+    /// `fallback` servers are only queried when the primary tier yields
+    /// fewer usable results than `MIN_QUORUM`. Default: primary.
+    pub tier: ServerTier,
+}
+
+/// Which pool a server belongs to, e.g. internal stratum-1 servers as
+/// `primary` and public pools as a last-resort `fallback`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerTier {
+    /// Queried on every sync.
+    #[default]
+    Primary,
+    /// Queried only when the primary tier cannot make a quorum.
+    Fallback,
+}
+
+/// Most exchanges one server may be sent per sync.
+const MAX_SERVER_SAMPLES: u32 = 8;
+
+impl ServerConfig {
+    /// A server with default options; `address` is normalised as in
+    /// `NTP_SERVERS`.
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: normalize_server(address),
+            weight: 1.0,
+            priority: 0,
+            key_id: None,
+            timeout_ms: None,
+            samples: 1,
+            tier: ServerTier::Primary,
+        }
+    }
+
+    /// Parse one entry, e.g. `ntp.corp.internal;weight=3;priority=1`.
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut parts = raw.split(';');
+        let mut server = Self::new(parts.next().unwrap_or_default());
+        if server.address.is_empty() {
+            anyhow::bail!("Invalid NTP_SERVERS entry: {}", raw.trim());
+        }
+        for option in parts.map(str::trim).filter(|o| !o.is_empty()) {
+            let (key, value) = option
+                .split_once('=')
+                .with_context(|| format!("Invalid NTP_SERVERS option: {}", option))?;
+            server.set_option(key.trim(), value.trim())?;
+        }
+        Ok(server)
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "weight" => {
+                self.weight = value
+                    .parse()
+                    .ok()
+                    .filter(|w: &f64| w.is_finite() && *w > 0.0)
+                    .with_context(|| format!("Invalid NTP_SERVERS weight: {}", value))?;
+            }
+            "priority" => {
+                self.priority = value
+                    .parse()
+                    .with_context(|| format!("Invalid NTP_SERVERS priority: {}", value))?;
+            }
+            "key" => {
+                self.key_id = Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid NTP_SERVERS key: {}", value))?,
+                );
+            }
+            "timeout" => {
+                self.timeout_ms = Some(
+                    parse_duration(value, Duration::from_secs(1))
+                        .ok()
+                        .filter(|t| !t.is_zero() && t.subsec_nanos() % 1_000_000 == 0)
+                        .map(|t| t.as_millis() as u64)
+                        .with_context(|| format!("Invalid NTP_SERVERS timeout: {}", value))?,
+                );
+            }
+            "samples" => {
+                self.samples = value
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=MAX_SERVER_SAMPLES).contains(n))
+                    .with_context(|| {
+                        format!(
+                            "Invalid NTP_SERVERS samples: {} (must be 1..={})",
+                            value, MAX_SERVER_SAMPLES
+                        )
+                    })?;
+            }
+            "tier" => {
+                self.tier = match value.to_lowercase().as_str() {
+                    "primary" => ServerTier::Primary,
+                    "fallback" => ServerTier::Fallback,
+                    _ => anyhow::bail!("Invalid NTP_SERVERS tier: {}", value),
+                };
+            }
+            other => anyhow::bail!("Invalid NTP_SERVERS option: {}", other),
+        }
+        Ok(())
+    }
+
+    /// This server's query timeout given the global `NTP_TIMEOUT`.
+    pub fn timeout(&self, default_ms: u64) -> Duration {
+        Duration::from_millis(self.timeout_ms.unwrap_or(default_ms))
+    }
+}
+
+/// One block of an `NTP_SERVERS_FILE`; the options of an `NTP_SERVERS` entry
+/// as JSON fields.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ServerBlock {
+    address: String,
+    weight: Option<f64>,
+    priority: Option<u8>,
+    key: Option<u32>,
+    timeout: Option<String>,
+    samples: Option<u32>,
+    tier: Option<String>,
+}
+
+/// Parse an `NTP_SERVERS_FILE`: a JSON array of server blocks, e.g.
+/// `[{"address": "ntp.corp.internal", "weight": 3, "timeout": "500ms", "samples": 4}]`.
+pub fn parse_servers_file(content: &str) -> Result<Vec<ServerConfig>> {
+    let blocks: Vec<ServerBlock> =
+        serde_json::from_str(content).context("Invalid NTP_SERVERS_FILE")?;
+    blocks
+        .into_iter()
+        .map(|block| {
+            let mut server = ServerConfig::new(&block.address);
+            if server.address.is_empty() {
+                anyhow::bail!("Invalid NTP_SERVERS_FILE address: {:?}", block.address);
+            }
+            let options = [
+                ("weight", block.weight.map(|w| w.to_string())),
+                ("priority", block.priority.map(|p| p.to_string())),
+                ("key", block.key.map(|k| k.to_string())),
+                ("timeout", block.timeout),
+                ("samples", block.samples.map(|n| n.to_string())),
+                ("tier", block.tier),
+            ];
+            for (key, value) in options {
+                if let Some(value) = value {
+                    server.set_option(key, value.trim()).with_context(|| {
+                        format!("Invalid NTP_SERVERS_FILE entry {}", server.address)
+                    })?;
+                }
+            }
+            Ok(server)
+        })
+        .collect()
+}
+
+/// Digest of an `NTP_KEYS_FILE` key (RFC 5905 appendix A.2 MAC, plus the
+/// SHA-1 and AES-CMAC variants ntpd accepts).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MacAlgorithm {
+    Md5,
+    Sha1,
+    Aes128Cmac,
+}
+
+impl MacAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MacAlgorithm::Md5 => "MD5",
+            MacAlgorithm::Sha1 => "SHA1",
+            MacAlgorithm::Aes128Cmac => "AES128CMAC",
+        }
+    }
+}
+
+/// One symmetric key from `NTP_KEYS_FILE`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct NtpKey {
+    /// Key identifier sent in the MAC, 1..=65534.
+    pub id: u32,
+    pub algorithm: MacAlgorithm,
+    pub secret: Vec<u8>,
+}
+
+impl std::fmt::Debug for NtpKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NtpKey")
+            .field("id", &self.id)
+            .field("algorithm", &self.algorithm)
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Parse an ntpd-style keys file: one `ID TYPE KEY` per line, `#` comments.
+///
+/// `TYPE` is `M`/`MD5`, `SHA1` or `AES128CMAC`.  A `KEY` of up to 20
+/// characters is used as ASCII, a longer one is decoded as hex.
+pub fn parse_ntp_keys(content: &str) -> Result<HashMap<u32, NtpKey>> {
+    let mut keys = HashMap::new();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [id, algorithm, secret] = fields[..] else {
+            anyhow::bail!("Invalid NTP_KEYS_FILE line {}: expected ID TYPE KEY", n + 1);
+        };
+        let id: u32 = id
+            .parse()
+            .ok()
+            .filter(|id| (1..=65534).contains(id))
+            .with_context(|| format!("Invalid NTP_KEYS_FILE key id: {}", id))?;
+        let algorithm = match algorithm.to_uppercase().as_str() {
+            "M" | "MD5" => MacAlgorithm::Md5,
+            "SHA1" => MacAlgorithm::Sha1,
+            "AES128CMAC" => MacAlgorithm::Aes128Cmac,
+            other => anyhow::bail!("Invalid NTP_KEYS_FILE key type: {}", other),
+        };
+        let secret = if secret.len() <= 20 {
+            secret.as_bytes().to_vec()
+        } else {
+            hex_decode(secret)
+                .with_context(|| format!("Invalid NTP_KEYS_FILE hex key for id {}", id))?
+        };
+        if algorithm == MacAlgorithm::Aes128Cmac && secret.len() != 16 {
+            anyhow::bail!("NTP_KEYS_FILE key {} must be 16 bytes for AES128CMAC", id);
+        }
+        if keys
+            .insert(
+                id,
+                NtpKey {
+                    id,
+                    algorithm,
+                    secret,
+                },
+            )
+            .is_some()
+        {
+            anyhow::bail!("Duplicate NTP_KEYS_FILE key id: {}", id);
+        }
+    }
+    Ok(keys)
+}
+
+/// Decode an even-length hex string; `None` on invalid input.
+fn hex_decode(input: &str) -> Option<Vec<u8>> {
+    if !input.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..input.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(input.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct NtpServerConfig {
-    /// Whether to listen for NTP client requests on UDP.
+    /// Whether to listen for NTP client requests on UDP. Set via `NTP_SERVER_ENABLED`.
     pub enabled: bool,
-    /// UDP bind address. Default `0.0.0.0:123`. Binding to ports < 1024
+    /// UDP bind address. Set via `NTP_SERVER_ADDR`. Default `0.0.0.0:123`. Binding to ports < 1024
     /// requires `CAP_NET_BIND_SERVICE` (or root).
     pub addr: SocketAddr,
-    /// Maximum packet size we will accept from a client.
+    /// Maximum packet size we will accept from a client. Set via `NTP_SERVER_MAX_PACKET_SIZE`.
     pub max_packet_size: usize,
     /// Hard ceiling on the `root_dispersion` we will ever advertise to
     /// downstream NTP clients (milliseconds). RFC 5905 §7.1 caps
@@ -213,73 +1146,406 @@ pub struct NtpServerConfig {
 /// * `max_duration_secs` — maximum connection length before the
 ///   server auto-closes. `0` is "unlimited" (no cap). The
 ///   `validate()` method enforces sane bounds.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub struct WsConfig {
+    /// Interval between `/stream` updates. Set via `WS_UPDATE_INTERVAL_MS`. Default: 1000.
     pub update_interval_ms: u64,
+    /// Shortest interval a client may ask for with `/stream?interval_ms=`. Set via
+    /// `WS_MIN_UPDATE_INTERVAL_MS`. Default: 100.
+    pub min_update_interval_ms: u64,
+    /// Longest `/stream` connection. Set via `WS_MAX_DURATION_SECS`. Default: 3600.
     pub max_duration_secs: u64,
+    /// Seconds between server Ping frames on `/stream`. Set via `WS_PING_INTERVAL_SECS`.
+    /// Default: 30 (0 disables heartbeats).
+    pub ping_interval_secs: u64,
+    /// Unanswered Pings after which a `/stream` connection is closed. Set via
+    /// `WS_MAX_MISSED_PONGS`. Default: 2.
+    pub max_missed_pongs: u32,
+    /// Close a `/stream` connection that sends no frame (Pongs included) for this long. Set
+    /// via `WS_IDLE_TIMEOUT_SECS`. Default: 120 (0 disables).
+    pub idle_timeout_secs: u64,
+    /// `/stream` connections open at once; further handshakes get 429. Set via
+    /// `WS_MAX_CONNECTIONS`. Default: 10000 (0 disables the limit).
+    pub max_connections: usize,
+    /// Ticks buffered per connection before `slow_consumer_policy` applies. Set via
+    /// `WS_SEND_QUEUE_CAPACITY`. Default: 8.
+    pub send_queue_capacity: usize,
+    /// What to do when a client's send queue is full. Set via `WS_SLOW_CONSUMER_POLICY`. Default:
+    /// drop_oldest.
+    pub slow_consumer_policy: SlowConsumerPolicy,
+}
+
+/// gRPC API (`proto/time_service.proto`).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GrpcConfig {
+    /// Whether to serve the gRPC API. Set via `GRPC_ENABLED`. Default: false.
+    pub enabled: bool,
+    /// TCP bind address of the gRPC server. Set via `GRPC_ADDR`. Default: `0.0.0.0:50051`.
+    pub addr: SocketAddr,
+    /// API keys accepted in `x-api-key` or `authorization: Bearer` metadata,
+    /// comma-separated. Set via `GRPC_API_KEYS` (or `GRPC_API_KEYS_FILE`). Default: empty.
+    pub api_keys: Vec<String>,
+    /// Which RPCs require credentials once any gRPC auth is configured. Set via
+    /// `GRPC_AUTH_SCOPE`. Default: streams.
+    pub auth_scope: GrpcAuthScope,
+    /// PEM certificate chain for gRPC over TLS. Set via `GRPC_TLS_CERT_FILE`. Default: unset
+    /// (plaintext h2c).
+    pub tls_cert_file: Option<String>,
+    /// PEM private key for `tls_cert_file`. Set via `GRPC_TLS_KEY_FILE`. Default: unset.
+    pub tls_key_file: Option<String>,
+    /// PEM CA bundle; client certificates it signs authenticate the caller (mTLS).
+    /// Set via `GRPC_TLS_CLIENT_CA_FILE`. Default: unset.
+    pub tls_client_ca_file: Option<String>,
+}
+
+impl GrpcConfig {
+    /// Whether `tls_cert_file` and `tls_key_file` are both set.
+    pub fn tls_enabled(&self) -> bool {
+        self.tls_cert_file.is_some() && self.tls_key_file.is_some()
+    }
+}
+
+/// RPCs covered by gRPC authentication.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GrpcAuthScope {
+    /// Only the streaming RPCs, mirroring `JWT_PROTECT_STREAM` on `/stream`.
+    Streams,
+    /// Every RPC.
+    All,
+}
+
+/// Policy for a stream client that cannot keep up with its tick rate.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SlowConsumerPolicy {
+    /// Discard the oldest queued tick to make room for the new one.
+    DropOldest,
+    /// Discard everything queued and keep only the newest tick.
+    Coalesce,
+    /// Close the connection.
+    Disconnect,
+}
+
+impl SlowConsumerPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SlowConsumerPolicy::DropOldest => "drop_oldest",
+            SlowConsumerPolicy::Coalesce => "coalesce",
+            SlowConsumerPolicy::Disconnect => "disconnect",
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LoggingConfig {
+    /// Default log filter. Set via `LOG_LEVEL`. Default: `info`.
     pub level: String,
+    /// Log output format, `json` or `pretty`. Set via `LOG_FORMAT`. Default: `json`.
     pub format: LogFormat,
+    /// Head-sampling ratio in `[0, 1]` for request spans on the slow path
+    /// (and any future trace exporter). Set via `TRACE_SAMPLE_RATIO`. Default: 1.0.
+    pub trace_sample_ratio: f64,
+    /// Log failed requests (5xx, timeouts) even when they were not sampled.
+    /// Set via `TRACE_ALWAYS_SAMPLE_ERRORS`. Default: true.
+    pub trace_always_sample_errors: bool,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum LogFormat {
     Json,
     Pretty,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MessageConfig {
+    /// `message` of a synced `/time` response. Set via `MSG_OK`. Default: `done`.
     pub ok: String,
+    /// `message` of a cached `/time` response. Set via `MSG_OK_CACHE`. Default: `done`.
     pub ok_cache: String,
+    /// `message` of an error response. Set via `MSG_ERROR`. Default: `error`.
     pub error: String,
+    /// Error text while unsynchronized. Set via `ERROR_TEXT_NO_SYNC`. Default: `Service not yet
+    /// synchronized with NTP`.
     pub error_no_sync: String,
+    /// Error text for internal failures. Set via `ERROR_TEXT_INTERNAL`. Default: `Internal server
+    /// error`.
     pub error_internal: String,
+    /// Error text for timed-out requests. Set via `ERROR_TEXT_TIMEOUT`. Default: `Request timeout`.
     pub error_timeout: String,
 }
 
 /// Resolve the replica ID using the priority chain:
 /// `REPLICA_ID` → `HOSTNAME` → `replica-<pid>`.
 pub(crate) fn resolve_replica_id() -> String {
-    std::env::var("REPLICA_ID")
-        .ok()
+    env_var("REPLICA_ID")
         .filter(|s| !s.is_empty())
         .or_else(|| std::env::var("HOSTNAME").ok().filter(|s| !s.is_empty()))
         .unwrap_or_else(|| format!("replica-{}", std::process::id()))
 }
 
+/// Trim an NTP server entry and default the port to 123.
+fn normalize_server(raw: &str) -> String {
+    let s = raw.trim().to_string();
+    if s.is_empty() || s.contains(':') {
+        s
+    } else {
+        format!("{}:123", s)
+    }
+}
+
+/// Parse a `"key1=value1,key2=value2"` list; malformed or empty entries are skipped.
+fn parse_key_value_list(raw: &str) -> HashMap<String, String> {
+    raw.split(',')
+        .filter(|s| s.contains('='))
+        .filter_map(|s| {
+            let mut parts = s.splitn(2, '=');
+            let k = parts.next()?.trim().to_string();
+            let v = parts.next()?.trim().to_string();
+            if k.is_empty() || v.is_empty() {
+                None
+            } else {
+                Some((k, v))
+            }
+        })
+        .collect()
+}
+
+/// `NTP_SERVERS` when unset.
+const DEFAULT_NTP_SERVERS: &str = "time.google.com:123,time.cloudflare.com:123,pool.ntp.org:123";
+
+/// Add `default` and `x-env` to the properties of `node`, recursing into
+/// the nested config structs in `defs`.
+fn annotate_schema(
+    node: &mut serde_json::Value,
+    defaults: &serde_json::Value,
+    defs: &mut serde_json::Value,
+) {
+    let Some(properties) = node
+        .get_mut("properties")
+        .and_then(serde_json::Value::as_object_mut)
+    else {
+        return;
+    };
+    for (name, property) in properties.iter_mut() {
+        let env = property
+            .get("description")
+            .and_then(serde_json::Value::as_str)
+            .map(documented_env_vars)
+            .unwrap_or_default();
+        if !env.is_empty() {
+            property["x-env"] = env.into();
+        }
+        // Secrets are never serialized, so they have no default to show.
+        let Some(default) = defaults.get(name) else {
+            continue;
+        };
+        property["default"] = default.clone();
+        let nested = property
+            .get("$ref")
+            .and_then(serde_json::Value::as_str)
+            .and_then(|r| r.strip_prefix("#/$defs/"))
+            .filter(|_| default.is_object());
+        if let Some(def_name) = nested
+            && let Some(mut def) = defs.get_mut(def_name).map(serde_json::Value::take)
+        {
+            annotate_schema(&mut def, default, defs);
+            defs[def_name] = def;
+        }
+    }
+}
+
+/// Variables a field doc names in its "Set via `NAME`" / "Set `NAME=..`" /
+/// "... via `NAME`" sentence, e.g. `ADMIN_API_TOKEN` and
+/// `ADMIN_API_TOKEN_FILE` in "Set via `ADMIN_API_TOKEN` or `ADMIN_API_TOKEN_FILE`.".
+fn documented_env_vars(doc: &str) -> Vec<String> {
+    let doc = doc.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut names = Vec::new();
+    for marker in ["via `", "Set `"] {
+        for (start, _) in doc.match_indices(marker) {
+            let sentence = doc[start + marker.len() - 1..]
+                .split(". ")
+                .next()
+                .unwrap_or_default();
+            let quoted = sentence.split('`').skip(1).step_by(2);
+            for name in quoted.map(|q| q.split('=').next().unwrap_or_default()) {
+                let is_env = !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+                if is_env && !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Upper bound on one startup lookup in [`Config::check_dns`].
+const DNS_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Prefix of the namespaced setting names: `NTPAPI_ADDR` is read before
+/// `ADDR`.
+pub const ENV_PREFIX: &str = "NTPAPI_";
+
+/// Unprefixed names that supplied a value, for a deprecation warning once
+/// logging is up.
+static LEGACY_ENV_VARS: std::sync::Mutex<BTreeSet<String>> = std::sync::Mutex::new(BTreeSet::new());
+
+/// Read setting `key` as `NTPAPI_{key}`, falling back to the deprecated
+/// unprefixed name.
+fn env_var(key: &str) -> Option<String> {
+    if let Ok(value) = std::env::var(format!("{ENV_PREFIX}{key}")) {
+        return Some(value);
+    }
+    let value = std::env::var(key).ok()?;
+    LEGACY_ENV_VARS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key.to_string());
+    Some(value)
+}
+
+/// Settings read from their deprecated unprefixed names so far.
+pub fn legacy_env_vars() -> Vec<String> {
+    LEGACY_ENV_VARS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect()
+}
+
 fn env_or_default(key: &str, default: &str) -> String {
-    std::env::var(key).unwrap_or_else(|_| default.to_string())
+    env_var(key).unwrap_or_else(|| default.to_string())
 }
 
 fn env_or_parse<T: std::str::FromStr>(key: &str, default: T) -> T
 where
     T::Err: std::fmt::Debug,
 {
-    std::env::var(key)
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(default)
+    env_var(key).and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// A secret setting, read from `{key}` or from the file named by
+/// `{key}_FILE` (Docker / Kubernetes secrets).  Setting both is an error; a
+/// single trailing newline in the file is ignored.
+fn env_secret(key: &str) -> Result<String> {
+    let file_key = format!("{key}_FILE");
+    let path = env_var(&file_key).filter(|p| !p.trim().is_empty());
+    match (env_var(key), path) {
+        (Some(_), Some(_)) => anyhow::bail!("Set only one of {key} and {file_key}"),
+        (value, None) => Ok(value.unwrap_or_default()),
+        (None, Some(path)) => {
+            let content = std::fs::read_to_string(path.trim())
+                .with_context(|| format!("Failed to read {file_key} {}", path.trim()))?;
+            let content = content.strip_suffix('\n').unwrap_or(&content);
+            Ok(content.strip_suffix('\r').unwrap_or(content).to_string())
+        }
+    }
+}
+
+/// Parse a duration setting: a humantime string (`30s`, `2m`, `1500ms`,
+/// `1h 30m`) or, as before, a bare integer counted in `bare_unit`.
+fn parse_duration(raw: &str, bare_unit: Duration) -> Result<Duration> {
+    let raw = raw.trim();
+    match raw.parse::<u64>() {
+        Ok(n) => Ok(Duration::from_millis(
+            n.saturating_mul(bare_unit.as_millis() as u64),
+        )),
+        Err(_) => humantime::parse_duration(raw).map_err(anyhow::Error::from),
+    }
+}
+
+/// Whole seconds from a duration env var; bare integers are seconds.
+fn env_or_secs(key: &str, default: u64) -> Result<u64> {
+    let Some(raw) = env_var(key) else {
+        return Ok(default);
+    };
+    let duration = parse_duration(&raw, Duration::from_secs(1))
+        .with_context(|| format!("Invalid {key}: {raw}"))?;
+    if duration.subsec_nanos() != 0 {
+        anyhow::bail!("{key} must be a whole number of seconds, got {raw}");
+    }
+    Ok(duration.as_secs())
+}
+
+/// Whole milliseconds from a duration env var; bare integers are `bare_unit`
+/// (milliseconds for `*_MS` settings).
+fn env_or_millis(key: &str, default: u64, bare_unit: Duration) -> Result<u64> {
+    let Some(raw) = env_var(key) else {
+        return Ok(default);
+    };
+    let duration =
+        parse_duration(&raw, bare_unit).with_context(|| format!("Invalid {key}: {raw}"))?;
+    if duration.subsec_nanos() % 1_000_000 != 0 {
+        anyhow::bail!("{key} must be a whole number of milliseconds, got {raw}");
+    }
+    Ok(duration.as_millis() as u64)
 }
 
 impl Config {
     pub fn from_env() -> Result<Self> {
         // HTTP config
-        let addr = env_or_default("ADDR", "0.0.0.0:8080")
-            .parse()
-            .context("Failed to parse ADDR")?;
-        let request_timeout_secs = env_or_parse("REQUEST_TIMEOUT", 5);
+        let listen = env_or_default("ADDR", "0.0.0.0:8080")
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(ListenAddr::parse)
+            .collect::<Result<Vec<_>>>()?;
+        let ops_listen = Some(env_or_default("OPS_ADDR", ""))
+            .filter(|raw| !raw.trim().is_empty())
+            .map(|raw| ListenAddr::parse(&raw).context("Failed to parse OPS_ADDR"))
+            .transpose()?;
+        let request_timeout_secs = env_or_secs("REQUEST_TIMEOUT", 5)?;
         let body_limit_bytes = env_or_parse("BODY_LIMIT_BYTES", 1024);
         let tcp_nodelay = env_or_parse("TCP_NODELAY", true);
-        let tcp_keepalive_secs = match env_or_parse("TCP_KEEPALIVE_SECS", 0) {
+        let tcp_keepalive_secs = match env_or_secs("TCP_KEEPALIVE_SECS", 0)? {
             0 => None,
             n => Some(n),
         };
+        let listen_backlog = env_or_parse("LISTEN_BACKLOG", 1024i32);
+        let fast_path_request_id = env_or_parse("FAST_PATH_REQUEST_ID", true);
+        let fast_path_metrics = env_or_parse("FAST_PATH_METRICS", false);
         let disable_rate_limiting = env_or_parse("DISABLE_RATE_LIMITING", false);
+        let rate_limit_per_second = env_or_parse("RATE_LIMIT_PER_SECOND", 1000u32);
+        let rate_limit_burst = env_or_parse("RATE_LIMIT_BURST", 100u32);
+        let ip_list = |key: &str| -> Vec<String> {
+            env_or_default(key, "")
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let trusted_proxies = ip_list("TRUSTED_PROXIES");
+        let ip_allowlist = ip_list("IP_ALLOWLIST");
+        let ip_denylist = ip_list("IP_DENYLIST");
+        let ip_filter_scope = match env_or_default("IP_FILTER_SCOPE", "all")
+            .to_lowercase()
+            .as_str()
+        {
+            "all" => IpFilterScope::All,
+            "ops" => IpFilterScope::Ops,
+            other => anyhow::bail!("Invalid IP_FILTER_SCOPE: {}", other),
+        };
+        let max_inflight_requests = env_or_parse("MAX_INFLIGHT_REQUESTS", 0usize);
+        let shutdown_readiness_delay_secs = env_or_secs("SHUTDOWN_READINESS_DELAY_SECS", 5)?;
+        let shutdown_drain_secs = env_or_secs("SHUTDOWN_DRAIN_SECS", 20)?;
+        let epoch_as_string = env_or_parse("EPOCH_AS_STRING", false);
+        let jsonp_enabled = env_or_parse("JSONP_ENABLED", false);
+        let compression_enabled = env_or_parse("HTTP_COMPRESSION", true);
+        let tls_file = |key: &str| Some(env_or_default(key, "")).filter(|v| !v.trim().is_empty());
+        let tls_cert_file = tls_file("TLS_CERT_FILE");
+        let tls_key_file = tls_file("TLS_KEY_FILE");
+        let tls_reload_interval_secs = env_or_secs("TLS_RELOAD_INTERVAL_SECS", 60)?;
+        let http2_enabled = env_or_parse("HTTP2_ENABLED", true);
+        let http2_max_concurrent_streams = env_or_parse("HTTP2_MAX_CONCURRENT_STREAMS", 200u32);
+        let http2_initial_stream_window_size =
+            env_or_parse("HTTP2_INITIAL_STREAM_WINDOW_SIZE", 1024 * 1024u32);
+        let http2_initial_connection_window_size =
+            env_or_parse("HTTP2_INITIAL_CONNECTION_WINDOW_SIZE", 1024 * 1024u32);
 
         // Logging config
         let level = env_or_default("LOG_LEVEL", "info");
@@ -287,24 +1553,34 @@ impl Config {
             "pretty" => LogFormat::Pretty,
             _ => LogFormat::Json,
         };
+        let trace_sample_ratio = env_or_parse("TRACE_SAMPLE_RATIO", 1.0f64);
+        let trace_always_sample_errors = env_or_parse("TRACE_ALWAYS_SAMPLE_ERRORS", true);
 
         // NTP config
-        let servers_str = env_or_default(
-            "NTP_SERVERS",
-            "time.google.com:123,time.cloudflare.com:123,pool.ntp.org:123",
-        );
-        let servers: Vec<String> = servers_str
-            .split(',')
-            .map(|s| {
-                let s = s.trim().to_string();
-                if s.is_empty() || s.contains(':') {
-                    s
-                } else {
-                    format!("{}:123", s)
+        let servers_file = env_var("NTP_SERVERS_FILE").filter(|p| !p.trim().is_empty());
+        let servers: Vec<ServerConfig> = match servers_file {
+            Some(path) => {
+                if env_var("NTP_SERVERS").is_some() {
+                    anyhow::bail!("Set only one of NTP_SERVERS and NTP_SERVERS_FILE");
                 }
-            })
-            .filter(|s| !s.is_empty())
-            .collect();
+                let content = std::fs::read_to_string(path.trim())
+                    .with_context(|| format!("Failed to read NTP_SERVERS_FILE {}", path.trim()))?;
+                parse_servers_file(&content)?
+            }
+            None => env_or_default("NTP_SERVERS", DEFAULT_NTP_SERVERS)
+                .split(',')
+                .filter(|s| !s.trim().is_empty())
+                .map(ServerConfig::parse)
+                .collect::<Result<_>>()?,
+        };
+        let keys = match env_var("NTP_KEYS_FILE").filter(|p| !p.trim().is_empty()) {
+            Some(path) => {
+                let content = std::fs::read_to_string(path.trim())
+                    .with_context(|| format!("Failed to read NTP_KEYS_FILE {}", path.trim()))?;
+                parse_ntp_keys(&content)?
+            }
+            None => HashMap::new(),
+        };
 
         if servers.is_empty() {
             anyhow::bail!("NTP_SERVERS cannot be empty");
@@ -320,58 +1596,171 @@ impl Config {
         let ntp_server_max_root_dispersion_ms =
             env_or_parse("NTP_SERVER_MAX_ROOT_DISPERSION_MS", 16_000u64);
 
-        // WebSocket config. 0 / unparseable falls back to the default.
-        // We apply the .filter(|&ms| ms > 0) guard here so the
-        // per-connection handler doesn't have to re-do the validation
-        // and divide-by-zero in the max_updates calculation.
-        let ws_update_interval_ms = env_or_parse("WS_UPDATE_INTERVAL_MS", 1000u64).max(1);
-        let ws_max_duration_secs = env_or_parse("WS_MAX_DURATION_SECS", 3600u64);
-
-        let timeout_secs = env_or_parse("NTP_TIMEOUT", 2);
-        let sync_interval_secs = env_or_parse("SYNC_INTERVAL", 30);
-        let probe_min_interval_secs = env_or_parse("PROBE_MIN_INTERVAL", 10);
-        let probe_max_interval_secs = env_or_parse("PROBE_MAX_INTERVAL", 20);
-        let max_staleness_secs = env_or_parse("MAX_STALENESS", 120);
-        let require_sync = env_or_parse("REQUIRE_SYNC", true);
-
-        let selection_strategy = match env_or_default("SELECTION_STRATEGY", "rtt_min")
+        // WebSocket config. The update interval is clamped to at least
+        // 1 ms here so the per-connection handler doesn't have to re-do the validation
+        // and divide-by-zero in the tick schedule.
+        let ws_update_interval_ms =
+            env_or_millis("WS_UPDATE_INTERVAL_MS", 1000, Duration::from_millis(1))?.max(1);
+        let ws_min_update_interval_ms =
+            env_or_millis("WS_MIN_UPDATE_INTERVAL_MS", 100, Duration::from_millis(1))?;
+        let ws_max_duration_secs = env_or_secs("WS_MAX_DURATION_SECS", 3600)?;
+        let ws_max_connections = env_or_parse("WS_MAX_CONNECTIONS", 10_000usize);
+        let ws_ping_interval_secs = env_or_secs("WS_PING_INTERVAL_SECS", 30)?;
+        let ws_max_missed_pongs = env_or_parse("WS_MAX_MISSED_PONGS", 2u32);
+        let ws_idle_timeout_secs = env_or_secs("WS_IDLE_TIMEOUT_SECS", 120)?;
+        let ws_send_queue_capacity = env_or_parse("WS_SEND_QUEUE_CAPACITY", 8usize);
+        let ws_slow_consumer_policy = match env_or_default("WS_SLOW_CONSUMER_POLICY", "drop_oldest")
             .to_lowercase()
             .as_str()
         {
-            "rtt_min" | "accuracy_first" => SelectionStrategy::AccuracyFirst,
-            other => anyhow::bail!("Invalid SELECTION_STRATEGY: {}", other),
+            "drop_oldest" => SlowConsumerPolicy::DropOldest,
+            "coalesce" => SlowConsumerPolicy::Coalesce,
+            "disconnect" => SlowConsumerPolicy::Disconnect,
+            other => anyhow::bail!("Invalid WS_SLOW_CONSUMER_POLICY: {}", other),
         };
 
-        // P1-6 selection config
-        let sel_max_stratum = env_or_parse("MAX_STRATUM", 4u8);
-        let sel_min_quorum = env_or_parse("MIN_QUORUM", 2usize);
-        let sel_reject_leap_alarm = env_or_parse("REJECT_LEAP_ALARM", true);
+        let grpc_enabled = env_or_parse("GRPC_ENABLED", false);
+        let grpc_addr = env_or_default("GRPC_ADDR", "0.0.0.0:50051")
+            .parse()
+            .context("Failed to parse GRPC_ADDR")?;
+        let grpc_api_keys = env_secret("GRPC_API_KEYS")?
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(String::from)
+            .collect();
+        let grpc_auth_scope = match env_or_default("GRPC_AUTH_SCOPE", "streams")
+            .to_lowercase()
+            .as_str()
+        {
+            "streams" => GrpcAuthScope::Streams,
+            "all" => GrpcAuthScope::All,
+            other => anyhow::bail!("Invalid GRPC_AUTH_SCOPE: {}", other),
+        };
+        let grpc_tls_cert_file = tls_file("GRPC_TLS_CERT_FILE");
+        let grpc_tls_key_file = tls_file("GRPC_TLS_KEY_FILE");
+        let grpc_tls_client_ca_file = tls_file("GRPC_TLS_CLIENT_CA_FILE");
+
+        let timeout_ms = env_or_millis("NTP_TIMEOUT", 2000, Duration::from_secs(1))?;
+        let sync_interval_secs = env_or_secs("SYNC_INTERVAL", 30)?;
+        let sync_interval_max_secs = env_or_secs("SYNC_INTERVAL_MAX", sync_interval_secs)?;
+        let poll_stable_offset_ms = env_or_parse("POLL_STABLE_OFFSET_MS", 5u64);
+        let max_step_ms = env_or_parse("MAX_STEP_MS", 0u64);
+        let max_step_stepout = env_or_parse("MAX_STEP_STEPOUT", 3u32);
+        let probe_min_interval_secs = env_or_secs("PROBE_MIN_INTERVAL", 10)?;
+        let probe_max_interval_secs = env_or_secs("PROBE_MAX_INTERVAL", 20)?;
+        let max_staleness_secs = env_or_secs("MAX_STALENESS", 120)?;
+        let require_sync = env_or_parse("REQUIRE_SYNC", true);
+
+        let selection_strategy = match env_or_default("SELECTION_STRATEGY", "rtt_min")
+            .to_lowercase()
+            .as_str()
+        {
+            "rtt_min" | "accuracy_first" => SelectionStrategy::AccuracyFirst,
+            "offset_median" => SelectionStrategy::OffsetMedian,
+            "weighted" => SelectionStrategy::Weighted,
+            "stratum_first" => SelectionStrategy::StratumFirst,
+            other => anyhow::bail!("Invalid SELECTION_STRATEGY: {}", other),
+        };
+
+        // P1-6 selection config
+        let sel_max_stratum = env_or_parse("MAX_STRATUM", 4u8);
+        let sel_min_quorum = env_or_parse("MIN_QUORUM", 2usize);
+        let sel_reject_leap_alarm = env_or_parse("REJECT_LEAP_ALARM", true);
         let sel_max_root_distance_ms = env_or_parse("MAX_ROOT_DISTANCE_MS", 500.0f64);
-        let sel_max_sample_age_secs = env_or_parse("MAX_SAMPLE_AGE_SECS", 60u64);
+        let sel_max_root_dispersion_ms = env_or_parse("MAX_ROOT_DISPERSION_MS", 250u32);
+        let sel_max_sample_age_secs = env_or_secs("MAX_SAMPLE_AGE_SECS", 60)?;
         let sel_provider_group_max_fraction = env_or_parse("PROVIDER_GROUP_MAX_FRACTION", 0.5f64);
-        let sel_provider_groups: HashMap<String, String> = {
-            let raw = env_or_default("NTP_PROVIDER_GROUPS", "");
-            raw.split(',')
-                .filter(|s| s.contains('='))
-                .filter_map(|s| {
-                    let mut parts = s.splitn(2, '=');
-                    let k = parts.next()?.trim().to_string();
-                    let v = parts.next()?.trim().to_string();
-                    if k.is_empty() || v.is_empty() {
-                        None
-                    } else {
-                        Some((k, v))
-                    }
-                })
-                .collect()
-        };
+        let sel_provider_groups = parse_key_value_list(&env_or_default("NTP_PROVIDER_GROUPS", ""));
         let sel_max_offset_skew_ms = env_or_parse("MAX_OFFSET_SKEW_MS", 1000i64);
         let sel_interval_selection_enabled = env_or_parse("NTP_INTERVAL_SELECTION_ENABLED", true);
 
+        // NTP query scheduling
+        let query_stagger_window_ms =
+            env_or_millis("NTP_QUERY_STAGGER_MS", 0, Duration::from_millis(1))?;
+        let query_max_in_flight = env_or_parse("NTP_QUERY_MAX_IN_FLIGHT", 0usize);
+        let query_retries = env_or_parse("NTP_QUERY_RETRIES", 0u32);
+        let query_retries_by_server =
+            parse_key_value_list(&env_or_default("NTP_QUERY_RETRIES_OVERRIDES", ""))
+                .into_iter()
+                .map(|(server, n)| {
+                    n.parse().map(|n| (server.clone(), n)).with_context(|| {
+                        format!("Invalid NTP_QUERY_RETRIES_OVERRIDES entry for {server}")
+                    })
+                })
+                .collect::<Result<HashMap<String, u32>>>()?;
+        let query_retry_delay_ms =
+            env_or_millis("NTP_QUERY_RETRY_DELAY_MS", 500, Duration::from_millis(1))?;
+        let query_timeout_jitter_ms =
+            env_or_millis("NTP_QUERY_TIMEOUT_JITTER_MS", 0, Duration::from_millis(1))?;
+        let query_expand_pools = env_or_parse("NTP_POOL_EXPANSION", false);
+        let query_pool_resolve_interval_secs = env_or_secs("NTP_POOL_RESOLVE_INTERVAL_SECS", 300)?;
+        let query_pool_max_addresses = env_or_parse("NTP_POOL_MAX_ADDRESSES", 4usize);
+        let query_clock_filter = env_or_parse("NTP_CLOCK_FILTER_ENABLED", true);
+
+        // Automatic bias calibration
+        let calibration_enabled = env_or_parse("NTP_BIAS_CALIBRATION_ENABLED", false);
+        let calibration_reference = Some(normalize_server(&env_or_default(
+            "NTP_BIAS_CALIBRATION_REFERENCE",
+            "",
+        )))
+        .filter(|s| !s.is_empty());
+        let calibration_alpha = env_or_parse("NTP_BIAS_CALIBRATION_ALPHA", 0.05f64);
+        let calibration_min_samples = env_or_parse("NTP_BIAS_CALIBRATION_MIN_SAMPLES", 20u32);
+        let calibration_max_ms = env_or_parse("NTP_BIAS_CALIBRATION_MAX_MS", 50u64);
+        let calibration_asymmetry = env_or_parse("NTP_ASYMMETRY_ESTIMATION_ENABLED", false);
+
         let monotonic_output = env_or_parse("MONOTONIC_OUTPUT", true);
+        let monotonic_scope = match env_or_default("MONOTONIC_SCOPE", "global")
+            .to_lowercase()
+            .as_str()
+        {
+            "global" => MonotonicScope::Global,
+            "shard" => MonotonicScope::Shard,
+            other => anyhow::bail!("Invalid MONOTONIC_SCOPE: {}", other),
+        };
+        let slew_rate_ppm = env_or_parse("SLEW_RATE_PPM", 0u64);
+        let slew_max_offset_ms = env_or_parse("SLEW_MAX_OFFSET_MS", 128u64);
+        let drift_compensation = env_or_parse("DRIFT_COMPENSATION", false);
+        let leap_seconds_file = env_var("LEAP_SECONDS_FILE")
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty());
         let offset_bias_ms = env_or_parse("OFFSET_BIAS_MS", 0);
         let asymmetry_bias_ms = env_or_parse("ASYMMETRY_BIAS_MS", 0);
         let max_consecutive_failures = env_or_parse("MAX_CONSECUTIVE_FAILURES", 10);
+        let address_family = match env_or_default("NTP_ADDRESS_FAMILY", "any")
+            .to_lowercase()
+            .as_str()
+        {
+            "any" => AddressFamily::Any,
+            "ipv4" => AddressFamily::Ipv4,
+            "ipv6" => AddressFamily::Ipv6,
+            other => anyhow::bail!("Invalid NTP_ADDRESS_FAMILY: {}", other),
+        };
+        let dns_check = match env_or_default("NTP_DNS_CHECK", "warn")
+            .to_lowercase()
+            .as_str()
+        {
+            "fail" => DnsCheck::Fail,
+            "warn" => DnsCheck::Warn,
+            "off" => DnsCheck::Off,
+            other => anyhow::bail!("Invalid NTP_DNS_CHECK: {}", other),
+        };
+        let source = SourceBinding {
+            addr: Some(env_or_default("NTP_BIND_ADDR", ""))
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| s.trim().parse())
+                .transpose()
+                .context("Failed to parse NTP_BIND_ADDR")?,
+            interface: Some(env_or_default("NTP_BIND_INTERFACE", "").trim().to_string())
+                .filter(|s| !s.is_empty()),
+        };
+        // A source address only reaches servers of its own family.
+        let address_family = match (address_family, source.addr) {
+            (AddressFamily::Any, Some(IpAddr::V4(_))) => AddressFamily::Ipv4,
+            (AddressFamily::Any, Some(IpAddr::V6(_))) => AddressFamily::Ipv6,
+            (family, _) => family,
+        };
 
         // Message config
         let ok = env_or_default("MSG_OK", "done");
@@ -391,56 +1780,148 @@ impl Config {
         let serve_degraded_max_uncertainty_ms =
             env_or_parse("SERVE_DEGRADED_MAX_UNCERTAINTY_MS", 250.0f64);
         let readiness_max_uncertainty_ms = env_or_parse("READINESS_MAX_UNCERTAINTY_MS", 250.0f64);
+        let system_clock_check_interval_secs = env_or_secs("SYSTEM_CLOCK_CHECK_INTERVAL_SECS", 10)?;
+        let system_clock_alert_ms = env_or_parse("SYSTEM_CLOCK_DIVERGENCE_ALERT_MS", 1000u64);
+        let system_clock_shadow_window_secs =
+            env_or_secs("SYSTEM_CLOCK_SHADOW_WINDOW_SECS", 86_400)?;
+        let clock_jump_threshold_ms = env_or_parse("CLOCK_JUMP_THRESHOLD_MS", 1000u64);
+        let clock_jump_check_interval_secs = env_or_secs("CLOCK_JUMP_CHECK_INTERVAL_SECS", 1)?;
+        let deep_health_min_interval_secs = env_or_secs("DEEP_HEALTH_MIN_INTERVAL_SECS", 10)?;
 
         // Persistence config
         let persist_enabled = env_or_parse("TIME_STATE_PERSIST_ENABLED", false);
         let persist_file =
             env_or_default("TIME_STATE_FILE", "/var/lib/ntp-time-json-api/state.json");
+        let persist_interval_secs = env_or_secs("TIME_STATE_PERSIST_INTERVAL_SECS", 5)?;
+
+        let roughtime_servers = env_or_default("ROUGHTIME_SERVERS", "")
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(RoughtimeServerConfig::parse)
+            .collect::<Result<Vec<_>>>()?;
+        let roughtime_interval_secs = env_or_secs("ROUGHTIME_INTERVAL_SECS", 300)?;
+        let roughtime_max_disagreement_ms = env_or_parse("ROUGHTIME_MAX_DISAGREEMENT_MS", 1000u64);
 
         // P1-8: replica identity
         let replica_id = resolve_replica_id();
 
         // Admin API config (P1-7)
         let admin_enabled = env_or_parse("ADMIN_API_ENABLED", false);
-        let admin_token = env_or_default("ADMIN_API_TOKEN", "");
-        let admin_max_ttl_secs = env_or_parse("MANUAL_OVERRIDE_MAX_TTL_SECS", 300u32);
+        let admin_token = env_secret("ADMIN_API_TOKEN")?;
+        let admin_max_ttl_secs = u32::try_from(env_or_secs("MANUAL_OVERRIDE_MAX_TTL_SECS", 300)?)
+            .context("MANUAL_OVERRIDE_MAX_TTL_SECS is too large")?;
         let admin_max_jump_ms = env_or_parse("MANUAL_OVERRIDE_MAX_JUMP_MS", 5000u64);
         let admin_dispersion_ms = env_or_parse("MANUAL_OVERRIDE_DISPERSION_MS", 1000u64);
         let admin_allow_force = env_or_parse("MANUAL_OVERRIDE_ALLOW_FORCE", false);
 
+        // Observability endpoint auth
+        let metrics_auth_token = env_secret("METRICS_AUTH_TOKEN")?;
+        let metrics_auth_basic = env_secret("METRICS_AUTH_BASIC")?;
+
+        // JWT auth for /stream and the admin API
+        let non_empty = |key: &str| Some(env_or_default(key, "")).filter(|v| !v.trim().is_empty());
+        let jwt = JwtConfig {
+            hs256_secret: env_secret("JWT_HS256_SECRET")?,
+            jwks_url: non_empty("JWT_JWKS_URL"),
+            jwks_refresh_secs: env_or_secs("JWT_JWKS_REFRESH_SECS", 300)?,
+            issuer: non_empty("JWT_ISSUER"),
+            audience: non_empty("JWT_AUDIENCE"),
+            leeway_secs: env_or_secs("JWT_LEEWAY_SECS", 60)?,
+            admin_scope: env_or_default("JWT_ADMIN_SCOPE", "ntp:admin"),
+            protect_stream: env_or_parse("JWT_PROTECT_STREAM", true),
+        };
+
         let config = Config {
             http: HttpConfig {
-                addr,
+                listen,
+                ops_listen,
                 request_timeout_secs,
                 body_limit_bytes,
                 tcp_nodelay,
                 tcp_keepalive_secs,
+                listen_backlog,
+                fast_path_request_id,
+                fast_path_metrics,
                 disable_rate_limiting,
+                rate_limit_per_second,
+                rate_limit_burst,
+                trusted_proxies,
+                max_inflight_requests,
+                ip_allowlist,
+                ip_denylist,
+                ip_filter_scope,
+                shutdown_readiness_delay_secs,
+                shutdown_drain_secs,
+                epoch_as_string,
+                jsonp_enabled,
+                compression_enabled,
+                tls_cert_file,
+                tls_key_file,
+                tls_reload_interval_secs,
+                http2_enabled,
+                http2_max_concurrent_streams,
+                http2_initial_stream_window_size,
+                http2_initial_connection_window_size,
             },
             ntp: NtpConfig {
                 servers,
-                timeout_secs,
+                timeout_ms,
                 sync_interval_secs,
+                sync_interval_max_secs,
+                poll_stable_offset_ms,
+                max_step_ms,
+                max_step_stepout,
                 probe_min_interval_secs,
                 probe_max_interval_secs,
                 max_staleness_secs,
                 require_sync,
-                selection_strategy,
                 monotonic_output,
+                monotonic_scope,
+                slew_rate_ppm,
+                slew_max_offset_ms,
+                drift_compensation,
+                leap_seconds_file,
                 offset_bias_ms,
                 asymmetry_bias_ms,
                 max_consecutive_failures,
+                address_family,
+                dns_check,
+                source,
                 selection: SelectionConfig {
+                    strategy: selection_strategy,
+                    server_options: HashMap::new(),
                     max_stratum: sel_max_stratum,
                     min_quorum: sel_min_quorum,
                     reject_leap_alarm: sel_reject_leap_alarm,
                     max_root_distance_ms: sel_max_root_distance_ms,
+                    max_root_dispersion_ms: sel_max_root_dispersion_ms,
                     max_sample_age_secs: sel_max_sample_age_secs,
                     provider_group_max_fraction: sel_provider_group_max_fraction,
                     provider_groups: sel_provider_groups,
                     max_offset_skew_ms: sel_max_offset_skew_ms,
                     interval_selection_enabled: sel_interval_selection_enabled,
                 },
+                query: QueryConfig {
+                    stagger_window_ms: query_stagger_window_ms,
+                    max_in_flight: query_max_in_flight,
+                    retries: query_retries,
+                    retries_by_server: query_retries_by_server,
+                    retry_delay_ms: query_retry_delay_ms,
+                    timeout_jitter_ms: query_timeout_jitter_ms,
+                    expand_pools: query_expand_pools,
+                    pool_resolve_interval_secs: query_pool_resolve_interval_secs,
+                    pool_max_addresses: query_pool_max_addresses,
+                    clock_filter: query_clock_filter,
+                },
+                calibration: CalibrationConfig {
+                    enabled: calibration_enabled,
+                    reference_server: calibration_reference,
+                    alpha: calibration_alpha,
+                    min_samples: calibration_min_samples,
+                    max_correction_ms: calibration_max_ms,
+                    asymmetry_enabled: calibration_asymmetry,
+                },
+                keys,
             },
             ntp_server: NtpServerConfig {
                 enabled: ntp_server_enabled,
@@ -454,16 +1935,49 @@ impl Config {
                 serve_ok_max_uncertainty_ms,
                 serve_degraded_max_uncertainty_ms,
                 readiness_max_uncertainty_ms,
+                system_clock_check_interval_secs,
+                system_clock_alert_ms,
+                system_clock_shadow_window_secs,
+                clock_jump_threshold_ms,
+                clock_jump_check_interval_secs,
+                deep_health_min_interval_secs,
             },
             persist: PersistConfig {
                 enabled: persist_enabled,
                 file_path: persist_file,
+                interval_secs: persist_interval_secs,
+            },
+            roughtime: RoughtimeConfig {
+                servers: roughtime_servers,
+                interval_secs: roughtime_interval_secs,
+                max_disagreement_ms: roughtime_max_disagreement_ms,
             },
             ws: WsConfig {
                 update_interval_ms: ws_update_interval_ms,
+                min_update_interval_ms: ws_min_update_interval_ms,
                 max_duration_secs: ws_max_duration_secs,
+                ping_interval_secs: ws_ping_interval_secs,
+                max_missed_pongs: ws_max_missed_pongs,
+                idle_timeout_secs: ws_idle_timeout_secs,
+                max_connections: ws_max_connections,
+                send_queue_capacity: ws_send_queue_capacity,
+                slow_consumer_policy: ws_slow_consumer_policy,
+            },
+            grpc: GrpcConfig {
+                enabled: grpc_enabled,
+                addr: grpc_addr,
+                api_keys: grpc_api_keys,
+                auth_scope: grpc_auth_scope,
+                tls_cert_file: grpc_tls_cert_file,
+                tls_key_file: grpc_tls_key_file,
+                tls_client_ca_file: grpc_tls_client_ca_file,
+            },
+            logging: LoggingConfig {
+                level,
+                format,
+                trace_sample_ratio,
+                trace_always_sample_errors,
             },
-            logging: LoggingConfig { level, format },
             messages: MessageConfig {
                 ok,
                 ok_cache,
@@ -480,6 +1994,11 @@ impl Config {
                 allow_force: admin_allow_force,
                 dispersion_ms: admin_dispersion_ms,
             },
+            metrics_auth: MetricsAuthConfig {
+                token: metrics_auth_token,
+                basic: metrics_auth_basic,
+            },
+            jwt,
             replica: ReplicaConfig { replica_id },
         };
 
@@ -488,14 +2007,131 @@ impl Config {
     }
 
     fn validate(&self) -> Result<()> {
+        if self.http.listen_backlog < 1 {
+            anyhow::bail!("LISTEN_BACKLOG must be at least 1");
+        }
+        if self.http.listen.is_empty() {
+            anyhow::bail!("ADDR must name at least one listen address");
+        }
+        let listeners: Vec<(&str, &ListenAddr)> = self
+            .http
+            .listen
+            .iter()
+            .map(|l| ("ADDR", l))
+            .chain(self.http.ops_listen.iter().map(|l| ("OPS_ADDR", l)))
+            .collect();
+        for (i, &(key, listen)) in listeners.iter().enumerate() {
+            if listeners[..i]
+                .iter()
+                .any(|(_, l)| l.target == listen.target)
+            {
+                anyhow::bail!("{listen} is listed more than once in ADDR/OPS_ADDR");
+            }
+            if listen.backlog.is_some_and(|b| b < 1) {
+                anyhow::bail!("{key} entry {listen}: backlog must be at least 1");
+            }
+            match listen.target {
+                ListenTarget::Tcp(_) if listen.unix_mode.is_some() => {
+                    anyhow::bail!("{key} entry {listen}: mode only applies to unix sockets");
+                }
+                ListenTarget::Unix(_) if !cfg!(unix) => {
+                    anyhow::bail!("{key} entry {listen}: unix sockets are not supported here");
+                }
+                ListenTarget::Unix(_)
+                    if listen.tcp_nodelay.is_some() || listen.tcp_keepalive_secs.is_some() =>
+                {
+                    anyhow::bail!("{key} entry {listen}: nodelay/keepalive only apply to TCP");
+                }
+                _ => {}
+            }
+        }
+        if self.http.rate_limit_per_second < 1 {
+            anyhow::bail!("RATE_LIMIT_PER_SECOND must be at least 1");
+        }
+        if self.http.rate_limit_burst < 1 {
+            anyhow::bail!("RATE_LIMIT_BURST must be at least 1");
+        }
+        for proxy in &self.http.trusted_proxies {
+            if crate::http::rate_limit::parse_trusted_proxy(proxy).is_none() {
+                anyhow::bail!("TRUSTED_PROXIES entry {proxy:?} is not an IP address or CIDR");
+            }
+        }
+        for (key, list) in [
+            ("IP_ALLOWLIST", &self.http.ip_allowlist),
+            ("IP_DENYLIST", &self.http.ip_denylist),
+        ] {
+            for entry in list {
+                if crate::http::rate_limit::parse_trusted_proxy(entry).is_none() {
+                    anyhow::bail!("{key} entry {entry:?} is not an IP address or CIDR");
+                }
+            }
+        }
+        if self.http.tls_cert_file.is_some() != self.http.tls_key_file.is_some() {
+            anyhow::bail!("TLS_CERT_FILE and TLS_KEY_FILE must be set together");
+        }
+        if self.grpc.tls_cert_file.is_some() != self.grpc.tls_key_file.is_some() {
+            anyhow::bail!("GRPC_TLS_CERT_FILE and GRPC_TLS_KEY_FILE must be set together");
+        }
+        if self.grpc.tls_client_ca_file.is_some() && !self.grpc.tls_enabled() {
+            anyhow::bail!(
+                "GRPC_TLS_CLIENT_CA_FILE requires GRPC_TLS_CERT_FILE and GRPC_TLS_KEY_FILE"
+            );
+        }
+        if self.http.tls_enabled() && self.http.tls_reload_interval_secs == 0 {
+            anyhow::bail!("TLS_RELOAD_INTERVAL_SECS must be at least 1");
+        }
+        if self.http.http2_max_concurrent_streams == 0 {
+            anyhow::bail!("HTTP2_MAX_CONCURRENT_STREAMS must be at least 1");
+        }
+        // RFC 9113 §6.5.2 / §6.9.1: windows are 31-bit; a connection window
+        // can only grow past the 65,535-byte protocol default.
+        if !(1..=MAX_HTTP2_WINDOW).contains(&self.http.http2_initial_stream_window_size) {
+            anyhow::bail!(
+                "HTTP2_INITIAL_STREAM_WINDOW_SIZE must be between 1 and {MAX_HTTP2_WINDOW}"
+            );
+        }
+        if !(65_535..=MAX_HTTP2_WINDOW).contains(&self.http.http2_initial_connection_window_size) {
+            anyhow::bail!(
+                "HTTP2_INITIAL_CONNECTION_WINDOW_SIZE must be between 65535 and {MAX_HTTP2_WINDOW}"
+            );
+        }
         if self.ntp.servers.is_empty() {
             anyhow::bail!("At least one NTP server must be configured");
         }
+        let mut addresses = HashSet::new();
+        if let Some(dup) = self
+            .ntp
+            .servers
+            .iter()
+            .find(|s| !addresses.insert(s.address.as_str()))
+        {
+            anyhow::bail!("NTP server {} is configured more than once", dup.address);
+        }
         if self.ntp.sync_interval_secs < 1 {
             anyhow::bail!("SYNC_INTERVAL must be at least 1 second");
         }
-        if self.ntp.timeout_secs < 1 {
-            anyhow::bail!("NTP_TIMEOUT must be at least 1 second");
+        if self.ntp.sync_interval_max_secs < self.ntp.sync_interval_secs {
+            anyhow::bail!("SYNC_INTERVAL_MAX must be >= SYNC_INTERVAL");
+        }
+        if self.ntp.sync_interval_max_secs > self.ntp.sync_interval_secs
+            && self.ntp.sync_interval_max_secs >= self.ntp.max_staleness_secs
+        {
+            anyhow::bail!("SYNC_INTERVAL_MAX must be shorter than MAX_STALENESS");
+        }
+        if !self.roughtime.servers.is_empty() && self.roughtime.interval_secs < 1 {
+            anyhow::bail!("ROUGHTIME_INTERVAL_SECS must be at least 1 second");
+        }
+        if self.ntp.slew_rate_ppm >= 1_000_000 {
+            anyhow::bail!("SLEW_RATE_PPM must be below 1000000");
+        }
+        if self.ntp.slew_rate_ppm > 0
+            && self.ntp.max_step_ms > 0
+            && self.ntp.slew_max_offset_ms >= self.ntp.max_step_ms
+        {
+            anyhow::bail!("SLEW_MAX_OFFSET_MS must be less than MAX_STEP_MS");
+        }
+        if self.ntp.timeout_ms < 1 {
+            anyhow::bail!("NTP_TIMEOUT must be at least 1 millisecond");
         }
         if self.ntp.probe_min_interval_secs > self.ntp.probe_max_interval_secs {
             anyhow::bail!("PROBE_MIN_INTERVAL cannot be greater than PROBE_MAX_INTERVAL");
@@ -509,6 +2145,18 @@ impl Config {
         if self.ws.update_interval_ms == 0 {
             anyhow::bail!("WS_UPDATE_INTERVAL_MS must be at least 1 ms");
         }
+        if self.ws.min_update_interval_ms == 0 {
+            anyhow::bail!("WS_MIN_UPDATE_INTERVAL_MS must be at least 1 ms");
+        }
+        if self.ws.send_queue_capacity == 0 {
+            anyhow::bail!("WS_SEND_QUEUE_CAPACITY must be at least 1");
+        }
+        if self.ws.max_missed_pongs == 0 {
+            anyhow::bail!("WS_MAX_MISSED_PONGS must be at least 1");
+        }
+        if !(0.0..=1.0).contains(&self.logging.trace_sample_ratio) {
+            anyhow::bail!("TRACE_SAMPLE_RATIO must be in [0, 1]");
+        }
         if self.quality.serve_ok_max_uncertainty_ms <= 0.0 {
             anyhow::bail!("SERVE_OK_MAX_UNCERTAINTY_MS must be > 0");
         }
@@ -519,8 +2167,27 @@ impl Config {
                 "SERVE_OK_MAX_UNCERTAINTY_MS must be less than SERVE_DEGRADED_MAX_UNCERTAINTY_MS"
             );
         }
-        if self.admin.enabled && self.admin.token.is_empty() {
-            anyhow::bail!("ADMIN_API_TOKEN must be set when ADMIN_API_ENABLED=true");
+        if self.quality.system_clock_check_interval_secs == 0 {
+            anyhow::bail!("SYSTEM_CLOCK_CHECK_INTERVAL_SECS must be at least 1");
+        }
+        if self.quality.clock_jump_threshold_ms > 0
+            && self.quality.clock_jump_check_interval_secs == 0
+        {
+            anyhow::bail!("CLOCK_JUMP_CHECK_INTERVAL_SECS must be at least 1");
+        }
+        if self.persist.enabled && self.persist.interval_secs == 0 {
+            anyhow::bail!("TIME_STATE_PERSIST_INTERVAL_SECS must be at least 1");
+        }
+        if self.admin.enabled && self.admin.token.is_empty() && !self.jwt.is_enabled() {
+            anyhow::bail!(
+                "ADMIN_API_TOKEN (or JWT_HS256_SECRET / JWT_JWKS_URL) must be set when ADMIN_API_ENABLED=true"
+            );
+        }
+        if self.jwt.jwks_url.is_some() && self.jwt.jwks_refresh_secs == 0 {
+            anyhow::bail!("JWT_JWKS_REFRESH_SECS must be at least 1");
+        }
+        if self.jwt.is_enabled() && self.jwt.admin_scope.trim().is_empty() {
+            anyhow::bail!("JWT_ADMIN_SCOPE must not be empty");
         }
         if self.admin.enabled && self.admin.max_ttl_secs == 0 {
             anyhow::bail!("MANUAL_OVERRIDE_MAX_TTL_SECS must be > 0");
@@ -528,6 +2195,9 @@ impl Config {
         if self.admin.enabled && self.admin.dispersion_ms == 0 {
             anyhow::bail!("MANUAL_OVERRIDE_DISPERSION_MS must be > 0");
         }
+        if !self.metrics_auth.basic.is_empty() && !self.metrics_auth.basic.contains(':') {
+            anyhow::bail!("METRICS_AUTH_BASIC must be in the form user:password");
+        }
         if self.replica.replica_id.is_empty() {
             anyhow::bail!("REPLICA_ID must not be empty");
         }
@@ -544,15 +2214,114 @@ impl Config {
         if sel.max_root_distance_ms <= 0.0 {
             anyhow::bail!("MAX_ROOT_DISTANCE_MS must be > 0");
         }
+        if sel.max_root_dispersion_ms == 0 {
+            anyhow::bail!("MAX_ROOT_DISPERSION_MS must be > 0");
+        }
         if sel.max_sample_age_secs == 0 {
             anyhow::bail!("MAX_SAMPLE_AGE_SECS must be > 0");
         }
+        if self.ntp.query.stagger_window_ms >= self.ntp.sync_interval_secs * 1000 {
+            anyhow::bail!("NTP_QUERY_STAGGER_MS must be shorter than SYNC_INTERVAL");
+        }
+        if self.ntp.query.expand_pools && self.ntp.query.pool_max_addresses == 0 {
+            anyhow::bail!("NTP_POOL_MAX_ADDRESSES must be > 0");
+        }
+        let cal = &self.ntp.calibration;
+        if cal.alpha <= 0.0 || cal.alpha > 1.0 {
+            anyhow::bail!("NTP_BIAS_CALIBRATION_ALPHA must be in (0, 1]");
+        }
+        if let Some(reference) = &cal.reference_server
+            && !self.ntp.servers.iter().any(|s| &s.address == reference)
+        {
+            anyhow::bail!("NTP_BIAS_CALIBRATION_REFERENCE must be one of NTP_SERVERS");
+        }
+        if let Some(addr) = self.ntp.source.addr
+            && !self.ntp.address_family.matches(&SocketAddr::new(addr, 0))
+        {
+            anyhow::bail!("NTP_BIND_ADDR {} does not match NTP_ADDRESS_FAMILY", addr);
+        }
+        if let Some(interface) = &self.ntp.source.interface {
+            if !cfg!(target_os = "linux") {
+                anyhow::bail!("NTP_BIND_INTERFACE is only supported on Linux");
+            }
+            // IFNAMSIZ includes the trailing NUL.
+            if interface.len() >= 16 {
+                anyhow::bail!("NTP_BIND_INTERFACE must be shorter than 16 bytes");
+            }
+        }
+        for server in &self.ntp.servers {
+            let Some(key_id) = server.key_id else {
+                continue;
+            };
+            if !self.ntp.keys.contains_key(&key_id) {
+                anyhow::bail!(
+                    "NTP_SERVERS entry {} uses key {} which is not in NTP_KEYS_FILE",
+                    server.address,
+                    key_id
+                );
+            }
+            if server.address.contains("://") {
+                anyhow::bail!(
+                    "NTP_SERVERS entry {} cannot use ;key= (plain NTP servers only)",
+                    server.address
+                );
+            }
+        }
         if sel.provider_group_max_fraction <= 0.0 || sel.provider_group_max_fraction > 1.0 {
             anyhow::bail!("PROVIDER_GROUP_MAX_FRACTION must be in (0, 1]");
         }
         Ok(())
     }
 
+    /// Resolve every configured server name, so a typo in `NTP_SERVERS` is
+    /// reported at startup (or stops it, under [`DnsCheck::Fail`]) instead of
+    /// surfacing as sync failures minutes later.
+    /// IP literals and refclocks are skipped; `nts://` entries are looked up
+    /// by their NTS-KE host.  Returns the entries that did not resolve, for
+    /// a warning; under [`DnsCheck::Fail`] it is an error when none does.
+    pub async fn check_dns(&self) -> Result<Vec<String>> {
+        if self.ntp.dns_check == DnsCheck::Off {
+            return Ok(Vec::new());
+        }
+        let family = self.ntp.address_family;
+        let names: Vec<&str> = self
+            .ntp
+            .servers
+            .iter()
+            .map(|server| server.address.as_str())
+            .filter(|address| {
+                !crate::ntp::refclock::is_refclock(address)
+                    && crate::ntp::nts::dns_target(address)
+                        .parse::<SocketAddr>()
+                        .is_err()
+            })
+            .collect();
+        let lookups = names.iter().map(|name| async move {
+            let target = crate::ntp::nts::dns_target(name);
+            match tokio::time::timeout(DNS_CHECK_TIMEOUT, tokio::net::lookup_host(target)).await {
+                Ok(Ok(mut addrs)) => addrs.any(|addr| family.matches(&addr)),
+                _ => false,
+            }
+        });
+        let resolved = futures_util::future::join_all(lookups).await;
+        let unresolved: Vec<String> = names
+            .iter()
+            .zip(resolved)
+            .filter(|(_, ok)| !ok)
+            .map(|(name, _)| name.to_string())
+            .collect();
+        if self.ntp.dns_check == DnsCheck::Fail
+            && !names.is_empty()
+            && unresolved.len() == names.len()
+        {
+            anyhow::bail!(
+                "None of the NTP servers resolve ({}); check NTP_SERVERS, or start with --skip-dns-check",
+                unresolved.join(", ")
+            );
+        }
+        Ok(unresolved)
+    }
+
     pub fn sync_interval(&self) -> Duration {
         Duration::from_secs(self.ntp.sync_interval_secs)
     }
@@ -560,33 +2329,128 @@ impl Config {
     pub fn request_timeout(&self) -> Duration {
         Duration::from_secs(self.http.request_timeout_secs)
     }
+
+    /// JSON Schema of the configuration, printed by `ntp-time-json-api config
+    /// schema`.  Generated from the serde structs: each field carries its doc
+    /// comment as `description`, its default as `default`, and, where the doc
+    /// names it ("Set via `NAME`"), the variable that sets it as `x-env`.
+    pub fn json_schema() -> serde_json::Value {
+        let mut schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default();
+        let defaults = serde_json::to_value(Config::default()).unwrap_or_default();
+        let mut defs = schema
+            .get_mut("$defs")
+            .map(serde_json::Value::take)
+            .unwrap_or_default();
+        annotate_schema(&mut schema, &defaults, &mut defs);
+        // Resolved per process from REPLICA_ID / HOSTNAME, so no fixed default.
+        if let Some(replica_id) = defs
+            .pointer_mut("/ReplicaConfig/properties/replica_id")
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            replica_id.remove("default");
+        }
+        schema["$defs"] = defs;
+        schema["description"] = format!(
+            "Configuration of ntp-time-json-api. Every x-env variable may also be \
+             set with the {ENV_PREFIX} prefix, which takes precedence."
+        )
+        .into();
+        schema
+    }
+
+    /// The effective configuration as JSON for `GET /admin/config`.
+    ///
+    /// Tokens, Basic credentials and the HS256 secret are replaced with
+    /// `"<redacted>"` when set (and left empty when not, so an operator can
+    /// still tell the two apart); NTP keys are listed by id and algorithm only.
+    pub fn redacted_json(&self) -> serde_json::Value {
+        const REDACTED: &str = "<redacted>";
+        let mask = |secret: &str| {
+            if secret.is_empty() { "" } else { REDACTED }
+        };
+
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        value["admin"]["token"] = mask(&self.admin.token).into();
+        value["metrics_auth"]["token"] = mask(&self.metrics_auth.token).into();
+        value["metrics_auth"]["basic"] = mask(&self.metrics_auth.basic).into();
+        value["jwt"]["hs256_secret"] = mask(&self.jwt.hs256_secret).into();
+        value["grpc"]["api_keys"] = self.grpc.api_keys.iter().map(|key| mask(key)).collect();
+
+        let mut keys: Vec<_> = self.ntp.keys.values().collect();
+        keys.sort_by_key(|key| key.id);
+        value["ntp"]["keys"] = keys
+            .into_iter()
+            .map(|key| serde_json::json!({ "id": key.id, "algorithm": key.algorithm }))
+            .collect();
+        value
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             http: HttpConfig {
-                addr: "0.0.0.0:8080".parse().unwrap(),
+                listen: vec![ListenAddr::tcp("0.0.0.0:8080".parse().unwrap())],
+                ops_listen: None,
                 request_timeout_secs: 5,
                 body_limit_bytes: 1024,
                 tcp_nodelay: true,
-                tcp_keepalive_secs: Some(60),
+                tcp_keepalive_secs: None,
+                listen_backlog: 1024,
+                fast_path_request_id: true,
+                fast_path_metrics: false,
                 disable_rate_limiting: false,
+                rate_limit_per_second: 1000,
+                rate_limit_burst: 100,
+                trusted_proxies: Vec::new(),
+                max_inflight_requests: 0,
+                ip_allowlist: Vec::new(),
+                ip_denylist: Vec::new(),
+                ip_filter_scope: IpFilterScope::All,
+                shutdown_readiness_delay_secs: 5,
+                shutdown_drain_secs: 20,
+                epoch_as_string: false,
+                jsonp_enabled: false,
+                compression_enabled: true,
+                tls_cert_file: None,
+                tls_key_file: None,
+                tls_reload_interval_secs: 60,
+                http2_enabled: true,
+                http2_max_concurrent_streams: 200,
+                http2_initial_stream_window_size: 1024 * 1024,
+                http2_initial_connection_window_size: 1024 * 1024,
             },
             ntp: NtpConfig {
-                servers: vec!["time.google.com:123".to_string()],
-                timeout_secs: 2,
+                servers: DEFAULT_NTP_SERVERS
+                    .split(',')
+                    .map(ServerConfig::new)
+                    .collect(),
+                timeout_ms: 2000,
                 sync_interval_secs: 30,
+                sync_interval_max_secs: 30,
+                poll_stable_offset_ms: 5,
+                max_step_ms: 0,
+                max_step_stepout: 3,
                 probe_min_interval_secs: 10,
                 probe_max_interval_secs: 20,
                 max_staleness_secs: 120,
                 require_sync: true,
-                selection_strategy: SelectionStrategy::AccuracyFirst,
                 monotonic_output: true,
+                monotonic_scope: MonotonicScope::Global,
+                slew_rate_ppm: 0,
+                slew_max_offset_ms: 128,
+                drift_compensation: false,
+                leap_seconds_file: None,
                 offset_bias_ms: 0,
                 asymmetry_bias_ms: 0,
                 max_consecutive_failures: 10,
+                address_family: AddressFamily::Any,
+                dns_check: DnsCheck::Warn,
+                source: SourceBinding::default(),
                 selection: SelectionConfig::default(),
+                query: QueryConfig::default(),
+                calibration: CalibrationConfig::default(),
+                keys: HashMap::new(),
             },
             ntp_server: NtpServerConfig {
                 enabled: false,
@@ -600,18 +2464,48 @@ impl Default for Config {
                 serve_ok_max_uncertainty_ms: 50.0,
                 serve_degraded_max_uncertainty_ms: 250.0,
                 readiness_max_uncertainty_ms: 250.0,
+                system_clock_check_interval_secs: 10,
+                system_clock_alert_ms: 1000,
+                system_clock_shadow_window_secs: 86_400,
+                clock_jump_threshold_ms: 1000,
+                clock_jump_check_interval_secs: 1,
+                deep_health_min_interval_secs: 10,
             },
             persist: PersistConfig {
                 enabled: false,
                 file_path: "/var/lib/ntp-time-json-api/state.json".to_string(),
+                interval_secs: 5,
+            },
+            roughtime: RoughtimeConfig {
+                servers: Vec::new(),
+                interval_secs: 300,
+                max_disagreement_ms: 1000,
             },
             ws: WsConfig {
                 update_interval_ms: 1000,
+                min_update_interval_ms: 100,
                 max_duration_secs: 3600,
+                ping_interval_secs: 30,
+                max_missed_pongs: 2,
+                idle_timeout_secs: 120,
+                max_connections: 10_000,
+                send_queue_capacity: 8,
+                slow_consumer_policy: SlowConsumerPolicy::DropOldest,
+            },
+            grpc: GrpcConfig {
+                enabled: false,
+                addr: "0.0.0.0:50051".parse().unwrap(),
+                api_keys: Vec::new(),
+                auth_scope: GrpcAuthScope::Streams,
+                tls_cert_file: None,
+                tls_key_file: None,
+                tls_client_ca_file: None,
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
                 format: LogFormat::Json,
+                trace_sample_ratio: 1.0,
+                trace_always_sample_errors: true,
             },
             messages: MessageConfig {
                 ok: "done".to_string(),
@@ -629,6 +2523,8 @@ impl Default for Config {
                 allow_force: false,
                 dispersion_ms: 1000,
             },
+            metrics_auth: MetricsAuthConfig::default(),
+            jwt: JwtConfig::default(),
             replica: ReplicaConfig {
                 replica_id: format!("replica-{}", std::process::id()),
             },
@@ -645,12 +2541,468 @@ mod tests {
         let config = Config::default();
         assert!(!config.ntp.servers.is_empty());
         assert_eq!(
-            config.ntp.selection_strategy,
+            config.ntp.selection.strategy,
             SelectionStrategy::AccuracyFirst
         );
         assert!(config.ntp.monotonic_output);
     }
 
+    #[test]
+    fn test_server_entry_options() {
+        let s = ServerConfig::parse(" ntp.corp.internal;weight=3; priority=1 ").unwrap();
+        assert_eq!(s.address, "ntp.corp.internal:123");
+        assert_eq!(s.weight, 3.0);
+        assert_eq!(s.priority, 1);
+
+        let s = ServerConfig::parse("time.google.com:123").unwrap();
+        assert_eq!((s.weight, s.priority), (1.0, 0));
+
+        assert!(ServerConfig::parse("a:123;weight=0").is_err());
+        assert!(ServerConfig::parse("a:123;priority=-1").is_err());
+        assert!(ServerConfig::parse("a:123;colour=red").is_err());
+        assert!(ServerConfig::parse("a:123;weight").is_err());
+        assert!(ServerConfig::parse(";weight=2").is_err());
+
+        let s = ServerConfig::parse("a:123;timeout=750ms;samples=4").unwrap();
+        assert_eq!((s.timeout_ms, s.samples), (Some(750), 4));
+        assert_eq!(
+            ServerConfig::parse("a;timeout=2").unwrap().timeout_ms,
+            Some(2000)
+        );
+        assert!(ServerConfig::parse("a:123;timeout=0s").is_err());
+        assert!(ServerConfig::parse("a:123;samples=0").is_err());
+        assert!(ServerConfig::parse("a:123;samples=9").is_err());
+
+        assert_eq!(
+            ServerConfig::parse("a;tier=Fallback").unwrap().tier,
+            ServerTier::Fallback
+        );
+        assert!(ServerConfig::parse("a;tier=backup").is_err());
+    }
+
+    #[test]
+    fn test_servers_file_parse() {
+        let servers = parse_servers_file(
+            r#"[
+                {"address": "ntp.corp.internal", "weight": 3, "priority": 1,
+                 "timeout": "250ms", "samples": 4},
+                {"address": "time.google.com:123", "tier": "fallback"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(servers[0].address, "ntp.corp.internal:123");
+        assert_eq!(servers[0].weight, 3.0);
+        assert_eq!(servers[0].timeout_ms, Some(250));
+        assert_eq!(servers[0].samples, 4);
+        assert_eq!(servers[0].tier, ServerTier::Primary);
+        assert_eq!(
+            servers[1],
+            ServerConfig {
+                tier: ServerTier::Fallback,
+                ..ServerConfig::new("time.google.com:123")
+            }
+        );
+
+        assert!(parse_servers_file(r#"[{"address": "a", "colour": "red"}]"#).is_err());
+        assert!(parse_servers_file(r#"[{"address": "a", "weight": 0}]"#).is_err());
+        assert!(parse_servers_file(r#"[{"address": ""}]"#).is_err());
+
+        let mut config = Config::default();
+        config.ntp.servers = servers;
+        assert!(config.validate().is_ok());
+        config
+            .ntp
+            .servers
+            .push(ServerConfig::new("ntp.corp.internal"));
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_roughtime_server_parse() {
+        let s = RoughtimeServerConfig::parse(
+            "roughtime.example.com:2002;key=AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=",
+        )
+        .unwrap();
+        assert_eq!(s.address, "roughtime.example.com:2002");
+        assert_eq!(s.public_key, std::array::from_fn(|i| i as u8));
+        // Unpadded keys are accepted too.
+        let unpadded = RoughtimeServerConfig::parse(
+            "roughtime.example.com:2002;key=AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8",
+        )
+        .unwrap();
+        assert_eq!(unpadded.public_key, s.public_key);
+
+        assert!(RoughtimeServerConfig::parse("roughtime.example.com:2002").is_err());
+        assert!(RoughtimeServerConfig::parse("roughtime.example.com;key=AAEC").is_err());
+        assert!(RoughtimeServerConfig::parse("host:2002;key=AAEC").is_err());
+        assert!(RoughtimeServerConfig::parse("host:2002;key=not*base64").is_err());
+    }
+
+    #[test]
+    fn test_ntp_keys_file_parse() {
+        let keys = parse_ntp_keys(
+            "# id type key\n\
+             1 M secret\n\
+             2 sha1 0123456789abcdef0123456789abcdef01234567  # hex\n\
+             \n\
+             3 AES128CMAC 000102030405060708090a0b0c0d0e0f\n",
+        )
+        .unwrap();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[&1].algorithm, MacAlgorithm::Md5);
+        assert_eq!(keys[&1].secret, b"secret");
+        assert_eq!(keys[&2].algorithm, MacAlgorithm::Sha1);
+        assert_eq!(keys[&2].secret.len(), 20);
+        assert_eq!(keys[&3].secret, (0..16).collect::<Vec<u8>>());
+        assert!(format!("{:?}", keys[&1]).contains("<redacted>"));
+
+        assert!(parse_ntp_keys("0 M secret").is_err());
+        assert!(parse_ntp_keys("1 DES secret").is_err());
+        assert!(parse_ntp_keys("1 M").is_err());
+        assert!(parse_ntp_keys("1 AES128CMAC short").is_err());
+        assert!(parse_ntp_keys("1 M a\n1 M b").is_err());
+
+        let s = ServerConfig::parse("ntp.corp.internal;key=1").unwrap();
+        assert_eq!(s.key_id, Some(1));
+        assert!(ServerConfig::parse("a:123;key=x").is_err());
+    }
+
+    #[test]
+    fn test_duration_settings() {
+        let secs = Duration::from_secs(1);
+        assert_eq!(parse_duration("30", secs).unwrap(), Duration::from_secs(30));
+        assert_eq!(
+            parse_duration(" 2m ", secs).unwrap(),
+            Duration::from_secs(120)
+        );
+        assert_eq!(
+            parse_duration("1h 30m", secs).unwrap(),
+            Duration::from_secs(5400)
+        );
+        assert_eq!(
+            parse_duration("1500ms", secs).unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert!(parse_duration("30 parsecs", secs).is_err());
+
+        // A unique key, so parallel tests never see it
+        let key = "NTPAPI_TEST_DURATION_SETTING";
+        let read = |raw: &str| {
+            unsafe { std::env::set_var(key, raw) };
+            let result = (env_or_secs(key, 7), env_or_millis(key, 7, secs));
+            unsafe { std::env::remove_var(key) };
+            result
+        };
+        assert_eq!(read("45").0.unwrap(), 45);
+        assert_eq!(read("45").1.unwrap(), 45_000);
+        assert_eq!(read("1500ms").1.unwrap(), 1500);
+        assert!(read("1500ms").0.is_err());
+        assert!(read("soon").0.is_err());
+        assert_eq!(env_or_secs(key, 7).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_prefixed_env_var_wins() {
+        let key = "NTPAPI_TEST_PREFIX_SETTING";
+        let prefixed = format!("{ENV_PREFIX}{key}");
+        unsafe { std::env::set_var(key, "legacy") };
+        assert_eq!(env_or_default(key, "default"), "legacy");
+        assert!(legacy_env_vars().contains(&key.to_string()));
+
+        unsafe { std::env::set_var(&prefixed, "namespaced") };
+        assert_eq!(env_or_default(key, "default"), "namespaced");
+        unsafe {
+            std::env::remove_var(key);
+            std::env::remove_var(&prefixed);
+        }
+        assert_eq!(env_or_default(key, "default"), "default");
+    }
+
+    #[test]
+    fn test_secret_from_file() {
+        let key = "NTPAPI_TEST_SECRET_SETTING";
+        let file_key = "NTPAPI_TEST_SECRET_SETTING_FILE";
+        let path = std::env::temp_dir().join(format!("ntpapi-secret-{}", std::process::id()));
+        std::fs::write(&path, "from-file\n").unwrap();
+
+        assert_eq!(env_secret(key).unwrap(), "");
+        unsafe { std::env::set_var(file_key, &path) };
+        assert_eq!(env_secret(key).unwrap(), "from-file");
+        unsafe { std::env::set_var(key, "inline") };
+        assert!(env_secret(key).is_err());
+        unsafe { std::env::remove_var(file_key) };
+        assert_eq!(env_secret(key).unwrap(), "inline");
+        unsafe {
+            std::env::remove_var(key);
+            std::env::set_var(file_key, path.with_extension("missing"));
+        }
+        assert!(env_secret(key).is_err());
+        unsafe { std::env::remove_var(file_key) };
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_redacted_json_hides_secrets() {
+        let mut config = Config::default();
+        config.admin.token = "admin-s3cret".into();
+        config.metrics_auth.basic = "scraper:b4sic-s3cret".into();
+        config.jwt.hs256_secret = "jwt-s3cret".into();
+        config.grpc.api_keys = vec!["grpc-s3cret".into()];
+        config.ntp.keys = parse_ntp_keys("7 M key-s3cret").unwrap();
+
+        let value = config.redacted_json();
+        assert!(!value.to_string().contains("s3cret"));
+        assert_eq!(value["admin"]["token"], "<redacted>");
+        assert_eq!(value["metrics_auth"]["basic"], "<redacted>");
+        assert_eq!(value["metrics_auth"]["token"], "");
+        assert_eq!(value["jwt"]["hs256_secret"], "<redacted>");
+        assert_eq!(value["grpc"]["api_keys"][0], "<redacted>");
+        assert_eq!(value["ntp"]["keys"][0]["id"], 7);
+        assert_eq!(
+            value["http"]["listen"],
+            serde_json::json!(config.http.listen)
+        );
+    }
+
+    #[test]
+    fn test_json_schema_describes_fields() {
+        let schema = Config::json_schema();
+        let defs = &schema["$defs"];
+        assert_eq!(
+            defs["AdminConfig"]["properties"]["token"]["x-env"],
+            serde_json::json!(["ADMIN_API_TOKEN", "ADMIN_API_TOKEN_FILE"])
+        );
+        let timeout = &defs["HttpConfig"]["properties"]["request_timeout_secs"];
+        assert_eq!(timeout["default"], 5);
+        assert_eq!(timeout["x-env"], serde_json::json!(["REQUEST_TIMEOUT"]));
+        assert_eq!(
+            defs["NtpConfig"]["properties"]["servers"]["x-env"],
+            serde_json::json!(["NTP_SERVERS", "NTP_SERVERS_FILE"])
+        );
+        // Secrets and host-dependent values get no default.
+        assert!(
+            defs["JwtConfig"]["properties"]["hs256_secret"]
+                .get("default")
+                .is_none()
+        );
+        assert!(
+            defs["ReplicaConfig"]["properties"]["replica_id"]
+                .get("default")
+                .is_none()
+        );
+
+        assert_eq!(
+            documented_env_vars("Set `FAST_PATH_REQUEST_ID=false` to skip it. Default: true."),
+            vec!["FAST_PATH_REQUEST_ID"]
+        );
+        assert!(documented_env_vars("`ALLOW_DEGRADED=false` → 503").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_check_dns() {
+        let mut config = Config::default();
+        // `.invalid` never resolves (RFC 6761); literals and refclocks are skipped.
+        config.ntp.servers = ["typo.invalid", "192.0.2.1", "gpsd://"]
+            .into_iter()
+            .map(ServerConfig::new)
+            .collect();
+        config.ntp.dns_check = DnsCheck::Fail;
+        assert!(config.check_dns().await.is_err());
+        config.ntp.dns_check = DnsCheck::Warn;
+        assert_eq!(config.check_dns().await.unwrap(), vec!["typo.invalid:123"]);
+        config.ntp.dns_check = DnsCheck::Off;
+        assert!(config.check_dns().await.unwrap().is_empty());
+
+        // Nothing to look up is not a failure.
+        config.ntp.dns_check = DnsCheck::Fail;
+        config.ntp.servers = vec![ServerConfig::new("192.0.2.1")];
+        assert!(config.check_dns().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_bind_addr_family() {
+        let mut config = Config::default();
+        config.ntp.source.addr = Some("192.0.2.10".parse().unwrap());
+        assert!(config.validate().is_ok());
+        config.ntp.address_family = AddressFamily::Ipv6;
+        assert!(config.validate().is_err());
+        config.ntp.address_family = AddressFamily::Ipv4;
+        assert!(config.validate().is_ok());
+
+        config.ntp.source.interface = Some("a-very-long-ifname".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_listen_addrs() {
+        let tcp = ListenAddr::parse(" [::1]:8080?backlog=64&nodelay=false&keepalive=0 ").unwrap();
+        assert_eq!(tcp.target, ListenTarget::Tcp("[::1]:8080".parse().unwrap()));
+        assert_eq!(tcp.backlog, Some(64));
+        assert_eq!(tcp.tcp_nodelay, Some(false));
+        assert_eq!(tcp.to_string(), "[::1]:8080");
+
+        let unix = ListenAddr::parse("unix:/run/ntp/http.sock?mode=660").unwrap();
+        assert_eq!(unix.target, ListenTarget::Unix("/run/ntp/http.sock".into()));
+        assert_eq!(unix.unix_mode, Some(0o660));
+        assert_eq!(unix.to_string(), "unix:/run/ntp/http.sock");
+
+        assert!(ListenAddr::parse("localhost:8080").is_err());
+        assert!(ListenAddr::parse("unix:").is_err());
+        assert!(ListenAddr::parse("127.0.0.1:80?mode=999").is_err());
+        assert!(ListenAddr::parse("127.0.0.1:80?reuseport=1").is_err());
+
+        // Unset overrides fall back to the global settings; keepalive=0 disables.
+        let http = Config::default().http;
+        let options = http.socket_options(&tcp);
+        assert_eq!(options.backlog, 64);
+        assert!(!options.tcp_nodelay);
+        assert_eq!(options.tcp_keepalive_secs, None);
+        let options = http.socket_options(&ListenAddr::tcp("127.0.0.1:80".parse().unwrap()));
+        assert_eq!(options.backlog, http.listen_backlog);
+        assert_eq!(options.tcp_keepalive_secs, http.tcp_keepalive_secs);
+    }
+
+    #[test]
+    fn test_validate_listen_addrs() {
+        let mut config = Config::default();
+        config
+            .http
+            .listen
+            .push(ListenAddr::parse("127.0.0.1:9090").unwrap());
+        config
+            .http
+            .listen
+            .push(ListenAddr::parse("unix:/tmp/ntp.sock").unwrap());
+        assert!(config.validate().is_ok());
+
+        config
+            .http
+            .listen
+            .push(ListenAddr::parse("127.0.0.1:9090?backlog=8").unwrap());
+        assert!(config.validate().is_err());
+        config.http.listen.pop();
+        config
+            .http
+            .listen
+            .push(ListenAddr::parse("127.0.0.1:80?mode=600").unwrap());
+        assert!(config.validate().is_err());
+        config.http.listen.pop();
+        config
+            .http
+            .listen
+            .push(ListenAddr::parse("unix:/tmp/other.sock?nodelay=true").unwrap());
+        assert!(config.validate().is_err());
+
+        config.http.listen.clear();
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.http.ops_listen = Some(ListenAddr::parse("127.0.0.1:9091").unwrap());
+        assert!(config.validate().is_ok());
+        config.http.ops_listen = Some(ListenAddr::parse("0.0.0.0:8080").unwrap());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_tls() {
+        let mut config = Config::default();
+        config.http.tls_cert_file = Some("cert.pem".into());
+        assert!(config.validate().is_err());
+        config.http.tls_key_file = Some("key.pem".into());
+        assert!(config.validate().is_ok());
+        config.http.tls_reload_interval_secs = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_grpc_tls() {
+        let mut config = Config::default();
+        config.grpc.tls_client_ca_file = Some("ca.pem".into());
+        assert!(config.validate().is_err());
+        config.grpc.tls_cert_file = Some("cert.pem".into());
+        assert!(config.validate().is_err());
+        config.grpc.tls_key_file = Some("key.pem".into());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_persist_interval() {
+        let mut config = Config::default();
+        config.persist.interval_secs = 0;
+        assert!(
+            config.validate().is_ok(),
+            "ignored while persistence is off"
+        );
+        config.persist.enabled = true;
+        assert!(config.validate().is_err());
+        config.persist.interval_secs = 1;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_clock_jump_interval() {
+        let mut config = Config::default();
+        config.quality.clock_jump_check_interval_secs = 0;
+        assert!(config.validate().is_err());
+        config.quality.clock_jump_threshold_ms = 0;
+        assert!(config.validate().is_ok(), "ignored while detection is off");
+    }
+
+    #[test]
+    fn test_validate_http2_settings() {
+        let mut config = Config::default();
+        config.http.http2_max_concurrent_streams = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.http.http2_initial_stream_window_size = MAX_HTTP2_WINDOW + 1;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.http.http2_initial_connection_window_size = 65_534;
+        assert!(config.validate().is_err());
+        config.http.http2_initial_connection_window_size = MAX_HTTP2_WINDOW;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_ip_lists() {
+        let mut config = Config::default();
+        config.http.ip_allowlist = vec!["10.0.0.0/8".into(), "fd00::/8".into()];
+        config.http.ip_denylist = vec!["10.0.0.1".into()];
+        assert!(config.validate().is_ok());
+        config.http.ip_denylist.push("10.0.0.0/40".into());
+        assert!(config.validate().is_err());
+        config.http.ip_denylist.pop();
+        config.http.ip_allowlist.push("internal".into());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_rate_limit() {
+        let mut config = Config::default();
+        config.http.trusted_proxies = vec!["10.0.0.0/8".into(), "::1".into()];
+        assert!(config.validate().is_ok());
+        config.http.trusted_proxies.push("proxy.internal".into());
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.http.rate_limit_burst = 0;
+        assert!(config.validate().is_err());
+        config.http.rate_limit_burst = 1;
+        config.http.rate_limit_per_second = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_server_key_must_exist() {
+        let mut config = Config::default();
+        config.ntp.servers[0].key_id = Some(7);
+        assert!(config.validate().is_err());
+        config.ntp.keys = parse_ntp_keys("7 MD5 secret").unwrap();
+        assert!(config.validate().is_ok());
+    }
+
     #[test]
     fn test_config_validation() {
         let mut config = Config::default();
@@ -660,7 +3012,7 @@ mod tests {
         assert!(config.validate().is_err());
 
         // Restore servers
-        config.ntp.servers = vec!["time.google.com:123".to_string()];
+        config.ntp.servers = vec![ServerConfig::new("time.google.com:123")];
         assert!(config.validate().is_ok());
 
         // Invalid probe intervals
@@ -669,11 +3021,39 @@ mod tests {
         assert!(config.validate().is_err());
 
         // WS update interval of 0 should fail (would cause
-        // divide-by-zero in the per-connection max_updates calc).
+        // divide-by-zero in the tick schedule).
         config.ntp.probe_min_interval_secs = 10;
         config.ntp.probe_max_interval_secs = 20;
         config.ws.update_interval_ms = 0;
         assert!(config.validate().is_err());
+
+        // A zero-length stream send queue could never hold a tick
+        config.ws.update_interval_ms = 1000;
+        config.ws.min_update_interval_ms = 0;
+        assert!(config.validate().is_err());
+        config.ws.min_update_interval_ms = 100;
+        config.ws.send_queue_capacity = 0;
+        assert!(config.validate().is_err());
+
+        // Non-positive listen backlog should fail
+        config.ws.send_queue_capacity = 8;
+        config.ws.max_missed_pongs = 0;
+        assert!(config.validate().is_err());
+        config.ws.max_missed_pongs = 2;
+        config.http.listen_backlog = 0;
+        assert!(config.validate().is_err());
+
+        // A slew rate of 1e6 ppm or more could run served time backwards
+        config.http.listen_backlog = 1024;
+        config.ntp.slew_rate_ppm = 1_000_000;
+        assert!(config.validate().is_err());
+
+        // Slewed corrections must stay below the panic threshold
+        config.ntp.slew_rate_ppm = 500;
+        config.ntp.max_step_ms = config.ntp.slew_max_offset_ms;
+        assert!(config.validate().is_err());
+        config.ntp.max_step_ms = config.ntp.slew_max_offset_ms + 1;
+        assert!(config.validate().is_ok());
     }
 
     #[test]
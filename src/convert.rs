@@ -0,0 +1,242 @@
+//! Timestamp conversion for `/convert?value=...&from=...&to=...`.
+//!
+//! Every input is parsed to a UTC instant with nanosecond precision and then
+//! rendered in the target representation.  String outputs are rendered in the
+//! requested zone (UTC by default); epoch outputs are zone-independent.
+
+use crate::format::TimeFormat;
+use chrono::{DateTime, LocalResult, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::{Value, json};
+
+/// IMF-fixdate from RFC 9110, as used in the HTTP `Date` header.
+const HTTP_DATE_PATTERN: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// Longest `value` accepted, in bytes.
+pub const MAX_VALUE_LEN: usize = 64;
+
+/// A timestamp representation accepted by `from` and `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representation {
+    UnixS,
+    UnixMs,
+    UnixUs,
+    UnixNs,
+    /// RFC 3339 profile of ISO 8601.  Input without an offset is read in `tz`.
+    Iso8601,
+    Rfc2822,
+    /// IMF-fixdate (`Sun, 29 Dec 2024 08:00:00 GMT`), always in GMT.
+    HttpDate,
+}
+
+impl Representation {
+    pub const NAMES: &'static [&'static str] = &[
+        "unix_s",
+        "unix_ms",
+        "unix_us",
+        "unix_ns",
+        "iso8601",
+        "rfc3339",
+        "rfc2822",
+        "http_date",
+    ];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "unix_s" | "unix" => Self::UnixS,
+            "unix_ms" => Self::UnixMs,
+            "unix_us" => Self::UnixUs,
+            "unix_ns" => Self::UnixNs,
+            "iso8601" | "rfc3339" => Self::Iso8601,
+            "rfc2822" => Self::Rfc2822,
+            "http_date" => Self::HttpDate,
+            other => {
+                return Err(format!(
+                    "Unknown representation: {other} (expected one of {})",
+                    Self::NAMES.join(", ")
+                ));
+            }
+        })
+    }
+
+    /// Parse `value` as this representation; zone-less ISO 8601 input is
+    /// interpreted in `tz`.
+    pub fn read(self, value: &str, tz: Tz) -> Result<DateTime<Utc>, String> {
+        let value = value.trim();
+        let invalid = || format!("value is not a valid {} timestamp", self.name());
+        let integer = || value.parse::<i64>().map_err(|_| invalid());
+        let instant = match self {
+            Self::UnixS => DateTime::from_timestamp(integer()?, 0),
+            Self::UnixMs => DateTime::from_timestamp_millis(integer()?),
+            Self::UnixUs => DateTime::from_timestamp_micros(integer()?),
+            Self::UnixNs => Some(DateTime::from_timestamp_nanos(integer()?)),
+            Self::Iso8601 => match DateTime::parse_from_rfc3339(value) {
+                Ok(time) => Some(time.to_utc()),
+                Err(_) => {
+                    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+                        .map_err(|_| invalid())?;
+                    match tz.from_local_datetime(&naive) {
+                        LocalResult::Single(time) => Some(time.to_utc()),
+                        LocalResult::Ambiguous(..) => {
+                            return Err(format!("{value} is ambiguous in {}", tz.name()));
+                        }
+                        LocalResult::None => {
+                            return Err(format!("{value} does not exist in {}", tz.name()));
+                        }
+                    }
+                }
+            },
+            Self::Rfc2822 => Some(
+                DateTime::parse_from_rfc2822(value)
+                    .map_err(|_| invalid())?
+                    .to_utc(),
+            ),
+            Self::HttpDate => Some(
+                NaiveDateTime::parse_from_str(value, HTTP_DATE_PATTERN)
+                    .map_err(|_| invalid())?
+                    .and_utc(),
+            ),
+        };
+        instant.ok_or_else(|| format!("value is out of range for {}", self.name()))
+    }
+
+    /// Render `time` in this representation: a JSON number for epoch units,
+    /// a string (in `tz` where the format carries an offset) otherwise.
+    pub fn write(self, time: DateTime<Utc>, tz: Tz) -> Result<Value, String> {
+        Ok(match self {
+            Self::UnixS => json!(time.timestamp()),
+            Self::UnixMs => json!(time.timestamp_millis()),
+            Self::UnixUs => json!(time.timestamp_micros()),
+            Self::UnixNs => json!(
+                time.timestamp_nanos_opt()
+                    .ok_or("value is out of range for unix_ns")?
+            ),
+            Self::Iso8601 => json!(
+                time.with_timezone(&tz)
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            ),
+            Self::Rfc2822 => json!(time.with_timezone(&tz).to_rfc2822()),
+            Self::HttpDate => json!(time.format(HTTP_DATE_PATTERN).to_string()),
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UnixS => "unix_s",
+            Self::UnixMs => "unix_ms",
+            Self::UnixUs => "unix_us",
+            Self::UnixNs => "unix_ns",
+            Self::Iso8601 => "iso8601",
+            Self::Rfc2822 => "rfc2822",
+            Self::HttpDate => "http_date",
+        }
+    }
+}
+
+/// Target of a conversion: a fixed representation or a client pattern.
+#[derive(Debug, Clone)]
+pub enum Target {
+    Representation(Representation),
+    Pattern(TimeFormat),
+}
+
+impl Target {
+    pub fn write(&self, time: DateTime<Utc>, tz: Tz) -> Result<Value, String> {
+        match self {
+            Self::Representation(representation) => representation.write(time, tz),
+            Self::Pattern(format) => Ok(json!(format.render(&time.with_timezone(&tz)))),
+        }
+    }
+}
+
+/// Convert `value` from `from` to `to`, returning the rendered result.
+pub fn convert(value: &str, from: Representation, to: &Target, tz: Tz) -> Result<Value, String> {
+    if value.len() > MAX_VALUE_LEN {
+        return Err(format!("value exceeds {MAX_VALUE_LEN} bytes"));
+    }
+    to.write(from.read(value, tz)?, tz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to(representation: Representation) -> Target {
+        Target::Representation(representation)
+    }
+
+    #[test]
+    fn converts_between_epoch_units_and_strings() {
+        use Representation::*;
+        let utc = Tz::UTC;
+        let iso = convert("1735459200000", UnixMs, &to(Iso8601), utc).unwrap();
+        assert_eq!(iso, json!("2024-12-29T08:00:00Z"));
+        let ms = convert("2024-12-29T08:00:00Z", Iso8601, &to(UnixMs), utc).unwrap();
+        assert_eq!(ms, json!(1_735_459_200_000i64));
+        assert_eq!(
+            convert("1735459200", UnixS, &to(UnixNs), utc).unwrap(),
+            json!(1_735_459_200_000_000_000i64)
+        );
+        assert_eq!(
+            convert("1735459200123456", UnixUs, &to(Iso8601), utc).unwrap(),
+            json!("2024-12-29T08:00:00.123456Z")
+        );
+        assert_eq!(
+            convert("1735459200000", UnixMs, &to(HttpDate), utc).unwrap(),
+            json!("Sun, 29 Dec 2024 08:00:00 GMT")
+        );
+        assert_eq!(
+            convert("Sun, 29 Dec 2024 08:00:00 GMT", HttpDate, &to(UnixS), utc).unwrap(),
+            json!(1_735_459_200i64)
+        );
+        assert_eq!(
+            convert("Sun, 29 Dec 2024 11:30:00 +0330", Rfc2822, &to(UnixS), utc).unwrap(),
+            json!(1_735_459_200i64)
+        );
+    }
+
+    #[test]
+    fn string_outputs_and_zone_less_inputs_use_tz() {
+        use Representation::*;
+        let tehran: Tz = "Asia/Tehran".parse().unwrap();
+        assert_eq!(
+            convert("1735459200000", UnixMs, &to(Iso8601), tehran).unwrap(),
+            json!("2024-12-29T11:30:00+03:30")
+        );
+        assert_eq!(
+            convert("2024-12-29T11:30:00", Iso8601, &to(UnixMs), tehran).unwrap(),
+            json!(1_735_459_200_000i64)
+        );
+        // Epoch outputs ignore tz.
+        assert_eq!(
+            convert("2024-12-29T08:00:00Z", Iso8601, &to(UnixS), tehran).unwrap(),
+            json!(1_735_459_200i64)
+        );
+
+        let pattern = Target::Pattern(TimeFormat::new(Some("%Y-%m-%d %H:%M"), None).unwrap());
+        assert_eq!(
+            convert("1735459200000", UnixMs, &pattern, tehran).unwrap(),
+            json!("2024-12-29 11:30")
+        );
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        use Representation::*;
+        let utc = Tz::UTC;
+        assert!(Representation::parse("fortnights").is_err());
+        assert!(convert("soon", UnixMs, &to(Iso8601), utc).is_err());
+        assert!(convert("2024-13-01T00:00:00Z", Iso8601, &to(UnixMs), utc).is_err());
+        for (value, from) in [("yesterday", Rfc2822), ("Sun, 29 Dec 2024", HttpDate)] {
+            let err = convert(value, from, &to(UnixS), utc).unwrap_err();
+            assert!(err.contains("is not a valid"), "{err}");
+        }
+        assert!(convert(&"1".repeat(MAX_VALUE_LEN + 1), UnixMs, &to(UnixS), utc).is_err());
+        // Beyond i64 nanoseconds (year 2262).
+        assert!(convert("9999999999999", UnixMs, &to(UnixNs), utc).is_err());
+        // Skipped by the spring-forward gap.
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        let err = convert("2024-03-10T02:30:00", Iso8601, &to(UnixS), new_york).unwrap_err();
+        assert!(err.contains("does not exist"), "{err}");
+    }
+}
@@ -0,0 +1,211 @@
+//! Response encodings negotiated from the `Accept` header.
+//!
+//! `/time` and `/status` bodies are built as JSON values; machine consumers
+//! polling at high frequency can ask for the same value as MessagePack or
+//! CBOR, or as a protobuf message, which are smaller and cheaper to parse.
+//! Time endpoints also answer `text/plain` with just the timestamp.
+//!
+//! Protobuf has no self-describing form, so the schemas are fixed (see
+//! `proto/time.proto`): `/time` answers a `TimeResponse`, and `/status` a
+//! `google.protobuf.Struct` mirroring its JSON, both encoded with prost.
+
+use serde_json::Value;
+
+/// Body encoding chosen for a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    /// Bare timestamp for shell scripts; only time endpoints render it.
+    Text,
+    MsgPack,
+    Cbor,
+    Protobuf,
+}
+
+impl Encoding {
+    /// Binary encodings, in `TimeCache` slot order.
+    pub const BINARY: [Self; 3] = [Self::MsgPack, Self::Cbor, Self::Protobuf];
+
+    /// The first supported media type listed in `accept`; JSON when none
+    /// is (including `*/*` and a missing header).  Quality values are not
+    /// weighed: list the preferred type first.
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        accept
+            .into_iter()
+            .flat_map(|accept| accept.split(','))
+            .filter_map(|range| {
+                let media_type = range.split(';').next().unwrap_or_default().trim();
+                match media_type.to_ascii_lowercase().as_str() {
+                    "application/json" | "*/*" | "application/*" => Some(Self::Json),
+                    "text/plain" => Some(Self::Text),
+                    "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                        Some(Self::MsgPack)
+                    }
+                    "application/cbor" => Some(Self::Cbor),
+                    "application/protobuf"
+                    | "application/x-protobuf"
+                    | "application/vnd.google.protobuf" => Some(Self::Protobuf),
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap_or(Self::Json)
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Text => "text/plain; charset=utf-8",
+            Self::MsgPack => "application/msgpack",
+            Self::Cbor => "application/cbor",
+            Self::Protobuf => "application/protobuf",
+        }
+    }
+
+    /// Encode `value` generically: JSON, MessagePack or CBOR as they are,
+    /// protobuf as a `google.protobuf.Struct`.  Text has no generic form
+    /// and falls back to JSON.
+    pub fn encode(self, value: &Value) -> Vec<u8> {
+        match self {
+            Self::Json | Self::Text => serde_json::to_vec(value).expect("json serialization"),
+            Self::MsgPack => to_msgpack(value),
+            Self::Cbor => to_cbor(value),
+            Self::Protobuf => protobuf::to_struct(value),
+        }
+    }
+}
+
+/// MessagePack encoding of `value`.
+pub fn to_msgpack(value: &Value) -> Vec<u8> {
+    rmp_serde::to_vec(value).expect("msgpack serialization")
+}
+
+/// CBOR (RFC 8949) encoding of `value`.
+pub fn to_cbor(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    ciborium::into_writer(value, &mut out).expect("cbor serialization");
+    out
+}
+
+// ── Protobuf ─────────────────────────────────────────────────────────────────
+
+/// Protobuf bodies: the prost-generated messages of `proto/time.proto`, and
+/// `google.protobuf.Struct` from `prost-types`.
+pub mod protobuf {
+    use prost::Message;
+    use prost_types::value::Kind;
+    use prost_types::{ListValue, Struct};
+    use serde_json::Value;
+
+    mod proto {
+        include!(concat!(env!("OUT_DIR"), "/ntp_time_json_api.rs"));
+    }
+
+    pub use proto::TimeResponse;
+
+    /// Encoded `TimeResponse`.
+    pub fn time_response(data: i64, message: &str, status: i32) -> Vec<u8> {
+        TimeResponse {
+            data,
+            message: message.to_owned(),
+            status,
+        }
+        .encode_to_vec()
+    }
+
+    /// `google.protobuf.Struct` for a JSON object; anything else is wrapped
+    /// as `{"value": ...}` so the result is always a valid `Struct`.
+    pub fn to_struct(value: &Value) -> Vec<u8> {
+        match value {
+            Value::Object(_) => struct_of(value).encode_to_vec(),
+            other => to_struct(&serde_json::json!({ "value": other })),
+        }
+    }
+
+    fn struct_of(value: &Value) -> Struct {
+        let fields = match value {
+            Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), value_of(v))).collect(),
+            _ => Default::default(),
+        };
+        Struct { fields }
+    }
+
+    /// `google.protobuf.Value`.
+    fn value_of(value: &Value) -> prost_types::Value {
+        let kind = match value {
+            Value::Null => Kind::NullValue(0),
+            Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+            Value::String(s) => Kind::StringValue(s.clone()),
+            Value::Bool(b) => Kind::BoolValue(*b),
+            Value::Object(_) => Kind::StructValue(struct_of(value)),
+            Value::Array(items) => Kind::ListValue(ListValue {
+                values: items.iter().map(value_of).collect(),
+            }),
+        };
+        prost_types::Value { kind: Some(kind) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn accept_picks_first_supported_type() {
+        assert_eq!(Encoding::from_accept(None), Encoding::Json);
+        assert_eq!(Encoding::from_accept(Some("*/*")), Encoding::Json);
+        assert_eq!(
+            Encoding::from_accept(Some("text/html, application/msgpack;q=0.9")),
+            Encoding::MsgPack
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("Application/CBOR")),
+            Encoding::Cbor
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("application/x-protobuf, application/json")),
+            Encoding::Protobuf
+        );
+        assert_eq!(
+            Encoding::from_accept(Some("application/json, application/cbor")),
+            Encoding::Json
+        );
+        assert_eq!(Encoding::from_accept(Some("text/html")), Encoding::Json);
+        assert_eq!(
+            Encoding::from_accept(Some("text/plain; charset=utf-8")),
+            Encoding::Text
+        );
+    }
+
+    #[test]
+    fn protobuf_time_response_wire_format() {
+        assert_eq!(
+            protobuf::time_response(150, "ok", 200),
+            [0x08, 0x96, 0x01, 0x12, 0x02, b'o', b'k', 0x18, 0xc8, 0x01]
+        );
+        // Negative int64 is ten bytes of two's complement; proto3 omits defaults.
+        assert_eq!(protobuf::time_response(-1, "", 0).len(), 1 + 10);
+    }
+
+    #[test]
+    fn protobuf_struct_wire_format() {
+        // fields { key: "a", value { bool_value: true } }
+        assert_eq!(
+            protobuf::to_struct(&json!({"a": true})),
+            [0x0a, 0x07, 0x0a, 0x01, b'a', 0x12, 0x02, 0x20, 0x01]
+        );
+        // fields { key: "n", value { null_value: NULL_VALUE } }
+        assert_eq!(
+            protobuf::to_struct(&json!({"n": null})),
+            [0x0a, 0x07, 0x0a, 0x01, b'n', 0x12, 0x02, 0x08, 0x00]
+        );
+        // Lists nest as ListValue { values = 1 }.
+        assert_eq!(
+            protobuf::to_struct(&json!({"l": ["x"]})),
+            [
+                0x0a, 0x0c, 0x0a, 0x01, b'l', 0x12, 0x07, 0x32, 0x05, 0x0a, 0x03, 0x1a, 0x01, b'x'
+            ]
+        );
+    }
+}
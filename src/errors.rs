@@ -1,6 +1,6 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde_json::json;
@@ -28,6 +28,31 @@ pub enum AppError {
         serve_state: String,
     },
 
+    /// Malformed or unsupported query parameter; `error` names the problem.
+    #[error("Bad request: {error}")]
+    BadRequest { error: String },
+
+    /// The `Accept`ed encoding cannot represent the requested body.
+    #[error("Not acceptable: {error}")]
+    NotAcceptable { error: String },
+
+    /// The client exceeded its rate limit; retry after `retry_after_secs`.
+    #[error("Too many requests; retry in {retry_after_secs}s")]
+    TooManyRequests { retry_after_secs: u64 },
+
+    /// The client IP is excluded by `IP_ALLOWLIST` / `IP_DENYLIST`.
+    #[error("Forbidden")]
+    Forbidden,
+
+    /// Too many requests are in flight (`MAX_INFLIGHT_REQUESTS`); the
+    /// request was shed without being processed.
+    #[error("Server overloaded")]
+    Overloaded,
+
+    /// Requested IANA timezone is not in the compiled tz database.
+    #[error("Unknown timezone: {zone}")]
+    UnknownTimezone { zone: String },
+
     /// Unexpected internal error. Wraps `anyhow::Error` so handlers
     /// can use `?` on any error type implementing
     /// `std::error::Error + Send + Sync + 'static`.
@@ -61,6 +86,70 @@ impl IntoResponse for AppError {
                 }));
                 (StatusCode::SERVICE_UNAVAILABLE, body).into_response()
             }
+            AppError::BadRequest { error } => {
+                let body = Json(json!({
+                    "message": "error",
+                    "status": 400,
+                    "data": 0,
+                    "error": error,
+                }));
+                (StatusCode::BAD_REQUEST, body).into_response()
+            }
+            AppError::NotAcceptable { error } => {
+                let body = Json(json!({
+                    "message": "error",
+                    "status": 406,
+                    "data": 0,
+                    "error": error,
+                }));
+                (StatusCode::NOT_ACCEPTABLE, body).into_response()
+            }
+            AppError::TooManyRequests { retry_after_secs } => {
+                let body = Json(json!({
+                    "message": "error",
+                    "status": 429,
+                    "data": 0,
+                    "error": format!("Too many requests; retry in {retry_after_secs}s"),
+                }));
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                    body,
+                )
+                    .into_response()
+            }
+            AppError::Forbidden => {
+                let body = Json(json!({
+                    "message": "error",
+                    "status": 403,
+                    "data": 0,
+                    "error": "Forbidden",
+                }));
+                (StatusCode::FORBIDDEN, body).into_response()
+            }
+            AppError::Overloaded => {
+                let body = Json(json!({
+                    "message": "error",
+                    "status": 503,
+                    "data": 0,
+                    "error": "Server overloaded; retry shortly",
+                }));
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [(header::RETRY_AFTER, "1")],
+                    body,
+                )
+                    .into_response()
+            }
+            AppError::UnknownTimezone { zone } => {
+                let body = Json(json!({
+                    "message": "error",
+                    "status": 404,
+                    "data": 0,
+                    "error": format!("Unknown timezone: {zone}"),
+                }));
+                (StatusCode::NOT_FOUND, body).into_response()
+            }
             AppError::Internal(_) => {
                 let body = Json(json!({
                     "message": "error",
@@ -73,3 +162,23 @@ impl IntoResponse for AppError {
         }
     }
 }
+
+/// gRPC status for the same error, so the gRPC API follows the HTTP serve
+/// policy: unavailable while unsynced or stopped, invalid argument for bad
+/// input.
+impl From<AppError> for tonic::Status {
+    fn from(error: AppError) -> Self {
+        let code = match &error {
+            AppError::NotSynced { .. } | AppError::ServeStopped { .. } | AppError::Overloaded => {
+                tonic::Code::Unavailable
+            }
+            AppError::BadRequest { .. }
+            | AppError::NotAcceptable { .. }
+            | AppError::UnknownTimezone { .. } => tonic::Code::InvalidArgument,
+            AppError::TooManyRequests { .. } => tonic::Code::ResourceExhausted,
+            AppError::Forbidden => tonic::Code::PermissionDenied,
+            AppError::Internal(_) => tonic::Code::Internal,
+        };
+        tonic::Status::new(code, error.to_string())
+    }
+}
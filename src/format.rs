@@ -0,0 +1,193 @@
+//! Client-chosen string formatting for `/time?pattern=...`.
+//!
+//! Patterns use chrono's strftime syntax but only a whitelisted subset of
+//! specifiers is accepted, and the pattern length is capped, so a request can
+//! neither trigger chrono's formatting errors (which panic in `to_string`)
+//! nor make the server render arbitrarily large strings.
+//!
+//! `?locale=` switches month and weekday names to the locale's (from the
+//! glibc locale data bundled with chrono) and, for locales that customarily
+//! use them, replaces ASCII digits with native ones.
+
+use chrono::{DateTime, Locale, TimeZone};
+use std::fmt::Display;
+
+/// Longest pattern accepted, in bytes.
+pub const MAX_PATTERN_LEN: usize = 64;
+
+/// Specifiers accepted after `%` (without the `%`).
+const ALLOWED_SPECIFIERS: &[&str] = &[
+    "Y", "y", "m", "b", "B", "d", "e", "a", "A", "j", "H", "I", "M", "S", "p", "F", "T", "s", "z",
+    ":z", "Z", ".3f", ".6f", ".9f", "%",
+];
+
+/// Pattern used when `?locale=` is given without `?pattern=`.
+pub const DEFAULT_LOCALIZED_PATTERN: &str = "%A %e %B %Y %H:%M:%S";
+
+/// Digit set substituted for ASCII `0`-`9` in localized output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Digits {
+    Ascii,
+    /// U+0660..U+0669, used by Arabic outside the Maghreb.
+    ArabicIndic,
+    /// U+06F0..U+06F9, used by Persian and Pashto.
+    ExtendedArabicIndic,
+}
+
+impl Digits {
+    fn for_language(language: &str, region: &str) -> Self {
+        match language {
+            "fa" | "ps" => Digits::ExtendedArabicIndic,
+            "ar" if !matches!(region, "MA" | "DZ" | "TN" | "LY") => Digits::ArabicIndic,
+            _ => Digits::Ascii,
+        }
+    }
+
+    fn apply(self, text: String) -> String {
+        let zero = match self {
+            Digits::Ascii => return text,
+            Digits::ArabicIndic => 0x0660,
+            Digits::ExtendedArabicIndic => 0x06F0,
+        };
+        text.chars()
+            .map(|c| match c {
+                '0'..='9' => char::from_u32(zero + (c as u32 - '0' as u32)).unwrap_or(c),
+                _ => c,
+            })
+            .collect()
+    }
+}
+
+/// A validated pattern plus optional locale, ready to render instants.
+#[derive(Debug, Clone)]
+pub struct TimeFormat {
+    pattern: String,
+    locale: Option<(Locale, Digits)>,
+}
+
+impl TimeFormat {
+    /// Build from the raw `?pattern=` and `?locale=` values.  At least one
+    /// must be present; a locale without a pattern uses
+    /// [`DEFAULT_LOCALIZED_PATTERN`].
+    pub fn new(pattern: Option<&str>, locale: Option<&str>) -> Result<Self, String> {
+        if let Some(pattern) = pattern {
+            validate_pattern(pattern)?;
+        }
+        let locale = locale.map(parse_locale).transpose()?;
+        let pattern = match (pattern, locale) {
+            (Some(pattern), _) => pattern.to_string(),
+            (None, Some(_)) => DEFAULT_LOCALIZED_PATTERN.to_string(),
+            (None, None) => return Err("pattern or locale is required".to_string()),
+        };
+        Ok(Self { pattern, locale })
+    }
+
+    /// Render `time` in this format.
+    pub fn render<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        match self.locale {
+            None => format_with_pattern(time, &self.pattern),
+            Some((locale, digits)) => {
+                digits.apply(time.format_localized(&self.pattern, locale).to_string())
+            }
+        }
+    }
+}
+
+/// Parse a BCP 47-style tag (`fa-IR`) or POSIX name (`fa_IR`).
+fn parse_locale(raw: &str) -> Result<(Locale, Digits), String> {
+    let name = raw.trim().replace('-', "_");
+    let locale =
+        Locale::try_from(name.as_str()).map_err(|_| format!("Unsupported locale: {raw}"))?;
+    let (language, region) = name.split_once('_').unwrap_or((name.as_str(), ""));
+    Ok((locale, Digits::for_language(language, region)))
+}
+
+/// Validate a `?pattern=` value against the length limit and specifier
+/// whitelist.  Errors name the first offending specifier.
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    if pattern.is_empty() {
+        return Err("pattern must not be empty".to_string());
+    }
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(format!("pattern exceeds {MAX_PATTERN_LEN} bytes"));
+    }
+
+    let mut rest = pattern;
+    while let Some(pos) = rest.find('%') {
+        let after = &rest[pos + 1..];
+        let Some(spec) = ALLOWED_SPECIFIERS
+            .iter()
+            .filter(|spec| after.starts_with(**spec))
+            .max_by_key(|spec| spec.len())
+        else {
+            let shown: String = after.chars().take(3).collect();
+            return Err(format!("Unsupported pattern specifier: %{shown}"));
+        };
+        rest = &after[spec.len()..];
+    }
+    Ok(())
+}
+
+/// Render `time` with a pattern that passed [`validate_pattern`].
+pub fn format_with_pattern<Tz: TimeZone>(time: &DateTime<Tz>, pattern: &str) -> String
+where
+    Tz::Offset: Display,
+{
+    time.format(pattern).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn formats_whitelisted_pattern() {
+        let time = DateTime::<Utc>::from_timestamp_millis(1_705_320_000_123).unwrap();
+        let pattern = "%Y-%m-%d %H:%M:%S%.3f";
+        assert!(validate_pattern(pattern).is_ok());
+        assert_eq!(
+            format_with_pattern(&time, pattern),
+            "2024-01-15 12:00:00.123"
+        );
+        assert!(validate_pattern("%F %T%:z 100%%").is_ok());
+    }
+
+    #[test]
+    fn rejects_unlisted_specifiers_and_long_patterns() {
+        assert_eq!(
+            validate_pattern("%Y %c").unwrap_err(),
+            "Unsupported pattern specifier: %c"
+        );
+        assert!(validate_pattern("%-d").is_err());
+        assert!(validate_pattern("trailing %").is_err());
+        assert!(validate_pattern("").is_err());
+        assert!(validate_pattern(&"%Y".repeat(MAX_PATTERN_LEN)).is_err());
+    }
+
+    #[test]
+    fn localizes_names_and_digits() {
+        let time = DateTime::<Utc>::from_timestamp_millis(1_705_320_000_000).unwrap();
+
+        let german = TimeFormat::new(None, Some("de-DE")).unwrap();
+        assert_eq!(german.render(&time), "Montag 15 Januar 2024 12:00:00");
+
+        let persian = TimeFormat::new(Some("%d %B %Y"), Some("fa_IR")).unwrap();
+        let rendered = persian.render(&time);
+        assert!(rendered.starts_with("۱۵ "), "{rendered}");
+        assert!(rendered.ends_with(" ۲۰۲۴"), "{rendered}");
+        assert!(!rendered.contains("January"));
+
+        let plain = TimeFormat::new(Some("%Y"), None).unwrap();
+        assert_eq!(plain.render(&time), "2024");
+
+        assert_eq!(
+            TimeFormat::new(None, Some("xx-YY")).unwrap_err(),
+            "Unsupported locale: xx-YY"
+        );
+        assert!(TimeFormat::new(Some("%c"), Some("de-DE")).is_err());
+    }
+}
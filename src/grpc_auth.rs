@@ -0,0 +1,226 @@
+//! Per-RPC authentication for the gRPC server — the gRPC counterpart of
+//! `require_stream_auth` and `require_admin_auth`.
+//!
+//! [`GrpcAuthLayer`] is enforced once any gRPC credential is configured:
+//! `GRPC_API_KEYS`, `GRPC_TLS_CLIENT_CA_FILE`, or JWT auth with
+//! `JWT_PROTECT_STREAM` (so the gRPC streams are never open while `/stream`
+//! is protected).  A call is accepted with any of
+//!
+//! - a client certificate verified against `GRPC_TLS_CLIENT_CA_FILE`,
+//! - an API key in `x-api-key` or `authorization: Bearer` metadata,
+//! - a valid JWT in `authorization: Bearer` (no scope required).
+//!
+//! `GRPC_AUTH_SCOPE` decides whether only the streaming RPCs or every RPC
+//! needs one.  Missing and wrong credentials get the same UNAUTHENTICATED
+//! status.
+//!
+//! SECURITY: credentials are never logged and API keys are compared in
+//! constant time.
+
+use crate::config::GrpcAuthScope;
+use crate::grpc_metrics::rpc_method_label;
+use crate::http::middleware::{constant_time_eq, validate_jwt};
+use crate::http::state::AppState;
+use axum::http::{HeaderMap, Request, Response, header::AUTHORIZATION};
+use futures_util::future::{Either, Ready, ready};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::Status;
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tower::{Layer, Service};
+
+/// RPCs covered by [`GrpcAuthScope::Streams`].
+const STREAMING_METHODS: &[&str] = &["StreamTime", "StreamTimeControlled"];
+
+/// Metadata key carrying an API key.
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Tower layer rejecting unauthenticated calls with UNAUTHENTICATED.
+#[derive(Clone)]
+pub struct GrpcAuthLayer {
+    state: Arc<AppState>,
+    enforced: bool,
+}
+
+impl GrpcAuthLayer {
+    pub fn new(state: Arc<AppState>) -> Self {
+        let config = &state.config;
+        let enforced = !config.grpc.api_keys.is_empty()
+            || config.grpc.tls_client_ca_file.is_some()
+            || (state.jwt.is_some() && config.jwt.protect_stream);
+        Self { state, enforced }
+    }
+
+    /// Whether a call to `path` must carry credentials.
+    fn requires_auth(&self, path: &str) -> bool {
+        self.enforced
+            && match self.state.config.grpc.auth_scope {
+                GrpcAuthScope::All => true,
+                GrpcAuthScope::Streams => STREAMING_METHODS.contains(&rpc_method_label(path)),
+            }
+    }
+
+    fn authenticated<B>(&self, request: &Request<B>) -> bool {
+        // rustls only completes a handshake with a chain it verified against
+        // the client CA, so any peer certificate is a trusted identity.
+        let has_client_cert = request
+            .extensions()
+            .get::<TlsConnectInfo<TcpConnectInfo>>()
+            .and_then(|info| info.peer_certs())
+            .is_some_and(|certs| !certs.is_empty());
+        has_client_cert || self.valid_credentials(request.headers())
+    }
+
+    fn valid_credentials(&self, headers: &HeaderMap) -> bool {
+        let api_key_ok = |provided: &str| {
+            // Check every key so timing does not reveal which one matched.
+            self.state
+                .config
+                .grpc
+                .api_keys
+                .iter()
+                .fold(false, |ok, key| constant_time_eq(key, provided) | ok)
+        };
+        if let Some(key) = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok())
+            && api_key_ok(key)
+        {
+            return true;
+        }
+        headers
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.strip_prefix("Bearer "))
+            .is_some_and(|token| api_key_ok(token) || validate_jwt(&self.state, token).is_some())
+    }
+}
+
+impl<S> Layer<S> for GrpcAuthLayer {
+    type Service = GrpcAuth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcAuth {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`GrpcAuthLayer`].
+#[derive(Clone)]
+pub struct GrpcAuth<S> {
+    inner: S,
+    layer: GrpcAuthLayer,
+}
+
+impl<S, B, ResBody> Service<Request<B>> for GrpcAuth<S>
+where
+    S: Service<Request<B>, Response = Response<ResBody>>,
+    ResBody: Default,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = Either<Ready<Result<Self::Response, Self::Error>>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        if self.layer.requires_auth(request.uri().path()) && !self.layer.authenticated(&request) {
+            tracing::debug!(
+                rpc = request.uri().path(),
+                "gRPC call rejected: unauthenticated"
+            );
+            let status = Status::unauthenticated("missing or invalid credentials");
+            return Either::Left(ready(Ok(status.into_http())));
+        }
+        Either::Right(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::http::jwt::tests::hs256_token;
+    use crate::metrics::Metrics;
+    use crate::performance::{LockFreeMetrics, TimeCache};
+    use crate::timebase::TimeBase;
+
+    const GET_TIME: &str = "/ntp_time_json_api.v1.TimeService/GetTime";
+    const STREAM_TIME: &str = "/ntp_time_json_api.v1.TimeService/StreamTime";
+
+    fn make_layer(configure: impl FnOnce(&mut Config)) -> GrpcAuthLayer {
+        let mut config = Config::default();
+        configure(&mut config);
+        let time_cache = Arc::new(TimeCache::new("done".into(), "done".into()));
+        GrpcAuthLayer::new(Arc::new(AppState::new(
+            Arc::new(config),
+            TimeBase::new(true).with_cache(time_cache.clone()),
+            Arc::new(Metrics::new()),
+            time_cache,
+            Arc::new(LockFreeMetrics::new()),
+        )))
+    }
+
+    fn request(header: Option<(&str, &str)>) -> Request<()> {
+        let mut builder = Request::builder().uri(STREAM_TIME);
+        if let Some((name, value)) = header {
+            builder = builder.header(name, value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn open_until_credentials_are_configured() {
+        let layer = make_layer(|_| {});
+        assert!(!layer.requires_auth(STREAM_TIME));
+
+        // JWT auth protects the streams only with JWT_PROTECT_STREAM.
+        let layer = make_layer(|config| {
+            config.jwt.hs256_secret = "s3cret".into();
+            config.jwt.protect_stream = false;
+        });
+        assert!(!layer.requires_auth(STREAM_TIME));
+    }
+
+    #[test]
+    fn scope_selects_protected_rpcs() {
+        let layer = make_layer(|config| config.grpc.api_keys = vec!["k1".into()]);
+        assert!(layer.requires_auth(STREAM_TIME));
+        assert!(!layer.requires_auth(GET_TIME));
+
+        let layer = make_layer(|config| {
+            config.grpc.api_keys = vec!["k1".into()];
+            config.grpc.auth_scope = GrpcAuthScope::All;
+        });
+        assert!(layer.requires_auth(GET_TIME));
+        assert!(layer.requires_auth("/grpc.health.v1.Health/Check"));
+    }
+
+    #[test]
+    fn accepts_api_keys_and_jwts() {
+        let layer = make_layer(|config| {
+            config.grpc.api_keys = vec!["k1".into(), "k2".into()];
+            config.jwt.hs256_secret = "s3cret".into();
+        });
+        let exp = chrono::Utc::now().timestamp() + 300;
+        let jwt = format!(
+            "Bearer {}",
+            hs256_token("s3cret", &serde_json::json!({"exp": exp}))
+        );
+        let forged = format!(
+            "Bearer {}",
+            hs256_token("wrong", &serde_json::json!({"exp": exp}))
+        );
+
+        assert!(layer.authenticated(&request(Some(("x-api-key", "k2")))));
+        assert!(layer.authenticated(&request(Some(("authorization", "Bearer k1")))));
+        assert!(layer.authenticated(&request(Some(("authorization", &jwt)))));
+
+        assert!(!layer.authenticated(&request(None)));
+        assert!(!layer.authenticated(&request(Some(("x-api-key", "k3")))));
+        assert!(!layer.authenticated(&request(Some(("authorization", "k1")))));
+        assert!(!layer.authenticated(&request(Some(("authorization", &forged)))));
+    }
+}
@@ -0,0 +1,311 @@
+//! Metrics and tracing for the gRPC server — the gRPC counterpart of
+//! `track_metrics` and the slow-path `TraceLayer`.
+//!
+//! [`GrpcMetricsLayer`] wraps the tonic server.  A call is counted as active
+//! from the request until its response body ends (or is dropped by a
+//! cancelling client), so streaming RPCs stay in `grpc_active_streams` for
+//! their whole lifetime.  The status code comes from the `grpc-status`
+//! header of trailers-only responses or from the trailers; a call dropped
+//! before either is recorded as `Cancelled`.
+
+use crate::grpc_service::RPC_METHODS;
+use crate::http::middleware::OTHER_PATH_LABEL;
+use crate::http::trace::should_sample;
+use crate::metrics::{GrpcMethodLabel, SharedMetrics};
+use axum::http::{HeaderMap, Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::Code;
+use tower::{Layer, Service};
+use tracing::{Instrument, Span};
+
+/// Prefix of every `TimeService` method path.
+const SERVICE_PREFIX: &str = "/ntp_time_json_api.v1.TimeService/";
+
+/// Tower layer recording per-RPC metrics and a head-sampled span.
+#[derive(Clone)]
+pub struct GrpcMetricsLayer {
+    metrics: SharedMetrics,
+    trace_sample_ratio: f64,
+    trace_always_sample_errors: bool,
+}
+
+impl GrpcMetricsLayer {
+    /// `trace_sample_ratio` and `trace_always_sample_errors` follow
+    /// `TRACE_SAMPLE_RATIO` and `TRACE_ALWAYS_SAMPLE_ERRORS`.
+    pub fn new(
+        metrics: SharedMetrics,
+        trace_sample_ratio: f64,
+        trace_always_sample_errors: bool,
+    ) -> Self {
+        Self {
+            metrics,
+            trace_sample_ratio,
+            trace_always_sample_errors,
+        }
+    }
+}
+
+impl<S> Layer<S> for GrpcMetricsLayer {
+    type Service = GrpcMetrics<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcMetrics {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service produced by [`GrpcMetricsLayer`].
+#[derive(Clone)]
+pub struct GrpcMetrics<S> {
+    inner: S,
+    layer: GrpcMetricsLayer,
+}
+
+impl<S, B, ResBody> Service<Request<B>> for GrpcMetrics<S>
+where
+    S: Service<Request<B>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = Response<MetricsBody<ResBody>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let method = rpc_method_label(request.uri().path());
+        let span = if should_sample(self.layer.trace_sample_ratio) {
+            tracing::info_span!("grpc", rpc = %method)
+        } else {
+            Span::none()
+        };
+        let mut call = RpcCall::start(&self.layer, method, span.clone());
+
+        // The ready service handles this call; a fresh clone takes its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(
+            async move {
+                let response = inner.call(request).await?;
+                call.code = grpc_status(response.headers());
+                Ok(response.map(|body| MetricsBody {
+                    inner: body,
+                    call: Some(call),
+                }))
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// Response body that finishes its [`RpcCall`] when the body ends or drops.
+pub struct MetricsBody<B> {
+    inner: B,
+    call: Option<RpcCall>,
+}
+
+impl<B> Body for MetricsBody<B>
+where
+    B: Body + Unpin,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        let span = this.call.as_ref().map(|call| call.span.clone());
+        let _entered = span.as_ref().map(Span::enter);
+        let frame = std::task::ready!(Pin::new(&mut this.inner).poll_frame(cx));
+        match &frame {
+            Some(Ok(frame)) => {
+                if let (Some(trailers), Some(call)) = (frame.trailers_ref(), this.call.as_mut()) {
+                    call.code = grpc_status(trailers).or(call.code);
+                }
+            }
+            // A body error aborts the HTTP/2 stream; the client sees no status.
+            Some(Err(_)) => {
+                if let Some(call) = this.call.as_mut() {
+                    call.code = call.code.or(Some(Code::Unknown));
+                }
+            }
+            None => {}
+        }
+        drop(_entered);
+        if frame.is_none() || this.inner.is_end_stream() {
+            this.call.take();
+        }
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// One in-flight RPC; dropping it records the call.
+struct RpcCall {
+    metrics: SharedMetrics,
+    method: &'static str,
+    start: Instant,
+    span: Span,
+    always_sample_errors: bool,
+    /// Status seen so far; `None` when the client went away first.
+    code: Option<Code>,
+}
+
+impl RpcCall {
+    fn start(layer: &GrpcMetricsLayer, method: &'static str, span: Span) -> Self {
+        layer
+            .metrics
+            .grpc_active_streams
+            .get_or_create(&GrpcMethodLabel {
+                method: method.to_string(),
+            })
+            .inc();
+        Self {
+            metrics: layer.metrics.clone(),
+            method,
+            start: Instant::now(),
+            span,
+            always_sample_errors: layer.trace_always_sample_errors,
+            code: None,
+        }
+    }
+}
+
+impl Drop for RpcCall {
+    fn drop(&mut self) {
+        let code = self.code.unwrap_or(Code::Cancelled);
+        let latency = self.start.elapsed();
+        self.metrics
+            .grpc_active_streams
+            .get_or_create(&GrpcMethodLabel {
+                method: self.method.to_string(),
+            })
+            .dec();
+        self.metrics
+            .record_grpc_call(self.method, code_label(code), latency);
+
+        let latency_ms = latency.as_millis() as u64;
+        if is_server_failure(code) && (!self.span.is_none() || self.always_sample_errors) {
+            tracing::error!(parent: &self.span, rpc = self.method, code = code_label(code), latency_ms, "grpc call failed");
+        } else if !self.span.is_none() {
+            tracing::info!(parent: &self.span, code = code_label(code), latency_ms, "finished grpc call");
+        }
+    }
+}
+
+/// Metric label for a request path: the `TimeService` method name, or
+/// [`OTHER_PATH_LABEL`] for anything else, keeping label cardinality bounded.
+pub fn rpc_method_label(path: &str) -> &'static str {
+    path.strip_prefix(SERVICE_PREFIX)
+        .and_then(|name| RPC_METHODS.iter().find(|method| **method == name))
+        .copied()
+        .unwrap_or(OTHER_PATH_LABEL)
+}
+
+/// Parse a `grpc-status` header.
+fn grpc_status(headers: &HeaderMap) -> Option<Code> {
+    let value = headers.get("grpc-status")?.to_str().ok()?;
+    value.parse::<i32>().ok().map(Code::from_i32)
+}
+
+/// Status code label in the canonical gRPC spelling (`OK`, `Unavailable`, ...).
+pub fn code_label(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "Cancelled",
+        Code::Unknown => "Unknown",
+        Code::InvalidArgument => "InvalidArgument",
+        Code::DeadlineExceeded => "DeadlineExceeded",
+        Code::NotFound => "NotFound",
+        Code::AlreadyExists => "AlreadyExists",
+        Code::PermissionDenied => "PermissionDenied",
+        Code::ResourceExhausted => "ResourceExhausted",
+        Code::FailedPrecondition => "FailedPrecondition",
+        Code::Aborted => "Aborted",
+        Code::OutOfRange => "OutOfRange",
+        Code::Unimplemented => "Unimplemented",
+        Code::Internal => "Internal",
+        Code::Unavailable => "Unavailable",
+        Code::DataLoss => "DataLoss",
+        Code::Unauthenticated => "Unauthenticated",
+    }
+}
+
+/// Codes that indicate a server fault rather than a client error or the
+/// serve policy (UNAVAILABLE while unsynced is expected, not a failure).
+fn is_server_failure(code: Code) -> bool {
+    matches!(code, Code::Unknown | Code::Internal | Code::DataLoss)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use std::sync::Arc;
+
+    #[test]
+    fn rpc_method_label_bounds_cardinality() {
+        assert_eq!(
+            rpc_method_label("/ntp_time_json_api.v1.TimeService/GetTime"),
+            "GetTime"
+        );
+        assert_eq!(
+            rpc_method_label("/ntp_time_json_api.v1.TimeService/StreamTime"),
+            "StreamTime"
+        );
+        assert_eq!(
+            rpc_method_label("/ntp_time_json_api.v1.TimeService/Nope"),
+            OTHER_PATH_LABEL
+        );
+        assert_eq!(
+            rpc_method_label("/grpc.health.v1.Health/Check"),
+            OTHER_PATH_LABEL
+        );
+    }
+
+    #[test]
+    fn grpc_status_parses_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(grpc_status(&headers), None);
+        headers.insert("grpc-status", "14".parse().unwrap());
+        assert_eq!(grpc_status(&headers), Some(Code::Unavailable));
+    }
+
+    #[test]
+    fn dropped_call_records_status_and_releases_gauge() {
+        let metrics: SharedMetrics = Arc::new(Metrics::new());
+        let layer = GrpcMetricsLayer::new(metrics.clone(), 0.0, false);
+
+        let mut call = RpcCall::start(&layer, "GetTime", Span::none());
+        let encoded = metrics.encode();
+        assert!(encoded.contains(r#"grpc_active_streams{method="GetTime"} 1"#));
+        call.code = Some(Code::Ok);
+        drop(call);
+
+        // A call without a status (client went away) counts as Cancelled.
+        drop(RpcCall::start(&layer, "StreamTime", Span::none()));
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains(r#"grpc_active_streams{method="GetTime"} 0"#));
+        assert!(encoded.contains(r#"grpc_requests_total{method="GetTime",code="OK"} 1"#));
+        assert!(encoded.contains(r#"grpc_requests_total{method="StreamTime",code="Cancelled"} 1"#));
+    }
+}
@@ -0,0 +1,497 @@
+//! gRPC API (`proto/time_service.proto`), served on `GRPC_ADDR` when
+//! `GRPC_ENABLED=true`.
+//!
+//! `GetTime` answers what `/time` would serve, under the same serve policy,
+//! and `GetTimeInterval` what `/time/interval` would.  The streams subscribe
+//! to the same [`StreamHub`](crate::streaming::StreamHub) tickers as
+//! `/stream`, so the HTTP and gRPC APIs never disagree.
+
+use crate::grpc_auth::GrpcAuthLayer;
+use crate::grpc_metrics::GrpcMetricsLayer;
+use crate::http::handlers::{ServedTime, serve_policy, served_time_ns, time_interval};
+use crate::http::state::AppState;
+use crate::streaming::{
+    StreamFormat, Tick, TickerKey, format_epoch_ms_to_iso8601, next_tick, validate_interval,
+};
+use anyhow::Context;
+use futures_util::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{mpsc, watch};
+use tonic::transport::server::{Router, ServerTlsConfig};
+use tonic::transport::{Certificate, Identity, Server};
+use tonic::{Request, Response, Status, Streaming};
+use tower::layer::util::{Identity as IdentityLayer, Stack};
+use tracing::debug;
+
+pub mod proto {
+    tonic::include_proto!("ntp_time_json_api.v1");
+}
+
+use proto::stream_control::Op;
+use proto::stream_event::Event;
+use proto::time_service_server::{TimeService, TimeServiceServer};
+use proto::{
+    ControlAck, ControlNack, GetTimeIntervalRequest, GetTimeRequest, Pong, StreamControl,
+    StreamEvent, StreamTimeRequest, TimeFormat, TimeIntervalReply, TimeReply,
+};
+
+/// `TimeService` method names, as used for metric labels.  Keep in sync
+/// with `proto/time_service.proto`.
+pub const RPC_METHODS: &[&str] = &[
+    "GetTime",
+    "GetTimeInterval",
+    "StreamTime",
+    "StreamTimeControlled",
+];
+
+/// Layers of the gRPC server: metrics outermost, so rejected calls are
+/// counted too.
+pub type GrpcLayers = Stack<GrpcAuthLayer, Stack<GrpcMetricsLayer, IdentityLayer>>;
+
+/// Events buffered per `StreamTimeControlled` call before the producer
+/// waits for the client.
+const CONTROLLED_STREAM_BUFFER: usize = 8;
+
+/// Implements `ntp_time_json_api.v1.TimeService`.
+pub struct GrpcTimeService {
+    state: Arc<AppState>,
+}
+
+impl GrpcTimeService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Wrap as a tonic service for `Server::add_service`.
+    pub fn into_server(self) -> TimeServiceServer<Self> {
+        TimeServiceServer::new(self)
+    }
+
+    /// The metrics/tracing layer for `Server::layer`, configured like the
+    /// HTTP request tracing.
+    pub fn metrics_layer(&self) -> GrpcMetricsLayer {
+        let logging = &self.state.config.logging;
+        GrpcMetricsLayer::new(
+            self.state.metrics.clone(),
+            logging.trace_sample_ratio,
+            logging.trace_always_sample_errors,
+        )
+    }
+
+    /// The authentication layer for `Server::layer` (see [`crate::grpc_auth`]).
+    pub fn auth_layer(&self) -> GrpcAuthLayer {
+        GrpcAuthLayer::new(self.state.clone())
+    }
+
+    /// The complete server: TLS when `GRPC_TLS_CERT_FILE` is set, then the
+    /// metrics and auth layers around this service.  Fails when a TLS file
+    /// cannot be read or parsed.
+    pub fn router(self) -> anyhow::Result<Router<GrpcLayers>> {
+        let mut server = Server::builder();
+        if let Some(tls) = self.tls_config()? {
+            server = server
+                .tls_config(tls)
+                .context("Invalid gRPC TLS configuration")?;
+        }
+        let metrics_layer = self.metrics_layer();
+        let auth_layer = self.auth_layer();
+        Ok(server
+            .layer(metrics_layer)
+            .layer(auth_layer)
+            .add_service(self.into_server()))
+    }
+
+    fn tls_config(&self) -> anyhow::Result<Option<ServerTlsConfig>> {
+        let config = &self.state.config.grpc;
+        let (Some(cert_file), Some(key_file)) = (&config.tls_cert_file, &config.tls_key_file)
+        else {
+            return Ok(None);
+        };
+        let read = |path: &str, key: &str| {
+            std::fs::read(path).with_context(|| format!("Failed to read {key} {path}"))
+        };
+        // tonic builds its rustls configs from the process-default provider,
+        // which rustls cannot pick itself with both ring and aws-lc-rs linked
+        // in.  Use ring, like the HTTP listener; an error means one is set.
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let identity = Identity::from_pem(
+            read(cert_file, "GRPC_TLS_CERT_FILE")?,
+            read(key_file, "GRPC_TLS_KEY_FILE")?,
+        );
+        let mut tls = ServerTlsConfig::new().identity(identity);
+        if let Some(ca_file) = &config.tls_client_ca_file {
+            // Optional at the handshake: GrpcAuthLayer decides per RPC, and
+            // API keys or JWTs remain valid alternatives.
+            tls = tls
+                .client_ca_root(Certificate::from_pem(read(
+                    ca_file,
+                    "GRPC_TLS_CLIENT_CA_FILE",
+                )?))
+                .client_auth_optional(true);
+        }
+        Ok(Some(tls))
+    }
+}
+
+/// Build the reply for `served`; `sequence` is 0 outside streams.
+fn time_reply(served: ServedTime, sequence: u64) -> TimeReply {
+    let quality = served.quality;
+    TimeReply {
+        epoch_ms: served.epoch_ms,
+        epoch_ns: served.epoch_ns,
+        iso8601: format_epoch_ms_to_iso8601(served.epoch_ms),
+        source: quality.source.to_string(),
+        serve_state: quality.serve_state.to_string(),
+        uncertainty_ms: quality.uncertainty_ms,
+        staleness_ms: quality.staleness_ms,
+        stratum: quality.stratum.map(u32::from),
+        selected_server: quality.selected_server,
+        sequence,
+    }
+}
+
+/// Trim a reply to the fields `format` sends, as `/stream` does.
+fn format_reply(reply: TimeReply, format: StreamFormat) -> TimeReply {
+    match format {
+        StreamFormat::Full => reply,
+        StreamFormat::Epoch => TimeReply {
+            epoch_ms: reply.epoch_ms,
+            sequence: reply.sequence,
+            ..Default::default()
+        },
+        StreamFormat::Iso => TimeReply {
+            iso8601: reply.iso8601,
+            sequence: reply.sequence,
+            ..Default::default()
+        },
+        StreamFormat::UnixNs => TimeReply {
+            epoch_ns: reply.epoch_ns,
+            sequence: reply.sequence,
+            ..Default::default()
+        },
+    }
+}
+
+fn time_format(format: StreamFormat) -> TimeFormat {
+    match format {
+        StreamFormat::Full => TimeFormat::Full,
+        StreamFormat::Epoch => TimeFormat::Epoch,
+        StreamFormat::Iso => TimeFormat::Iso,
+        StreamFormat::UnixNs => TimeFormat::UnixNs,
+    }
+}
+
+/// `None` keeps the current format.
+fn stream_format(format: i32) -> Result<Option<StreamFormat>, String> {
+    match TimeFormat::try_from(format) {
+        Ok(TimeFormat::Unspecified) => Ok(None),
+        Ok(TimeFormat::Full) => Ok(Some(StreamFormat::Full)),
+        Ok(TimeFormat::Epoch) => Ok(Some(StreamFormat::Epoch)),
+        Ok(TimeFormat::Iso) => Ok(Some(StreamFormat::Iso)),
+        Ok(TimeFormat::UnixNs) => Ok(Some(StreamFormat::UnixNs)),
+        Err(_) => Err(format!("unknown format {format}")),
+    }
+}
+
+type TimeStream = Pin<Box<dyn Stream<Item = Result<TimeReply, Status>> + Send>>;
+type EventStream = Pin<Box<dyn Stream<Item = Result<StreamEvent, Status>> + Send>>;
+
+/// A [`StreamHub`](crate::streaming::StreamHub) subscription feeding one
+/// gRPC stream.
+struct StreamTicks {
+    state: Arc<AppState>,
+    shutdown: watch::Receiver<bool>,
+    key: TickerKey,
+    /// `None` while paused.
+    ticks: Option<broadcast::Receiver<Tick>>,
+    done: bool,
+}
+
+impl StreamTicks {
+    fn new(state: &Arc<AppState>, key: TickerKey) -> Self {
+        Self {
+            shutdown: state.shutdown_signal.subscribe(),
+            ticks: Some(state.stream_hub.subscribe(state, key)),
+            state: state.clone(),
+            key,
+            done: false,
+        }
+    }
+
+    /// Switch to `key`'s ticker, resuming if paused.
+    fn subscribe(&mut self, key: TickerKey) {
+        self.key = key;
+        self.ticks = Some(self.state.stream_hub.subscribe(&self.state, key));
+    }
+
+    fn paused(&self) -> bool {
+        self.ticks.is_none()
+    }
+
+    /// Wait for the next tick and report the time served then; while paused
+    /// only shutdown ends the wait.
+    async fn next(&mut self) -> Result<TimeReply, Status> {
+        loop {
+            let tick = tokio::select! {
+                tick = next_tick(&mut self.ticks) => tick,
+                _ = self.shutdown.wait_for(|stop| *stop) => {
+                    return Err(Status::unavailable("server shutting down"));
+                }
+            };
+            match tick {
+                Ok(tick) => {
+                    let served = serve_policy(&self.state, tick.epoch_ns, (*tick.quality).clone())?;
+                    return Ok(format_reply(
+                        time_reply(served, tick.sequence),
+                        self.key.format,
+                    ));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!(
+                        skipped,
+                        "gRPC stream fell behind its ticker, skipping ahead"
+                    );
+                }
+                Err(RecvError::Closed) => {
+                    return Err(Status::unavailable("stream ticker stopped"));
+                }
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl TimeService for GrpcTimeService {
+    async fn get_time(
+        &self,
+        _request: Request<GetTimeRequest>,
+    ) -> Result<Response<TimeReply>, Status> {
+        let served = served_time_ns(&self.state)?;
+        Ok(Response::new(time_reply(served, 0)))
+    }
+
+    async fn get_time_interval(
+        &self,
+        _request: Request<GetTimeIntervalRequest>,
+    ) -> Result<Response<TimeIntervalReply>, Status> {
+        let interval = time_interval(&self.state)?;
+        let quality = interval.served.quality;
+        Ok(Response::new(TimeIntervalReply {
+            earliest_ms: interval.earliest_ms,
+            latest_ms: interval.latest_ms,
+            epoch_ms: interval.served.epoch_ms,
+            uncertainty_ms: interval.uncertainty_ms,
+            source: quality.source.to_string(),
+            serve_state: quality.serve_state.to_string(),
+            staleness_ms: quality.staleness_ms,
+        }))
+    }
+
+    type StreamTimeStream = TimeStream;
+
+    async fn stream_time(
+        &self,
+        request: Request<StreamTimeRequest>,
+    ) -> Result<Response<Self::StreamTimeStream>, Status> {
+        let ws = &self.state.config.ws;
+        let interval_ms = match request.into_inner().interval_ms {
+            0 => ws.update_interval_ms,
+            interval_ms => validate_interval(interval_ms, ws).map_err(Status::invalid_argument)?,
+        };
+        debug!(interval_ms, "gRPC time stream opened");
+
+        // A served-time error or shutdown ends the stream with UNAVAILABLE.
+        let ticks = StreamTicks::new(
+            &self.state,
+            TickerKey {
+                interval_ms,
+                format: StreamFormat::Full,
+            },
+        );
+        let stream = futures_util::stream::unfold(ticks, |mut ticks| async move {
+            if ticks.done {
+                return None;
+            }
+            let reply = ticks.next().await;
+            ticks.done = reply.is_err();
+            Some((reply, ticks))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    type StreamTimeControlledStream = EventStream;
+
+    async fn stream_time_controlled(
+        &self,
+        request: Request<Streaming<StreamControl>>,
+    ) -> Result<Response<Self::StreamTimeControlledStream>, Status> {
+        let mut controls = request.into_inner();
+        let key = TickerKey {
+            interval_ms: self.state.config.ws.update_interval_ms,
+            format: StreamFormat::Full,
+        };
+        let mut stream = ControlledStream {
+            ticks: StreamTicks::new(&self.state, key),
+        };
+        debug!(
+            interval_ms = key.interval_ms,
+            "gRPC controlled time stream opened"
+        );
+
+        // The producer runs until the client goes away or a tick fails; the
+        // client half-closing its side only stops the controls.
+        let (tx, rx) = mpsc::channel(CONTROLLED_STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut controls_open = true;
+            loop {
+                let event = tokio::select! {
+                    reply = stream.ticks.next() => reply.map(|reply| StreamEvent {
+                        event: Some(Event::Tick(reply)),
+                    }),
+                    control = controls.message(), if controls_open => match control {
+                        Ok(Some(control)) => Ok(stream.apply(control)),
+                        Ok(None) => {
+                            controls_open = false;
+                            continue;
+                        }
+                        Err(_) => return,
+                    },
+                    _ = tx.closed() => return,
+                };
+                let done = event.is_err();
+                if tx.send(event).await.is_err() || done {
+                    return;
+                }
+            }
+        });
+        let events = futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|event| (event, rx))
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+/// One `StreamTimeControlled` call; its ticker key holds the settings.
+struct ControlledStream {
+    ticks: StreamTicks,
+}
+
+impl ControlledStream {
+    /// Apply one control and build its ack, nack or pong.
+    fn apply(&mut self, control: StreamControl) -> StreamEvent {
+        let ws = &self.ticks.state.config.ws;
+        let nack = |op: &str, error: String| StreamEvent {
+            event: Some(Event::Nack(ControlNack {
+                op: op.to_string(),
+                error,
+            })),
+        };
+        let op = match control.op {
+            Some(Op::Subscribe(subscribe)) => {
+                let interval_ms = match subscribe.interval_ms.map(|ms| validate_interval(ms, ws)) {
+                    Some(Err(error)) => return nack("subscribe", error),
+                    Some(Ok(ms)) => Some(ms),
+                    None => None,
+                };
+                let format = match stream_format(subscribe.format) {
+                    Ok(format) => format,
+                    Err(error) => return nack("subscribe", error),
+                };
+                let key = self.ticks.key;
+                self.ticks.subscribe(TickerKey {
+                    interval_ms: interval_ms.unwrap_or(key.interval_ms),
+                    format: format.unwrap_or(key.format),
+                });
+                "subscribe"
+            }
+            Some(Op::SetInterval(set_interval)) => match validate_interval(set_interval.ms, ws) {
+                Ok(ms) => {
+                    self.ticks.subscribe(TickerKey {
+                        interval_ms: ms,
+                        ..self.ticks.key
+                    });
+                    "set_interval"
+                }
+                Err(error) => return nack("set_interval", error),
+            },
+            Some(Op::Pause(_)) => {
+                self.ticks.ticks = None;
+                "pause"
+            }
+            Some(Op::Resume(_)) => {
+                if self.ticks.paused() {
+                    self.ticks.subscribe(self.ticks.key);
+                }
+                "resume"
+            }
+            Some(Op::Ping(_)) => {
+                return StreamEvent {
+                    event: Some(Event::Pong(Pong {
+                        epoch_ms: self.ticks.state.timebase.now_ms(),
+                    })),
+                };
+            }
+            None => return nack("", "control carries no op".to_string()),
+        };
+        debug!(
+            op,
+            interval_ms = self.ticks.key.interval_ms,
+            paused = self.ticks.paused(),
+            "Applied gRPC stream control"
+        );
+        StreamEvent {
+            event: Some(Event::Ack(ControlAck {
+                op: op.to_string(),
+                update_interval_ms: self.ticks.key.interval_ms,
+                format: time_format(self.ticks.key.format).into(),
+                paused: self.ticks.paused(),
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_reply_keeps_the_format_fields() {
+        let reply = TimeReply {
+            epoch_ms: 1_735_446_000_000,
+            epoch_ns: 1_735_446_000_000_123_456,
+            iso8601: "2024-12-29T04:20:00.000Z".to_string(),
+            source: "ntp".to_string(),
+            stratum: Some(2),
+            sequence: 7,
+            ..Default::default()
+        };
+        assert_eq!(format_reply(reply.clone(), StreamFormat::Full), reply);
+
+        let epoch = format_reply(reply.clone(), StreamFormat::Epoch);
+        assert_eq!((epoch.epoch_ms, epoch.sequence), (1_735_446_000_000, 7));
+        assert!(epoch.iso8601.is_empty() && epoch.stratum.is_none());
+
+        let iso = format_reply(reply.clone(), StreamFormat::Iso);
+        assert_eq!(iso.iso8601, "2024-12-29T04:20:00.000Z");
+        assert_eq!((iso.epoch_ms, iso.sequence), (0, 7));
+
+        let unix_ns = format_reply(reply, StreamFormat::UnixNs);
+        assert_eq!(
+            (unix_ns.epoch_ns, unix_ns.sequence),
+            (1_735_446_000_000_123_456, 7)
+        );
+        assert_eq!(unix_ns.epoch_ms, 0);
+    }
+
+    #[test]
+    fn stream_format_unspecified_keeps_current() {
+        assert_eq!(stream_format(TimeFormat::Unspecified.into()), Ok(None));
+        assert_eq!(
+            stream_format(TimeFormat::Iso.into()),
+            Ok(Some(StreamFormat::Iso))
+        );
+        assert!(stream_format(42).is_err());
+    }
+}
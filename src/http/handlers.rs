@@ -1,6 +1,21 @@
-use super::state::{AppState, TimeQuality};
+use super::state::{AppState, DeepHealthResult, TimeQuality};
+use crate::convert::{self, Representation, Target};
+use crate::encoding::{Encoding, protobuf};
 use crate::errors::AppError;
-use axum::{Json, extract::State, http::StatusCode, response::Response};
+use crate::format;
+use crate::leap;
+use crate::ntp::nts;
+use crate::performance::TimestampFormat;
+use crate::timebase::NANOS_PER_MS;
+use crate::timezone::{self, ZoneInfo};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::Datelike;
+use chrono_tz::Tz;
 use serde_json::{Value, json};
 use std::sync::Arc;
 use std::time::Instant;
@@ -20,11 +35,73 @@ use std::time::Instant;
 /// returns HTTP 200 for all quality states including degraded and holdover.
 /// HTTP 503 is only returned when uninitialized (no seed) + REQUIRE_SYNC=true,
 /// or when STRICT_SLA_MODE=true and uncertainty exceeds the configured threshold.
-pub async fn time_handler(State(state): State<Arc<AppState>>) -> Result<Response, AppError> {
+///
+/// Optional body extensions (see [`TimeQuery`]); invalid values return 400.
+/// Without any of them the body is served from the pre-serialized cache
+/// exactly as before.
+pub async fn time_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TimeQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    serve_time(&state, &query, accepted_encoding(&headers))
+}
+
+/// GET /time.txt — `/time` as `Accept: text/plain` would answer it: the bare
+/// epoch milliseconds, for shell scripts without `jq`.
+pub async fn time_text_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TimeQuery>,
+) -> Result<Response, AppError> {
+    serve_time(&state, &query, Encoding::Text)
+}
+
+fn serve_time(
+    state: &AppState,
+    query: &TimeQuery,
+    mut encoding: Encoding,
+) -> Result<Response, AppError> {
     let start = Instant::now();
 
-    let result: Result<Response, AppError> = match state.timebase.now_ms() {
-        Some(epoch_ms) => {
+    if query.callback.is_some() {
+        if !state.config.http.jsonp_enabled {
+            state.perf_metrics.record_error();
+            return Err(AppError::BadRequest {
+                error: "JSONP is disabled (JSONP_ENABLED=false)".into(),
+            });
+        }
+        // A JSONP callback always wraps JSON, whatever `Accept` says.
+        encoding = Encoding::Json;
+    }
+
+    let extras = match query.extras() {
+        Ok(extras) => extras,
+        Err(error) => {
+            state.perf_metrics.record_error();
+            return Err(AppError::BadRequest { error });
+        }
+    };
+    if extras.is_some() && matches!(encoding, Encoding::Protobuf | Encoding::Text) {
+        state.perf_metrics.record_error();
+        return Err(AppError::NotAcceptable {
+            error:
+                "text and protobuf bodies cannot carry query extensions; use json, msgpack or cbor"
+                    .into(),
+        });
+    }
+
+    // Sub-millisecond units need the nanosecond reading; everything else
+    // keeps the millisecond clamp of the cached fast path.
+    let reading_ns = if extras
+        .as_ref()
+        .is_some_and(|extras| extras.unit.sub_millisecond())
+    {
+        state.timebase.now_ns()
+    } else {
+        state.timebase.now_ms().map(|ms| ms * NANOS_PER_MS)
+    };
+    let result: Result<Response, AppError> = match reading_ns {
+        Some(epoch_ns) => {
             let quality = state.compute_quality();
             // Only return 503 in strict SLA mode when serve_state="stopped".
             // In default mode (strict_sla_mode=false), always serve 200 after seed.
@@ -39,7 +116,13 @@ pub async fn time_handler(State(state): State<Arc<AppState>>) -> Result<Response
                 })
             } else {
                 state.perf_metrics.record_cache_hit();
-                Ok(build_time_response(&state, epoch_ms, &quality))
+                Ok(build_time_response(
+                    state,
+                    epoch_ns,
+                    &quality,
+                    extras.as_ref(),
+                    encoding,
+                ))
             }
         }
         None if state.config.ntp.require_sync => Err(AppError::NotSynced {
@@ -48,7 +131,12 @@ pub async fn time_handler(State(state): State<Arc<AppState>>) -> Result<Response
         }),
         None => {
             let quality = state.compute_quality(); // source="unsynced"
-            Ok(build_system_clock_response(&state, &quality))
+            Ok(build_system_clock_response(
+                state,
+                &quality,
+                extras.as_ref(),
+                encoding,
+            ))
         }
     };
 
@@ -61,61 +149,572 @@ pub async fn time_handler(State(state): State<Arc<AppState>>) -> Result<Response
     result
 }
 
-/// Build the 200 OK response for the synced path. Uses the
-/// pre-serialized JSON cache (zero-copy via `Arc<String>`) so the
-/// hot path stays fast. Appends quality headers without touching the body.
-fn build_time_response(state: &AppState, epoch_ms: i64, quality: &TimeQuality) -> Response {
-    let is_stale = quality.serve_state != "ok";
+/// GET /time/iso — `"2024-01-15T12:00:00.000Z"`; see [`formatted_time_response`].
+pub async fn time_iso_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    formatted_time_response(&state, &headers, TimestampFormat::Iso)
+}
 
-    // PERFORMANCE: Update cache with current time, then get
-    // pre-serialized JSON. This avoids json!() macro and serde
-    // overhead on the hot path.
-    state.time_cache.update(epoch_ms, is_stale);
-    let json_body = state.time_cache.get_json(is_stale);
+/// GET /time/unix — `1705320000`; see [`formatted_time_response`].
+pub async fn time_unix_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    formatted_time_response(&state, &headers, TimestampFormat::Unix)
+}
 
-    let mut builder = axum::response::Response::builder()
-        .status(StatusCode::OK)
-        .header("content-type", "application/json")
-        .header("x-time-source", quality.source)
-        .header("x-time-serve-state", quality.serve_state);
+/// GET /time/rfc2822 — `"Mon, 15 Jan 2024 12:00:00 +0000"`; see
+/// [`formatted_time_response`].
+pub async fn time_rfc2822_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    formatted_time_response(&state, &headers, TimestampFormat::Rfc2822)
+}
 
-    if let Some(u) = quality.uncertainty_ms {
-        builder = builder.header("x-time-uncertainty-ms", format!("{u:.3}"));
+/// GET /time/all — one instant in every common representation, plus its
+/// staleness, so dashboards need neither several calls nor their own
+/// conversions.  Serve policy and `X-Time-*` headers are those of `/time`.
+pub async fn time_all_handler(State(state): State<Arc<AppState>>) -> Result<Response, AppError> {
+    let start = Instant::now();
+    let served = match served_time(&state) {
+        Ok(served) => served,
+        Err(error) => {
+            state.perf_metrics.record_error();
+            return Err(error);
+        }
+    };
+    let epoch_ms = served.epoch_ms;
+    let utc = timezone::utc_datetime(epoch_ms);
+    let (staleness_secs, stale) = staleness(&state, &served.quality);
+    let body = json!({
+        "epoch_ms": epoch_ms,
+        "epoch_s": epoch_ms.div_euclid(1000),
+        "iso8601": TimestampFormat::Iso.render(epoch_ms),
+        "rfc2822": TimestampFormat::Rfc2822.render(epoch_ms),
+        "week_number": utc.iso_week().week(),
+        "day_of_year": utc.ordinal(),
+        "source": served.quality.source,
+        "staleness_ms": served.quality.staleness_ms,
+        "staleness_secs": staleness_secs,
+        "stale": stale,
+    });
+    state
+        .perf_metrics
+        .record_success(start.elapsed().as_micros() as u64);
+    Ok(
+        quality_response_builder(&served.quality, epoch_ms, "application/json")
+            .body(axum::body::Body::from(body.to_string()))
+            .expect("failed to build /time/all response"),
+    )
+}
+
+/// Just the current time in one format, for clients that cannot pass query
+/// parameters.  The body is a bare JSON value (a number for `/time/unix`),
+/// or the plain text with `Accept: text/plain`.  Renderings come from a
+/// per-format cache entry.  Serve policy and `X-Time-*` headers are those
+/// of `/time`; errors keep the JSON error body.
+fn formatted_time_response(
+    state: &AppState,
+    headers: &HeaderMap,
+    format: TimestampFormat,
+) -> Result<Response, AppError> {
+    let start = Instant::now();
+    let result = served_time(state).map(|served| {
+        let plain = accepted_encoding(headers) == Encoding::Text;
+        let body = state
+            .time_cache
+            .get_formatted(format, served.epoch_ms, !plain);
+        let encoding = if plain {
+            Encoding::Text
+        } else {
+            Encoding::Json
+        };
+        quality_response_builder(&served.quality, served.epoch_ms, encoding.content_type())
+            .body(axum::body::Body::from((*body).clone()))
+            .expect("failed to build formatted time response")
+    });
+
+    match &result {
+        Ok(_) => {
+            state.perf_metrics.record_cache_hit();
+            state
+                .perf_metrics
+                .record_success(start.elapsed().as_micros() as u64);
+        }
+        Err(_) => state.perf_metrics.record_error(),
     }
-    if let Some(s) = quality.stratum {
-        builder = builder.header("x-time-stratum", s.to_string());
+    result
+}
+
+/// What `/time` would serve now.
+pub(crate) struct ServedTime {
+    pub(crate) epoch_ms: i64,
+    /// `epoch_ms` at the resolution it was read with (see [`served_time_ns`]).
+    pub(crate) epoch_ns: i64,
+    pub(crate) quality: TimeQuality,
+    /// Host clock stand-in: `REQUIRE_SYNC=false` and never seeded.
+    pub(crate) host_clock: bool,
+}
+
+/// The time `/time` would serve now, under the same policy: the time base
+/// once seeded (unless strict SLA mode has stopped serving), the host clock
+/// when `REQUIRE_SYNC=false` and never seeded.  Time and quality are read
+/// at one instant, so the uncertainty describes this very timestamp.
+pub(crate) fn served_time(state: &AppState) -> Result<ServedTime, AppError> {
+    let now = Instant::now();
+    let epoch_ns = state.timebase.now_ms_at(now).map(|ms| ms * NANOS_PER_MS);
+    serve_policy(state, epoch_ns, state.compute_quality_at(now))
+}
+
+/// [`served_time`] read at nanosecond resolution.
+pub(crate) fn served_time_ns(state: &AppState) -> Result<ServedTime, AppError> {
+    let now = Instant::now();
+    serve_policy(
+        state,
+        state.timebase.now_ns_at(now),
+        state.compute_quality_at(now),
+    )
+}
+
+/// [`served_time`] for a time base reading in nanoseconds (`None` while
+/// unsynced) and quality taken elsewhere, e.g. once per stream tick.
+pub(crate) fn serve_policy(
+    state: &AppState,
+    epoch_ns: Option<i64>,
+    quality: TimeQuality,
+) -> Result<ServedTime, AppError> {
+    match epoch_ns {
+        Some(epoch_ns) => {
+            if state.config.quality.strict_sla_mode && quality.serve_state == "stopped" {
+                return Err(AppError::ServeStopped {
+                    message: state.config.messages.error.clone(),
+                    error: format!(
+                        "Time uncertainty ({:.1} ms) exceeds the configured SLA threshold",
+                        quality.uncertainty_ms.unwrap_or(0.0)
+                    ),
+                    serve_state: "stopped".into(),
+                });
+            }
+            Ok(ServedTime {
+                epoch_ms: epoch_ns.div_euclid(NANOS_PER_MS),
+                epoch_ns,
+                quality,
+                host_clock: false,
+            })
+        }
+        None if state.config.ntp.require_sync => Err(AppError::NotSynced {
+            message: state.config.messages.error.clone(),
+            error: state.config.messages.error_no_sync.clone(),
+        }),
+        None => {
+            let epoch_ns = system_clock_ns();
+            Ok(ServedTime {
+                epoch_ms: epoch_ns.div_euclid(NANOS_PER_MS),
+                epoch_ns,
+                quality,
+                host_clock: true,
+            })
+        }
     }
-    if let Some(ms) = quality.staleness_ms {
-        builder = builder.header("x-time-staleness-ms", ms.to_string());
+}
+
+/// The served time widened to the interval the true time lies in.
+pub(crate) struct TimeInterval {
+    pub(crate) earliest_ms: i64,
+    pub(crate) latest_ms: i64,
+    /// Half-width of the interval before rounding outwards to whole ms.
+    pub(crate) uncertainty_ms: f64,
+    pub(crate) served: ServedTime,
+}
+
+/// TrueTime-style `[earliest, latest]` around [`served_time`]: the served
+/// time ± its uncertainty, i.e. the RFC 5905 dispersion (upstream
+/// dispersion, half the sync RTT, jitter, and drift over the staleness),
+/// or `MANUAL_OVERRIDE_DISPERSION_MS` under a manual override.  Without a known
+/// uncertainty (host clock, or seeded before any NTP sync) there is no
+/// bound to give, so this fails with 503 like an unsynced `/time`.
+pub(crate) fn time_interval(state: &AppState) -> Result<TimeInterval, AppError> {
+    let served = served_time(state)?;
+    let uncertainty_ms = match (served.host_clock, served.quality.uncertainty_ms) {
+        (false, Some(uncertainty_ms)) => uncertainty_ms,
+        _ => {
+            return Err(AppError::NotSynced {
+                message: state.config.messages.error.clone(),
+                error: "Time uncertainty is unknown until the first NTP sync".into(),
+            });
+        }
+    };
+    let half_width_ms = uncertainty_ms.ceil() as i64;
+    Ok(TimeInterval {
+        earliest_ms: served.epoch_ms.saturating_sub(half_width_ms),
+        latest_ms: served.epoch_ms.saturating_add(half_width_ms),
+        uncertainty_ms,
+        served,
+    })
+}
+
+/// GET /time/interval — the bounded interval `[earliest_ms, latest_ms]`
+/// the true time is within, for callers that need bounds rather than a
+/// point estimate (commit-wait, lease expiry).  Headers are those of `/time`.
+pub async fn time_interval_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let interval = time_interval(&state)?;
+    let quality = &interval.served.quality;
+    let body = json!({
+        "earliest_ms": interval.earliest_ms,
+        "latest_ms": interval.latest_ms,
+        "epoch_ms": interval.served.epoch_ms,
+        "uncertainty_ms": interval.uncertainty_ms,
+        "source": quality.source,
+        "serve_state": quality.serve_state,
+        "staleness_ms": quality.staleness_ms,
+    });
+    Ok(
+        quality_response_builder(quality, interval.served.epoch_ms, "application/json")
+            .body(axum::body::Body::from(body.to_string()))
+            .expect("failed to build /time/interval response"),
+    )
+}
+
+/// Longest `/time/next` sleeps between checks of the served time.
+const TIME_NEXT_RECHECK: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// GET /time/next?after=<epoch_ms> — long poll for a moment in time.
+///
+/// Waits until the served time passes `after`, then answers exactly as
+/// `/time` would (same body, headers and serve policy).  The wait is bounded
+/// by `REQUEST_TIMEOUT`, after which the request fails with 408 and the
+/// client polls again.  An `after` already in the past answers at once.
+pub async fn time_next_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TimeNextQuery>,
+) -> Result<Response, AppError> {
+    let after = match query.after.as_deref().map(str::parse::<i64>) {
+        Some(Ok(after)) => after,
+        Some(Err(_)) | None => {
+            state.perf_metrics.record_error();
+            return Err(AppError::BadRequest {
+                error: "after must be an epoch in milliseconds".into(),
+            });
+        }
+    };
+
+    loop {
+        let served = match served_time(&state) {
+            Ok(served) => served,
+            Err(e) => {
+                state.perf_metrics.record_error();
+                return Err(e);
+            }
+        };
+        if served.epoch_ms > after {
+            let start = Instant::now();
+            let response = if served.host_clock {
+                build_system_clock_response(&state, &served.quality, None, Encoding::Json)
+            } else {
+                build_time_response(
+                    &state,
+                    served.epoch_ns,
+                    &served.quality,
+                    None,
+                    Encoding::Json,
+                )
+            };
+            state
+                .perf_metrics
+                .record_success(start.elapsed().as_micros() as u64);
+            return Ok(response);
+        }
+        // Re-check at least every TIME_NEXT_RECHECK: a slew can make served
+        // time run slower than the monotonic clock, and a sync or override
+        // can step it past `after` early.
+        let remaining = std::time::Duration::from_millis((after - served.epoch_ms + 1) as u64);
+        tokio::time::sleep(remaining.min(TIME_NEXT_RECHECK)).await;
     }
-    if let Some(ref srv) = quality.selected_server {
-        builder = builder.header("x-time-selected-server", srv.as_str());
+}
+
+/// Query parameters accepted by `/time/next`.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct TimeNextQuery {
+    /// Epoch (ms) the served time must pass before the request answers.
+    pub after: Option<String>,
+}
+
+/// Query parameters accepted by `/time`.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct TimeQuery {
+    /// Comma-separated IANA zones, e.g. `UTC,Asia/Tehran,America/New_York`;
+    /// adds a `timezones` array (request order, at most `MAX_ZONES_PER_REQUEST`).
+    pub tz: Option<String>,
+    /// strftime-style pattern, e.g. `%Y-%m-%d %H:%M:%S%.3f`; adds a
+    /// `formatted` string (UTC) and formats each `timezones` entry too.
+    /// Whitelisted specifiers only, at most `MAX_PATTERN_LEN` bytes.
+    pub pattern: Option<String>,
+    /// Locale for month/weekday names and digits, e.g. `fa-IR` or `de_DE`;
+    /// without `pattern` it implies `DEFAULT_LOCALIZED_PATTERN`.
+    pub locale: Option<String>,
+    /// `1`/`true` adds sync provenance (`source`, `serve_state`, `age_ms`,
+    /// `staleness_secs`, `stale`, `uncertainty_ms`, `error_bound_ms`,
+    /// `stratum`, `selected_server`, `rtt_ms`, `offset_ms`, `last_sync`) and
+    /// `utc_tai_offset` to the body.
+    pub verbose: Option<String>,
+    /// `1`/`true` emits `data` as a JSON string, `0`/`false` as a number;
+    /// overrides `EPOCH_AS_STRING` for this request.
+    pub epoch_as_string: Option<String>,
+    /// Unit of `data`: `unix_ms` (default), `unix_us`, `unix_ns`, or `tai`
+    /// for TAI milliseconds.  Nanoseconds exceed 2^53, so JavaScript
+    /// clients want `epoch_as_string=1` with them.
+    pub format: Option<String>,
+    /// JSONP: wrap the JSON body as `/**/callback(...);`.  Requires
+    /// `JSONP_ENABLED`; a JavaScript identifier path of at most
+    /// `MAX_CALLBACK_LEN` bytes.
+    pub callback: Option<String>,
+}
+
+/// Longest `?callback=` accepted, in bytes.
+pub const MAX_CALLBACK_LEN: usize = 64;
+
+/// Validated `/time` body extensions.
+#[derive(Debug, Default)]
+pub struct TimeExtras {
+    zones: Vec<Tz>,
+    format: Option<format::TimeFormat>,
+    verbose: bool,
+    epoch_as_string: Option<bool>,
+    unit: EpochUnit,
+    callback: Option<String>,
+}
+
+/// Unit of the `/time` epoch (`?format=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EpochUnit {
+    #[default]
+    Millis,
+    Micros,
+    Nanos,
+    /// Milliseconds on the TAI scale (`CLOCK_TAI`): UTC plus TAI − UTC
+    /// from the leap-second table.
+    TaiMillis,
+}
+
+impl EpochUnit {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw {
+            "unix_ms" => Ok(EpochUnit::Millis),
+            "unix_us" => Ok(EpochUnit::Micros),
+            "unix_ns" => Ok(EpochUnit::Nanos),
+            "tai" => Ok(EpochUnit::TaiMillis),
+            other => Err(format!(
+                "format must be one of unix_ms, unix_us, unix_ns, tai: {other}"
+            )),
+        }
     }
 
-    builder
-        .body(axum::body::Body::from((*json_body).clone()))
-        .expect("failed to build /time response")
+    /// Whether this unit needs a nanosecond reading.
+    fn sub_millisecond(self) -> bool {
+        matches!(self, EpochUnit::Micros | EpochUnit::Nanos)
+    }
+
+    /// `epoch_ns` in this unit, rounded down.
+    fn scale_ns(self, epoch_ns: i64) -> i64 {
+        match self {
+            EpochUnit::Millis => epoch_ns.div_euclid(NANOS_PER_MS),
+            EpochUnit::Micros => epoch_ns.div_euclid(1_000),
+            EpochUnit::Nanos => epoch_ns,
+            EpochUnit::TaiMillis => leap::current().tai_ms(epoch_ns.div_euclid(NANOS_PER_MS)),
+        }
+    }
 }
 
-/// Build the 200 OK response for the `REQUIRE_SYNC=false` fallback,
-/// where the service reports the OS wall clock instead of the
-/// NTP-derived time. Defeats the "NTP-authoritative" design but
-/// useful for development; never enabled in production.
-fn build_system_clock_response(state: &AppState, quality: &TimeQuality) -> Response {
-    let epoch_ms = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_millis() as i64)
-        .unwrap_or(0);
+impl TimeQuery {
+    /// Validate the query; `None` when no extension was requested.
+    pub fn extras(&self) -> Result<Option<TimeExtras>, String> {
+        let verbose = parse_flag("verbose", self.verbose.as_deref())?.unwrap_or(false);
+        let epoch_as_string = parse_flag("epoch_as_string", self.epoch_as_string.as_deref())?;
+        let unit = match self.format.as_deref() {
+            Some(raw) => EpochUnit::parse(raw)?,
+            None => EpochUnit::Millis,
+        };
+        if self.tz.is_none()
+            && self.pattern.is_none()
+            && self.locale.is_none()
+            && !verbose
+            && epoch_as_string.is_none()
+            && unit == EpochUnit::Millis
+            && self.callback.is_none()
+        {
+            return Ok(None);
+        }
+        if let Some(callback) = self.callback.as_deref() {
+            validate_callback(callback)?;
+        }
+        let zones = match self.tz.as_deref() {
+            Some(raw) => timezone::parse_zone_list(raw)?,
+            None => Vec::new(),
+        };
+        let format = if self.pattern.is_some() || self.locale.is_some() {
+            Some(format::TimeFormat::new(
+                self.pattern.as_deref(),
+                self.locale.as_deref(),
+            )?)
+        } else {
+            None
+        };
+        Ok(Some(TimeExtras {
+            zones,
+            format,
+            verbose,
+            epoch_as_string,
+            unit,
+            callback: self.callback.clone(),
+        }))
+    }
+}
 
-    let body = json!({
-        "message": &state.config.messages.ok,
+/// Accept only dotted JavaScript identifiers (`cb`, `jQuery.cb_1`), so the
+/// callback cannot inject script around the JSON it wraps.
+fn validate_callback(callback: &str) -> Result<(), String> {
+    if callback.len() > MAX_CALLBACK_LEN {
+        return Err(format!("callback exceeds {MAX_CALLBACK_LEN} bytes"));
+    }
+    let valid = callback.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+    });
+    if !valid {
+        return Err("callback must be a JavaScript identifier such as jsonpCallback".to_string());
+    }
+    Ok(())
+}
+
+/// Parse a boolean query flag given as `0`/`1`/`false`/`true`.
+fn parse_flag(name: &str, value: Option<&str>) -> Result<Option<bool>, String> {
+    match value {
+        None => Ok(None),
+        Some("0") | Some("false") => Ok(Some(false)),
+        Some("1") | Some("true") => Ok(Some(true)),
+        Some(other) => Err(format!("{name} must be 0, 1, true or false: {other}")),
+    }
+}
+
+/// An epoch value as JSON: a string when `as_string` (exact in JavaScript
+/// beyond 2^53), otherwise a number.
+pub(crate) fn epoch_json(epoch: i64, as_string: bool) -> Value {
+    if as_string {
+        Value::String(epoch.to_string())
+    } else {
+        Value::from(epoch)
+    }
+}
+
+/// Body encoding requested by the `Accept` header (JSON by default).
+fn accepted_encoding(headers: &HeaderMap) -> Encoding {
+    Encoding::from_accept(
+        headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok()),
+    )
+}
+
+/// `{message, status, data}` plus the requested extensions.
+fn time_body_with_extras(
+    state: &AppState,
+    message: &str,
+    epoch_ns: i64,
+    quality: &TimeQuality,
+    extras: &TimeExtras,
+) -> Value {
+    let epoch_ms = epoch_ns.div_euclid(NANOS_PER_MS);
+    let format = extras.format.as_ref();
+    let epoch_as_string = extras
+        .epoch_as_string
+        .unwrap_or(state.config.http.epoch_as_string);
+    let mut body = json!({
+        "message": message,
         "status": 200,
-        "data": epoch_ms,
+        "data": epoch_json(extras.unit.scale_ns(epoch_ns), epoch_as_string),
     });
+    if extras.verbose {
+        body["source"] = json!(quality.source);
+        body["serve_state"] = json!(quality.serve_state);
+        body["age_ms"] = json!(quality.staleness_ms);
+        body["uncertainty_ms"] = json!(quality.uncertainty_ms);
+        body["error_bound_ms"] = json!(quality.error_bound_ms);
+        body["stratum"] = json!(quality.stratum);
+        body["selected_server"] = json!(quality.selected_server);
+        let manual = quality.source == "manual";
+        let (staleness_secs, stale) = staleness(state, quality);
+        body["staleness_secs"] = json!(staleness_secs);
+        body["stale"] = json!(stale);
+        let (rtt_ms, offset_ms) = match state.last_sync_quality.read().as_ref() {
+            Some(q) if !manual => (Some(q.measured_rtt_ms), Some(q.offset_ms)),
+            _ => (None, None),
+        };
+        body["rtt_ms"] = json!(rtt_ms);
+        body["offset_ms"] = json!(offset_ms);
+        body["last_sync"] = last_sync_json(state);
+        body["utc_tai_offset"] = json!(leap::current().utc_tai_offset(epoch_ms));
+    }
+    if let Some(format) = format {
+        body["formatted"] = json!(format.render(&timezone::utc_datetime(epoch_ms)));
+    }
+    if !extras.zones.is_empty() {
+        let timezones: Vec<timezone::LocalTime> = extras
+            .zones
+            .iter()
+            .map(|tz| timezone::localize(*tz, epoch_ms, format))
+            .collect();
+        body["timezones"] = json!(timezones);
+    }
+    body
+}
 
+/// Seconds since the sync behind `quality`, and whether that exceeds
+/// `MAX_STALENESS`.  A manual override has no upstream exchange and is never
+/// stale; without any sync, the answer is as stale as it gets.
+fn staleness(state: &AppState, quality: &TimeQuality) -> (Option<u64>, bool) {
+    let staleness_secs = quality.staleness_ms.map(|ms| ms / 1000);
+    let stale = quality.source != "manual"
+        && (state.is_clock_jump_detected()
+            || staleness_secs.is_none_or(|s| s > state.config.ntp.max_staleness_secs));
+    (staleness_secs, stale)
+}
+
+/// `{server, offset_ms, rtt_ms, synced_at_ms, age_ms}` of the sync the time
+/// base was last updated from, or `null` before the first one.
+pub(crate) fn last_sync_json(state: &AppState) -> Value {
+    state.timebase.last_sync().map_or(Value::Null, |sync| {
+        json!({
+            "server": sync.server,
+            "offset_ms": sync.offset_ms,
+            "rtt_ms": sync.rtt_ms,
+            "synced_at_ms": sync.synced_at_ms,
+            "age_ms": sync.age_ms(),
+        })
+    })
+}
+
+/// Start a 200 OK response carrying the `X-Time-*` quality headers, plus
+/// the served time itself as `X-NTP-Epoch-Ms` and `Date` so header-only
+/// clients (`HEAD /time`) never need to parse a body.
+fn quality_response_builder(
+    quality: &TimeQuality,
+    epoch_ms: i64,
+    content_type: &'static str,
+) -> axum::http::response::Builder {
     let mut builder = axum::response::Response::builder()
         .status(StatusCode::OK)
-        .header("content-type", "application/json")
+        .header("content-type", content_type)
+        .header("vary", "accept")
+        .header("x-ntp-epoch-ms", epoch_ms.to_string())
+        .header(header::DATE, http_date(epoch_ms))
         .header("x-time-source", quality.source)
         .header("x-time-serve-state", quality.serve_state);
 
@@ -131,13 +730,134 @@ fn build_system_clock_response(state: &AppState, quality: &TimeQuality) -> Respo
     if let Some(ref srv) = quality.selected_server {
         builder = builder.header("x-time-selected-server", srv.as_str());
     }
+    builder
+}
+
+/// RFC 9110 IMF-fixdate (`Mon, 15 Jan 2024 12:00:00 GMT`).  Set by the
+/// handler, hyper leaves it alone instead of stamping the host clock.
+fn http_date(epoch_ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(epoch_ms)
+        .unwrap_or_default()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// Build the 200 OK response for the synced path. Uses the
+/// pre-serialized JSON cache (zero-copy via `Arc<String>`) so the
+/// hot path stays fast. Appends quality headers without touching the body.
+/// Requests with body extensions bypass the cache and serialize their own body.
+/// Binary encodings come from their own `TimeCache` slots.
+fn build_time_response(
+    state: &AppState,
+    epoch_ns: i64,
+    quality: &TimeQuality,
+    extras: Option<&TimeExtras>,
+    encoding: Encoding,
+) -> Response {
+    let epoch_ms = epoch_ns.div_euclid(NANOS_PER_MS);
+    let is_stale = quality.serve_state != "ok";
+
+    if let Some(extras) = extras {
+        let message = if is_stale {
+            &state.config.messages.ok_cache
+        } else {
+            &state.config.messages.ok
+        };
+        let body = time_body_with_extras(state, message, epoch_ns, quality, extras);
+        let (bytes, content_type) = encode_extras(&body, extras, encoding);
+        return quality_response_builder(quality, epoch_ms, content_type)
+            .body(axum::body::Body::from(bytes))
+            .expect("failed to build /time response");
+    }
+
+    let builder = quality_response_builder(quality, epoch_ms, encoding.content_type());
+    if encoding == Encoding::Text {
+        return builder
+            .body(axum::body::Body::from(epoch_ms.to_string()))
+            .expect("failed to build /time response");
+    }
+    if encoding != Encoding::Json {
+        let body = state.time_cache.get_encoded(encoding, epoch_ms, is_stale);
+        return builder
+            .body(axum::body::Body::from((*body).clone()))
+            .expect("failed to build /time response");
+    }
+
+    // PERFORMANCE: Update cache with current time, then get
+    // pre-serialized JSON. This avoids json!() macro and serde
+    // overhead on the hot path.
+    state.time_cache.update(epoch_ms, is_stale);
+    let json_body = state.time_cache.get_json(is_stale);
 
-    let body_bytes = serde_json::to_vec(&body).expect("json serialization");
     builder
+        .body(axum::body::Body::from((*json_body).clone()))
+        .expect("failed to build /time response")
+}
+
+/// Serialize a `/time` body built from extensions, as JSONP when a callback
+/// was given.  Returns the bytes and their content type.
+fn encode_extras(body: &Value, extras: &TimeExtras, encoding: Encoding) -> (Vec<u8>, &'static str) {
+    match &extras.callback {
+        // The leading comment defuses content-sniffing attacks that treat
+        // a response starting with attacker-chosen bytes as another type.
+        Some(callback) => (
+            format!("/**/{callback}({body});").into_bytes(),
+            "application/javascript; charset=utf-8",
+        ),
+        None => (encoding.encode(body), encoding.content_type()),
+    }
+}
+
+/// Build the 200 OK response for the `REQUIRE_SYNC=false` fallback,
+/// where the service reports the OS wall clock instead of the
+/// NTP-derived time. Defeats the "NTP-authoritative" design but
+/// useful for development; never enabled in production.
+fn build_system_clock_response(
+    state: &AppState,
+    quality: &TimeQuality,
+    extras: Option<&TimeExtras>,
+    encoding: Encoding,
+) -> Response {
+    let epoch_ns = system_clock_ns();
+    let epoch_ms = epoch_ns.div_euclid(NANOS_PER_MS);
+
+    let message = &state.config.messages.ok;
+    let (body_bytes, content_type) = match extras {
+        Some(extras) => encode_extras(
+            &time_body_with_extras(state, message, epoch_ns, quality, extras),
+            extras,
+            encoding,
+        ),
+        None if encoding == Encoding::Text => {
+            (epoch_ms.to_string().into_bytes(), encoding.content_type())
+        }
+        None if encoding == Encoding::Protobuf => (
+            protobuf::time_response(epoch_ms, message, 200),
+            encoding.content_type(),
+        ),
+        None => (
+            encoding.encode(&json!({
+                "message": message,
+                "status": 200,
+                "data": epoch_json(epoch_ms, state.config.http.epoch_as_string),
+            })),
+            encoding.content_type(),
+        ),
+    };
+
+    quality_response_builder(quality, epoch_ms, content_type)
         .body(axum::body::Body::from(body_bytes))
         .expect("failed to build system-clock response")
 }
 
+/// The OS wall clock in epoch nanoseconds (0 before the epoch).
+fn system_clock_ns() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or(0)
+}
+
 /// GET /healthz - Liveness probe
 pub async fn healthz_handler() -> (StatusCode, Json<Value>) {
     (
@@ -148,12 +868,175 @@ pub async fn healthz_handler() -> (StatusCode, Json<Value>) {
     )
 }
 
+/// GET /health/deep - End-to-end NTP capability check.
+///
+/// Unlike `/healthz` (process alive) and `/readyz` (current sync state), this
+/// resolves one upstream server and completes a live NTP exchange with it,
+/// exercising DNS, UDP egress and reply parsing.  Each stage is bounded by
+/// `NTP_TIMEOUT`.  A result is reused for `DEEP_HEALTH_MIN_INTERVAL_SECS` so
+/// frequent callers cannot turn the endpoint into upstream load.
+/// 200 when every stage passes, 503 otherwise.
+pub async fn deep_health_handler(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let min_interval =
+        std::time::Duration::from_secs(state.config.quality.deep_health_min_interval_secs);
+    let mut last = state.last_deep_health.lock().await;
+    let (result, cached) = match last.as_ref() {
+        Some(previous) if previous.checked_at.elapsed() < min_interval => (previous.clone(), true),
+        _ => {
+            let fresh = run_deep_health_probe(&state).await;
+            *last = Some(fresh.clone());
+            (fresh, false)
+        }
+    };
+    drop(last);
+
+    let mut body = result.body;
+    body["cached"] = json!(cached);
+    body["checked_ms_ago"] = json!(result.checked_at.elapsed().as_millis() as u64);
+    let status = if result.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(body))
+}
+
+/// Upstream for the deep probe: the currently selected server, else one that
+/// answered the last sync, else the first configured, skipping any a
+/// Kiss-o'-Death reply told us to stop querying.  `Err` names the first
+/// candidate when every one is suppressed.
+async fn deep_probe_server(state: &AppState) -> Option<Result<String, String>> {
+    let mut candidates = Vec::new();
+    if let Some(quality) = state.last_sync_quality.read().as_ref() {
+        candidates.push(quality.selected_server.clone());
+    }
+    {
+        let exchanges = state.last_ntp_exchanges.read();
+        let servers = &state.config.ntp.servers;
+        let (answered, rest): (Vec<_>, Vec<_>) = servers
+            .iter()
+            .map(|server| server.address.clone())
+            .partition(|address| exchanges.contains_key(address));
+        candidates.extend(answered);
+        candidates.extend(rest);
+    }
+    let first = candidates.first()?.clone();
+    for server in candidates {
+        let suppressed = match &state.ntp_syncer {
+            Some(syncer) => syncer.is_suppressed(&server).await,
+            None => false,
+        };
+        if !suppressed {
+            return Some(Ok(server));
+        }
+    }
+    Some(Err(first))
+}
+
+/// Run the DNS and NTP-exchange stages against [`deep_probe_server`].
+async fn run_deep_health_probe(state: &AppState) -> DeepHealthResult {
+    let checked_at = Instant::now();
+    let server = match deep_probe_server(state).await {
+        Some(Ok(server)) => server,
+        Some(Err(server)) => {
+            return DeepHealthResult {
+                checked_at,
+                healthy: false,
+                body: json!({
+                    "status": "fail",
+                    "server": server,
+                    "suppressed": true,
+                    "error": "all NTP servers are suppressed by Kiss-o'-Death replies",
+                }),
+            };
+        }
+        None => {
+            return DeepHealthResult {
+                checked_at,
+                healthy: false,
+                body: json!({ "status": "fail", "error": "no NTP servers configured" }),
+            };
+        }
+    };
+    let timeout = std::time::Duration::from_millis(state.config.ntp.timeout_ms);
+
+    let dns_start = Instant::now();
+    let dns = match tokio::time::timeout(timeout, tokio::net::lookup_host(nts::dns_target(&server)))
+        .await
+    {
+        Ok(Ok(addrs)) => match addrs.count() {
+            0 => Err("no addresses resolved".to_string()),
+            n => Ok(n),
+        },
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("DNS resolution timed out".to_string()),
+    };
+    let dns_check = json!({
+        "ok": dns.is_ok(),
+        "duration_ms": dns_start.elapsed().as_millis() as u64,
+        "addresses": dns.as_ref().ok(),
+        "error": dns.as_ref().err(),
+    });
+
+    // The exchange covers UDP send/receive plus reply parsing and validation;
+    // a failure's error names the step that failed.
+    let exchange_check = if dns.is_ok() {
+        let exchange_start = Instant::now();
+        let result = tokio::time::timeout(timeout, state.ntp_client.query(&server, timeout)).await;
+        let duration_ms = exchange_start.elapsed().as_millis() as u64;
+        match result {
+            Ok(Ok(sample)) => json!({
+                "ok": true,
+                "duration_ms": duration_ms,
+                "rtt_ms": sample.delay_ms,
+                "offset_ms": sample.offset_ms,
+                "stratum": sample.stratum,
+                "leap": sample.leap,
+            }),
+            Ok(Err(e)) => {
+                json!({ "ok": false, "duration_ms": duration_ms, "error": format!("{e:#}") })
+            }
+            Err(_) => {
+                json!({ "ok": false, "duration_ms": duration_ms, "error": "NTP query timed out" })
+            }
+        }
+    } else {
+        json!({ "ok": false, "skipped": true })
+    };
+
+    let healthy = exchange_check["ok"] == true;
+    DeepHealthResult {
+        checked_at,
+        healthy,
+        body: json!({
+            "status": if healthy { "ok" } else { "fail" },
+            "server": server,
+            "duration_ms": checked_at.elapsed().as_millis() as u64,
+            "checks": {
+                "dns": dns_check,
+                "ntp_exchange": exchange_check,
+            },
+        }),
+    }
+}
+
 /// GET /readyz - Readiness probe
 ///
 /// Returns 503 before first sync (if `REQUIRE_SYNC=true`). After first sync,
 /// also returns 503 if `uncertainty > READINESS_MAX_UNCERTAINTY_MS` — a synced
-/// but high-uncertainty pod should not receive traffic.
+/// but high-uncertainty pod should not receive traffic.  Once shutdown has
+/// begun it always returns 503, so traffic moves away while requests drain.
 pub async fn readyz_handler(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    if state.is_shutting_down() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "status": "not_ready",
+                "reason": "shutting_down"
+            })),
+        );
+    }
+
     if state.config.ntp.require_sync && !state.timebase.has_synced() {
         return (
             StatusCode::SERVICE_UNAVAILABLE,
@@ -279,12 +1162,13 @@ pub async fn time_full_handler(State(state): State<Arc<AppState>>) -> (StatusCod
         Json(json!({
             "message": message,
             "status": status_code.as_u16(),
-            "data": epoch_ms,
+            "data": epoch_json(epoch_ms, state.config.http.epoch_as_string),
             "replica_id": state.config.replica.replica_id,
             "source": quality.source,
             "serve_state": quality.serve_state,
             "uncertainty_ms": quality.uncertainty_ms,
             "staleness_ms": quality.staleness_ms,
+            "age_ms": quality.staleness_ms,
             "stratum": quality.stratum,
             "selected_server": quality.selected_server,
             "selected_provider": selected_provider,
@@ -296,13 +1180,169 @@ pub async fn time_full_handler(State(state): State<Arc<AppState>>) -> (StatusCod
     )
 }
 
+/// Epoch ms that timezone computations are evaluated at: NTP time, or the
+/// host clock before the first sync when `REQUIRE_SYNC=false`.
+fn timezone_reference_ms(state: &AppState) -> Result<i64, AppError> {
+    match state.timebase.now_ms() {
+        Some(ms) => Ok(ms),
+        None if state.config.ntp.require_sync => Err(AppError::NotSynced {
+            message: state.config.messages.error.clone(),
+            error: state.config.messages.error_no_sync.clone(),
+        }),
+        None => Ok(std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0)),
+    }
+}
+
+/// GET /timezones - Every IANA zone with its current UTC offset and next
+/// offset transition, evaluated at NTP time.
+pub async fn timezones_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Value>, AppError> {
+    let epoch_ms = timezone_reference_ms(&state)?;
+    let zones: Vec<ZoneInfo> = timezone::all_zones()
+        .iter()
+        .map(|tz| timezone::zone_info(*tz, epoch_ms))
+        .collect();
+
+    Ok(Json(json!({
+        "epoch_ms": epoch_ms,
+        "count": zones.len(),
+        "zones": zones,
+    })))
+}
+
+/// GET /timezones/{zone} - One IANA zone (e.g. `/timezones/Asia/Tehran`)
+/// with its local time, current UTC offset and next transition.  Unknown
+/// zones return 404.
+pub async fn timezone_handler(
+    State(state): State<Arc<AppState>>,
+    Path(zone): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    let tz = timezone::parse_zone(&zone).ok_or(AppError::UnknownTimezone { zone })?;
+    let epoch_ms = timezone_reference_ms(&state)?;
+    let info = timezone::zone_info(tz, epoch_ms);
+    let local_time = timezone::utc_datetime(epoch_ms)
+        .with_timezone(&tz)
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, false);
+
+    Ok(Json(json!({
+        "epoch_ms": epoch_ms,
+        "local_time": local_time,
+        "zone": info.zone,
+        "utc_offset_seconds": info.utc_offset_seconds,
+        "abbreviation": info.abbreviation,
+        "is_dst": info.is_dst,
+        "next_transition": info.next_transition,
+    })))
+}
+
+/// GET /api/timezone - Every zone name, as worldtimeapi.org lists them.
+pub async fn world_time_zones_handler() -> Json<Vec<&'static str>> {
+    Json(timezone::all_zones().iter().map(|tz| tz.name()).collect())
+}
+
+/// GET /api/timezone/{area}/{location} - worldtimeapi.org-compatible body
+/// for one zone, evaluated at NTP time.  A bare area (`/api/timezone/Europe`)
+/// lists its zones instead; unknown names return 404.
+pub async fn world_time_handler(
+    State(state): State<Arc<AppState>>,
+    Path(zone): Path<String>,
+    extensions: axum::http::Extensions,
+) -> Result<Json<Value>, AppError> {
+    let Some(tz) = timezone::parse_zone(&zone) else {
+        let prefix = format!("{zone}/");
+        let area: Vec<&str> = timezone::all_zones()
+            .iter()
+            .map(|tz| tz.name())
+            .filter(|name| name.starts_with(&prefix))
+            .collect();
+        if area.is_empty() {
+            return Err(AppError::UnknownTimezone { zone });
+        }
+        return Ok(Json(json!(area)));
+    };
+    let epoch_ms = timezone_reference_ms(&state)?;
+    let mut body = json!(timezone::world_time(tz, epoch_ms));
+    let client_ip = extensions
+        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        .map(|info| info.0.ip().to_string());
+    body["client_ip"] = json!(client_ip);
+    Ok(Json(body))
+}
+
+/// `/convert` query parameters.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ConvertQuery {
+    pub value: Option<String>,
+    /// Input representation; defaults to `unix_ms`.
+    pub from: Option<String>,
+    /// Output representation; defaults to `iso8601`, or to the pattern
+    /// when `pattern`/`locale` is given.
+    pub to: Option<String>,
+    /// IANA zone for string output and zone-less ISO 8601 input; defaults to UTC.
+    pub tz: Option<String>,
+    pub pattern: Option<String>,
+    pub locale: Option<String>,
+}
+
+/// GET /convert - Convert `value` between epoch units, ISO 8601, RFC 2822
+/// and HTTP dates, or render it with a `/time`-style `pattern`.  Pure
+/// arithmetic: it works before the first sync.
+pub async fn convert_handler(Query(query): Query<ConvertQuery>) -> Result<Json<Value>, AppError> {
+    let bad_request = |error: String| AppError::BadRequest { error };
+    let value = query
+        .value
+        .as_deref()
+        .ok_or_else(|| bad_request("value is required".into()))?;
+    let from =
+        Representation::parse(query.from.as_deref().unwrap_or("unix_ms")).map_err(bad_request)?;
+    let tz = match query.tz.as_deref() {
+        Some(name) => timezone::parse_zone(name)
+            .ok_or_else(|| bad_request(format!("Unknown timezone: {name}")))?,
+        None => Tz::UTC,
+    };
+    let target = match (
+        query.to.as_deref(),
+        query.pattern.is_some() || query.locale.is_some(),
+    ) {
+        (Some(_), true) => {
+            return Err(bad_request(
+                "to cannot be combined with pattern or locale".into(),
+            ));
+        }
+        (_, true) => Target::Pattern(
+            format::TimeFormat::new(query.pattern.as_deref(), query.locale.as_deref())
+                .map_err(bad_request)?,
+        ),
+        (to, false) => Target::Representation(
+            Representation::parse(to.unwrap_or("iso8601")).map_err(bad_request)?,
+        ),
+    };
+    let result = convert::convert(value, from, &target, tz).map_err(bad_request)?;
+
+    Ok(Json(json!({
+        "value": value,
+        "from": from.name(),
+        "to": match &target {
+            Target::Representation(to) => to.name(),
+            Target::Pattern(_) => "pattern",
+        },
+        "tz": tz.name(),
+        "result": result,
+    })))
+}
+
 /// GET /status - Operational quality envelope.
 ///
-/// Always returns 200. The `serve_state` field communicates whether the
+/// JSON by default; MessagePack, CBOR or a protobuf `google.protobuf.Struct`
+/// when the `Accept` header asks for one.  Always returns 200. The `serve_state` field communicates whether the
 /// service is currently healthy, degraded, or would stop serving `/time`.
 /// Callers that need to gate on time quality should read `serve_state`
 /// rather than checking the HTTP status code.
-pub async fn status_handler(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+pub async fn status_handler(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
     let quality = state.compute_quality();
     let ntp_synced = state.timebase.has_synced();
 
@@ -316,10 +1356,44 @@ pub async fn status_handler(State(state): State<Arc<AppState>>) -> (StatusCode,
     let selection_state = quality.selection.as_ref().map(|s| json!(s.selection_state));
     // P1F-12: intersection diagnostics
     let intersection = quality.selection.as_ref().map(|s| json!(&s.intersection));
+    let path_asymmetry = state.config.ntp.calibration.asymmetry_enabled.then(|| {
+        let estimates = state.path_asymmetry.read();
+        json!(
+            estimates
+                .iter()
+                .collect::<std::collections::BTreeMap<_, _>>()
+        )
+    });
+    // Wall-clock time of the last successful sync, on the NTP time base.
+    let last_sync_ms = state
+        .timebase
+        .ntp_base_now_ms()
+        .zip(*state.last_sync_time.read())
+        .map(|(now_ms, at)| now_ms - at.elapsed().as_millis() as i64);
+    let drift_ppm = state
+        .config
+        .ntp
+        .drift_compensation
+        .then(|| state.timebase.drift_ppm());
+    let ntp = &state.config.ntp;
+    let servers = match &state.ntp_syncer {
+        Some(syncer) => syncer.servers(),
+        None => ntp.servers.iter().map(|s| s.address.clone()).collect(),
+    };
+    let config_summary = json!({
+        "servers": servers,
+        "sync_interval_secs": ntp.sync_interval_secs,
+        "sync_interval_max_secs": ntp.sync_interval_max_secs,
+        "max_staleness_secs": ntp.max_staleness_secs,
+        "require_sync": ntp.require_sync,
+        "selection_strategy": ntp.selection.strategy,
+        "min_quorum": ntp.selection.min_quorum,
+        "monotonic_output": ntp.monotonic_output,
+        "drift_compensation": ntp.drift_compensation,
+        "clock_filter": ntp.query.clock_filter,
+    });
 
-    (
-        StatusCode::OK,
-        Json(json!({
+    let body = json!({
             "replica_id": state.config.replica.replica_id,
             "source": quality.source,
             "serve_state": quality.serve_state,
@@ -333,9 +1407,128 @@ pub async fn status_handler(State(state): State<Arc<AppState>>) -> (StatusCode,
             "selection_state": selection_state,
             "leap": quality.leap,
             "ntp_synced": ntp_synced,
+            "last_sync_ms": last_sync_ms,
+            "last_sync": last_sync_json(&state),
+            "staleness_secs": state.get_staleness_seconds(),
+            "consecutive_failures": state.get_consecutive_failures(),
+            "drift_ppm": drift_ppm,
             "override_info": quality.override_info,
             "selection": quality.selection,
             "intersection": intersection,
+            "quality_score": quality.score,
+            "system_clock_divergence_ms": *state.system_clock_divergence_ms.read(),
+            "system_clock_alert": state.system_clock_alert.load(std::sync::atomic::Ordering::Acquire),
+            "clock_jump_detected": state.is_clock_jump_detected(),
+            "roughtime_alert": !state.roughtime_alerting.lock().is_empty(),
+            "path_asymmetry": path_asymmetry,
+            "config": config_summary,
+    });
+    // Only time endpoints have a plain-text form.
+    let encoding = match accepted_encoding(&headers) {
+        Encoding::Text => Encoding::Json,
+        encoding => encoding,
+    };
+    (
+        [
+            (header::CONTENT_TYPE, encoding.content_type()),
+            (header::VARY, "accept"),
+        ],
+        encoding.encode(&body),
+    )
+        .into_response()
+}
+
+/// GET /report/clock-drift - Host-clock divergence over the shadow window.
+///
+/// Min/max/mean and p50/p90/p99 of host − NTP time (signed and absolute)
+/// across the samples taken by the system-clock check during the last
+/// `SYSTEM_CLOCK_SHADOW_WINDOW_SECS`.  Before the first sync there is no NTP
+/// time to anchor the window and the report is empty.
+pub async fn clock_drift_report_handler(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let now_ms = state.timebase.ntp_base_now_ms().unwrap_or_default();
+    let report = state
+        .clock_drift
+        .lock()
+        .report(now_ms, state.config.quality.system_clock_alert_ms);
+    (StatusCode::OK, Json(json!(report)))
+}
+
+/// GET /debug/ntp - Raw fields of the most recent NTP exchange per server.
+///
+/// Packet-level view (all four timestamps, stratum, poll, precision, root
+/// delay/dispersion, leap indicator) for debugging offset anomalies without
+/// shelling into the host.  Servers that have never answered are omitted.
+/// Behind the metrics credential when `METRICS_AUTH_*` is configured.
+pub async fn debug_ntp_handler(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let exchanges = state.last_ntp_exchanges.read();
+    let mut servers: Vec<&String> = exchanges.keys().collect();
+    servers.sort();
+
+    let servers: Vec<Value> = servers
+        .into_iter()
+        .map(|server| {
+            let s = &exchanges[server];
+            json!({
+                "server": server,
+                "age_ms": s.t4_instant.elapsed().as_millis() as u64,
+                "t1_client_send_ms": s.t1_unix_ms,
+                "t2_server_recv_ms": s.t2_unix_ms,
+                "t3_server_send_ms": s.t3_unix_ms,
+                "t4_client_recv_ms": s.t4_unix_ms,
+                "offset_ms": s.offset_ms,
+                "delay_ms": s.delay_ms,
+                "stratum": s.stratum,
+                "poll": s.poll,
+                "precision_log2": s.precision_log2,
+                "root_delay_ms": s.root_delay_ms,
+                "root_dispersion_ms": s.root_dispersion_ms,
+                "leap": s.leap,
+                "reference_id": s.reference_id,
+            })
+        })
+        .collect();
+
+    (StatusCode::OK, Json(json!({ "servers": servers })))
+}
+
+/// GET /debug/selection - Scoring breakdown of the most recent selection round.
+///
+/// Per-candidate offset, RTT, root distance, weight, distance from the
+/// weighted median and inlier/outlier/falseticker status, plus hard-gate
+/// rejections and the sticky-server decision, so the selector's reasoning
+/// can be read in one place instead of reassembled from log lines.
+/// `selection` is `null` until the first sync attempt.  Behind the metrics
+/// credential when `METRICS_AUTH_*` is configured.
+pub async fn debug_selection_handler(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, Json<Value>) {
+    let Some(diag) = state.last_selection_diagnostics.read().clone() else {
+        return (StatusCode::OK, Json(json!({ "selection": null })));
+    };
+
+    let mut candidates = diag.candidates;
+    candidates.sort_by(|a, b| a.server.cmp(&b.server));
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "selection": {
+                "selection_state": diag.selection_state,
+                "selected_server": diag.selected_server,
+                "weighted_median_offset_ms": diag.weighted_median_offset_ms,
+                "max_offset_skew_ms": state.config.ntp.selection.max_offset_skew_ms,
+                "max_root_distance_ms": diag.max_root_distance_ms,
+                "quorum_size": diag.quorum_size,
+                "min_quorum": diag.min_quorum,
+                "single_provider": diag.single_provider,
+                "combined_uncertainty_ms": diag.combined_uncertainty_ms,
+                "candidates": candidates,
+                "rejected": diag.rejected_sources,
+                "intersection": diag.intersection,
+                "sticky": diag.sticky,
+            }
         })),
     )
 }
@@ -378,6 +1571,11 @@ pub async fn performance_handler(State(state): State<Arc<AppState>>) -> (StatusC
         0.0
     };
 
+    let stream = &perf.stream;
+    let relaxed = std::sync::atomic::Ordering::Relaxed;
+    let stream_avg_latency_us = stream.avg_send_latency_us();
+    let stream_max_latency_us = stream.max_send_latency_us.load(relaxed);
+
     let ntp_timing = state.last_ntp_timing.read().clone().map(|t| {
         use crate::ntp::selection::TimingSource;
         let timing_source = match t.timing_source {
@@ -429,6 +1627,17 @@ pub async fn performance_handler(State(state): State<Arc<AppState>>) -> (StatusC
                 "rates": {
                     "error_rate": format!("{:.4}", error_rate),
                 },
+                "streams": {
+                    "active_connections": stream.active_connections.load(relaxed),
+                    "ticks_sent": stream.ticks_sent.load(relaxed),
+                    "ticks_dropped": stream.ticks_dropped.load(relaxed),
+                    "queue_depth": stream.queue_depth.load(relaxed),
+                    "max_queue_depth": stream.max_queue_depth.load(relaxed),
+                    "send_latency_milliseconds": {
+                        "avg": format!("{:.3}", stream_avg_latency_us / 1000.0),
+                        "max": format!("{:.3}", stream_max_latency_us as f64 / 1000.0),
+                    },
+                },
             },
             "ntp_timing": ntp_timing,
         })),
@@ -450,10 +1659,10 @@ mod tests {
     fn create_test_state_with_config(config: Arc<Config>) -> Arc<AppState> {
         use crate::performance::{LockFreeMetrics, TimeCache};
 
-        let time_cache = Arc::new(TimeCache::new(
-            config.messages.ok.clone(),
-            config.messages.ok_cache.clone(),
-        ));
+        let time_cache = Arc::new(
+            TimeCache::new(config.messages.ok.clone(), config.messages.ok_cache.clone())
+                .with_epoch_as_string(config.http.epoch_as_string),
+        );
         let perf_metrics = Arc::new(LockFreeMetrics::new());
         let timebase = TimeBase::new(config.ntp.require_sync).with_cache(time_cache.clone());
         let metrics = Arc::new(Metrics::new());
@@ -472,10 +1681,92 @@ mod tests {
         assert_eq!(status, StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn test_deep_health_reports_failed_exchange_and_caches() {
+        use crate::ntp::client::MockNtpClient;
+
+        let mut config = Config::default();
+        config.ntp.servers = vec![crate::config::ServerConfig::new("127.0.0.1:123")];
+        let state = Arc::unwrap_or_clone(create_test_state_with_config(Arc::new(config)))
+            .with_ntp_client(Arc::new(MockNtpClient::err(
+                "Failed to receive NTP response",
+            )));
+        let state = Arc::new(state);
+
+        let (status, Json(body)) = deep_health_handler(State(state.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["server"], "127.0.0.1:123");
+        assert_eq!(body["checks"]["dns"]["ok"], true);
+        assert_eq!(body["checks"]["ntp_exchange"]["ok"], false);
+        assert_eq!(body["cached"], false);
+
+        // Within DEEP_HEALTH_MIN_INTERVAL_SECS the previous result is reused
+        let (status, Json(body)) = deep_health_handler(State(state)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["cached"], true);
+    }
+
+    #[tokio::test]
+    async fn test_deep_health_skips_servers_suppressed_by_kod() {
+        use crate::ntp::NtpSyncer;
+        use crate::ntp::client::{KissOfDeath, MockNtpClient, NtpClient, NtpSample};
+
+        struct Deny;
+
+        #[async_trait::async_trait]
+        impl NtpClient for Deny {
+            async fn query(
+                &self,
+                _server: &str,
+                _timeout: std::time::Duration,
+            ) -> anyhow::Result<NtpSample> {
+                Err(KissOfDeath {
+                    reference_id: u32::from_be_bytes(*b"DENY"),
+                }
+                .into())
+            }
+        }
+
+        let mut config = Config::default();
+        config.ntp.servers = vec![crate::config::ServerConfig::new("127.0.0.1:123")];
+        let syncer = Arc::new(NtpSyncer::with_client(
+            Arc::new(config.ntp.clone()),
+            Arc::new(Deny),
+        ));
+        assert!(syncer.sync().await.is_err());
+
+        // The only server denied us: report it without querying
+        let state = Arc::unwrap_or_clone(create_test_state_with_config(Arc::new(config.clone())))
+            .with_ntp_syncer(syncer.clone())
+            .with_ntp_client(Arc::new(MockNtpClient::err("must not be queried")));
+        let (status, Json(body)) = deep_health_handler(State(Arc::new(state))).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["server"], "127.0.0.1:123");
+        assert_eq!(body["suppressed"], true);
+        assert!(body.get("checks").is_none());
+
+        // With another server configured the probe moves on to it
+        config
+            .ntp
+            .servers
+            .push(crate::config::ServerConfig::new("127.0.0.2:123"));
+        let state = Arc::unwrap_or_clone(create_test_state_with_config(Arc::new(config)))
+            .with_ntp_syncer(syncer)
+            .with_ntp_client(Arc::new(MockNtpClient::err("unreachable")));
+        let (_, Json(body)) = deep_health_handler(State(Arc::new(state))).await;
+        assert_eq!(body["server"], "127.0.0.2:123");
+        assert_eq!(body["checks"]["ntp_exchange"]["ok"], false);
+    }
+
     #[tokio::test]
     async fn test_time_before_sync() {
         let state = create_test_state();
-        let result = time_handler(State(state.clone())).await;
+        let result = time_handler(
+            State(state.clone()),
+            Query(TimeQuery::default()),
+            HeaderMap::new(),
+        )
+        .await;
 
         if state.config.ntp.require_sync {
             // The handler should return Err(NotSynced) which
@@ -501,6 +1792,22 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_readyz_fails_once_shutting_down() {
+        let mut config = Config::default();
+        config.ntp.require_sync = false;
+        let state = create_test_state_with_config(Arc::new(config));
+        let (status, _) = readyz_handler(State(state.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+
+        state.fail_readiness();
+        let (status, Json(body)) = readyz_handler(State(state.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["reason"], "shutting_down");
+        // Streams stay open until the listeners close
+        assert!(!*state.shutdown_signal.borrow());
+    }
+
     #[tokio::test]
     async fn test_metrics() {
         let state = create_test_state();
@@ -520,7 +1827,7 @@ mod tests {
         // TimeBase is unsynced (no update() called).
         assert!(!state.timebase.has_synced());
 
-        let response = time_handler(State(state))
+        let response = time_handler(State(state), Query(TimeQuery::default()), HeaderMap::new())
             .await
             .expect("expected Ok when REQUIRE_SYNC=false");
 
@@ -537,7 +1844,9 @@ mod tests {
         config.ntp.require_sync = false;
         let state = create_test_state_with_config(Arc::new(config));
 
-        let response = time_handler(State(state)).await.expect("expected Ok");
+        let response = time_handler(State(state), Query(TimeQuery::default()), HeaderMap::new())
+            .await
+            .expect("expected Ok");
 
         let bytes = to_bytes(response.into_body(), 512).await.unwrap();
         let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
@@ -550,6 +1859,332 @@ mod tests {
         assert!(json["message"].is_string());
     }
 
+    #[tokio::test]
+    async fn test_time_tz_list_localizes_each_zone() {
+        use axum::body::to_bytes;
+
+        let state = create_test_state();
+        state.timebase.set_manual(1_705_320_000_000, 60); // 2024-01-15T12:00:00Z
+
+        let query = TimeQuery {
+            tz: Some("UTC,Asia/Tehran,America/New_York".into()),
+            ..TimeQuery::default()
+        };
+        let response = time_handler(State(state.clone()), Query(query), HeaderMap::new())
+            .await
+            .expect("expected Ok");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key("x-time-source"));
+
+        let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let zones = json["timezones"].as_array().unwrap();
+        assert_eq!(zones.len(), 3);
+        assert_eq!(zones[1]["zone"], "Asia/Tehran");
+        assert_eq!(zones[1]["utc_offset_seconds"], 12_600);
+        assert_eq!(zones[2]["abbreviation"], "EST");
+        assert!(json["data"].as_i64().unwrap() >= 1_705_320_000_000);
+
+        let bad = TimeQuery {
+            tz: Some("UTC,Nowhere/City".into()),
+            ..TimeQuery::default()
+        };
+        let err = time_handler(State(state), Query(bad), HeaderMap::new())
+            .await
+            .expect_err("unknown zone must be rejected");
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_time_pattern_formats_utc_and_zones() {
+        use axum::body::to_bytes;
+
+        let state = create_test_state();
+        state.timebase.set_manual(1_705_320_000_000, 60); // 2024-01-15T12:00:00Z
+
+        let query = TimeQuery {
+            tz: Some("Asia/Tehran".into()),
+            pattern: Some("%Y-%m-%d %H:%M".into()),
+            ..TimeQuery::default()
+        };
+        let response = time_handler(State(state.clone()), Query(query), HeaderMap::new())
+            .await
+            .expect("expected Ok");
+        let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["formatted"], "2024-01-15 12:00");
+        assert_eq!(json["timezones"][0]["formatted"], "2024-01-15 15:30");
+
+        let bad = TimeQuery {
+            pattern: Some("%Y %n".into()),
+            ..TimeQuery::default()
+        };
+        let err = time_handler(State(state), Query(bad), HeaderMap::new())
+            .await
+            .expect_err("unlisted specifier must be rejected");
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_time_locale_localizes_formatted_fields() {
+        use axum::body::to_bytes;
+
+        let state = create_test_state();
+        state.timebase.set_manual(1_705_320_000_000, 60); // 2024-01-15T12:00:00Z
+
+        let query = TimeQuery {
+            tz: Some("Asia/Tehran".into()),
+            pattern: Some("%B %Y".into()),
+            locale: Some("fa-IR".into()),
+            ..TimeQuery::default()
+        };
+        let response = time_handler(State(state.clone()), Query(query), HeaderMap::new())
+            .await
+            .expect("expected Ok");
+        let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let formatted = json["formatted"].as_str().unwrap();
+        assert!(formatted.ends_with("۲۰۲۴"), "{formatted}");
+        assert!(!formatted.contains("January"));
+        assert!(json["timezones"][0]["formatted"].is_string());
+
+        let bad = TimeQuery {
+            locale: Some("xx-YY".into()),
+            ..TimeQuery::default()
+        };
+        let err = time_handler(State(state), Query(bad), HeaderMap::new())
+            .await
+            .expect_err("unknown locale must be rejected");
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_time_verbose_adds_provenance() {
+        use axum::body::to_bytes;
+
+        let state = create_test_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        inject_sync_quality(&state, 1, 3);
+
+        let query = TimeQuery {
+            verbose: Some("1".into()),
+            ..TimeQuery::default()
+        };
+        let response = time_handler(State(state.clone()), Query(query), HeaderMap::new())
+            .await
+            .expect("expected Ok");
+        let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["stratum"], 2);
+        assert_eq!(json["selected_server"], "ntp.test:123");
+        let age_ms = json["age_ms"].as_u64().unwrap();
+        assert!((3000..4000).contains(&age_ms), "age_ms={age_ms}");
+        assert_eq!(json["staleness_secs"], 3);
+        assert_eq!(json["stale"], false);
+        assert_eq!(json["rtt_ms"], 5);
+        assert_eq!(json["offset_ms"], 1);
+        // A manual override has no NTP error bound.
+        assert!(json["error_bound_ms"].is_null());
+        assert_eq!(json["utc_tai_offset"], 37);
+
+        let bad = TimeQuery {
+            verbose: Some("yes".into()),
+            ..TimeQuery::default()
+        };
+        let err = time_handler(State(state), Query(bad), HeaderMap::new())
+            .await
+            .expect_err("unrecognised verbose value must be rejected");
+        assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_error_bound_matches_uncertainty_and_skips_seeds() {
+        use crate::ntp::SyncResult;
+        use crate::ntp::selection::TimingSource;
+
+        let state = create_test_state();
+        let result = SyncResult {
+            epoch_ms: 1_700_000_000_000,
+            server: "persisted".to_string(),
+            rtt: std::time::Duration::ZERO,
+            instant: Instant::now(),
+            offset_ms: 0,
+            t1_client_send_ms: 0,
+            t2_server_recv_ms: 0,
+            t3_server_send_ms: 0,
+            t4_client_recv_ms: 0,
+            root_delay_ms: 0,
+            root_dispersion_ms: 5,
+            stratum: 2,
+            leap: 0,
+            precision_log2: 0,
+            reference_id: 0,
+            timing_source: TimingSource::Estimated,
+        };
+        // A seed no server has confirmed has no bound, even with quality.
+        state.timebase.seed(&result);
+        inject_sync_quality(&state, 1, 0);
+        assert_eq!(state.compute_quality().error_bound_ms, None);
+
+        state.timebase.update(&SyncResult {
+            server: "ntp.test:123".into(),
+            ..result
+        });
+        let served = served_time(&state).unwrap();
+        assert_eq!(
+            served.quality.error_bound_ms, served.quality.uncertainty_ms,
+            "nothing being slewed in"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_time_verbose_flags_stale_sync() {
+        use axum::body::to_bytes;
+
+        let state = create_test_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let max_staleness = state.config.ntp.max_staleness_secs;
+        inject_sync_quality(&state, 1, max_staleness + 5);
+
+        let query = TimeQuery {
+            verbose: Some("true".into()),
+            ..TimeQuery::default()
+        };
+        let response = time_handler(State(state), Query(query), HeaderMap::new())
+            .await
+            .expect("expected Ok");
+        let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["staleness_secs"], max_staleness + 5);
+        assert_eq!(json["stale"], true);
+        assert_eq!(json["serve_state"], "holdover");
+    }
+
+    #[tokio::test]
+    async fn test_time_epoch_as_string() {
+        use axum::body::to_bytes;
+
+        let mut config = Config::default();
+        config.http.epoch_as_string = true;
+        let state = create_test_state_with_config(Arc::new(config));
+        state.timebase.set_manual(1_705_320_000_000, 60);
+
+        // Cached fast path honours EPOCH_AS_STRING
+        let response = time_handler(
+            State(state.clone()),
+            Query(TimeQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .expect("expected Ok");
+        let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let data: i64 = json["data"].as_str().unwrap().parse().unwrap();
+        assert!(data >= 1_705_320_000_000);
+
+        // The query parameter overrides the config per request
+        let query = TimeQuery {
+            epoch_as_string: Some("0".into()),
+            ..TimeQuery::default()
+        };
+        let response = time_handler(State(state), Query(query), HeaderMap::new())
+            .await
+            .expect("expected Ok");
+        let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(json["data"].is_i64());
+    }
+
+    #[tokio::test]
+    async fn test_time_format_selects_epoch_unit() {
+        use axum::body::to_bytes;
+
+        let state = create_test_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let data_for = |format: &str| {
+            let state = state.clone();
+            let query = TimeQuery {
+                format: Some(format.into()),
+                ..TimeQuery::default()
+            };
+            async move {
+                let response = time_handler(State(state), Query(query), HeaderMap::new()).await?;
+                let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+                let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+                Ok::<_, AppError>(json["data"].as_i64().unwrap())
+            }
+        };
+
+        let ms = data_for("unix_ms").await.unwrap();
+        let us = data_for("unix_us").await.unwrap();
+        let ns = data_for("unix_ns").await.unwrap();
+        assert!((1_705_320_000_000..1_705_320_001_000).contains(&ms));
+        assert!(us >= ms * 1_000 && us < (ms + 1_000) * 1_000);
+        assert!(ns >= us * 1_000 && ns < (ms + 1_000) * NANOS_PER_MS);
+        // TAI runs 37 s ahead of UTC since 2017.
+        let tai = data_for("tai").await.unwrap();
+        assert!((ms + 37_000..ms + 38_000).contains(&tai), "tai={tai}");
+
+        let error = data_for("unix_s").await.unwrap_err();
+        assert!(matches!(error, AppError::BadRequest { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_time_jsonp_callback() {
+        use axum::body::to_bytes;
+
+        let query = |callback: &str| TimeQuery {
+            callback: Some(callback.into()),
+            ..TimeQuery::default()
+        };
+
+        // Off by default.
+        let state = create_test_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let response = time_handler(State(state), Query(query("cb")), HeaderMap::new())
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let mut config = Config::default();
+        config.http.jsonp_enabled = true;
+        let state = create_test_state_with_config(Arc::new(config));
+        state.timebase.set_manual(1_705_320_000_000, 60);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, "application/cbor".parse().unwrap());
+        let response = time_handler(State(state.clone()), Query(query("jQuery.cb_1")), headers)
+            .await
+            .expect("expected Ok");
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/javascript; charset=utf-8"
+        );
+        let bytes = to_bytes(response.into_body(), 4096).await.unwrap();
+        let body = std::str::from_utf8(&bytes).unwrap();
+        let json = body
+            .strip_prefix("/**/jQuery.cb_1(")
+            .and_then(|rest| rest.strip_suffix(");"))
+            .unwrap_or_else(|| panic!("not JSONP: {body}"));
+        let json: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert!(json["data"].as_i64().unwrap() >= 1_705_320_000_000);
+
+        for bad in [
+            "alert(1)//",
+            "1cb",
+            "cb..x",
+            "",
+            &"a".repeat(MAX_CALLBACK_LEN + 1),
+        ] {
+            let response = time_handler(State(state.clone()), Query(query(bad)), HeaderMap::new())
+                .await
+                .unwrap_err()
+                .into_response();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{bad:?}");
+        }
+    }
+
     // ── P0-4: quality policy table ────────────────────────────────────────
 
     fn inject_sync_quality(state: &AppState, upstream_dispersion_ms: u32, age_secs: u64) {
@@ -712,7 +2347,12 @@ mod tests {
         state.timebase.update(&sync_result);
         inject_sync_quality(&state, 100, 0);
 
-        let result = time_handler(State(state.clone())).await;
+        let result = time_handler(
+            State(state.clone()),
+            Query(TimeQuery::default()),
+            HeaderMap::new(),
+        )
+        .await;
         let response = result
             .expect_err("expected ServeStopped error")
             .into_response();
@@ -750,9 +2390,13 @@ mod tests {
         state.timebase.update(&sync_result);
         inject_sync_quality(&state, 1, 0);
 
-        let response = time_handler(State(state.clone()))
-            .await
-            .expect("expected 200");
+        let response = time_handler(
+            State(state.clone()),
+            Query(TimeQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .expect("expected 200");
         assert_eq!(response.status(), StatusCode::OK);
 
         let headers = response.headers();
@@ -813,9 +2457,13 @@ mod tests {
         state.timebase.update(&sync_result);
         inject_sync_quality(&state, 1, 0);
 
-        let response = time_handler(State(state.clone()))
-            .await
-            .expect("expected 200");
+        let response = time_handler(
+            State(state.clone()),
+            Query(TimeQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .expect("expected 200");
         let body = to_bytes(response.into_body(), 256).await.unwrap();
         let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
@@ -869,9 +2517,13 @@ mod tests {
         state.timebase.update(&sync_result);
         inject_sync_quality(&state, 200, 0);
 
-        let response = time_handler(State(state.clone()))
-            .await
-            .expect("expected 200 in default mode even with high uncertainty");
+        let response = time_handler(
+            State(state.clone()),
+            Query(TimeQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .expect("expected 200 in default mode even with high uncertainty");
         assert_eq!(response.status(), StatusCode::OK);
         // serve_state header should be "holdover" not "stopped"
         assert_eq!(response.headers()["x-time-serve-state"], "holdover");
@@ -919,9 +2571,13 @@ mod tests {
         assert!(state.timebase.now_ms().is_some());
 
         // /time should still return 200
-        let response = time_handler(State(state.clone()))
-            .await
-            .expect("expected 200 after failures");
+        let response = time_handler(
+            State(state.clone()),
+            Query(TimeQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .expect("expected 200 after failures");
         assert_eq!(response.status(), StatusCode::OK);
     }
 
@@ -994,7 +2650,9 @@ mod tests {
         assert_eq!(q.serve_state, "holdover");
 
         // /time must return 200 (has_synced=true → now_ms=Some)
-        let response = time_handler(State(state)).await.expect("expected 200");
+        let response = time_handler(State(state), Query(TimeQuery::default()), HeaderMap::new())
+            .await
+            .expect("expected 200");
         assert_eq!(response.status(), StatusCode::OK);
     }
 
@@ -1032,9 +2690,13 @@ mod tests {
 
         // TimeBase is still seeded; /time should return 200
         let state_clone = state.clone();
-        let response = time_handler(State(state_clone))
-            .await
-            .expect("expected 200");
+        let response = time_handler(
+            State(state_clone),
+            Query(TimeQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .expect("expected 200");
         assert_eq!(response.status(), StatusCode::OK);
         assert!(state.timebase.has_synced());
     }
@@ -1,6 +1,12 @@
 use super::state::{AppState, ManualOverrideState};
+use crate::config::ServerConfig;
 use crate::metrics::RejectLabel;
-use axum::{Json, extract::State, http::StatusCode};
+use crate::ntp::sync::{NtpSyncer, ServerSetError};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
 use serde::Deserialize;
 use serde_json::{Value, json};
 use std::sync::Arc;
@@ -193,7 +199,7 @@ pub async fn post_override(
             reference_id: u32::from_be_bytes(*b"MANU"),
             timing_source: TimingSource::Estimated,
         };
-        state.timebase.update(&seed);
+        state.timebase.seed(&seed);
         info!(
             epoch_ms = body.epoch_ms,
             reason = %body.reason,
@@ -239,6 +245,7 @@ pub async fn post_override(
             .metrics
             .manual_override_expiry_timestamp_seconds
             .set(0);
+        state_clone.update_quality_metrics();
         warn!(
             action = "expired",
             epoch_ms = log_epoch,
@@ -262,6 +269,7 @@ pub async fn post_override(
         .manual_override_expiry_timestamp_seconds
         .set(expires_at_ms / 1000);
     state.metrics.time_source_mode.set(3); // manual
+    state.update_quality_metrics();
 
     // Audit log — token is NEVER included in any log field.
     warn!(
@@ -323,6 +331,7 @@ pub async fn delete_override(State(state): State<Arc<AppState>>) -> (StatusCode,
             "manual" => 3,
             _ => 4, // "holdover"
         });
+        state.update_quality_metrics();
 
         if let Some(ov) = prev_state {
             warn!(
@@ -345,3 +354,283 @@ pub async fn delete_override(State(state): State<Arc<AppState>>) -> (StatusCode,
         Json(json!({ "status": 200, "message": "no active override" })),
     )
 }
+
+#[derive(Debug, Deserialize)]
+pub struct AddServerRequest {
+    /// One `NTP_SERVERS` entry, e.g. `ntp.corp.internal;weight=3;priority=1`.
+    pub server: String,
+    pub operator: Option<String>,
+}
+
+/// GET /admin/config
+///
+/// Returns the configuration the process is actually running with, after env
+/// parsing and defaults.  Tokens, credentials and key material are redacted.
+pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<Value> {
+    Json(state.config.redacted_json())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetLogFilterRequest {
+    /// `EnvFilter` directives, e.g. `info,ntp_time_json_api::ntp=debug`.
+    pub filter: String,
+    /// Revert to the previous filter after this many seconds.
+    pub ttl_seconds: Option<u64>,
+    pub operator: Option<String>,
+}
+
+fn log_filter_unavailable() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "status": 503,
+            "error": "LoggingUnavailable",
+            "message": "log filter cannot be changed at runtime"
+        })),
+    )
+}
+
+/// GET /admin/logging
+///
+/// Returns the active tracing filter directives.
+pub async fn get_logging(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    match state.log_filter.as_ref().map(|filter| filter.current()) {
+        Some(Ok(filter)) => (StatusCode::OK, Json(json!({ "filter": filter }))),
+        _ => log_filter_unavailable(),
+    }
+}
+
+/// PUT /admin/logging
+///
+/// Replaces the tracing filter without a restart, e.g. to turn on
+/// `ntp=debug` while investigating a sync problem.  With `ttl_seconds` the
+/// previous filter is restored afterwards, unless it was changed again in
+/// the meantime.  Invalid directives are rejected 400.
+pub async fn put_logging(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<SetLogFilterRequest>,
+) -> (StatusCode, Json<Value>) {
+    let Some(log_filter) = state.log_filter.clone() else {
+        return log_filter_unavailable();
+    };
+    if body.ttl_seconds == Some(0) {
+        return validation_error("ttl_seconds must be greater than 0".to_string());
+    }
+    let (previous, generation) = match log_filter.set(&body.filter) {
+        Ok(change) => change,
+        Err(e) => return validation_error(format!("{e:#}")),
+    };
+    info!(
+        action = "set",
+        filter = %body.filter,
+        previous = %previous,
+        ttl_seconds = ?body.ttl_seconds,
+        operator = ?body.operator,
+        "Log filter changed via admin API"
+    );
+
+    if let Some(ttl) = body.ttl_seconds {
+        let previous = previous.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(ttl)).await;
+            match log_filter.revert(generation, &previous) {
+                Ok(true) => info!(action = "revert", filter = %previous, "Log filter TTL expired"),
+                Ok(false) => {}
+                Err(e) => warn!(error = %e, "Failed to revert log filter"),
+            }
+        });
+    }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "status": 200,
+            "message": "log filter updated",
+            "filter": body.filter,
+            "previous": previous,
+            "ttl_seconds": body.ttl_seconds,
+        })),
+    )
+}
+
+/// POST /admin/sync
+///
+/// Runs an NTP sync now instead of waiting up to `SYNC_INTERVAL`, and answers
+/// once its result has been applied: the selected server, offset and RTT, or
+/// 502 with the error when the sync failed.  Requests arriving while a sync
+/// is pending share its result.
+pub async fn post_sync(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let unavailable = || {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "status": 503,
+                "error": "SyncerUnavailable",
+                "message": "NTP sync loop is not running"
+            })),
+        )
+    };
+    let Some(requests) = state.sync_requests.as_ref() else {
+        return unavailable();
+    };
+    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+    if requests.send(reply_tx).await.is_err() {
+        return unavailable();
+    }
+
+    match reply_rx.await {
+        Ok(Ok(report)) => {
+            info!(
+                action = "sync",
+                server = %report.server,
+                offset_ms = report.offset_ms,
+                rtt_ms = report.rtt_ms,
+                "NTP sync forced via admin API"
+            );
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": 200,
+                    "message": "sync completed",
+                    "sync": report
+                })),
+            )
+        }
+        Ok(Err(error)) => {
+            warn!(action = "sync", %error, "Forced NTP sync failed");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({
+                    "status": 502,
+                    "error": "SyncFailed",
+                    "message": error
+                })),
+            )
+        }
+        Err(_) => unavailable(),
+    }
+}
+
+/// The live syncer, or the 503 returned when there is none.
+fn live_syncer(state: &AppState) -> Result<&Arc<NtpSyncer>, (StatusCode, Json<Value>)> {
+    state.ntp_syncer.as_ref().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "status": 503,
+                "error": "SyncerUnavailable",
+                "message": "NTP syncer is not running"
+            })),
+        )
+    })
+}
+
+fn server_set_error(err: ServerSetError) -> (StatusCode, Json<Value>) {
+    let (status, error) = match err {
+        ServerSetError::AlreadyConfigured => (StatusCode::CONFLICT, "AlreadyConfigured"),
+        ServerSetError::NotConfigured => (StatusCode::NOT_FOUND, "NotConfigured"),
+        ServerSetError::LastServer => (StatusCode::CONFLICT, "LastServer"),
+    };
+    (
+        status,
+        Json(json!({
+            "status": status.as_u16(),
+            "error": error,
+            "message": err.to_string()
+        })),
+    )
+}
+
+fn validation_error(message: String) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "status": 400,
+            "error": "ValidationError",
+            "message": message
+        })),
+    )
+}
+
+/// GET /admin/servers
+///
+/// Lists the upstream servers queried on each sync.
+pub async fn get_servers(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    match live_syncer(&state) {
+        Ok(syncer) => (StatusCode::OK, Json(json!({ "servers": syncer.servers() }))),
+        Err(response) => response,
+    }
+}
+
+/// POST /admin/servers
+///
+/// Adds an upstream server, in `NTP_SERVERS` entry syntax, from the next sync
+/// on.  Keyed entries (`;key=`) are rejected: keys are bound to the client at
+/// startup.  409 when the server is already configured.
+pub async fn post_server(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<AddServerRequest>,
+) -> (StatusCode, Json<Value>) {
+    let syncer = match live_syncer(&state) {
+        Ok(syncer) => syncer,
+        Err(response) => return response,
+    };
+    let server = match ServerConfig::parse(&body.server) {
+        Ok(server) => server,
+        Err(e) => return validation_error(format!("{e:#}")),
+    };
+    if server.key_id.is_some() {
+        return validation_error(
+            "keyed servers must be configured with NTP_SERVERS and NTP_KEYS_FILE".to_string(),
+        );
+    }
+    let address = server.address.clone();
+    if let Err(e) = syncer.add_server(server).await {
+        return server_set_error(e);
+    }
+    info!(
+        action = "added",
+        server = %address,
+        operator = ?body.operator,
+        "NTP server added at runtime"
+    );
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "status": 201,
+            "message": "server added",
+            "servers": syncer.servers()
+        })),
+    )
+}
+
+/// DELETE /admin/servers/{addr}
+///
+/// Stops querying `addr` (normalised as in `NTP_SERVERS`, so a bare host
+/// means port 123) and drops its stats.  404 when it is not configured, 409
+/// when it is the last server.
+pub async fn delete_server(
+    State(state): State<Arc<AppState>>,
+    Path(addr): Path<String>,
+) -> (StatusCode, Json<Value>) {
+    let syncer = match live_syncer(&state) {
+        Ok(syncer) => syncer,
+        Err(response) => return response,
+    };
+    let address = match ServerConfig::parse(&addr) {
+        Ok(server) => server.address,
+        Err(e) => return validation_error(format!("{e:#}")),
+    };
+    if let Err(e) = syncer.remove_server(&address).await {
+        return server_set_error(e);
+    }
+    info!(action = "removed", server = %address, "NTP server removed at runtime");
+    (
+        StatusCode::OK,
+        Json(json!({
+            "status": 200,
+            "message": "server removed",
+            "servers": syncer.servers()
+        })),
+    )
+}
@@ -0,0 +1,384 @@
+//! JWT bearer-token validation (RFC 7519) for `/stream` and the admin API.
+//!
+//! Supports HS256 with a shared secret and RS256 with keys from a JWKS
+//! document (an OIDC provider's `jwks_uri`), refreshed in the background.
+//! Only those two algorithms are accepted — in particular never `none`, and
+//! never HS256 signed with an RSA public key — and the algorithm is chosen by
+//! the configured key material, not trusted from the token header alone.
+//!
+//! Expiry and not-before are checked against NTP time, so a skewed host
+//! clock cannot extend a token's life.
+
+use crate::config::JwtConfig;
+use arc_swap::ArcSwap;
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use ring::{hmac, signature};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Why a token was rejected.  Never echoed to clients (every failure is the
+/// same 401); used for logs and tests.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum JwtError {
+    #[error("malformed token")]
+    Malformed,
+    #[error("unsupported algorithm {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("no key for this token")]
+    UnknownKey,
+    #[error("bad signature")]
+    BadSignature,
+    #[error("token expired")]
+    Expired,
+    #[error("token not yet valid")]
+    NotYetValid,
+    #[error("wrong issuer")]
+    WrongIssuer,
+    #[error("wrong audience")]
+    WrongAudience,
+}
+
+/// An RS256 public key from the JWKS.
+#[derive(Debug, Clone)]
+struct RsaKey {
+    n: Vec<u8>,
+    e: Vec<u8>,
+}
+
+/// Validated claims of an accepted token.
+#[derive(Debug, Clone)]
+pub struct Claims(Map<String, Value>);
+
+impl Claims {
+    pub fn subject(&self) -> Option<&str> {
+        self.0.get("sub").and_then(Value::as_str)
+    }
+
+    /// Whether `scope` (space-separated, RFC 8693) or `scp` (string or
+    /// array, as Azure AD and Okta issue it) grants `wanted`.
+    pub fn has_scope(&self, wanted: &str) -> bool {
+        let listed = |value: Option<&Value>| match value {
+            Some(Value::String(s)) => s.split_whitespace().any(|scope| scope == wanted),
+            Some(Value::Array(items)) => items.iter().any(|item| item.as_str() == Some(wanted)),
+            _ => false,
+        };
+        listed(self.0.get("scope")) || listed(self.0.get("scp"))
+    }
+}
+
+/// Validates tokens against the configured secret / JWKS and claims policy.
+pub struct JwtValidator {
+    config: JwtConfig,
+    hs256: Option<hmac::Key>,
+    /// RS256 keys by `kid`, replaced wholesale on every JWKS refresh.
+    jwks: ArcSwap<HashMap<String, RsaKey>>,
+}
+
+impl std::fmt::Debug for JwtValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Keeps the HS256 secret out of debug output.
+        f.debug_struct("JwtValidator")
+            .field("hs256", &self.hs256.is_some())
+            .field("jwks_keys", &self.jwks.load().len())
+            .finish()
+    }
+}
+
+impl JwtValidator {
+    /// `None` unless JWT auth is configured.
+    pub fn new(config: &JwtConfig) -> Option<Self> {
+        if !config.is_enabled() {
+            return None;
+        }
+        Some(Self {
+            hs256: (!config.hs256_secret.is_empty())
+                .then(|| hmac::Key::new(hmac::HMAC_SHA256, config.hs256_secret.as_bytes())),
+            jwks: ArcSwap::from_pointee(HashMap::new()),
+            config: config.clone(),
+        })
+    }
+
+    /// Replace the RS256 keys from a JWKS document; returns how many were
+    /// loaded.  Non-RSA and encryption keys are skipped.
+    pub fn load_jwks(&self, document: &Value) -> anyhow::Result<usize> {
+        let keys = document
+            .get("keys")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow::anyhow!("JWKS has no keys array"))?;
+        let mut loaded = HashMap::new();
+        for key in keys {
+            let field = |name: &str| key.get(name).and_then(Value::as_str);
+            if field("kty") != Some("RSA")
+                || field("use").is_some_and(|u| u != "sig")
+                || field("alg").is_some_and(|alg| alg != "RS256")
+            {
+                continue;
+            }
+            let (Some(n), Some(e)) = (
+                field("n").and_then(base64url_decode),
+                field("e").and_then(base64url_decode),
+            ) else {
+                continue;
+            };
+            loaded.insert(
+                field("kid").unwrap_or_default().to_string(),
+                RsaKey { n, e },
+            );
+        }
+        let count = loaded.len();
+        self.jwks.store(Arc::new(loaded));
+        Ok(count)
+    }
+
+    /// Fetch `JWT_JWKS_URL` and load its keys.
+    pub async fn refresh_jwks(&self, client: &reqwest::Client) -> anyhow::Result<usize> {
+        let Some(url) = self.config.jwks_url.as_deref() else {
+            return Ok(0);
+        };
+        let document: Value = client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        self.load_jwks(&document)
+    }
+
+    /// Verify `token`'s signature and claims at `now_secs` (unix seconds).
+    pub fn validate(&self, token: &str, now_secs: i64) -> Result<Claims, JwtError> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(payload), Some(sig), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(JwtError::Malformed);
+        };
+        let decode_json = |part: &str| -> Result<Map<String, Value>, JwtError> {
+            base64url_decode(part)
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .ok_or(JwtError::Malformed)
+        };
+        let header_json = decode_json(header)?;
+        let signature = base64url_decode(sig).ok_or(JwtError::Malformed)?;
+        let signed = &token[..header.len() + 1 + payload.len()];
+
+        match header_json.get("alg").and_then(Value::as_str) {
+            Some("HS256") => {
+                let key = self.hs256.as_ref().ok_or(JwtError::UnknownKey)?;
+                hmac::verify(key, signed.as_bytes(), &signature)
+                    .map_err(|_| JwtError::BadSignature)?;
+            }
+            Some("RS256") => {
+                let keys = self.jwks.load();
+                let kid = header_json.get("kid").and_then(Value::as_str);
+                let key = match kid {
+                    Some(kid) => keys.get(kid),
+                    // Without a kid, only an unambiguous single key will do.
+                    None if keys.len() == 1 => keys.values().next(),
+                    None => None,
+                }
+                .ok_or(JwtError::UnknownKey)?;
+                signature::RsaPublicKeyComponents {
+                    n: &key.n,
+                    e: &key.e,
+                }
+                .verify(
+                    &signature::RSA_PKCS1_2048_8192_SHA256,
+                    signed.as_bytes(),
+                    &signature,
+                )
+                .map_err(|_| JwtError::BadSignature)?;
+            }
+            other => {
+                return Err(JwtError::UnsupportedAlgorithm(
+                    other.unwrap_or("missing").to_string(),
+                ));
+            }
+        }
+
+        let claims = decode_json(payload)?;
+        self.check_claims(&claims, now_secs)?;
+        Ok(Claims(claims))
+    }
+
+    fn check_claims(&self, claims: &Map<String, Value>, now_secs: i64) -> Result<(), JwtError> {
+        let leeway = self.config.leeway_secs as i64;
+        let exp = claims
+            .get("exp")
+            .and_then(Value::as_i64)
+            .ok_or(JwtError::Malformed)?;
+        if now_secs > exp + leeway {
+            return Err(JwtError::Expired);
+        }
+        if let Some(nbf) = claims.get("nbf").and_then(Value::as_i64)
+            && now_secs + leeway < nbf
+        {
+            return Err(JwtError::NotYetValid);
+        }
+        if let Some(issuer) = &self.config.issuer
+            && claims.get("iss").and_then(Value::as_str) != Some(issuer.as_str())
+        {
+            return Err(JwtError::WrongIssuer);
+        }
+        if let Some(audience) = &self.config.audience {
+            let matches = match claims.get("aud") {
+                Some(Value::String(aud)) => aud == audience,
+                Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !matches {
+                return Err(JwtError::WrongAudience);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Unpadded base64url (RFC 4648 §5), as used by JWS; trailing `=` is tolerated.
+const BASE64URL: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+fn base64url_decode(input: &str) -> Option<Vec<u8>> {
+    BASE64URL.decode(input).ok()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NOW: i64 = 1_705_320_000;
+
+    fn base64url_encode(input: &[u8]) -> String {
+        BASE64URL.encode(input)
+    }
+
+    /// An HS256 token over `claims` signed with `secret`.
+    pub(crate) fn hs256_token(secret: &str, claims: &Value) -> String {
+        let header = base64url_encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+        let payload = base64url_encode(claims.to_string().as_bytes());
+        let signed = format!("{header}.{payload}");
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+        let sig = base64url_encode(hmac::sign(&key, signed.as_bytes()).as_ref());
+        format!("{signed}.{sig}")
+    }
+
+    fn validator(configure: impl FnOnce(&mut JwtConfig)) -> JwtValidator {
+        let mut config = JwtConfig {
+            hs256_secret: "s3cret".into(),
+            ..JwtConfig::default()
+        };
+        configure(&mut config);
+        JwtValidator::new(&config).unwrap()
+    }
+
+    #[test]
+    fn accepts_valid_hs256_and_reads_scopes() {
+        let v = validator(|_| {});
+        let token = hs256_token(
+            "s3cret",
+            &json!({"sub": "ops", "exp": NOW + 60, "scope": "read ntp:admin"}),
+        );
+        let claims = v.validate(&token, NOW).unwrap();
+        assert_eq!(claims.subject(), Some("ops"));
+        assert!(claims.has_scope("ntp:admin"));
+        assert!(!claims.has_scope("ntp"));
+
+        let token = hs256_token("s3cret", &json!({"exp": NOW, "scp": ["ntp:admin"]}));
+        assert!(v.validate(&token, NOW).unwrap().has_scope("ntp:admin"));
+    }
+
+    #[test]
+    fn rejects_bad_signatures_and_algorithms() {
+        let v = validator(|_| {});
+        let claims = json!({"exp": NOW + 60});
+        let forged = hs256_token("other", &claims);
+        assert_eq!(
+            v.validate(&forged, NOW).unwrap_err(),
+            JwtError::BadSignature
+        );
+
+        let payload = base64url_encode(claims.to_string().as_bytes());
+        let none = format!("{}.{payload}.", base64url_encode(br#"{"alg":"none"}"#));
+        assert_eq!(
+            v.validate(&none, NOW).unwrap_err(),
+            JwtError::UnsupportedAlgorithm("none".into())
+        );
+        // RS256 without any JWKS key loaded.
+        let rs = format!("{}.{payload}.AAAA", base64url_encode(br#"{"alg":"RS256"}"#));
+        assert_eq!(v.validate(&rs, NOW).unwrap_err(), JwtError::UnknownKey);
+        assert_eq!(v.validate("a.b", NOW).unwrap_err(), JwtError::Malformed);
+        assert_eq!(v.validate("!.!.!", NOW).unwrap_err(), JwtError::Malformed);
+    }
+
+    #[test]
+    fn enforces_time_issuer_and_audience() {
+        let v = validator(|c| {
+            c.issuer = Some("https://idp.example".into());
+            c.audience = Some("ntp-api".into());
+        });
+        let token = |claims: Value| hs256_token("s3cret", &claims);
+        let ok = json!({"exp": NOW + 60, "iss": "https://idp.example", "aud": ["x", "ntp-api"]});
+        assert!(v.validate(&token(ok), NOW).is_ok());
+
+        let expired = json!({"exp": NOW - 61, "iss": "https://idp.example", "aud": "ntp-api"});
+        assert_eq!(
+            v.validate(&token(expired), NOW).unwrap_err(),
+            JwtError::Expired
+        );
+        // Within the 60 s leeway.
+        let grace = json!({"exp": NOW - 30, "iss": "https://idp.example", "aud": "ntp-api"});
+        assert!(v.validate(&token(grace), NOW).is_ok());
+        let early = json!({"exp": NOW + 600, "nbf": NOW + 300, "iss": "https://idp.example", "aud": "ntp-api"});
+        assert_eq!(
+            v.validate(&token(early), NOW).unwrap_err(),
+            JwtError::NotYetValid
+        );
+        let no_exp = json!({"iss": "https://idp.example", "aud": "ntp-api"});
+        assert_eq!(
+            v.validate(&token(no_exp), NOW).unwrap_err(),
+            JwtError::Malformed
+        );
+
+        let wrong_iss = json!({"exp": NOW + 60, "iss": "https://evil.example", "aud": "ntp-api"});
+        assert_eq!(
+            v.validate(&token(wrong_iss), NOW).unwrap_err(),
+            JwtError::WrongIssuer
+        );
+        let wrong_aud = json!({"exp": NOW + 60, "iss": "https://idp.example", "aud": "other"});
+        assert_eq!(
+            v.validate(&token(wrong_aud), NOW).unwrap_err(),
+            JwtError::WrongAudience
+        );
+    }
+
+    #[test]
+    fn loads_only_rsa_signing_keys_from_jwks() {
+        let v = validator(|c| c.jwks_url = Some("https://idp.example/jwks".into()));
+        let jwks = json!({"keys": [
+            {"kty": "RSA", "kid": "a", "use": "sig", "alg": "RS256", "n": "sXch", "e": "AQAB"},
+            {"kty": "RSA", "kid": "enc", "use": "enc", "n": "sXch", "e": "AQAB"},
+            {"kty": "EC", "kid": "ec", "crv": "P-256", "x": "AA", "y": "AA"},
+            {"kty": "RSA", "kid": "bad", "n": "***", "e": "AQAB"},
+        ]});
+        assert_eq!(v.load_jwks(&jwks).unwrap(), 1);
+        assert!(v.load_jwks(&json!({})).is_err());
+    }
+
+    #[test]
+    fn base64url_decodes_jws_segments() {
+        assert_eq!(base64url_decode("AQAB").unwrap(), [1, 0, 1]);
+        assert_eq!(base64url_decode("-_8").unwrap(), [0xfb, 0xff]);
+        assert_eq!(base64url_decode("-_8=").unwrap(), [0xfb, 0xff]);
+        assert!(base64url_decode("A").is_none());
+        assert!(base64url_decode("a+b/").is_none());
+    }
+}
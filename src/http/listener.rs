@@ -0,0 +1,350 @@
+//! HTTP listeners and their instrumentation.
+//!
+//! [`bind_tcp`] and [`bind_unix`] create the sockets for each `ADDR` entry
+//! with its [`SocketOptions`].  [`CountingListener`] wraps each of them and
+//! tracks connection-level metrics (open, accepted, lifetime) so capacity
+//! planning is not based on request counts alone.
+//!
+//! The kernel silently drops SYNs / completed handshakes when the listen
+//! backlog (`LISTEN_BACKLOG`) is full.  On Linux the drops are visible in the
+//! `TcpExt` section of `/proc/net/netstat` (`ListenOverflows` and
+//! `ListenDrops`).  The counters are per network namespace, so inside a
+//! container they reflect this pod's listeners only.  Other platforms do not
+//! expose an equivalent and `read_listen_queue_stats` returns `None`.
+
+use crate::config::SocketOptions;
+use crate::metrics::SharedMetrics;
+use axum::serve::Listener;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Bind a TCP listener on `addr` with SO_REUSEADDR and `options`.
+pub fn bind_tcp(addr: SocketAddr, options: &SocketOptions) -> io::Result<TcpListener> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    // Faster restarts
+    socket.set_reuse_address(true)?;
+    // Lower latency (disable Nagle's algorithm)
+    if options.tcp_nodelay {
+        socket.set_tcp_nodelay(true)?;
+    }
+    if let Some(keepalive_secs) = options.tcp_keepalive_secs {
+        let keepalive = socket2::TcpKeepalive::new().with_time(Duration::from_secs(keepalive_secs));
+        socket.set_tcp_keepalive(&keepalive)?;
+    }
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(options.backlog)?;
+    TcpListener::from_std(socket.into())
+}
+
+/// Bind a Unix domain socket at `path`, replacing a stale socket file left
+/// by a previous run, and apply `options.unix_mode` to it.
+#[cfg(unix)]
+pub fn bind_unix(
+    path: &std::path::Path,
+    options: &SocketOptions,
+) -> io::Result<UnixSocketListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let socket = socket2::Socket::new(socket2::Domain::UNIX, socket2::Type::STREAM, None)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&socket2::SockAddr::unix(path)?)?;
+    socket.listen(options.backlog)?;
+    if let Some(mode) = options.unix_mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    let std_listener: std::os::unix::net::UnixListener = socket.into();
+    Ok(UnixSocketListener {
+        inner: tokio::net::UnixListener::from_std(std_listener)?,
+    })
+}
+
+/// Unix domain socket listener whose peers are reported as `127.0.0.1:0`.
+///
+/// The router's client-IP logic (rate limiting, `ConnectInfo`) works on
+/// `SocketAddr`s, and a Unix peer has none.  Every socket client shares the
+/// loopback key, so a local proxy forwarding over the socket should be listed
+/// in `TRUSTED_PROXIES` for its `X-Forwarded-For` to be honoured.
+#[cfg(unix)]
+pub struct UnixSocketListener {
+    inner: tokio::net::UnixListener,
+}
+
+#[cfg(unix)]
+impl UnixSocketListener {
+    /// Address reported for every Unix socket peer.
+    pub const PEER_ADDR: SocketAddr =
+        SocketAddr::new(std::net::IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), 0);
+}
+
+#[cfg(unix)]
+impl Listener for UnixSocketListener {
+    type Io = tokio::net::UnixStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let (stream, _) = Listener::accept(&mut self.inner).await;
+        (stream, Self::PEER_ADDR)
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(Self::PEER_ADDR)
+    }
+}
+
+/// `axum::serve` listener that counts accepted and open connections.
+///
+/// Accept errors are retried by the inner listener exactly as before; only
+/// successfully accepted connections are counted.
+pub struct CountingListener<L = TcpListener> {
+    inner: L,
+    metrics: SharedMetrics,
+}
+
+impl<L> CountingListener<L> {
+    pub fn new(inner: L, metrics: SharedMetrics) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+impl<L: Listener> Listener for CountingListener<L> {
+    type Io = CountedStream<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let (stream, addr) = Listener::accept(&mut self.inner).await;
+        self.metrics.http_connections_accepted_total.inc();
+        self.metrics.http_connections_open.inc();
+        let io = CountedStream {
+            inner: stream,
+            metrics: self.metrics.clone(),
+            opened_at: Instant::now(),
+        };
+        (io, addr)
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+/// Accepted connection; decrements the open gauge and records the connection
+/// lifetime when hyper drops it.
+pub struct CountedStream<Io = TcpStream> {
+    inner: Io,
+    metrics: SharedMetrics,
+    opened_at: Instant,
+}
+
+impl<Io> Drop for CountedStream<Io> {
+    fn drop(&mut self) {
+        self.metrics.http_connections_open.dec();
+        self.metrics
+            .http_connection_duration_seconds
+            .observe(self.opened_at.elapsed().as_secs_f64());
+    }
+}
+
+impl<Io: AsyncRead + Unpin> AsyncRead for CountedStream<Io> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<Io: AsyncWrite + Unpin> AsyncWrite for CountedStream<Io> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Cumulative accept-queue counters as reported by the kernel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListenQueueStats {
+    /// Times the accept queue of a listening socket was full.
+    pub listen_overflows: u64,
+    /// SYNs dropped on a listening socket for any reason (superset of overflows).
+    pub listen_drops: u64,
+}
+
+/// Parse the `TcpExt` header/value line pair of `/proc/net/netstat`.
+///
+/// Returns `None` when the section or either counter is missing.
+pub fn parse_netstat(contents: &str) -> Option<ListenQueueStats> {
+    let mut lines = contents.lines();
+    while let Some(header) = lines.next() {
+        let Some(names) = header.strip_prefix("TcpExt:") else {
+            continue;
+        };
+        let values = lines.next()?.strip_prefix("TcpExt:")?;
+
+        let mut overflows = None;
+        let mut drops = None;
+        for (name, value) in names.split_whitespace().zip(values.split_whitespace()) {
+            match name {
+                "ListenOverflows" => overflows = value.parse().ok(),
+                "ListenDrops" => drops = value.parse().ok(),
+                _ => {}
+            }
+        }
+        return Some(ListenQueueStats {
+            listen_overflows: overflows?,
+            listen_drops: drops?,
+        });
+    }
+    None
+}
+
+/// Read the current accept-queue counters, if the platform exposes them.
+pub fn read_listen_queue_stats() -> Option<ListenQueueStats> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/net/netstat")
+            .ok()
+            .and_then(|s| parse_netstat(&s))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use std::sync::Arc;
+
+    const SAMPLE: &str = "\
+TcpExt: SyncookiesSent SyncookiesRecv ListenOverflows ListenDrops TCPTimeouts
+TcpExt: 0 0 17 23 4
+IpExt: InNoRoutes InTruncatedPkts
+IpExt: 0 0
+";
+
+    #[test]
+    fn parses_listen_counters() {
+        let stats = parse_netstat(SAMPLE).unwrap();
+        assert_eq!(stats.listen_overflows, 17);
+        assert_eq!(stats.listen_drops, 23);
+    }
+
+    #[tokio::test]
+    async fn counting_listener_tracks_open_connections() {
+        let metrics = Arc::new(Metrics::new());
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = tcp.local_addr().unwrap();
+        let mut listener = CountingListener::new(tcp, metrics.clone());
+
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (io, _) = Listener::accept(&mut listener).await;
+        assert_eq!(metrics.http_connections_accepted_total.get(), 1);
+        assert_eq!(metrics.http_connections_open.get(), 1);
+
+        drop(io);
+        assert_eq!(metrics.http_connections_open.get(), 0);
+        assert!(
+            metrics
+                .encode()
+                .contains("http_connection_duration_seconds_count 1")
+        );
+    }
+
+    #[tokio::test]
+    async fn binds_tcp_with_options() {
+        let options = SocketOptions {
+            backlog: 16,
+            tcp_nodelay: true,
+            tcp_keepalive_secs: Some(30),
+            unix_mode: None,
+        };
+        let listener = bind_tcp("127.0.0.1:0".parse().unwrap(), &options).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (_, peer) = listener.accept().await.unwrap();
+        assert!(peer.ip().is_loopback());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn binds_unix_socket_replacing_stale_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("uds-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("http.sock");
+        let options = SocketOptions {
+            backlog: 16,
+            tcp_nodelay: false,
+            tcp_keepalive_secs: None,
+            unix_mode: Some(0o600),
+        };
+
+        // A socket left behind by a previous run is replaced.
+        drop(bind_unix(&path, &options).unwrap());
+        let mut listener = bind_unix(&path, &options).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _client = tokio::net::UnixStream::connect(&path).await.unwrap();
+        let (_, peer) = Listener::accept(&mut listener).await;
+        assert_eq!(peer, UnixSocketListener::PEER_ADDR);
+
+        // A regular file is never deleted.
+        let file = dir.join("not-a-socket");
+        std::fs::write(&file, b"keep").unwrap();
+        assert!(bind_unix(&file, &options).is_err());
+        assert!(file.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_section_returns_none() {
+        assert!(parse_netstat("IpExt: InNoRoutes\nIpExt: 0\n").is_none());
+        assert!(parse_netstat("TcpExt: SyncookiesSent\nTcpExt: 0\n").is_none());
+        assert!(parse_netstat("").is_none());
+    }
+}
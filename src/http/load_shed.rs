@@ -0,0 +1,116 @@
+//! Global in-flight request limit.
+//!
+//! Once `MAX_INFLIGHT_REQUESTS` requests are being processed, further ones
+//! are answered immediately with 503, `Retry-After: 1` and the usual JSON
+//! error body instead of queueing until they time out.  Shed requests count
+//! towards `http_load_shed_total`.
+//!
+//! A WebSocket upgrade holds its slot only until the 101 response is sent;
+//! the long-lived stream itself is not counted.
+//!
+//! Kubernetes probes and `/metrics` are never shed and take no slot: a
+//! liveness probe failing under load would restart healthy pods exactly
+//! when they are busiest, and the scrape is how overload is seen at all.
+
+use super::version::ApiVersion;
+use crate::errors::AppError;
+use crate::metrics::SharedMetrics;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Shared in-flight counter checked by [`shed_load`].
+#[derive(Clone)]
+pub struct LoadShedder {
+    inflight: Arc<AtomicUsize>,
+    max_inflight: usize,
+    metrics: SharedMetrics,
+}
+
+impl LoadShedder {
+    pub fn new(max_inflight: usize, metrics: SharedMetrics) -> Self {
+        Self {
+            inflight: Arc::new(AtomicUsize::new(0)),
+            max_inflight,
+            metrics,
+        }
+    }
+
+    /// Reserve a slot, or `None` when the limit is reached.
+    fn try_acquire(&self) -> Option<InflightGuard> {
+        self.inflight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < self.max_inflight).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| InflightGuard {
+                inflight: self.inflight.clone(),
+            })
+    }
+}
+
+/// Releases its slot when the response (or a cancelled request) is dropped.
+struct InflightGuard {
+    inflight: Arc<AtomicUsize>,
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        self.inflight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Paths answered whatever the in-flight count (also under `/v1`).
+const EXEMPT_PATHS: &[&str] = &["/healthz", "/readyz", "/startupz", "/metrics"];
+
+fn is_exempt(path: &str) -> bool {
+    let path = path.strip_prefix(ApiVersion::V1.prefix()).unwrap_or(path);
+    EXEMPT_PATHS.contains(&path)
+}
+
+pub async fn shed_load(
+    State(shedder): State<LoadShedder>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if is_exempt(request.uri().path()) {
+        return next.run(request).await;
+    }
+    let Some(_guard) = shedder.try_acquire() else {
+        shedder.metrics.http_load_shed_total.inc();
+        return AppError::Overloaded.into_response();
+    };
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+
+    #[test]
+    fn slots_are_released_on_drop() {
+        let shedder = LoadShedder::new(2, Arc::new(Metrics::new()));
+        let first = shedder.try_acquire().unwrap();
+        let _second = shedder.try_acquire().unwrap();
+        assert!(shedder.try_acquire().is_none());
+
+        drop(first);
+        assert!(shedder.try_acquire().is_some());
+        assert_eq!(shedder.inflight.load(Ordering::Acquire), 1);
+    }
+
+    #[test]
+    fn probes_and_metrics_are_exempt() {
+        assert!(is_exempt("/healthz"));
+        assert!(is_exempt("/v1/readyz"));
+        assert!(is_exempt("/metrics"));
+        assert!(!is_exempt("/time"));
+        assert!(!is_exempt("/health/deep"));
+        assert!(!is_exempt("/healthz/extra"));
+    }
+}
@@ -1,15 +1,22 @@
+use crate::http::jwt::Claims;
 use crate::http::state::AppState;
 use axum::{
-    extract::{Request, State},
-    http::{StatusCode, header::AUTHORIZATION},
+    extract::{MatchedPath, Request, State},
+    http::{
+        StatusCode,
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    },
     middleware::Next,
     response::Response,
 };
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
 use std::sync::Arc;
 use std::time::Instant;
 
-/// Admin auth middleware — requires `Authorization: Bearer <token>` matching
-/// `config.admin.token`.  Missing and wrong tokens return an identical 401
+/// Admin auth middleware — requires `Authorization: Bearer <token>` where the
+/// token matches `config.admin.token` or is a valid JWT carrying
+/// `JWT_ADMIN_SCOPE`.  Missing and wrong tokens return an identical 401
 /// body so the response is not an oracle for distinguishing the two cases.
 ///
 /// SECURITY: The token is NEVER logged or included in any error message.
@@ -19,38 +26,154 @@ pub async fn require_admin_auth(
     request: Request,
     next: Next,
 ) -> Response {
-    use subtle::ConstantTimeEq;
+    let provided = bearer_token(&request).unwrap_or("");
+
+    let static_ok = !state.config.admin.token.is_empty()
+        && constant_time_eq(&state.config.admin.token, provided);
+    let jwt_ok = || {
+        validate_jwt(&state, provided)
+            .is_some_and(|claims| claims.has_scope(&state.config.jwt.admin_scope))
+    };
+    if !static_ok && !jwt_ok() {
+        return unauthorized(None);
+    }
+
+    next.run(request).await
+}
+
+/// `/stream` auth middleware, installed when JWT auth is configured.  Takes
+/// the token from `Authorization: Bearer` or, because browsers cannot set
+/// headers on a WebSocket handshake, from `?access_token=` (RFC 6750 §2.3).
+/// Any valid token is accepted; no scope is required.
+pub async fn require_stream_auth(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let provided = bearer_token(&request).or_else(|| {
+        request
+            .uri()
+            .query()?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("access_token="))
+    });
+
+    if provided
+        .and_then(|token| validate_jwt(&state, token))
+        .is_none()
+    {
+        return unauthorized(Some(r#"Bearer realm="stream""#));
+    }
+
+    next.run(request).await
+}
 
-    let provided = request
+fn bearer_token(request: &Request) -> Option<&str> {
+    request
         .headers()
         .get(AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
         .and_then(|s| s.strip_prefix("Bearer "))
+}
+
+/// Validate `token` against NTP time (system time before the first sync).
+/// Rejections are logged at debug level without the token.
+pub(crate) fn validate_jwt(state: &AppState, token: &str) -> Option<Claims> {
+    let validator = state.jwt.as_ref()?;
+    let now_ms = state
+        .timebase
+        .now_ms()
+        .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+    validator
+        .validate(token, now_ms.div_euclid(1000))
+        .inspect_err(|error| tracing::debug!(%error, "JWT rejected"))
+        .ok()
+}
+
+/// Observability auth middleware for `/metrics`, `/performance` and
+/// `/debug/*`.  Only installed when `METRICS_AUTH_TOKEN` and/or
+/// `METRICS_AUTH_BASIC` is set; accepts either credential.  Same 401 body as
+/// the admin middleware, plus a `WWW-Authenticate` challenge when Basic auth
+/// is configured.
+///
+/// SECURITY: credentials are never logged and compared in constant time.
+pub async fn require_metrics_auth(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let auth = &state.config.metrics_auth;
+    let header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    let expected = state.config.admin.token.as_bytes();
-    let provided_bytes = provided.as_bytes();
+    let valid = if let Some(token) = header.strip_prefix("Bearer ") {
+        !auth.token.is_empty() && constant_time_eq(&auth.token, token)
+    } else if let Some(encoded) = header.strip_prefix("Basic ") {
+        !auth.basic.is_empty() && constant_time_eq(&STANDARD.encode(auth.basic.as_bytes()), encoded)
+    } else {
+        false
+    };
+
+    if !valid {
+        let challenge = (!auth.basic.is_empty()).then_some(r#"Basic realm="metrics""#);
+        return unauthorized(challenge);
+    }
+
+    next.run(request).await
+}
+
+/// Compare a secret against a client-provided value without leaking either
+/// the contents or (beyond the length check) the position of a mismatch.
+pub(crate) fn constant_time_eq(expected: &str, provided: &str) -> bool {
+    use subtle::ConstantTimeEq;
 
-    let valid: bool = if expected.len() == provided_bytes.len() {
-        expected.ct_eq(provided_bytes).into()
+    let expected = expected.as_bytes();
+    let provided = provided.as_bytes();
+    if expected.len() == provided.len() {
+        expected.ct_eq(provided).into()
     } else {
         // Lengths differ: still run a dummy comparison so the branch takes
         // similar time and the compiler cannot elide the constant-time path.
         let _ = expected.ct_eq(expected);
         false
-    };
+    }
+}
 
-    if !valid {
-        return Response::builder()
-            .status(StatusCode::UNAUTHORIZED)
-            .header("content-type", "application/json")
-            .body(axum::body::Body::from(
-                r#"{"status":401,"error":"Unauthorized","message":"error"}"#,
-            ))
-            .expect("static 401 body");
+/// Static 401 body shared by all auth middlewares so the response is not an
+/// oracle for which check failed.
+fn unauthorized(www_authenticate: Option<&'static str>) -> Response {
+    let mut builder = Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header("content-type", "application/json");
+    if let Some(challenge) = www_authenticate {
+        builder = builder.header(WWW_AUTHENTICATE, challenge);
     }
+    builder
+        .body(axum::body::Body::from(
+            r#"{"status":401,"error":"Unauthorized","message":"error"}"#,
+        ))
+        .expect("static 401 body")
+}
 
-    next.run(request).await
+/// `path` label used for every route that did not match a registered route.
+pub const OTHER_PATH_LABEL: &str = "__other__";
+
+/// Bounded-cardinality `path` label for `HttpLabels`.
+///
+/// Uses the matched route template (e.g. `/users/{id}` rather than
+/// `/users/42`), so parameterized routes share one series.  Unmatched
+/// requests collapse into [`OTHER_PATH_LABEL`]; the raw URI (including any
+/// query string) never reaches the label, so a scanner probing random URLs
+/// cannot create unbounded Prometheus series.
+pub fn metrics_path_label(request: &Request) -> String {
+    request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| OTHER_PATH_LABEL.to_string())
 }
 
 pub async fn track_metrics(
@@ -60,7 +183,7 @@ pub async fn track_metrics(
 ) -> Response {
     let start = Instant::now();
     let method = request.method().to_string();
-    let path = request.uri().path().to_string();
+    let path = metrics_path_label(&request);
 
     // Increment inflight requests
     state.metrics.http_inflight_requests.inc();
@@ -81,3 +204,16 @@ pub async fn track_metrics(
 
     response
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_time_eq_requires_exact_match() {
+        assert!(constant_time_eq("token", "token"));
+        assert!(!constant_time_eq("token", "tokem"));
+        assert!(!constant_time_eq("token", "token2"));
+        assert!(!constant_time_eq("token", ""));
+    }
+}
@@ -1,33 +1,93 @@
 pub mod handlers;
 pub mod handlers_admin;
+pub mod ip_filter;
+pub mod jwt;
+pub mod listener;
+pub mod load_shed;
 pub mod middleware;
+pub mod rate_limit;
+pub mod server;
 pub mod state;
+pub mod tls;
+pub mod trace;
+pub mod version;
 pub mod websocket;
 
-use axum::{Router, http::StatusCode, middleware as axum_middleware, routing::get};
+use crate::config::IpFilterScope;
+use axum::{
+    Extension, Router,
+    http::StatusCode,
+    middleware as axum_middleware,
+    routing::{delete, get, post},
+};
 use state::AppState;
 use std::sync::Arc;
 use std::time::Duration;
 use tower_governor::{GovernorLayer, governor::GovernorConfigBuilder};
 use tower_http::{
+    compression::CompressionLayer,
     cors::{Any, CorsLayer},
     limit::RequestBodyLimitLayer,
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
     timeout::TimeoutLayer,
-    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
+    trace::TraceLayer,
 };
-use tracing::Level;
+use version::ApiVersion;
+
+/// Routes a router serves.  With `OPS_ADDR` set the main listener gets
+/// [`Surface::Public`] and the ops listener [`Surface::Ops`]; otherwise the
+/// main listener serves [`Surface::All`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Surface {
+    All,
+    /// `/time*`, `/stream` and the other client-facing API routes.
+    Public,
+    /// Probes, `/metrics`, `/performance`, `/debug/*` and `/admin/*`.
+    Ops,
+}
+
+impl Surface {
+    fn serves_public(self) -> bool {
+        self != Surface::Ops
+    }
 
+    fn serves_ops(self) -> bool {
+        self != Surface::Public
+    }
+}
+
+/// Router for the main `ADDR` listener(s).
 pub fn create_router(state: Arc<AppState>) -> Router {
     let enable_rate_limiting = !state.config.http.disable_rate_limiting;
-    create_router_internal(state, enable_rate_limiting)
+    let surface = if state.config.http.ops_listen.is_some() {
+        Surface::Public
+    } else {
+        Surface::All
+    };
+    create_router_internal(state, enable_rate_limiting, surface)
+}
+
+/// Router for the `OPS_ADDR` listener.
+pub fn create_ops_router(state: Arc<AppState>) -> Router {
+    let enable_rate_limiting = !state.config.http.disable_rate_limiting;
+    create_router_internal(state, enable_rate_limiting, Surface::Ops)
 }
 
 pub fn create_router_for_test(state: Arc<AppState>) -> Router {
-    create_router_internal(state, false)
+    create_router_internal(state, false, Surface::All)
 }
 
-fn create_router_internal(state: Arc<AppState>, enable_rate_limiting: bool) -> Router {
+fn create_router_internal(
+    state: Arc<AppState>,
+    enable_rate_limiting: bool,
+    surface: Surface,
+) -> Router {
     let config = &state.config;
+    // IP_ALLOWLIST / IP_DENYLIST, on every route or only the ops surface
+    let ip_filter = ip_filter::IpFilter::new(&config.http, state.metrics.clone());
+    let ops_ip_filter = ip_filter
+        .clone()
+        .filter(|_| config.http.ip_filter_scope == IpFilterScope::Ops);
 
     // PERFORMANCE: Fast path - NO middleware for hot endpoints
     // This eliminates tracing, metrics, timeout, and body limit overhead
@@ -35,22 +95,98 @@ fn create_router_internal(state: Arc<AppState>, enable_rate_limiting: bool) -> R
     let fast_router = Router::new()
         .route("/time", get(handlers::time_handler))
         .route("/", get(handlers::time_handler)) // Alias
+        .route("/time.txt", get(handlers::time_text_handler))
+        .route("/time/iso", get(handlers::time_iso_handler))
+        .route("/time/unix", get(handlers::time_unix_handler))
+        .route("/time/rfc2822", get(handlers::time_rfc2822_handler))
+        .route("/time/all", get(handlers::time_all_handler))
         .with_state(state.clone());
 
-    // Slow path - full middleware stack for less critical endpoints
-    let slow_router = Router::new()
-        // WebSocket endpoint
-        .route("/stream", get(websocket::websocket_handler))
-        // Probe endpoints (Kubernetes probes don't need full middleware)
-        .route("/healthz", get(handlers::healthz_handler))
-        .route("/readyz", get(handlers::readyz_handler))
-        .route("/startupz", get(handlers::startupz_handler))
-        // Metrics (needs full stack for monitoring)
+    // Lightweight layers for accountability on the hot path (FAST_PATH_*):
+    // request ids by default, metrics opt-in. Rate limiting needs no flag:
+    // the GovernorLayer below already wraps every route, including this one.
+    let fast_router = if config.http.fast_path_metrics {
+        fast_router.layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            middleware::track_metrics,
+        ))
+    } else {
+        fast_router
+    };
+    let fast_router = if config.http.fast_path_request_id {
+        with_request_id(fast_router)
+    } else {
+        fast_router
+    };
+
+    // Observability endpoints - optionally behind a dedicated scrape credential
+    // (METRICS_AUTH_TOKEN / METRICS_AUTH_BASIC), independent of the admin token.
+    let observability_router = Router::new()
         .route("/metrics", get(handlers::metrics_handler))
         .route("/performance", get(handlers::performance_handler))
-        // Time-quality envelope endpoints (P0-4)
-        .route("/time/full", get(handlers::time_full_handler))
-        .route("/status", get(handlers::status_handler))
+        .route("/debug/ntp", get(handlers::debug_ntp_handler))
+        .route("/debug/selection", get(handlers::debug_selection_handler));
+    let observability_router = if config.metrics_auth.is_enabled() {
+        observability_router.route_layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            middleware::require_metrics_auth,
+        ))
+    } else {
+        observability_router
+    };
+    let observability_router = match ops_ip_filter.clone() {
+        Some(filter) => observability_router.route_layer(axum_middleware::from_fn_with_state(
+            filter,
+            ip_filter::filter_ip,
+        )),
+        None => observability_router,
+    };
+
+    // WebSocket endpoint - behind a JWT when JWT auth is configured
+    let stream_router = Router::new().route("/stream", get(websocket::websocket_handler));
+    let stream_router = if state.jwt.is_some() && config.jwt.protect_stream {
+        stream_router.route_layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            middleware::require_stream_auth,
+        ))
+    } else {
+        stream_router
+    };
+
+    // Slow path - full middleware stack for less critical endpoints
+    let mut slow_routes = Router::new();
+    if surface.serves_public() {
+        slow_routes = slow_routes
+            .merge(stream_router)
+            // Time-quality envelope endpoints (P0-4)
+            .route("/time/full", get(handlers::time_full_handler))
+            .route("/time/interval", get(handlers::time_interval_handler))
+            // Long poll: bounded by the TimeoutLayer below (REQUEST_TIMEOUT)
+            .route("/time/next", get(handlers::time_next_handler))
+            .route("/status", get(handlers::status_handler))
+            .route(
+                "/report/clock-drift",
+                get(handlers::clock_drift_report_handler),
+            )
+            // IANA timezone database evaluated at NTP time
+            .route("/timezones", get(handlers::timezones_handler))
+            .route("/timezones/{*zone}", get(handlers::timezone_handler))
+            .route("/convert", get(handlers::convert_handler))
+            // worldtimeapi.org-compatible
+            .route("/api/timezone", get(handlers::world_time_zones_handler))
+            .route("/api/timezone/{*zone}", get(handlers::world_time_handler));
+    }
+    if surface.serves_ops() {
+        slow_routes = slow_routes
+            // Probe endpoints (Kubernetes probes don't need full middleware)
+            .route("/healthz", get(handlers::healthz_handler))
+            .route("/health/deep", get(handlers::deep_health_handler))
+            .route("/readyz", get(handlers::readyz_handler))
+            .route("/startupz", get(handlers::startupz_handler))
+            // Metrics (needs full stack for monitoring)
+            .merge(observability_router);
+    }
+    let slow_router = slow_routes
         .with_state(state.clone())
         // Middleware - applied bottom-up
         .layer(axum_middleware::from_fn_with_state(
@@ -64,9 +200,24 @@ fn create_router_internal(state: Arc<AppState>, enable_rate_limiting: bool) -> R
         ))
         .layer(
             TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
-                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+                .make_span_with(trace::SampledMakeSpan::new(
+                    config.logging.trace_sample_ratio,
+                ))
+                .on_response(trace::SampledOnResponse::default())
+                .on_failure(trace::SampledOnFailure::new(
+                    config.logging.trace_always_sample_errors,
+                )),
         );
+    // gzip/brotli for large bodies: the Prometheus exposition reaches
+    // hundreds of KB with per-server label families. CompressionLayer skips
+    // small bodies, SSE and WebSocket upgrades on its own.
+    let slow_router = if config.http.compression_enabled {
+        slow_router.layer(CompressionLayer::new())
+    } else {
+        slow_router
+    };
+    // Outside the TraceLayer, so the request span can record the id.
+    let slow_router = with_request_id(slow_router);
 
     // CORS configuration - allow all origins for public time API
     let cors = CorsLayer::new()
@@ -75,9 +226,14 @@ fn create_router_internal(state: Arc<AppState>, enable_rate_limiting: bool) -> R
         .allow_headers(Any)
         .max_age(Duration::from_secs(3600));
 
+    let mut router = Router::new().merge(slow_router);
+    if surface.serves_public() {
+        router = router.merge(fast_router);
+    }
+
     // Admin router — only registered when ADMIN_API_ENABLED=true.
     // If disabled, /admin/* routes return 404 (not 401), per security contract.
-    let router = if config.admin.enabled {
+    if surface.serves_ops() && config.admin.enabled {
         let admin_router = Router::new()
             .route(
                 "/admin/time/override",
@@ -85,38 +241,100 @@ fn create_router_internal(state: Arc<AppState>, enable_rate_limiting: bool) -> R
                     .post(handlers_admin::post_override)
                     .delete(handlers_admin::delete_override),
             )
+            .route(
+                "/admin/servers",
+                get(handlers_admin::get_servers).post(handlers_admin::post_server),
+            )
+            .route(
+                "/admin/servers/{addr}",
+                delete(handlers_admin::delete_server),
+            )
+            .route("/admin/sync", post(handlers_admin::post_sync))
+            .route("/admin/config", get(handlers_admin::get_config))
+            .route(
+                "/admin/logging",
+                get(handlers_admin::get_logging).put(handlers_admin::put_logging),
+            )
             .with_state(state.clone())
-            .layer(axum_middleware::from_fn_with_state(
+            // route_layer: unmatched paths stay 404 rather than 401
+            .route_layer(axum_middleware::from_fn_with_state(
                 state.clone(),
                 middleware::require_admin_auth,
             ))
             .layer(RequestBodyLimitLayer::new(config.http.body_limit_bytes));
-        Router::new()
-            .merge(fast_router)
-            .merge(slow_router)
-            .merge(admin_router)
-    } else {
-        Router::new().merge(fast_router).merge(slow_router)
-    };
+        let admin_router = match ops_ip_filter {
+            Some(filter) => admin_router.route_layer(axum_middleware::from_fn_with_state(
+                filter,
+                ip_filter::filter_ip,
+            )),
+            None => admin_router,
+        };
+        router = router.merge(with_request_id(admin_router));
+    }
 
-    // Apply rate limiting in production only (requires real IP addresses)
+    // Every route lives under `/v1`; the unprefixed paths stay as aliases
+    // of the legacy version.  A `/v2` would nest its own router here.
+    let router = Router::new()
+        .nest(
+            ApiVersion::V1.prefix(),
+            router.clone().layer(Extension(ApiVersion::V1)),
+        )
+        .merge(router.layer(Extension(ApiVersion::LEGACY)));
+
+    // Apply rate limiting in production only (requires real IP addresses):
+    // a token bucket per client IP (RATE_LIMIT_PER_SECOND, RATE_LIMIT_BURST).
     let router = if enable_rate_limiting {
-        // Rate limiting configuration (1000 req/sec per IP, burst of 100)
         let governor_conf = Arc::new(
             GovernorConfigBuilder::default()
-                .per_second(1000)
-                .burst_size(100)
+                .period(rate_limit::refill_period(config.http.rate_limit_per_second))
+                .burst_size(config.http.rate_limit_burst)
+                .key_extractor(rate_limit::ClientIpKeyExtractor::new(
+                    &config.http.trusted_proxies,
+                ))
                 .finish()
-                .unwrap(),
+                .expect("RATE_LIMIT_* validated as non-zero"),
         );
-        router.layer(GovernorLayer::new(governor_conf))
+        let state = state.clone();
+        router.layer(
+            GovernorLayer::new(governor_conf)
+                .error_handler(move |error| rate_limit::error_response(&state, error)),
+        )
+    } else {
+        router
+    };
+
+    // Shed load before any other work once MAX_INFLIGHT_REQUESTS requests
+    // are in flight, so overload ends in a fast 503 rather than timeouts.
+    // Probes and /metrics are exempt (see load_shed).
+    let router = if config.http.max_inflight_requests > 0 {
+        router.layer(axum_middleware::from_fn_with_state(
+            load_shed::LoadShedder::new(config.http.max_inflight_requests, state.metrics.clone()),
+            load_shed::shed_load,
+        ))
     } else {
         router
     };
 
+    // Reject filtered clients before they can take an in-flight slot
+    let router = match ip_filter.filter(|_| config.http.ip_filter_scope == IpFilterScope::All) {
+        Some(filter) => router.layer(axum_middleware::from_fn_with_state(
+            filter,
+            ip_filter::filter_ip,
+        )),
+        None => router,
+    };
+
     router.layer(cors)
 }
 
+/// Honour an incoming `x-request-id` or stamp a UUID, and echo it on the
+/// response so client reports can be matched to server logs.
+fn with_request_id(router: Router) -> Router {
+    router
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,6 +445,357 @@ mod tests {
         assert_eq!(response.status(), 503);
     }
 
+    #[tokio::test]
+    async fn test_format_endpoints_serve_bare_timestamps() {
+        let state = make_state();
+        let app = create_router_for_test(state.clone());
+        for uri in ["/time/iso", "/time/unix", "/time/rfc2822"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 503, "{uri} before sync");
+        }
+
+        state.timebase.set_manual(1_705_320_000_000, 60); // 2024-01-15T12:00:00Z
+        let get = |uri: &'static str, accept: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri(uri)
+                            .header("accept", accept)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), 200);
+                let content_type = response.headers()["content-type"]
+                    .to_str()
+                    .unwrap()
+                    .to_string();
+                let body = to_bytes(response.into_body(), 1024).await.unwrap();
+                (content_type, String::from_utf8(body.to_vec()).unwrap())
+            }
+        };
+
+        let (content_type, body) = get("/time/iso", "*/*").await;
+        assert_eq!(content_type, "application/json");
+        let iso: String = serde_json::from_str(&body).unwrap();
+        assert!(
+            iso.starts_with("2024-01-15T12:00:0") && iso.ends_with('Z'),
+            "{iso}"
+        );
+
+        let (_, body) = get("/time/unix", "application/json").await;
+        let secs: i64 = serde_json::from_str(&body).unwrap();
+        assert!((1_705_320_000..1_705_320_010).contains(&secs), "{secs}");
+
+        let (content_type, body) = get("/time/rfc2822", "text/plain").await;
+        assert_eq!(content_type, "text/plain; charset=utf-8");
+        assert!(body.starts_with("Mon, 15 Jan 2024 12:00:0"), "{body}");
+        assert!(body.ends_with(" +0000"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn test_v1_prefix_aliases_legacy_routes() {
+        let state = make_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let app = create_router_for_test(state);
+        let status = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        for uri in [
+            "/v1/time",
+            "/v1",
+            "/v1/time/iso",
+            "/v1/status",
+            "/v1/healthz",
+        ] {
+            assert_eq!(status(uri).await, 200, "{uri}");
+        }
+        for uri in ["/time", "/", "/status"] {
+            assert_eq!(status(uri).await, 200, "{uri}");
+        }
+        assert_eq!(status("/v2/time").await, 404);
+    }
+
+    #[tokio::test]
+    async fn test_api_version_marker_reaches_handlers() {
+        use version::ApiVersion;
+
+        // Same wiring as create_router_internal, with a probe handler.
+        let api = Router::new().route(
+            "/version",
+            get(|version: ApiVersion| async move { version.prefix() }),
+        );
+        let app = Router::new()
+            .nest(
+                ApiVersion::V1.prefix(),
+                api.clone().layer(Extension(ApiVersion::V1)),
+            )
+            .merge(api.layer(Extension(ApiVersion::LEGACY)));
+
+        for uri in ["/v1/version", "/version"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let body = to_bytes(response.into_body(), 64).await.unwrap();
+            assert_eq!(&body[..], b"/v1", "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_time_all_returns_every_representation() {
+        let state = make_state();
+        let app = create_router_for_test(state.clone());
+        let get = || {
+            app.clone().oneshot(
+                Request::builder()
+                    .uri("/time/all")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+        assert_eq!(get().await.unwrap().status(), 503, "before sync");
+
+        state.timebase.set_manual(1_705_320_000_000, 60); // 2024-01-15T12:00:00Z
+        let response = get().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.headers().contains_key("x-time-source"));
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let epoch_ms = json["epoch_ms"].as_i64().unwrap();
+        assert!((1_705_320_000_000..1_705_320_010_000).contains(&epoch_ms));
+        assert_eq!(json["epoch_s"], epoch_ms / 1000);
+        assert!(
+            json["iso8601"]
+                .as_str()
+                .unwrap()
+                .starts_with("2024-01-15T12:00:0")
+        );
+        assert!(
+            json["rfc2822"]
+                .as_str()
+                .unwrap()
+                .starts_with("Mon, 15 Jan 2024 12:00:0")
+        );
+        assert_eq!(json["week_number"], 3);
+        assert_eq!(json["day_of_year"], 15);
+        // A bare set_manual seeds the time base without any upstream sync.
+        assert_eq!(json["source"], "unsynced");
+        assert!(json["staleness_ms"].is_null());
+        assert_eq!(json["stale"], true);
+    }
+
+    #[tokio::test]
+    async fn test_time_and_status_negotiate_binary_encodings() {
+        use crate::encoding::{protobuf, to_cbor, to_msgpack};
+
+        let state = make_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let app = create_router_for_test(state);
+        let get = |uri: &'static str, accept: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri(uri)
+                            .header("accept", accept)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let content_type = response.headers()["content-type"]
+                    .to_str()
+                    .unwrap()
+                    .to_string();
+                let body = to_bytes(response.into_body(), 16384).await.unwrap();
+                (status, content_type, body.to_vec())
+            }
+        };
+
+        let (status, content_type, body) = get("/time", "application/msgpack").await;
+        assert_eq!(status, 200);
+        assert_eq!(content_type, "application/msgpack");
+        assert_eq!(body[0], 0x83, "three-entry map");
+        assert!(body.windows(4).any(|w| w == b"data"));
+
+        let (_, content_type, body) = get("/time", "application/cbor").await;
+        assert_eq!(content_type, "application/cbor");
+        assert_eq!(body[0], 0xa3, "three-entry map");
+
+        let (_, content_type, body) = get("/time", "application/x-protobuf").await;
+        assert_eq!(content_type, "application/protobuf");
+        assert_eq!(body[0], 0x08, "field 1, varint");
+
+        let (status, content_type, _) = get("/time?tz=UTC", "application/protobuf").await;
+        assert_eq!(status, 406);
+        assert_eq!(content_type, "application/json");
+        let (status, _, body) = get("/time?tz=UTC", "application/cbor").await;
+        assert_eq!(status, 200);
+        assert!(body.windows(9).any(|w| w == b"timezones"));
+
+        // /status mirrors its JSON body in every encoding.
+        let (_, content_type, json) = get("/status", "application/json").await;
+        assert_eq!(content_type, "application/json");
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let (_, content_type, cbor) = get("/status", "application/cbor").await;
+        assert_eq!(content_type, "application/cbor");
+        let (_, _, msgpack) = get("/status", "application/msgpack").await;
+        let (_, _, proto) = get("/status", "application/protobuf").await;
+        // Only the timing fields can move between requests; compare sizes
+        // against a fresh encoding of the JSON rather than exact bytes.
+        for (encoded, reference) in [
+            (cbor, to_cbor(&value)),
+            (msgpack, to_msgpack(&value)),
+            (proto, protobuf::to_struct(&value)),
+        ] {
+            let diff = encoded.len().abs_diff(reference.len());
+            assert!(diff <= 16, "{} vs {}", encoded.len(), reference.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_head_time_carries_time_headers() {
+        let state = make_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let app = create_router_for_test(state);
+
+        for (method, uri) in [
+            ("HEAD", "/time"),
+            ("GET", "/time/iso"),
+            ("GET", "/time.txt"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(method)
+                        .uri(uri)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200, "{method} {uri}");
+            let headers = response.headers();
+            let epoch_ms: i64 = headers["x-ntp-epoch-ms"].to_str().unwrap().parse().unwrap();
+            assert!(epoch_ms >= 1_705_320_000_000);
+            let date = headers["date"].to_str().unwrap();
+            let parsed = chrono::DateTime::parse_from_rfc2822(date).unwrap();
+            assert!(date.ends_with(" GMT"), "{date}");
+            assert_eq!(parsed.timestamp(), epoch_ms / 1000);
+            let body = to_bytes(response.into_body(), 4096).await.unwrap();
+            if method == "HEAD" {
+                assert!(body.is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_time_plain_text_is_bare_epoch_ms() {
+        let state = make_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let app = create_router_for_test(state);
+        let get = |uri: &'static str, accept: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri(uri)
+                            .header("accept", accept)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let content_type = response.headers()["content-type"]
+                    .to_str()
+                    .unwrap()
+                    .to_string();
+                let body = to_bytes(response.into_body(), 4096).await.unwrap();
+                (
+                    status,
+                    content_type,
+                    String::from_utf8(body.to_vec()).unwrap(),
+                )
+            }
+        };
+
+        for (uri, accept) in [("/time", "text/plain"), ("/time.txt", "application/json")] {
+            let (status, content_type, body) = get(uri, accept).await;
+            assert_eq!(status, 200, "{uri}");
+            assert_eq!(content_type, "text/plain; charset=utf-8");
+            let epoch_ms: i64 = body.parse().expect("bare digits");
+            assert!(epoch_ms >= 1_705_320_000_000);
+        }
+
+        let (status, _, _) = get("/time.txt?tz=UTC", "text/plain").await;
+        assert_eq!(status, 406);
+        // /status has no text form and falls back to JSON.
+        let (_, content_type, _) = get("/status", "text/plain").await;
+        assert_eq!(content_type, "application/json");
+    }
+
+    #[tokio::test]
+    async fn test_time_next_waits_until_after() {
+        let state = make_state();
+        let app = create_router_for_test(state.clone());
+        let get = |uri: String| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let response = get("/time/next?after=0".into()).await;
+        assert_eq!(response.status(), 503, "not synced");
+
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        for uri in ["/time/next", "/time/next?after=soon"] {
+            assert_eq!(get(uri.into()).await.status(), 400, "{uri}");
+        }
+
+        let after = state.timebase.now_ms().unwrap() + 150;
+        let started = Instant::now();
+        let response = get(format!("/time/next?after={after}")).await;
+        assert_eq!(response.status(), 200);
+        assert!(started.elapsed() >= Duration::from_millis(140));
+        assert!(response.headers().contains_key("x-time-source"));
+        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let data = json["data"].as_i64().unwrap();
+        assert!(
+            data > after && data < after + 1000,
+            "data={data} after={after}"
+        );
+
+        // A moment already passed answers at once.
+        let started = Instant::now();
+        assert_eq!(get("/time/next?after=0".into()).await.status(), 200);
+        assert!(started.elapsed() < Duration::from_millis(1000));
+    }
+
     #[tokio::test]
     async fn test_readyz_before_sync_returns_503() {
         let state = make_state();
@@ -272,8 +841,8 @@ mod tests {
 
         // Build NtpConfig pointing at the mock server.
         let mut config = Config::default();
-        config.ntp.servers = vec![ntp_addr.to_string()];
-        config.ntp.timeout_secs = 5;
+        config.ntp.servers = vec![crate::config::ServerConfig::new(&ntp_addr.to_string())];
+        config.ntp.timeout_ms = 5000;
         config.ntp.require_sync = true;
         config.ntp.selection.min_quorum = 1;
         let config = Arc::new(config);
@@ -432,7 +1001,7 @@ mod tests {
             .unwrap();
         assert_eq!(response.status(), 200);
 
-        let body = to_bytes(response.into_body(), 8192).await.unwrap();
+        let body = to_bytes(response.into_body(), 65536).await.unwrap();
         let text = std::str::from_utf8(&body).unwrap();
         // These counters are registered unconditionally in Metrics::new(),
         // so they must appear even before any request is processed.
@@ -441,86 +1010,560 @@ mod tests {
         }
     }
 
-    /// /performance endpoint returns 200 with the expected JSON structure.
-    /// The response shape is: `{"status": "ok", "metrics": {"requests": {...}, ...}}`.
+    /// Slow-path responses are compressed per `Accept-Encoding`; the `/time`
+    /// fast path and `HTTP_COMPRESSION=false` leave bodies untouched.
     #[tokio::test]
-    async fn test_performance_endpoint_structure() {
+    async fn test_slow_path_responses_are_compressed() {
+        let encoding_for = |app: Router, uri: &'static str, accept: &'static str| async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri(uri)
+                        .header("accept-encoding", accept)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200, "{uri}");
+            response
+                .headers()
+                .get("content-encoding")
+                .map(|v| v.to_str().unwrap().to_string())
+        };
+
         let state = make_state();
+        state.timebase.set_manual(1_705_320_000_000, 60);
         let app = create_router_for_test(state);
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/performance")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), 200);
-
-        let body = to_bytes(response.into_body(), 2048).await.unwrap();
-        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
-        let metrics = &json["metrics"];
-        assert!(metrics.is_object(), "missing top-level 'metrics' key");
-        assert!(metrics["requests"].is_object(), "missing 'requests' key");
-        assert!(
-            metrics["latency_microseconds"].is_object(),
-            "missing 'latency_microseconds' key"
+        assert_eq!(
+            encoding_for(app.clone(), "/metrics", "gzip")
+                .await
+                .as_deref(),
+            Some("gzip")
         );
-        assert!(metrics["cache"].is_object(), "missing 'cache' key");
-        // ntp_timing is null before first sync
-        assert!(
-            json["ntp_timing"].is_null(),
-            "ntp_timing should be null before sync"
+        assert_eq!(
+            encoding_for(app.clone(), "/metrics", "gzip;q=0.5, br")
+                .await
+                .as_deref(),
+            Some("br")
+        );
+        assert_eq!(
+            encoding_for(app.clone(), "/metrics", "identity").await,
+            None
         );
+        assert_eq!(encoding_for(app, "/time", "gzip").await, None);
+
+        let mut config = Config::default();
+        config.http.compression_enabled = false;
+        let app = create_router_for_test(make_state_with_config(Arc::new(config)));
+        assert_eq!(encoding_for(app, "/metrics", "gzip").await, None);
     }
 
-    /// After a successful sync, /performance must include the RFC 5905
-    /// four-tuple and packet-level fields in the `ntp_timing` object.
+    /// The `path` label must use the matched route, never the raw URI: query
+    /// strings are dropped and unknown paths collapse into `__other__`.
     #[tokio::test]
-    async fn test_performance_includes_ntp_timing_after_sync() {
-        use crate::http::state::NtpTimingSummary;
-        use crate::ntp::selection::TimingSource;
-
+    async fn test_metrics_path_label_is_normalized() {
         let state = make_state();
+        let app = create_router_for_test(state.clone());
+        for uri in ["/status?probe=1", "/scanner/random-1", "/scanner/random-2"] {
+            let _ = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+        }
 
-        // Inject timing data directly (simulating what sync_loop does).
-        *state.last_ntp_timing.write() = Some(NtpTimingSummary {
-            server: "ntp.test:123".into(),
-            t1_client_send_ms: 1_700_000_001_000,
-            t2_server_recv_ms: 1_700_000_001_010,
-            t3_server_send_ms: 1_700_000_001_011,
-            t4_client_recv_ms: 1_700_000_001_021,
-            offset_ms: 5,
-            rtt_ms: 21,
-            root_delay_ms: 10,
-            root_dispersion_ms: 5,
-            stratum: 2,
-            leap: 0,
-            precision_log2: -20,
-            reference_id: u32::from_be_bytes(*b"GPS "),
-            timing_source: TimingSource::Measured,
-        });
+        let text = state.metrics.encode();
+        assert!(text.contains(r#"path="/status""#), "{text}");
+        assert!(!text.contains("probe=1"));
+        assert!(!text.contains("/scanner/"));
 
-        let app = create_router_for_test(state);
+        let request = Request::builder().uri("/nope").body(Body::empty()).unwrap();
+        assert_eq!(
+            middleware::metrics_path_label(&request),
+            middleware::OTHER_PATH_LABEL
+        );
+    }
+
+    /// FAST_PATH_* flags toggle the fast path's request-id (default on) and
+    /// metrics (default off) layers.
+    #[tokio::test]
+    async fn test_fast_path_optional_layers() {
+        let mut config = Config::default();
+        config.http.fast_path_request_id = false;
+        let state = make_state_with_config(Arc::new(config));
+        let app = create_router_for_test(state.clone());
+        let response = app
+            .oneshot(Request::builder().uri("/time").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key("x-request-id"));
+        assert!(!state.metrics.encode().contains(r#"path="/time""#));
+
+        let mut config = Config::default();
+        config.http.fast_path_metrics = true;
+        let state = make_state_with_config(Arc::new(config));
+        let app = create_router_for_test(state.clone());
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/time").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(response.headers().contains_key("x-request-id"));
+        assert!(state.metrics.encode().contains(r#"path="/time""#));
+
+        // A caller-supplied id is propagated rather than replaced.
         let response = app
             .oneshot(
                 Request::builder()
-                    .uri("/performance")
+                    .uri("/time")
+                    .header("x-request-id", "abc-123")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["x-request-id"], "abc-123");
+    }
 
-        let body = to_bytes(response.into_body(), 2048).await.unwrap();
-        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    #[tokio::test]
+    async fn test_rate_limit_rejects_with_retry_after() {
+        use axum::extract::ConnectInfo;
+        use std::net::SocketAddr;
 
-        let timing = &json["ntp_timing"];
-        assert!(
-            timing.is_object(),
-            "ntp_timing must be an object after sync"
+        let mut config = Config::default();
+        config.http.rate_limit_per_second = 1;
+        config.http.rate_limit_burst = 2;
+        config.http.trusted_proxies = vec!["10.0.0.0/8".into()];
+        let state = make_state_with_config(Arc::new(config));
+        let app = create_router(state.clone());
+        let get = |peer: &str, forwarded_for: Option<&str>| {
+            let mut request = Request::builder().uri("/healthz");
+            if let Some(forwarded_for) = forwarded_for {
+                request = request.header("x-forwarded-for", forwarded_for);
+            }
+            let mut request = request.body(Body::empty()).unwrap();
+            let peer: SocketAddr = peer.parse().unwrap();
+            request.extensions_mut().insert(ConnectInfo(peer));
+            app.clone().oneshot(request)
+        };
+
+        for _ in 0..2 {
+            assert_eq!(get("203.0.113.1:5000", None).await.unwrap().status(), 200);
+        }
+        let response = get("203.0.113.1:5001", None).await.unwrap();
+        assert_eq!(response.status(), 429);
+        let retry_after: u64 = response.headers()["retry-after"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after >= 1);
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], 429);
+
+        // Other clients have their own bucket, including ones behind a
+        // trusted proxy; a forged header from an untrusted peer is ignored.
+        assert_eq!(get("203.0.113.2:5000", None).await.unwrap().status(), 200);
+        let proxied = get("10.0.0.5:5000", Some("198.51.100.1")).await.unwrap();
+        assert_eq!(proxied.status(), 200);
+        let forged = get("203.0.113.1:5002", Some("198.51.100.2")).await.unwrap();
+        assert_eq!(forged.status(), 429);
+
+        assert_eq!(state.metrics.http_rate_limited_total.get(), 2);
+        assert!(state.metrics.encode().contains("http_rate_limited_total"));
+    }
+
+    #[tokio::test]
+    async fn test_load_shedding_over_inflight_limit() {
+        let mut config = Config::default();
+        config.http.max_inflight_requests = 1;
+        let state = make_state_with_config(Arc::new(config));
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let app = create_router_for_test(state.clone());
+
+        // A long poll occupies the only slot until it is answered.
+        let long_poll = tokio::spawn(
+            app.clone().oneshot(
+                Request::builder()
+                    .uri("/time/next?after=9999999999999")
+                    .body(Body::empty())
+                    .unwrap(),
+            ),
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri("/time").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 503);
+        assert_eq!(response.headers()["retry-after"], "1");
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["status"], 503);
+        assert_eq!(state.metrics.http_load_shed_total.get(), 1);
+
+        // Probes and the scrape still answer while shedding.
+        for uri in ["/healthz", "/v1/healthz", "/metrics"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200, "{uri}");
+        }
+        assert_eq!(state.metrics.http_load_shed_total.get(), 1);
+
+        long_poll.abort();
+        let _ = long_poll.await;
+        let response = app
+            .oneshot(Request::builder().uri("/time").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_ip_filter_scopes() {
+        use axum::extract::ConnectInfo;
+        use std::net::SocketAddr;
+
+        let status_for = |app: Router, uri: &'static str, peer: &'static str| async move {
+            let mut request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let peer: SocketAddr = peer.parse().unwrap();
+            request.extensions_mut().insert(ConnectInfo(peer));
+            app.oneshot(request).await.unwrap().status()
+        };
+
+        let mut config = Config::default();
+        config.http.ip_allowlist = vec!["10.0.0.0/8".into()];
+        config.http.ip_filter_scope = IpFilterScope::Ops;
+        let state = make_state_with_config(Arc::new(config));
+        let app = create_router_for_test(state.clone());
+        assert_eq!(
+            status_for(app.clone(), "/healthz", "203.0.113.1:1").await,
+            200
+        );
+        assert_eq!(
+            status_for(app.clone(), "/metrics", "203.0.113.1:1").await,
+            403
+        );
+        assert_eq!(
+            status_for(app.clone(), "/v1/metrics", "203.0.113.1:1").await,
+            403
+        );
+        assert_eq!(status_for(app, "/metrics", "10.1.1.1:1").await, 200);
+        assert_eq!(state.metrics.http_ip_filtered_total.get(), 2);
+
+        let mut config = Config::default();
+        config.http.ip_denylist = vec!["198.51.100.0/24".into()];
+        let app = create_router_for_test(make_state_with_config(Arc::new(config)));
+        assert_eq!(
+            status_for(app.clone(), "/healthz", "198.51.100.9:1").await,
+            403
+        );
+        assert_eq!(
+            status_for(app.clone(), "/time", "198.51.100.9:1").await,
+            403
+        );
+        assert_eq!(status_for(app, "/healthz", "203.0.113.1:1").await, 200);
+    }
+
+    /// With OPS_ADDR, the main listener keeps only the client-facing API and
+    /// the ops listener only probes, observability and admin routes.
+    #[tokio::test]
+    async fn test_public_and_ops_surfaces_are_disjoint() {
+        let mut config = Config::default();
+        config.admin.enabled = true;
+        config.admin.token = "t".to_string();
+        let state = make_state_with_config(Arc::new(config));
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        let public = create_router_internal(state.clone(), false, Surface::Public);
+        let ops = create_router_internal(state, false, Surface::Ops);
+        let status = |app: &Router, uri: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        for uri in ["/time", "/v1/time", "/time/full", "/status", "/timezones"] {
+            assert_eq!(status(&public, uri).await, 200, "public {uri}");
+            assert_eq!(status(&ops, uri).await, 404, "ops {uri}");
+        }
+        for uri in ["/healthz", "/metrics", "/v1/performance", "/debug/ntp"] {
+            assert_eq!(status(&public, uri).await, 404, "public {uri}");
+            assert_eq!(status(&ops, uri).await, 200, "ops {uri}");
+        }
+        assert_eq!(status(&public, "/admin/servers").await, 404);
+        assert_eq!(status(&ops, "/admin/servers").await, 401);
+    }
+
+    #[tokio::test]
+    async fn test_admin_logging_updates_filter() {
+        use crate::logging::LogFilter;
+        use tracing_subscriber::{EnvFilter, Registry, reload};
+
+        let mut config = Config::default();
+        config.admin.enabled = true;
+        config.admin.token = "t".to_string();
+        let (_layer, handle) = reload::Layer::<_, Registry>::new(EnvFilter::new("info"));
+        let state = (*make_state_with_config(Arc::new(config)))
+            .clone()
+            .with_log_filter(Arc::new(LogFilter::new(handle)));
+        let app = create_router_for_test(Arc::new(state));
+        let put = |filter: &str| {
+            Request::builder()
+                .method("PUT")
+                .uri("/admin/logging")
+                .header("authorization", "Bearer t")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "filter": filter }).to_string(),
+                ))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(put("info,ntp=debug")).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let response = app.clone().oneshot(put("ntp=loud")).await.unwrap();
+        assert_eq!(response.status(), 400);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/logging")
+                    .header("authorization", "Bearer t")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["filter"], "ntp=debug,info");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_on_every_router() {
+        let mut config = Config::default();
+        config.admin.enabled = true;
+        config.admin.token = "t".to_string();
+        let app = create_router_for_test(make_state_with_config(Arc::new(config)));
+        for uri in ["/time", "/v1/time", "/healthz", "/status", "/admin/servers"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let id = response.headers()["x-request-id"].to_str().unwrap();
+            assert_eq!(id.len(), 36, "{uri}: generated UUID, got {id}");
+
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(uri)
+                        .header("x-request-id", "client-7")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.headers()["x-request-id"], "client-7", "{uri}");
+        }
+    }
+
+    /// With METRICS_AUTH_* set, /metrics and /performance require either the
+    /// bearer token or the Basic credentials; other endpoints stay open.
+    #[tokio::test]
+    async fn test_metrics_auth_required_when_configured() {
+        let mut config = Config::default();
+        config.metrics_auth.token = "scrape-token".to_string();
+        config.metrics_auth.basic = "prom:s3cret".to_string();
+        let app = create_router_for_test(make_state_with_config(Arc::new(config)));
+
+        let status_for = |uri: &'static str, auth: Option<&'static str>| {
+            let app = app.clone();
+            async move {
+                let mut req = Request::builder().uri(uri);
+                if let Some(auth) = auth {
+                    req = req.header("authorization", auth);
+                }
+                app.oneshot(req.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        assert_eq!(status_for("/metrics", None).await, 401);
+        assert_eq!(status_for("/performance", None).await, 401);
+        assert_eq!(status_for("/metrics", Some("Bearer wrong")).await, 401);
+        assert_eq!(
+            status_for("/metrics", Some("Bearer scrape-token")).await,
+            200
+        );
+        assert_eq!(
+            status_for("/performance", Some("Basic cHJvbTpzM2NyZXQ=")).await,
+            200
+        );
+        assert_eq!(
+            status_for("/metrics", Some("Basic cHJvbTp3cm9uZw==")).await,
+            401
+        );
+        assert_eq!(status_for("/healthz", None).await, 200);
+    }
+
+    /// With JWT auth configured, /stream needs any valid token (header or
+    /// `access_token` query) and the admin API needs one with the admin scope.
+    #[tokio::test]
+    async fn test_jwt_auth_for_stream_and_admin() {
+        use crate::http::jwt::tests::hs256_token;
+
+        let mut config = Config::default();
+        config.admin.enabled = true;
+        config.jwt.hs256_secret = "s3cret".to_string();
+        let app = create_router_for_test(make_state_with_config(Arc::new(config)));
+
+        let exp = chrono::Utc::now().timestamp() + 300;
+        let reader = hs256_token("s3cret", &serde_json::json!({"exp": exp, "scope": "read"}));
+        let admin = hs256_token(
+            "s3cret",
+            &serde_json::json!({"exp": exp, "scope": "read ntp:admin"}),
+        );
+        let forged = hs256_token(
+            "wrong",
+            &serde_json::json!({"exp": exp, "scope": "ntp:admin"}),
+        );
+
+        let status_for = |uri: String, token: Option<&str>| {
+            let app = app.clone();
+            let auth = token.map(|token| format!("Bearer {token}"));
+            async move {
+                let mut req = Request::builder().uri(uri);
+                if let Some(auth) = auth {
+                    req = req.header("authorization", auth);
+                }
+                app.oneshot(req.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+                    .status()
+            }
+        };
+
+        assert_eq!(status_for("/stream".into(), None).await, 401);
+        assert_eq!(status_for("/stream".into(), Some(&forged)).await, 401);
+        // Authorized, then rejected by the WebSocket extractor for lacking
+        // upgrade headers.
+        assert_ne!(status_for("/stream".into(), Some(&reader)).await, 401);
+        assert_ne!(
+            status_for(format!("/v1/stream?access_token={reader}"), None).await,
+            401
+        );
+
+        assert_eq!(status_for("/admin/servers".into(), None).await, 401);
+        assert_eq!(
+            status_for("/admin/servers".into(), Some(&reader)).await,
+            401
+        );
+        assert_eq!(
+            status_for("/admin/servers".into(), Some(&forged)).await,
+            401
+        );
+        // An empty static token must never match an empty bearer.
+        assert_eq!(status_for("/admin/servers".into(), Some("")).await, 401);
+        assert_ne!(status_for("/admin/servers".into(), Some(&admin)).await, 401);
+    }
+
+    /// /performance endpoint returns 200 with the expected JSON structure.
+    /// The response shape is: `{"status": "ok", "metrics": {"requests": {...}, ...}}`.
+    #[tokio::test]
+    async fn test_performance_endpoint_structure() {
+        let state = make_state();
+        let app = create_router_for_test(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/performance")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        let body = to_bytes(response.into_body(), 2048).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let metrics = &json["metrics"];
+        assert!(metrics.is_object(), "missing top-level 'metrics' key");
+        assert!(metrics["requests"].is_object(), "missing 'requests' key");
+        assert!(
+            metrics["latency_microseconds"].is_object(),
+            "missing 'latency_microseconds' key"
+        );
+        assert!(metrics["cache"].is_object(), "missing 'cache' key");
+        // ntp_timing is null before first sync
+        assert!(
+            json["ntp_timing"].is_null(),
+            "ntp_timing should be null before sync"
+        );
+    }
+
+    /// After a successful sync, /performance must include the RFC 5905
+    /// four-tuple and packet-level fields in the `ntp_timing` object.
+    #[tokio::test]
+    async fn test_performance_includes_ntp_timing_after_sync() {
+        use crate::http::state::NtpTimingSummary;
+        use crate::ntp::selection::TimingSource;
+
+        let state = make_state();
+
+        // Inject timing data directly (simulating what sync_loop does).
+        *state.last_ntp_timing.write() = Some(NtpTimingSummary {
+            server: "ntp.test:123".into(),
+            t1_client_send_ms: 1_700_000_001_000,
+            t2_server_recv_ms: 1_700_000_001_010,
+            t3_server_send_ms: 1_700_000_001_011,
+            t4_client_recv_ms: 1_700_000_001_021,
+            offset_ms: 5,
+            rtt_ms: 21,
+            root_delay_ms: 10,
+            root_dispersion_ms: 5,
+            stratum: 2,
+            leap: 0,
+            precision_log2: -20,
+            reference_id: u32::from_be_bytes(*b"GPS "),
+            timing_source: TimingSource::Measured,
+        });
+
+        let app = create_router_for_test(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/performance")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        let body = to_bytes(response.into_body(), 2048).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        let timing = &json["ntp_timing"];
+        assert!(
+            timing.is_object(),
+            "ntp_timing must be an object after sync"
         );
         assert_eq!(timing["server"], "ntp.test:123");
         assert_eq!(timing["t1_client_send_ms"], 1_700_000_001_000_i64);
@@ -555,6 +1598,45 @@ mod tests {
         state.record_sync_success();
     }
 
+    #[tokio::test]
+    async fn time_interval_bounds_the_served_time() {
+        let state = make_state();
+        let app = create_router_for_test(state.clone());
+        let get = || {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .uri("/time/interval")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        assert_eq!(get().await.status(), 503, "not synced");
+        // Seeded without an NTP sync: no uncertainty, so no bound.
+        state.timebase.set_manual(1_705_320_000_000, 60);
+        assert_eq!(get().await.status(), 503, "uncertainty unknown");
+
+        inject_quality(&state, 5);
+        let response = get().await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["x-time-source"], "ntp");
+        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let epoch_ms = json["epoch_ms"].as_i64().unwrap();
+        let uncertainty_ms = json["uncertainty_ms"].as_f64().unwrap();
+        // 5 ms upstream dispersion + ~1 ms precision + half the 5 ms RTT
+        assert!((8.0..10.0).contains(&uncertainty_ms), "{uncertainty_ms}");
+        let half_width = uncertainty_ms.ceil() as i64;
+        assert_eq!(json["earliest_ms"].as_i64().unwrap(), epoch_ms - half_width);
+        assert_eq!(json["latest_ms"].as_i64().unwrap(), epoch_ms + half_width);
+        assert_eq!(json["serve_state"], "ok");
+    }
+
     #[tokio::test]
     async fn status_returns_200_with_full_envelope() {
         use crate::ntp::selection::TimingSource;
@@ -593,7 +1675,7 @@ mod tests {
             .unwrap();
         assert_eq!(response.status(), 200);
 
-        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
         let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
         assert!(json["source"].is_string(), "source field missing");
@@ -606,6 +1688,16 @@ mod tests {
         );
         assert_eq!(json["stratum"], 2);
         assert_eq!(json["selected_server"], "ntp.test:123");
+        assert_eq!(json["consecutive_failures"], 0);
+        assert_eq!(json["staleness_secs"], 0);
+        let last_sync_ms = json["last_sync_ms"].as_i64().unwrap();
+        assert!(last_sync_ms >= 1_700_000_000_000, "{last_sync_ms}");
+        assert_eq!(json["last_sync"]["server"], "ntp.test:123");
+        assert_eq!(json["last_sync"]["rtt_ms"], 5.0);
+        assert_eq!(json["last_sync"]["synced_at_ms"], 1_700_000_000_000i64);
+        assert!(json["drift_ppm"].is_null(), "drift compensation is off");
+        assert!(json["config"]["servers"].is_array());
+        assert_eq!(json["config"]["require_sync"], true);
     }
 
     #[tokio::test]
@@ -624,13 +1716,536 @@ mod tests {
             .unwrap();
         assert_eq!(response.status(), 200, "/status always returns 200");
 
-        let body = to_bytes(response.into_body(), 1024).await.unwrap();
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
         let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
         assert_eq!(json["source"], "unsynced");
         assert_eq!(json["serve_state"], "unsynced");
         assert!(json["uncertainty_ms"].is_null());
         assert!(!json["ntp_synced"].as_bool().unwrap());
+        assert!(json["last_sync_ms"].is_null());
+        assert!(json["last_sync"].is_null());
+        assert!(json["staleness_secs"].is_null());
+    }
+
+    #[tokio::test]
+    async fn status_reports_quality_score() {
+        let state = make_state();
+        let app = create_router_for_test(state.clone());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/status")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["quality_score"], 0.0, "unsynced scores zero");
+
+        // Fresh, low-jitter sync but no selection diagnostics: agreement
+        // contributes nothing, the other three sub-scores are near 1.
+        inject_quality(&state, 1);
+        let score = state.compute_quality().score.unwrap();
+        assert!((0.7..0.75).contains(&score), "score={score}");
+
+        let config = Config::default();
+        let stale_ms = config.ntp.max_staleness_secs * 1000;
+        let worst = state::quality_score(
+            None,
+            f64::MAX,
+            stale_ms,
+            config.quality.serve_degraded_max_uncertainty_ms,
+            &config,
+        );
+        assert_eq!(worst, 0.0);
+    }
+
+    #[tokio::test]
+    async fn system_clock_alert_fires_once_per_excursion() {
+        use crate::ntp::selection::TimingSource;
+
+        let mut config = Config::default();
+        config.quality.system_clock_alert_ms = 1000;
+        let state = make_state_with_config(Arc::new(config));
+        assert_eq!(
+            state.check_system_clock(),
+            None,
+            "no divergence before sync"
+        );
+
+        // NTP time an hour behind the host clock.
+        let host_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        state.timebase.update(&SyncResult {
+            epoch_ms: host_ms - 3_600_000,
+            server: "ntp.test:123".into(),
+            rtt: Duration::from_millis(5),
+            instant: Instant::now(),
+            offset_ms: 0,
+            t1_client_send_ms: 0,
+            t2_server_recv_ms: 0,
+            t3_server_send_ms: 0,
+            t4_client_recv_ms: 0,
+            root_delay_ms: 10,
+            root_dispersion_ms: 1,
+            stratum: 2,
+            leap: 0,
+            precision_log2: -10,
+            reference_id: 0,
+            timing_source: TimingSource::Measured,
+        });
+
+        let divergence = state.check_system_clock().unwrap();
+        assert!((3_590_000..3_610_000).contains(&divergence), "{divergence}");
+        state.check_system_clock();
+        assert_eq!(state.metrics.time_system_clock_alerts_total.get(), 1);
+        assert_eq!(state.metrics.time_system_clock_alert.get(), 1);
+
+        let app = create_router_for_test(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/status")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["system_clock_alert"], true);
+        assert!(json["system_clock_divergence_ms"].as_i64().unwrap() > 1000);
+    }
+
+    #[tokio::test]
+    async fn clock_jump_marks_time_stale_until_resync_steps_the_base() {
+        use crate::clock_jump::ClockJump;
+        use crate::ntp::selection::TimingSource;
+        use crate::timebase::Correction;
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let mut state = Arc::unwrap_or_clone(make_state()).with_sync_requests(tx);
+        state.timebase = TimeBase::new(false)
+            .with_slew(500, 128)
+            .with_max_step(1_000, 0);
+        let state = Arc::new(state);
+        let sync = |epoch_ms| SyncResult {
+            epoch_ms,
+            server: "ntp.test:123".into(),
+            rtt: Duration::from_millis(5),
+            instant: Instant::now(),
+            offset_ms: 0,
+            t1_client_send_ms: 0,
+            t2_server_recv_ms: 0,
+            t3_server_send_ms: 0,
+            t4_client_recv_ms: 0,
+            root_delay_ms: 10,
+            root_dispersion_ms: 1,
+            stratum: 2,
+            leap: 0,
+            precision_log2: -10,
+            reference_id: 0,
+            timing_source: TimingSource::Measured,
+        };
+        let before = sync(1_000_000_000);
+        state.timebase.update(&before);
+        inject_quality(&state, 1);
+        assert_eq!(state.compute_quality().serve_state, "ok");
+
+        state.record_clock_jump(&ClockJump { gap_ms: 60_000 });
+        assert!(rx.try_recv().is_ok(), "resync requested");
+        assert_eq!(state.compute_quality().serve_state, "holdover");
+        assert_eq!(state.metrics.time_clock_jumps_total.get(), 1);
+
+        let app = create_router_for_test(state.clone());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/status")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = to_bytes(response.into_body(), 8192).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["clock_jump_detected"], true);
+
+        // A sync that landed before the base was corrected leaves the mark.
+        state.record_correction(Correction::Slewed { step_ms: 50 });
+        assert!(state.is_clock_jump_detected());
+
+        // The resync steps by the whole gap despite MAX_STEP_MS, then clears it.
+        let after = SyncResult {
+            epoch_ms: before.epoch_ms + 60_000,
+            instant: before.instant,
+            ..sync(0)
+        };
+        let correction = state.timebase.update(&after);
+        assert_eq!(correction, Correction::Stepped { step_ms: 60_000 });
+        state.record_sync_success();
+        state.record_correction(correction);
+        assert!(!state.is_clock_jump_detected());
+        assert_eq!(state.compute_quality().serve_state, "ok");
+    }
+
+    #[tokio::test]
+    async fn roughtime_alert_fires_once_per_excursion() {
+        use crate::ntp::roughtime::RoughtimeSample;
+        use crate::ntp::selection::TimingSource;
+
+        let state = make_state_with_config(Arc::new(Config::default()));
+        let sample = |midpoint_ms: i64| RoughtimeSample {
+            server: "rt.test:2002".into(),
+            midpoint_ms,
+            radius_ms: 1000,
+            rtt: Duration::from_millis(20),
+            received_unix_ms: 0,
+            received_instant: Instant::now(),
+        };
+        assert_eq!(state.check_roughtime(&sample(0)), None, "no NTP time yet");
+
+        let ntp_ms = 1_700_000_000_000;
+        state.timebase.update(&SyncResult {
+            epoch_ms: ntp_ms,
+            server: "ntp.test:123".into(),
+            rtt: Duration::from_millis(5),
+            instant: Instant::now(),
+            offset_ms: 0,
+            t1_client_send_ms: 0,
+            t2_server_recv_ms: 0,
+            t3_server_send_ms: 0,
+            t4_client_recv_ms: 0,
+            root_delay_ms: 10,
+            root_dispersion_ms: 1,
+            stratum: 2,
+            leap: 0,
+            precision_log2: -10,
+            reference_id: 0,
+            timing_source: TimingSource::Measured,
+        });
+
+        // Within radius + round trip.
+        assert_eq!(state.check_roughtime(&sample(ntp_ms + 500)), Some(0));
+        // NTP time an hour behind the signed bound.
+        let disagreement = state.check_roughtime(&sample(ntp_ms + 3_600_000)).unwrap();
+        assert!(disagreement > 3_500_000, "{disagreement}");
+        state.check_roughtime(&sample(ntp_ms + 3_600_000));
+        assert_eq!(state.metrics.roughtime_alerts_total.get(), 1);
+        assert!(!state.roughtime_alerting.lock().is_empty());
+
+        state.check_roughtime(&sample(ntp_ms));
+        assert!(state.roughtime_alerting.lock().is_empty());
+        assert_eq!(state.metrics.roughtime_alerts_total.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn clock_drift_report_summarises_system_clock_checks() {
+        let state = make_state();
+        state.timebase.update(&SyncResult {
+            epoch_ms: 1_700_000_000_000,
+            server: "ntp.test:123".into(),
+            rtt: Duration::from_millis(5),
+            instant: Instant::now(),
+            offset_ms: 0,
+            t1_client_send_ms: 0,
+            t2_server_recv_ms: 0,
+            t3_server_send_ms: 0,
+            t4_client_recv_ms: 0,
+            root_delay_ms: 10,
+            root_dispersion_ms: 1,
+            stratum: 2,
+            leap: 0,
+            precision_log2: -10,
+            reference_id: 0,
+            timing_source: crate::ntp::selection::TimingSource::Measured,
+        });
+        for _ in 0..3 {
+            state.check_system_clock();
+        }
+
+        let app = create_router_for_test(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/report/clock-drift")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["enabled"], true);
+        assert_eq!(json["window_secs"], 86_400);
+        assert_eq!(json["samples"], 3);
+        // NTP time is pinned to 2023, so the host clock is years ahead.
+        assert!(json["divergence_ms"]["min"].as_i64().unwrap() > 0);
+        assert_eq!(json["samples_over_threshold"], 3);
+    }
+
+    #[tokio::test]
+    async fn timezones_endpoints_list_and_look_up_zones() {
+        let state = make_state();
+        state.timebase.set_manual(1_705_320_000_000, 60); // 2024-01-15T12:00:00Z
+        let app = create_router_for_test(state);
+
+        let get_json = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), 1 << 20).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                )
+            }
+        };
+
+        let (status, json) = get_json("/timezones").await;
+        assert_eq!(status, 200);
+        assert_eq!(
+            json["count"].as_u64().unwrap() as usize,
+            json["zones"].as_array().unwrap().len()
+        );
+        assert!(
+            json["zones"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|z| z["zone"] == "Europe/London")
+        );
+
+        let (status, json) = get_json("/timezones/America/New_York").await;
+        assert_eq!(status, 200);
+        assert_eq!(json["utc_offset_seconds"], -18_000);
+        let local_time = json["local_time"].as_str().unwrap();
+        assert!(local_time.starts_with("2024-01-15T07:00:0") && local_time.ends_with("-05:00"));
+        assert_eq!(json["next_transition"]["at_ms"], 1_710_054_000_000i64);
+
+        let (status, _) = get_json("/timezones/Mars/Olympus_Mons").await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn worldtimeapi_compatible_endpoints() {
+        let state = make_state();
+        state.timebase.set_manual(1_705_320_000_000, 60); // 2024-01-15T12:00:00Z
+        let app = create_router_for_test(state);
+        let get_json = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), 1 << 20).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                )
+            }
+        };
+
+        let (status, json) = get_json("/api/timezone/America/New_York").await;
+        assert_eq!(status, 200);
+        assert_eq!(json["timezone"], "America/New_York");
+        assert_eq!(json["abbreviation"], "EST");
+        assert_eq!(json["utc_offset"], "-05:00");
+        assert_eq!(json["raw_offset"], -18_000);
+        assert_eq!(json["dst"], false);
+        assert!(json["dst_until"].is_null());
+        assert_eq!(json["day_of_week"], 1);
+        assert_eq!(json["day_of_year"], 15);
+        assert_eq!(json["week_number"], 3);
+        let unixtime = json["unixtime"].as_i64().unwrap();
+        assert!((1_705_320_000..1_705_320_010).contains(&unixtime));
+        assert!(
+            json["datetime"]
+                .as_str()
+                .unwrap()
+                .starts_with("2024-01-15T07:00:0")
+        );
+        assert!(json.get("client_ip").is_some());
+
+        let (status, json) = get_json("/api/timezone/Etc/UTC").await;
+        assert_eq!(status, 200);
+        assert_eq!(json["utc_offset"], "+00:00");
+
+        let (status, json) = get_json("/api/timezone/Australia").await;
+        assert_eq!(status, 200);
+        assert!(
+            json.as_array()
+                .unwrap()
+                .contains(&"Australia/Sydney".into())
+        );
+        let (_, json) = get_json("/api/timezone").await;
+        assert!(json.as_array().unwrap().contains(&"Europe/London".into()));
+
+        let (status, _) = get_json("/api/timezone/Mars/Olympus_Mons").await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn convert_endpoint_converts_timestamps() {
+        // No sync needed: conversion never reads the clock.
+        let app = create_router_for_test(make_state());
+        let get_json = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), 4096).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                )
+            }
+        };
+
+        let (status, json) =
+            get_json("/convert?value=1735459200000&from=unix_ms&to=iso8601&tz=UTC").await;
+        assert_eq!(status, 200);
+        assert_eq!(json["result"], "2024-12-29T08:00:00Z");
+        assert_eq!(json["from"], "unix_ms");
+        assert_eq!(json["tz"], "UTC");
+
+        let (_, json) = get_json("/convert?value=1735459200000").await;
+        assert_eq!(json["to"], "iso8601");
+        let (_, json) =
+            get_json("/convert?value=2024-12-29T08:00:00Z&from=iso8601&to=unix_s").await;
+        assert_eq!(json["result"], 1_735_459_200i64);
+        let (_, json) =
+            get_json("/convert?value=1735459200&from=unix_s&pattern=%25H:%25M&tz=Asia/Tehran")
+                .await;
+        assert_eq!(
+            (&json["to"], &json["result"]),
+            (&"pattern".into(), &"11:30".into())
+        );
+
+        for uri in [
+            "/convert",
+            "/convert?value=x",
+            "/convert?value=1&to=fortnights",
+            "/convert?value=1&tz=Mars/Olympus_Mons",
+            "/convert?value=1&to=unix_s&pattern=%25Y",
+        ] {
+            let (status, json) = get_json(uri).await;
+            assert_eq!(status, 400, "{uri}");
+            assert!(json["error"].is_string(), "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn debug_ntp_lists_raw_exchanges() {
+        use crate::ntp::client::NtpSample;
+
+        let state = make_state();
+        let now = Instant::now();
+        state.last_ntp_exchanges.write().insert(
+            "ntp.test:123".into(),
+            NtpSample {
+                server: "ntp.test:123".into(),
+                t1_unix_ms: 1_700_000_000_000,
+                t2_unix_ms: 1_700_000_000_010,
+                t3_unix_ms: 1_700_000_000_011,
+                t4_unix_ms: 1_700_000_000_020,
+                t1_instant: now,
+                t4_instant: now,
+                offset_ms: 0,
+                delay_ms: 19,
+                root_delay_ms: 3,
+                root_dispersion_ms: 7,
+                precision_log2: -20,
+                stratum: 1,
+                leap: 0,
+                reference_id: u32::from_be_bytes(*b"GPS\0"),
+                poll: 6,
+            },
+        );
+
+        let app = create_router_for_test(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/debug/ntp")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let server = &json["servers"][0];
+        assert_eq!(server["server"], "ntp.test:123");
+        assert_eq!(server["t3_server_send_ms"], 1_700_000_000_011i64);
+        assert_eq!(server["poll"], 6);
+        assert_eq!(server["root_dispersion_ms"], 7);
+    }
+
+    #[tokio::test]
+    async fn debug_selection_explains_last_round() {
+        use crate::config::SelectionConfig;
+        use crate::ntp::selection::{NtpResult, WeightedMedianSelector};
+
+        let state = make_state();
+        let app = create_router_for_test(state.clone());
+        let request = || {
+            Request::builder()
+                .uri("/debug/selection")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(request()).await.unwrap();
+        let body = to_bytes(response.into_body(), 4096).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(json["selection"].is_null());
+
+        let now = Instant::now();
+        let results = vec![
+            NtpResult::for_testing("a:123", 0, Duration::from_millis(10), 0, now),
+            NtpResult::for_testing("b:123", 0, Duration::from_millis(20), 5, now),
+            NtpResult::for_testing("c:123", 0, Duration::from_millis(30), 5_000, now),
+        ];
+        let config = SelectionConfig {
+            min_quorum: 2,
+            interval_selection_enabled: false,
+            ..SelectionConfig::default()
+        };
+        let output =
+            WeightedMedianSelector::select(results, &std::collections::HashMap::new(), &config);
+        *state.last_selection_diagnostics.write() = Some(output.diagnostics);
+
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body = to_bytes(response.into_body(), 8192).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let selection = &json["selection"];
+        assert_eq!(selection["selection_state"], "ok");
+        let candidates = selection["candidates"].as_array().unwrap();
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0]["server"], "a:123");
+        assert_eq!(candidates[0]["status"], "inlier");
+        assert_eq!(candidates[1]["rtt_ms"], 20);
+        assert_eq!(candidates[2]["status"], "outlier");
+        assert!(candidates[2]["distance_from_median_ms"].as_f64().unwrap() > 1000.0);
     }
 
     #[tokio::test]
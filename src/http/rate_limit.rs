@@ -0,0 +1,205 @@
+//! Per-client token-bucket rate limiting.
+//!
+//! Wraps `tower_governor` with a key extractor that knows about reverse
+//! proxies: the TCP peer is the client unless it is listed in
+//! `TRUSTED_PROXIES`, in which case the client is the right-most address in
+//! `X-Forwarded-For` that is not itself a trusted proxy (or `X-Real-IP`).
+//! Walking from the right means a client cannot pick its own key by sending
+//! a forged header — only hops appended by trusted proxies are believed.
+//!
+//! Rejections answer 429 with `Retry-After` and the usual JSON error body,
+//! and count towards `http_rate_limited_total`.
+
+use super::state::AppState;
+use crate::errors::AppError;
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{HeaderMap, Request, Response};
+use axum::response::IntoResponse;
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_governor::GovernorError;
+use tower_governor::key_extractor::KeyExtractor;
+
+/// Parse one `TRUSTED_PROXIES` entry: a CIDR (`10.0.0.0/8`) or a bare IP.
+pub fn parse_trusted_proxy(raw: &str) -> Option<IpNet> {
+    let raw = raw.trim();
+    raw.parse::<IpNet>()
+        .ok()
+        .or_else(|| raw.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+/// Keys requests by client IP, honouring forwarding headers from trusted
+/// proxies only.
+#[derive(Debug, Clone, Default)]
+pub struct ClientIpKeyExtractor {
+    trusted: Arc<Vec<IpNet>>,
+}
+
+impl ClientIpKeyExtractor {
+    /// Build from `TRUSTED_PROXIES` entries; invalid entries are rejected by
+    /// config validation and skipped here.
+    pub fn new(trusted_proxies: &[String]) -> Self {
+        Self {
+            trusted: Arc::new(
+                trusted_proxies
+                    .iter()
+                    .filter_map(|raw| parse_trusted_proxy(raw))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted.iter().any(|net| net.contains(&ip))
+    }
+
+    /// The client behind `peer`, given the request's forwarding headers.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+        // Every X-Forwarded-For line counts, in order: a proxy may append its
+        // own line rather than extend the one the client sent.  A line that
+        // is not valid UTF-8 becomes an unparseable hop.
+        let forwarded_for: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .map(|v| v.to_str().unwrap_or(""))
+            .collect();
+        if !forwarded_for.is_empty() {
+            let mut client = peer;
+            for hop in forwarded_for.join(",").rsplit(',') {
+                // A malformed hop ends the chain of hops we can vouch for.
+                let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+                    break;
+                };
+                client = ip;
+                if !self.is_trusted(ip) {
+                    break;
+                }
+            }
+            return client;
+        }
+        headers
+            .get("x-real-ip")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+}
+
+impl KeyExtractor for ClientIpKeyExtractor {
+    type Key = IpAddr;
+
+    fn extract<T>(&self, req: &Request<T>) -> Result<Self::Key, GovernorError> {
+        let peer = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip())
+            .ok_or(GovernorError::UnableToExtractKey)?;
+        Ok(self.client_ip(peer, req.headers()))
+    }
+}
+
+/// Interval at which one token is added back to a bucket, for a sustained
+/// rate of `per_second` requests a second.
+pub fn refill_period(per_second: u32) -> Duration {
+    Duration::from_secs(1) / per_second.max(1)
+}
+
+/// Turn a governor rejection into the service's JSON error response,
+/// counting rate-limited requests.
+pub fn error_response(state: &AppState, error: GovernorError) -> Response<Body> {
+    match error {
+        GovernorError::TooManyRequests { wait_time, .. } => {
+            state.metrics.http_rate_limited_total.inc();
+            // governor truncates to whole seconds; never tell a client to
+            // retry immediately while its bucket is still empty.
+            AppError::TooManyRequests {
+                retry_after_secs: wait_time.max(1),
+            }
+            .into_response()
+        }
+        other => other.into_response().map(Body::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn untrusted_peer_is_the_client() {
+        let extractor = ClientIpKeyExtractor::new(&["10.0.0.0/8".into()]);
+        let forged = headers(&[("x-forwarded-for", "1.2.3.4"), ("x-real-ip", "1.2.3.4")]);
+        assert_eq!(
+            extractor.client_ip(ip("203.0.113.9"), &forged),
+            ip("203.0.113.9")
+        );
+    }
+
+    #[test]
+    fn trusted_proxy_chain_is_walked_from_the_right() {
+        let extractor = ClientIpKeyExtractor::new(&["10.0.0.0/8".into(), "192.0.2.1".into()]);
+        let peer = ip("10.1.1.1");
+        // The left-most entry was supplied by the client and is ignored.
+        let chain = headers(&[("x-forwarded-for", "6.6.6.6, 198.51.100.7, 192.0.2.1")]);
+        assert_eq!(extractor.client_ip(peer, &chain), ip("198.51.100.7"));
+
+        let real_ip = headers(&[("x-real-ip", "198.51.100.8")]);
+        assert_eq!(extractor.client_ip(peer, &real_ip), ip("198.51.100.8"));
+        assert_eq!(extractor.client_ip(peer, &HeaderMap::new()), peer);
+
+        // Only trusted hops: the left-most is the best we know.
+        let all_trusted = headers(&[("x-forwarded-for", "10.2.2.2, 10.3.3.3")]);
+        assert_eq!(extractor.client_ip(peer, &all_trusted), ip("10.2.2.2"));
+        // A garbage hop stops the walk at the last hop we could parse.
+        let garbage = headers(&[("x-forwarded-for", "1.1.1.1, junk, 10.3.3.3")]);
+        assert_eq!(extractor.client_ip(peer, &garbage), ip("10.3.3.3"));
+    }
+
+    #[test]
+    fn separate_forwarded_for_lines_are_walked_as_one_chain() {
+        let extractor = ClientIpKeyExtractor::new(&["10.0.0.0/8".into()]);
+        let peer = ip("10.1.1.1");
+        // The client's forged line comes first; the proxy added its own.
+        let lines = headers(&[
+            ("x-forwarded-for", "6.6.6.6"),
+            ("x-forwarded-for", "198.51.100.7, 10.2.2.2"),
+        ]);
+        assert_eq!(extractor.client_ip(peer, &lines), ip("198.51.100.7"));
+
+        let forged_only_first = headers(&[
+            ("x-forwarded-for", "10.9.9.9"),
+            ("x-forwarded-for", "203.0.113.5"),
+        ]);
+        assert_eq!(
+            extractor.client_ip(peer, &forged_only_first),
+            ip("203.0.113.5")
+        );
+    }
+
+    #[test]
+    fn parses_trusted_proxy_entries() {
+        assert!(parse_trusted_proxy("10.0.0.0/8").is_some());
+        assert!(parse_trusted_proxy(" ::1 ").is_some());
+        assert!(parse_trusted_proxy("fd00::/8").is_some());
+        assert!(parse_trusted_proxy("proxy.local").is_none());
+        assert!(parse_trusted_proxy("10.0.0.0/33").is_none());
+    }
+}
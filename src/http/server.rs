@@ -0,0 +1,247 @@
+//! HTTP connection serving with configurable HTTP/2.
+//!
+//! Equivalent to `axum::serve(..).with_graceful_shutdown(..)` with
+//! `ConnectInfo<SocketAddr>`, except that the hyper connection builder is
+//! ours: `axum::serve` does not expose HTTP/2 settings.  Each connection is
+//! auto-detected as HTTP/1.1 or HTTP/2, so plaintext clients can use h2c with
+//! prior knowledge, and TLS clients negotiate `h2` through ALPN.  With
+//! `HTTP2_ENABLED=false` only HTTP/1.1 is spoken.
+//!
+//! On shutdown the listener stops accepting, in-flight connections are asked
+//! to finish (HTTP/2 GOAWAY, HTTP/1.1 `Connection: close`) and the call
+//! returns once all of them have closed.
+
+use crate::config::HttpConfig;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::serve::Listener;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use std::fmt::Display;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::{Pin, pin};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tower::{Service, ServiceExt};
+use tracing::debug;
+
+/// HTTP/1.1 + HTTP/2 connection builder with the configured HTTP/2
+/// flow-control and stream limits.
+pub fn connection_builder(config: &HttpConfig) -> auto::Builder<TokioExecutor> {
+    let mut builder = auto::Builder::new(TokioExecutor::new());
+    builder
+        .http2()
+        .max_concurrent_streams(config.http2_max_concurrent_streams)
+        .initial_stream_window_size(config.http2_initial_stream_window_size)
+        .initial_connection_window_size(config.http2_initial_connection_window_size)
+        // Extended CONNECT (RFC 8441) for WebSockets over HTTP/2.
+        .enable_connect_protocol();
+    builder
+}
+
+/// Serve `router` on `listener` until `shutdown` resolves, then drain.
+pub async fn serve<L>(
+    mut listener: L,
+    router: Router,
+    config: &HttpConfig,
+    shutdown: impl Future<Output = ()>,
+) where
+    L: Listener<Addr = SocketAddr>,
+{
+    // `auto::Builder::http1_only` has no effect on upgradeable connections,
+    // so HTTP/1.1-only serving goes straight to hyper's http1 builder.
+    let builder = config
+        .http2_enabled
+        .then(|| Arc::new(connection_builder(config)));
+    // Stamps each connection's requests with ConnectInfo<SocketAddr>, which
+    // the rate limiter and client-IP lookups read.
+    let mut make_service = router.into_make_service_with_connect_info::<SocketAddr>();
+    let mut shutdown = pin!(shutdown);
+    // Dropping `signal_tx` tells connections to drain; every connection task
+    // holds a `close_rx`, so `close_tx.closed()` resolves once all are gone.
+    let (signal_tx, signal_rx) = watch::channel(());
+    let (close_tx, close_rx) = watch::channel(());
+
+    loop {
+        let (io, remote_addr) = tokio::select! {
+            accepted = listener.accept() => accepted,
+            () = &mut shutdown => break,
+        };
+        let service = make_service
+            .call(remote_addr)
+            .await
+            .unwrap_or_else(|err| match err {})
+            .map_request(|req: Request<Incoming>| req.map(Body::new));
+        let service = TowerToHyperService::new(service);
+        let io = TokioIo::new(io);
+        let builder = builder.clone();
+        let signal = signal_rx.clone();
+        let close_rx = close_rx.clone();
+
+        tokio::spawn(async move {
+            let result = match builder {
+                Some(builder) => {
+                    let connection = pin!(builder.serve_connection_with_upgrades(io, service));
+                    drive(connection, |c| c.graceful_shutdown(), signal).await
+                }
+                None => {
+                    let connection = pin!(
+                        http1::Builder::new()
+                            .serve_connection(io, service)
+                            .with_upgrades()
+                    );
+                    drive(connection, |c| c.graceful_shutdown(), signal).await
+                }
+            };
+            if let Err(e) = result {
+                debug!(error = %e, client = %remote_addr, "Connection closed with error");
+            }
+            drop(close_rx);
+        });
+    }
+
+    drop(listener);
+    drop(signal_tx);
+    drop(close_rx);
+    close_tx.closed().await;
+}
+
+/// Run `connection` to completion, starting a graceful shutdown (HTTP/2
+/// GOAWAY, HTTP/1.1 `Connection: close`) once `signal`'s sender is dropped.
+async fn drive<C, E>(
+    mut connection: Pin<&mut C>,
+    graceful_shutdown: fn(Pin<&mut C>),
+    mut signal: watch::Receiver<()>,
+) -> Result<(), String>
+where
+    C: Future<Output = Result<(), E>>,
+    E: Display,
+{
+    let mut draining = false;
+    loop {
+        tokio::select! {
+            result = connection.as_mut() => return result.map_err(|e| e.to_string()),
+            _ = signal.changed(), if !draining => {
+                draining = true;
+                graceful_shutdown(connection.as_mut());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// HTTP/2 connection preface and an empty SETTINGS frame.
+    const H2_PREFACE: &[u8] =
+        b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n\x00\x00\x00\x04\x00\x00\x00\x00\x00";
+
+    async fn start(config: HttpConfig) -> (SocketAddr, tokio::sync::oneshot::Sender<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = Router::new().route(
+            "/",
+            get(|axum::extract::ConnectInfo(peer): axum::extract::ConnectInfo<SocketAddr>| async move {
+                peer.ip().to_string()
+            }),
+        );
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            serve(listener, router, &config, async {
+                let _ = rx.await;
+            })
+            .await
+        });
+        (addr, tx)
+    }
+
+    /// The SETTINGS frame the server opens with, as (id, value) pairs.
+    async fn server_settings(stream: &mut TcpStream) -> Vec<(u16, u32)> {
+        let mut header = [0u8; 9];
+        stream.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[3], 0x4, "first frame must be SETTINGS");
+        let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).await.unwrap();
+        payload
+            .chunks(6)
+            .map(|s| {
+                (
+                    u16::from_be_bytes([s[0], s[1]]),
+                    u32::from_be_bytes([s[2], s[3], s[4], s[5]]),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn serves_http1_and_h2c_with_configured_settings() {
+        let config = HttpConfig {
+            http2_max_concurrent_streams: 64,
+            http2_initial_stream_window_size: 262_144,
+            ..crate::config::Config::default().http
+        };
+        let (addr, _shutdown) = start(config).await;
+
+        let mut http1 = TcpStream::connect(addr).await.unwrap();
+        http1
+            .write_all(b"GET / HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        http1.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("127.0.0.1"), "{response}");
+
+        let mut h2c = TcpStream::connect(addr).await.unwrap();
+        h2c.write_all(H2_PREFACE).await.unwrap();
+        let settings = server_settings(&mut h2c).await;
+        // SETTINGS_MAX_CONCURRENT_STREAMS, SETTINGS_INITIAL_WINDOW_SIZE,
+        // SETTINGS_ENABLE_CONNECT_PROTOCOL.
+        assert!(settings.contains(&(0x3, 64)), "{settings:?}");
+        assert!(settings.contains(&(0x4, 262_144)), "{settings:?}");
+        assert!(settings.contains(&(0x8, 1)), "{settings:?}");
+    }
+
+    #[tokio::test]
+    async fn http2_can_be_disabled() {
+        let config = HttpConfig {
+            http2_enabled: false,
+            ..crate::config::Config::default().http
+        };
+        let (addr, _shutdown) = start(config).await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(H2_PREFACE).await.unwrap();
+        // The preface is treated as (malformed) HTTP/1.1 rather than answered
+        // with an HTTP/2 SETTINGS frame.
+        let mut response = [0u8; 16];
+        let read = tokio::time::timeout(
+            std::time::Duration::from_millis(300),
+            stream.read(&mut response),
+        )
+        .await;
+        if let Ok(Ok(n)) = read {
+            assert!(n == 0 || response.starts_with(b"HTTP/1.1 "), "{response:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_after_shutdown_signal() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = crate::config::Config::default().http;
+        let served = serve(listener, Router::new(), &config, async {});
+        tokio::time::timeout(std::time::Duration::from_secs(5), served)
+            .await
+            .expect("serve returns once shutdown resolves");
+    }
+}
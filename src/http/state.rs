@@ -1,11 +1,22 @@
+use crate::clock_drift::ClockDriftSeries;
+use crate::clock_jump::ClockJump;
 use crate::config::Config;
+use crate::http::jwt::JwtValidator;
+use crate::logging::LogFilter;
 use crate::metrics::SharedMetrics;
+use crate::ntp::calibration::AsymmetryEstimate;
+use crate::ntp::client::{NtpClient, NtpSample, PacketNtpClient};
+use crate::ntp::roughtime::RoughtimeSample;
 use crate::ntp::selection::{SelectionDiagnostics, TimingSource};
+use crate::ntp::sync::{NtpSyncer, SyncOutcome};
 use crate::performance::{LockFreeMetrics, TimeCache};
-use crate::timebase::TimeBase;
+use crate::streaming::StreamHub;
+use crate::timebase::{Correction, TimeBase};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::time::Instant;
+use tracing::{error, info, warn};
 
 /// RFC 5905 §8 four-tuple timing data from the most recent successful
 /// NTP sync. After P0-1/P0-2 the T2/T3 values and root fields are
@@ -39,6 +50,34 @@ pub struct NtpTimingSummary {
 // and re-exported here for convenience.
 pub use crate::ntp::SyncQuality;
 
+/// Outcome of an out-of-band sync requested through `POST /admin/sync`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ForcedSyncReport {
+    pub server: String,
+    pub offset_ms: i64,
+    pub rtt_ms: u64,
+    pub stratum: u8,
+    pub jitter_ms: u64,
+    pub quorum_size: usize,
+}
+
+impl ForcedSyncReport {
+    pub fn from_outcome(outcome: &SyncOutcome) -> Self {
+        Self {
+            server: outcome.result.server.clone(),
+            offset_ms: outcome.result.offset_ms,
+            rtt_ms: outcome.result.rtt.as_millis() as u64,
+            stratum: outcome.result.stratum,
+            jitter_ms: outcome.jitter_ms,
+            quorum_size: outcome.diagnostics.quorum_size,
+        }
+    }
+}
+
+/// Request for an immediate sync.  The sync loop answers once the result
+/// has been applied (or the sync failed, with the error message).
+pub type SyncRequest = tokio::sync::oneshot::Sender<Result<ForcedSyncReport, String>>;
+
 /// Snapshot of the current manual time override, stored in `AppState`.
 /// Populated by `POST /admin/time/override` and cleared on expiry or DELETE.
 #[derive(Debug)]
@@ -84,6 +123,11 @@ pub struct TimeQuality {
     pub serve_state: &'static str,
     /// RFC 5905 §11.2 dispersion (ms). `None` when unsynced.
     pub uncertainty_ms: Option<f64>,
+    /// How far time served at the same instant may be from UTC (ms):
+    /// `uncertainty_ms` plus any correction still being slewed in.  `None`
+    /// without NTP quality, while the time base is an unverified seed, and
+    /// under a manual override.
+    pub error_bound_ms: Option<f64>,
     /// Milliseconds since last successful sync (or since override was set). `None` when unsynced.
     pub staleness_ms: Option<u64>,
     pub stratum: Option<u8>,
@@ -93,6 +137,56 @@ pub struct TimeQuality {
     pub override_info: Option<OverrideInfo>,
     /// P1-6 selection diagnostics from the most recent sync; None until first sync or when source="manual".
     pub selection: Option<SelectionDiagnostics>,
+    /// Composite quality score in [0, 1] (see [`quality_score`]); 0 when
+    /// unsynced, `None` while a manual override is active.
+    pub score: Option<f64>,
+}
+
+/// Composite sync quality score in [0, 1] — 1 is perfect.
+///
+/// Mean of four sub-scores, each clamped to [0, 1]:
+/// - agreement: fraction of queried servers that joined the quorum
+/// - jitter: `1 − jitter / SERVE_OK_MAX_UNCERTAINTY_MS`
+/// - staleness: `1 − age / NTP_MAX_STALENESS`
+/// - uncertainty: `1 − uncertainty / SERVE_DEGRADED_MAX_UNCERTAINTY_MS`
+///
+/// Exposed as `time_quality_score` and `/status.quality_score` so dashboards
+/// can alert on one number rather than each signal separately.
+pub fn quality_score(
+    selection: Option<&SelectionDiagnostics>,
+    jitter_ms: f64,
+    staleness_ms: u64,
+    uncertainty_ms: f64,
+    config: &Config,
+) -> f64 {
+    fn inverse_ratio(value: f64, limit: f64) -> f64 {
+        if limit <= 0.0 {
+            return 0.0;
+        }
+        (1.0 - value / limit).clamp(0.0, 1.0)
+    }
+
+    let agreement = selection
+        .map(|s| {
+            let queried = s.candidate_count + s.rejected_count;
+            if queried == 0 {
+                0.0
+            } else {
+                (s.quorum_size as f64 / queried as f64).min(1.0)
+            }
+        })
+        .unwrap_or(0.0);
+    let jitter = inverse_ratio(jitter_ms, config.quality.serve_ok_max_uncertainty_ms);
+    let staleness = inverse_ratio(
+        staleness_ms as f64,
+        config.ntp.max_staleness_secs as f64 * 1000.0,
+    );
+    let uncertainty = inverse_ratio(
+        uncertainty_ms,
+        config.quality.serve_degraded_max_uncertainty_ms,
+    );
+
+    (agreement + jitter + staleness + uncertainty) / 4.0
 }
 
 #[derive(Clone)]
@@ -116,11 +210,70 @@ pub struct AppState {
     pub last_sync_quality: Arc<parking_lot::RwLock<Option<SyncQuality>>>,
     /// P1-6 selection diagnostics from the most recent sync (success or failure).
     pub last_selection_diagnostics: Arc<parking_lot::RwLock<Option<SelectionDiagnostics>>>,
+    /// Raw most-recent NTP exchange per upstream server, for `/debug/ntp`.
+    /// Refreshed from the syncer's stats after every sync attempt.
+    pub last_ntp_exchanges: Arc<parking_lot::RwLock<HashMap<String, NtpSample>>>,
+    /// Fitted path asymmetry per upstream server, for `/status`.  Refreshed
+    /// with `last_ntp_exchanges`; empty unless asymmetry estimation is enabled.
+    pub path_asymmetry: Arc<parking_lot::RwLock<HashMap<String, AsymmetryEstimate>>>,
+    /// Host clock minus NTP time (ms) from the latest system-clock check.
+    /// `None` until the first sync.
+    pub system_clock_divergence_ms: Arc<parking_lot::RwLock<Option<i64>>>,
+    /// True while the divergence exceeds `SYSTEM_CLOCK_DIVERGENCE_ALERT_MS`.
+    pub system_clock_alert: Arc<AtomicBool>,
+    /// Set when a suspend/resume is detected: the monotonic clock missed
+    /// time, so served time is stale until a sync has stepped it right.
+    pub clock_jump_detected: Arc<AtomicBool>,
+    /// Roughtime servers whose signed bound currently disagrees with NTP
+    /// time by more than `ROUGHTIME_MAX_DISAGREEMENT_MS`.
+    pub roughtime_alerting: Arc<parking_lot::Mutex<HashSet<String>>>,
+    /// Divergence history over `SYSTEM_CLOCK_SHADOW_WINDOW_SECS`, for
+    /// `/report/clock-drift`.
+    pub clock_drift: Arc<parking_lot::Mutex<ClockDriftSeries>>,
     /// Active manual time override state (P1-7).  `None` when no override is set.
     pub override_state: Arc<parking_lot::RwLock<Option<ManualOverrideState>>>,
     /// Handle to the background expiry task for the current override.
     /// Aborted and replaced on each new POST, aborted on DELETE.
     pub override_task: Arc<parking_lot::Mutex<Option<tokio::task::AbortHandle>>>,
+    /// Client used by `/health/deep` for its live NTP exchange.
+    pub ntp_client: Arc<dyn NtpClient>,
+    /// Live syncer whose server set `/admin/servers` changes.  `None` in
+    /// tests that never sync.
+    pub ntp_syncer: Option<Arc<NtpSyncer>>,
+    /// Channel to the sync loop for `POST /admin/sync`.  `None` when no
+    /// sync loop is running (most tests).
+    pub sync_requests: Option<tokio::sync::mpsc::Sender<SyncRequest>>,
+    /// Reloadable tracing filter for `PUT /admin/logging`.  `None` when the
+    /// global subscriber was not installed by `main` (tests).
+    pub log_filter: Option<Arc<LogFilter>>,
+    /// Most recent `/health/deep` result, reused within
+    /// `DEEP_HEALTH_MIN_INTERVAL_SECS`.  The async lock also keeps
+    /// concurrent callers from starting parallel probes.
+    pub last_deep_health: Arc<tokio::sync::Mutex<Option<DeepHealthResult>>>,
+    /// JWT validator for `/stream` and the admin API.  `None` unless
+    /// `JWT_HS256_SECRET` or `JWT_JWKS_URL` is set.
+    pub jwt: Option<Arc<JwtValidator>>,
+    /// Set on SIGTERM / Ctrl+C; `/readyz` answers 503 from then on so load
+    /// balancers stop routing here while in-flight requests drain.
+    pub shutting_down: Arc<AtomicBool>,
+    /// Flips to `true` when the listeners close, after `shutting_down`;
+    /// open `/stream` connections watch it to close with "server shutting
+    /// down".
+    pub shutdown_signal: Arc<tokio::sync::watch::Sender<bool>>,
+    /// Shared tick schedules of `/stream` and the gRPC streams, one per
+    /// interval and format; each is started by its first subscriber.
+    pub stream_hub: Arc<StreamHub>,
+    /// `/stream` connections holding a `WS_MAX_CONNECTIONS` slot, counted
+    /// from the accepted handshake until the socket closes.
+    pub stream_connections: Arc<AtomicUsize>,
+}
+
+/// Outcome of one live `/health/deep` probe.
+#[derive(Debug, Clone)]
+pub struct DeepHealthResult {
+    pub checked_at: Instant,
+    pub healthy: bool,
+    pub body: serde_json::Value,
 }
 
 impl AppState {
@@ -131,6 +284,11 @@ impl AppState {
         time_cache: Arc<TimeCache>,
         perf_metrics: Arc<LockFreeMetrics>,
     ) -> Self {
+        let clock_drift = ClockDriftSeries::new(
+            config.quality.system_clock_shadow_window_secs,
+            config.quality.system_clock_check_interval_secs,
+        );
+        let jwt = JwtValidator::new(&config.jwt).map(Arc::new);
         Self {
             config,
             timebase,
@@ -143,16 +301,107 @@ impl AppState {
             last_ntp_timing: Arc::new(parking_lot::RwLock::new(None)),
             last_sync_quality: Arc::new(parking_lot::RwLock::new(None)),
             last_selection_diagnostics: Arc::new(parking_lot::RwLock::new(None)),
+            last_ntp_exchanges: Arc::new(parking_lot::RwLock::new(HashMap::new())),
+            path_asymmetry: Arc::new(parking_lot::RwLock::new(HashMap::new())),
+            system_clock_divergence_ms: Arc::new(parking_lot::RwLock::new(None)),
+            system_clock_alert: Arc::new(AtomicBool::new(false)),
+            clock_jump_detected: Arc::new(AtomicBool::new(false)),
+            roughtime_alerting: Arc::new(parking_lot::Mutex::new(HashSet::new())),
+            clock_drift: Arc::new(parking_lot::Mutex::new(clock_drift)),
             override_state: Arc::new(parking_lot::RwLock::new(None)),
             override_task: Arc::new(parking_lot::Mutex::new(None)),
+            ntp_client: Arc::new(PacketNtpClient::default()),
+            ntp_syncer: None,
+            sync_requests: None,
+            log_filter: None,
+            last_deep_health: Arc::new(tokio::sync::Mutex::new(None)),
+            jwt,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            shutdown_signal: Arc::new(tokio::sync::watch::Sender::new(false)),
+            stream_hub: Arc::new(StreamHub::default()),
+            stream_connections: Arc::new(AtomicUsize::new(0)),
         }
     }
 
+    /// Replace the NTP client used for live health probes.
+    pub fn with_ntp_client(mut self, client: Arc<dyn NtpClient>) -> Self {
+        self.ntp_client = client;
+        self
+    }
+
+    /// Expose `syncer` to the admin server-set endpoints.
+    pub fn with_ntp_syncer(mut self, syncer: Arc<NtpSyncer>) -> Self {
+        self.ntp_syncer = Some(syncer);
+        self
+    }
+
+    /// Fail `/readyz` without closing streams yet; see
+    /// [`AppState::shutting_down`].
+    pub fn fail_readiness(&self) {
+        self.shutting_down.store(true, Ordering::Release);
+    }
+
+    /// Mark the service as draining and tell open streams to close.
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::Release);
+        self.shutdown_signal.send_replace(true);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Acquire)
+    }
+
+    /// Let `POST /admin/sync` reach the sync loop through `requests`.
+    pub fn with_sync_requests(mut self, requests: tokio::sync::mpsc::Sender<SyncRequest>) -> Self {
+        self.sync_requests = Some(requests);
+        self
+    }
+
+    /// Let `PUT /admin/logging` change the installed tracing filter.
+    pub fn with_log_filter(mut self, filter: Arc<LogFilter>) -> Self {
+        self.log_filter = Some(filter);
+        self
+    }
+
     pub fn record_sync_success(&self) {
         *self.last_sync_time.write() = Some(Instant::now());
         *self.consecutive_failures.write() = 0;
     }
 
+    /// Clear the clock-jump mark once `correction` has put the time base
+    /// right: the base is verified again and the step was taken in full
+    /// rather than slewed.
+    pub fn record_correction(&self, correction: Correction) {
+        if self.timebase.is_verified() && !matches!(correction, Correction::Slewed { .. }) {
+            self.clock_jump_detected.store(false, Ordering::Release);
+        }
+    }
+
+    /// Handle a detected suspend/resume: invalidate the time base so the
+    /// next sync steps it by the gap (slew and `MAX_STEP_MS` do not apply),
+    /// mark served time stale and ask the sync loop for an immediate resync.
+    /// The time base lags true time by up to the gap until that sync lands.
+    pub fn record_clock_jump(&self, jump: &ClockJump) {
+        self.timebase.invalidate();
+        self.clock_jump_detected.store(true, Ordering::Release);
+        self.metrics.time_clock_jumps_total.inc();
+        warn!(
+            gap_ms = jump.gap_ms,
+            "Suspend/resume detected; marking time stale and resyncing"
+        );
+        if let Some(requests) = &self.sync_requests {
+            // Nobody waits for the report; a full queue already has a sync
+            // pending.
+            let (reply, _) = tokio::sync::oneshot::channel();
+            let _ = requests.try_send(reply);
+        }
+    }
+
+    /// Whether a clock jump has been detected and not yet corrected.
+    pub fn is_clock_jump_detected(&self) -> bool {
+        self.clock_jump_detected.load(Ordering::Acquire)
+    }
+
     pub fn record_sync_failure(&self) {
         *self.consecutive_failures.write() += 1;
     }
@@ -168,6 +417,94 @@ impl AppState {
         *self.consecutive_failures.read()
     }
 
+    /// Compare the host clock against NTP time, publish the divergence and
+    /// raise/clear the system-clock alert.  Consumers that still read the host
+    /// clock directly rely on this to learn when it is wrong.
+    ///
+    /// The alert fires (error log + `time_system_clock_alerts_total`) once per
+    /// excursion beyond `SYSTEM_CLOCK_DIVERGENCE_ALERT_MS`, and logs again on
+    /// recovery.  Returns the divergence, or `None` before the first sync.
+    pub fn check_system_clock(&self) -> Option<i64> {
+        let divergence_ms = self.timebase.system_clock_divergence_ms()?;
+        *self.system_clock_divergence_ms.write() = Some(divergence_ms);
+        if let Some(ntp_ms) = self.timebase.ntp_base_now_ms() {
+            self.clock_drift.lock().record(ntp_ms, divergence_ms);
+        }
+        self.metrics
+            .time_system_clock_divergence_milliseconds
+            .set(divergence_ms);
+
+        let threshold_ms = self.config.quality.system_clock_alert_ms;
+        let alerting = threshold_ms > 0 && divergence_ms.unsigned_abs() > threshold_ms;
+        let was_alerting = self.system_clock_alert.swap(alerting, Ordering::AcqRel);
+        if alerting && !was_alerting {
+            self.metrics.time_system_clock_alerts_total.inc();
+            error!(
+                divergence_ms,
+                threshold_ms, "System clock diverges from NTP time beyond alert threshold"
+            );
+        } else if !alerting && was_alerting {
+            info!(
+                divergence_ms,
+                threshold_ms, "System clock back within alert threshold of NTP time"
+            );
+        }
+        self.metrics
+            .time_system_clock_alert
+            .set(if alerting { 1 } else { 0 });
+
+        Some(divergence_ms)
+    }
+
+    /// Compare NTP time against a verified Roughtime sample and return how
+    /// far it lies outside the signed bound (ms); `None` before the first
+    /// sync.  Like the system-clock alert, the alert fires (error log +
+    /// `roughtime_alerts_total`) once per excursion per server.
+    pub fn check_roughtime(&self, sample: &RoughtimeSample) -> Option<u64> {
+        let since_reply = sample.received_instant.elapsed().as_millis() as i64;
+        let ntp_ms = self.timebase.ntp_base_now_ms()? - since_reply;
+        let disagreement_ms = sample.disagreement_ms(ntp_ms);
+        let label = crate::metrics::ServerLabel {
+            server: sample.server.clone(),
+        };
+        self.metrics
+            .roughtime_offset_milliseconds
+            .get_or_create(&label)
+            .set(ntp_ms - sample.midpoint_ms);
+
+        let threshold_ms = self.config.roughtime.max_disagreement_ms;
+        let alerting = disagreement_ms > threshold_ms;
+        let was_alerting = if alerting {
+            !self.roughtime_alerting.lock().insert(sample.server.clone())
+        } else {
+            self.roughtime_alerting.lock().remove(&sample.server)
+        };
+        if alerting && !was_alerting {
+            self.metrics.roughtime_alerts_total.inc();
+            error!(
+                server = %sample.server,
+                ntp_ms,
+                midpoint_ms = sample.midpoint_ms,
+                radius_ms = sample.radius_ms,
+                disagreement_ms,
+                threshold_ms,
+                "NTP time disagrees with signed Roughtime bound; possible NTP spoofing"
+            );
+        } else if !alerting && was_alerting {
+            info!(
+                server = %sample.server,
+                disagreement_ms,
+                threshold_ms, "NTP time back within Roughtime bound"
+            );
+        }
+        self.metrics
+            .roughtime_alert
+            .get_or_create(&label)
+            .set(if alerting { 1 } else { 0 });
+
+        Some(disagreement_ms)
+    }
+
     /// Compute the current time-quality envelope.
     ///
     /// State machine (source / serve_state):
@@ -186,6 +523,25 @@ impl AppState {
     /// not via the HTTP status code.  HTTP 503 is reserved for UNSYNCED
     /// (when `REQUIRE_SYNC=true`) or STOPPED (strict mode only).
     pub fn compute_quality(&self) -> TimeQuality {
+        self.compute_quality_at(Instant::now())
+    }
+
+    /// Publish the current quality score and error bound.  The score gauge
+    /// reads 0 whenever there is no score (manual override), so `/metrics`
+    /// never keeps a stale NTP value `/status` no longer reports.
+    pub fn update_quality_metrics(&self) {
+        let quality = self.compute_quality();
+        self.metrics
+            .time_quality_score
+            .set(quality.score.unwrap_or(0.0));
+        if let Some(bound) = quality.error_bound_ms {
+            self.metrics.time_error_bound_milliseconds.set(bound);
+        }
+    }
+
+    /// [`Self::compute_quality`] as of `at`, the instant the time it
+    /// describes was read.
+    pub fn compute_quality_at(&self, at: Instant) -> TimeQuality {
         // ── 1. Manual override (highest priority) ─────────────────────────────
         if self.timebase.is_manual_active() {
             let guard = self.override_state.read();
@@ -207,12 +563,14 @@ impl AppState {
                     source: "manual",
                     serve_state: "ok",
                     uncertainty_ms: Some(self.config.admin.dispersion_ms as f64),
+                    error_bound_ms: None,
                     staleness_ms: Some(age_ms as u64),
                     stratum: Some(2),
                     selected_server: None,
                     leap: Some(0),
                     override_info: Some(override_info),
                     selection: None,
+                    score: None,
                 };
             }
         }
@@ -220,10 +578,17 @@ impl AppState {
         // ── 2. NTP quality available ──────────────────────────────────────────
         let quality_guard = self.last_sync_quality.read();
         if let Some(q) = quality_guard.as_ref() {
-            let uncertainty_ms = q.compute_dispersion_ms();
-            let age_ms = q.last_sync_instant.elapsed().as_millis() as u64;
+            let uncertainty_ms = q.dispersion_ms_at(at);
+            let error_bound_ms = self
+                .timebase
+                .is_verified()
+                .then(|| uncertainty_ms + self.timebase.unslewed_ms_at(at));
+            let age_ms = at
+                .saturating_duration_since(q.last_sync_instant)
+                .as_millis() as u64;
             let age_secs = age_ms / 1000;
-            let is_stale = age_secs > self.config.ntp.max_staleness_secs;
+            let is_stale =
+                age_secs > self.config.ntp.max_staleness_secs || self.is_clock_jump_detected();
             let ok_max = self.config.quality.serve_ok_max_uncertainty_ms;
             let degraded_max = self.config.quality.serve_degraded_max_uncertainty_ms;
 
@@ -246,16 +611,27 @@ impl AppState {
                 }
             };
 
+            let selection = self.last_selection_diagnostics.read().clone();
+            let score = quality_score(
+                selection.as_ref(),
+                q.jitter_ms as f64,
+                age_ms,
+                uncertainty_ms,
+                &self.config,
+            );
+
             return TimeQuality {
                 source,
                 serve_state,
                 uncertainty_ms: Some(uncertainty_ms),
+                error_bound_ms,
                 staleness_ms: Some(age_ms),
                 stratum: Some(q.stratum),
                 selected_server: Some(q.selected_server.clone()),
                 leap: Some(q.leap),
                 override_info: None,
-                selection: self.last_selection_diagnostics.read().clone(),
+                selection,
+                score: Some(score),
             };
         }
         drop(quality_guard);
@@ -267,12 +643,14 @@ impl AppState {
                 source: "holdover",
                 serve_state: "holdover",
                 uncertainty_ms: None,
+                error_bound_ms: None,
                 staleness_ms: None,
                 stratum: None,
                 selected_server: None,
                 leap: None,
                 override_info: None,
                 selection: self.last_selection_diagnostics.read().clone(),
+                score: Some(0.0),
             };
         }
 
@@ -281,12 +659,14 @@ impl AppState {
             source: "unsynced",
             serve_state: "unsynced",
             uncertainty_ms: None,
+            error_bound_ms: None,
             staleness_ms: None,
             stratum: None,
             selected_server: None,
             leap: None,
             override_info: None,
             selection: self.last_selection_diagnostics.read().clone(),
+            score: Some(0.0),
         }
     }
 }
@@ -0,0 +1,311 @@
+//! Native HTTPS termination (`TLS_CERT_FILE` / `TLS_KEY_FILE`).
+//!
+//! [`TlsListener`] wraps any `axum::serve` listener (normally the
+//! [`CountingListener`](super::listener::CountingListener)) and hands
+//! `axum::serve` connections whose TLS handshake has already completed.
+//! Handshakes run in their own tasks with a timeout, so one slow or
+//! malicious client cannot hold up accepting the next connection.
+//!
+//! The certificate is served through [`CertStore`], a rustls cert resolver
+//! whose key pair can be swapped at runtime: `reload_if_changed` re-reads the
+//! PEM files when their modification time changes, and new handshakes pick up
+//! the new certificate while established connections are unaffected.
+
+use crate::metrics::SharedMetrics;
+use arc_swap::ArcSwap;
+use axum::serve::Listener;
+use rustls::ServerConfig;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::server::TlsStream;
+use tracing::debug;
+
+/// Longest a client may take to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Handshaken connections waiting for `axum::serve` to pick them up.
+const READY_QUEUE: usize = 128;
+
+/// Certificate chain and key loaded from PEM files, reloadable in place.
+#[derive(Debug)]
+pub struct CertStore {
+    cert_file: PathBuf,
+    key_file: PathBuf,
+    provider: Arc<CryptoProvider>,
+    current: ArcSwap<CertifiedKey>,
+    /// Modification times of (cert, key) at the last successful load.
+    loaded_mtimes: parking_lot::Mutex<(Option<SystemTime>, Option<SystemTime>)>,
+}
+
+impl CertStore {
+    /// Load the certificate chain and key; fails if either file is missing,
+    /// unparseable, or the key does not match the certificate.
+    pub fn load(cert_file: &str, key_file: &str) -> anyhow::Result<Self> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let cert_file = PathBuf::from(cert_file);
+        let key_file = PathBuf::from(key_file);
+        let mtimes = (modified(&cert_file), modified(&key_file));
+        let key = read_key_pair(&cert_file, &key_file, &provider)?;
+        Ok(Self {
+            cert_file,
+            key_file,
+            provider,
+            current: ArcSwap::from_pointee(key),
+            loaded_mtimes: parking_lot::Mutex::new(mtimes),
+        })
+    }
+
+    /// Re-read the files if either changed since the last load.  Returns
+    /// whether a new certificate was installed; on error the current one
+    /// stays in service.
+    pub fn reload_if_changed(&self) -> anyhow::Result<bool> {
+        let mtimes = (modified(&self.cert_file), modified(&self.key_file));
+        if *self.loaded_mtimes.lock() == mtimes {
+            return Ok(false);
+        }
+        let key = read_key_pair(&self.cert_file, &self.key_file, &self.provider)?;
+        self.current.store(Arc::new(key));
+        *self.loaded_mtimes.lock() = mtimes;
+        Ok(true)
+    }
+
+    /// rustls server config serving this store's current certificate, with
+    /// ALPN for HTTP/1.1 and, if `http2`, HTTP/2.
+    pub fn server_config(self: &Arc<Self>, http2: bool) -> anyhow::Result<ServerConfig> {
+        let mut config = ServerConfig::builder_with_provider(self.provider.clone())
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_cert_resolver(self.clone());
+        config.alpn_protocols = if http2 {
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        } else {
+            vec![b"http/1.1".to_vec()]
+        };
+        Ok(config)
+    }
+}
+
+impl ResolvesServerCert for CertStore {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.load_full())
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn read_key_pair(
+    cert_file: &Path,
+    key_file: &Path,
+    provider: &CryptoProvider,
+) -> anyhow::Result<CertifiedKey> {
+    let certs = CertificateDer::pem_file_iter(cert_file)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow::anyhow!("reading {}: {e}", cert_file.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("{} contains no certificates", cert_file.display());
+    }
+    let key = PrivateKeyDer::from_pem_file(key_file)
+        .map_err(|e| anyhow::anyhow!("reading {}: {e}", key_file.display()))?;
+    CertifiedKey::from_der(certs, key, provider)
+        .map_err(|e| anyhow::anyhow!("invalid TLS key pair: {e}"))
+}
+
+/// Listener that yields connections after a successful TLS handshake.
+///
+/// Failed or timed-out handshakes are dropped and counted in
+/// `http_tls_handshake_failures_total`.
+pub struct TlsListener<L: Listener> {
+    ready: mpsc::Receiver<(TlsStream<L::Io>, L::Addr)>,
+    local_addr: L::Addr,
+    accept_task: AbortHandle,
+}
+
+impl<L> TlsListener<L>
+where
+    L: Listener,
+    L::Addr: Clone + Sync + 'static,
+{
+    /// Start accepting from `inner`; must be called inside a tokio runtime.
+    pub fn new(mut inner: L, config: ServerConfig, metrics: SharedMetrics) -> io::Result<Self> {
+        let local_addr = inner.local_addr()?;
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        let (tx, ready) = mpsc::channel(READY_QUEUE);
+        let accept_task = tokio::spawn(async move {
+            loop {
+                let (io, addr) = inner.accept().await;
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                let metrics = metrics.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(io)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = tx.send((stream, addr)).await;
+                        }
+                        Ok(Err(e)) => {
+                            metrics.http_tls_handshake_failures_total.inc();
+                            debug!(error = %e, "TLS handshake failed");
+                        }
+                        Err(_) => {
+                            metrics.http_tls_handshake_failures_total.inc();
+                            debug!("TLS handshake timed out");
+                        }
+                    }
+                });
+            }
+        })
+        .abort_handle();
+        Ok(Self {
+            ready,
+            local_addr,
+            accept_task,
+        })
+    }
+}
+
+impl<L: Listener> Drop for TlsListener<L> {
+    fn drop(&mut self) {
+        self.accept_task.abort();
+    }
+}
+
+impl<L> Listener for TlsListener<L>
+where
+    L: Listener,
+    L::Addr: Clone + Sync + 'static,
+{
+    type Io = TlsStream<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.ready.recv().await {
+            Some(connection) => connection,
+            // The accept task only stops when this listener is dropped.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use rustls::pki_types::ServerName;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Writes a fresh self-signed `localhost` cert and key into `dir`,
+    /// returning the certificate DER.
+    fn write_self_signed(dir: &Path) -> CertificateDer<'static> {
+        let generated = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        std::fs::write(dir.join("cert.pem"), generated.cert.pem()).unwrap();
+        std::fs::write(dir.join("key.pem"), generated.signing_key.serialize_pem()).unwrap();
+        generated.cert.der().clone()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tls-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn load(dir: &Path) -> anyhow::Result<CertStore> {
+        CertStore::load(
+            dir.join("cert.pem").to_str().unwrap(),
+            dir.join("key.pem").to_str().unwrap(),
+        )
+    }
+
+    #[test]
+    fn reloads_only_when_files_change() {
+        let dir = temp_dir("reload");
+        let first = write_self_signed(&dir);
+        let store = load(&dir).unwrap();
+        assert_eq!(store.current.load().cert[0], first);
+        assert!(!store.reload_if_changed().unwrap());
+
+        std::thread::sleep(Duration::from_millis(20));
+        let second = write_self_signed(&dir);
+        assert!(store.reload_if_changed().unwrap());
+        assert_eq!(store.current.load().cert[0], second);
+
+        // A key that does not match the certificate is refused and the
+        // previous pair stays in service.
+        std::thread::sleep(Duration::from_millis(20));
+        let other = rcgen::KeyPair::generate().unwrap();
+        std::fs::write(dir.join("key.pem"), other.serialize_pem()).unwrap();
+        assert!(store.reload_if_changed().is_err());
+        assert_eq!(store.current.load().cert[0], second);
+        assert!(load(&dir).is_err());
+
+        assert!(CertStore::load("/nonexistent/cert.pem", "/nonexistent/key.pem").is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn serves_https_and_counts_failed_handshakes() {
+        let dir = temp_dir("serve");
+        let cert = write_self_signed(&dir);
+        let store = Arc::new(load(&dir).unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let metrics = Arc::new(Metrics::new());
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listener =
+            TlsListener::new(tcp, store.server_config(true).unwrap(), metrics.clone()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = axum::Router::new().route("/", axum::routing::get(|| async { "secure" }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // A plaintext client fails the handshake without blocking others.
+        let mut plain = TcpStream::connect(addr).await.unwrap();
+        plain.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let client = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+        let connector = tokio_rustls::TlsConnector::from(Arc::new(client));
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let server_name = ServerName::try_from("localhost").unwrap();
+        let mut tls = connector.connect(server_name, tcp).await.unwrap();
+        tls.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        tls.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("secure"), "{response}");
+
+        let mut rest = Vec::new();
+        let _ = plain.read_to_end(&mut rest).await;
+        // The failure is counted just after the connection is dropped.
+        for _ in 0..100 {
+            if metrics.http_tls_handshake_failures_total.get() > 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(metrics.http_tls_handshake_failures_total.get(), 1);
+    }
+}
@@ -0,0 +1,145 @@
+//! Head-sampled request tracing for the slow-path `TraceLayer`.
+//!
+//! At high request rates a span plus a "finished processing request" event
+//! per request is too expensive.  [`SampledMakeSpan`] decides once per
+//! request (head sampling, `TRACE_SAMPLE_RATIO`) whether to create the span;
+//! unsampled requests get `Span::none()` and the response event is skipped.
+//! Failures (5xx, timeouts) are still logged for unsampled requests when
+//! `TRACE_ALWAYS_SAMPLE_ERRORS=true`.
+//!
+//! Sampled spans carry the request's `x-request-id` (stamped by the
+//! request-id layer outside the `TraceLayer`), so every event logged while
+//! serving it can be matched to the id echoed to the client.
+
+use axum::http::{Request, Response};
+use std::fmt;
+use std::time::Duration;
+use tower_http::trace::{DefaultOnFailure, DefaultOnResponse, MakeSpan, OnFailure, OnResponse};
+use tracing::{Level, Span};
+
+/// Head-sampling decision for a single request.
+pub fn should_sample(ratio: f64) -> bool {
+    if ratio >= 1.0 {
+        true
+    } else if ratio <= 0.0 {
+        false
+    } else {
+        rand::random::<f64>() < ratio
+    }
+}
+
+/// An INFO `request` span (method, URI, version, request id) for sampled
+/// requests, `Span::none()` otherwise.
+#[derive(Clone, Debug)]
+pub struct SampledMakeSpan {
+    ratio: f64,
+}
+
+impl SampledMakeSpan {
+    pub fn new(ratio: f64) -> Self {
+        Self { ratio }
+    }
+}
+
+impl<B> MakeSpan<B> for SampledMakeSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        if !should_sample(self.ratio) {
+            return Span::none();
+        }
+        let request_id = request
+            .headers()
+            .get("x-request-id")
+            .and_then(|id| id.to_str().ok())
+            .unwrap_or("");
+        tracing::info_span!(
+            "request",
+            method = %request.method(),
+            uri = %request.uri(),
+            version = ?request.version(),
+            request_id = %request_id,
+        )
+    }
+}
+
+/// `DefaultOnResponse` at INFO, emitted only for sampled requests.
+#[derive(Clone, Debug)]
+pub struct SampledOnResponse {
+    inner: DefaultOnResponse,
+}
+
+impl Default for SampledOnResponse {
+    fn default() -> Self {
+        Self {
+            inner: DefaultOnResponse::new().level(Level::INFO),
+        }
+    }
+}
+
+impl<B> OnResponse<B> for SampledOnResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        if !span.is_none() {
+            self.inner.on_response(response, latency, span);
+        }
+    }
+}
+
+/// `DefaultOnFailure`, emitted for sampled requests and, when
+/// `always_sample_errors` is set, for unsampled ones too.
+#[derive(Clone, Debug)]
+pub struct SampledOnFailure {
+    inner: DefaultOnFailure,
+    always_sample_errors: bool,
+}
+
+impl SampledOnFailure {
+    pub fn new(always_sample_errors: bool) -> Self {
+        Self {
+            inner: DefaultOnFailure::new(),
+            always_sample_errors,
+        }
+    }
+}
+
+impl<FailureClass: fmt::Display> OnFailure<FailureClass> for SampledOnFailure {
+    fn on_failure(&mut self, classification: FailureClass, latency: Duration, span: &Span) {
+        if !span.is_none() || self.always_sample_errors {
+            self.inner.on_failure(classification, latency, span);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_ratio_bounds() {
+        assert!((0..100).all(|_| should_sample(1.0)));
+        assert!((0..100).all(|_| !should_sample(0.0)));
+        let hits = (0..10_000).filter(|_| should_sample(0.5)).count();
+        assert!((4_000..6_000).contains(&hits), "hits={hits}");
+    }
+
+    #[test]
+    fn unsampled_requests_get_no_span() {
+        let request = Request::builder().uri("/status").body(()).unwrap();
+        assert!(SampledMakeSpan::new(0.0).make_span(&request).is_none());
+    }
+
+    #[test]
+    fn sampled_span_records_request_id() {
+        let request = Request::builder()
+            .uri("/status")
+            .header("x-request-id", "abc-123")
+            .body(())
+            .unwrap();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(Level::INFO)
+            .finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = SampledMakeSpan::new(1.0).make_span(&request);
+            assert!(!span.is_none());
+            assert!(span.has_field("request_id"));
+        });
+    }
+}
@@ -0,0 +1,44 @@
+//! API version marker for versioned routing.
+//!
+//! Every route is served under `/v1` and, for existing consumers, at its
+//! legacy unprefixed path.  The router stamps each request with the
+//! [`ApiVersion`] it was routed through; handlers that need to change their
+//! response shape take `ApiVersion` as an extractor and branch on it, so a
+//! breaking change can ship as `/v2` while `/v1` and the legacy paths keep
+//! the old shape.
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use std::convert::Infallible;
+
+/// The API version a request was routed through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    /// Version served at the unprefixed legacy paths.  Stays at `V1` when
+    /// newer versions are added, so unversioned clients never break.
+    pub const LEGACY: ApiVersion = ApiVersion::V1;
+
+    /// Path prefix this version is nested under, e.g. `/v1`.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/v1",
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ApiVersion {
+    type Rejection = Infallible;
+
+    /// Requests that bypass the router (handler unit tests) count as legacy.
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<ApiVersion>()
+            .copied()
+            .unwrap_or(ApiVersion::LEGACY))
+    }
+}
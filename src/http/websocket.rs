@@ -1,39 +1,173 @@
+use super::handlers::{epoch_json, last_sync_json};
 use super::state::AppState;
+use crate::config::{SlowConsumerPolicy, WsConfig};
+use crate::errors::AppError;
+use crate::metrics::RejectLabel;
+use crate::streaming::{StreamFormat, TickerKey, next_tick, validate_interval};
 use axum::{
     extract::{
-        State,
-        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
     },
     response::IntoResponse,
 };
+use futures_util::SinkExt;
+use futures_util::stream::StreamExt;
+use parking_lot::Mutex;
 use serde_json::json;
+use std::collections::VecDeque;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::time::interval;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::{debug, info, warn};
 
-/// WebSocket upgrade handler
+/// WebSocket upgrade handler; invalid stream parameters (see [`StreamQuery`])
+/// are refused with 400 and handshakes beyond `WS_MAX_CONNECTIONS` with 429,
+/// both before the upgrade.
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| websocket_connection(socket, state))
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let params = query
+        .params(&state.config.ws)
+        .map_err(|error| AppError::BadRequest { error })?;
+    let Some(slot) = StreamSlot::acquire(&state) else {
+        state.metrics.websocket_rejected_total.inc();
+        warn!(
+            max_connections = state.config.ws.max_connections,
+            "WebSocket connection limit reached, rejecting handshake"
+        );
+        return Err(AppError::TooManyRequests {
+            retry_after_secs: 1,
+        });
+    };
+    Ok(ws.on_upgrade(move |socket| async move {
+        websocket_connection(socket, state, params).await;
+        drop(slot);
+    }))
+}
+
+/// One of the `WS_MAX_CONNECTIONS` stream slots; released on drop, so a
+/// handshake that never completes gives its slot back too.
+struct StreamSlot {
+    connections: Arc<AtomicUsize>,
+}
+
+impl StreamSlot {
+    /// Reserve a slot, or `None` when the limit is reached.
+    fn acquire(state: &AppState) -> Option<Self> {
+        let max = match state.config.ws.max_connections {
+            0 => usize::MAX,
+            max => max,
+        };
+        state
+            .stream_connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| StreamSlot {
+                connections: state.stream_connections.clone(),
+            })
+    }
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        self.connections.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Query parameters accepted by `/stream`.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct StreamQuery {
+    /// Tick interval in milliseconds, between `WS_MIN_UPDATE_INTERVAL_MS`
+    /// and `MAX_STREAM_INTERVAL_MS`; defaults to `WS_UPDATE_INTERVAL_MS`.
+    pub interval_ms: Option<String>,
+    /// Tick body: `full` (default), `epoch`, `iso` or `unix_ns`.
+    pub format: Option<String>,
+    /// Connection lifetime in seconds; may shorten `WS_MAX_DURATION_SECS`
+    /// but never extend it.
+    pub max_duration: Option<String>,
+    /// Last `sequence` a reconnecting client saw; the stream then opens with
+    /// a `resume` message counting the ticks it missed.
+    pub resume_from: Option<String>,
+}
+
+/// Validated `/stream` parameters of one connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamParams {
+    pub interval_ms: u64,
+    pub format: StreamFormat,
+    pub max_duration_secs: u64,
+    pub resume_from: Option<u64>,
+}
+
+impl StreamQuery {
+    /// Validate the query, filling unset parameters from `ws`.
+    pub fn params(&self, ws: &WsConfig) -> Result<StreamParams, String> {
+        let interval_ms = match self.interval_ms.as_deref() {
+            Some(raw) => validate_interval(
+                raw.parse::<u64>().map_err(|_| {
+                    "interval_ms must be a whole number of milliseconds".to_string()
+                })?,
+                ws,
+            )?,
+            None => ws.update_interval_ms,
+        };
+        let format = match self.format.as_deref() {
+            Some(raw) => StreamFormat::parse(raw)?,
+            None => StreamFormat::Full,
+        };
+        let max_duration_secs = match self.max_duration.as_deref() {
+            Some(raw) => {
+                let limit = match ws.max_duration_secs {
+                    0 => u64::MAX,
+                    limit => limit,
+                };
+                match raw.parse::<u64>() {
+                    Ok(secs) if (1..=limit).contains(&secs) => secs,
+                    _ => {
+                        return Err(format!(
+                            "max_duration must be between 1 and {limit} seconds"
+                        ));
+                    }
+                }
+            }
+            None => ws.max_duration_secs,
+        };
+        let resume_from = self
+            .resume_from
+            .as_deref()
+            .map(str::parse::<u64>)
+            .transpose()
+            .map_err(|_| "resume_from must be a tick sequence number".to_string())?;
+        Ok(StreamParams {
+            interval_ms,
+            format,
+            max_duration_secs,
+            resume_from,
+        })
+    }
 }
 
 /// Handle WebSocket connection - streams time updates
-async fn websocket_connection(socket: WebSocket, state: Arc<AppState>) {
+async fn websocket_connection(socket: WebSocket, state: Arc<AppState>, params: StreamParams) {
     let (mut sender, mut receiver) = socket.split();
 
     // Client info
     info!("WebSocket client connected");
+    state.perf_metrics.stream.connection_opened();
+    state.metrics.ws_connections_active.inc();
+    let qos = Arc::new(ConnectionQos::default());
 
-    // Read the WS config once (it was populated at startup from
-    // the WS_UPDATE_INTERVAL_MS / WS_MAX_DURATION_SECS env vars
-    // and validated in Config::from_env). Re-reading std::env on
-    // every connection would defeat rolling deploys and waste
-    // a few microseconds per handshake.
-    let update_interval_ms = state.config.ws.update_interval_ms;
-    let max_duration_secs = state.config.ws.max_duration_secs;
+    // Interval and duration come from the query, defaulting to the
+    // WS_UPDATE_INTERVAL_MS / WS_MAX_DURATION_SECS config read at startup.
+    let update_interval_ms = params.interval_ms;
+    let max_duration_secs = params.max_duration_secs;
 
     // Send welcome message
     let welcome = json!({
@@ -41,6 +175,8 @@ async fn websocket_connection(socket: WebSocket, state: Arc<AppState>) {
         "message": "Connected to NTP Time JSON API WebSocket",
         "update_interval_ms": update_interval_ms,
         "max_duration_secs": max_duration_secs,
+        "format": params.format.as_str(),
+        "last_sync": last_sync_json(&state),
     });
 
     if sender
@@ -54,86 +190,276 @@ async fn websocket_connection(socket: WebSocket, state: Arc<AppState>) {
         return;
     }
 
-    // Spawn a task to send time updates
+    // Ticks are produced on schedule into a bounded per-connection queue and
+    // written by a separate task, so a stalled client can neither delay tick
+    // generation nor grow memory without bound.
+    let queue = Arc::new(SendQueue::new(
+        state.config.ws.send_queue_capacity,
+        state.config.ws.slow_consumer_policy,
+    ));
+
+    // Ticks come formatted from the ticker shared by every connection with
+    // the same interval and format; this task only relays them into the
+    // connection's queue, applies control messages and enforces the
+    // connection's max duration.
+    let mut key = TickerKey {
+        interval_ms: update_interval_ms,
+        format: params.format,
+    };
+    let mut ticks = Some(state.stream_hub.subscribe(&state, key));
+    let (control_tx, mut controls) =
+        tokio::sync::mpsc::channel::<Control>(CONTROL_CHANNEL_CAPACITY);
     let state_clone = state.clone();
-    let send_task = tokio::spawn(async move {
-        let mut tick = interval(Duration::from_millis(update_interval_ms));
+    let producer_queue = queue.clone();
+    let producer_qos = qos.clone();
+    let mut shutdown = state.shutdown_signal.subscribe();
+    let mut resume_from = params.resume_from;
+    let producer_task = tokio::spawn(async move {
         let mut count = 0u64;
-        let max_updates = compute_max_updates(max_duration_secs, update_interval_ms);
+        let deadline = (max_duration_secs > 0)
+            .then(|| tokio::time::Instant::now() + Duration::from_secs(max_duration_secs));
 
         loop {
-            tick.tick().await;
-
-            if count >= max_updates {
-                info!(
-                    updates_sent = count,
-                    max_duration_secs = max_duration_secs,
-                    "WebSocket max duration reached, closing connection"
-                );
-                break;
-            }
-
-            let message = match state_clone.timebase.now_ms() {
-                Some(epoch_ms) => {
-                    let quality = state_clone.compute_quality();
-                    let is_stale = quality.serve_state != "ok";
-                    let staleness_secs = quality.staleness_ms.unwrap_or(0) / 1000;
-
-                    json!({
-                        "type": "tick",
-                        "epoch_ms": epoch_ms,
-                        "iso8601": format_epoch_ms_to_iso8601(epoch_ms),
-                        "is_stale": is_stale,
-                        "staleness_secs": staleness_secs,
-                        "message": if is_stale {
-                            &state_clone.config.messages.ok_cache
-                        } else {
-                            &state_clone.config.messages.ok
-                        },
-                        "sequence": count,
-                        // P0-4 quality fields
-                        "source": quality.source,
-                        "serve_state": quality.serve_state,
-                        "uncertainty_ms": quality.uncertainty_ms,
-                        "staleness_ms": quality.staleness_ms,
-                    })
+            let next = tokio::select! {
+                next = next_tick(&mut ticks) => next,
+                Some(control) = controls.recv() => {
+                    let op = control.op();
+                    match control {
+                        Control::Subscribe { interval_ms, format, .. } => {
+                            key = TickerKey {
+                                interval_ms: interval_ms.unwrap_or(key.interval_ms),
+                                format: format.unwrap_or(key.format),
+                            };
+                            ticks = Some(state_clone.stream_hub.subscribe(&state_clone, key));
+                        }
+                        Control::Ping => {
+                            let epoch_ms = state_clone.timebase.now_ms();
+                            producer_queue.push(control_reply(json!({
+                                "type": "pong",
+                                "epoch_ms": epoch_ms.map(|epoch_ms| epoch_json(epoch_ms, state_clone.config.http.epoch_as_string)),
+                            })));
+                            continue;
+                        }
+                        Control::Pause => ticks = None,
+                        Control::Resume => {
+                            if ticks.is_none() {
+                                ticks = Some(state_clone.stream_hub.subscribe(&state_clone, key));
+                            }
+                        }
+                    }
+                    debug!(op, interval_ms = key.interval_ms, paused = ticks.is_none(), "Applied stream control");
+                    producer_queue.push(control_reply(json!({
+                        "type": "ack",
+                        "op": op,
+                        "update_interval_ms": key.interval_ms,
+                        "format": key.format.as_str(),
+                        "paused": ticks.is_none(),
+                    })));
+                    continue;
+                }
+                _ = sleep_until_deadline(deadline) => {
+                    info!(
+                        updates_sent = count,
+                        max_duration_secs = max_duration_secs,
+                        "WebSocket max duration reached, closing connection"
+                    );
+                    break;
                 }
-                None => {
-                    json!({
-                        "type": "error",
-                        "message": &state_clone.config.messages.error_no_sync,
-                        "sequence": count,
-                        "source": "unsynced",
-                        "serve_state": "unsynced",
-                    })
+                _ = shutdown.wait_for(|stop| *stop) => {
+                    info!(updates_sent = count, "Server shutting down, closing WebSocket connection");
+                    producer_queue.close(CloseFrame {
+                        code: 1001, // Going away
+                        reason: "Server shutting down".into(),
+                    });
+                    return;
                 }
             };
+            let tick = match next {
+                Ok(tick) => tick,
+                Err(RecvError::Lagged(skipped)) => {
+                    producer_qos.record_dropped(&state_clone, skipped as usize);
+                    state_clone
+                        .metrics
+                        .ws_messages_dropped_total
+                        .get_or_create(&RejectLabel {
+                            reason: "lagged".to_string(),
+                        })
+                        .inc_by(skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
 
-            let text = serde_json::to_string(&message).unwrap();
+            // A resumed stream first tells the client how many ticks it
+            // missed since `resume_from`; sequences are aligned to NTP time,
+            // so the count holds across reconnects, ticker restarts and
+            // replicas.
+            if let Some(last_seen) = resume_from.take() {
+                let resume = json!({
+                    "type": "resume",
+                    "resume_from": last_seen,
+                    "sequence": tick.sequence,
+                    "missed": tick.sequence.saturating_sub(last_seen.saturating_add(1)),
+                });
+                producer_queue.push(Message::Text(
+                    serde_json::to_string(&resume).unwrap().into(),
+                ));
+            }
 
-            if sender.send(Message::Text(text.into())).await.is_err() {
-                debug!(updates_sent = count, "WebSocket client disconnected");
-                break;
+            let outcome = producer_queue.push(Message::Text(tick.json));
+            producer_qos.observe_depth(&state_clone, producer_queue.len());
+            match outcome {
+                PushOutcome::Queued => {}
+                PushOutcome::Dropped(dropped) => {
+                    producer_qos.record_dropped(&state_clone, dropped);
+                    state_clone
+                        .metrics
+                        .ws_messages_dropped_total
+                        .get_or_create(&RejectLabel {
+                            reason: producer_queue.policy.as_str().to_string(),
+                        })
+                        .inc_by(dropped as u64);
+                }
+                PushOutcome::Closed => return,
+                PushOutcome::Overflow => {
+                    warn!(
+                        updates_sent = count,
+                        "WebSocket client cannot keep up, disconnecting"
+                    );
+                    state_clone.metrics.ws_slow_consumer_disconnects_total.inc();
+                    producer_queue.close(CloseFrame {
+                        code: 1008, // Policy violation
+                        reason: "Client too slow to receive updates".into(),
+                    });
+                    return;
+                }
             }
 
             count += 1;
         }
 
-        // Send close message
-        let _ = sender
-            .send(Message::Close(Some(axum::extract::ws::CloseFrame {
-                code: 1000, // Normal closure
-                reason: "Max duration reached or client closed".into(),
-            })))
-            .await;
+        producer_queue.close(CloseFrame {
+            code: 1000, // Normal closure
+            reason: "Max duration reached or client closed".into(),
+        });
+    });
+
+    // Server Pings go out from the writer so they are never queued behind
+    // (or dropped with) ticks; the reader matches the Pongs.
+    let heartbeat = Arc::new(Heartbeat::default());
+    let ping_interval = state.config.ws.ping_interval_secs;
+    let max_missed_pongs = state.config.ws.max_missed_pongs as usize;
+
+    let writer_queue = queue.clone();
+    let writer_qos = qos.clone();
+    let writer_state = state.clone();
+    let writer_heartbeat = heartbeat.clone();
+    let send_task = tokio::spawn(async move {
+        let mut pings = (ping_interval > 0).then(|| {
+            let every = Duration::from_secs(ping_interval);
+            tokio::time::interval_at(tokio::time::Instant::now() + every, every)
+        });
+        loop {
+            let next = tokio::select! {
+                next = writer_queue.pop() => next,
+                _ = next_ping(&mut pings) => {
+                    if writer_heartbeat.outstanding() >= max_missed_pongs {
+                        info!(
+                            missed_pongs = max_missed_pongs,
+                            "WebSocket client stopped answering pings, closing connection"
+                        );
+                        writer_state
+                            .metrics
+                            .ws_liveness_disconnects_total
+                            .get_or_create(&RejectLabel {
+                                reason: "heartbeat".to_string(),
+                            })
+                            .inc();
+                        writer_queue.close(CloseFrame {
+                            code: 1000, // Normal closure
+                            reason: "Heartbeat timeout".into(),
+                        });
+                        pings = None;
+                        continue;
+                    }
+                    if sender.send(Message::Ping(writer_heartbeat.ping())).await.is_err() {
+                        debug!("WebSocket client disconnected");
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let Some((message, enqueued_at)) = next else {
+                break;
+            };
+            writer_qos.observe_depth(&writer_state, writer_queue.len());
+            let is_close = matches!(message, Message::Close(_));
+            if sender.send(message).await.is_err() {
+                debug!("WebSocket client disconnected");
+                break;
+            }
+            if is_close {
+                break;
+            }
+            writer_qos.record_sent(&writer_state, enqueued_at.elapsed());
+        }
     });
 
-    // Spawn a task to receive messages (ping/pong, close)
+    // Spawn a task to receive messages (ping/pong, close).  Any frame counts
+    // as activity for WS_IDLE_TIMEOUT_SECS; an idle client is sent a Close
+    // and the connection ends once the writer has delivered it.
+    let mut idle_timeout = (state.config.ws.idle_timeout_secs > 0)
+        .then(|| Duration::from_secs(state.config.ws.idle_timeout_secs));
+    let reader_queue = queue.clone();
+    let reader_qos = qos.clone();
+    let reader_state = state.clone();
     let recv_task = tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
+        loop {
+            let next = match idle_timeout {
+                Some(idle) => match tokio::time::timeout(idle, receiver.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        info!(
+                            idle_timeout_secs = idle.as_secs(),
+                            "WebSocket client idle, closing connection"
+                        );
+                        reader_state
+                            .metrics
+                            .ws_liveness_disconnects_total
+                            .get_or_create(&RejectLabel {
+                                reason: "idle".to_string(),
+                            })
+                            .inc();
+                        reader_queue.close(CloseFrame {
+                            code: 1000, // Normal closure
+                            reason: "Idle timeout".into(),
+                        });
+                        idle_timeout = None;
+                        continue;
+                    }
+                },
+                None => receiver.next().await,
+            };
+            let Some(Ok(msg)) = next else {
+                break;
+            };
             match msg {
                 Message::Text(text) => {
                     debug!(message = %text, "Received text message from client");
+                    match parse_control(&text, &reader_state.config.ws) {
+                        Ok(control) => {
+                            if control_tx.send(control).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err((op, error)) => {
+                            reader_queue.push(control_reply(json!({
+                                "type": "nack",
+                                "op": op,
+                                "error": error,
+                            })));
+                        }
+                    }
                 }
                 Message::Close(_) => {
                     debug!("Client sent close message");
@@ -144,97 +470,538 @@ async fn websocket_connection(socket: WebSocket, state: Arc<AppState>) {
                     // Axum automatically sends pong
                     let _ = data; // Suppress unused warning
                 }
-                Message::Pong(_) => {
-                    debug!("Received pong");
-                }
+                Message::Pong(payload) => match heartbeat.pong(&payload) {
+                    Some(latency) => {
+                        debug!(latency_us = latency.as_micros() as u64, "Received pong");
+                        reader_qos.record_pong(&reader_state, latency);
+                    }
+                    None => debug!("Received unsolicited pong"),
+                },
                 _ => {}
             }
         }
     });
 
     // Wait for either task to complete
+    let mut send_task = send_task;
+    let mut recv_task = recv_task;
     tokio::select! {
-        _ = send_task => {
+        _ = &mut send_task => {
             info!("WebSocket send task completed");
+            // The writer ends after sending our Close; give the client a
+            // moment to answer it so the closing handshake completes.
+            let _ = tokio::time::timeout(CLOSE_HANDSHAKE_TIMEOUT, &mut recv_task).await;
         }
-        _ = recv_task => {
+        _ = &mut recv_task => {
             info!("WebSocket receive task completed");
         }
     }
+    producer_task.abort();
+    send_task.abort();
+    recv_task.abort();
+
+    qos.observe_depth(&state, 0);
+    state.perf_metrics.stream.connection_closed();
+    state.metrics.ws_connections_active.dec();
+    info!(
+        ticks_sent = qos.sent.load(Ordering::Relaxed),
+        ticks_dropped = qos.dropped.load(Ordering::Relaxed),
+        max_queue_depth = qos.max_queue_depth.load(Ordering::Relaxed),
+        max_send_latency_us = qos.max_send_latency_us.load(Ordering::Relaxed),
+        max_pong_latency_us = qos.max_pong_latency_us.load(Ordering::Relaxed),
+        "WebSocket stream QoS"
+    );
 
     info!("WebSocket connection closed");
 }
 
-/// Compute the maximum number of tick messages to send for a connection.
-///
-/// Returns `u64::MAX` when `max_duration_secs` is 0 (unlimited).
-/// Uses saturating multiplication to defend against absurdly large values.
-/// `update_interval_ms` is guaranteed > 0 by `Config::validate`.
-fn compute_max_updates(max_duration_secs: u64, update_interval_ms: u64) -> u64 {
-    if max_duration_secs == 0 {
-        u64::MAX
-    } else {
-        max_duration_secs.saturating_mul(1000) / update_interval_ms
+/// How long to wait for the client's reply to a server-sent Close frame.
+const CLOSE_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Stream QoS of one connection, shared by its producer and writer tasks.
+/// Every update is also folded into the process-wide aggregates.
+#[derive(Default)]
+struct ConnectionQos {
+    queue_depth: AtomicU64,
+    max_queue_depth: AtomicU64,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    max_send_latency_us: AtomicU64,
+    max_pong_latency_us: AtomicU64,
+}
+
+impl ConnectionQos {
+    /// Record the connection's current queue depth.
+    fn observe_depth(&self, state: &AppState, depth: usize) {
+        let depth = depth as u64;
+        let previous = self.queue_depth.swap(depth, Ordering::Relaxed);
+        self.max_queue_depth.fetch_max(depth, Ordering::Relaxed);
+        state
+            .perf_metrics
+            .stream
+            .adjust_queue_depth(previous, depth);
+        state
+            .metrics
+            .ws_send_queue_depth
+            .inc_by(depth as i64 - previous as i64);
+    }
+
+    /// Record a delivered tick and how long it took from enqueue to written.
+    fn record_sent(&self, state: &AppState, latency: Duration) {
+        let latency_us = latency.as_micros() as u64;
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        self.max_send_latency_us
+            .fetch_max(latency_us, Ordering::Relaxed);
+        state.perf_metrics.stream.record_sent(latency_us);
+        state.metrics.ws_messages_sent_total.inc();
+        state
+            .metrics
+            .ws_send_latency_seconds
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Record the round trip of an answered server Ping.
+    fn record_pong(&self, state: &AppState, latency: Duration) {
+        self.max_pong_latency_us
+            .fetch_max(latency.as_micros() as u64, Ordering::Relaxed);
+        state
+            .metrics
+            .ws_pong_latency_seconds
+            .observe(latency.as_secs_f64());
+    }
+
+    fn record_dropped(&self, state: &AppState, dropped: usize) {
+        self.dropped.fetch_add(dropped as u64, Ordering::Relaxed);
+        state.perf_metrics.stream.record_dropped(dropped as u64);
     }
 }
 
-/// Format epoch milliseconds to ISO 8601 string
-fn format_epoch_ms_to_iso8601(epoch_ms: i64) -> String {
-    use chrono::DateTime;
+/// Control messages a connection may have waiting for its producer task.
+const CONTROL_CHANNEL_CAPACITY: usize = 4;
 
-    let secs = epoch_ms / 1000;
-    let nsecs = ((epoch_ms % 1000) * 1_000_000) as u32;
+/// Client control message on `/stream`, e.g. `{"op":"set_interval","ms":100}`.
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum ControlMessage {
+    /// Switch interval and/or format; also resumes a paused stream.
+    Subscribe {
+        interval_ms: Option<u64>,
+        format: Option<String>,
+    },
+    SetInterval {
+        ms: u64,
+    },
+    Pause,
+    Resume,
+    Ping,
+}
+
+/// Validated control message.
+#[derive(Debug, PartialEq, Eq)]
+enum Control {
+    /// `subscribe` or `set_interval`, as named in the ack.
+    Subscribe {
+        op: &'static str,
+        interval_ms: Option<u64>,
+        format: Option<StreamFormat>,
+    },
+    Pause,
+    Resume,
+    /// Answered by the producer with a `pong` carrying the current time.
+    Ping,
+}
 
-    match DateTime::from_timestamp(secs, nsecs) {
-        Some(dt) => dt.to_rfc3339(),
-        None => "invalid".to_string(),
+impl Control {
+    fn op(&self) -> &'static str {
+        match self {
+            Control::Subscribe { op, .. } => op,
+            Control::Pause => "pause",
+            Control::Resume => "resume",
+            Control::Ping => "ping",
+        }
     }
 }
 
-use futures_util::SinkExt;
-use futures_util::stream::StreamExt;
+/// Parse and validate a client text frame; the error names the op (if any)
+/// and the problem.
+fn parse_control(text: &str, ws: &WsConfig) -> Result<Control, (Option<String>, String)> {
+    let op = serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .and_then(|value| value.get("op")?.as_str().map(str::to_string));
+    let message = serde_json::from_str::<ControlMessage>(text)
+        .map_err(|e| (op.clone(), format!("invalid control message: {e}")))?;
+    let invalid = |error: String| (op.clone(), error);
+    Ok(match message {
+        ControlMessage::Subscribe {
+            interval_ms,
+            format,
+        } => Control::Subscribe {
+            op: "subscribe",
+            interval_ms: interval_ms
+                .map(|ms| validate_interval(ms, ws))
+                .transpose()
+                .map_err(invalid)?,
+            format: format
+                .as_deref()
+                .map(StreamFormat::parse)
+                .transpose()
+                .map_err(invalid)?,
+        },
+        ControlMessage::SetInterval { ms } => Control::Subscribe {
+            op: "set_interval",
+            interval_ms: Some(validate_interval(ms, ws).map_err(invalid)?),
+            format: None,
+        },
+        ControlMessage::Pause => Control::Pause,
+        ControlMessage::Resume => Control::Resume,
+        ControlMessage::Ping => Control::Ping,
+    })
+}
+
+/// Serialize a control reply for the send queue.
+fn control_reply(reply: serde_json::Value) -> Message {
+    Message::Text(serde_json::to_string(&reply).unwrap().into())
+}
+
+/// Wait for `deadline`; never resolves without one.
+async fn sleep_until_deadline(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Server Pings of one connection awaiting their Pong.  Each Ping carries an
+/// 8-byte nonce; a Pong answers its Ping and any older ones still pending.
+#[derive(Default)]
+struct Heartbeat {
+    pending: Mutex<VecDeque<(u64, Instant)>>,
+    next_nonce: AtomicU64,
+}
+
+impl Heartbeat {
+    /// Payload for the next Ping, remembered until answered.
+    fn ping(&self) -> axum::body::Bytes {
+        let nonce = self.next_nonce.fetch_add(1, Ordering::Relaxed);
+        self.pending.lock().push_back((nonce, Instant::now()));
+        axum::body::Bytes::copy_from_slice(&nonce.to_be_bytes())
+    }
+
+    /// Round trip of the Ping `payload` answers; `None` for a Pong that
+    /// matches no pending Ping.
+    fn pong(&self, payload: &[u8]) -> Option<Duration> {
+        let nonce = u64::from_be_bytes(payload.try_into().ok()?);
+        let mut pending = self.pending.lock();
+        let answered = pending.iter().position(|(sent, _)| *sent == nonce)?;
+        let (_, sent_at) = pending.drain(..=answered).next_back()?;
+        Some(sent_at.elapsed())
+    }
+
+    /// Pings sent and not yet answered.
+    fn outstanding(&self) -> usize {
+        self.pending.lock().len()
+    }
+}
+
+/// Wait for the next heartbeat; never resolves when heartbeats are off.
+async fn next_ping(pings: &mut Option<tokio::time::Interval>) {
+    match pings {
+        Some(pings) => {
+            pings.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Result of offering a message to a [`SendQueue`].
+#[derive(Debug, PartialEq, Eq)]
+enum PushOutcome {
+    Queued,
+    /// Queued after discarding this many older messages.
+    Dropped(usize),
+    /// Queue full under `SlowConsumerPolicy::Disconnect`; nothing queued.
+    Overflow,
+    /// The stream is already closing; nothing queued.
+    Closed,
+}
+
+/// Bounded per-connection queue between the tick producer and the socket writer.
+struct SendQueue {
+    inner: Mutex<SendQueueState>,
+    notify: Notify,
+    capacity: usize,
+    policy: SlowConsumerPolicy,
+}
+
+#[derive(Default)]
+struct SendQueueState {
+    messages: VecDeque<(Message, Instant)>,
+    closed: bool,
+}
+
+impl SendQueue {
+    fn new(capacity: usize, policy: SlowConsumerPolicy) -> Self {
+        Self {
+            inner: Mutex::new(SendQueueState::default()),
+            notify: Notify::new(),
+            capacity: capacity.max(1),
+            policy,
+        }
+    }
+
+    /// Enqueue `message`, applying the slow-consumer policy when full.
+    fn push(&self, message: Message) -> PushOutcome {
+        let outcome = {
+            let mut inner = self.inner.lock();
+            if inner.closed {
+                return PushOutcome::Closed;
+            }
+            let outcome = if inner.messages.len() < self.capacity {
+                PushOutcome::Queued
+            } else {
+                match self.policy {
+                    SlowConsumerPolicy::DropOldest => {
+                        inner.messages.pop_front();
+                        PushOutcome::Dropped(1)
+                    }
+                    SlowConsumerPolicy::Coalesce => {
+                        let dropped = inner.messages.len();
+                        inner.messages.clear();
+                        PushOutcome::Dropped(dropped)
+                    }
+                    SlowConsumerPolicy::Disconnect => return PushOutcome::Overflow,
+                }
+            };
+            inner.messages.push_back((message, Instant::now()));
+            outcome
+        };
+        self.notify.notify_one();
+        outcome
+    }
+
+    /// Discard anything still queued and end the stream with `frame`.
+    fn close(&self, frame: CloseFrame) {
+        {
+            let mut inner = self.inner.lock();
+            inner.messages.clear();
+            inner
+                .messages
+                .push_back((Message::Close(Some(frame)), Instant::now()));
+            inner.closed = true;
+        }
+        self.notify.notify_one();
+    }
+
+    /// Next message to send with its enqueue time; `None` once closed and drained.
+    async fn pop(&self) -> Option<(Message, Instant)> {
+        loop {
+            {
+                let mut inner = self.inner.lock();
+                if let Some(message) = inner.messages.pop_front() {
+                    return Some(message);
+                }
+                if inner.closed {
+                    return None;
+                }
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.inner.lock().messages.len()
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_iso8601_formatting() {
-        let epoch_ms = 1735459200000; // Recent timestamp
-        let iso = format_epoch_ms_to_iso8601(epoch_ms);
-        // Verify it's not invalid and contains a date and time
-        assert_ne!(iso, "invalid");
-        assert!(iso.contains("T")); // ISO8601 has T separator
-        assert!(iso.len() > 10); // Should be full date-time
+    fn tick(n: u32) -> Message {
+        Message::Text(n.to_string().into())
+    }
+
+    #[tokio::test]
+    async fn test_send_queue_drop_oldest_keeps_newest() {
+        let queue = SendQueue::new(2, SlowConsumerPolicy::DropOldest);
+        assert_eq!(queue.push(tick(1)), PushOutcome::Queued);
+        assert_eq!(queue.push(tick(2)), PushOutcome::Queued);
+        assert_eq!(queue.push(tick(3)), PushOutcome::Dropped(1));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop().await.map(|(m, _)| m), Some(tick(2)));
+        assert_eq!(queue.pop().await.map(|(m, _)| m), Some(tick(3)));
+    }
+
+    #[tokio::test]
+    async fn test_send_queue_coalesce_keeps_only_latest() {
+        let queue = SendQueue::new(3, SlowConsumerPolicy::Coalesce);
+        for n in 1..=3 {
+            queue.push(tick(n));
+        }
+        assert_eq!(queue.push(tick(4)), PushOutcome::Dropped(3));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop().await.map(|(m, _)| m), Some(tick(4)));
+    }
+
+    #[tokio::test]
+    async fn test_send_queue_disconnect_policy_closes() {
+        let queue = SendQueue::new(1, SlowConsumerPolicy::Disconnect);
+        assert_eq!(queue.push(tick(1)), PushOutcome::Queued);
+        assert_eq!(queue.push(tick(2)), PushOutcome::Overflow);
+        queue.close(CloseFrame {
+            code: 1008,
+            reason: "slow".into(),
+        });
+        // Close replaces queued ticks; the stream then ends
+        assert!(matches!(queue.pop().await, Some((Message::Close(_), _))));
+        assert!(queue.pop().await.is_none());
+    }
+
+    fn make_state(configure: impl FnOnce(&mut WsConfig)) -> Arc<AppState> {
+        use crate::config::Config;
+        use crate::metrics::Metrics;
+        use crate::performance::{LockFreeMetrics, TimeCache};
+        use crate::timebase::TimeBase;
+
+        let mut config = Config::default();
+        configure(&mut config.ws);
+        let time_cache = Arc::new(TimeCache::new("done".into(), "done".into()));
+        Arc::new(AppState::new(
+            Arc::new(config),
+            TimeBase::new(true).with_cache(time_cache.clone()),
+            Arc::new(Metrics::new()),
+            time_cache,
+            Arc::new(LockFreeMetrics::new()),
+        ))
     }
 
     #[test]
-    fn test_compute_max_updates_unlimited() {
-        // max_duration_secs=0 means unlimited — should return u64::MAX
-        assert_eq!(compute_max_updates(0, 1000), u64::MAX);
-        assert_eq!(compute_max_updates(0, 500), u64::MAX);
+    fn test_heartbeat_matches_pongs_to_pings() {
+        let heartbeat = Heartbeat::default();
+        let first = heartbeat.ping();
+        let second = heartbeat.ping();
+        assert_eq!(heartbeat.outstanding(), 2);
+
+        // Unknown or malformed payloads answer nothing
+        assert!(heartbeat.pong(b"hello").is_none());
+        assert!(heartbeat.pong(&99u64.to_be_bytes()).is_none());
+        assert_eq!(heartbeat.outstanding(), 2);
+
+        // A Pong for the newer Ping also settles the older one
+        assert!(heartbeat.pong(&second).is_some());
+        assert_eq!(heartbeat.outstanding(), 0);
+        assert!(heartbeat.pong(&first).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_closed_queue_refuses_pushes() {
+        let queue = SendQueue::new(4, SlowConsumerPolicy::Disconnect);
+        queue.close(CloseFrame {
+            code: 1000,
+            reason: "Idle timeout".into(),
+        });
+        assert_eq!(queue.push(tick(1)), PushOutcome::Closed);
     }
 
     #[test]
-    fn test_compute_max_updates_normal() {
-        // 60 seconds at 1000ms interval = 60 updates
-        assert_eq!(compute_max_updates(60, 1000), 60);
-        // 60 seconds at 500ms interval = 120 updates
-        assert_eq!(compute_max_updates(60, 500), 120);
-        // 3600 seconds at 1000ms = 3600 updates
-        assert_eq!(compute_max_updates(3600, 1000), 3600);
+    fn test_stream_slots_are_limited_and_released() {
+        let state = make_state(|ws| ws.max_connections = 2);
+
+        let first = StreamSlot::acquire(&state).unwrap();
+        let _second = StreamSlot::acquire(&state).unwrap();
+        assert!(StreamSlot::acquire(&state).is_none());
+        drop(first);
+        assert!(StreamSlot::acquire(&state).is_some());
+        assert_eq!(state.stream_connections.load(Ordering::Relaxed), 1);
     }
 
     #[test]
-    fn test_compute_max_updates_truncates() {
-        // 1 second at 300ms interval = 3 (not 3.33), integer truncation
-        assert_eq!(compute_max_updates(1, 300), 3);
+    fn test_parse_control_messages() {
+        let ws = crate::config::Config::default().ws;
+        assert_eq!(
+            parse_control(r#"{"op":"set_interval","ms":250}"#, &ws),
+            Ok(Control::Subscribe {
+                op: "set_interval",
+                interval_ms: Some(250),
+                format: None,
+            })
+        );
+        assert_eq!(
+            parse_control(r#"{"op":"subscribe","format":"epoch"}"#, &ws),
+            Ok(Control::Subscribe {
+                op: "subscribe",
+                interval_ms: None,
+                format: Some(StreamFormat::Epoch),
+            })
+        );
+        assert_eq!(parse_control(r#"{"op":"pause"}"#, &ws), Ok(Control::Pause));
+        assert_eq!(
+            parse_control(r#"{"op":"resume"}"#, &ws),
+            Ok(Control::Resume)
+        );
+        assert_eq!(parse_control(r#"{"op":"ping"}"#, &ws), Ok(Control::Ping));
+
+        // Errors name the op when there is one
+        let (op, _) = parse_control(r#"{"op":"set_interval","ms":1}"#, &ws).unwrap_err();
+        assert_eq!(op.as_deref(), Some("set_interval"));
+        let (op, _) = parse_control(r#"{"op":"rewind"}"#, &ws).unwrap_err();
+        assert_eq!(op.as_deref(), Some("rewind"));
+        let (op, _) = parse_control("hello", &ws).unwrap_err();
+        assert_eq!(op, None);
     }
 
     #[test]
-    fn test_compute_max_updates_saturating() {
-        // u64::MAX * 1000 would overflow without saturating_mul; verify it doesn't panic
-        let result = compute_max_updates(u64::MAX, 1);
-        assert_eq!(result, u64::MAX); // saturates at u64::MAX, then / 1 = u64::MAX
+    fn test_stream_query_params() {
+        let ws = crate::config::Config::default().ws;
+        let query =
+            |interval_ms: Option<&str>, format: Option<&str>, max_duration: Option<&str>| {
+                StreamQuery {
+                    interval_ms: interval_ms.map(str::to_string),
+                    format: format.map(str::to_string),
+                    max_duration: max_duration.map(str::to_string),
+                    resume_from: None,
+                }
+                .params(&ws)
+            };
+
+        // Unset parameters fall back to the configuration
+        assert_eq!(
+            query(None, None, None),
+            Ok(StreamParams {
+                interval_ms: 1000,
+                format: StreamFormat::Full,
+                max_duration_secs: 3600,
+                resume_from: None,
+            })
+        );
+        assert_eq!(
+            query(Some("100"), Some("ISO"), Some("600")),
+            Ok(StreamParams {
+                interval_ms: 100,
+                format: StreamFormat::Iso,
+                max_duration_secs: 600,
+                resume_from: None,
+            })
+        );
+
+        // Below WS_MIN_UPDATE_INTERVAL_MS, above the cap, or not a number
+        assert!(query(Some("99"), None, None).is_err());
+        assert!(query(Some("60001"), None, None).is_err());
+        assert!(query(Some("1s"), None, None).is_err());
+        assert!(query(None, Some("xml"), None).is_err());
+        // A client may shorten the connection but not extend it
+        assert!(query(None, None, Some("3601")).is_err());
+        assert!(query(None, None, Some("0")).is_err());
+
+        let resume = |raw: &str| {
+            StreamQuery {
+                resume_from: Some(raw.to_string()),
+                ..StreamQuery::default()
+            }
+            .params(&ws)
+            .map(|params| params.resume_from)
+        };
+        assert_eq!(resume("42"), Ok(Some(42)));
+        assert!(resume("-1").is_err());
     }
 }
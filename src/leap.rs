@@ -0,0 +1,151 @@
+//! Leap-second table: TAI − UTC at any instant since 1972.
+//!
+//! A table in the IERS/IETF `leap-seconds.list` format is bundled into the
+//! binary; `LEAP_SECONDS_FILE` replaces it at startup with a newer copy, so
+//! a leap second announced after a release needs no rebuild.  `/time`
+//! serves TAI (`?format=tai`) and `utc_tai_offset` from the installed table.
+
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+/// Seconds from the NTP era (1900-01-01) to the Unix epoch.
+const NTP_UNIX_OFFSET_SECS: i64 = 2_208_988_800;
+
+/// Table compiled into the binary.
+const BUNDLED: &str = include_str!("leap-seconds.list");
+
+static INSTALLED: Lazy<RwLock<Arc<LeapTable>>> = Lazy::new(|| {
+    let table = LeapTable::parse(BUNDLED).expect("bundled leap-second table is valid");
+    RwLock::new(Arc::new(table))
+});
+
+/// TAI − UTC offsets and when each took effect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeapTable {
+    /// (Unix seconds the offset applies from, TAI − UTC seconds), ascending.
+    entries: Vec<(i64, i32)>,
+    /// Unix seconds after which the table may be missing a leap second.
+    expires_unix_secs: Option<i64>,
+}
+
+impl LeapTable {
+    /// Parse `leap-seconds.list` content: `NTP_SECONDS OFFSET [# comment]`
+    /// lines in ascending order, `#@ NTP_SECONDS` for the expiry, and `#`
+    /// comments.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut entries: Vec<(i64, i32)> = Vec::new();
+        let mut expires_unix_secs = None;
+        for (n, line) in content.lines().enumerate() {
+            if let Some(expiry) = line.strip_prefix("#@") {
+                let ntp_secs: i64 = expiry
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Invalid leap-second expiry on line {}", n + 1))?;
+                expires_unix_secs = Some(ntp_secs - NTP_UNIX_OFFSET_SECS);
+                continue;
+            }
+            let data = line.split('#').next().unwrap_or_default();
+            let mut fields = data.split_whitespace();
+            let (Some(ntp_secs), Some(offset)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let ntp_secs: i64 = ntp_secs
+                .parse()
+                .with_context(|| format!("Invalid leap-second timestamp on line {}", n + 1))?;
+            let offset: i32 = offset
+                .parse()
+                .with_context(|| format!("Invalid TAI-UTC offset on line {}", n + 1))?;
+            let unix_secs = ntp_secs - NTP_UNIX_OFFSET_SECS;
+            if entries.last().is_some_and(|(last, _)| *last >= unix_secs) {
+                anyhow::bail!("Leap-second entries out of order on line {}", n + 1);
+            }
+            entries.push((unix_secs, offset));
+        }
+        if entries.is_empty() {
+            anyhow::bail!("Leap-second table has no entries");
+        }
+        Ok(Self {
+            entries,
+            expires_unix_secs,
+        })
+    }
+
+    /// Read and parse a `leap-seconds.list` file.
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read LEAP_SECONDS_FILE {path}"))?;
+        Self::parse(&content).with_context(|| format!("Invalid LEAP_SECONDS_FILE {path}"))
+    }
+
+    /// TAI − UTC (seconds) at `unix_ms`; the 1972 offset of 10 s before the
+    /// table starts.
+    pub fn utc_tai_offset(&self, unix_ms: i64) -> i32 {
+        let unix_secs = unix_ms.div_euclid(1000);
+        let applied = self.entries.partition_point(|(from, _)| *from <= unix_secs);
+        self.entries[applied.saturating_sub(1)].1
+    }
+
+    /// `unix_ms` on the TAI time scale, counted like `CLOCK_TAI`: UTC epoch
+    /// milliseconds plus TAI − UTC.
+    pub fn tai_ms(&self, unix_ms: i64) -> i64 {
+        unix_ms + i64::from(self.utc_tai_offset(unix_ms)) * 1000
+    }
+
+    /// When the table stops being authoritative, in Unix ms.
+    pub fn expires_ms(&self) -> Option<i64> {
+        self.expires_unix_secs.map(|secs| secs * 1000)
+    }
+
+    /// Whether `unix_ms` is past the table's expiry.
+    pub fn is_expired(&self, unix_ms: i64) -> bool {
+        self.expires_ms().is_some_and(|expires| unix_ms >= expires)
+    }
+}
+
+/// The table in use: bundled, or the last one passed to [`install`].
+pub fn current() -> Arc<LeapTable> {
+    INSTALLED.read().clone()
+}
+
+/// Replace the table in use, e.g. with `LEAP_SECONDS_FILE`.
+pub fn install(table: LeapTable) {
+    *INSTALLED.write() = Arc::new(table);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2017-01-01T00:00:00Z, when TAI − UTC became 37 s.
+    const JAN_2017_MS: i64 = 1_483_228_800_000;
+
+    #[test]
+    fn bundled_table_covers_every_leap_second() {
+        let table = LeapTable::parse(BUNDLED).unwrap();
+        assert_eq!(table.entries.len(), 28);
+        assert_eq!(table.utc_tai_offset(0), 10);
+        assert_eq!(table.utc_tai_offset(JAN_2017_MS - 1), 36);
+        assert_eq!(table.utc_tai_offset(JAN_2017_MS), 37);
+        assert_eq!(table.tai_ms(JAN_2017_MS), JAN_2017_MS + 37_000);
+        assert!(
+            table
+                .expires_ms()
+                .is_some_and(|expires| expires > JAN_2017_MS)
+        );
+    }
+
+    #[test]
+    fn parse_reads_expiry_and_rejects_bad_tables() {
+        let table =
+            LeapTable::parse("#@ 3692217601\n# comment\n3692217600 37 # 1 Jan 2017\n").unwrap();
+        assert_eq!(table.expires_ms(), Some(JAN_2017_MS + 1000));
+        assert!(!table.is_expired(JAN_2017_MS));
+        assert!(table.is_expired(JAN_2017_MS + 1000));
+
+        assert!(LeapTable::parse("# nothing\n").is_err());
+        assert!(LeapTable::parse("3692217600 x\n").is_err());
+        assert!(LeapTable::parse("3692217600 37\n3644697600 36\n").is_err());
+    }
+}
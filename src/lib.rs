@@ -1,8 +1,20 @@
+pub mod clock_drift;
+pub mod clock_jump;
 pub mod config;
+pub mod convert;
+pub mod encoding;
 pub mod errors;
+pub mod format;
+pub mod grpc_auth;
+pub mod grpc_metrics;
+pub mod grpc_service;
 pub mod http;
+pub mod leap;
+pub mod logging;
 pub mod metrics;
 pub mod ntp;
 pub mod performance;
 pub mod persist;
+pub mod streaming;
 pub mod timebase;
+pub mod timezone;
@@ -0,0 +1,84 @@
+//! Runtime-adjustable tracing filter.
+//!
+//! `init_logging` installs the `EnvFilter` behind a reload layer so that
+//! `PUT /admin/logging` can change verbosity (e.g. `info,ntp_time_json_api::ntp=debug`)
+//! without a restart, optionally reverting after a TTL.
+
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+pub type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Shared handle to the live log filter.
+pub struct LogFilter {
+    handle: FilterHandle,
+    /// Bumped on every change, so a pending TTL revert can tell whether it
+    /// has been superseded.
+    generation: AtomicU64,
+}
+
+impl LogFilter {
+    pub fn new(handle: FilterHandle) -> Self {
+        Self {
+            handle,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// The active filter directives.
+    pub fn current(&self) -> Result<String> {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .context("log filter is no longer installed")
+    }
+
+    /// Replace the filter.  Returns the previous directives and the
+    /// generation of this change, for [`LogFilter::revert`].
+    pub fn set(&self, directives: &str) -> Result<(String, u64)> {
+        let filter = EnvFilter::try_new(directives)
+            .with_context(|| format!("Invalid log filter '{directives}'"))?;
+        let previous = self.current()?;
+        self.handle
+            .reload(filter)
+            .context("log filter is no longer installed")?;
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        Ok((previous, generation))
+    }
+
+    /// Restore `directives` unless the filter changed again after
+    /// `generation`.  Returns whether the revert was applied.
+    pub fn revert(&self, generation: u64, directives: &str) -> Result<bool> {
+        if self.generation.load(Ordering::Acquire) != generation {
+            return Ok(false);
+        }
+        self.set(directives)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_revert() {
+        let (_layer, handle) = reload::Layer::<_, Registry>::new(EnvFilter::new("info"));
+        let filter = LogFilter::new(handle);
+        assert_eq!(filter.current().unwrap(), "info");
+
+        let (previous, first) = filter.set("info,ntp=debug").unwrap();
+        assert_eq!(previous, "info");
+        assert_eq!(filter.current().unwrap(), "ntp=debug,info");
+
+        // A later change wins over the first change's pending revert.
+        let (_, second) = filter.set("warn").unwrap();
+        assert!(!filter.revert(first, &previous).unwrap());
+        assert_eq!(filter.current().unwrap(), "warn");
+        assert!(filter.revert(second, "info").unwrap());
+        assert_eq!(filter.current().unwrap(), "info");
+
+        assert!(filter.set("ntp=loud").is_err());
+        assert_eq!(filter.current().unwrap(), "info");
+    }
+}
@@ -6,52 +6,127 @@ use tikv_jemallocator::Jemalloc;
 #[global_allocator]
 static GLOBAL: Jemalloc = Jemalloc;
 
-use ntp_time_json_api::config::{Config, LogFormat};
+use anyhow::Context;
+use ntp_time_json_api::clock_jump::ClockJumpDetector;
+use ntp_time_json_api::config::{
+    Config, DnsCheck, ENV_PREFIX, ListenTarget, LogFormat, legacy_env_vars,
+};
+use ntp_time_json_api::grpc_service::GrpcTimeService;
 use ntp_time_json_api::http;
-use ntp_time_json_api::http::state::{AppState, NtpTimingSummary};
+use ntp_time_json_api::http::state::{AppState, ForcedSyncReport, NtpTimingSummary, SyncRequest};
+use ntp_time_json_api::leap;
+use ntp_time_json_api::logging::LogFilter;
 use ntp_time_json_api::metrics::Metrics;
-use ntp_time_json_api::metrics::{RejectLabel, ReplicaLabel};
+use ntp_time_json_api::metrics::{KodLabels, RejectLabel, ReplicaLabel};
+use ntp_time_json_api::ntp::poll::AdaptivePoll;
+use ntp_time_json_api::ntp::roughtime;
 use ntp_time_json_api::ntp::{NtpServer, NtpSyncer, SyncQuality};
 use ntp_time_json_api::performance;
 use ntp_time_json_api::persist;
-use ntp_time_json_api::timebase::TimeBase;
+use ntp_time_json_api::timebase::{Correction, TimeBase};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal;
-use tokio::time::{interval, sleep};
+use tokio::time::{Instant, interval, sleep, sleep_until};
 use tracing::{error, info, warn};
-use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, reload, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let skip_dns_check = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => false,
+        ["--skip-dns-check"] => true,
+        ["config", "schema"] => {
+            println!("{}", serde_json::to_string_pretty(&Config::json_schema())?);
+            return Ok(());
+        }
+        _ => anyhow::bail!("Usage: ntp-time-json-api [--skip-dns-check | config schema]"),
+    };
+
     // Load configuration
-    let config = Arc::new(Config::from_env()?);
+    let mut config = Config::from_env()?;
+    if skip_dns_check {
+        config.ntp.dns_check = DnsCheck::Off;
+    }
+    let config = Arc::new(config);
 
     // Initialize logging
-    init_logging(&config);
+    let log_filter = Arc::new(init_logging(&config));
+
+    let legacy_env_vars = legacy_env_vars();
+    if !legacy_env_vars.is_empty() {
+        warn!(
+            variables = ?legacy_env_vars,
+            "Unprefixed configuration variables are deprecated; use the {} prefix",
+            ENV_PREFIX
+        );
+    }
+
+    let unresolved = config.check_dns().await?;
+    if !unresolved.is_empty() {
+        warn!(
+            servers = ?unresolved,
+            "NTP servers do not resolve; check NTP_SERVERS"
+        );
+    }
 
     info!(
         version = env!("CARGO_PKG_VERSION"),
-        addr = %config.http.addr,
+        listen = ?config.http.listen.iter().map(ToString::to_string).collect::<Vec<_>>(),
         "Starting NTP Time JSON API"
     );
 
+    // A newer leap-second table replaces the bundled one
+    if let Some(path) = &config.ntp.leap_seconds_file {
+        leap::install(leap::LeapTable::load(path)?);
+        info!(path = %path, "Loaded leap-second table");
+    }
+    let leap_table = leap::current();
+    let now_unix_ms = chrono::Utc::now().timestamp_millis();
+    if leap_table.is_expired(now_unix_ms) {
+        warn!(
+            expires_ms = leap_table.expires_ms(),
+            "Leap-second table has expired; set LEAP_SECONDS_FILE to a current leap-seconds.list"
+        );
+    }
+
     // Initialize components
-    let time_cache = Arc::new(performance::TimeCache::new(
-        config.messages.ok.clone(),
-        config.messages.ok_cache.clone(),
-    ));
+    let time_cache = Arc::new(
+        performance::TimeCache::new(config.messages.ok.clone(), config.messages.ok_cache.clone())
+            .with_epoch_as_string(config.http.epoch_as_string),
+    );
     let perf_metrics = Arc::new(performance::LockFreeMetrics::new());
-    let timebase = TimeBase::new(config.ntp.monotonic_output).with_cache(time_cache.clone());
+    let timebase = TimeBase::new(config.ntp.monotonic_output)
+        .with_monotonic_scope(config.ntp.monotonic_scope)
+        .with_slew(config.ntp.slew_rate_ppm, config.ntp.slew_max_offset_ms)
+        .with_max_step(config.ntp.max_step_ms, config.ntp.max_step_stepout)
+        .with_drift_compensation(config.ntp.drift_compensation)
+        .with_cache(time_cache.clone());
     let metrics = Arc::new(Metrics::new());
     let ntp_syncer = Arc::new(NtpSyncer::new(Arc::new(config.ntp.clone())));
-    let state = Arc::new(AppState::new(
-        config.clone(),
-        timebase.clone(),
-        metrics.clone(),
-        time_cache.clone(),
-        perf_metrics.clone(),
-    ));
+    // POST /admin/sync asks the sync loop for an immediate sync
+    let (sync_requests_tx, sync_requests_rx) = tokio::sync::mpsc::channel(8);
+    let state = Arc::new(
+        AppState::new(
+            config.clone(),
+            timebase.clone(),
+            metrics.clone(),
+            time_cache.clone(),
+            perf_metrics.clone(),
+        )
+        .with_ntp_client(ntp_syncer.client())
+        .with_ntp_syncer(ntp_syncer.clone())
+        .with_sync_requests(sync_requests_tx)
+        .with_log_filter(log_filter),
+    );
 
     // Load persisted state if enabled — seeds TimeBase so holdover works on restart
     // when NTP is temporarily unavailable (internet down, DNS failure, etc.).
@@ -86,11 +161,18 @@ async fn main() -> anyhow::Result<()> {
                     reference_id: u32::from_be_bytes(*b"LOAD"),
                     timing_source: TimingSource::Estimated,
                 };
-                timebase.update(&seed);
+                timebase.seed(&seed);
                 info!(
                     saved_epoch_ms = persisted.saved_epoch_ms,
                     elapsed_ms, effective_epoch_ms, "Seeded TimeBase from persisted state"
                 );
+                if let Some(last_served_ms) = persisted.last_served_ms {
+                    timebase.restore_last_served_ms(last_served_ms);
+                    info!(
+                        last_served_ms,
+                        "Restored monotonic floor from persisted state"
+                    );
+                }
             }
             Ok(None) => {
                 info!("No persisted state file found, starting fresh");
@@ -107,6 +189,7 @@ async fn main() -> anyhow::Result<()> {
         timebase.clone(),
         state.clone(),
         config.clone(),
+        sync_requests_rx,
     ));
 
     // Start probe loop (for keeping server stats fresh)
@@ -140,69 +223,228 @@ async fn main() -> anyhow::Result<()> {
     // Create HTTP router
     let app = http::create_router(state.clone());
 
-    // Start HTTP server with TCP optimizations
-    let listener = {
-        use socket2::{Domain, Protocol, Socket, Type};
-        use std::net::SocketAddr as StdSocketAddr;
+    // Load the HTTPS certificate up front so a bad key pair fails startup
+    let tls_store = match (&config.http.tls_cert_file, &config.http.tls_key_file) {
+        (Some(cert_file), Some(key_file)) => Some(Arc::new(
+            http::tls::CertStore::load(cert_file, key_file)
+                .map_err(|e| anyhow::anyhow!("Failed to load TLS certificate: {e}"))?,
+        )),
+        _ => None,
+    };
 
-        let addr: StdSocketAddr = config.http.addr;
-        let domain = if addr.is_ipv4() {
-            Domain::IPV4
-        } else {
-            Domain::IPV6
+    // Bind every ADDR entry (and OPS_ADDR) up front so a bad address fails
+    // startup.  With OPS_ADDR set, probes, metrics and the admin API move to
+    // their own router there.
+    let ops_app = config
+        .http
+        .ops_listen
+        .as_ref()
+        .map(|_| http::create_ops_router(state.clone()));
+    let mut listeners = Vec::with_capacity(config.http.listen.len() + 1);
+    let targets = config
+        .http
+        .listen
+        .iter()
+        .map(|listen| (listen, &app, "main"))
+        .chain(
+            config
+                .http
+                .ops_listen
+                .iter()
+                .zip(&ops_app)
+                .map(|(listen, ops_app)| (listen, ops_app, "ops")),
+        );
+    for (listen, router, role) in targets {
+        let options = config.http.socket_options(listen);
+        let listener = match &listen.target {
+            ListenTarget::Tcp(addr) => BoundListener::Tcp(
+                http::listener::bind_tcp(*addr, &options)
+                    .with_context(|| format!("Failed to bind {listen}"))?,
+            ),
+            #[cfg(unix)]
+            ListenTarget::Unix(path) => BoundListener::Unix(
+                http::listener::bind_unix(path, &options)
+                    .with_context(|| format!("Failed to bind {listen}"))?,
+            ),
+            #[cfg(not(unix))]
+            ListenTarget::Unix(_) => anyhow::bail!("{listen}: unix sockets are not supported"),
         };
+        info!(
+            addr = %listen,
+            role,
+            tcp_nodelay = options.tcp_nodelay,
+            tcp_keepalive = ?options.tcp_keepalive_secs,
+            tls = tls_store.is_some() && matches!(listener, BoundListener::Tcp(_)),
+            http2 = config.http.http2_enabled,
+            listen_backlog = options.backlog,
+            "HTTP server listening"
+        );
+        listeners.push((listener, router.clone()));
+    }
 
-        let socket = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))
-            .expect("Failed to create socket");
+    // Bind the gRPC listener up front too, so a bad GRPC_ADDR fails startup
+    let grpc_listener = if config.grpc.enabled {
+        let listener = tokio::net::TcpListener::bind(config.grpc.addr)
+            .await
+            .with_context(|| format!("Failed to bind GRPC_ADDR {}", config.grpc.addr))?;
+        info!(addr = %config.grpc.addr, "gRPC server listening");
+        Some(listener)
+    } else {
+        None
+    };
+
+    // Export kernel accept-queue overflow counters where the platform has them
+    let listen_stats_handle = if http::listener::read_listen_queue_stats().is_some() {
+        Some(tokio::spawn(listen_queue_loop(state.clone())))
+    } else {
+        info!("Accept-queue overflow counters not available on this platform");
+        None
+    };
 
-        // Enable SO_REUSEADDR for faster restarts
-        socket
-            .set_reuse_address(true)
-            .expect("Failed to set SO_REUSEADDR");
+    // Keep the persisted snapshot's monotonic floor close to what was served
+    let persist_handle = config
+        .persist
+        .enabled
+        .then(|| tokio::spawn(persist_loop(state.clone())));
 
-        // Enable TCP_NODELAY for lower latency (disable Nagle's algorithm)
-        if config.http.tcp_nodelay {
-            socket
-                .set_tcp_nodelay(true)
-                .expect("Failed to set TCP_NODELAY");
-        }
+    // Compare the host clock against NTP time for consumers still using it
+    let system_clock_handle = tokio::spawn(system_clock_loop(state.clone()));
 
-        // Enable TCP keepalive if configured
-        if let Some(keepalive_secs) = config.http.tcp_keepalive_secs {
-            let keepalive = socket2::TcpKeepalive::new()
-                .with_time(std::time::Duration::from_secs(keepalive_secs));
-            socket
-                .set_tcp_keepalive(&keepalive)
-                .expect("Failed to set TCP keepalive");
-        }
+    // Resync straight after a suspend/resume or clock jump
+    let clock_jump_handle = (config.quality.clock_jump_threshold_ms > 0)
+        .then(|| tokio::spawn(clock_jump_loop(state.clone())));
 
-        socket
-            .set_nonblocking(true)
-            .expect("Failed to set non-blocking");
-        socket.bind(&addr.into()).expect("Failed to bind");
-        socket.listen(1024).expect("Failed to listen");
+    // Cross-check NTP time against signed Roughtime bounds
+    let roughtime_handle = if config.roughtime.servers.is_empty() {
+        None
+    } else {
+        Some(tokio::spawn(roughtime_loop(state.clone())))
+    };
 
-        tokio::net::TcpListener::from_std(socket.into())
-            .expect("Failed to convert to tokio listener")
+    // Keep the RS256 signing keys in step with the identity provider
+    let jwks_handle = if config.jwt.jwks_url.is_some() {
+        Some(tokio::spawn(jwks_loop(state.clone())))
+    } else {
+        None
     };
 
-    info!(
-        addr = %config.http.addr,
-        tcp_nodelay = config.http.tcp_nodelay,
-        tcp_keepalive = ?config.http.tcp_keepalive_secs,
-        "HTTP server listening"
-    );
+    // Pick up renewed certificates without a restart
+    let tls_reload_handle = tls_store
+        .clone()
+        .map(|store| tokio::spawn(tls_reload_loop(store, config.clone())));
 
-    // into_make_service_with_connect_info is required: tower_governor's PeerIpKeyExtractor reads ConnectInfo<SocketAddr>.
-    let http_server = axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
-    )
-    .with_graceful_shutdown(shutdown_signal());
+    // One shutdown signal, fanned out to every listener.  /readyz fails
+    // first, and the listeners keep accepting for the readiness delay so load
+    // balancers see the 503 and stop routing here before connections are refused.
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let shutdown_state = state.clone();
+    let readiness_delay = Duration::from_secs(config.http.shutdown_readiness_delay_secs);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        shutdown_state.fail_readiness();
+        if !readiness_delay.is_zero() {
+            info!(
+                delay_secs = readiness_delay.as_secs(),
+                "Readiness failed; closing listeners after the delay"
+            );
+            sleep(readiness_delay).await;
+        }
+        shutdown_state.begin_shutdown();
+        let _ = shutdown_tx.send(true);
+    });
+    let tls_config = tls_store
+        .as_ref()
+        .map(|store| store.server_config(config.http.http2_enabled))
+        .transpose()?;
 
-    // Run HTTP server and wait for shutdown
-    if let Err(e) = http_server.await {
-        error!(error = %e, "HTTP server error");
+    // Run the HTTP servers (HTTP/1.1 and h2c, or HTTPS on TCP) and wait for
+    // shutdown. Each counts accepted/open connections, and http::server::serve
+    // attaches the ConnectInfo<SocketAddr> the rate limiter's
+    // ClientIpKeyExtractor reads.
+    let mut servers: Vec<Pin<Box<dyn Future<Output = ()> + Send>>> = Vec::new();
+    for (listener, app) in listeners {
+        let mut shutdown_rx = shutdown_rx.clone();
+        let shutdown = async move {
+            let _ = shutdown_rx.wait_for(|stop| *stop).await;
+        };
+        let http_config = config.http.clone();
+        servers.push(match (listener, &tls_config) {
+            (BoundListener::Tcp(tcp), Some(tls_config)) => {
+                let listener = http::tls::TlsListener::new(
+                    http::listener::CountingListener::new(tcp, metrics.clone()),
+                    tls_config.clone(),
+                    metrics.clone(),
+                )?;
+                Box::pin(
+                    async move { http::server::serve(listener, app, &http_config, shutdown).await },
+                )
+            }
+            (BoundListener::Tcp(tcp), None) => {
+                let listener = http::listener::CountingListener::new(tcp, metrics.clone());
+                Box::pin(
+                    async move { http::server::serve(listener, app, &http_config, shutdown).await },
+                )
+            }
+            #[cfg(unix)]
+            (BoundListener::Unix(unix), _) => {
+                let listener = http::listener::CountingListener::new(unix, metrics.clone());
+                Box::pin(
+                    async move { http::server::serve(listener, app, &http_config, shutdown).await },
+                )
+            }
+        });
+    }
+    if let Some(listener) = grpc_listener {
+        let mut shutdown_rx = shutdown_rx.clone();
+        let router = GrpcTimeService::new(state.clone()).router()?;
+        servers.push(Box::pin(async move {
+            let result = router
+                .serve_with_incoming_shutdown(
+                    tonic::transport::server::TcpIncoming::from(listener),
+                    async move {
+                        let _ = shutdown_rx.wait_for(|stop| *stop).await;
+                    },
+                )
+                .await;
+            if let Err(e) = result {
+                error!(error = %e, "gRPC server terminated");
+            }
+        }));
+    }
+    let servers = futures_util::future::join_all(servers);
+    tokio::pin!(servers);
+    tokio::select! {
+        _ = &mut servers => {}
+        _ = async {
+            let _ = shutdown_rx.clone().wait_for(|stop| *stop).await;
+        } => {
+            // Listeners are closed and streams have been sent Close 1001;
+            // give in-flight requests and the closing handshakes up to
+            // SHUTDOWN_DRAIN_SECS before stopping everything.
+            let drain = Duration::from_secs(config.http.shutdown_drain_secs);
+            info!(drain_secs = drain.as_secs(), "Draining in-flight requests and streams");
+            let drained = tokio::time::timeout(drain, async {
+                (&mut servers).await;
+                while metrics.ws_connections_active.get() > 0 {
+                    sleep(Duration::from_millis(100)).await;
+                }
+            })
+            .await;
+            if drained.is_err() {
+                warn!(
+                    open_connections = metrics.http_connections_open.get(),
+                    open_streams = metrics.ws_connections_active.get(),
+                    "Drain period elapsed with connections still open"
+                );
+            }
+        }
+    }
+
+    // Leave no socket files behind for the next start to replace
+    for listen in config.http.listen.iter().chain(&config.http.ops_listen) {
+        if let ListenTarget::Unix(path) = &listen.target {
+            let _ = std::fs::remove_file(path);
+        }
     }
 
     info!("Shutting down...");
@@ -214,6 +456,27 @@ async fn main() -> anyhow::Result<()> {
     if let Some(h) = ntp_server_handle.as_ref() {
         h.abort();
     }
+    if let Some(h) = listen_stats_handle {
+        h.abort();
+    }
+    system_clock_handle.abort();
+    if let Some(h) = clock_jump_handle {
+        h.abort();
+    }
+    if let Some(h) = persist_handle {
+        h.abort();
+        // Nothing is served after the drain, so this floor is final.
+        persist_time_state(&state);
+    }
+    if let Some(h) = roughtime_handle {
+        h.abort();
+    }
+    if let Some(h) = jwks_handle {
+        h.abort();
+    }
+    if let Some(h) = tls_reload_handle {
+        h.abort();
+    }
     sync_handle.abort();
     probe_handle.abort();
 
@@ -242,25 +505,62 @@ async fn sync_loop(
     timebase: TimeBase,
     state: Arc<AppState>,
     config: Arc<Config>,
+    mut sync_requests: tokio::sync::mpsc::Receiver<SyncRequest>,
 ) {
-    let mut sync_interval = interval(config.sync_interval());
+    let mut poll = AdaptivePoll::new(
+        config.sync_interval(),
+        Duration::from_secs(config.ntp.sync_interval_max_secs),
+        config.ntp.poll_stable_offset_ms,
+    );
+
+    // Callers of POST /admin/sync waiting for the next sync's result
+    let mut pending_requests: Vec<SyncRequest> = Vec::new();
 
     // Add initial jitter to avoid thundering herd
     let jitter = rand::random::<u64>() % 5000;
-    sleep(Duration::from_millis(jitter)).await;
+    tokio::select! {
+        _ = sleep(Duration::from_millis(jitter)) => {}
+        Some(request) = sync_requests.recv() => pending_requests.push(request),
+    }
 
     loop {
-        sync_interval.tick().await;
-
+        let started = Instant::now();
         state.metrics.ntp_sync_total.inc();
 
-        match syncer.sync().await {
+        let synced = match syncer.sync().await {
+            // Slew, step or reject by step size (SLEW_MAX_OFFSET_MS, MAX_STEP_MS)
+            Ok(outcome) => match timebase.update(&outcome.result) {
+                Correction::Rejected { step_ms } => {
+                    error!(
+                        server = %outcome.result.server,
+                        step_ms,
+                        max_step_ms = config.ntp.max_step_ms,
+                        "Rejected NTP sync result: time step exceeds MAX_STEP_MS"
+                    );
+                    state.metrics.ntp_step_rejected_total.inc();
+                    Err(anyhow::anyhow!(
+                        "time step of {} ms exceeds MAX_STEP_MS ({} ms)",
+                        step_ms,
+                        config.ntp.max_step_ms
+                    ))
+                }
+                correction => {
+                    state.record_correction(correction);
+                    Ok(outcome)
+                }
+            },
+            Err(e) => Err(e),
+        };
+
+        let report = match synced {
             Ok(outcome) => {
+                let report = ForcedSyncReport::from_outcome(&outcome);
+                if outcome.fallback_tier {
+                    state.metrics.ntp_fallback_tier_syncs_total.inc();
+                }
                 let result = outcome.result;
                 let diag = outcome.diagnostics;
-
-                // Update timebase
-                timebase.update(&result);
+                poll.on_success(result.offset_ms);
 
                 // Update state
                 state.record_sync_success();
@@ -281,6 +581,7 @@ async fn sync_loop(
                     .metrics
                     .ntp_offset_seconds
                     .set(result.offset_ms as f64 / 1000.0);
+                state.metrics.ntp_clock_drift_ppm.set(timebase.drift_ppm());
                 let rtt_ms = result.rtt.as_millis() as u64;
                 state
                     .last_rtt_ms
@@ -432,27 +733,7 @@ async fn sync_loop(
 
                 // Persist last-good state if enabled
                 if config.persist.enabled {
-                    let now_unix_ms = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap_or_default()
-                        .as_millis() as i64;
-                    let persisted = persist::PersistedState {
-                        version: persist::PERSIST_VERSION,
-                        saved_epoch_ms: result.epoch_ms,
-                        saved_at_unix_ms: now_unix_ms,
-                        uncertainty_ms: quality.uncertainty_ms,
-                        source: "ntp".to_string(),
-                        selected_server: Some(result.server.clone()),
-                        selected_provider: None,
-                        last_successful_ntp_sync_unix_ms: Some(now_unix_ms),
-                    };
-                    if let Err(e) = persist::save_state(&config.persist.file_path, &persisted) {
-                        warn!(
-                            error = %e,
-                            path = %config.persist.file_path,
-                            "Failed to persist time state"
-                        );
-                    }
+                    persist_time_state(&state);
                 }
 
                 info!(
@@ -461,8 +742,10 @@ async fn sync_loop(
                     offset_ms = result.offset_ms,
                     "NTP sync successful"
                 );
+                Ok(report)
             }
             Err(e) => {
+                poll.on_failure();
                 state.record_sync_failure();
                 state.metrics.ntp_sync_errors_total.inc();
                 state
@@ -508,13 +791,64 @@ async fn sync_loop(
                         "NTP sync failed; service not yet synchronized"
                     );
                 }
+                Err(format!("{e:#}"))
             }
+        };
+
+        for kod in syncer.take_kod_events() {
+            state
+                .metrics
+                .ntp_kod_received_total
+                .get_or_create(&KodLabels {
+                    server: kod.server,
+                    code: kod.code,
+                })
+                .inc();
         }
 
+        // Refresh the raw per-server exchanges served by /debug/ntp and the
+        // asymmetry estimates served by /status
+        let stats = syncer.get_stats().await;
+        if config.ntp.calibration.asymmetry_enabled {
+            *state.path_asymmetry.write() = stats
+                .iter()
+                .filter_map(|(server, stat)| Some((server.clone(), stat.asymmetry.estimate()?)))
+                .collect();
+        }
+        *state.last_ntp_exchanges.write() = stats
+            .into_iter()
+            .filter_map(|(server, stat)| stat.last_exchange.map(|sample| (server, sample)))
+            .collect();
+
         // Update staleness metric
         if let Some(staleness) = state.get_staleness_seconds() {
             state.metrics.ntp_staleness_seconds.set(staleness as i64);
         }
+        state.update_quality_metrics();
+        for request in pending_requests.drain(..) {
+            let _ = request.send(report.clone());
+        }
+
+        // Adaptive poll: the next sync is due `interval` after this one started.
+        let next = poll.interval();
+        state
+            .metrics
+            .ntp_poll_interval_seconds
+            .set(next.as_secs() as i64);
+        tokio::select! {
+            _ = sleep_until(started + next) => {}
+            Some(request) = sync_requests.recv() => {
+                // Requests that arrive together share one sync
+                pending_requests.push(request);
+                while let Ok(request) = sync_requests.try_recv() {
+                    pending_requests.push(request);
+                }
+                info!(
+                    requests = pending_requests.len(),
+                    "Immediate NTP sync requested"
+                );
+            }
+        }
     }
 }
 
@@ -545,6 +879,16 @@ async fn probe_loop(syncer: Arc<NtpSyncer>, state: Arc<AppState>, config: Arc<Co
                 })
                 .set(is_up);
 
+            if config.ntp.calibration.enabled && stat.bias.samples > 0 {
+                state
+                    .metrics
+                    .ntp_server_bias_estimate_milliseconds
+                    .get_or_create(&ntp_time_json_api::metrics::ServerLabel {
+                        server: server.clone(),
+                    })
+                    .set(stat.bias.estimate_ms);
+            }
+
             if let Some(rtt) = stat.last_rtt {
                 state
                     .metrics
@@ -556,10 +900,200 @@ async fn probe_loop(syncer: Arc<NtpSyncer>, state: Arc<AppState>, config: Arc<Co
     }
 }
 
-/// Initialize logging based on configuration
-fn init_logging(config: &Config) {
+/// Persist loop - rewrites the state snapshot every
+/// `TIME_STATE_PERSIST_INTERVAL_SECS`, so a crash loses at most that much of
+/// the monotonic floor (syncs and shutdown write it too).
+async fn persist_loop(state: Arc<AppState>) {
+    let mut ticker = interval(Duration::from_secs(state.config.persist.interval_secs));
+    loop {
+        ticker.tick().await;
+        persist_time_state(&state);
+    }
+}
+
+/// Write the current time base to `TIME_STATE_FILE`: the NTP projection
+/// (as the base for a holdover seed), the last sync, and the last served
+/// epoch_ms.  Skipped until the time base has been synced or seeded.
+fn persist_time_state(state: &AppState) {
+    let Some(saved_epoch_ms) = state.timebase.ntp_base_now_ms() else {
+        return;
+    };
+    let now_unix_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    let quality = state.compute_quality();
+    let last_sync_unix_ms = state
+        .last_sync_time
+        .read()
+        .map(|at| now_unix_ms - at.elapsed().as_millis() as i64);
+    let persisted = persist::PersistedState {
+        version: persist::PERSIST_VERSION,
+        saved_epoch_ms,
+        saved_at_unix_ms: now_unix_ms,
+        uncertainty_ms: quality.uncertainty_ms,
+        source: quality.source.to_string(),
+        selected_server: state.timebase.last_sync().map(|sync| sync.server),
+        selected_provider: None,
+        last_successful_ntp_sync_unix_ms: last_sync_unix_ms,
+        last_served_ms: Some(state.timebase.last_served_ms()).filter(|ms| *ms > 0),
+    };
+    let path = &state.config.persist.file_path;
+    if let Err(e) = persist::save_state(path, &persisted) {
+        warn!(error = %e, path = %path, "Failed to persist time state");
+    }
+}
+
+/// Accept-queue loop - mirrors the kernel listen overflow/drop counters into
+/// Prometheus so SYN drops during traffic spikes are visible.
+async fn listen_queue_loop(state: Arc<AppState>) {
+    let mut ticker = interval(Duration::from_secs(15));
+    let mut last = http::listener::ListenQueueStats::default();
+
+    loop {
+        ticker.tick().await;
+
+        let Some(stats) = http::listener::read_listen_queue_stats() else {
+            continue;
+        };
+        let new_overflows = stats.listen_overflows.saturating_sub(last.listen_overflows);
+        let new_drops = stats.listen_drops.saturating_sub(last.listen_drops);
+        state
+            .metrics
+            .tcp_listen_overflows_total
+            .inc_by(new_overflows);
+        state.metrics.tcp_listen_drops_total.inc_by(new_drops);
+
+        if new_overflows > 0 && last != http::listener::ListenQueueStats::default() {
+            warn!(
+                new_overflows,
+                listen_backlog = state.config.http.listen_backlog,
+                "Accept queue overflowed; consider raising LISTEN_BACKLOG / net.core.somaxconn"
+            );
+        }
+        last = stats;
+    }
+}
+
+/// System-clock loop - reports host clock vs NTP divergence and raises the
+/// alert when it exceeds `SYSTEM_CLOCK_DIVERGENCE_ALERT_MS`.
+async fn system_clock_loop(state: Arc<AppState>) {
+    let mut ticker = interval(Duration::from_secs(
+        state.config.quality.system_clock_check_interval_secs,
+    ));
+
+    loop {
+        ticker.tick().await;
+        state.check_system_clock();
+    }
+}
+
+/// Clock-jump loop - compares the monotonic clock against boot time every
+/// `CLOCK_JUMP_CHECK_INTERVAL_SECS`; a suspend longer than
+/// `CLOCK_JUMP_THRESHOLD_MS` marks time stale and triggers a resync.
+async fn clock_jump_loop(state: Arc<AppState>) {
+    let mut detector = ClockJumpDetector::new(state.config.quality.clock_jump_threshold_ms);
+    let mut ticker = interval(Duration::from_secs(
+        state.config.quality.clock_jump_check_interval_secs,
+    ));
+
+    loop {
+        ticker.tick().await;
+        if let Some(jump) = detector.check() {
+            state.record_clock_jump(&jump);
+        }
+    }
+}
+
+/// Roughtime loop - queries every `ROUGHTIME_SERVERS` entry each
+/// `ROUGHTIME_INTERVAL_SECS` and raises the alert when NTP time falls outside
+/// a signed bound.
+async fn roughtime_loop(state: Arc<AppState>) {
+    let config = &state.config.roughtime;
+    let timeout = Duration::from_millis(state.config.ntp.timeout_ms);
+    let mut ticker = interval(Duration::from_secs(config.interval_secs));
+
+    loop {
+        ticker.tick().await;
+        for server in &config.servers {
+            match roughtime::query(server, timeout).await {
+                Ok(sample) => {
+                    state.check_roughtime(&sample);
+                }
+                Err(e) => {
+                    state
+                        .metrics
+                        .roughtime_errors_total
+                        .get_or_create(&ntp_time_json_api::metrics::ServerLabel {
+                            server: server.address.clone(),
+                        })
+                        .inc();
+                    warn!(server = %server.address, error = %e, "Roughtime query failed");
+                }
+            }
+        }
+    }
+}
+
+/// A bound `ADDR` entry, before metrics and TLS wrapping.
+enum BoundListener {
+    Tcp(tokio::net::TcpListener),
+    #[cfg(unix)]
+    Unix(http::listener::UnixSocketListener),
+}
+
+/// TLS reload loop - checks `TLS_CERT_FILE` / `TLS_KEY_FILE` every
+/// `TLS_RELOAD_INTERVAL_SECS` and installs the new pair when they change.  A
+/// pair that fails to load (e.g. caught mid-rotation) keeps the old one.
+async fn tls_reload_loop(store: Arc<http::tls::CertStore>, config: Arc<Config>) {
+    let mut ticker = interval(Duration::from_secs(config.http.tls_reload_interval_secs));
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        match store.reload_if_changed() {
+            Ok(true) => info!("TLS certificate reloaded"),
+            Ok(false) => {}
+            Err(e) => {
+                warn!(error = %e, "TLS certificate reload failed; keeping previous certificate")
+            }
+        }
+    }
+}
+
+/// JWKS loop - re-fetches `JWT_JWKS_URL` every `JWT_JWKS_REFRESH_SECS` so
+/// rotated signing keys are picked up.  A failed fetch keeps the previous keys.
+async fn jwks_loop(state: Arc<AppState>) {
+    let Some(validator) = state.jwt.clone() else {
+        return;
+    };
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            error!(error = %e, "Failed to build JWKS HTTP client");
+            return;
+        }
+    };
+    let mut ticker = interval(Duration::from_secs(state.config.jwt.jwks_refresh_secs));
+
+    loop {
+        ticker.tick().await;
+        match validator.refresh_jwks(&client).await {
+            Ok(keys) => info!(keys, "JWKS refreshed"),
+            Err(e) => warn!(error = %e, "JWKS refresh failed; keeping previous keys"),
+        }
+    }
+}
+
+/// Initialize logging based on configuration.  The filter is installed
+/// behind a reload layer so `PUT /admin/logging` can change it at runtime.
+fn init_logging(config: &Config) -> LogFilter {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.logging.level));
+    let (env_filter, handle) = reload::Layer::new(env_filter);
 
     match config.logging.format {
         LogFormat::Json => {
@@ -575,6 +1109,7 @@ fn init_logging(config: &Config) {
                 .init();
         }
     }
+    LogFilter::new(handle)
 }
 
 /// Graceful shutdown signal handler
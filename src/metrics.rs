@@ -15,6 +15,17 @@ pub struct HttpLabels {
     pub status: String,
 }
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct GrpcLabels {
+    pub method: String,
+    pub code: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct GrpcMethodLabel {
+    pub method: String,
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
 pub struct ServerLabel {
     pub server: String,
@@ -26,6 +37,12 @@ pub struct BuildInfoLabels {
     pub git_sha: String,
 }
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct KodLabels {
+    pub server: String,
+    pub code: String,
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
 pub struct RejectLabel {
     pub reason: String,
@@ -43,17 +60,45 @@ pub struct Metrics {
     pub http_requests_total: Family<HttpLabels, Counter>,
     pub http_request_duration_seconds: Family<HttpLabels, Histogram>,
     pub http_inflight_requests: Gauge,
+    /// Currently open HTTP connections (counted at accept, released on close).
+    pub http_connections_open: Gauge,
+    /// Total HTTP connections accepted since process start.
+    pub http_connections_accepted_total: Counter,
+    /// Lifetime of closed HTTP connections.
+    pub http_connection_duration_seconds: Histogram,
+    /// Failed TLS handshakes on the HTTP listener (stays 0 without TLS termination).
+    pub http_tls_handshake_failures_total: Counter,
+    /// Requests rejected with 429 by the per-client rate limiter.
+    pub http_rate_limited_total: Counter,
+    /// Requests rejected with 503 because `MAX_INFLIGHT_REQUESTS` was reached.
+    pub http_load_shed_total: Counter,
+    /// Requests rejected with 403 by `IP_ALLOWLIST` / `IP_DENYLIST`.
+    pub http_ip_filtered_total: Counter,
+    /// Kernel `TcpExt.ListenOverflows` (accept queue full). Linux only.
+    pub tcp_listen_overflows_total: Counter,
+    /// Kernel `TcpExt.ListenDrops` (SYNs dropped on listeners). Linux only.
+    pub tcp_listen_drops_total: Counter,
 
     // NTP client metrics
     pub ntp_sync_total: Counter,
     pub ntp_sync_errors_total: Counter,
+    /// Sync results rejected for stepping served time by more than `MAX_STEP_MS`.
+    pub ntp_step_rejected_total: Counter,
+    /// Successful syncs that had to query the fallback server tier.
+    pub ntp_fallback_tier_syncs_total: Counter,
+    /// Current (adaptive) interval until the next sync, in seconds.
+    pub ntp_poll_interval_seconds: Gauge,
     pub ntp_last_sync_timestamp_seconds: Gauge,
     pub ntp_staleness_seconds: Gauge,
     pub ntp_offset_seconds: Gauge<f64, AtomicU64>,
+    /// Estimated frequency error of the local clock (`DRIFT_COMPENSATION`).
+    pub ntp_clock_drift_ppm: Gauge<f64, AtomicU64>,
     pub ntp_rtt_seconds: Histogram,
     pub ntp_server_up: Family<ServerLabel, Gauge>,
     /// Most recent RTT for each NTP *client* server, in milliseconds.
     pub ntp_server_rtt_milliseconds: Family<ServerLabel, Gauge>,
+    /// Kiss-o'-Death replies received, by upstream server and kiss code.
+    pub ntp_kod_received_total: Family<KodLabels, Counter>,
     pub ntp_consecutive_failures: Gauge,
 
     // NTP server (responds to NTP clients on UDP) metrics
@@ -67,10 +112,31 @@ pub struct Metrics {
     // Time-quality envelope metrics (P0-4)
     /// Computed time uncertainty (ms) from the most recent sync quality snapshot.
     pub time_uncertainty_milliseconds: Gauge<f64, AtomicU64>,
+    /// Error bound (ms) of the served time; see `TimeQuality::error_bound_ms`.
+    pub time_error_bound_milliseconds: Gauge<f64, AtomicU64>,
     /// Encoded time source mode: 0=ntp, 1=degraded, 2=unsynced, 3=manual, 4=holdover.
     pub time_source_mode: Gauge,
     /// Encoded serve state: 0=ok, 1=degraded, 2=stopped, 3=unsynced, 4=holdover.
     pub time_serve_state: Gauge,
+    /// Composite quality score in [0, 1]; see `http::state::quality_score`.
+    pub time_quality_score: Gauge<f64, AtomicU64>,
+    /// Host clock minus NTP time (ms); positive = host clock ahead.
+    pub time_system_clock_divergence_milliseconds: Gauge,
+    /// 1 while the host-clock divergence exceeds `SYSTEM_CLOCK_DIVERGENCE_ALERT_MS`.
+    pub time_system_clock_alert: Gauge,
+    /// Times the system-clock divergence alert has fired.
+    pub time_system_clock_alerts_total: Counter,
+    /// Suspend/resume gaps detected in the monotonic clock.
+    pub time_clock_jumps_total: Counter,
+    /// NTP time minus each Roughtime server's signed midpoint (ms).
+    pub roughtime_offset_milliseconds: Family<ServerLabel, Gauge>,
+    /// 1 while NTP time lies outside a server's signed Roughtime bound by
+    /// more than `ROUGHTIME_MAX_DISAGREEMENT_MS`.
+    pub roughtime_alert: Family<ServerLabel, Gauge>,
+    /// Times the Roughtime disagreement alert has fired.
+    pub roughtime_alerts_total: Counter,
+    /// Roughtime queries that failed or did not verify.
+    pub roughtime_errors_total: Family<ServerLabel, Counter>,
 
     // P1-6 selection metrics
     /// Number of agreers in the most recent weighted-median selection.
@@ -79,6 +145,8 @@ pub struct Metrics {
     pub ntp_selection_falsetickers_total: Counter,
     /// Per-server λ (root distance) from the most recent selection (ms).
     pub ntp_sample_uncertainty_milliseconds: Family<ServerLabel, Gauge<f64, AtomicU64>>,
+    /// Learned per-server offset bias (ms) when bias calibration is enabled.
+    pub ntp_server_bias_estimate_milliseconds: Family<ServerLabel, Gauge<f64, AtomicU64>>,
     /// Combined uncertainty of the selected result (ms); set after each sync.
     pub ntp_combined_uncertainty_milliseconds: Gauge<f64, AtomicU64>,
     /// Total samples rejected in selection, broken down by reason.
@@ -108,6 +176,34 @@ pub struct Metrics {
     /// Time source mode of this replica: 0=ntp, 1=degraded, 2=unsynced, 3=manual, 4=holdover.
     pub time_replica_source_mode: Family<ReplicaLabel, Gauge>,
 
+    // WebSocket stream metrics
+    /// Ticks discarded for slow stream clients, by policy (drop_oldest, coalesce).
+    pub ws_messages_dropped_total: Family<RejectLabel, Counter>,
+    /// Stream connections closed because their send queue overflowed.
+    pub ws_slow_consumer_disconnects_total: Counter,
+    /// Currently open stream connections.
+    pub ws_connections_active: Gauge,
+    /// Stream handshakes refused with 429 because `WS_MAX_CONNECTIONS` were open.
+    pub websocket_rejected_total: Counter,
+    /// Ticks written to stream clients.
+    pub ws_messages_sent_total: Counter,
+    /// Enqueue-to-written latency of stream ticks.
+    pub ws_send_latency_seconds: Histogram,
+    /// Ticks queued for sending, summed over open connections.
+    pub ws_send_queue_depth: Gauge,
+    /// Round-trip time from a server Ping to the client's Pong.
+    pub ws_pong_latency_seconds: Histogram,
+    /// Stream connections closed as dead, by reason (heartbeat, idle).
+    pub ws_liveness_disconnects_total: Family<RejectLabel, Counter>,
+
+    // gRPC metrics
+    /// Finished RPCs by method and gRPC status code.
+    pub grpc_requests_total: Family<GrpcLabels, Counter>,
+    /// Call duration (stream lifetime for streaming RPCs) by method and code.
+    pub grpc_request_duration_seconds: Family<GrpcLabels, Histogram>,
+    /// RPCs currently in flight, by method.
+    pub grpc_active_streams: Family<GrpcMethodLabel, Gauge>,
+
     // Manual override metrics (P1-7)
     /// 1 while a manual time override is active, 0 otherwise.
     pub manual_override_active: Gauge,
@@ -154,6 +250,72 @@ impl Metrics {
             http_inflight_requests.clone(),
         );
 
+        // Connection-level metrics
+        let http_connections_open = Gauge::default();
+        registry.register(
+            "http_connections_open",
+            "Number of currently open HTTP connections",
+            http_connections_open.clone(),
+        );
+
+        let http_connections_accepted_total = Counter::default();
+        registry.register(
+            "http_connections_accepted_total",
+            "Total number of accepted HTTP connections",
+            http_connections_accepted_total.clone(),
+        );
+
+        let http_connection_duration_seconds = Histogram::new(
+            exponential_buckets(0.01, 4.0, 10), // 10ms to ~44min
+        );
+        registry.register(
+            "http_connection_duration_seconds",
+            "Lifetime of closed HTTP connections in seconds",
+            http_connection_duration_seconds.clone(),
+        );
+
+        let http_tls_handshake_failures_total = Counter::default();
+        registry.register(
+            "http_tls_handshake_failures_total",
+            "Total number of failed TLS handshakes on the HTTP listener",
+            http_tls_handshake_failures_total.clone(),
+        );
+
+        let http_rate_limited_total = Counter::default();
+        registry.register(
+            "http_rate_limited_total",
+            "Total number of requests rejected by the per-client rate limiter",
+            http_rate_limited_total.clone(),
+        );
+
+        let http_load_shed_total = Counter::default();
+        registry.register(
+            "http_load_shed_total",
+            "Total number of requests shed with 503 because too many were in flight",
+            http_load_shed_total.clone(),
+        );
+
+        let http_ip_filtered_total = Counter::default();
+        registry.register(
+            "http_ip_filtered_total",
+            "Total number of requests rejected by the client IP allowlist/denylist",
+            http_ip_filtered_total.clone(),
+        );
+
+        let tcp_listen_overflows_total = Counter::default();
+        registry.register(
+            "tcp_listen_overflows_total",
+            "Times a listen socket's accept queue overflowed (kernel TcpExt.ListenOverflows)",
+            tcp_listen_overflows_total.clone(),
+        );
+
+        let tcp_listen_drops_total = Counter::default();
+        registry.register(
+            "tcp_listen_drops_total",
+            "SYNs dropped on listen sockets (kernel TcpExt.ListenDrops)",
+            tcp_listen_drops_total.clone(),
+        );
+
         // NTP metrics
         let ntp_sync_total = Counter::default();
         registry.register(
@@ -162,6 +324,13 @@ impl Metrics {
             ntp_sync_total.clone(),
         );
 
+        let ntp_poll_interval_seconds = Gauge::default();
+        registry.register(
+            "ntp_poll_interval_seconds",
+            "Current adaptive interval between NTP syncs in seconds",
+            ntp_poll_interval_seconds.clone(),
+        );
+
         let ntp_sync_errors_total = Counter::default();
         registry.register(
             "ntp_sync_errors_total",
@@ -169,6 +338,20 @@ impl Metrics {
             ntp_sync_errors_total.clone(),
         );
 
+        let ntp_step_rejected_total = Counter::default();
+        registry.register(
+            "ntp_step_rejected_total",
+            "Total number of NTP sync results rejected for exceeding MAX_STEP_MS",
+            ntp_step_rejected_total.clone(),
+        );
+
+        let ntp_fallback_tier_syncs_total = Counter::default();
+        registry.register(
+            "ntp_fallback_tier_syncs_total",
+            "Total number of NTP syncs that queried the fallback server tier",
+            ntp_fallback_tier_syncs_total.clone(),
+        );
+
         let ntp_last_sync_timestamp_seconds = Gauge::default();
         registry.register(
             "ntp_last_sync_timestamp_seconds",
@@ -190,6 +373,13 @@ impl Metrics {
             ntp_offset_seconds.clone(),
         );
 
+        let ntp_clock_drift_ppm = Gauge::<f64, AtomicU64>::default();
+        registry.register(
+            "ntp_clock_drift_ppm",
+            "Estimated frequency error of the local clock in ppm",
+            ntp_clock_drift_ppm.clone(),
+        );
+
         let ntp_rtt_seconds = Histogram::new(
             exponential_buckets(0.001, 2.0, 10), // 1ms to ~1s
         );
@@ -213,6 +403,13 @@ impl Metrics {
             ntp_server_rtt_milliseconds.clone(),
         );
 
+        let ntp_kod_received_total = Family::<KodLabels, Counter>::default();
+        registry.register(
+            "ntp_kod_received_total",
+            "Kiss-o'-Death replies received from upstream NTP servers, by server and kiss code",
+            ntp_kod_received_total.clone(),
+        );
+
         let ntp_consecutive_failures = Gauge::default();
         registry.register(
             "ntp_consecutive_failures",
@@ -243,6 +440,14 @@ impl Metrics {
             ntp_sample_uncertainty_milliseconds.clone(),
         );
 
+        let ntp_server_bias_estimate_milliseconds =
+            Family::<ServerLabel, Gauge<f64, AtomicU64>>::default();
+        registry.register(
+            "ntp_server_bias_estimate_milliseconds",
+            "Learned per-server offset bias against the calibration reference (ms)",
+            ntp_server_bias_estimate_milliseconds.clone(),
+        );
+
         let ntp_combined_uncertainty_milliseconds = Gauge::<f64, AtomicU64>::default();
         registry.register(
             "ntp_combined_uncertainty_milliseconds",
@@ -344,6 +549,13 @@ impl Metrics {
             time_uncertainty_milliseconds.clone(),
         );
 
+        let time_error_bound_milliseconds = Gauge::<f64, AtomicU64>::default();
+        registry.register(
+            "time_error_bound_milliseconds",
+            "Error bound (ms) of the served time: its RFC 5905 dispersion plus any correction still being slewed in",
+            time_error_bound_milliseconds.clone(),
+        );
+
         let time_source_mode = Gauge::default();
         registry.register(
             "time_source_mode",
@@ -358,6 +570,69 @@ impl Metrics {
             time_serve_state.clone(),
         );
 
+        let time_quality_score = Gauge::<f64, AtomicU64>::default();
+        registry.register(
+            "time_quality_score",
+            "Composite sync quality score in [0, 1] (agreement, jitter, staleness, uncertainty)",
+            time_quality_score.clone(),
+        );
+
+        let time_system_clock_divergence_milliseconds = Gauge::default();
+        registry.register(
+            "time_system_clock_divergence_milliseconds",
+            "Host clock (SystemTime) minus NTP-derived time in milliseconds",
+            time_system_clock_divergence_milliseconds.clone(),
+        );
+
+        let time_system_clock_alert = Gauge::default();
+        registry.register(
+            "time_system_clock_alert",
+            "1 while host-clock divergence exceeds SYSTEM_CLOCK_DIVERGENCE_ALERT_MS",
+            time_system_clock_alert.clone(),
+        );
+
+        let time_system_clock_alerts_total = Counter::default();
+        registry.register(
+            "time_system_clock_alerts_total",
+            "Times the system-clock divergence alert has fired",
+            time_system_clock_alerts_total.clone(),
+        );
+
+        let time_clock_jumps_total = Counter::default();
+        registry.register(
+            "time_clock_jumps_total",
+            "Suspend/resume gaps detected in the monotonic clock",
+            time_clock_jumps_total.clone(),
+        );
+
+        let roughtime_offset_milliseconds = Family::<ServerLabel, Gauge>::default();
+        registry.register(
+            "roughtime_offset_milliseconds",
+            "NTP-derived time minus the signed Roughtime midpoint in milliseconds",
+            roughtime_offset_milliseconds.clone(),
+        );
+
+        let roughtime_alert = Family::<ServerLabel, Gauge>::default();
+        registry.register(
+            "roughtime_alert",
+            "1 while NTP time is outside the signed Roughtime bound by more than ROUGHTIME_MAX_DISAGREEMENT_MS",
+            roughtime_alert.clone(),
+        );
+
+        let roughtime_alerts_total = Counter::default();
+        registry.register(
+            "roughtime_alerts_total",
+            "Times the Roughtime disagreement alert has fired",
+            roughtime_alerts_total.clone(),
+        );
+
+        let roughtime_errors_total = Family::<ServerLabel, Counter>::default();
+        registry.register(
+            "roughtime_errors_total",
+            "Roughtime queries that failed or returned an unverifiable reply",
+            roughtime_errors_total.clone(),
+        );
+
         // P1-8 replica drift visibility metrics
         let time_replica_offset_milliseconds =
             Family::<ReplicaLabel, Gauge<f64, AtomicU64>>::default();
@@ -389,6 +664,109 @@ impl Metrics {
             time_replica_source_mode.clone(),
         );
 
+        // WebSocket stream metrics
+        let ws_messages_dropped_total = Family::<RejectLabel, Counter>::default();
+        registry.register(
+            "ws_messages_dropped_total",
+            "Stream ticks discarded because the client could not keep up, by policy",
+            ws_messages_dropped_total.clone(),
+        );
+
+        let ws_slow_consumer_disconnects_total = Counter::default();
+        registry.register(
+            "ws_slow_consumer_disconnects_total",
+            "Stream connections closed because their send queue overflowed",
+            ws_slow_consumer_disconnects_total.clone(),
+        );
+
+        // Exported as websocket_rejected_total; prometheus-client appends `_total`
+        let websocket_rejected_total = Counter::default();
+        registry.register(
+            "websocket_rejected",
+            "Stream handshakes refused because WS_MAX_CONNECTIONS were open",
+            websocket_rejected_total.clone(),
+        );
+
+        let ws_connections_active = Gauge::default();
+        registry.register(
+            "ws_connections_active",
+            "Number of currently open stream connections",
+            ws_connections_active.clone(),
+        );
+        // Same gauge under the name the connection-limit dashboards use
+        registry.register(
+            "websocket_active_connections",
+            "Number of currently open stream connections",
+            ws_connections_active.clone(),
+        );
+
+        let ws_messages_sent_total = Counter::default();
+        registry.register(
+            "ws_messages_sent_total",
+            "Stream ticks written to clients",
+            ws_messages_sent_total.clone(),
+        );
+
+        let ws_send_latency_seconds = Histogram::new(
+            exponential_buckets(0.0005, 2.0, 12), // 0.5ms to ~1s
+        );
+        registry.register(
+            "ws_send_latency_seconds",
+            "Time from enqueueing a stream tick to writing it to the client",
+            ws_send_latency_seconds.clone(),
+        );
+
+        let ws_send_queue_depth = Gauge::default();
+        registry.register(
+            "ws_send_queue_depth",
+            "Stream ticks queued for sending, summed over open connections",
+            ws_send_queue_depth.clone(),
+        );
+
+        let ws_pong_latency_seconds = Histogram::new(
+            exponential_buckets(0.001, 2.0, 14), // 1ms to ~8s
+        );
+        registry.register(
+            "ws_pong_latency_seconds",
+            "Round-trip time from a server Ping to the stream client's Pong",
+            ws_pong_latency_seconds.clone(),
+        );
+
+        let ws_liveness_disconnects_total = Family::<RejectLabel, Counter>::default();
+        registry.register(
+            "ws_liveness_disconnects_total",
+            "Stream connections closed for missed heartbeats or idleness, by reason",
+            ws_liveness_disconnects_total.clone(),
+        );
+
+        // gRPC metrics
+        let grpc_requests_total = Family::<GrpcLabels, Counter>::default();
+        // prometheus-client appends `_total` to counters on export
+        registry.register(
+            "grpc_requests",
+            "Total number of finished gRPC calls, by method and status code",
+            grpc_requests_total.clone(),
+        );
+
+        let grpc_request_duration_seconds =
+            Family::<GrpcLabels, Histogram>::new_with_constructor(|| {
+                Histogram::new(
+                    exponential_buckets(0.001, 2.0, 20), // 1ms to ~9min (streams)
+                )
+            });
+        registry.register(
+            "grpc_request_duration_seconds",
+            "gRPC call duration in seconds; for streaming RPCs the stream lifetime",
+            grpc_request_duration_seconds.clone(),
+        );
+
+        let grpc_active_streams = Family::<GrpcMethodLabel, Gauge>::default();
+        registry.register(
+            "grpc_active_streams",
+            "Number of gRPC calls currently in flight, by method",
+            grpc_active_streams.clone(),
+        );
+
         // Manual override metrics (P1-7)
         let manual_override_active = Gauge::default();
         registry.register(
@@ -434,18 +812,33 @@ impl Metrics {
             http_requests_total,
             http_request_duration_seconds,
             http_inflight_requests,
+            http_connections_open,
+            http_connections_accepted_total,
+            http_connection_duration_seconds,
+            http_tls_handshake_failures_total,
+            http_rate_limited_total,
+            http_load_shed_total,
+            http_ip_filtered_total,
+            tcp_listen_overflows_total,
+            tcp_listen_drops_total,
             ntp_sync_total,
             ntp_sync_errors_total,
+            ntp_step_rejected_total,
+            ntp_fallback_tier_syncs_total,
+            ntp_poll_interval_seconds,
             ntp_last_sync_timestamp_seconds,
             ntp_staleness_seconds,
             ntp_offset_seconds,
+            ntp_clock_drift_ppm,
             ntp_rtt_seconds,
             ntp_server_up,
             ntp_server_rtt_milliseconds,
+            ntp_kod_received_total,
             ntp_consecutive_failures,
             ntp_selection_quorum_size,
             ntp_selection_falsetickers_total,
             ntp_sample_uncertainty_milliseconds,
+            ntp_server_bias_estimate_milliseconds,
             ntp_combined_uncertainty_milliseconds,
             ntp_selection_rejected_total,
             ntp_selection_single_provider,
@@ -464,8 +857,30 @@ impl Metrics {
             ntp_udp_server_unsynced_responses_total,
             ntp_udp_server_root_dispersion_seconds,
             time_uncertainty_milliseconds,
+            time_error_bound_milliseconds,
             time_source_mode,
             time_serve_state,
+            time_quality_score,
+            time_system_clock_divergence_milliseconds,
+            time_system_clock_alert,
+            time_system_clock_alerts_total,
+            time_clock_jumps_total,
+            roughtime_offset_milliseconds,
+            roughtime_alert,
+            roughtime_alerts_total,
+            roughtime_errors_total,
+            ws_messages_dropped_total,
+            ws_slow_consumer_disconnects_total,
+            ws_connections_active,
+            websocket_rejected_total,
+            ws_messages_sent_total,
+            ws_send_latency_seconds,
+            ws_send_queue_depth,
+            ws_pong_latency_seconds,
+            ws_liveness_disconnects_total,
+            grpc_requests_total,
+            grpc_request_duration_seconds,
+            grpc_active_streams,
             manual_override_active,
             manual_override_total,
             manual_override_expiry_timestamp_seconds,
@@ -498,6 +913,18 @@ impl Metrics {
             .get_or_create(&labels)
             .observe(duration.as_secs_f64());
     }
+
+    pub fn record_grpc_call(&self, method: &str, code: &str, duration: std::time::Duration) {
+        let labels = GrpcLabels {
+            method: method.to_string(),
+            code: code.to_string(),
+        };
+
+        self.grpc_requests_total.get_or_create(&labels).inc();
+        self.grpc_request_duration_seconds
+            .get_or_create(&labels)
+            .observe(duration.as_secs_f64());
+    }
 }
 
 impl Default for Metrics {
@@ -532,6 +959,30 @@ mod tests {
         assert!(encoded.contains("http_request_duration_seconds"));
     }
 
+    #[test]
+    fn test_grpc_metrics() {
+        let metrics = Metrics::new();
+
+        metrics.record_grpc_call("GetTime", "OK", std::time::Duration::from_millis(2));
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains(r#"grpc_requests_total{method="GetTime",code="OK"} 1"#));
+        assert!(encoded.contains("grpc_request_duration_seconds"));
+    }
+
+    #[test]
+    fn test_websocket_limit_metrics() {
+        let metrics = Metrics::new();
+
+        metrics.ws_connections_active.inc();
+        metrics.websocket_rejected_total.inc();
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains("websocket_active_connections 1"));
+        assert!(encoded.contains("ws_connections_active 1"));
+        assert!(encoded.contains("websocket_rejected_total 1"));
+    }
+
     #[test]
     fn test_ntp_metrics() {
         let metrics = Metrics::new();
@@ -0,0 +1,244 @@
+//! NTP symmetric key authentication (RFC 5905 §7.3, appendix A.2).
+//!
+//! An `NTP_SERVERS` entry with `;key=ID` has every request authenticated
+//! with key `ID` from `NTP_KEYS_FILE`: the key identifier and a message
+//! digest over the 48-byte header are appended to the packet, and the reply
+//! must carry a valid digest under the same key.  Unauthenticated replies,
+//! crypto-NAKs and digest mismatches are rejected before any field is used.
+//!
+//! Digests follow ntpd: MD5 and SHA-1 over `key ‖ packet`, and AES-128-CMAC
+//! keyed with the secret.  MD5 is implemented here, as no other part of the
+//! service needs it.
+
+use anyhow::{Result, bail};
+use subtle::ConstantTimeEq;
+
+use super::protocol::NTP_PACKET_SIZE;
+use crate::config::{MacAlgorithm, NtpKey};
+
+/// Size of the key identifier that precedes the digest.
+const KEY_ID_LEN: usize = 4;
+
+/// Digest length for `algorithm`.
+pub fn digest_len(algorithm: MacAlgorithm) -> usize {
+    match algorithm {
+        MacAlgorithm::Md5 | MacAlgorithm::Aes128Cmac => 16,
+        MacAlgorithm::Sha1 => 20,
+    }
+}
+
+/// Message digest of `data` under `key`.
+pub fn digest(key: &NtpKey, data: &[u8]) -> Vec<u8> {
+    match key.algorithm {
+        MacAlgorithm::Md5 => {
+            let mut input = Vec::with_capacity(key.secret.len() + data.len());
+            input.extend_from_slice(&key.secret);
+            input.extend_from_slice(data);
+            md5(&input).to_vec()
+        }
+        MacAlgorithm::Sha1 => {
+            let mut ctx = ring::digest::Context::new(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY);
+            ctx.update(&key.secret);
+            ctx.update(data);
+            ctx.finish().as_ref().to_vec()
+        }
+        MacAlgorithm::Aes128Cmac => {
+            use cmac::{Cmac, Mac};
+            let mut mac = <Cmac<aes::Aes128> as Mac>::new_from_slice(&key.secret)
+                .expect("AES128CMAC keys are 16 bytes");
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+    }
+}
+
+/// Append the MAC (key identifier, then digest of everything before it).
+pub fn append_mac(packet: &mut Vec<u8>, key: &NtpKey) {
+    let mac = digest(key, packet);
+    packet.extend_from_slice(&key.id.to_be_bytes());
+    packet.extend_from_slice(&mac);
+}
+
+/// Check that `reply` ends in a valid MAC under `key`.
+pub fn verify_mac(reply: &[u8], key: &NtpKey) -> Result<()> {
+    let mac_len = KEY_ID_LEN + digest_len(key.algorithm);
+    if reply.len() == NTP_PACKET_SIZE + KEY_ID_LEN {
+        // RFC 5905 §7.4: a MAC of key id 0 and no digest is a crypto-NAK.
+        bail!("NTP server sent a crypto-NAK (key {} rejected)", key.id);
+    }
+    if reply.len() < NTP_PACKET_SIZE + mac_len {
+        bail!("NTP reply is not authenticated (expected key {})", key.id);
+    }
+    let (signed, mac) = reply.split_at(reply.len() - mac_len);
+    let key_id = u32::from_be_bytes([mac[0], mac[1], mac[2], mac[3]]);
+    if key_id != key.id {
+        bail!(
+            "NTP reply authenticated with key {} (expected {})",
+            key_id,
+            key.id
+        );
+    }
+    if !bool::from(digest(key, signed).ct_eq(&mac[KEY_ID_LEN..])) {
+        bail!("NTP reply MAC does not verify (key {})", key.id);
+    }
+    Ok(())
+}
+
+/// Per-round shift amounts (RFC 1321 §3.4).
+const MD5_SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// MD5 (RFC 1321).
+fn md5(input: &[u8]) -> [u8; 16] {
+    // K[i] = floor(abs(sin(i + 1)) * 2^32)
+    let k: [u32; 64] =
+        std::array::from_fn(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32);
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    let mut message = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((input.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    for block in message.chunks_exact(64) {
+        let m: [u32; 16] = std::array::from_fn(|i| {
+            u32::from_le_bytes([
+                block[4 * i],
+                block[4 * i + 1],
+                block[4 * i + 2],
+                block[4 * i + 3],
+            ])
+        });
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(k[i])
+                .wrapping_add(m[g])
+                .rotate_left(MD5_SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+
+    let mut out = [0u8; 16];
+    for (chunk, word) in out.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn key(id: u32, algorithm: MacAlgorithm, secret: &[u8]) -> NtpKey {
+        NtpKey {
+            id,
+            algorithm,
+            secret: secret.to_vec(),
+        }
+    }
+
+    #[test]
+    fn md5_test_vectors() {
+        assert_eq!(hex(&md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(&md5(b"abc")), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            hex(&md5(b"The quick brown fox jumps over the lazy dog")),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+        // Spans two blocks after padding.
+        assert_eq!(
+            hex(&md5(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            )),
+            "57edf4a22be3c955ac49da2e2107b67a"
+        );
+    }
+
+    #[test]
+    fn sha1_digest_is_over_key_then_packet() {
+        let k = key(1, MacAlgorithm::Sha1, b"ab");
+        // SHA-1("abc")
+        assert_eq!(
+            hex(&digest(&k, b"c")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+    }
+
+    #[test]
+    fn cmac_digest_matches_rfc4493() {
+        let secret: Vec<u8> = [
+            0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
+            0x4f, 0x3c,
+        ]
+        .to_vec();
+        let k = key(1, MacAlgorithm::Aes128Cmac, &secret);
+        assert_eq!(hex(&digest(&k, b"")), "bb1d6929e95937287fa37d129b756746");
+    }
+
+    #[test]
+    fn mac_round_trips_for_every_algorithm() {
+        for k in [
+            key(1, MacAlgorithm::Md5, b"secret"),
+            key(2, MacAlgorithm::Sha1, b"secret"),
+            key(3, MacAlgorithm::Aes128Cmac, &[7u8; 16]),
+        ] {
+            let mut packet = vec![0x24u8; NTP_PACKET_SIZE];
+            append_mac(&mut packet, &k);
+            assert_eq!(packet.len(), NTP_PACKET_SIZE + 4 + digest_len(k.algorithm));
+            assert_eq!(
+                &packet[NTP_PACKET_SIZE..NTP_PACKET_SIZE + 4],
+                k.id.to_be_bytes()
+            );
+            verify_mac(&packet, &k).unwrap();
+
+            // Tampering with the header breaks the MAC.
+            packet[40] ^= 1;
+            assert!(verify_mac(&packet, &k).is_err());
+        }
+    }
+
+    #[test]
+    fn verify_rejects_unauthenticated_wrong_key_and_crypto_nak() {
+        let k = key(5, MacAlgorithm::Md5, b"secret");
+        let header = vec![0x24u8; NTP_PACKET_SIZE];
+        assert!(verify_mac(&header, &k).is_err());
+
+        let mut nak = header.clone();
+        nak.extend_from_slice(&0u32.to_be_bytes());
+        let err = verify_mac(&nak, &k).unwrap_err();
+        assert!(err.to_string().contains("crypto-NAK"));
+
+        let mut other = header.clone();
+        append_mac(&mut other, &key(6, MacAlgorithm::Md5, b"secret"));
+        let err = verify_mac(&other, &k).unwrap_err();
+        assert!(err.to_string().contains("key 6"));
+
+        let mut wrong_secret = header;
+        append_mac(&mut wrong_secret, &key(5, MacAlgorithm::Md5, b"other"));
+        assert!(verify_mac(&wrong_secret, &k).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+//! Automatic per-server path-asymmetry (offset bias) calibration.
+//!
+//! NTP assumes symmetric paths; a server reached over an asymmetric route
+//! reports an offset that is consistently wrong by half the asymmetry.  With
+//! calibration enabled, each server's *residual* — its raw offset minus the
+//! reference offset — is smoothed with an EWMA after every successful sync.
+//! Once enough samples have accumulated, the learned residual is subtracted
+//! from that server's future samples before selection, replacing hand-tuned
+//! `OFFSET_BIAS_MS` / `ASYMMETRY_BIAS_MS` values for per-path bias.
+//!
+//! The reference is either a trusted server (`NTP_BIAS_CALIBRATION_REFERENCE`)
+//! or, by default, the selected consensus offset.  Only agreers (truechimers)
+//! update their estimate, so a falseticker's error is never learned, and the
+//! applied correction is capped at `NTP_BIAS_CALIBRATION_MAX_MS`.
+//!
+//! Asymmetry estimation (`NTP_ASYMMETRY_ESTIMATION_ENABLED`) needs no
+//! reference.  Queueing delay above a path's minimum usually builds up on one
+//! leg, which shifts the measured offset by up to half the extra delay.  Each
+//! server's offsets are regressed on their excess delay (huff-n-puff); the
+//! fitted slope, bounded to ±0.5, says how the extra delay splits between the
+//! legs, and each new sample is corrected by slope × its excess delay.
+
+use crate::config::CalibrationConfig;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// Delay/offset pairs kept per server for asymmetry estimation.
+const ASYMMETRY_WINDOW: usize = 64;
+
+/// Pairs required before an asymmetry estimate is used.
+const ASYMMETRY_MIN_SAMPLES: usize = 8;
+
+/// Excess-delay spread (ms) required to fit a slope; below it every sample
+/// saw about the same delay and there is nothing to learn.
+const ASYMMETRY_MIN_SPREAD_MS: f64 = 2.0;
+
+/// EWMA of one server's offset residual against the calibration reference.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BiasEstimate {
+    /// Smoothed residual (ms); positive = server reads ahead of the reference.
+    pub estimate_ms: f64,
+    /// Residuals folded into the estimate so far.
+    pub samples: u32,
+}
+
+impl BiasEstimate {
+    /// Fold a new residual into the estimate.  The first sample seeds it.
+    pub fn update(&mut self, residual_ms: f64, alpha: f64) {
+        if self.samples == 0 {
+            self.estimate_ms = residual_ms;
+        } else {
+            self.estimate_ms += alpha * (residual_ms - self.estimate_ms);
+        }
+        self.samples = self.samples.saturating_add(1);
+    }
+
+    /// Correction (ms) to subtract from this server's offset, once warmed up.
+    pub fn correction_ms(&self, cfg: &CalibrationConfig) -> Option<i64> {
+        if self.samples < cfg.min_samples {
+            return None;
+        }
+        let max = cfg.max_correction_ms as f64;
+        Some(self.estimate_ms.clamp(-max, max).round() as i64)
+    }
+}
+
+/// Recent delay/offset pairs of one server, for asymmetry estimation.
+#[derive(Debug, Clone, Default)]
+pub struct AsymmetryEstimator {
+    samples: VecDeque<(f64, f64)>,
+}
+
+/// Fitted delay asymmetry of one server's path.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AsymmetryEstimate {
+    /// Offset change per ms of delay above the minimum, in [-0.5, 0.5]:
+    /// +0.5 = all extra delay on the outbound leg, -0.5 = all on the return.
+    pub slope: f64,
+    /// Smallest delay in the window (ms), taken as the uncongested path.
+    pub min_delay_ms: f64,
+    pub samples: usize,
+}
+
+impl AsymmetryEstimator {
+    /// Record the delay and offset (ms) of a successful exchange.
+    pub fn record(&mut self, delay_ms: f64, offset_ms: f64) {
+        if self.samples.len() >= ASYMMETRY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((delay_ms, offset_ms));
+    }
+
+    /// Least-squares slope of offset against excess delay; `None` until
+    /// enough samples with enough delay spread have been seen.
+    pub fn estimate(&self) -> Option<AsymmetryEstimate> {
+        let n = self.samples.len();
+        if n < ASYMMETRY_MIN_SAMPLES {
+            return None;
+        }
+        let min_delay_ms = self
+            .samples
+            .iter()
+            .map(|&(d, _)| d)
+            .fold(f64::MAX, f64::min);
+        let max_delay_ms = self
+            .samples
+            .iter()
+            .map(|&(d, _)| d)
+            .fold(f64::MIN, f64::max);
+        if max_delay_ms - min_delay_ms < ASYMMETRY_MIN_SPREAD_MS {
+            return None;
+        }
+        let mean_x = self.samples.iter().map(|&(d, _)| d).sum::<f64>() / n as f64;
+        let mean_y = self.samples.iter().map(|&(_, o)| o).sum::<f64>() / n as f64;
+        let (sxy, sxx) = self.samples.iter().fold((0.0, 0.0), |(sxy, sxx), &(d, o)| {
+            let dx = d - mean_x;
+            (sxy + dx * (o - mean_y), sxx + dx * dx)
+        });
+        Some(AsymmetryEstimate {
+            slope: (sxy / sxx).clamp(-0.5, 0.5),
+            min_delay_ms,
+            samples: n,
+        })
+    }
+}
+
+impl AsymmetryEstimate {
+    /// Correction (ms) to subtract from a sample measured at `delay_ms`,
+    /// capped at `max_correction_ms`.
+    pub fn correction_ms(&self, delay_ms: f64, cfg: &CalibrationConfig) -> i64 {
+        let max = cfg.max_correction_ms as f64;
+        (self.slope * (delay_ms - self.min_delay_ms).max(0.0))
+            .clamp(-max, max)
+            .round() as i64
+    }
+}
+
+/// Reference offset (ms) residuals are measured against: the trusted
+/// reference server's raw offset when configured, else the selected
+/// consensus offset.  `None` when the reference server did not answer.
+pub fn reference_offset_ms(
+    cfg: &CalibrationConfig,
+    raw_offsets: &HashMap<String, i64>,
+    consensus_offset_ms: i64,
+) -> Option<i64> {
+    match &cfg.reference_server {
+        Some(reference) => raw_offsets.get(reference).copied(),
+        None => Some(consensus_offset_ms),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> CalibrationConfig {
+        CalibrationConfig {
+            enabled: true,
+            reference_server: None,
+            alpha: 0.5,
+            min_samples: 3,
+            max_correction_ms: 20,
+            asymmetry_enabled: true,
+        }
+    }
+
+    #[test]
+    fn estimate_seeds_then_smooths() {
+        let mut est = BiasEstimate::default();
+        est.update(10.0, 0.5);
+        assert_eq!(est.estimate_ms, 10.0);
+        est.update(20.0, 0.5);
+        assert_eq!(est.estimate_ms, 15.0);
+        assert_eq!(est.samples, 2);
+    }
+
+    #[test]
+    fn correction_requires_warmup_and_is_capped() {
+        let cfg = cfg();
+        let mut est = BiasEstimate::default();
+        est.update(12.0, cfg.alpha);
+        est.update(12.0, cfg.alpha);
+        assert_eq!(est.correction_ms(&cfg), None);
+        est.update(12.0, cfg.alpha);
+        assert_eq!(est.correction_ms(&cfg), Some(12));
+
+        let mut large = BiasEstimate::default();
+        for _ in 0..3 {
+            large.update(-500.0, cfg.alpha);
+        }
+        assert_eq!(large.correction_ms(&cfg), Some(-20));
+    }
+
+    #[test]
+    fn asymmetry_slope_recovers_one_sided_queueing() {
+        let mut est = AsymmetryEstimator::default();
+        // True offset 5 ms; all queueing (0..=35 ms) on the outbound leg.
+        for i in 0..7 {
+            let excess = (i * 5) as f64;
+            est.record(20.0 + excess, 5.0 + excess / 2.0);
+        }
+        assert_eq!(est.estimate(), None, "needs warm-up");
+        est.record(20.0, 5.0);
+
+        let fit = est.estimate().unwrap();
+        assert!((fit.slope - 0.5).abs() < 1e-9, "slope {}", fit.slope);
+        assert_eq!(fit.min_delay_ms, 20.0);
+        assert_eq!(fit.samples, 8);
+        // A sample with 30 ms of queueing read 20 ms; 15 of that is path.
+        assert_eq!(fit.correction_ms(50.0, &cfg()), 15);
+        assert_eq!(fit.correction_ms(20.0, &cfg()), 0);
+        // Capped at max_correction_ms.
+        assert_eq!(fit.correction_ms(200.0, &cfg()), 20);
+    }
+
+    #[test]
+    fn asymmetry_needs_delay_spread_and_bounds_slope() {
+        let mut flat = AsymmetryEstimator::default();
+        for i in 0..10 {
+            flat.record(20.0 + (i % 2) as f64, i as f64);
+        }
+        assert_eq!(flat.estimate(), None);
+
+        let mut steep = AsymmetryEstimator::default();
+        for i in 0..10 {
+            steep.record(20.0 + i as f64, -3.0 * i as f64);
+        }
+        assert_eq!(steep.estimate().unwrap().slope, -0.5);
+    }
+
+    #[test]
+    fn reference_prefers_trusted_server() {
+        let raw = HashMap::from([("ref:123".to_string(), 7), ("b:123".to_string(), 30)]);
+        assert_eq!(reference_offset_ms(&cfg(), &raw, 25), Some(25));
+
+        let trusted = CalibrationConfig {
+            reference_server: Some("ref:123".to_string()),
+            ..cfg()
+        };
+        assert_eq!(reference_offset_ms(&trusted, &raw, 25), Some(7));
+        let missing = CalibrationConfig {
+            reference_server: Some("gone:123".to_string()),
+            ..cfg()
+        };
+        assert_eq!(reference_offset_ms(&missing, &raw, 25), None);
+    }
+}
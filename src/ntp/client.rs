@@ -6,9 +6,15 @@
 
 use anyhow::{Context, Result, bail};
 use async_trait::async_trait;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
-use tokio::net::UdpSocket;
+use tokio::net::{TcpSocket, TcpStream, ToSocketAddrs, UdpSocket};
 
+use crate::config::{AddressFamily, NtpKey, SourceBinding};
+
+use super::auth::{append_mac, verify_mac};
 use super::protocol::{
     LI_ALARM_UNSYNCHRONIZED, LI_NO_WARNING, MODE_CLIENT, NTP_VERSION, STRATUM_UNSPECIFIED,
     STRATUM_UNSYNCHRONIZED, serialize_packet,
@@ -57,32 +63,106 @@ pub trait NtpClient: Send + Sync {
     async fn query(&self, server: &str, timeout: Duration) -> Result<NtpSample>;
 }
 
+/// Kiss-o'-Death reply (RFC 5905 §7.4): stratum 0 with a four-character
+/// ASCII kiss code in the reference ID.  Returned (inside `anyhow::Error`)
+/// by [`NtpClient::query`] so callers can downcast and act on the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KissOfDeath {
+    pub reference_id: u32,
+}
+
+/// What a client must do about a kiss code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KodAction {
+    /// `RATE`: reduce the query rate to this server.
+    BackOff,
+    /// `DENY` / `RSTR`: stop querying this server.
+    Disable,
+    /// Any other code: treat as an ordinary failed query.
+    Failure,
+}
+
+impl KissOfDeath {
+    /// The kiss code as text; non-printable bytes become `?`.
+    pub fn code(&self) -> String {
+        self.reference_id
+            .to_be_bytes()
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() { b as char } else { '?' })
+            .collect()
+    }
+
+    pub fn action(&self) -> KodAction {
+        match &self.reference_id.to_be_bytes() {
+            b"RATE" => KodAction::BackOff,
+            b"DENY" | b"RSTR" => KodAction::Disable,
+            _ => KodAction::Failure,
+        }
+    }
+}
+
+impl std::fmt::Display for KissOfDeath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "NTP Kiss-of-Death reply (stratum=0, code={})",
+            self.code()
+        )
+    }
+}
+
+impl std::error::Error for KissOfDeath {}
+
 /// Production NTP client: sends a UDP NTPv4 packet and parses the response.
-pub struct PacketNtpClient;
+#[derive(Debug, Clone, Default)]
+pub struct PacketNtpClient {
+    family: AddressFamily,
+    /// Local address/interface the query sockets bind to.
+    source: SourceBinding,
+    /// Symmetric key per server (`;key=ID`); other servers are unauthenticated.
+    keys: Arc<HashMap<String, NtpKey>>,
+}
+
+impl PacketNtpClient {
+    /// Client that only connects to addresses of `family`.
+    pub fn new(family: AddressFamily) -> Self {
+        Self {
+            family,
+            source: SourceBinding::default(),
+            keys: Arc::default(),
+        }
+    }
+
+    /// Bind query sockets to `source` (`NTP_BIND_ADDR` / `NTP_BIND_INTERFACE`).
+    pub fn with_source(mut self, source: SourceBinding) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Authenticate exchanges with each server in `keys` (RFC 5905 §7.3).
+    pub fn with_keys(mut self, keys: HashMap<String, NtpKey>) -> Self {
+        self.keys = Arc::new(keys);
+        self
+    }
+}
 
 #[async_trait]
 impl NtpClient for PacketNtpClient {
     async fn query(&self, server: &str, timeout: Duration) -> Result<NtpSample> {
-        query_impl(server, timeout).await
+        let key = self.keys.get(server);
+        query_impl(server, timeout, self.family, &self.source, key).await
     }
 }
 
-async fn query_impl(server: &str, timeout_dur: Duration) -> Result<NtpSample> {
-    // 1. Resolve host:port → SocketAddr
-    let addr = tokio::net::lookup_host(server)
-        .await
-        .with_context(|| format!("DNS resolution failed for {server}"))?
-        .next()
-        .with_context(|| format!("No address resolved for {server}"))?;
-
-    // 2. Bind ephemeral UDP socket and connect
-    let socket = UdpSocket::bind("0.0.0.0:0")
-        .await
-        .context("Failed to bind UDP socket")?;
-    socket
-        .connect(addr)
-        .await
-        .context("Failed to connect UDP socket")?;
+async fn query_impl(
+    server: &str,
+    timeout_dur: Duration,
+    family: AddressFamily,
+    source: &SourceBinding,
+    key: Option<&NtpKey>,
+) -> Result<NtpSample> {
+    // 1-2. Resolve host:port and connect an ephemeral UDP socket
+    let socket = connect_udp(server, family, source).await?;
 
     // 3. Capture T1 and build request — both captures happen back-to-back
     //    to minimise the skew between the two clocks.
@@ -94,7 +174,10 @@ async fn query_impl(server: &str, timeout_dur: Duration) -> Result<NtpSample> {
     // The server echoes this back as origin_timestamp; we verify it to detect
     // stale or spoofed replies.
     request.transmit_timestamp = unix_ms_to_ntp(t1_unix_ms);
-    let buf = serialize_packet(&request);
+    let mut buf = serialize_packet(&request).to_vec();
+    if let Some(key) = key {
+        append_mac(&mut buf, key);
+    }
 
     socket
         .send(&buf)
@@ -112,12 +195,130 @@ async fn query_impl(server: &str, timeout_dur: Duration) -> Result<NtpSample> {
     let t4_sys = SystemTime::now();
     let t4_unix_ms = system_time_unix_ms(t4_sys);
 
-    // 5. Parse the server response packet
+    // 5. Authenticate (keyed servers only) and parse the server response packet
+    if let Some(key) = key {
+        verify_mac(&recv_buf[..n], key)
+            .with_context(|| format!("NTP authentication failed for {server}"))?;
+    }
     let reply =
         parse_server_response(&recv_buf[..n]).context("Failed to parse NTP server response")?;
 
+    sample_from_reply(
+        server, &reply, t1_unix_ms, t1_instant, t4_unix_ms, t4_instant,
+    )
+}
+
+/// Resolve `target`, keeping only addresses of `family`; `label` names it
+/// in errors.
+pub(super) async fn resolve_addrs(
+    target: impl ToSocketAddrs,
+    label: &str,
+    family: AddressFamily,
+) -> Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host(target)
+        .await
+        .with_context(|| format!("DNS resolution failed for {label}"))?
+        .filter(|addr| family.matches(addr))
+        .collect();
+    if addrs.is_empty() {
+        match family {
+            AddressFamily::Any => bail!("No address resolved for {label}"),
+            AddressFamily::Ipv4 => bail!("No IPv4 address resolved for {label}"),
+            AddressFamily::Ipv6 => bail!("No IPv6 address resolved for {label}"),
+        }
+    }
+    Ok(addrs)
+}
+
+/// Local address for a socket to `remote`: the configured source IP, else
+/// the unspecified address of `remote`'s family.
+fn local_addr(remote: &SocketAddr, source: &SourceBinding) -> SocketAddr {
+    match source.addr {
+        Some(ip) => SocketAddr::new(ip, 0),
+        None if remote.is_ipv4() => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        None => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    }
+}
+
+/// Pin `socket` to `source.interface` with `SO_BINDTODEVICE`.
+#[cfg(target_os = "linux")]
+fn bind_interface(socket: impl std::os::fd::AsFd, source: &SourceBinding) -> Result<()> {
+    match &source.interface {
+        Some(interface) => socket2::SockRef::from(&socket)
+            .bind_device(Some(interface.as_bytes()))
+            .with_context(|| format!("Failed to bind socket to interface {interface}")),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn bind_interface<S>(_socket: S, source: &SourceBinding) -> Result<()> {
+    match &source.interface {
+        Some(_) => bail!("NTP_BIND_INTERFACE is only supported on Linux"),
+        None => Ok(()),
+    }
+}
+
+/// Connect a TCP stream to the first reachable of `addrs`, bound per `source`.
+pub(super) async fn connect_tcp(addrs: &[SocketAddr], source: &SourceBinding) -> Result<TcpStream> {
+    let mut last_err = None;
+    for addr in addrs {
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()
+        } else {
+            TcpSocket::new_v6()
+        }
+        .context("Failed to create TCP socket")?;
+        bind_interface(&socket, source)?;
+        if source.addr.is_some() {
+            socket
+                .bind(local_addr(addr, source))
+                .context("Failed to bind TCP socket")?;
+        }
+        match socket.connect(*addr).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.map_or_else(
+        || anyhow::anyhow!("No address to connect to"),
+        anyhow::Error::from,
+    ))
+}
+
+/// Resolve `server` (`host:port`) and return an ephemeral UDP socket, bound
+/// per `source`, connected to its first address of `family`.
+pub(super) async fn connect_udp(
+    server: &str,
+    family: AddressFamily,
+    source: &SourceBinding,
+) -> Result<UdpSocket> {
+    let addr = resolve_addrs(server, server, family).await?[0];
+
+    let socket = UdpSocket::bind(local_addr(&addr, source))
+        .await
+        .context("Failed to bind UDP socket")?;
+    bind_interface(&socket, source)?;
+    socket
+        .connect(addr)
+        .await
+        .context("Failed to connect UDP socket")?;
+    Ok(socket)
+}
+
+/// Validate a parsed reply to a request sent at T1 and derive the sample.
+///
+/// The request's transmit timestamp must have been `unix_ms_to_ntp(t1_unix_ms)`.
+pub(super) fn sample_from_reply(
+    server: &str,
+    reply: &NtpPacket,
+    t1_unix_ms: i64,
+    t1_instant: Instant,
+    t4_unix_ms: i64,
+    t4_instant: Instant,
+) -> Result<NtpSample> {
     // 6. Safety-critical validations (must happen before we use any reply fields)
-    validate_response(&reply, request.transmit_timestamp)?;
+    validate_response(reply, unix_ms_to_ntp(t1_unix_ms))?;
 
     // 7. Extract MEASURED T2/T3 directly from packet bytes
     let t2_unix_ms = ntp_to_unix_ms(reply.receive_timestamp);
@@ -176,7 +377,10 @@ fn validate_response(reply: &NtpPacket, our_transmit_ts: u64) -> Result<()> {
 
     // Stratum 0 = Kiss-of-Death (KoD); server is telling us to back off.
     if reply.stratum == STRATUM_UNSPECIFIED {
-        bail!("NTP Kiss-of-Death reply (stratum=0)");
+        return Err(KissOfDeath {
+            reference_id: reply.reference_id,
+        }
+        .into());
     }
 
     // Stratum >= 16 = server is unsynchronized.
@@ -197,7 +401,7 @@ fn validate_response(reply: &NtpPacket, our_transmit_ts: u64) -> Result<()> {
     Ok(())
 }
 
-fn system_time_unix_ms(t: SystemTime) -> i64 {
+pub(super) fn system_time_unix_ms(t: SystemTime) -> i64 {
     t.duration_since(SystemTime::UNIX_EPOCH)
         .map(|d| d.as_millis() as i64)
         .unwrap_or(0)
@@ -346,7 +550,7 @@ mod tests {
 
         let mock = MockServer::start(move |req| good_reply(req, t2_ntp, t3_ntp, 0, 0)).await;
 
-        let sample = PacketNtpClient
+        let sample = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_secs(2))
             .await
             .expect("query should succeed");
@@ -377,7 +581,7 @@ mod tests {
         })
         .await;
 
-        let sample = PacketNtpClient
+        let sample = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_secs(2))
             .await
             .expect("query should succeed");
@@ -398,7 +602,7 @@ mod tests {
 
         let mock = MockServer::start(move |req| good_reply(req, t2_ntp, t3_ntp, 0, 0)).await;
 
-        let s = PacketNtpClient
+        let s = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_secs(2))
             .await
             .expect("query should succeed");
@@ -433,7 +637,7 @@ mod tests {
         })
         .await;
 
-        let err = PacketNtpClient
+        let err = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_secs(2))
             .await
             .unwrap_err();
@@ -467,7 +671,7 @@ mod tests {
         })
         .await;
 
-        let err = PacketNtpClient
+        let err = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_secs(2))
             .await
             .unwrap_err();
@@ -476,6 +680,21 @@ mod tests {
             msg.contains("Kiss-of-Death"),
             "expected KoD error, got: {msg}"
         );
+        let kod = err.downcast_ref::<KissOfDeath>().expect("typed KoD error");
+        assert_eq!(kod.action(), KodAction::Failure);
+    }
+
+    #[test]
+    fn kiss_codes_map_to_actions() {
+        let kod = |code: &[u8; 4]| KissOfDeath {
+            reference_id: u32::from_be_bytes(*code),
+        };
+        assert_eq!(kod(b"RATE").action(), KodAction::BackOff);
+        assert_eq!(kod(b"DENY").action(), KodAction::Disable);
+        assert_eq!(kod(b"RSTR").action(), KodAction::Disable);
+        assert_eq!(kod(b"INIT").action(), KodAction::Failure);
+        assert_eq!(kod(b"RATE").code(), "RATE");
+        assert_eq!(KissOfDeath { reference_id: 0 }.code(), "????");
     }
 
     /// LI=3 (alarm / unsynchronized) must be rejected.
@@ -501,7 +720,7 @@ mod tests {
         })
         .await;
 
-        let err = PacketNtpClient
+        let err = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_secs(2))
             .await
             .unwrap_err();
@@ -535,7 +754,7 @@ mod tests {
         })
         .await;
 
-        let err = PacketNtpClient
+        let err = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_secs(2))
             .await
             .unwrap_err();
@@ -561,7 +780,7 @@ mod tests {
         })
         .await;
 
-        let err = PacketNtpClient
+        let err = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_secs(2))
             .await
             .unwrap_err();
@@ -577,7 +796,7 @@ mod tests {
     async fn times_out_on_silence() {
         let mock = MockServer::start_silent().await;
 
-        let err = PacketNtpClient
+        let err = PacketNtpClient::default()
             .query(mock.addr(), Duration::from_millis(100))
             .await
             .unwrap_err();
@@ -588,6 +807,112 @@ mod tests {
         );
     }
 
+    /// NTP_ADDRESS_FAMILY=ipv6 must refuse an IPv4-only target instead of
+    /// falling back to it; ipv4 still reaches it.
+    #[tokio::test]
+    async fn address_family_filters_resolved_addresses() {
+        let mock = MockServer::start(|req| {
+            let now = unix_ms_to_ntp(1_700_000_000_000);
+            good_reply(req, now, now, 0, 0)
+        })
+        .await;
+
+        let err = PacketNtpClient::new(AddressFamily::Ipv6)
+            .query(mock.addr(), Duration::from_secs(2))
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("No IPv6 address"));
+
+        PacketNtpClient::new(AddressFamily::Ipv4)
+            .query(mock.addr(), Duration::from_secs(2))
+            .await
+            .expect("IPv4 target reachable with ipv4 family");
+    }
+
+    #[tokio::test]
+    async fn queries_from_configured_source_address() {
+        let mock = MockServer::start(|req| {
+            let now = unix_ms_to_ntp(1_700_000_000_000);
+            good_reply(req, now, now, 0, 0)
+        })
+        .await;
+        let source = SourceBinding {
+            addr: Some("127.0.0.1".parse().unwrap()),
+            interface: None,
+        };
+        let socket = connect_udp(mock.addr(), AddressFamily::Any, &source)
+            .await
+            .unwrap();
+        assert_eq!(socket.local_addr().unwrap().ip(), source.addr.unwrap());
+
+        PacketNtpClient::new(AddressFamily::Any)
+            .with_source(source)
+            .query(mock.addr(), Duration::from_secs(2))
+            .await
+            .expect("query from 127.0.0.1 succeeds");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn unknown_bind_interface_fails_the_query() {
+        let source = SourceBinding {
+            addr: None,
+            interface: Some("nosuchif0".to_string()),
+        };
+        let err = connect_udp("127.0.0.1:123", AddressFamily::Any, &source)
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("nosuchif0"));
+    }
+
+    fn md5_key() -> NtpKey {
+        NtpKey {
+            id: 42,
+            algorithm: crate::config::MacAlgorithm::Md5,
+            secret: b"secret".to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn keyed_server_exchange_is_authenticated() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            let (n, peer) = socket.recv_from(&mut buf).await.unwrap();
+            verify_mac(&buf[..n], &md5_key()).expect("request carries a valid MAC");
+            let req = parse_packet(&buf[..n]).unwrap();
+            let now = unix_ms_to_ntp(1_700_000_000_000);
+            let mut reply = serialize_packet(&good_reply(&req, now, now, 0, 0)).to_vec();
+            append_mac(&mut reply, &md5_key());
+            socket.send_to(&reply, peer).await.unwrap();
+        });
+
+        let client =
+            PacketNtpClient::default().with_keys(HashMap::from([(addr.clone(), md5_key())]));
+        client
+            .query(&addr, Duration::from_secs(2))
+            .await
+            .expect("authenticated exchange succeeds");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn keyed_server_rejects_unauthenticated_reply() {
+        let mock = MockServer::start(|req| {
+            let now = unix_ms_to_ntp(1_700_000_000_000);
+            good_reply(req, now, now, 0, 0)
+        })
+        .await;
+
+        let err = PacketNtpClient::default()
+            .with_keys(HashMap::from([(mock.addr().to_string(), md5_key())]))
+            .query(mock.addr(), Duration::from_secs(2))
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("not authenticated"));
+    }
+
     /// MockNtpClient works correctly for injection tests (used in P0-2+).
     #[tokio::test]
     async fn mock_client_returns_scripted_sample() {
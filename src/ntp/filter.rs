@@ -0,0 +1,132 @@
+//! ntpd-style clock filter (RFC 5905 §10, appendix A.5.2).
+//!
+//! Each server keeps its last eight samples in a shift register.  Queueing
+//! delay only ever adds to the round trip, and the offset error it causes is
+//! bounded by half the extra delay, so the sample with the smallest delay is
+//! the one whose offset is most trustworthy.  That sample — not necessarily
+//! the latest — is what reaches selection.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Samples kept per server (ntpd's `NTP_SHIFT`).
+pub const FILTER_SIZE: usize = 8;
+
+/// Frequency tolerance used to age dispersion: 15 PPM, as in RFC 5905.
+const PHI: f64 = 15e-6;
+
+/// One sample in the shift register.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterSample {
+    pub offset_ms: i64,
+    /// Round trip measured on the monotonic clock.
+    pub delay: Duration,
+    /// Dispersion when the sample was taken: precision plus `PHI × delay` (ms).
+    pub dispersion_ms: f64,
+    pub taken_at: Instant,
+}
+
+impl FilterSample {
+    /// Dispersion grown by `PHI` for every second since the sample was taken.
+    pub fn dispersion_at(&self, now: Instant) -> f64 {
+        let age = now.saturating_duration_since(self.taken_at).as_secs_f64();
+        self.dispersion_ms + PHI * age * 1000.0
+    }
+}
+
+/// Per-server shift register of the last [`FILTER_SIZE`] samples.
+#[derive(Debug, Clone, Default)]
+pub struct ClockFilter {
+    samples: VecDeque<FilterSample>,
+}
+
+impl ClockFilter {
+    /// Shift in a new sample and return the one with the smallest delay.
+    /// Ties go to the newest sample.
+    pub fn push(
+        &mut self,
+        offset_ms: i64,
+        delay: Duration,
+        precision_log2: i8,
+        taken_at: Instant,
+    ) -> FilterSample {
+        if self.samples.len() >= FILTER_SIZE {
+            self.samples.pop_front();
+        }
+        let precision_ms = 2f64.powi(precision_log2.into()) * 1000.0;
+        self.samples.push_back(FilterSample {
+            offset_ms,
+            delay,
+            dispersion_ms: precision_ms + PHI * delay.as_secs_f64() * 1000.0,
+            taken_at,
+        });
+        self.best().expect("a sample was just pushed")
+    }
+
+    /// The minimum-delay sample, if any.
+    pub fn best(&self) -> Option<FilterSample> {
+        self.samples.iter().rev().min_by_key(|s| s.delay).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn picks_the_minimum_delay_sample() {
+        let now = Instant::now();
+        let mut filter = ClockFilter::default();
+        filter.push(40, ms(120), -20, now);
+        let best = filter.push(3, ms(20), -20, now);
+        assert_eq!(best.offset_ms, 3);
+        // A later, slower sample does not displace the fast one.
+        let best = filter.push(-35, ms(90), -20, now);
+        assert_eq!((best.offset_ms, best.delay), (3, ms(20)));
+    }
+
+    #[test]
+    fn ties_go_to_the_newest_sample() {
+        let now = Instant::now();
+        let mut filter = ClockFilter::default();
+        filter.push(1, ms(30), -20, now);
+        assert_eq!(filter.push(2, ms(30), -20, now).offset_ms, 2);
+    }
+
+    #[test]
+    fn keeps_only_the_last_eight_samples() {
+        let now = Instant::now();
+        let mut filter = ClockFilter::default();
+        assert!(filter.is_empty() && filter.best().is_none());
+        filter.push(99, ms(1), -20, now);
+        for i in 0..FILTER_SIZE as i64 {
+            filter.push(i, ms(50), -20, now);
+        }
+        assert_eq!(filter.len(), FILTER_SIZE);
+        // The fast sample has been shifted out.
+        assert_eq!(filter.best().unwrap().offset_ms, FILTER_SIZE as i64 - 1);
+    }
+
+    #[test]
+    fn dispersion_grows_with_age() {
+        let now = Instant::now();
+        let mut filter = ClockFilter::default();
+        // 2^-10 s ≈ 0.98 ms precision, plus 15 PPM of a 100 ms delay.
+        let sample = filter.push(0, ms(100), -10, now);
+        assert!((sample.dispersion_ms - (1000.0 / 1024.0 + 0.0015)).abs() < 1e-9);
+        let aged = sample.dispersion_at(now + Duration::from_secs(1000));
+        assert!((aged - sample.dispersion_ms - 15.0).abs() < 1e-9);
+    }
+}
@@ -1,5 +1,13 @@
+pub mod auth;
+pub mod calibration;
 pub mod client;
+pub mod filter;
+pub mod nts;
+pub mod poll;
 pub mod protocol;
+pub mod refclock;
+pub mod resolve;
+pub mod roughtime;
 pub mod selection;
 pub mod server;
 pub mod stats;
@@ -0,0 +1,794 @@
+//! Network Time Security (RFC 8915) for upstream servers.
+//!
+//! A server listed in `NTP_SERVERS` as `nts://host[:port]` is queried with
+//! NTS instead of plain SNTP:
+//!
+//! 1. **NTS-KE** — a TLS 1.3 connection to `host:port` (default 4460, ALPN
+//!    `ntske/1`) negotiates NTPv4 + `AEAD_AES_SIV_CMAC_256`, returns a batch
+//!    of cookies and, optionally, a different NTP host/port.  The C2S and S2C
+//!    keys are exported from the TLS session.
+//! 2. **Authenticated NTP** — each request carries a Unique Identifier, one
+//!    cookie, cookie placeholders to top the pool back up, and an
+//!    Authenticator extension field sealing the whole packet with the C2S
+//!    key.  A reply is accepted only if it echoes the Unique Identifier and
+//!    its Authenticator verifies under the S2C key; the fresh cookies it
+//!    carries are encrypted inside that field.
+//!
+//! Cookies are single-use.  Key exchange is repeated when the pool runs dry
+//! or the server answers with an `NTSN` kiss-o'-death.  Everything else —
+//! origin-timestamp check, KoD/leap/stratum validation, T1–T4 capture — is
+//! shared with [`PacketNtpClient`](super::client::PacketNtpClient).
+
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, RootCertStore};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_rustls::TlsConnector;
+use tracing::{debug, info};
+
+use crate::config::{AddressFamily, SourceBinding};
+
+use super::client::{
+    NtpClient, NtpSample, connect_tcp, connect_udp, resolve_addrs, sample_from_reply,
+    system_time_unix_ms,
+};
+use super::protocol::{
+    LI_NO_WARNING, MODE_CLIENT, NTP_PACKET_SIZE, NTP_VERSION, NtpPacket, STRATUM_UNSPECIFIED,
+    parse_server_response, serialize_packet, unix_ms_to_ntp,
+};
+
+/// Prefix marking an `NTP_SERVERS` entry as NTS-secured.
+pub const NTS_SCHEME: &str = "nts://";
+
+/// IANA-assigned NTS-KE port.
+pub const DEFAULT_KE_PORT: u16 = 4460;
+
+const DEFAULT_NTP_PORT: u16 = 123;
+const ALPN_NTSKE: &[u8] = b"ntske/1";
+const EXPORTER_LABEL: &[u8] = b"EXPORTER-network-time-security";
+
+const PROTOCOL_NTPV4: u16 = 0;
+const AEAD_AES_SIV_CMAC_256: u16 = 15;
+const KEY_LEN: usize = 32;
+
+// NTS-KE record types (RFC 8915 §4).
+const RECORD_CRITICAL: u16 = 0x8000;
+const RECORD_END_OF_MESSAGE: u16 = 0;
+const RECORD_NEXT_PROTOCOL: u16 = 1;
+const RECORD_ERROR: u16 = 2;
+const RECORD_WARNING: u16 = 3;
+const RECORD_AEAD_ALGORITHM: u16 = 4;
+const RECORD_NEW_COOKIE: u16 = 5;
+const RECORD_NTP_SERVER: u16 = 6;
+const RECORD_NTP_PORT: u16 = 7;
+
+// NTP extension field types (RFC 8915 §5).
+const EF_UNIQUE_IDENTIFIER: u16 = 0x0104;
+const EF_COOKIE: u16 = 0x0204;
+const EF_COOKIE_PLACEHOLDER: u16 = 0x0304;
+const EF_AUTHENTICATOR: u16 = 0x0404;
+
+/// Smallest extension field RFC 7822 allows (header included).
+const MIN_EF_LEN: usize = 16;
+const UNIQUE_ID_LEN: usize = 32;
+const NONCE_LEN: usize = 16;
+
+/// Cookies kept per server; each request asks for enough new ones to
+/// return the pool to this size.
+const COOKIE_POOL_TARGET: usize = 8;
+
+/// Upper bound on an NTS-KE response, to cap memory on a hostile server.
+const MAX_KE_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// Kiss code telling the client its cookie was rejected.
+const KOD_NTS_NAK: u32 = u32::from_be_bytes(*b"NTSN");
+
+/// Split an `nts://host[:port]` entry into host and NTS-KE port.
+/// Returns `None` for plain (non-NTS) entries.
+pub fn parse_nts_server(server: &str) -> Option<(String, u16)> {
+    let rest = server.strip_prefix(NTS_SCHEME)?;
+    match rest.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() => {
+            Some((host.to_string(), port.parse().unwrap_or(DEFAULT_KE_PORT)))
+        }
+        _ => Some((rest.to_string(), DEFAULT_KE_PORT)),
+    }
+}
+
+/// `host:port` to resolve when checking reachability of `server`: the
+/// NTS-KE endpoint for `nts://` entries, the entry itself otherwise.
+pub fn dns_target(server: &str) -> String {
+    match parse_nts_server(server) {
+        Some((host, port)) => format!("{host}:{port}"),
+        None => server.to_string(),
+    }
+}
+
+/// Keys and cookies from one NTS-KE session with a server.
+struct NtsSession {
+    c2s: [u8; KEY_LEN],
+    s2c: [u8; KEY_LEN],
+    cookies: Vec<Vec<u8>>,
+    /// `host:port` to send NTP requests to.
+    ntp_addr: String,
+}
+
+/// One cookie taken from a session, with what is needed to use it.
+struct Checkout {
+    cookie: Vec<u8>,
+    c2s: [u8; KEY_LEN],
+    s2c: [u8; KEY_LEN],
+    ntp_addr: String,
+    /// New cookies to request so the pool returns to [`COOKIE_POOL_TARGET`].
+    wanted: usize,
+}
+
+/// What an NTS-KE server told us, before keys are attached.
+#[derive(Debug, PartialEq, Eq)]
+struct KeResponse {
+    cookies: Vec<Vec<u8>>,
+    ntp_server: Option<String>,
+    ntp_port: Option<u16>,
+}
+
+/// [`NtpClient`] that speaks NTS to `nts://` servers and delegates every
+/// other server to an inner client.
+pub struct NtsNtpClient {
+    plain: Arc<dyn NtpClient>,
+    tls: Arc<ClientConfig>,
+    sessions: Mutex<HashMap<String, NtsSession>>,
+    /// IP family for NTS-KE and NTS-protected NTP connections.
+    family: AddressFamily,
+    /// Local address/interface for NTS-KE and NTS-protected NTP sockets.
+    source: SourceBinding,
+}
+
+impl NtsNtpClient {
+    /// Wrap `plain`, trusting the Mozilla root set for NTS-KE.
+    pub fn new(plain: Arc<dyn NtpClient>) -> Self {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        Self::with_roots(plain, roots)
+    }
+
+    /// Wrap `plain`, trusting only `roots` for NTS-KE (private CAs, tests).
+    pub fn with_roots(plain: Arc<dyn NtpClient>, roots: RootCertStore) -> Self {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut tls = ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .expect("ring provider supports TLS 1.3")
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        tls.alpn_protocols = vec![ALPN_NTSKE.to_vec()];
+        Self {
+            plain,
+            tls: Arc::new(tls),
+            sessions: Mutex::new(HashMap::new()),
+            family: AddressFamily::Any,
+            source: SourceBinding::default(),
+        }
+    }
+
+    /// Restrict NTS connections to `family` (`NTP_ADDRESS_FAMILY`).
+    pub fn with_address_family(mut self, family: AddressFamily) -> Self {
+        self.family = family;
+        self
+    }
+
+    /// Bind NTS connections per `source` (`NTP_BIND_ADDR` / `NTP_BIND_INTERFACE`).
+    pub fn with_source(mut self, source: SourceBinding) -> Self {
+        self.source = source;
+        self
+    }
+
+    /// Take one cookie (and the session keys) for `server`, running key
+    /// exchange first if there is no session or it has run out of cookies.
+    /// Key exchange must finish by `deadline`.
+    async fn checkout(
+        &self,
+        server: &str,
+        host: &str,
+        port: u16,
+        deadline: tokio::time::Instant,
+    ) -> Result<Checkout> {
+        if let Some(checkout) = self.take_cookie(server) {
+            return Ok(checkout);
+        }
+        let session = tokio::time::timeout_at(deadline, self.key_exchange(host, port))
+            .await
+            .with_context(|| format!("NTS-KE with {host}:{port} timed out"))??;
+        info!(
+            server,
+            cookies = session.cookies.len(),
+            ntp_addr = %session.ntp_addr,
+            "NTS key exchange completed"
+        );
+        self.sessions.lock().insert(server.to_string(), session);
+        self.take_cookie(server)
+            .context("NTS-KE returned no usable cookies")
+    }
+
+    fn take_cookie(&self, server: &str) -> Option<Checkout> {
+        let mut sessions = self.sessions.lock();
+        let session = sessions.get_mut(server)?;
+        let cookie = session.cookies.pop()?;
+        Some(Checkout {
+            cookie,
+            c2s: session.c2s,
+            s2c: session.s2c,
+            ntp_addr: session.ntp_addr.clone(),
+            wanted: COOKIE_POOL_TARGET.saturating_sub(session.cookies.len()),
+        })
+    }
+
+    /// Run NTS-KE against `host:port` and export the session keys.
+    async fn key_exchange(&self, host: &str, port: u16) -> Result<NtsSession> {
+        let label = format!("{host}:{port}");
+        let addrs = resolve_addrs((host, port), &label, self.family).await?;
+        let tcp = connect_tcp(&addrs, &self.source)
+            .await
+            .with_context(|| format!("NTS-KE connect to {host}:{port} failed"))?;
+        let name = ServerName::try_from(host.to_string())
+            .with_context(|| format!("Invalid NTS-KE server name {host}"))?;
+        let mut tls = TlsConnector::from(self.tls.clone())
+            .connect(name, tcp)
+            .await
+            .with_context(|| format!("NTS-KE TLS handshake with {host}:{port} failed"))?;
+
+        tls.write_all(&encode_ke_request())
+            .await
+            .context("Failed to send NTS-KE request")?;
+        tls.flush().await.context("Failed to send NTS-KE request")?;
+        let records = read_ke_records(&mut tls).await?;
+        let _ = tls.shutdown().await;
+
+        let (_, conn) = tls.get_ref();
+        if conn.alpn_protocol() != Some(ALPN_NTSKE) {
+            bail!("NTS-KE server did not negotiate ALPN ntske/1");
+        }
+        let response = parse_ke_response(&records)?;
+        let export = |direction: u8| {
+            let mut context = [0u8; 5];
+            context[0..2].copy_from_slice(&PROTOCOL_NTPV4.to_be_bytes());
+            context[2..4].copy_from_slice(&AEAD_AES_SIV_CMAC_256.to_be_bytes());
+            context[4] = direction;
+            conn.export_keying_material([0u8; KEY_LEN], EXPORTER_LABEL, Some(&context))
+                .context("NTS key export failed")
+        };
+
+        Ok(NtsSession {
+            c2s: export(0)?,
+            s2c: export(1)?,
+            cookies: response.cookies,
+            ntp_addr: format!(
+                "{}:{}",
+                response.ntp_server.as_deref().unwrap_or(host),
+                response.ntp_port.unwrap_or(DEFAULT_NTP_PORT)
+            ),
+        })
+    }
+
+    /// One NTS-protected exchange.  Key exchange (when needed) and the UDP
+    /// round trip share one `timeout`, so a query never holds its slot longer.
+    async fn query_nts(
+        &self,
+        server: &str,
+        host: &str,
+        port: u16,
+        timeout: Duration,
+    ) -> Result<NtpSample> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let checkout = self.checkout(server, host, port, deadline).await?;
+        let socket = connect_udp(&checkout.ntp_addr, self.family, &self.source).await?;
+
+        let unique_id: [u8; UNIQUE_ID_LEN] = rand::random();
+        let t1_instant = Instant::now();
+        let t1_unix_ms = system_time_unix_ms(SystemTime::now());
+        let request = encode_request(
+            unix_ms_to_ntp(t1_unix_ms),
+            &unique_id,
+            &checkout.cookie,
+            // The cookie itself is answered with one new cookie
+            checkout.wanted.saturating_sub(1),
+            &checkout.c2s,
+        );
+        socket
+            .send(&request)
+            .await
+            .context("Failed to send NTS-protected NTP request")?;
+
+        let mut recv_buf = [0u8; 2048];
+        let n = tokio::time::timeout_at(deadline, socket.recv(&mut recv_buf))
+            .await
+            .context("NTP query timed out")?
+            .context("Failed to receive NTP response")?;
+        let t4_instant = Instant::now();
+        let t4_unix_ms = system_time_unix_ms(SystemTime::now());
+
+        let (reply, cookies) = match decode_response(&recv_buf[..n], &unique_id, &checkout.s2c) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                if is_nts_nak(&recv_buf[..n], &unique_id) {
+                    // The server no longer accepts our cookies: re-key next time.
+                    self.sessions.lock().remove(server);
+                    bail!("NTS negative acknowledgment (NTSN) from {server}; cookies discarded");
+                }
+                return Err(e);
+            }
+        };
+        if let Some(session) = self.sessions.lock().get_mut(server) {
+            session.cookies.extend(cookies);
+            debug!(
+                server,
+                cookies = session.cookies.len(),
+                "NTS cookie pool replenished"
+            );
+        }
+
+        sample_from_reply(
+            server, &reply, t1_unix_ms, t1_instant, t4_unix_ms, t4_instant,
+        )
+    }
+}
+
+#[async_trait]
+impl NtpClient for NtsNtpClient {
+    async fn query(&self, server: &str, timeout: Duration) -> Result<NtpSample> {
+        match parse_nts_server(server) {
+            Some((host, port)) => self.query_nts(server, &host, port, timeout).await,
+            None => self.plain.query(server, timeout).await,
+        }
+    }
+}
+
+// ── NTS-KE records ────────────────────────────────────────────────────────────
+
+fn push_record(buf: &mut Vec<u8>, record_type: u16, body: &[u8]) {
+    buf.extend_from_slice(&record_type.to_be_bytes());
+    buf.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    buf.extend_from_slice(body);
+}
+
+/// Client request: NTPv4, AES-SIV-CMAC-256, end of message.
+fn encode_ke_request() -> Vec<u8> {
+    let mut buf = Vec::new();
+    push_record(
+        &mut buf,
+        RECORD_CRITICAL | RECORD_NEXT_PROTOCOL,
+        &PROTOCOL_NTPV4.to_be_bytes(),
+    );
+    push_record(
+        &mut buf,
+        RECORD_AEAD_ALGORITHM,
+        &AEAD_AES_SIV_CMAC_256.to_be_bytes(),
+    );
+    push_record(&mut buf, RECORD_CRITICAL | RECORD_END_OF_MESSAGE, &[]);
+    buf
+}
+
+/// Read `(type, body)` records up to and including End of Message.
+async fn read_ke_records<S: AsyncReadExt + Unpin>(stream: &mut S) -> Result<Vec<(u16, Vec<u8>)>> {
+    let mut records = Vec::new();
+    let mut total = 0usize;
+    loop {
+        let mut header = [0u8; 4];
+        stream
+            .read_exact(&mut header)
+            .await
+            .context("NTS-KE response ended before End of Message")?;
+        let record_type = u16::from_be_bytes([header[0], header[1]]);
+        let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+        total += 4 + len;
+        if total > MAX_KE_RESPONSE_BYTES {
+            bail!("NTS-KE response exceeds {MAX_KE_RESPONSE_BYTES} bytes");
+        }
+        let mut body = vec![0u8; len];
+        stream
+            .read_exact(&mut body)
+            .await
+            .context("NTS-KE response truncated")?;
+        let end = record_type & !RECORD_CRITICAL == RECORD_END_OF_MESSAGE;
+        records.push((record_type, body));
+        if end {
+            return Ok(records);
+        }
+    }
+}
+
+fn u16_list(body: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    body.chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+}
+
+/// Interpret the server's records, rejecting errors, unsupported
+/// negotiation results and unknown critical records.
+fn parse_ke_response(records: &[(u16, Vec<u8>)]) -> Result<KeResponse> {
+    let mut protocol_ok = false;
+    let mut aead_ok = false;
+    let mut response = KeResponse {
+        cookies: Vec::new(),
+        ntp_server: None,
+        ntp_port: None,
+    };
+
+    for (record_type, body) in records {
+        let critical = record_type & RECORD_CRITICAL != 0;
+        match record_type & !RECORD_CRITICAL {
+            RECORD_END_OF_MESSAGE => break,
+            RECORD_NEXT_PROTOCOL => protocol_ok = u16_list(body).eq([PROTOCOL_NTPV4]),
+            RECORD_ERROR => {
+                let code = u16_list(body).next().unwrap_or(u16::MAX);
+                bail!("NTS-KE server returned error code {code}");
+            }
+            RECORD_WARNING => {
+                debug!(code = ?u16_list(body).next(), "NTS-KE server warning");
+            }
+            RECORD_AEAD_ALGORITHM => aead_ok = u16_list(body).eq([AEAD_AES_SIV_CMAC_256]),
+            RECORD_NEW_COOKIE => response.cookies.push(body.clone()),
+            RECORD_NTP_SERVER => {
+                let name = std::str::from_utf8(body).context("NTS-KE server name is not ASCII")?;
+                response.ntp_server = Some(name.to_string());
+            }
+            RECORD_NTP_PORT => {
+                response.ntp_port = u16_list(body).next();
+            }
+            other if critical => bail!("NTS-KE server sent unknown critical record {other}"),
+            _ => {}
+        }
+    }
+
+    if !protocol_ok {
+        bail!("NTS-KE server did not agree to NTPv4");
+    }
+    if !aead_ok {
+        bail!("NTS-KE server did not agree to AEAD_AES_SIV_CMAC_256");
+    }
+    if response.cookies.is_empty() {
+        bail!("NTS-KE server returned no cookies");
+    }
+    Ok(response)
+}
+
+// ── NTP extension fields ──────────────────────────────────────────────────────
+
+fn padded_len(len: usize) -> usize {
+    len.div_ceil(4) * 4
+}
+
+fn push_extension(buf: &mut Vec<u8>, field_type: u16, body: &[u8]) {
+    let len = (4 + padded_len(body.len())).max(MIN_EF_LEN);
+    buf.extend_from_slice(&field_type.to_be_bytes());
+    buf.extend_from_slice(&(len as u16).to_be_bytes());
+    buf.extend_from_slice(body);
+    buf.resize(buf.len() + len - 4 - body.len(), 0);
+}
+
+/// Walk extension fields, yielding `(offset, type, body)`.
+fn extensions(bytes: &[u8]) -> impl Iterator<Item = Result<(usize, u16, &[u8])>> + '_ {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        if pos + 4 > bytes.len() {
+            return None;
+        }
+        let field_type = u16::from_be_bytes([bytes[pos], bytes[pos + 1]]);
+        let len = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        if len < 4 || !len.is_multiple_of(4) || pos + len > bytes.len() {
+            pos = bytes.len();
+            return Some(Err(anyhow::anyhow!("Malformed NTP extension field")));
+        }
+        let item = (pos, field_type, &bytes[pos + 4..pos + len]);
+        pos += len;
+        Some(Ok(item))
+    })
+}
+
+/// A client request: header, Unique Identifier, cookie, `placeholders`
+/// cookie placeholders, and an Authenticator sealed with `c2s`.
+fn encode_request(
+    transmit_timestamp: u64,
+    unique_id: &[u8],
+    cookie: &[u8],
+    placeholders: usize,
+    c2s: &[u8; KEY_LEN],
+) -> Vec<u8> {
+    let mut header = NtpPacket::new(LI_NO_WARNING, NTP_VERSION, MODE_CLIENT);
+    header.transmit_timestamp = transmit_timestamp;
+    let mut buf = serialize_packet(&header).to_vec();
+    push_extension(&mut buf, EF_UNIQUE_IDENTIFIER, unique_id);
+    push_extension(&mut buf, EF_COOKIE, cookie);
+    let placeholder = vec![0u8; cookie.len()];
+    for _ in 0..placeholders {
+        push_extension(&mut buf, EF_COOKIE_PLACEHOLDER, &placeholder);
+    }
+
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = siv::seal(c2s, &buf, &nonce, &[]);
+    let mut auth = Vec::with_capacity(4 + NONCE_LEN + ciphertext.len());
+    auth.extend_from_slice(&(NONCE_LEN as u16).to_be_bytes());
+    auth.extend_from_slice(&(ciphertext.len() as u16).to_be_bytes());
+    auth.extend_from_slice(&nonce);
+    auth.extend_from_slice(&ciphertext);
+    push_extension(&mut buf, EF_AUTHENTICATOR, &auth);
+    buf
+}
+
+/// Verify a server reply and return its header plus the new cookies sealed
+/// in its Authenticator.  Fields after the Authenticator are unauthenticated
+/// and ignored.
+fn decode_response(
+    bytes: &[u8],
+    unique_id: &[u8],
+    s2c: &[u8; KEY_LEN],
+) -> Result<(NtpPacket, Vec<Vec<u8>>)> {
+    let reply = parse_server_response(bytes).context("Failed to parse NTP server response")?;
+    let mut id_matches = false;
+
+    for field in extensions(&bytes[NTP_PACKET_SIZE..]) {
+        let (offset, field_type, body) = field?;
+        match field_type {
+            EF_UNIQUE_IDENTIFIER => id_matches = body == unique_id,
+            EF_AUTHENTICATOR => {
+                if !id_matches {
+                    bail!("NTS reply does not echo our Unique Identifier");
+                }
+                let associated = &bytes[..NTP_PACKET_SIZE + offset];
+                let plaintext = open_authenticator(body, associated, s2c)?;
+                let cookies = extensions(&plaintext)
+                    .filter_map(Result::ok)
+                    .filter(|(_, field_type, _)| *field_type == EF_COOKIE)
+                    .map(|(_, _, cookie)| cookie.to_vec())
+                    .collect();
+                return Ok((reply, cookies));
+            }
+            _ => {}
+        }
+    }
+    bail!("NTS reply has no Authenticator extension field")
+}
+
+fn open_authenticator(body: &[u8], associated: &[u8], s2c: &[u8; KEY_LEN]) -> Result<Vec<u8>> {
+    if body.len() < 4 {
+        bail!("Malformed NTS Authenticator");
+    }
+    let nonce_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+    let ciphertext_len = u16::from_be_bytes([body[2], body[3]]) as usize;
+    let nonce_start = 4;
+    let ciphertext_start = nonce_start + padded_len(nonce_len);
+    if ciphertext_start + ciphertext_len > body.len() {
+        bail!("Malformed NTS Authenticator");
+    }
+    let nonce = &body[nonce_start..nonce_start + nonce_len];
+    let ciphertext = &body[ciphertext_start..ciphertext_start + ciphertext_len];
+    siv::open(s2c, associated, nonce, ciphertext)
+        .context("NTS Authenticator verification failed (forged or corrupted reply)")
+}
+
+/// Unauthenticated `NTSN` kiss-o'-death echoing our Unique Identifier.
+fn is_nts_nak(bytes: &[u8], unique_id: &[u8]) -> bool {
+    let Ok(reply) = parse_server_response(bytes) else {
+        return false;
+    };
+    reply.stratum == STRATUM_UNSPECIFIED
+        && reply.reference_id == KOD_NTS_NAK
+        && extensions(&bytes[NTP_PACKET_SIZE..])
+            .any(|field| matches!(field, Ok((_, EF_UNIQUE_IDENTIFIER, body)) if body == unique_id))
+}
+
+/// AEAD_AES_SIV_CMAC_256 (RFC 5297) from the RustCrypto `aes-siv` crate.
+/// The associated data are the packet up to the Authenticator, then the
+/// nonce (RFC 8915 §5.6), which is the order `Aes128SivAead` uses.
+mod siv {
+    use aes_siv::aead::{Aead, Payload};
+    use aes_siv::siv::Aes128Siv;
+    use aes_siv::{Aes128SivAead, KeyInit, Nonce};
+
+    /// Synthetic IV followed by the ciphertext.
+    pub fn seal(key: &[u8; 32], associated: &[u8], nonce: &[u8; 16], plaintext: &[u8]) -> Vec<u8> {
+        Aes128SivAead::new(key.into())
+            .encrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: associated,
+                },
+            )
+            .expect("AES-SIV seals any plaintext")
+    }
+
+    /// Inverse of [`seal`]; `None` when authentication fails.  The nonce is
+    /// the server's, and its length is the server's choice, so this takes
+    /// the SIV headers directly rather than a fixed-size AEAD nonce.
+    pub fn open(key: &[u8; 32], associated: &[u8], nonce: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+        Aes128Siv::new(key.into())
+            .decrypt([associated, nonce], sealed)
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntp::protocol::{MODE_SERVER, STRATUM_PRIMARY};
+
+    fn hex(s: &str) -> Vec<u8> {
+        let s: String = s.split_whitespace().collect();
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn siv_matches_rfc5297_deterministic_vector() {
+        use aes_siv::KeyInit;
+
+        let key: [u8; 32] = hex("fffefdfc fbfaf9f8 f7f6f5f4 f3f2f1f0
+             f0f1f2f3 f4f5f6f7 f8f9fafb fcfdfeff")
+        .try_into()
+        .unwrap();
+        let ad = hex("10111213 14151617 18191a1b 1c1d1e1f 20212223 24252627");
+        let plaintext = hex("11223344 55667788 99aabbcc ddee");
+
+        // The RFC vector has no nonce: S2V over the AD alone.
+        let sealed = aes_siv::siv::Aes128Siv::new((&key).into())
+            .encrypt([&ad], &plaintext)
+            .unwrap();
+        assert_eq!(
+            sealed,
+            hex("85632d07 c6e8f37f 950acd32 0a2ecc93 40c02b96 90c4dc04 daef7f6a fe5c")
+        );
+
+        // Sealed under a nonce, opened with the nonce as the last header.
+        let nonce = [7u8; 16];
+        let sealed = siv::seal(&key, &ad, &nonce, &plaintext);
+        assert_eq!(siv::open(&key, &ad, &nonce, &sealed), Some(plaintext));
+
+        let mut tampered = sealed.clone();
+        tampered[20] ^= 1;
+        assert_eq!(siv::open(&key, &ad, &nonce, &tampered), None);
+        assert_eq!(siv::open(&key, &ad, &[7u8; 15], &sealed), None);
+    }
+
+    #[test]
+    fn parses_nts_server_entries() {
+        assert_eq!(
+            parse_nts_server("nts://time.cloudflare.com"),
+            Some(("time.cloudflare.com".to_string(), DEFAULT_KE_PORT))
+        );
+        assert_eq!(
+            parse_nts_server("nts://nts.example.org:4461"),
+            Some(("nts.example.org".to_string(), 4461))
+        );
+        assert_eq!(parse_nts_server("time.google.com:123"), None);
+        assert_eq!(
+            dns_target("nts://time.cloudflare.com"),
+            "time.cloudflare.com:4460"
+        );
+        assert_eq!(dns_target("time.google.com:123"), "time.google.com:123");
+    }
+
+    #[test]
+    fn ke_response_negotiation() {
+        let mut records = vec![
+            (RECORD_CRITICAL | RECORD_NEXT_PROTOCOL, vec![0, 0]),
+            (RECORD_AEAD_ALGORITHM, vec![0, 15]),
+            (RECORD_NEW_COOKIE, vec![1; 100]),
+            (RECORD_NEW_COOKIE, vec![2; 100]),
+            (RECORD_NTP_SERVER, b"ntp.example.org".to_vec()),
+            (RECORD_NTP_PORT, vec![0x01, 0x7b]),
+            (RECORD_CRITICAL | RECORD_END_OF_MESSAGE, vec![]),
+        ];
+        let response = parse_ke_response(&records).unwrap();
+        assert_eq!(response.cookies.len(), 2);
+        assert_eq!(response.ntp_server.as_deref(), Some("ntp.example.org"));
+        assert_eq!(response.ntp_port, Some(379));
+
+        records[1].1 = vec![0, 30];
+        assert!(parse_ke_response(&records).is_err(), "unsupported AEAD");
+        records[1].1 = vec![0, 15];
+        records.insert(0, (RECORD_CRITICAL | 0x4000, vec![]));
+        assert!(parse_ke_response(&records).is_err(), "unknown critical");
+        records[0] = (RECORD_CRITICAL | RECORD_ERROR, vec![0, 1]);
+        assert!(parse_ke_response(&records).is_err(), "server error");
+
+        let request = encode_ke_request();
+        assert_eq!(
+            request,
+            [0x80, 1, 0, 2, 0, 0, 0, 4, 0, 2, 0, 15, 0x80, 0, 0, 0]
+        );
+    }
+
+    /// Server side of one NTS exchange, as far as the client can observe it.
+    fn server_reply(request: &[u8], c2s: &[u8; 32], s2c: &[u8; 32], cookies: &[&[u8]]) -> Vec<u8> {
+        let client = parse_server_response(request).unwrap();
+        let mut unique_id = Vec::new();
+        for field in extensions(&request[NTP_PACKET_SIZE..]) {
+            let (offset, field_type, body) = field.unwrap();
+            match field_type {
+                EF_UNIQUE_IDENTIFIER => unique_id = body.to_vec(),
+                EF_AUTHENTICATOR => {
+                    let associated = &request[..NTP_PACKET_SIZE + offset];
+                    open_authenticator(body, associated, c2s).expect("client seal verifies");
+                }
+                _ => {}
+            }
+        }
+
+        let now = unix_ms_to_ntp(1_700_000_000_000);
+        let header = NtpPacket {
+            li: LI_NO_WARNING,
+            vn: NTP_VERSION,
+            mode: MODE_SERVER,
+            stratum: STRATUM_PRIMARY,
+            poll: 4,
+            precision: -20,
+            root_delay: 0,
+            root_dispersion: 0,
+            reference_id: u32::from_be_bytes(*b"GPS\0"),
+            ref_timestamp: now,
+            origin_timestamp: client.transmit_timestamp,
+            receive_timestamp: now,
+            transmit_timestamp: now,
+        };
+        let mut buf = serialize_packet(&header).to_vec();
+        push_extension(&mut buf, EF_UNIQUE_IDENTIFIER, &unique_id);
+        let mut plaintext = Vec::new();
+        for cookie in cookies {
+            push_extension(&mut plaintext, EF_COOKIE, cookie);
+        }
+        let nonce = [7u8; NONCE_LEN];
+        let sealed = siv::seal(s2c, &buf, &nonce, &plaintext);
+        let mut auth = Vec::new();
+        auth.extend_from_slice(&(NONCE_LEN as u16).to_be_bytes());
+        auth.extend_from_slice(&(sealed.len() as u16).to_be_bytes());
+        auth.extend_from_slice(&nonce);
+        auth.extend_from_slice(&sealed);
+        push_extension(&mut buf, EF_AUTHENTICATOR, &auth);
+        buf
+    }
+
+    #[test]
+    fn authenticated_exchange_round_trip() {
+        let (c2s, s2c) = ([1u8; 32], [2u8; 32]);
+        let unique_id = [9u8; UNIQUE_ID_LEN];
+        let request = encode_request(
+            unix_ms_to_ntp(1_700_000_000_000),
+            &unique_id,
+            &[5; 96],
+            2,
+            &c2s,
+        );
+        // Header + UID (36) + cookie (100) + 2 placeholders + authenticator (40)
+        assert_eq!(request.len(), NTP_PACKET_SIZE + 36 + 3 * 100 + 40);
+
+        let reply = server_reply(&request, &c2s, &s2c, &[&[6; 96], &[7; 96]]);
+        let (header, cookies) = decode_response(&reply, &unique_id, &s2c).unwrap();
+        assert_eq!(header.origin_timestamp, unix_ms_to_ntp(1_700_000_000_000));
+        assert_eq!(cookies, vec![vec![6; 96], vec![7; 96]]);
+
+        // Wrong key, tampered header, or foreign Unique Identifier: rejected.
+        assert!(decode_response(&reply, &unique_id, &c2s).is_err());
+        let mut tampered = reply.clone();
+        tampered[40] ^= 1;
+        assert!(decode_response(&tampered, &unique_id, &s2c).is_err());
+        assert!(decode_response(&reply, &[0u8; UNIQUE_ID_LEN], &s2c).is_err());
+        // Plain SNTP reply without extension fields: rejected.
+        assert!(decode_response(&reply[..NTP_PACKET_SIZE], &unique_id, &s2c).is_err());
+    }
+
+    #[test]
+    fn recognises_nts_nak() {
+        let unique_id = [3u8; UNIQUE_ID_LEN];
+        let mut header = NtpPacket::new(LI_NO_WARNING, NTP_VERSION, MODE_SERVER);
+        header.reference_id = KOD_NTS_NAK;
+        let mut nak = serialize_packet(&header).to_vec();
+        push_extension(&mut nak, EF_UNIQUE_IDENTIFIER, &unique_id);
+        assert!(is_nts_nak(&nak, &unique_id));
+        assert!(!is_nts_nak(&nak, &[4u8; UNIQUE_ID_LEN]));
+    }
+}
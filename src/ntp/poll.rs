@@ -0,0 +1,135 @@
+//! Adaptive sync (poll) interval, in the spirit of ntpd's poll-adjust.
+//!
+//! The interval starts at `SYNC_INTERVAL` and doubles — up to
+//! `SYNC_INTERVAL_MAX` — after a run of syncs whose offset barely moved.  A
+//! moderate offset change halves it; a large change or a failed sync drops it
+//! straight back to `SYNC_INTERVAL`.  With `SYNC_INTERVAL_MAX` equal to
+//! `SYNC_INTERVAL` (the default) the interval is fixed.
+
+use std::time::Duration;
+
+/// Consecutive stable syncs required before the interval doubles.
+const STABLE_RUN_TO_LENGTHEN: u32 = 4;
+
+/// An offset change above this multiple of the stability threshold resets
+/// the interval to the minimum instead of halving it.
+const RESET_MULTIPLIER: u64 = 4;
+
+#[derive(Debug, Clone)]
+pub struct AdaptivePoll {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+    stable_offset_ms: u64,
+    stable_run: u32,
+    last_offset_ms: Option<i64>,
+}
+
+impl AdaptivePoll {
+    /// `stable_offset_ms` is the largest sync-to-sync offset change still
+    /// considered stable.
+    pub fn new(min: Duration, max: Duration, stable_offset_ms: u64) -> Self {
+        Self {
+            min,
+            max: max.max(min),
+            current: min,
+            stable_offset_ms,
+            stable_run: 0,
+            last_offset_ms: None,
+        }
+    }
+
+    /// Interval until the next sync.
+    pub fn interval(&self) -> Duration {
+        self.current
+    }
+
+    /// Adjust after a successful sync that measured `offset_ms`.
+    pub fn on_success(&mut self, offset_ms: i64) -> Duration {
+        if let Some(last) = self.last_offset_ms.replace(offset_ms) {
+            let change = offset_ms.abs_diff(last);
+            if change <= self.stable_offset_ms {
+                self.stable_run += 1;
+                if self.stable_run >= STABLE_RUN_TO_LENGTHEN {
+                    self.current = (self.current * 2).min(self.max);
+                    self.stable_run = 0;
+                }
+            } else if change > self.stable_offset_ms * RESET_MULTIPLIER {
+                self.reset();
+            } else {
+                self.current = (self.current / 2).max(self.min);
+                self.stable_run = 0;
+            }
+        }
+        self.current
+    }
+
+    /// Adjust after a failed sync: poll at the minimum interval until it
+    /// recovers.
+    pub fn on_failure(&mut self) -> Duration {
+        self.reset();
+        self.last_offset_ms = None;
+        self.current
+    }
+
+    fn reset(&mut self) {
+        self.current = self.min;
+        self.stable_run = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll() -> AdaptivePoll {
+        AdaptivePoll::new(Duration::from_secs(30), Duration::from_secs(300), 5)
+    }
+
+    #[test]
+    fn stable_offsets_lengthen_up_to_max() {
+        let mut p = poll();
+        p.on_success(100);
+        for _ in 0..STABLE_RUN_TO_LENGTHEN {
+            p.on_success(102);
+        }
+        assert_eq!(p.interval(), Duration::from_secs(60));
+        for _ in 0..(STABLE_RUN_TO_LENGTHEN * 10) {
+            p.on_success(101);
+        }
+        assert_eq!(p.interval(), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn offset_changes_and_failures_shorten() {
+        let mut p = poll();
+        p.on_success(0);
+        for _ in 0..(STABLE_RUN_TO_LENGTHEN * 3) {
+            p.on_success(0);
+        }
+        assert_eq!(p.interval(), Duration::from_secs(240));
+
+        // Moderate change halves.
+        p.on_success(10);
+        assert_eq!(p.interval(), Duration::from_secs(120));
+        // Large change resets to the minimum.
+        p.on_success(100);
+        assert_eq!(p.interval(), Duration::from_secs(30));
+
+        for _ in 0..STABLE_RUN_TO_LENGTHEN {
+            p.on_success(100);
+        }
+        assert_eq!(p.interval(), Duration::from_secs(60));
+        p.on_failure();
+        assert_eq!(p.interval(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn max_equal_to_min_keeps_interval_fixed() {
+        let mut p = AdaptivePoll::new(Duration::from_secs(30), Duration::from_secs(30), 5);
+        for _ in 0..20 {
+            p.on_success(0);
+        }
+        assert_eq!(p.interval(), Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,372 @@
+//! Local reference clocks via gpsd.
+//!
+//! A server listed in `NTP_SERVERS` as `gpsd://host[:port]` (default port
+//! 2947) is read from a gpsd daemon instead of queried over NTP.  Each query
+//! opens the JSON socket, enables watching and waits for a report:
+//!
+//! - **PPS** — when gpsd is attached to a PPS device (`gpsd /dev/ttyS0
+//!   /dev/pps0`), each pulse is reported with the true top of second and the
+//!   system clock at the edge, giving microsecond-level offsets.
+//! - **TPV** — without PPS, the fix time of a TPV report against its arrival
+//!   time.  Serial latency makes this good only to tens of milliseconds, so
+//!   these samples carry [`TPV_DISPERSION_MS`] of root dispersion.
+//!
+//! Samples enter selection like any NTP sample, as stratum 0 sources, so a
+//! PPS-disciplined GPS usually wins and the UDP server advertises stratum 1.
+
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+use super::client::{NtpClient, NtpSample, system_time_unix_ms};
+use super::protocol::LI_NO_WARNING;
+
+/// Prefix marking an `NTP_SERVERS` entry as a gpsd reference clock.
+pub const GPSD_SCHEME: &str = "gpsd://";
+
+/// gpsd's IANA-assigned port.
+pub const DEFAULT_GPSD_PORT: u16 = 2947;
+
+/// Reference ID for samples disciplined by a PPS edge.
+pub const REFERENCE_ID_PPS: u32 = u32::from_be_bytes(*b"PPS\0");
+
+/// Reference ID for samples taken from GPS fix times alone.
+pub const REFERENCE_ID_GPS: u32 = u32::from_be_bytes(*b"GPS\0");
+
+/// Root dispersion charged to TPV samples for serial/processing latency.
+pub const TPV_DISPERSION_MS: u32 = 100;
+
+/// TPV reports to wait through for a PPS report before settling for TPV.
+const TPV_REPORTS_BEFORE_FALLBACK: usize = 2;
+
+const WATCH_COMMAND: &[u8] = b"?WATCH={\"enable\":true,\"json\":true,\"pps\":true};\n";
+
+/// Whether `server` names a reference clock rather than an NTP server.
+pub fn is_refclock(server: &str) -> bool {
+    server.starts_with(GPSD_SCHEME)
+}
+
+/// `host:port` of a `gpsd://` entry, or `None` for other servers.
+pub fn parse_gpsd_server(server: &str) -> Option<String> {
+    let rest = server.strip_prefix(GPSD_SCHEME)?;
+    let rest = if rest.is_empty() { "127.0.0.1" } else { rest };
+    // A colon after any IPv6 literal's closing bracket introduces the port.
+    let has_port = match rest.rfind(']') {
+        Some(i) => rest[i..].contains(':'),
+        None => rest.contains(':'),
+    };
+    if has_port {
+        Some(rest.to_string())
+    } else {
+        Some(format!("{rest}:{DEFAULT_GPSD_PORT}"))
+    }
+}
+
+/// A time report decoded from one gpsd JSON line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Report {
+    /// True time minus system time at the pulse edge, in ns.
+    Pps { offset_ns: i64, precision_log2: i8 },
+    /// Fix time (unix ms) of a TPV report.
+    Tpv { fix_unix_ms: i64 },
+}
+
+fn parse_report(line: &str) -> Option<Report> {
+    let msg: Value = serde_json::from_str(line).ok()?;
+    match msg.get("class")?.as_str()? {
+        "PPS" => {
+            let ns = |sec: &str, nsec: &str| -> Option<i64> {
+                Some(msg.get(sec)?.as_i64()? * 1_000_000_000 + msg.get(nsec)?.as_i64()?)
+            };
+            let offset_ns = ns("real_sec", "real_nsec")? - ns("clock_sec", "clock_nsec")?;
+            let precision_log2 = msg
+                .get("precision")
+                .and_then(Value::as_i64)
+                .map_or(-20, |p| p.clamp(-32, 0) as i8);
+            Some(Report::Pps {
+                offset_ns,
+                precision_log2,
+            })
+        }
+        // mode 0/1: no fix, so `time` is not from the satellites.
+        "TPV" if msg.get("mode")?.as_u64()? >= 2 => {
+            let time = chrono::DateTime::parse_from_rfc3339(msg.get("time")?.as_str()?).ok()?;
+            Some(Report::Tpv {
+                fix_unix_ms: time.timestamp_millis(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Build a sample from a report received at `received_unix_ms` (system
+/// clock) / `received_instant`.  There is no round trip, so T1 = T4 and
+/// T2 = T3 = true time.
+fn sample_from_report(
+    server: &str,
+    report: Report,
+    received_unix_ms: i64,
+    received_instant: Instant,
+) -> NtpSample {
+    let (offset_ms, precision_log2, root_dispersion_ms, reference_id) = match report {
+        Report::Pps {
+            offset_ns,
+            precision_log2,
+        } => (
+            (offset_ns as f64 / 1_000_000.0).round() as i64,
+            precision_log2,
+            0,
+            REFERENCE_ID_PPS,
+        ),
+        Report::Tpv { fix_unix_ms } => (
+            fix_unix_ms - received_unix_ms,
+            -7,
+            TPV_DISPERSION_MS,
+            REFERENCE_ID_GPS,
+        ),
+    };
+    let true_ms = received_unix_ms + offset_ms;
+    NtpSample {
+        server: server.to_string(),
+        t1_unix_ms: received_unix_ms,
+        t2_unix_ms: true_ms,
+        t3_unix_ms: true_ms,
+        t4_unix_ms: received_unix_ms,
+        t1_instant: received_instant,
+        t4_instant: received_instant,
+        offset_ms,
+        delay_ms: 0,
+        root_delay_ms: 0,
+        root_dispersion_ms,
+        precision_log2,
+        stratum: 0,
+        leap: LI_NO_WARNING,
+        reference_id,
+        poll: 0,
+    }
+}
+
+/// Reads `gpsd://` servers from gpsd; everything else goes to `inner`.
+pub struct RefclockNtpClient {
+    inner: Arc<dyn NtpClient>,
+}
+
+impl RefclockNtpClient {
+    pub fn new(inner: Arc<dyn NtpClient>) -> Self {
+        Self { inner }
+    }
+
+    async fn read_gpsd(&self, server: &str, addr: &str) -> Result<NtpSample> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .with_context(|| format!("Failed to connect to gpsd at {addr}"))?;
+        let (read, mut write) = stream.into_split();
+        write
+            .write_all(WATCH_COMMAND)
+            .await
+            .context("Failed to send gpsd WATCH command")?;
+
+        let mut lines = BufReader::new(read).lines();
+        let mut fallback = None;
+        let mut tpv_seen = 0;
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .context("Failed to read from gpsd")?
+        {
+            let received_instant = Instant::now();
+            let received_unix_ms = system_time_unix_ms(SystemTime::now());
+            match parse_report(&line) {
+                Some(report @ Report::Pps { .. }) => {
+                    return Ok(sample_from_report(
+                        server,
+                        report,
+                        received_unix_ms,
+                        received_instant,
+                    ));
+                }
+                Some(report @ Report::Tpv { .. }) => {
+                    fallback.get_or_insert_with(|| {
+                        sample_from_report(server, report, received_unix_ms, received_instant)
+                    });
+                    tpv_seen += 1;
+                    if tpv_seen >= TPV_REPORTS_BEFORE_FALLBACK {
+                        debug!(server, "No PPS reports from gpsd; using TPV fix time");
+                        break;
+                    }
+                }
+                None => {}
+            }
+        }
+        match fallback {
+            Some(sample) => Ok(sample),
+            None => bail!("gpsd at {addr} closed the connection without a time report"),
+        }
+    }
+}
+
+#[async_trait]
+impl NtpClient for RefclockNtpClient {
+    async fn query(&self, server: &str, timeout: Duration) -> Result<NtpSample> {
+        match parse_gpsd_server(server) {
+            Some(addr) => tokio::time::timeout(timeout, self.read_gpsd(server, &addr))
+                .await
+                .with_context(|| format!("No time report from gpsd at {addr}"))?,
+            None => self.inner.query(server, timeout).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntp::client::MockNtpClient;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn parses_gpsd_server_entries() {
+        assert_eq!(
+            parse_gpsd_server("gpsd://"),
+            Some("127.0.0.1:2947".to_string())
+        );
+        assert_eq!(
+            parse_gpsd_server("gpsd://gps.local"),
+            Some("gps.local:2947".to_string())
+        );
+        assert_eq!(
+            parse_gpsd_server("gpsd://10.0.0.5:3000"),
+            Some("10.0.0.5:3000".to_string())
+        );
+        assert_eq!(
+            parse_gpsd_server("gpsd://[::1]"),
+            Some("[::1]:2947".to_string())
+        );
+        assert_eq!(parse_gpsd_server("time.google.com:123"), None);
+        assert!(is_refclock("gpsd://"));
+        assert!(!is_refclock("nts://time.cloudflare.com"));
+    }
+
+    #[test]
+    fn parses_pps_and_tpv_reports() {
+        let pps = r#"{"class":"PPS","device":"/dev/pps0","real_sec":1700000000,"real_nsec":0,"clock_sec":1699999999,"clock_nsec":997000000,"precision":-20}"#;
+        assert_eq!(
+            parse_report(pps),
+            Some(Report::Pps {
+                offset_ns: 3_000_000,
+                precision_log2: -20
+            })
+        );
+        let tpv =
+            r#"{"class":"TPV","device":"/dev/ttyS0","mode":3,"time":"2023-11-14T22:13:20.000Z"}"#;
+        assert_eq!(
+            parse_report(tpv),
+            Some(Report::Tpv {
+                fix_unix_ms: 1_700_000_000_000
+            })
+        );
+        let no_fix = r#"{"class":"TPV","mode":1,"time":"2023-11-14T22:13:20.000Z"}"#;
+        assert_eq!(parse_report(no_fix), None);
+        assert_eq!(
+            parse_report(r#"{"class":"VERSION","release":"3.25"}"#),
+            None
+        );
+        assert_eq!(parse_report("not json"), None);
+    }
+
+    #[test]
+    fn samples_carry_refclock_quality() {
+        let now = Instant::now();
+        let pps = sample_from_report(
+            "gpsd://",
+            Report::Pps {
+                offset_ns: -2_400_000,
+                precision_log2: -20,
+            },
+            1_000_000,
+            now,
+        );
+        assert_eq!(pps.offset_ms, -2);
+        assert_eq!(pps.t2_unix_ms, 999_998);
+        assert_eq!(pps.stratum, 0);
+        assert_eq!(pps.root_dispersion_ms, 0);
+        assert_eq!(pps.reference_id, REFERENCE_ID_PPS);
+
+        let tpv = sample_from_report(
+            "gpsd://",
+            Report::Tpv {
+                fix_unix_ms: 999_900,
+            },
+            1_000_000,
+            now,
+        );
+        assert_eq!(tpv.offset_ms, -100);
+        assert_eq!(tpv.root_dispersion_ms, TPV_DISPERSION_MS);
+        assert_eq!(tpv.reference_id, REFERENCE_ID_GPS);
+    }
+
+    async fn fake_gpsd(reports: &'static [&'static str]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream
+                .write_all(b"{\"class\":\"VERSION\",\"release\":\"3.25\"}\n")
+                .await
+                .unwrap();
+            let mut buf = [0u8; 128];
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(buf[..n].starts_with(b"?WATCH="));
+            for report in reports {
+                stream.write_all(report.as_bytes()).await.unwrap();
+                stream.write_all(b"\n").await.unwrap();
+            }
+        });
+        format!("gpsd://{addr}")
+    }
+
+    #[tokio::test]
+    async fn reads_pps_from_gpsd_and_passes_other_servers_through() {
+        let server = fake_gpsd(&[
+            r#"{"class":"TPV","mode":3,"time":"2023-11-14T22:13:20.000Z"}"#,
+            r#"{"class":"PPS","real_sec":1700000000,"real_nsec":0,"clock_sec":1700000000,"clock_nsec":1000000}"#,
+        ])
+        .await;
+        let client = RefclockNtpClient::new(Arc::new(MockNtpClient::err("no NTP")));
+        let sample = client
+            .query(&server, Duration::from_secs(2))
+            .await
+            .expect("PPS sample");
+        assert_eq!(sample.offset_ms, -1);
+        assert_eq!(sample.reference_id, REFERENCE_ID_PPS);
+        assert_eq!(sample.server, server);
+
+        assert!(
+            client
+                .query("time.google.com:123", Duration::from_secs(1))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_tpv_without_pps() {
+        let server = fake_gpsd(&[
+            r#"{"class":"TPV","mode":3,"time":"2023-11-14T22:13:20.000Z"}"#,
+            r#"{"class":"TPV","mode":3,"time":"2023-11-14T22:13:21.000Z"}"#,
+        ])
+        .await;
+        let client = RefclockNtpClient::new(Arc::new(MockNtpClient::err("no NTP")));
+        let sample = client
+            .query(&server, Duration::from_secs(2))
+            .await
+            .expect("TPV sample");
+        assert_eq!(sample.reference_id, REFERENCE_ID_GPS);
+        assert_eq!(sample.t2_unix_ms, 1_700_000_000_000);
+    }
+}
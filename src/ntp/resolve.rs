@@ -0,0 +1,308 @@
+//! Pool hostname expansion.
+//!
+//! A pool name such as `pool.ntp.org` resolves to a rotating set of unrelated
+//! servers, but without expansion it is queried — and scored — as a single
+//! server.  With `NTP_POOL_EXPANSION=true` every configured hostname is
+//! expanded into its A/AAAA records, each address is queried and tracked as
+//! its own server, and names are re-resolved every
+//! `NTP_POOL_RESOLVE_INTERVAL_SECS` so pool rotation is honoured.
+
+use super::nts::NTS_SCHEME;
+use super::refclock::is_refclock;
+use super::selection::provider_group;
+use crate::config::{AddressFamily, QueryConfig, SelectionConfig, ServerConfig};
+use futures_util::future::join_all;
+use parking_lot::Mutex;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Upper bound on one hostname lookup.
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One address to query and the `NTP_SERVERS` entry it was expanded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTarget {
+    /// Server identifier used for the query, stats and selection.
+    pub address: String,
+    /// Configured entry; equal to `address` when not expanded.
+    pub origin: String,
+}
+
+impl QueryTarget {
+    fn unexpanded(server: &str) -> Self {
+        Self {
+            address: server.to_string(),
+            origin: server.to_string(),
+        }
+    }
+
+    pub fn is_expanded(&self) -> bool {
+        self.address != self.origin
+    }
+}
+
+/// Expands configured hostnames into per-address query targets, caching
+/// the expansion for the re-resolve interval.
+pub struct PoolResolver {
+    enabled: bool,
+    family: AddressFamily,
+    refresh: Duration,
+    max_addresses: usize,
+    /// Entries never expanded: keyed servers, whose key is bound to the
+    /// configured name.
+    pinned: HashSet<String>,
+    /// Last expansion and when it was made.
+    cache: Mutex<Option<(Instant, Vec<QueryTarget>)>>,
+}
+
+impl PoolResolver {
+    pub fn new(config: &QueryConfig, family: AddressFamily) -> Self {
+        Self {
+            enabled: config.expand_pools,
+            family,
+            refresh: Duration::from_secs(config.pool_resolve_interval_secs),
+            max_addresses: config.pool_max_addresses,
+            pinned: HashSet::new(),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Drop the cached expansion so the next `targets` call re-resolves,
+    /// e.g. after the configured server set changed.
+    pub fn invalidate(&self) {
+        *self.cache.lock() = None;
+    }
+
+    /// Never expand `servers`; they are always queried by name.
+    pub fn pinning(mut self, servers: impl IntoIterator<Item = String>) -> Self {
+        self.pinned.extend(servers);
+        self
+    }
+
+    /// Targets to query for `servers`.  Without expansion this is `servers`
+    /// unchanged.  A hostname that fails to resolve keeps its previous
+    /// expansion, or is queried by name if it never resolved; an address
+    /// shared by two entries is queried once.
+    pub async fn targets(&self, servers: &[String]) -> Vec<QueryTarget> {
+        if !self.enabled {
+            return servers.iter().map(|s| QueryTarget::unexpanded(s)).collect();
+        }
+        let previous = match &*self.cache.lock() {
+            Some((at, targets)) if at.elapsed() < self.refresh => return targets.clone(),
+            Some((_, targets)) => targets.clone(),
+            None => Vec::new(),
+        };
+
+        let lookups = join_all(servers.iter().map(|s| self.resolve(s))).await;
+        let mut seen = HashSet::new();
+        let mut targets = Vec::new();
+        for (server, lookup) in servers.iter().zip(lookups) {
+            let expanded: Vec<QueryTarget> = match lookup {
+                Some(addrs) if !addrs.is_empty() => addrs
+                    .into_iter()
+                    .map(|addr| QueryTarget {
+                        address: addr.to_string(),
+                        origin: server.clone(),
+                    })
+                    .collect(),
+                Some(_) => {
+                    let kept: Vec<QueryTarget> = previous
+                        .iter()
+                        .filter(|t| &t.origin == server)
+                        .cloned()
+                        .collect();
+                    warn!(
+                        server = %server,
+                        kept = kept.len(),
+                        "NTP pool re-resolution failed; keeping previous addresses"
+                    );
+                    if kept.is_empty() {
+                        vec![QueryTarget::unexpanded(server)]
+                    } else {
+                        kept
+                    }
+                }
+                None => vec![QueryTarget::unexpanded(server)],
+            };
+            targets.extend(
+                expanded
+                    .into_iter()
+                    .filter(|t| seen.insert(t.address.clone())),
+            );
+        }
+
+        info!(
+            configured = servers.len(),
+            targets = targets.len(),
+            "Resolved NTP pool hostnames"
+        );
+        *self.cache.lock() = Some((Instant::now(), targets.clone()));
+        targets
+    }
+
+    /// Up to `max_addresses` addresses of the configured family for
+    /// `server`; `None` for entries that
+    /// are never expanded (IP literals, reference clocks, pinned servers and
+    /// `nts://` servers, whose cookies are bound to the NTS-KE host).  An empty list means resolution failed.
+    async fn resolve(&self, server: &str) -> Option<Vec<SocketAddr>> {
+        if server.starts_with(NTS_SCHEME)
+            || self.pinned.contains(server)
+            || is_refclock(server)
+            || server.parse::<SocketAddr>().is_ok()
+        {
+            return None;
+        }
+        let addrs =
+            match tokio::time::timeout(RESOLVE_TIMEOUT, tokio::net::lookup_host(server)).await {
+                Ok(Ok(addrs)) => addrs,
+                Ok(Err(e)) => {
+                    debug!(server = %server, error = %e, "NTP pool lookup failed");
+                    return Some(Vec::new());
+                }
+                Err(_) => {
+                    debug!(server = %server, "NTP pool lookup timed out");
+                    return Some(Vec::new());
+                }
+            };
+        let mut unique = Vec::new();
+        for addr in addrs.filter(|addr| self.family.matches(addr)) {
+            if !unique.contains(&addr) {
+                unique.push(addr);
+            }
+            if unique.len() == self.max_addresses {
+                break;
+            }
+        }
+        Some(unique)
+    }
+}
+
+/// `selection` with every expanded address inheriting its origin's provider
+/// group and weight/priority, so a pool's addresses still count as one
+/// provider and keep the entry's `NTP_SERVERS` options.
+pub fn selection_for<'a>(
+    selection: &'a SelectionConfig,
+    targets: &[QueryTarget],
+) -> Cow<'a, SelectionConfig> {
+    if !targets.iter().any(QueryTarget::is_expanded) {
+        return Cow::Borrowed(selection);
+    }
+    let mut expanded = selection.clone();
+    for target in targets.iter().filter(|t| t.is_expanded()) {
+        expanded.provider_groups.insert(
+            target.address.clone(),
+            provider_group(&target.origin, &selection.provider_groups),
+        );
+        if let Some(options) = selection.server_options.get(&target.origin) {
+            expanded.server_options.insert(
+                target.address.clone(),
+                ServerConfig {
+                    address: target.address.clone(),
+                    ..options.clone()
+                },
+            );
+        }
+    }
+    Cow::Owned(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver(expand_pools: bool) -> PoolResolver {
+        PoolResolver::new(
+            &QueryConfig {
+                expand_pools,
+                ..QueryConfig::default()
+            },
+            AddressFamily::Any,
+        )
+    }
+
+    #[tokio::test]
+    async fn disabled_resolver_returns_servers_unchanged() {
+        let servers = vec!["localhost:123".to_string()];
+        let targets = resolver(false).targets(&servers).await;
+        assert_eq!(targets, vec![QueryTarget::unexpanded("localhost:123")]);
+    }
+
+    #[tokio::test]
+    async fn expands_hostnames_but_not_literals_or_nts() {
+        let servers = vec![
+            "localhost:123".to_string(),
+            "192.0.2.1:123".to_string(),
+            "nts://time.cloudflare.com".to_string(),
+        ];
+        let targets = resolver(true).targets(&servers).await;
+
+        let local: Vec<&QueryTarget> = targets
+            .iter()
+            .filter(|t| t.origin == "localhost:123")
+            .collect();
+        assert!(!local.is_empty());
+        for t in &local {
+            let addr: SocketAddr = t.address.parse().expect("expanded to an address");
+            assert!(addr.ip().is_loopback());
+            assert_eq!(addr.port(), 123);
+        }
+        assert!(targets.contains(&QueryTarget::unexpanded("192.0.2.1:123")));
+        assert!(targets.contains(&QueryTarget::unexpanded("nts://time.cloudflare.com")));
+    }
+
+    #[tokio::test]
+    async fn pinned_hostname_is_not_expanded() {
+        let servers = vec!["localhost:123".to_string()];
+        let targets = resolver(true)
+            .pinning(servers.clone())
+            .targets(&servers)
+            .await;
+        assert_eq!(targets, vec![QueryTarget::unexpanded("localhost:123")]);
+    }
+
+    #[tokio::test]
+    async fn unresolvable_host_is_queried_by_name() {
+        let servers = vec!["no-such-host.invalid:123".to_string()];
+        let targets = resolver(true).targets(&servers).await;
+        assert_eq!(
+            targets,
+            vec![QueryTarget::unexpanded("no-such-host.invalid:123")]
+        );
+    }
+
+    #[test]
+    fn expanded_addresses_inherit_provider_group_and_options() {
+        let mut selection = SelectionConfig::default();
+        selection.server_options.insert(
+            "pool.ntp.org:123".to_string(),
+            ServerConfig {
+                weight: 2.0,
+                priority: 1,
+                ..ServerConfig::new("pool.ntp.org:123")
+            },
+        );
+        let targets = vec![
+            QueryTarget {
+                address: "192.0.2.1:123".to_string(),
+                origin: "pool.ntp.org:123".to_string(),
+            },
+            QueryTarget::unexpanded("time.google.com:123"),
+        ];
+        let expanded = selection_for(&selection, &targets);
+        assert_eq!(
+            provider_group("192.0.2.1:123", &expanded.provider_groups),
+            "ntp.org"
+        );
+        assert_eq!(expanded.server_weight("192.0.2.1:123"), 2.0);
+        assert_eq!(expanded.server_priority("192.0.2.1:123"), 1);
+
+        let unexpanded = [QueryTarget::unexpanded("time.google.com:123")];
+        assert!(matches!(
+            selection_for(&selection, &unexpanded),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,406 @@
+//! Roughtime client for cross-checking the NTP-derived time.
+//!
+//! Roughtime replies carry a midpoint and radius signed by the server, so a
+//! client can prove what time the server claimed.  Each configured server
+//! (`ROUGHTIME_SERVERS`) is queried periodically with Google-Roughtime:
+//!
+//! 1. The request is a 1024-byte tagged message carrying a random 64-byte
+//!    nonce (`NONC`) and padding.
+//! 2. The reply's certificate (`CERT`) must carry a delegation (`DELE`)
+//!    signed by the configured long-term key.  The signed response (`SREP`)
+//!    must be signed by the delegated key, and its Merkle root must commit
+//!    to our nonce through `PATH`/`INDX`.
+//! 3. The midpoint must fall inside the delegation's validity window.
+//!
+//! The resulting bound is not used to set the time; it is compared against
+//! [`TimeBase`](crate::timebase::TimeBase) and disagreement is flagged.
+
+use anyhow::{Context, Result, bail};
+use ring::digest::{SHA512, digest};
+use ring::signature::{ED25519, UnparsedPublicKey};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::net::UdpSocket;
+
+use super::client::system_time_unix_ms;
+use crate::config::RoughtimeServerConfig;
+
+/// Requests are padded to this size so replies cannot amplify traffic.
+const REQUEST_SIZE: usize = 1024;
+
+const NONCE_SIZE: usize = 64;
+const HASH_SIZE: usize = 64;
+const SIGNATURE_SIZE: usize = 64;
+
+const DELEGATION_CONTEXT: &[u8] = b"RoughTime v1 delegation signature--\0";
+const RESPONSE_CONTEXT: &[u8] = b"RoughTime v1 response signature\0";
+
+const fn tag(name: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*name)
+}
+
+const TAG_SIG: u32 = tag(b"SIG\0");
+const TAG_NONC: u32 = tag(b"NONC");
+const TAG_PAD: u32 = tag(b"PAD\xff");
+const TAG_SREP: u32 = tag(b"SREP");
+const TAG_CERT: u32 = tag(b"CERT");
+const TAG_PATH: u32 = tag(b"PATH");
+const TAG_INDX: u32 = tag(b"INDX");
+const TAG_ROOT: u32 = tag(b"ROOT");
+const TAG_MIDP: u32 = tag(b"MIDP");
+const TAG_RADI: u32 = tag(b"RADI");
+const TAG_DELE: u32 = tag(b"DELE");
+const TAG_PUBK: u32 = tag(b"PUBK");
+const TAG_MINT: u32 = tag(b"MINT");
+const TAG_MAXT: u32 = tag(b"MAXT");
+
+/// A verified Roughtime reply.
+#[derive(Debug, Clone)]
+pub struct RoughtimeSample {
+    pub server: String,
+    /// Signed midpoint (unix ms).
+    pub midpoint_ms: i64,
+    /// Signed radius: true time was within `midpoint ± radius` (ms).
+    pub radius_ms: i64,
+    /// Round trip of the exchange.
+    pub rtt: Duration,
+    /// System clock (unix ms) and monotonic instant when the reply arrived.
+    pub received_unix_ms: i64,
+    pub received_instant: Instant,
+}
+
+impl RoughtimeSample {
+    /// How far `time_ms`, read when the reply arrived, lies outside the
+    /// signed bound widened by the round trip; 0 when it agrees.
+    pub fn disagreement_ms(&self, time_ms: i64) -> u64 {
+        let slack = self.radius_ms + self.rtt.as_millis() as i64;
+        (time_ms - self.midpoint_ms)
+            .unsigned_abs()
+            .saturating_sub(slack as u64)
+    }
+}
+
+/// A parsed Roughtime message: `(tag, value)` pairs in wire order.
+struct Message<'a> {
+    fields: Vec<(u32, &'a [u8])>,
+}
+
+impl<'a> Message<'a> {
+    fn parse(buf: &'a [u8]) -> Result<Self> {
+        let word = |i: usize| -> Result<u32> {
+            buf.get(i * 4..i * 4 + 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap_or_default()))
+                .context("Roughtime message truncated")
+        };
+        let count = word(0)? as usize;
+        if count == 0 || count > 64 {
+            bail!("Roughtime message has {count} tags");
+        }
+        let header = 4 * (2 * count);
+        if buf.len() < header || !buf.len().is_multiple_of(4) {
+            bail!("Roughtime message truncated");
+        }
+        let values = &buf[header..];
+        let mut fields = Vec::with_capacity(count);
+        let mut start = 0;
+        for i in 0..count {
+            let end = if i + 1 < count {
+                word(1 + i)? as usize
+            } else {
+                values.len()
+            };
+            if end < start || end > values.len() || !end.is_multiple_of(4) {
+                bail!("Roughtime message has invalid offsets");
+            }
+            let tag = word(count + i)?;
+            if fields.last().is_some_and(|(prev, _)| *prev >= tag) {
+                bail!("Roughtime message tags are not sorted");
+            }
+            fields.push((tag, &values[start..end]));
+            start = end;
+        }
+        Ok(Self { fields })
+    }
+
+    fn get(&self, tag: u32) -> Result<&'a [u8]> {
+        self.fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, v)| *v)
+            .with_context(|| {
+                format!(
+                    "Roughtime message lacks {}",
+                    String::from_utf8_lossy(&tag.to_le_bytes())
+                )
+            })
+    }
+
+    fn get_u64(&self, tag: u32) -> Result<u64> {
+        let v: [u8; 8] = self.get(tag)?.try_into().context("Roughtime field size")?;
+        Ok(u64::from_le_bytes(v))
+    }
+
+    fn get_u32(&self, tag: u32) -> Result<u32> {
+        let v: [u8; 4] = self.get(tag)?.try_into().context("Roughtime field size")?;
+        Ok(u32::from_le_bytes(v))
+    }
+}
+
+/// Encode `(tag, value)` pairs, which must be sorted by tag and 4-aligned.
+fn encode_message(fields: &[(u32, &[u8])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+    let mut offset = 0;
+    for (_, value) in &fields[..fields.len() - 1] {
+        offset += value.len() as u32;
+        out.extend_from_slice(&offset.to_le_bytes());
+    }
+    for (tag, _) in fields {
+        out.extend_from_slice(&tag.to_le_bytes());
+    }
+    for (_, value) in fields {
+        out.extend_from_slice(value);
+    }
+    out
+}
+
+fn encode_request(nonce: &[u8; NONCE_SIZE]) -> Vec<u8> {
+    // Header (count, one offset, two tags: 16 bytes) + nonce + padding.
+    let padding = vec![0u8; REQUEST_SIZE - 16 - NONCE_SIZE];
+    encode_message(&[(TAG_NONC, nonce), (TAG_PAD, &padding)])
+}
+
+fn sha512(parts: &[&[u8]]) -> [u8; HASH_SIZE] {
+    let joined = parts.concat();
+    let mut out = [0u8; HASH_SIZE];
+    out.copy_from_slice(digest(&SHA512, &joined).as_ref());
+    out
+}
+
+fn verify(public_key: &[u8], context: &[u8], signed: &[u8], signature: &[u8]) -> Result<()> {
+    if signature.len() != SIGNATURE_SIZE {
+        bail!("Roughtime signature has wrong size");
+    }
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(&[context, signed].concat(), signature)
+        .map_err(|_| anyhow::anyhow!("Roughtime signature verification failed"))
+}
+
+/// Verify a reply to `nonce` against `public_key`, returning the signed
+/// `(midpoint, radius)` in microseconds.
+fn verify_response(
+    reply: &[u8],
+    nonce: &[u8; NONCE_SIZE],
+    public_key: &[u8; 32],
+) -> Result<(u64, u32)> {
+    let msg = Message::parse(reply)?;
+    let cert = Message::parse(msg.get(TAG_CERT)?)?;
+    let dele_bytes = cert.get(TAG_DELE)?;
+    verify(
+        public_key,
+        DELEGATION_CONTEXT,
+        dele_bytes,
+        cert.get(TAG_SIG)?,
+    )
+    .context("Roughtime delegation not signed by the configured key")?;
+    let dele = Message::parse(dele_bytes)?;
+
+    let srep_bytes = msg.get(TAG_SREP)?;
+    verify(
+        dele.get(TAG_PUBK)?,
+        RESPONSE_CONTEXT,
+        srep_bytes,
+        msg.get(TAG_SIG)?,
+    )
+    .context("Roughtime response not signed by the delegated key")?;
+    let srep = Message::parse(srep_bytes)?;
+
+    let path = msg.get(TAG_PATH)?;
+    if !path.len().is_multiple_of(HASH_SIZE) {
+        bail!("Roughtime PATH has invalid length");
+    }
+    let mut index = msg.get_u32(TAG_INDX)?;
+    let mut hash = sha512(&[&[0x00], nonce]);
+    for node in path.chunks(HASH_SIZE) {
+        hash = if index & 1 == 0 {
+            sha512(&[&[0x01], &hash, node])
+        } else {
+            sha512(&[&[0x01], node, &hash])
+        };
+        index >>= 1;
+    }
+    if index != 0 || srep.get(TAG_ROOT)? != hash {
+        bail!("Roughtime Merkle proof does not cover our nonce");
+    }
+
+    let midpoint = srep.get_u64(TAG_MIDP)?;
+    let radius = srep.get_u32(TAG_RADI)?;
+    if midpoint < dele.get_u64(TAG_MINT)? || midpoint > dele.get_u64(TAG_MAXT)? {
+        bail!("Roughtime midpoint outside the delegation's validity");
+    }
+    Ok((midpoint, radius))
+}
+
+/// Query one Roughtime server and verify its reply.
+pub async fn query(server: &RoughtimeServerConfig, timeout: Duration) -> Result<RoughtimeSample> {
+    let nonce: [u8; NONCE_SIZE] = std::array::from_fn(|_| rand::random());
+    let request = encode_request(&nonce);
+
+    let addr = tokio::net::lookup_host(&server.address)
+        .await
+        .with_context(|| format!("Failed to resolve {}", server.address))?
+        .next()
+        .with_context(|| format!("No addresses for {}", server.address))?;
+    let bind = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.connect(addr).await?;
+
+    let sent = Instant::now();
+    socket
+        .send(&request)
+        .await
+        .context("Failed to send Roughtime request")?;
+    let mut buf = vec![0u8; 4096];
+    let n = tokio::time::timeout(timeout, socket.recv(&mut buf))
+        .await
+        .context("Roughtime query timed out")?
+        .context("Failed to receive Roughtime reply")?;
+    let received_instant = Instant::now();
+    let received_unix_ms = system_time_unix_ms(SystemTime::now());
+
+    let (midpoint_us, radius_us) = verify_response(&buf[..n], &nonce, &server.public_key)
+        .with_context(|| format!("Invalid Roughtime reply from {}", server.address))?;
+    Ok(RoughtimeSample {
+        server: server.address.clone(),
+        midpoint_ms: (midpoint_us / 1000) as i64,
+        radius_ms: radius_us.div_ceil(1000) as i64,
+        rtt: received_instant.duration_since(sent),
+        received_unix_ms,
+        received_instant,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    fn key_pair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    /// A single-request reply, as a Roughtime server would build it.
+    fn sign_reply(long_term: &Ed25519KeyPair, nonce: &[u8], midpoint_us: u64) -> Vec<u8> {
+        let online = key_pair();
+        let dele = encode_message(&[
+            (TAG_PUBK, online.public_key().as_ref()),
+            (TAG_MINT, &0u64.to_le_bytes()),
+            (TAG_MAXT, &u64::MAX.to_le_bytes()),
+        ]);
+        let dele_sig = long_term.sign(&[DELEGATION_CONTEXT, &dele].concat());
+        let cert = encode_message(&[(TAG_SIG, dele_sig.as_ref()), (TAG_DELE, &dele)]);
+
+        let root = sha512(&[&[0x00], nonce]);
+        let srep = encode_message(&[
+            (TAG_RADI, &1_000_000u32.to_le_bytes()),
+            (TAG_MIDP, &midpoint_us.to_le_bytes()),
+            (TAG_ROOT, &root),
+        ]);
+        let srep_sig = online.sign(&[RESPONSE_CONTEXT, &srep].concat());
+        encode_message(&[
+            (TAG_SIG, srep_sig.as_ref()),
+            (TAG_PATH, &[]),
+            (TAG_SREP, &srep),
+            (TAG_CERT, &cert),
+            (TAG_INDX, &0u32.to_le_bytes()),
+        ])
+    }
+
+    fn public_key(pair: &Ed25519KeyPair) -> [u8; 32] {
+        pair.public_key().as_ref().try_into().unwrap()
+    }
+
+    #[test]
+    fn request_is_padded_and_parses() {
+        let nonce = [7u8; NONCE_SIZE];
+        let request = encode_request(&nonce);
+        assert_eq!(request.len(), REQUEST_SIZE);
+        let msg = Message::parse(&request).unwrap();
+        assert_eq!(msg.get(TAG_NONC).unwrap(), &nonce);
+    }
+
+    #[test]
+    fn verifies_signed_reply() {
+        let long_term = key_pair();
+        let nonce = [1u8; NONCE_SIZE];
+        let reply = sign_reply(&long_term, &nonce, 1_700_000_000_000_000);
+        let (midpoint, radius) = verify_response(&reply, &nonce, &public_key(&long_term)).unwrap();
+        assert_eq!(midpoint, 1_700_000_000_000_000);
+        assert_eq!(radius, 1_000_000);
+    }
+
+    #[test]
+    fn rejects_wrong_key_nonce_or_tampering() {
+        let long_term = key_pair();
+        let nonce = [1u8; NONCE_SIZE];
+        let reply = sign_reply(&long_term, &nonce, 1_700_000_000_000_000);
+
+        let other = key_pair();
+        assert!(verify_response(&reply, &nonce, &public_key(&other)).is_err());
+        assert!(verify_response(&reply, &[2u8; NONCE_SIZE], &public_key(&long_term)).is_err());
+
+        // Flip a bit of the signed midpoint.
+        let mut tampered = reply.clone();
+        let midp = 1_700_000_000_000_000u64.to_le_bytes();
+        let at = tampered.windows(8).position(|w| w == midp).unwrap();
+        tampered[at] ^= 1;
+        assert!(verify_response(&tampered, &nonce, &public_key(&long_term)).is_err());
+    }
+
+    #[test]
+    fn disagreement_allows_radius_and_round_trip() {
+        let sample = RoughtimeSample {
+            server: "rt:2002".into(),
+            midpoint_ms: 1_000_000,
+            radius_ms: 1000,
+            rtt: Duration::from_millis(50),
+            received_unix_ms: 1_000_000,
+            received_instant: Instant::now(),
+        };
+        assert_eq!(sample.disagreement_ms(1_000_900), 0);
+        assert_eq!(sample.disagreement_ms(999_000), 0);
+        assert_eq!(sample.disagreement_ms(1_003_050), 2000);
+        assert_eq!(sample.disagreement_ms(996_950), 2000);
+    }
+
+    #[tokio::test]
+    async fn queries_server_over_udp() {
+        let long_term = key_pair();
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = RoughtimeServerConfig {
+            address: server.local_addr().unwrap().to_string(),
+            public_key: public_key(&long_term),
+        };
+        tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            let (n, peer) = server.recv_from(&mut buf).await.unwrap();
+            assert_eq!(n, REQUEST_SIZE);
+            let nonce = Message::parse(&buf[..n])
+                .unwrap()
+                .get(TAG_NONC)
+                .unwrap()
+                .to_vec();
+            let reply = sign_reply(&long_term, &nonce, 1_700_000_000_123_456);
+            server.send_to(&reply, peer).await.unwrap();
+        });
+
+        let sample = query(&config, Duration::from_secs(2)).await.unwrap();
+        assert_eq!(sample.midpoint_ms, 1_700_000_000_123);
+        assert_eq!(sample.radius_ms, 1000);
+    }
+}
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
-use crate::config::SelectionConfig;
+use super::stats::SmoothedStats;
+use crate::config::{SelectionConfig, SelectionStrategy};
 
 /// Whether T2/T3 (and root fields) were parsed directly from the NTP
 /// packet bytes, or algebraically reconstructed from offset/delay.
@@ -200,6 +201,77 @@ pub struct SelectionDiagnostics {
     pub candidate_lambdas: Vec<(String, f64)>,
     /// P1F-12 interval-intersection diagnostics.
     pub intersection: IntersectionDiagnostics,
+    /// Per-candidate scoring breakdown, for `/debug/selection`.
+    #[serde(skip)]
+    pub candidates: Vec<CandidateScore>,
+    /// Sticky-server decision that followed a successful selection, for
+    /// `/debug/selection`.  `None` when selection failed.
+    #[serde(skip)]
+    pub sticky: Option<StickyDecision>,
+}
+
+/// How a candidate (a server that passed the hard gates) fared in selection.
+#[derive(Debug, Clone, Copy, serde::Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateStatus {
+    /// Within `max_offset_skew_ms` of the weighted median; counts towards quorum.
+    Inlier,
+    /// Further than `max_offset_skew_ms` from the weighted median.
+    Outlier,
+    /// Eliminated by the interval-intersection filter.
+    Falseticker,
+    /// Selection failed before this candidate was classified.
+    Unclassified,
+}
+
+/// Scoring breakdown of one candidate in a selection round.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CandidateScore {
+    pub server: String,
+    pub offset_ms: i64,
+    pub rtt_ms: u64,
+    /// Root distance (ms) used for gating, weighting and ranking.
+    pub lambda_ms: f64,
+    /// Weighted-median weight, `server_weight / (lambda + 1)`.
+    pub weight: f64,
+    /// Configured priority (`;priority=` in `NTP_SERVERS`); higher wins.
+    pub priority: u8,
+    /// |offset − weighted median| (ms); `None` when no median was computed
+    /// or the candidate was excluded before the median step.
+    pub distance_from_median_ms: Option<f64>,
+    pub status: CandidateStatus,
+}
+
+/// Why the sticky-server step kept or replaced the current server.
+#[derive(Debug, Clone, Copy, serde::Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StickyReason {
+    /// No server had been selected before.
+    InitialSelection,
+    /// The current server is not among this round's agreers.
+    CurrentNotAgreer,
+    /// The current server is also this round's best agreer.
+    CurrentIsBest,
+    /// The best agreer's RTT beats the current server's by at least the threshold.
+    SignificantlyFaster,
+    /// The best agreer is better, but not by enough to switch.
+    BelowSwitchThreshold,
+}
+
+/// Outcome of the sticky-server step after a successful selection.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StickyDecision {
+    /// Server in use before this round.
+    pub previous_server: Option<String>,
+    /// Best agreer chosen by selection (lowest λ, RTT tiebreaker).
+    pub best_server: String,
+    /// Server actually used for this round.
+    pub selected_server: String,
+    pub switched: bool,
+    pub reason: StickyReason,
+    /// Current server's RTT minus the best agreer's (ms), when both are agreers.
+    pub rtt_advantage_ms: Option<i64>,
+    pub switch_threshold_ms: i64,
 }
 
 // ── SelectionOutput ───────────────────────────────────────────────────────────
@@ -208,6 +280,9 @@ pub struct SelectionOutput {
     pub selected: Option<NtpResult>,
     /// Agreers passed to `sticky_select`; empty when no quorum.
     pub agreers: Vec<NtpResult>,
+    /// Offset to serve in place of the selected server's own, for strategies
+    /// that combine several agreers (`offset_median`, `weighted`).
+    pub served_offset_ms: Option<i64>,
     pub diagnostics: SelectionDiagnostics,
 }
 
@@ -220,11 +295,20 @@ struct ScoredResult {
 }
 
 /// RFC 5905 §11.2 root-distance (lambda) in milliseconds.
-fn compute_lambda(r: &NtpResult, jitter_ms: f64) -> f64 {
+///
+/// With `smoothed` stats the delay term uses the larger of this sample's RTT
+/// and the smoothed RTT, and the jitter term the smoothed jitter, so a single
+/// fast reply does not lower a noisy server's distance.
+fn compute_lambda(r: &NtpResult, smoothed: Option<&SmoothedStats>) -> f64 {
     use super::protocol::precision_log2_to_ms;
     const PHI_MS_PER_MS: f64 = 15e-6; // 15 µs/s = 0.015 ms/s = 15e-6 ms/ms; multiply by age_ms
     let age_ms = r.instant.elapsed().as_millis() as f64;
-    let delay_half_ms = r.rtt.as_millis() as f64 / 2.0;
+    let rtt_ms = r.rtt.as_millis() as f64;
+    let (rtt_ms, jitter_ms) = match smoothed {
+        Some(s) => (rtt_ms.max(s.rtt_ms), s.jitter_ms),
+        None => (rtt_ms, 0.0),
+    };
+    let delay_half_ms = rtt_ms / 2.0;
     let precision_ms = precision_log2_to_ms(r.precision_log2).abs();
     (r.root_delay_ms as f64) / 2.0
         + (r.root_dispersion_ms as f64)
@@ -237,10 +321,13 @@ fn compute_lambda(r: &NtpResult, jitter_ms: f64) -> f64 {
 /// Extract provider group from a server address.
 /// Uses the last two DNS labels of the hostname, or the IP literal if it has no DNS.
 /// Optional `overrides` map takes precedence.
-fn provider_group(server: &str, overrides: &HashMap<String, String>) -> String {
+pub(crate) fn provider_group(server: &str, overrides: &HashMap<String, String>) -> String {
     if let Some(g) = overrides.get(server) {
         return g.clone();
     }
+    let server = server
+        .strip_prefix(super::nts::NTS_SCHEME)
+        .unwrap_or(server);
     let host = server.split(':').next().unwrap_or(server);
     let parts: Vec<&str> = host.split('.').collect();
     if parts.len() < 2 {
@@ -446,22 +533,55 @@ fn marzullo_filter(candidates: &[ScoredResult], min_quorum: usize) -> MarzulloRe
 
 // ── WeightedMedianSelector ────────────────────────────────────────────────────
 
+/// Unweighted median offset of `sorted` (ascending by offset); the mean of
+/// the two middle offsets when the count is even.
+fn plain_median(sorted: &[ScoredResult]) -> f64 {
+    let n = sorted.len();
+    if n == 0 {
+        return 0.0;
+    }
+    let mid = |i: usize| sorted[i].result.offset_ms as f64;
+    if n % 2 == 1 {
+        mid(n / 2)
+    } else {
+        (mid(n / 2 - 1) + mid(n / 2)) / 2.0
+    }
+}
+
+/// Mean of agreer offsets weighted by server weight / (RTT + 1 ms).
+fn rtt_weighted_mean(agreers: &[&ScoredResult], config: &SelectionConfig) -> f64 {
+    let (sum, total_weight) = agreers.iter().fold((0.0, 0.0), |(sum, tw), a| {
+        let w =
+            config.server_weight(&a.result.server) / (a.result.rtt.as_secs_f64() * 1000.0 + 1.0);
+        (sum + w * a.result.offset_ms as f64, tw + w)
+    });
+    if total_weight > 0.0 {
+        sum / total_weight
+    } else {
+        0.0
+    }
+}
+
 pub struct WeightedMedianSelector;
 
 impl WeightedMedianSelector {
     /// Select the best NTP server using uncertainty-aware weighted median + quorum.
     ///
     /// Algorithm:
-    /// 1. Compute λ (root distance) for each result; apply hard-rejection gates.
-    /// 2. Weight = 1 / (λ + 1) (low-uncertainty servers carry more weight).
-    /// 3. Weighted median offset → consensus.
+    /// 1. Compute λ (root distance) for each result from its sample and the
+    ///    server's smoothed RTT/jitter; apply hard-rejection gates.
+    /// 2. Weight = server weight / (λ + 1) (low-uncertainty servers carry more weight).
+    /// 3. Weighted median offset → consensus (plain median for `offset_median`).
     /// 4. Agreers: servers within `max_offset_skew_ms` of the consensus.
     /// 5. Quorum check: agreers.len() ≥ `min_quorum`.
     /// 6. Provider-group cap: if one group > `provider_group_max_fraction`, flag `single_provider`.
-    /// 7. Select best agreer (lowest λ, RTT as tiebreaker).
+    /// 7. Select best agreer among the highest-priority ones (lowest λ, RTT
+    ///    as tiebreaker; lowest stratum first for `stratum_first`).
+    /// 8. `offset_median` / `weighted`: set `served_offset_ms` to the median /
+    ///    RTT-weighted mean of agreer offsets.
     pub fn select(
         results: Vec<NtpResult>,
-        jitter_by_server: &HashMap<String, u64>,
+        smoothed_by_server: &HashMap<String, SmoothedStats>,
         config: &SelectionConfig,
     ) -> SelectionOutput {
         use super::protocol::LI_ALARM_UNSYNCHRONIZED;
@@ -473,7 +593,9 @@ impl WeightedMedianSelector {
 
         for r in results {
             // ── Hard gates ────────────────────────────────────────────────────
-            if r.stratum == 0 {
+            // Stratum 0 from a network server is a kiss code; from a
+            // reference clock it is the clock itself.
+            if r.stratum == 0 && !super::refclock::is_refclock(&r.server) {
                 rejected.push(RejectedSource {
                     server: r.server.clone(),
                     reason: "stratum_zero",
@@ -494,6 +616,13 @@ impl WeightedMedianSelector {
                 });
                 continue;
             }
+            if r.root_dispersion_ms > config.max_root_dispersion_ms {
+                rejected.push(RejectedSource {
+                    server: r.server.clone(),
+                    reason: "root_dispersion_too_high",
+                });
+                continue;
+            }
             let age_secs = r.instant.elapsed().as_secs();
             if age_secs > config.max_sample_age_secs {
                 rejected.push(RejectedSource {
@@ -503,8 +632,7 @@ impl WeightedMedianSelector {
                 continue;
             }
 
-            let jitter_ms = *jitter_by_server.get(&r.server).unwrap_or(&0) as f64;
-            let lambda_ms = compute_lambda(&r, jitter_ms);
+            let lambda_ms = compute_lambda(&r, smoothed_by_server.get(&r.server));
 
             if lambda_ms > config.max_root_distance_ms {
                 rejected.push(RejectedSource {
@@ -514,7 +642,7 @@ impl WeightedMedianSelector {
                 continue;
             }
 
-            let weight = 1.0 / (lambda_ms + 1.0);
+            let weight = config.server_weight(&r.server) / (lambda_ms + 1.0);
             candidates.push(ScoredResult {
                 result: r,
                 lambda_ms,
@@ -523,6 +651,19 @@ impl WeightedMedianSelector {
         }
 
         let candidate_count = candidates.len();
+        let mut explained: Vec<CandidateScore> = candidates
+            .iter()
+            .map(|c| CandidateScore {
+                server: c.result.server.clone(),
+                offset_ms: c.result.offset_ms,
+                rtt_ms: c.result.rtt.as_millis() as u64,
+                lambda_ms: c.lambda_ms,
+                weight: c.weight,
+                priority: config.server_priority(&c.result.server),
+                distance_from_median_ms: None,
+                status: CandidateStatus::Unclassified,
+            })
+            .collect();
 
         if candidates.is_empty() {
             warn!(
@@ -533,6 +674,7 @@ impl WeightedMedianSelector {
             return SelectionOutput {
                 selected: None,
                 agreers: Vec::new(),
+                served_offset_ms: None,
                 diagnostics: SelectionDiagnostics {
                     quorum_size: 0,
                     candidate_count: 0,
@@ -546,6 +688,8 @@ impl WeightedMedianSelector {
                     min_quorum: config.min_quorum,
                     weighted_median_offset_ms: None,
                     candidate_lambdas: vec![],
+                    candidates: explained,
+                    sticky: None,
                     intersection: IntersectionDiagnostics::disabled(),
                 },
             };
@@ -584,6 +728,7 @@ impl WeightedMedianSelector {
                     return SelectionOutput {
                         selected: None,
                         agreers: vec![],
+                        served_offset_ms: None,
                         diagnostics: SelectionDiagnostics {
                             quorum_size: 0,
                             candidate_count,
@@ -597,6 +742,8 @@ impl WeightedMedianSelector {
                             min_quorum: config.min_quorum,
                             weighted_median_offset_ms: None,
                             candidate_lambdas,
+                            candidates: explained,
+                            sticky: None,
                             intersection: inter,
                         },
                     };
@@ -621,6 +768,7 @@ impl WeightedMedianSelector {
                     return SelectionOutput {
                         selected: None,
                         agreers: vec![],
+                        served_offset_ms: None,
                         diagnostics: SelectionDiagnostics {
                             quorum_size: 0,
                             candidate_count,
@@ -634,6 +782,8 @@ impl WeightedMedianSelector {
                             min_quorum: config.min_quorum,
                             weighted_median_offset_ms: None,
                             candidate_lambdas,
+                            candidates: explained,
+                            sticky: None,
                             intersection: inter,
                         },
                     };
@@ -644,6 +794,11 @@ impl WeightedMedianSelector {
                         min_quorum = config.min_quorum,
                         "NTP selection: intersection found but truechimers < min_quorum"
                     );
+                    for (i, c) in explained.iter_mut().enumerate() {
+                        if !mr.truechimer_indices.contains(&i) {
+                            c.status = CandidateStatus::Falseticker;
+                        }
+                    }
                     let inter = IntersectionDiagnostics {
                         enabled: true,
                         state: IntersectionState::InsufficientQuorum,
@@ -659,6 +814,7 @@ impl WeightedMedianSelector {
                     return SelectionOutput {
                         selected: None,
                         agreers: vec![],
+                        served_offset_ms: None,
                         diagnostics: SelectionDiagnostics {
                             quorum_size: mr.truechimer_indices.len(),
                             candidate_count,
@@ -672,6 +828,8 @@ impl WeightedMedianSelector {
                             min_quorum: config.min_quorum,
                             weighted_median_offset_ms: None,
                             candidate_lambdas,
+                            candidates: explained,
+                            sticky: None,
                             intersection: inter,
                         },
                     };
@@ -692,6 +850,11 @@ impl WeightedMedianSelector {
                     };
                     let tc_set: std::collections::HashSet<usize> =
                         mr.truechimer_indices.into_iter().collect();
+                    for (i, c) in explained.iter_mut().enumerate() {
+                        if !tc_set.contains(&i) {
+                            c.status = CandidateStatus::Falseticker;
+                        }
+                    }
                     let filtered: Vec<ScoredResult> = candidates
                         .into_iter()
                         .enumerate()
@@ -718,20 +881,25 @@ impl WeightedMedianSelector {
         // Runs on truechimers only (when intersection enabled) or all candidates.
         let mut active = active_candidates;
         active.sort_by_key(|a| a.result.offset_ms);
-        let total_weight: f64 = active.iter().map(|c| c.weight).sum();
-        let half = total_weight / 2.0;
-        let mut cumulative = 0.0;
-        let mut wm_offset = active
-            .last()
-            .map(|c| c.result.offset_ms as f64)
-            .unwrap_or(0.0);
-        for c in &active {
-            cumulative += c.weight;
-            if cumulative >= half {
-                wm_offset = c.result.offset_ms as f64;
-                break;
+        let wm_offset = if config.strategy == SelectionStrategy::OffsetMedian {
+            plain_median(&active)
+        } else {
+            let total_weight: f64 = active.iter().map(|c| c.weight).sum();
+            let half = total_weight / 2.0;
+            let mut cumulative = 0.0;
+            let mut wm_offset = active
+                .last()
+                .map(|c| c.result.offset_ms as f64)
+                .unwrap_or(0.0);
+            for c in &active {
+                cumulative += c.weight;
+                if cumulative >= half {
+                    wm_offset = c.result.offset_ms as f64;
+                    break;
+                }
             }
-        }
+            wm_offset
+        };
 
         // ── Agreers ───────────────────────────────────────────────────────────
         let skew = config.max_offset_skew_ms as f64;
@@ -742,6 +910,19 @@ impl WeightedMedianSelector {
 
         let quorum_size = agreers.len();
 
+        for c in explained
+            .iter_mut()
+            .filter(|c| c.status != CandidateStatus::Falseticker)
+        {
+            let distance = (c.offset_ms as f64 - wm_offset).abs();
+            c.distance_from_median_ms = Some(distance);
+            c.status = if distance <= skew {
+                CandidateStatus::Inlier
+            } else {
+                CandidateStatus::Outlier
+            };
+        }
+
         info!(
             candidates = candidate_count,
             agreers = quorum_size,
@@ -759,6 +940,7 @@ impl WeightedMedianSelector {
             return SelectionOutput {
                 selected: None,
                 agreers: Vec::new(),
+                served_offset_ms: None,
                 diagnostics: SelectionDiagnostics {
                     quorum_size,
                     candidate_count,
@@ -772,6 +954,8 @@ impl WeightedMedianSelector {
                     min_quorum: config.min_quorum,
                     weighted_median_offset_ms: Some(wm_offset),
                     candidate_lambdas,
+                    candidates: explained,
+                    sticky: None,
                     intersection: intersection_diag,
                 },
             };
@@ -787,17 +971,37 @@ impl WeightedMedianSelector {
         let single_provider =
             (max_group as f64) > (quorum_size as f64) * config.provider_group_max_fraction;
 
-        // ── Best agreer (lowest λ, RTT tiebreaker) ────────────────────────────
+        // ── Best agreer (priority, then lowest λ, RTT tiebreaker) ─────────────
+        let stratum_first = config.strategy == SelectionStrategy::StratumFirst;
         let best = agreers
             .iter()
             .min_by(|a, b| {
-                a.lambda_ms
-                    .partial_cmp(&b.lambda_ms)
-                    .unwrap_or(std::cmp::Ordering::Equal)
+                let by_priority = config
+                    .server_priority(&b.result.server)
+                    .cmp(&config.server_priority(&a.result.server));
+                let by_stratum = if stratum_first {
+                    a.result.stratum.cmp(&b.result.stratum)
+                } else {
+                    std::cmp::Ordering::Equal
+                };
+                by_priority
+                    .then(by_stratum)
+                    .then_with(|| {
+                        a.lambda_ms
+                            .partial_cmp(&b.lambda_ms)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
                     .then_with(|| a.result.rtt.cmp(&b.result.rtt))
             })
             .unwrap(); // safe: quorum_size >= min_quorum >= 1
 
+        // ── Served offset (combining strategies) ──────────────────────────────
+        let served_offset_ms = match config.strategy {
+            SelectionStrategy::OffsetMedian => Some(wm_offset.round() as i64),
+            SelectionStrategy::Weighted => Some(rtt_weighted_mean(&agreers, config).round() as i64),
+            SelectionStrategy::AccuracyFirst | SelectionStrategy::StratumFirst => None,
+        };
+
         // ── Combined uncertainty ──────────────────────────────────────────────
         // When intersection is enabled, the combined uncertainty is the larger of:
         // - the best truechimer's λ (point precision of the selected server)
@@ -820,6 +1024,8 @@ impl WeightedMedianSelector {
         info!(
             selected_server = %best.result.server,
             lambda_ms = best.lambda_ms,
+            strategy = ?config.strategy,
+            served_offset_ms,
             intersection_radius_ms = intersection_radius,
             quorum_size,
             single_provider,
@@ -830,6 +1036,7 @@ impl WeightedMedianSelector {
         SelectionOutput {
             selected: Some(best.result.clone()),
             agreers: agreer_results,
+            served_offset_ms,
             diagnostics: SelectionDiagnostics {
                 quorum_size,
                 candidate_count,
@@ -843,6 +1050,8 @@ impl WeightedMedianSelector {
                 min_quorum: config.min_quorum,
                 weighted_median_offset_ms: Some(wm_offset),
                 candidate_lambdas,
+                candidates: explained,
+                sticky: None,
                 intersection: intersection_diag,
             },
         }
@@ -859,10 +1068,13 @@ mod tests {
     /// P1-6 test config: interval selection OFF (tests weighted-median only).
     fn cfg(min_quorum: usize) -> SelectionConfig {
         SelectionConfig {
+            strategy: SelectionStrategy::AccuracyFirst,
+            server_options: HashMap::new(),
             max_stratum: 4,
             min_quorum,
             reject_leap_alarm: true,
             max_root_distance_ms: 500.0,
+            max_root_dispersion_ms: 1_000,
             max_sample_age_secs: 60,
             provider_group_max_fraction: 0.5,
             provider_groups: HashMap::new(),
@@ -1062,6 +1274,17 @@ mod tests {
         assert_eq!(out.diagnostics.rejected_sources[0].reason, "stratum_zero");
     }
 
+    #[test]
+    fn refclock_stratum_zero_passes_gates() {
+        let results = vec![
+            r_full("gpsd://", 0, 100, 0, 0, 0, 0),
+            r_full("b:123", 10, 100, 1, 0, 0, 0),
+        ];
+        let out = WeightedMedianSelector::select(results, &HashMap::new(), &cfg(1));
+        assert_eq!(out.diagnostics.rejected_count, 0);
+        assert_eq!(out.selected.unwrap().server, "gpsd://");
+    }
+
     #[test]
     fn stratum_too_high_hard_gated() {
         let results = vec![
@@ -1076,6 +1299,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn root_dispersion_too_high_hard_gated() {
+        let results = vec![
+            r_full("a:123", 10, 100, 2, 0, 0, 300), // dispersion 300 > 250 → rejected
+            r_full("b:123", 10, 100, 2, 0, 0, 5),
+        ];
+        let config = SelectionConfig {
+            max_root_dispersion_ms: 250,
+            ..cfg(1)
+        };
+        let out = WeightedMedianSelector::select(results, &HashMap::new(), &config);
+        assert_eq!(out.diagnostics.rejected_count, 1);
+        assert_eq!(
+            out.diagnostics.rejected_sources[0].reason,
+            "root_dispersion_too_high"
+        );
+        assert_eq!(out.selected.unwrap().server, "b:123");
+    }
+
     #[test]
     fn all_gated_returns_no_candidates() {
         let results = vec![
@@ -1194,13 +1436,54 @@ mod tests {
             NtpResult::for_testing("a:123", 0, Duration::from_millis(10), 100, now),
             NtpResult::for_testing("b:123", 0, Duration::from_millis(10), 100, now),
         ];
-        let jitter: HashMap<String, u64> = [("a:123".to_string(), 400u64)].into_iter().collect();
-        let out = WeightedMedianSelector::select(results, &jitter, &cfg(1));
+        let smoothed: HashMap<String, SmoothedStats> = [(
+            "a:123".to_string(),
+            SmoothedStats {
+                jitter_ms: 400.0,
+                ..SmoothedStats::default()
+            },
+        )]
+        .into_iter()
+        .collect();
+        let out = WeightedMedianSelector::select(results, &smoothed, &cfg(1));
         assert_eq!(out.diagnostics.selection_state, SelectionState::Ok);
         // "b" has lower λ so should be selected
         assert_eq!(out.selected.as_ref().unwrap().server, "b:123");
     }
 
+    #[test]
+    fn lucky_fast_sample_does_not_beat_smoothed_rtt() {
+        // "a" usually takes 200 ms but this round answered in 2 ms; "b" is a
+        // steady 20 ms.  Scored on the last sample alone "a" would win.
+        let now = Instant::now();
+        let results = vec![
+            NtpResult::for_testing("a:123", 0, Duration::from_millis(2), 100, now),
+            NtpResult::for_testing("b:123", 0, Duration::from_millis(20), 100, now),
+        ];
+        let smoothed: HashMap<String, SmoothedStats> = [
+            (
+                "a:123".to_string(),
+                SmoothedStats {
+                    rtt_ms: 200.0,
+                    ..SmoothedStats::default()
+                },
+            ),
+            (
+                "b:123".to_string(),
+                SmoothedStats {
+                    rtt_ms: 20.0,
+                    ..SmoothedStats::default()
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let out = WeightedMedianSelector::select(results.clone(), &HashMap::new(), &cfg(1));
+        assert_eq!(out.selected.as_ref().unwrap().server, "a:123");
+        let out = WeightedMedianSelector::select(results, &smoothed, &cfg(1));
+        assert_eq!(out.selected.as_ref().unwrap().server, "b:123");
+    }
+
     #[test]
     fn single_provider_flag_set_when_one_group_dominates() {
         // All 3 agreers from *.google.com — one group dominates
@@ -1451,6 +1734,134 @@ mod tests {
         ];
         let out = WeightedMedianSelector::select(results, &HashMap::new(), &cfg(1));
         assert_eq!(out.selected.as_ref().unwrap().server, "b:123");
+        assert!(out.served_offset_ms.is_none());
+    }
+
+    // ── Selection strategies ─────────────────────────────────────────────────
+
+    fn cfg_strategy(strategy: SelectionStrategy) -> SelectionConfig {
+        SelectionConfig { strategy, ..cfg(2) }
+    }
+
+    #[test]
+    fn offset_median_serves_plain_median() {
+        let results = vec![
+            r("a:123", 10, 0),
+            r("b:123", 20, 10),
+            r("c:123", 30, 40),
+            r("d:123", 40, 100),
+        ];
+        let out = WeightedMedianSelector::select(
+            results,
+            &HashMap::new(),
+            &cfg_strategy(SelectionStrategy::OffsetMedian),
+        );
+        assert_eq!(out.diagnostics.weighted_median_offset_ms, Some(25.0));
+        assert_eq!(out.served_offset_ms, Some(25));
+        assert_eq!(out.agreers.len(), 4);
+    }
+
+    #[test]
+    fn weighted_serves_rtt_weighted_mean_of_agreers() {
+        let results = vec![
+            r("a:123", 0, 0),
+            r("b:123", 1, 30),
+            // Outlier: excluded from the mean.
+            r("c:123", 0, 5_000),
+        ];
+        let out = WeightedMedianSelector::select(
+            results,
+            &HashMap::new(),
+            &cfg_strategy(SelectionStrategy::Weighted),
+        );
+        // Weights 1/(0+1) = 1 and 1/(1+1) = 0.5 → (0·1 + 30·0.5) / 1.5 = 10.
+        assert_eq!(out.served_offset_ms, Some(10));
+        assert_eq!(out.agreers.len(), 2);
+    }
+
+    #[test]
+    fn stratum_first_prefers_lower_stratum_over_lambda() {
+        let now = Instant::now();
+        let results = vec![
+            NtpResult::for_testing_with(
+                "near:123",
+                0,
+                Duration::from_millis(5),
+                0,
+                now,
+                3,
+                0,
+                0,
+                1,
+                -20,
+            ),
+            NtpResult::for_testing_with(
+                "gps:123",
+                0,
+                Duration::from_millis(80),
+                10,
+                now,
+                1,
+                0,
+                0,
+                1,
+                -20,
+            ),
+        ];
+        let out = WeightedMedianSelector::select(
+            results.clone(),
+            &HashMap::new(),
+            &cfg_strategy(SelectionStrategy::AccuracyFirst),
+        );
+        assert_eq!(out.selected.unwrap().server, "near:123");
+
+        let out = WeightedMedianSelector::select(
+            results,
+            &HashMap::new(),
+            &cfg_strategy(SelectionStrategy::StratumFirst),
+        );
+        assert_eq!(out.selected.unwrap().server, "gps:123");
+        assert!(out.served_offset_ms.is_none());
+    }
+
+    #[test]
+    fn higher_priority_agreer_wins_unless_it_disagrees() {
+        let now = Instant::now();
+        let onprem = |offset_ms| {
+            NtpResult::for_testing_with(
+                "onprem:123",
+                0,
+                Duration::from_millis(40),
+                offset_ms,
+                now,
+                1,
+                0,
+                0,
+                100,
+                -20,
+            )
+        };
+        let mut config = cfg(2);
+        config.server_options.insert(
+            "onprem:123".to_string(),
+            crate::config::ServerConfig {
+                weight: 3.0,
+                priority: 1,
+                ..crate::config::ServerConfig::new("onprem:123")
+            },
+        );
+
+        let results = vec![onprem(0), r("pool-a:123", 5, 5), r("pool-b:123", 5, 10)];
+        let out = WeightedMedianSelector::select(results, &HashMap::new(), &config);
+        assert_eq!(out.selected.unwrap().server, "onprem:123");
+        let scores = &out.diagnostics.candidates;
+        assert_eq!(scores[0].priority, 1);
+        assert_eq!(scores[0].weight, 3.0 / (scores[0].lambda_ms + 1.0));
+
+        // Once it no longer agrees with the consensus, the pool takes over.
+        let results = vec![onprem(5_000), r("pool-a:123", 5, 5), r("pool-b:123", 5, 10)];
+        let out = WeightedMedianSelector::select(results, &HashMap::new(), &config);
+        assert_eq!(out.selected.unwrap().server, "pool-a:123");
     }
 
     // ── Kept from original selection.rs ──────────────────────────────────────
@@ -1521,6 +1932,10 @@ mod tests {
             "192.168.1.1"
         );
         assert_eq!(provider_group("bare:123", &HashMap::new()), "bare");
+        assert_eq!(
+            provider_group("nts://time.cloudflare.com", &HashMap::new()),
+            "cloudflare.com"
+        );
     }
 
     #[test]
@@ -1957,8 +2372,8 @@ mod tests {
         // zero so the only λ contribution is PHI * age_ms.
         let old_instant = Instant::now() - Duration::from_secs(1000);
         let sample = NtpResult::for_testing("a:123", 0, Duration::from_millis(0), 0, old_instant);
-        // jitter_ms = 0 → lambda = PHI_MS_PER_MS * age_ms ≈ 15e-6 * 1_000_000 = 15 ms
-        let lambda = compute_lambda(&sample, 0.0);
+        // No smoothed stats → jitter 0 → lambda = PHI_MS_PER_MS * age_ms ≈ 15e-6 * 1_000_000 = 15 ms
+        let lambda = compute_lambda(&sample, None);
         assert!(
             (lambda - 15.0).abs() < 1.0,
             "PHI contribution for 1000 s age should be ≈15 ms, got {lambda:.3} ms"
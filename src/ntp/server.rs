@@ -8,7 +8,8 @@
 //! - When `TimeBase::has_synced()` is `true`, replies with LI=0,
 //!   Stratum=2, Reference ID = `"LOCL"`, receive_timestamp set at packet
 //!   intake, transmit_timestamp set just before send, and origin_timestamp
-//!   echoed from the client's transmit_timestamp.
+//!   echoed from the client's transmit_timestamp.  When the selected
+//!   source is a local GPS reference clock, Stratum=1 and `"GPS"` instead.
 //! - When not synced, replies with LI=3, Stratum=16, and the system clock
 //!   (so the client at least gets something monotonic) — but the
 //!   unsynced kiss code signals "do not trust this server".
@@ -20,6 +21,7 @@ use super::protocol::{
     LI_ALARM_UNSYNCHRONIZED, LI_NO_WARNING, NTP_VERSION, NtpPacket, STRATUM_PRIMARY,
     STRATUM_UNSYNCHRONIZED, parse_packet, serialize_packet, system_unix_ms, unix_ms_to_ntp,
 };
+use super::refclock::{REFERENCE_ID_GPS, is_refclock};
 use super::sync::SyncQuality;
 use crate::metrics::Metrics;
 use crate::timebase::TimeBase;
@@ -363,20 +365,32 @@ fn build_response(
     }
 
     let synced = timebase.has_synced();
+    // Disciplined directly by a local GPS (stratum 0) we are a primary server.
+    let primary =
+        synced && quality.is_some_and(|q| q.stratum == 0 && is_refclock(&q.selected_server));
 
-    let (li, stratum) = if synced {
+    let (li, stratum) = if primary {
+        (LI_NO_WARNING, STRATUM_PRIMARY)
+    } else if synced {
         (LI_NO_WARNING, STRATUM_PRIMARY + 1) // Stratum 2: secondary server
     } else {
         (LI_ALARM_UNSYNCHRONIZED, STRATUM_UNSYNCHRONIZED)
     };
 
     // Reference ID:
-    //   - Stratum 1 (primary) encodes a 4-char ASCII clock source.
+    //   - Stratum 1 (primary) encodes a 4-char ASCII clock source: "GPS"
+    //     when a gpsd reference clock is selected.
     //   - Stratum 2-15 (secondary) encodes the upstream IPv4 (or "LOCL").
     //   - We advertise "LOCL" — a generic "local clock" kiss code, which
     //     is acceptable for a Stratum-2 server and avoids hard-coding an
     //     upstream IP that may not match reality.
-    let reference_id = if synced { REFERENCE_ID_LOCAL } else { 0 };
+    let reference_id = if primary {
+        REFERENCE_ID_GPS
+    } else if synced {
+        REFERENCE_ID_LOCAL
+    } else {
+        0
+    };
 
     // Reference timestamp = the time we last received a clean sync.
     // For the unsynced path, set it to 0 (RFC 5905 §7.3).
@@ -519,6 +533,20 @@ mod tests {
         assert_eq!(r.reference_id, REFERENCE_ID_LOCAL);
     }
 
+    #[test]
+    fn build_response_refclock_uses_stratum_1() {
+        let tb = synced_timebase();
+        let req = NtpPacket::new(0, 4, 3);
+        let q = SyncQuality {
+            stratum: 0,
+            selected_server: "gpsd://".into(),
+            ..make_sync_quality(0, 0, 0)
+        };
+        let r = build_response(&tb, &req, 0, Some(&q), 16_000, 1000);
+        assert_eq!(r.stratum, STRATUM_PRIMARY);
+        assert_eq!(r.reference_id, REFERENCE_ID_GPS);
+    }
+
     #[test]
     fn build_response_unsynced_uses_stratum_16() {
         let tb = unsynced_timebase();
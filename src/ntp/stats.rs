@@ -1,8 +1,55 @@
+use super::calibration::{AsymmetryEstimator, BiasEstimate};
+use super::client::{KodAction, NtpSample};
+use super::filter::ClockFilter;
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
 const JITTER_RING_SIZE: usize = 8;
 
+/// EWMA gain for offset and RTT (1/8, as for TCP's smoothed RTT).
+const EWMA_GAIN: f64 = 0.125;
+
+/// EWMA gain for jitter; RFC 5905 averages jitter over about four samples.
+const JITTER_EWMA_GAIN: f64 = 0.25;
+
+/// Ceiling on the back-off imposed by repeated `RATE` kisses.
+const KOD_MAX_BACKOFF: Duration = Duration::from_secs(3600);
+
+/// Exponentially-weighted moving averages of a server's successful samples.
+///
+/// Selection scores servers on these rather than on the latest exchange
+/// alone, so one unusually fast reply cannot make a noisy server look best.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SmoothedStats {
+    pub offset_ms: f64,
+    /// RMS of each offset's deviation from the smoothed offset.
+    pub jitter_ms: f64,
+    pub rtt_ms: f64,
+}
+
+impl SmoothedStats {
+    /// Fold in one sample; the first sample seeds the averages.
+    fn update(previous: Option<Self>, offset_ms: i64, rtt: Duration) -> Self {
+        let offset_ms = offset_ms as f64;
+        let rtt_ms = rtt.as_secs_f64() * 1000.0;
+        let Some(prev) = previous else {
+            return Self {
+                offset_ms,
+                jitter_ms: 0.0,
+                rtt_ms,
+            };
+        };
+        let deviation = offset_ms - prev.offset_ms;
+        Self {
+            offset_ms: prev.offset_ms + EWMA_GAIN * deviation,
+            jitter_ms: ((1.0 - JITTER_EWMA_GAIN) * prev.jitter_ms.powi(2)
+                + JITTER_EWMA_GAIN * deviation.powi(2))
+            .sqrt(),
+            rtt_ms: prev.rtt_ms + EWMA_GAIN * (rtt_ms - prev.rtt_ms),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ServerStats {
     /// Server address. Kept for log/debug visibility; not used by the
@@ -16,8 +63,24 @@ pub struct ServerStats {
     pub total_queries: u64,
     pub total_failures: u64,
     pub disabled: bool,
+    /// Learned offset bias against the calibration reference (see `ntp::calibration`).
+    pub bias: BiasEstimate,
+    /// Delay/offset history for automatic asymmetry estimation.
+    pub asymmetry: AsymmetryEstimator,
+    /// Last eight samples; the minimum-delay one is passed to selection.
+    pub filter: ClockFilter,
+    /// Raw fields of the most recent successful exchange, for `/debug/ntp`.
+    pub last_exchange: Option<NtpSample>,
+    /// Set by a `DENY`/`RSTR` kiss code: the server is never queried again.
+    pub kod_denied: bool,
+    /// Set by a `RATE` kiss code: the server is not queried before this.
+    pub kod_backoff_until: Option<Instant>,
+    /// `RATE` kisses since the last successful query; each doubles the back-off.
+    kod_rate_strikes: u32,
     /// Ring buffer of the last JITTER_RING_SIZE offset_ms values for this server.
     recent_offsets: VecDeque<i64>,
+    /// Smoothed offset/jitter/RTT; `None` until the first successful sample.
+    pub smoothed: Option<SmoothedStats>,
 }
 
 impl ServerStats {
@@ -31,10 +94,23 @@ impl ServerStats {
             total_queries: 0,
             total_failures: 0,
             disabled: false,
+            bias: BiasEstimate::default(),
+            asymmetry: AsymmetryEstimator::default(),
+            filter: ClockFilter::default(),
+            last_exchange: None,
+            kod_denied: false,
+            kod_backoff_until: None,
+            kod_rate_strikes: 0,
             recent_offsets: VecDeque::with_capacity(JITTER_RING_SIZE),
+            smoothed: None,
         }
     }
 
+    /// Fold a successful sample into the smoothed offset, jitter and RTT.
+    pub fn record_smoothed(&mut self, offset_ms: i64, rtt: Duration) {
+        self.smoothed = Some(SmoothedStats::update(self.smoothed, offset_ms, rtt));
+    }
+
     /// Record a new offset sample for jitter computation.
     pub fn record_offset(&mut self, offset_ms: i64) {
         if self.recent_offsets.len() >= JITTER_RING_SIZE {
@@ -68,6 +144,8 @@ impl ServerStats {
         self.last_success = Some(Instant::now());
         self.consecutive_failures = 0;
         self.total_queries += 1;
+        self.kod_rate_strikes = 0;
+        self.kod_backoff_until = None;
 
         // Re-enable server if it was disabled
         let was_disabled = self.disabled;
@@ -89,6 +167,31 @@ impl ServerStats {
         false
     }
 
+    /// Act on a kiss code received at `now`: `RATE` skips the server for
+    /// `base × 2^(strikes − 1)` (capped at an hour), `DENY`/`RSTR` disable it
+    /// for good.  The query itself is recorded via `record_failure`.
+    pub fn record_kod(&mut self, action: KodAction, base: Duration, now: Instant) {
+        match action {
+            KodAction::BackOff => {
+                self.kod_rate_strikes += 1;
+                let backoff = base
+                    .saturating_mul(1 << (self.kod_rate_strikes - 1).min(16))
+                    .min(KOD_MAX_BACKOFF);
+                self.kod_backoff_until = Some(now + backoff);
+            }
+            KodAction::Disable => {
+                self.kod_denied = true;
+                self.disabled = true;
+            }
+            KodAction::Failure => {}
+        }
+    }
+
+    /// True while a kiss code forbids querying this server.
+    pub fn is_suppressed(&self, now: Instant) -> bool {
+        self.kod_denied || self.kod_backoff_until.is_some_and(|until| now < until)
+    }
+
     pub fn is_healthy(&self) -> bool {
         // Server is healthy if not disabled
         !self.disabled
@@ -145,4 +248,55 @@ mod tests {
         assert!(!stats.disabled);
         assert_eq!(stats.consecutive_failures, 0);
     }
+
+    #[test]
+    fn rate_kod_backs_off_exponentially_and_deny_disables() {
+        let mut stats = ServerStats::new("pool.ntp.org:123".to_string());
+        let base = Duration::from_secs(30);
+        let now = Instant::now();
+
+        stats.record_kod(KodAction::BackOff, base, now);
+        assert!(stats.is_suppressed(now + Duration::from_secs(29)));
+        assert!(!stats.is_suppressed(now + Duration::from_secs(30)));
+        stats.record_kod(KodAction::BackOff, base, now);
+        assert_eq!(stats.kod_backoff_until, Some(now + Duration::from_secs(60)));
+        for _ in 0..20 {
+            stats.record_kod(KodAction::BackOff, base, now);
+        }
+        assert_eq!(stats.kod_backoff_until, Some(now + KOD_MAX_BACKOFF));
+
+        // A successful query clears the back-off.
+        stats.record_success(Duration::from_millis(10));
+        assert!(!stats.is_suppressed(now));
+
+        stats.record_kod(KodAction::Disable, base, now);
+        assert!(stats.is_suppressed(now + Duration::from_secs(86_400)));
+        assert!(!stats.is_healthy());
+    }
+
+    #[test]
+    fn smoothed_stats_damp_a_single_lucky_sample() {
+        let mut stats = ServerStats::new("noisy:123".to_string());
+        assert_eq!(stats.smoothed, None);
+        stats.record_smoothed(10, Duration::from_millis(80));
+        assert_eq!(
+            stats.smoothed,
+            Some(SmoothedStats {
+                offset_ms: 10.0,
+                jitter_ms: 0.0,
+                rtt_ms: 80.0,
+            })
+        );
+
+        for offset in [-30, 50, -30, 50] {
+            stats.record_smoothed(offset, Duration::from_millis(80));
+        }
+        // One fast, on-average reply barely moves the smoothed RTT and keeps
+        // the jitter earned by the swings before it.
+        stats.record_smoothed(10, Duration::from_millis(2));
+        let s = stats.smoothed.unwrap();
+        assert!(s.rtt_ms > 70.0, "rtt {}", s.rtt_ms);
+        assert!(s.jitter_ms > 25.0, "jitter {}", s.jitter_ms);
+        assert!((s.offset_ms - 10.0).abs() < 15.0, "offset {}", s.offset_ms);
+    }
 }
@@ -1,14 +1,21 @@
-use super::client::{NtpClient, PacketNtpClient};
-use super::selection::{NtpResult, SelectionDiagnostics, TimingSource, WeightedMedianSelector};
-use super::stats::ServerStats;
-use crate::config::NtpConfig;
+use super::calibration::reference_offset_ms;
+use super::client::{KissOfDeath, KodAction, NtpClient, NtpSample, PacketNtpClient};
+use super::nts::NtsNtpClient;
+use super::refclock::RefclockNtpClient;
+use super::resolve::{self, PoolResolver, QueryTarget};
+use super::selection::{
+    NtpResult, SelectionDiagnostics, StickyDecision, StickyReason, TimingSource,
+    WeightedMedianSelector,
+};
+use super::stats::{ServerStats, SmoothedStats};
+use crate::config::{NtpConfig, SelectionConfig, SelectionStrategy, ServerConfig, ServerTier};
 use anyhow::{Context, Result};
 use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-use tracing::{error, info, warn};
+use tokio::sync::{RwLock, Semaphore};
+use tracing::{debug, error, info, warn};
 
 /// Full quality snapshot from the most recent successful NTP sync.
 ///
@@ -45,8 +52,15 @@ impl SyncQuality {
     /// upstream_dispersion + |precision| + jitter + PHI×age_s×1000 + rtt/2
     /// ```
     pub fn compute_dispersion_ms(&self) -> f64 {
+        self.dispersion_ms_at(Instant::now())
+    }
+
+    /// [`Self::compute_dispersion_ms`] as of `at`.
+    pub fn dispersion_ms_at(&self, at: Instant) -> f64 {
         use super::protocol::precision_log2_to_ms;
-        let age_s = self.last_sync_instant.elapsed().as_secs_f64();
+        let age_s = at
+            .saturating_duration_since(self.last_sync_instant)
+            .as_secs_f64();
         let precision_ms = precision_log2_to_ms(self.precision_log2).abs();
         ((self.upstream_root_dispersion_ms as f64)
             + precision_ms
@@ -85,38 +99,97 @@ pub struct SyncOutcome {
     pub diagnostics: SelectionDiagnostics,
     /// Jitter (offset stddev, ms) for the selected server from its ring buffer.
     pub jitter_ms: u64,
+    /// The fallback tier was queried because the primary tier had no quorum.
+    pub fallback_tier: bool,
 }
 
 pub struct NtpSyncer {
     config: Arc<NtpConfig>,
+    /// Servers queried each sync, with their options: `NTP_SERVERS`, as
+    /// changed since through the admin API.
+    servers: Mutex<Vec<ServerConfig>>,
     stats: Arc<RwLock<HashMap<String, ServerStats>>>,
     current_server: Arc<RwLock<Option<String>>>,
     client: Arc<dyn NtpClient>,
+    /// Expands pool hostnames into per-address servers (`NTP_POOL_EXPANSION`).
+    resolver: PoolResolver,
     /// Most recent selection diagnostics — updated on every sync attempt, even failures.
     last_diagnostics: Arc<Mutex<Option<SelectionDiagnostics>>>,
+    /// Kiss-o'-Death replies not yet collected by `take_kod_events`.
+    kod_events: Mutex<Vec<KodEvent>>,
+}
+
+/// Why a runtime server-set change was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ServerSetError {
+    #[error("server is already configured")]
+    AlreadyConfigured,
+    #[error("server is not configured")]
+    NotConfigured,
+    #[error("cannot remove the last NTP server")]
+    LastServer,
+}
+
+/// One Kiss-o'-Death reply, for metrics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KodEvent {
+    pub server: String,
+    pub code: String,
 }
 
 impl NtpSyncer {
-    /// Create with the default production client (`PacketNtpClient`).
+    /// Create with the default production client: `PacketNtpClient`, with
+    /// `nts://` servers secured by NTS (RFC 8915) and `gpsd://` servers read
+    /// as local reference clocks.
     pub fn new(config: Arc<NtpConfig>) -> Self {
-        Self::with_client(config, Arc::new(PacketNtpClient))
+        let keys = config
+            .servers
+            .iter()
+            .filter_map(|s| Some((s.address.clone(), config.keys.get(&s.key_id?)?.clone())))
+            .collect();
+        let plain = PacketNtpClient::new(config.address_family)
+            .with_source(config.source.clone())
+            .with_keys(keys);
+        let nts = NtsNtpClient::new(Arc::new(plain))
+            .with_address_family(config.address_family)
+            .with_source(config.source.clone());
+        let client = RefclockNtpClient::new(Arc::new(nts));
+        Self::with_client(config, Arc::new(client))
     }
 
     /// Create with an injected client — used in tests to supply a mock.
     pub fn with_client(config: Arc<NtpConfig>, client: Arc<dyn NtpClient>) -> Self {
         let mut stats_map = HashMap::new();
         for server in &config.servers {
-            stats_map.insert(server.clone(), ServerStats::new(server.clone()));
+            stats_map.insert(
+                server.address.clone(),
+                ServerStats::new(server.address.clone()),
+            );
         }
         Self {
+            resolver: PoolResolver::new(&config.query, config.address_family).pinning(
+                config
+                    .servers
+                    .iter()
+                    .filter(|s| s.key_id.is_some())
+                    .map(|s| s.address.clone()),
+            ),
+            servers: Mutex::new(config.servers.clone()),
             config,
             stats: Arc::new(RwLock::new(stats_map)),
             current_server: Arc::new(RwLock::new(None)),
             client,
             last_diagnostics: Arc::new(Mutex::new(None)),
+            kod_events: Mutex::new(Vec::new()),
         }
     }
 
+    /// The client used for upstream queries, so other probes can share its
+    /// NTS sessions.
+    pub fn client(&self) -> Arc<dyn NtpClient> {
+        self.client.clone()
+    }
+
     /// Last selection diagnostics (success or failure).  `None` until first sync attempt.
     pub fn last_diagnostics(&self) -> Option<SelectionDiagnostics> {
         self.last_diagnostics.lock().clone()
@@ -132,68 +205,130 @@ impl NtpSyncer {
             .unwrap_or(0)
     }
 
-    /// Perform a full sync: query all servers, run P1-6 weighted-median selection.
-    pub async fn sync(&self) -> Result<SyncOutcome> {
-        let all_servers: Vec<String> = self.config.servers.clone();
-        let current_server_opt = self.current_server.read().await.clone();
+    /// Servers currently queried, in configuration order.
+    pub fn servers(&self) -> Vec<String> {
+        self.servers
+            .lock()
+            .iter()
+            .map(|s| s.address.clone())
+            .collect()
+    }
 
-        info!(
-            servers = ?all_servers,
-            total_count = all_servers.len(),
-            "Testing all NTP servers to find best one"
+    /// Start querying `server` from the next sync on.
+    pub async fn add_server(&self, server: ServerConfig) -> Result<(), ServerSetError> {
+        {
+            let mut servers = self.servers.lock();
+            if servers.iter().any(|s| s.address == server.address) {
+                return Err(ServerSetError::AlreadyConfigured);
+            }
+            servers.push(server.clone());
+        }
+        self.stats.write().await.insert(
+            server.address.clone(),
+            ServerStats::new(server.address.clone()),
         );
+        self.resolver.invalidate();
+        Ok(())
+    }
 
-        // Query all servers in parallel
-        let mut query_tasks = Vec::new();
-        for server in &all_servers {
-            let server = server.clone();
-            let timeout_duration = Duration::from_secs(self.config.timeout_secs);
-            let offset_bias = self.config.offset_bias_ms;
-            let asymmetry_bias = self.config.asymmetry_bias_ms;
-            let client = self.client.clone();
-            let task = tokio::spawn(async move {
-                Self::query_with_client(
-                    client,
-                    server,
-                    timeout_duration,
-                    offset_bias,
-                    asymmetry_bias,
-                )
-                .await
-            });
-            query_tasks.push(task);
+    /// Stop querying `address` and drop its stats.  Addresses expanded from
+    /// it are dropped by the next sync's pool resolution.
+    pub async fn remove_server(&self, address: &str) -> Result<(), ServerSetError> {
+        {
+            let mut servers = self.servers.lock();
+            let Some(index) = servers.iter().position(|s| s.address == address) else {
+                return Err(ServerSetError::NotConfigured);
+            };
+            if servers.len() == 1 {
+                return Err(ServerSetError::LastServer);
+            }
+            servers.remove(index);
+        }
+        self.stats.write().await.remove(address);
+        self.resolver.invalidate();
+        let mut current = self.current_server.write().await;
+        if current.as_deref() == Some(address) {
+            *current = None;
         }
+        Ok(())
+    }
 
-        // Collect results and update per-server stats + offset ring
-        let mut results = Vec::new();
-        for (server, task) in all_servers.iter().zip(query_tasks) {
-            match task.await {
-                Ok(Ok(result)) => {
-                    info!(
-                        server = %server,
-                        rtt_ms = result.rtt.as_millis(),
-                        "NTP query successful"
-                    );
-                    let mut stats_write = self.stats.write().await;
-                    if let Some(stat) = stats_write.get_mut(server) {
-                        let was_disabled = stat.record_success(result.rtt);
-                        stat.record_offset(result.offset_ms);
-                        if was_disabled {
-                            info!(server = %server, "NTP server re-enabled after successful response");
-                        }
+    /// Perform a full sync: query all servers, run P1-6 weighted-median selection.
+    pub async fn sync(&self) -> Result<SyncOutcome> {
+        let configured_servers = self.servers.lock().clone();
+        let servers = self.servers();
+        let targets = self.resolver.targets(&servers).await;
+        if self.config.query.expand_pools {
+            let resolved: Vec<String> = targets.iter().map(|t| t.address.clone()).collect();
+            self.track_targets(&resolved, &servers).await;
+        }
+        // Servers under a Kiss-o'-Death back-off or denial are not queried.
+        let targets: Vec<QueryTarget> = {
+            let now = Instant::now();
+            let stats_read = self.stats.read().await;
+            targets
+                .into_iter()
+                .filter(|t| {
+                    let suppressed = stats_read
+                        .get(&t.address)
+                        .is_some_and(|s| s.is_suppressed(now));
+                    if suppressed {
+                        debug!(server = %t.address, "Skipping NTP server after Kiss-o'-Death");
                     }
-                    drop(stats_write);
-                    results.push(result);
-                }
-                Ok(Err(e)) => {
-                    warn!(server = %server, error = %e, "NTP query failed");
-                    self.record_server_failure(server).await;
-                }
-                Err(e) => {
-                    error!(server = %server, error = %e, "NTP query task panicked");
-                    self.record_server_failure(server).await;
-                }
+                    !suppressed
+                })
+                .collect()
+        };
+        if targets.is_empty() {
+            anyhow::bail!("All NTP servers are suppressed by Kiss-o'-Death replies");
+        }
+        let current_server_opt = self.current_server.read().await.clone();
+        let server_options: HashMap<String, ServerConfig> = configured_servers
+            .into_iter()
+            .map(|s| (s.address.clone(), s))
+            .collect();
+        let configured = SelectionConfig {
+            server_options,
+            ..self.config.selection.clone()
+        };
+        let selection = resolve::selection_for(&configured, &targets);
+
+        // Fallback-tier servers are only queried when the primary tier
+        // cannot make a quorum on its own.
+        let (primary, fallback): (Vec<QueryTarget>, Vec<QueryTarget>) =
+            targets.into_iter().partition(|t| {
+                configured
+                    .server_options
+                    .get(&t.origin)
+                    .is_none_or(|s| s.tier == ServerTier::Primary)
+            });
+        let mut raw_offsets: HashMap<String, i64> = HashMap::new();
+        let mut results = self
+            .query_targets(&primary, &configured.server_options, &mut raw_offsets)
+            .await;
+        let mut queried = primary.len();
+        // P1-6 weighted-median + quorum selection, over both tiers when the
+        // fallback tier was queried.
+        let (mut jitter_by_server, mut smoothed_by_server) = self.jitter_and_smoothed().await;
+        let mut output =
+            WeightedMedianSelector::select(results.clone(), &smoothed_by_server, &selection);
+        let fallback_tier = output.selected.is_none() && !fallback.is_empty();
+        if fallback_tier {
+            if !primary.is_empty() {
+                warn!(
+                    answered = results.len(),
+                    min_quorum = selection.min_quorum,
+                    "Primary NTP servers cannot make a quorum; querying fallback tier"
+                );
             }
+            results.extend(
+                self.query_targets(&fallback, &configured.server_options, &mut raw_offsets)
+                    .await,
+            );
+            queried += fallback.len();
+            (jitter_by_server, smoothed_by_server) = self.jitter_and_smoothed().await;
+            output =
+                WeightedMedianSelector::select(results.clone(), &smoothed_by_server, &selection);
         }
 
         if results.is_empty() {
@@ -201,30 +336,14 @@ impl NtpSyncer {
         }
 
         let successful = results.len();
-        let failed = all_servers.len() - successful;
+        let failed = queried - successful;
         info!(
             successful,
             failed,
-            total = all_servers.len(),
+            total = queried,
             "NTP server test summary"
         );
 
-        // Build jitter map from stats (accumulated across prior syncs)
-        let jitter_by_server: HashMap<String, u64> = {
-            let stats_read = self.stats.read().await;
-            stats_read
-                .iter()
-                .map(|(k, v)| (k.clone(), v.jitter_ms()))
-                .collect()
-        };
-
-        // P1-6 weighted-median + quorum selection
-        let output = WeightedMedianSelector::select(
-            results.clone(),
-            &jitter_by_server,
-            &self.config.selection,
-        );
-
         // Always store diagnostics (even on failure)
         *self.last_diagnostics.lock() = Some(output.diagnostics.clone());
 
@@ -232,9 +351,47 @@ impl NtpSyncer {
             .selected
             .context("No quorum: insufficient agreers after selection")?;
 
+        if self.config.calibration.enabled {
+            self.update_bias_estimates(&raw_offsets, &output.agreers, best.offset_ms)
+                .await;
+        }
+
         // Sticky: switch servers only if the new best is significantly faster
-        let (selected_result, new_sticky) =
-            sticky_select(&output.agreers, best, current_server_opt.as_deref(), 50);
+        let best_server = best.server.clone();
+        // Only a current server of the same priority (and, for stratum_first,
+        // the same stratum) may be kept, so stickiness never overrides them.
+        let stratum_first = selection.strategy == SelectionStrategy::StratumFirst;
+        let best_priority = selection.server_priority(&best.server);
+        let sticky_pool: Vec<NtpResult> = output
+            .agreers
+            .iter()
+            .filter(|r| selection.server_priority(&r.server) == best_priority)
+            .filter(|r| !stratum_first || r.stratum == best.stratum)
+            .cloned()
+            .collect();
+        let (mut selected_result, new_sticky, sticky_reason) = sticky_select(
+            &sticky_pool,
+            best,
+            current_server_opt.as_deref(),
+            STICKY_SWITCH_THRESHOLD_MS,
+        );
+        let mut diagnostics = output.diagnostics;
+        diagnostics.sticky = Some(StickyDecision {
+            rtt_advantage_ms: current_server_opt
+                .as_deref()
+                .and_then(|current| output.agreers.iter().find(|r| r.server == current))
+                .and_then(|current| {
+                    let best = output.agreers.iter().find(|r| r.server == best_server)?;
+                    Some(current.rtt.as_millis() as i64 - best.rtt.as_millis() as i64)
+                }),
+            previous_server: current_server_opt.clone(),
+            best_server,
+            selected_server: selected_result.server.clone(),
+            switched: new_sticky.is_some(),
+            reason: sticky_reason,
+            switch_threshold_ms: STICKY_SWITCH_THRESHOLD_MS,
+        });
+        *self.last_diagnostics.lock() = Some(diagnostics.clone());
 
         if let Some(ref new_server) = new_sticky {
             let old = current_server_opt.as_deref().unwrap_or("<none>");
@@ -268,6 +425,13 @@ impl NtpSyncer {
             );
         }
 
+        // Combining strategies serve the consensus offset on top of the
+        // selected server's exchange, keeping its static biases.
+        if let Some(served_offset_ms) = output.served_offset_ms {
+            selected_result.epoch_ms += served_offset_ms - selected_result.offset_ms;
+            selected_result.offset_ms = served_offset_ms;
+        }
+
         let jitter_ms = jitter_by_server
             .get(&selected_result.server)
             .copied()
@@ -292,25 +456,208 @@ impl NtpSyncer {
                 reference_id: selected_result.reference_id,
                 timing_source: selected_result.timing_source,
             },
-            diagnostics: output.diagnostics,
+            diagnostics,
             jitter_ms,
+            fallback_tier,
         })
     }
 
-    /// Query a single NTP server using the injected `NtpClient`.
-    async fn query_with_client(
-        client: Arc<dyn NtpClient>,
+    /// Query `targets` (one tier) in parallel and fold each answer into its
+    /// server's stats.  Raw offsets are recorded for calibration.
+    async fn query_targets(
+        &self,
+        targets: &[QueryTarget],
+        server_options: &HashMap<String, ServerConfig>,
+        raw_offsets: &mut HashMap<String, i64>,
+    ) -> Vec<NtpResult> {
+        if targets.is_empty() {
+            return Vec::new();
+        }
+        let all_servers: Vec<&str> = targets.iter().map(|t| t.address.as_str()).collect();
+        info!(
+            servers = ?all_servers,
+            total_count = all_servers.len(),
+            "Testing all NTP servers to find best one"
+        );
+
+        // Query all servers in parallel, optionally staggered over a window and
+        // capped by a semaphore so one sync never bursts every server at once.
+        let query_config = &self.config.query;
+        let in_flight = (query_config.max_in_flight > 0)
+            .then(|| Arc::new(Semaphore::new(query_config.max_in_flight)));
+        let mut query_tasks = Vec::new();
+        for (index, target) in targets.iter().enumerate() {
+            let server = target.address.clone();
+            let options = server_options.get(&target.origin);
+            let timeout_duration = options
+                .map_or(Duration::from_millis(self.config.timeout_ms), |s| {
+                    s.timeout(self.config.timeout_ms)
+                });
+            let samples = options.map_or(1, |s| s.samples);
+            let client = self.client.clone();
+            let start_delay =
+                stagger_delay(query_config.stagger_window_ms, index, all_servers.len());
+            let in_flight = in_flight.clone();
+            let retries = query_config.retries_for(&target.origin);
+            let retry_delay_ms = Duration::from_millis(query_config.retry_delay_ms);
+            let timeout_jitter_ms = Duration::from_millis(query_config.timeout_jitter_ms);
+            let task = tokio::spawn(async move {
+                if !start_delay.is_zero() {
+                    tokio::time::sleep(start_delay).await;
+                }
+                let _permit = match in_flight {
+                    Some(sem) => Some(sem.acquire_owned().await.expect("semaphore never closed")),
+                    None => None,
+                };
+                let query = RetryingQuery {
+                    client: client.as_ref(),
+                    server: &server,
+                    timeout: timeout_duration,
+                    timeout_jitter: timeout_jitter_ms,
+                    retries,
+                    retry_delay: retry_delay_ms,
+                };
+                // With `samples > 1`, keep the lowest-delay exchange, the one
+                // least disturbed by queueing.
+                let mut best = query.run().await?;
+                for _ in 1..samples {
+                    match query.run().await {
+                        Ok(sample) if sample.delay_ms < best.delay_ms => best = sample,
+                        Ok(_) => {}
+                        Err(e) if e.downcast_ref::<KissOfDeath>().is_some() => return Err(e),
+                        Err(e) => debug!(server = %server, error = %e, "Extra NTP sample failed"),
+                    }
+                }
+                Ok(best)
+            });
+            query_tasks.push(task);
+        }
+
+        // Collect results and update per-server stats + offset ring
+        let calibration = &self.config.calibration;
+        let mut results = Vec::new();
+        for (target, task) in targets.iter().zip(query_tasks) {
+            let server = &target.address;
+            match task.await {
+                Ok(Ok(sample)) => {
+                    let mut result = Self::to_ntp_result(
+                        server.clone(),
+                        &sample,
+                        self.config.offset_bias_ms,
+                        self.config.asymmetry_bias_ms,
+                    );
+                    info!(
+                        server = %server,
+                        rtt_ms = result.rtt.as_millis(),
+                        "NTP query successful"
+                    );
+                    let mut delay_ms = sample.delay_ms as f64;
+                    let mut stats_write = self.stats.write().await;
+                    if let Some(stat) = stats_write.get_mut(server) {
+                        // Huff-n-puff: learn from the uncorrected offset,
+                        // then take the delay-dependent part out.
+                        stat.asymmetry.record(delay_ms, result.offset_ms as f64);
+                        if self.config.query.clock_filter {
+                            let best = stat.filter.push(
+                                result.offset_ms,
+                                result.rtt,
+                                sample.precision_log2,
+                                sample.t4_instant,
+                            );
+                            if best.taken_at != sample.t4_instant {
+                                debug!(
+                                    server = %server,
+                                    offset_ms = best.offset_ms,
+                                    rtt_ms = best.delay.as_millis(),
+                                    "Clock filter kept an earlier, lower-delay sample"
+                                );
+                            }
+                            result.epoch_ms += best.offset_ms - result.offset_ms;
+                            result.offset_ms = best.offset_ms;
+                            result.rtt = best.delay;
+                            delay_ms = best.delay.as_secs_f64() * 1000.0;
+                        }
+                        stat.last_exchange = Some(sample);
+                        if calibration.asymmetry_enabled
+                            && let Some(estimate) = stat.asymmetry.estimate()
+                        {
+                            let correction = estimate.correction_ms(delay_ms, calibration);
+                            result.offset_ms -= correction;
+                            result.epoch_ms -= correction;
+                        }
+                        // Jitter and smoothed stats see the offset after the
+                        // asymmetry correction but before the learned bias, so a
+                        // bias estimate never feeds back into its own input.
+                        let was_disabled = stat.record_success(result.rtt);
+                        stat.record_offset(result.offset_ms);
+                        stat.record_smoothed(result.offset_ms, result.rtt);
+                        if was_disabled {
+                            info!(server = %server, "NTP server re-enabled after successful response");
+                        }
+                        // Bias estimation learns from the same uncorrected offset.
+                        raw_offsets.insert(server.clone(), result.offset_ms);
+                        if target.is_expanded() {
+                            // A calibration reference names the configured entry.
+                            raw_offsets
+                                .entry(target.origin.clone())
+                                .or_insert(result.offset_ms);
+                        }
+                        // Apply the learned per-server bias before selection;
+                        // only the candidate carries the corrected offset.
+                        if calibration.enabled
+                            && let Some(correction) = stat.bias.correction_ms(calibration)
+                        {
+                            result.offset_ms -= correction;
+                            result.epoch_ms -= correction;
+                        }
+                    }
+                    drop(stats_write);
+                    results.push(result);
+                }
+                Ok(Err(e)) => {
+                    warn!(server = %server, error = %e, "NTP query failed");
+                    self.record_server_failure(server).await;
+                    if let Some(kod) = e.downcast_ref::<KissOfDeath>() {
+                        self.record_kod(server, kod).await;
+                    }
+                }
+                Err(e) => {
+                    error!(server = %server, error = %e, "NTP query task panicked");
+                    self.record_server_failure(server).await;
+                }
+            }
+        }
+        results
+    }
+
+    /// Per-server jitter and smoothed stats for selection, accumulated across
+    /// prior syncs as well as this one.
+    async fn jitter_and_smoothed(&self) -> (HashMap<String, u64>, HashMap<String, SmoothedStats>) {
+        let stats_read = self.stats.read().await;
+        let jitter = stats_read
+            .iter()
+            .map(|(k, v)| (k.clone(), v.jitter_ms()))
+            .collect();
+        let smoothed = stats_read
+            .iter()
+            .filter_map(|(k, v)| Some((k.clone(), v.smoothed?)))
+            .collect();
+        (jitter, smoothed)
+    }
+
+    /// Convert a raw exchange from the injected `NtpClient` into a selection
+    /// candidate, applying the static offset/asymmetry biases.
+    fn to_ntp_result(
         server: String,
-        timeout_duration: Duration,
+        sample: &NtpSample,
         offset_bias_ms: i64,
         asymmetry_bias_ms: i64,
-    ) -> Result<NtpResult> {
-        let sample = client.query(&server, timeout_duration).await?;
+    ) -> NtpResult {
         let epoch_ms = sample.t4_unix_ms + sample.offset_ms + offset_bias_ms + asymmetry_bias_ms;
         let rtt = sample
             .t4_instant
             .saturating_duration_since(sample.t1_instant);
-        Ok(NtpResult {
+        NtpResult {
             server,
             epoch_ms,
             rtt,
@@ -327,13 +674,94 @@ impl NtpSyncer {
             precision_log2: sample.precision_log2,
             reference_id: sample.reference_id,
             timing_source: TimingSource::Measured,
-        })
+        }
+    }
+
+    /// Fold each agreer's raw residual against the calibration reference into
+    /// its bias estimate.  Skipped when the trusted reference did not answer.
+    async fn update_bias_estimates(
+        &self,
+        raw_offsets: &HashMap<String, i64>,
+        agreers: &[NtpResult],
+        consensus_offset_ms: i64,
+    ) {
+        let calibration = &self.config.calibration;
+        let Some(reference) = reference_offset_ms(calibration, raw_offsets, consensus_offset_ms)
+        else {
+            debug!("Calibration reference server did not answer; bias estimates unchanged");
+            return;
+        };
+        let mut stats_write = self.stats.write().await;
+        for agreer in agreers {
+            if let (Some(raw), Some(stat)) = (
+                raw_offsets.get(&agreer.server),
+                stats_write.get_mut(&agreer.server),
+            ) {
+                stat.bias
+                    .update((raw - reference) as f64, calibration.alpha);
+            }
+        }
+    }
+
+    /// True while a Kiss-o'-Death back-off or denial forbids querying `server`.
+    pub async fn is_suppressed(&self, server: &str) -> bool {
+        self.stats
+            .read()
+            .await
+            .get(server)
+            .is_some_and(|s| s.is_suppressed(Instant::now()))
     }
 
     pub async fn get_stats(&self) -> HashMap<String, ServerStats> {
         self.stats.read().await.clone()
     }
 
+    /// Apply a Kiss-o'-Death from `server` and queue it for the
+    /// `ntp_kod_received_total` metric.
+    async fn record_kod(&self, server: &str, kod: &KissOfDeath) {
+        let action = kod.action();
+        let base = Duration::from_secs(self.config.sync_interval_secs);
+        if let Some(stat) = self.stats.write().await.get_mut(server) {
+            stat.record_kod(action, base, Instant::now());
+            match action {
+                KodAction::BackOff => warn!(
+                    server = %server,
+                    until_secs = stat
+                        .kod_backoff_until
+                        .map(|t| t.saturating_duration_since(Instant::now()).as_secs()),
+                    "NTP server sent RATE kiss; backing off"
+                ),
+                KodAction::Disable => error!(
+                    server = %server,
+                    code = %kod.code(),
+                    "NTP server denied access; disabled until restart"
+                ),
+                KodAction::Failure => {}
+            }
+        }
+        self.kod_events.lock().push(KodEvent {
+            server: server.to_string(),
+            code: kod.code(),
+        });
+    }
+
+    /// Kiss-o'-Death replies received since the last call.
+    pub fn take_kod_events(&self) -> Vec<KodEvent> {
+        std::mem::take(&mut *self.kod_events.lock())
+    }
+
+    /// Start stats for newly resolved addresses and drop those of addresses a
+    /// pool no longer returns, keeping configured entries.
+    async fn track_targets(&self, targets: &[String], configured: &[String]) {
+        let mut stats_write = self.stats.write().await;
+        for server in targets {
+            stats_write
+                .entry(server.clone())
+                .or_insert_with(|| ServerStats::new(server.clone()));
+        }
+        stats_write.retain(|server, _| targets.contains(server) || configured.contains(server));
+    }
+
     async fn record_server_failure(&self, server: &str) {
         let mut stats_write = self.stats.write().await;
         if let Some(stat) = stats_write.get_mut(server) {
@@ -350,6 +778,64 @@ impl NtpSyncer {
     }
 }
 
+/// One upstream exchange, retried with jittered timeouts/delays so a single
+/// dropped UDP packet does not count as a server failure.
+struct RetryingQuery<'a> {
+    client: &'a dyn NtpClient,
+    server: &'a str,
+    timeout: Duration,
+    timeout_jitter: Duration,
+    retries: u32,
+    retry_delay: Duration,
+}
+
+impl RetryingQuery<'_> {
+    async fn run(&self) -> Result<NtpSample> {
+        let mut attempt = 0;
+        loop {
+            let timeout = self.timeout + jitter_up_to(self.timeout_jitter);
+            match self.client.query(self.server, timeout).await {
+                // A kiss code is an answer, not a lost packet: never retry it.
+                Err(e) if attempt < self.retries && e.downcast_ref::<KissOfDeath>().is_none() => {
+                    attempt += 1;
+                    let delay = self.retry_delay / 2 + jitter_up_to(self.retry_delay);
+                    debug!(
+                        server = %self.server,
+                        attempt,
+                        retries = self.retries,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %e,
+                        "NTP query failed, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                outcome => return outcome,
+            }
+        }
+    }
+}
+
+/// Start offset for query `index` of `count` when spreading queries evenly
+/// over `window_ms`.
+fn stagger_delay(window_ms: u64, index: usize, count: usize) -> Duration {
+    if window_ms == 0 || count <= 1 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(window_ms * index as u64 / count as u64)
+}
+
+/// Uniformly random duration in `[0, max)`; zero when `max` is zero.
+fn jitter_up_to(max: Duration) -> Duration {
+    let max_ms = max.as_millis() as u64;
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(rand::random::<u64>() % max_ms)
+}
+
+/// RTT advantage (ms) the best agreer needs over the current server to replace it.
+const STICKY_SWITCH_THRESHOLD_MS: i64 = 50;
+
 /// Pure sticky-server selection algorithm.
 ///
 /// Operates on the `agreers` list (post-gate, post-agreement-filter), not all results.
@@ -359,54 +845,76 @@ fn sticky_select(
     best: NtpResult,
     current_server: Option<&str>,
     switch_threshold_ms: i64,
-) -> (NtpResult, Option<String>) {
+) -> (NtpResult, Option<String>, StickyReason) {
     let Some(current) = current_server else {
         let s = best.server.clone();
-        return (best, Some(s));
+        return (best, Some(s), StickyReason::InitialSelection);
     };
 
     let Some(current_result) = agreers.iter().find(|r| r.server == current) else {
         let s = best.server.clone();
-        return (best, Some(s));
+        return (best, Some(s), StickyReason::CurrentNotAgreer);
     };
 
     if best.server == current {
-        return (current_result.clone(), None);
+        return (current_result.clone(), None, StickyReason::CurrentIsBest);
     }
 
     let rtt_diff_ms = current_result.rtt.as_millis() as i64 - best.rtt.as_millis() as i64;
     if rtt_diff_ms >= switch_threshold_ms {
         let s = best.server.clone();
-        (best, Some(s))
+        (best, Some(s), StickyReason::SignificantlyFaster)
     } else {
-        (current_result.clone(), None)
+        (
+            current_result.clone(),
+            None,
+            StickyReason::BelowSwitchThreshold,
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{SelectionConfig, SelectionStrategy};
+    use crate::config::{
+        AddressFamily, CalibrationConfig, DnsCheck, MonotonicScope, QueryConfig, SelectionConfig,
+        SourceBinding,
+    };
     use crate::ntp::client::{MockNtpClient, NtpSample};
+    use std::collections::VecDeque;
 
     fn make_ntp_config() -> Arc<NtpConfig> {
         Arc::new(NtpConfig {
-            servers: vec!["mock:123".to_string()],
-            timeout_secs: 2,
+            servers: vec![ServerConfig::new("mock:123")],
+            timeout_ms: 2000,
             sync_interval_secs: 30,
+            sync_interval_max_secs: 30,
+            poll_stable_offset_ms: 5,
+            max_step_ms: 0,
+            max_step_stepout: 3,
             probe_min_interval_secs: 10,
             probe_max_interval_secs: 20,
             max_staleness_secs: 120,
             require_sync: true,
-            selection_strategy: SelectionStrategy::AccuracyFirst,
             monotonic_output: true,
+            monotonic_scope: MonotonicScope::Global,
+            slew_rate_ppm: 0,
+            slew_max_offset_ms: 128,
+            drift_compensation: false,
+            leap_seconds_file: None,
             offset_bias_ms: 0,
             asymmetry_bias_ms: 0,
             max_consecutive_failures: 10,
+            address_family: AddressFamily::Any,
+            dns_check: DnsCheck::Warn,
+            source: SourceBinding::default(),
             selection: SelectionConfig {
                 min_quorum: 1,
                 ..SelectionConfig::default()
             },
+            query: QueryConfig::default(),
+            calibration: CalibrationConfig::default(),
+            keys: HashMap::new(),
         })
     }
 
@@ -435,28 +943,69 @@ mod tests {
     #[tokio::test]
     async fn test_ntp_syncer_creation() {
         let config = Arc::new(NtpConfig {
-            servers: vec!["time.google.com:123".to_string()],
-            timeout_secs: 2,
+            servers: vec![ServerConfig::new("time.google.com:123")],
+            timeout_ms: 2000,
             sync_interval_secs: 30,
+            sync_interval_max_secs: 30,
+            poll_stable_offset_ms: 5,
+            max_step_ms: 0,
+            max_step_stepout: 3,
             probe_min_interval_secs: 10,
             probe_max_interval_secs: 20,
             max_staleness_secs: 120,
             require_sync: true,
-            selection_strategy: SelectionStrategy::AccuracyFirst,
             monotonic_output: true,
+            monotonic_scope: MonotonicScope::Global,
+            slew_rate_ppm: 0,
+            slew_max_offset_ms: 128,
+            drift_compensation: false,
+            leap_seconds_file: None,
             offset_bias_ms: 0,
             asymmetry_bias_ms: 0,
             max_consecutive_failures: 10,
+            address_family: AddressFamily::Any,
+            dns_check: DnsCheck::Warn,
+            source: SourceBinding::default(),
             selection: SelectionConfig {
                 min_quorum: 1,
                 ..SelectionConfig::default()
             },
+            query: QueryConfig::default(),
+            calibration: CalibrationConfig::default(),
+            keys: HashMap::new(),
         });
         let syncer = NtpSyncer::new(config);
         let stats = syncer.get_stats().await;
         assert!(!stats.is_empty());
     }
 
+    #[tokio::test]
+    async fn server_set_can_be_changed_at_runtime() {
+        let client = Arc::new(MockNtpClient::err("unused"));
+        let syncer = NtpSyncer::with_client(make_ntp_config(), client);
+        let extra = ServerConfig::parse("extra:123;weight=2").unwrap();
+
+        syncer.add_server(extra.clone()).await.unwrap();
+        assert_eq!(syncer.servers(), vec!["mock:123", "extra:123"]);
+        assert!(syncer.get_stats().await.contains_key("extra:123"));
+        assert_eq!(
+            syncer.add_server(extra).await,
+            Err(ServerSetError::AlreadyConfigured)
+        );
+
+        syncer.remove_server("extra:123").await.unwrap();
+        assert_eq!(syncer.servers(), vec!["mock:123"]);
+        assert!(!syncer.get_stats().await.contains_key("extra:123"));
+        assert_eq!(
+            syncer.remove_server("extra:123").await,
+            Err(ServerSetError::NotConfigured)
+        );
+        assert_eq!(
+            syncer.remove_server("mock:123").await,
+            Err(ServerSetError::LastServer)
+        );
+    }
+
     #[tokio::test]
     async fn sync_populates_real_timing() {
         let sample = make_ntp_sample("mock:123");
@@ -489,22 +1038,36 @@ mod tests {
     async fn sync_applies_bias() {
         let sample = make_ntp_sample("mock:123");
         let mut config_val = NtpConfig {
-            servers: vec!["mock:123".to_string()],
-            timeout_secs: 2,
+            servers: vec![ServerConfig::new("mock:123")],
+            timeout_ms: 2000,
             sync_interval_secs: 30,
+            sync_interval_max_secs: 30,
+            poll_stable_offset_ms: 5,
+            max_step_ms: 0,
+            max_step_stepout: 3,
             probe_min_interval_secs: 10,
             probe_max_interval_secs: 20,
             max_staleness_secs: 120,
             require_sync: true,
-            selection_strategy: SelectionStrategy::AccuracyFirst,
             monotonic_output: true,
+            monotonic_scope: MonotonicScope::Global,
+            slew_rate_ppm: 0,
+            slew_max_offset_ms: 128,
+            drift_compensation: false,
+            leap_seconds_file: None,
             offset_bias_ms: 100,
             asymmetry_bias_ms: 50,
             max_consecutive_failures: 10,
+            address_family: AddressFamily::Any,
+            dns_check: DnsCheck::Warn,
+            source: SourceBinding::default(),
             selection: SelectionConfig {
                 min_quorum: 1,
                 ..SelectionConfig::default()
             },
+            query: QueryConfig::default(),
+            calibration: CalibrationConfig::default(),
+            keys: HashMap::new(),
         };
         config_val.offset_bias_ms = 100;
         config_val.asymmetry_bias_ms = 50;
@@ -533,7 +1096,7 @@ mod tests {
     async fn no_quorum_sync_returns_err_not_min_rtt_fallback() {
         let sample = make_ntp_sample("mock:123");
         let config = Arc::new(NtpConfig {
-            servers: vec!["mock:123".to_string()],
+            servers: vec![ServerConfig::new("mock:123")],
             selection: SelectionConfig {
                 min_quorum: 2, // impossible with 1 server
                 ..SelectionConfig::default()
@@ -571,7 +1134,7 @@ mod tests {
 
         // Second syncer: same mock data but min_quorum=2 → Err
         let config_fail = Arc::new(NtpConfig {
-            servers: vec!["mock:123".to_string()],
+            servers: vec![ServerConfig::new("mock:123")],
             selection: SelectionConfig {
                 min_quorum: 2,
                 ..SelectionConfig::default()
@@ -590,6 +1153,381 @@ mod tests {
         let _ = saved_epoch; // used above
     }
 
+    // ── Query scheduling tests ───────────────────────────────────────────────
+
+    /// Client that records the peak number of concurrent queries.
+    struct ConcurrencyProbeClient {
+        sample: NtpSample,
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl NtpClient for ConcurrencyProbeClient {
+        async fn query(&self, server: &str, _timeout: Duration) -> Result<NtpSample> {
+            use std::sync::atomic::Ordering;
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(NtpSample {
+                server: server.to_string(),
+                ..self.sample.clone()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_caps_in_flight_queries() {
+        let servers: Vec<String> = (0..6).map(|i| format!("mock{i}:123")).collect();
+        let config = Arc::new(NtpConfig {
+            servers: servers.iter().map(|s| ServerConfig::new(s)).collect(),
+            query: QueryConfig {
+                max_in_flight: 2,
+                ..QueryConfig::default()
+            },
+            ..(*make_ntp_config()).clone()
+        });
+        let client = Arc::new(ConcurrencyProbeClient {
+            sample: make_ntp_sample("mock0:123"),
+            in_flight: Default::default(),
+            peak: Default::default(),
+        });
+        let syncer = NtpSyncer::with_client(config, client.clone());
+
+        syncer.sync().await.expect("sync should succeed");
+        assert_eq!(client.peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    /// Client that fails the first `failures` queries, then succeeds.
+    struct FlakyClient {
+        sample: NtpSample,
+        failures: usize,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl NtpClient for FlakyClient {
+        async fn query(&self, _server: &str, _timeout: Duration) -> Result<NtpSample> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if call < self.failures {
+                anyhow::bail!("simulated packet loss");
+            }
+            Ok(self.sample.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_retries_before_counting_failure() {
+        let flaky = || {
+            Arc::new(FlakyClient {
+                sample: make_ntp_sample("mock:123"),
+                failures: 1,
+                calls: Default::default(),
+            })
+        };
+        let with_retries = |retries: u32, overrides: &[(&str, u32)]| {
+            Arc::new(NtpConfig {
+                query: QueryConfig {
+                    retries,
+                    retries_by_server: overrides.iter().map(|(s, n)| (s.to_string(), *n)).collect(),
+                    retry_delay_ms: 0,
+                    ..QueryConfig::default()
+                },
+                ..(*make_ntp_config()).clone()
+            })
+        };
+
+        // No retries: the dropped packet fails the sync and is recorded.
+        let syncer = NtpSyncer::with_client(with_retries(0, &[]), flaky());
+        assert!(syncer.sync().await.is_err());
+        assert_eq!(syncer.get_stats().await["mock:123"].total_failures, 1);
+
+        // One retry: the second attempt succeeds and no failure is recorded.
+        let syncer = NtpSyncer::with_client(with_retries(1, &[]), flaky());
+        syncer.sync().await.expect("retry should recover");
+        assert_eq!(syncer.get_stats().await["mock:123"].total_failures, 0);
+
+        // Per-server override wins over the global budget.
+        let syncer = NtpSyncer::with_client(with_retries(0, &[("mock:123", 1)]), flaky());
+        syncer.sync().await.expect("override should allow a retry");
+    }
+
+    /// Client replaying queued `(delay_ms, offset_ms)` exchanges in order
+    /// (a 500 ms delay once they run out), recording the timeout of every
+    /// query.
+    struct DelaySequenceClient {
+        sample: NtpSample,
+        exchanges: std::sync::Mutex<VecDeque<(i64, i64)>>,
+        timeouts: std::sync::Mutex<Vec<Duration>>,
+    }
+
+    impl DelaySequenceClient {
+        fn new(exchanges: impl IntoIterator<Item = (i64, i64)>) -> Self {
+            Self {
+                sample: make_ntp_sample("mock:123"),
+                exchanges: std::sync::Mutex::new(exchanges.into_iter().collect()),
+                timeouts: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl NtpClient for DelaySequenceClient {
+        async fn query(&self, _server: &str, timeout: Duration) -> Result<NtpSample> {
+            self.timeouts.lock().unwrap().push(timeout);
+            let (delay_ms, offset_ms) = self
+                .exchanges
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or((500, 50));
+            let t1_instant = Instant::now();
+            Ok(NtpSample {
+                t1_instant,
+                t4_instant: t1_instant + Duration::from_millis(delay_ms as u64),
+                delay_ms,
+                offset_ms,
+                ..self.sample.clone()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn per_server_timeout_and_samples_are_honored() {
+        let client = Arc::new(DelaySequenceClient::new([(150, 15), (40, 4), (90, 9)]));
+        let config = Arc::new(NtpConfig {
+            servers: vec![ServerConfig::parse("mock:123;timeout=500ms;samples=3").unwrap()],
+            ..(*make_ntp_config()).clone()
+        });
+        let syncer = NtpSyncer::with_client(config, client.clone());
+
+        let outcome = syncer.sync().await.expect("sync should succeed");
+        assert_eq!(outcome.result.offset_ms, 4, "lowest-delay sample wins");
+        assert_eq!(
+            *client.timeouts.lock().unwrap(),
+            vec![Duration::from_millis(500); 3]
+        );
+    }
+
+    /// Client whose every query is answered with the given kiss code.
+    struct KissClient {
+        code: [u8; 4],
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl NtpClient for KissClient {
+        async fn query(&self, _server: &str, _timeout: Duration) -> Result<NtpSample> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(KissOfDeath {
+                reference_id: u32::from_be_bytes(self.code),
+            }
+            .into())
+        }
+    }
+
+    #[tokio::test]
+    async fn kiss_of_death_suppresses_server_and_is_reported() {
+        use std::sync::atomic::Ordering;
+        let config = Arc::new(NtpConfig {
+            query: QueryConfig {
+                retries: 3,
+                retry_delay_ms: 0,
+                ..QueryConfig::default()
+            },
+            ..(*make_ntp_config()).clone()
+        });
+
+        for code in [*b"RATE", *b"DENY"] {
+            let client = Arc::new(KissClient {
+                code,
+                calls: Default::default(),
+            });
+            let syncer = NtpSyncer::with_client(config.clone(), client.clone());
+
+            assert!(syncer.sync().await.is_err());
+            assert!(syncer.is_suppressed("mock:123").await);
+            // Not retried, and the next sync does not query the server at all.
+            assert_eq!(client.calls.load(Ordering::SeqCst), 1);
+            let Err(err) = syncer.sync().await else {
+                panic!("suppressed server must not be queried");
+            };
+            assert!(format!("{err}").contains("Kiss-o'-Death"));
+            assert_eq!(client.calls.load(Ordering::SeqCst), 1);
+
+            let code = String::from_utf8(code.to_vec()).unwrap();
+            assert_eq!(
+                syncer.take_kod_events(),
+                vec![KodEvent {
+                    server: "mock:123".to_string(),
+                    code,
+                }]
+            );
+            assert!(syncer.take_kod_events().is_empty());
+        }
+    }
+
+    /// Client recording every queried server; servers in `down` time out.
+    struct TieredClient {
+        down: Vec<&'static str>,
+        queried: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NtpClient for TieredClient {
+        async fn query(&self, server: &str, _timeout: Duration) -> Result<NtpSample> {
+            self.queried.lock().unwrap().push(server.to_string());
+            if self.down.contains(&server) {
+                anyhow::bail!("timed out");
+            }
+            Ok(make_ntp_sample(server))
+        }
+    }
+
+    #[tokio::test]
+    async fn fallback_tier_is_queried_only_without_primary_quorum() {
+        let config = Arc::new(NtpConfig {
+            servers: ["internal-a:123", "internal-b:123", "pool:123;tier=fallback"]
+                .into_iter()
+                .map(|s| ServerConfig::parse(s).unwrap())
+                .collect(),
+            selection: SelectionConfig {
+                min_quorum: 2,
+                ..SelectionConfig::default()
+            },
+            ..(*make_ntp_config()).clone()
+        });
+
+        let client = Arc::new(TieredClient {
+            down: vec![],
+            queried: Default::default(),
+        });
+        let syncer = NtpSyncer::with_client(config.clone(), client.clone());
+        let outcome = syncer.sync().await.expect("primary tier has a quorum");
+        assert!(!outcome.fallback_tier);
+        assert_eq!(
+            *client.queried.lock().unwrap(),
+            ["internal-a:123", "internal-b:123"]
+        );
+
+        let client = Arc::new(TieredClient {
+            down: vec!["internal-b:123"],
+            queried: Default::default(),
+        });
+        let syncer = NtpSyncer::with_client(config, client.clone());
+        let outcome = syncer
+            .sync()
+            .await
+            .expect("fallback tier completes the quorum");
+        assert!(outcome.fallback_tier);
+        assert!(
+            client
+                .queried
+                .lock()
+                .unwrap()
+                .contains(&"pool:123".to_string())
+        );
+    }
+
+    // ── Bias calibration tests ───────────────────────────────────────────────
+
+    /// Client returning a fixed offset per server.
+    struct PerServerOffsetClient {
+        sample: NtpSample,
+        offsets: HashMap<String, i64>,
+    }
+
+    #[async_trait::async_trait]
+    impl NtpClient for PerServerOffsetClient {
+        async fn query(&self, server: &str, _timeout: Duration) -> Result<NtpSample> {
+            Ok(NtpSample {
+                server: server.to_string(),
+                offset_ms: self.offsets[server],
+                ..self.sample.clone()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn calibration_corrects_consistently_biased_server() {
+        let calibration = CalibrationConfig {
+            enabled: true,
+            reference_server: Some("ref:123".to_string()),
+            alpha: 0.5,
+            min_samples: 3,
+            max_correction_ms: 50,
+            asymmetry_enabled: false,
+        };
+        let config = Arc::new(NtpConfig {
+            servers: vec![
+                ServerConfig::new("ref:123"),
+                ServerConfig::new("biased:123"),
+            ],
+            calibration: calibration.clone(),
+            ..(*make_ntp_config()).clone()
+        });
+        let client = Arc::new(PerServerOffsetClient {
+            sample: make_ntp_sample("ref:123"),
+            offsets: HashMap::from([("ref:123".to_string(), 0), ("biased:123".to_string(), 20)]),
+        });
+        let syncer = NtpSyncer::with_client(config, client);
+
+        for _ in 0..3 {
+            syncer.sync().await.expect("sync should succeed");
+        }
+        let stats = syncer.get_stats().await;
+        assert_eq!(
+            stats["biased:123"].bias.correction_ms(&calibration),
+            Some(20)
+        );
+        assert_eq!(stats["ref:123"].bias.correction_ms(&calibration), Some(0));
+
+        // Warmed up: both servers now agree on the reference offset.
+        let outcome = syncer.sync().await.expect("sync should succeed");
+        assert_eq!(outcome.result.offset_ms, 0);
+    }
+
+    #[tokio::test]
+    async fn clock_filter_keeps_minimum_delay_sample() {
+        let client = Arc::new(DelaySequenceClient::new([(20, 10), (200, 90), (15, 12)]));
+        let syncer = NtpSyncer::with_client(make_ntp_config(), client);
+
+        assert_eq!(syncer.sync().await.unwrap().result.offset_ms, 10);
+        // The congested exchange is filtered out in favour of the fast one.
+        let outcome = syncer.sync().await.unwrap();
+        assert_eq!(outcome.result.offset_ms, 10);
+        assert_eq!(outcome.result.rtt, Duration::from_millis(20));
+        assert_eq!(syncer.sync().await.unwrap().result.offset_ms, 12);
+    }
+
+    #[tokio::test]
+    async fn clock_filter_can_be_disabled() {
+        let client = Arc::new(DelaySequenceClient::new([(20, 10), (200, 90)]));
+        let config = Arc::new(NtpConfig {
+            query: QueryConfig {
+                clock_filter: false,
+                ..QueryConfig::default()
+            },
+            ..(*make_ntp_config()).clone()
+        });
+        let syncer = NtpSyncer::with_client(config, client);
+
+        syncer.sync().await.unwrap();
+        assert_eq!(syncer.sync().await.unwrap().result.offset_ms, 90);
+    }
+
+    #[test]
+    fn stagger_delay_spreads_evenly() {
+        assert_eq!(stagger_delay(0, 3, 4), Duration::ZERO);
+        assert_eq!(stagger_delay(400, 0, 1), Duration::ZERO);
+        let delays: Vec<_> = (0..4).map(|i| stagger_delay(400, i, 4)).collect();
+        assert_eq!(
+            delays,
+            [0, 100, 200, 300].map(Duration::from_millis).to_vec()
+        );
+    }
+
     // ── sticky_select unit tests ──────────────────────────────────────────────
 
     fn make_result(server: &str, rtt_ms: u64, offset_ms: i64) -> NtpResult {
@@ -609,9 +1547,10 @@ mod tests {
         let best = r1.clone();
         let agreers = vec![r1, r2];
 
-        let (selected, new_sticky) = sticky_select(&agreers, best, None, 50);
+        let (selected, new_sticky, reason) = sticky_select(&agreers, best, None, 50);
         assert_eq!(selected.server, "a:123");
         assert_eq!(new_sticky.as_deref(), Some("a:123"));
+        assert_eq!(reason, StickyReason::InitialSelection);
     }
 
     #[test]
@@ -619,9 +1558,10 @@ mod tests {
         let r1 = make_result("a:123", 10, 5);
         let agreers = vec![r1.clone()];
 
-        let (selected, new_sticky) = sticky_select(&agreers, r1, Some("old:123"), 50);
+        let (selected, new_sticky, reason) = sticky_select(&agreers, r1, Some("old:123"), 50);
         assert_eq!(selected.server, "a:123");
         assert_eq!(new_sticky.as_deref(), Some("a:123"));
+        assert_eq!(reason, StickyReason::CurrentNotAgreer);
     }
 
     #[test]
@@ -630,9 +1570,10 @@ mod tests {
         let agreers = vec![r1.clone()];
         let best = r1;
 
-        let (selected, new_sticky) = sticky_select(&agreers, best, Some("a:123"), 50);
+        let (selected, new_sticky, reason) = sticky_select(&agreers, best, Some("a:123"), 50);
         assert_eq!(selected.server, "a:123");
         assert!(new_sticky.is_none(), "sticky should not change");
+        assert_eq!(reason, StickyReason::CurrentIsBest);
     }
 
     #[test]
@@ -641,9 +1582,10 @@ mod tests {
         let new_best = make_result("b:123", 20, 8);
         let agreers = vec![current.clone(), new_best.clone()];
 
-        let (selected, new_sticky) = sticky_select(&agreers, new_best, Some("a:123"), 50);
+        let (selected, new_sticky, reason) = sticky_select(&agreers, new_best, Some("a:123"), 50);
         assert_eq!(selected.server, "a:123");
         assert!(new_sticky.is_none());
+        assert_eq!(reason, StickyReason::BelowSwitchThreshold);
     }
 
     #[test]
@@ -652,9 +1594,10 @@ mod tests {
         let new_best = make_result("b:123", 20, 8);
         let agreers = vec![current.clone(), new_best.clone()];
 
-        let (selected, new_sticky) = sticky_select(&agreers, new_best, Some("a:123"), 50);
+        let (selected, new_sticky, reason) = sticky_select(&agreers, new_best, Some("a:123"), 50);
         assert_eq!(selected.server, "b:123");
         assert_eq!(new_sticky.as_deref(), Some("b:123"));
+        assert_eq!(reason, StickyReason::SignificantlyFaster);
     }
 
     #[test]
@@ -663,8 +1606,9 @@ mod tests {
         let new_best = make_result("b:123", 20, 8);
         let agreers = vec![current.clone(), new_best.clone()];
 
-        let (selected, new_sticky) = sticky_select(&agreers, new_best, Some("a:123"), 50);
+        let (selected, new_sticky, reason) = sticky_select(&agreers, new_best, Some("a:123"), 50);
         assert_eq!(selected.server, "b:123");
         assert_eq!(new_sticky.as_deref(), Some("b:123"));
+        assert_eq!(reason, StickyReason::SignificantlyFaster);
     }
 }
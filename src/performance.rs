@@ -1,7 +1,69 @@
 use arc_swap::ArcSwap;
+use chrono::SecondsFormat;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 
+use crate::encoding::{Encoding, protobuf};
+use crate::timezone;
+
+/// Single-format renderings served by `/time/iso`, `/time/unix` and
+/// `/time/rfc2822`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// RFC 3339 UTC with milliseconds, e.g. `2024-01-15T12:00:00.000Z`.
+    Iso,
+    /// Whole Unix seconds, e.g. `1705320000`.
+    Unix,
+    /// RFC 2822, e.g. `Mon, 15 Jan 2024 12:00:00 +0000`.
+    Rfc2822,
+}
+
+impl TimestampFormat {
+    const ALL: [Self; 3] = [Self::Iso, Self::Unix, Self::Rfc2822];
+
+    /// Finest unit the rendering shows (ms): every epoch within one unit
+    /// renders identically, so one cache entry serves the whole unit.
+    fn resolution_ms(self) -> i64 {
+        match self {
+            Self::Iso => 1,
+            Self::Unix | Self::Rfc2822 => 1000,
+        }
+    }
+
+    /// The bare timestamp, as served with `Accept: text/plain`.
+    pub fn render(self, epoch_ms: i64) -> String {
+        match self {
+            Self::Iso => {
+                timezone::utc_datetime(epoch_ms).to_rfc3339_opts(SecondsFormat::Millis, true)
+            }
+            Self::Unix => epoch_ms.div_euclid(1000).to_string(),
+            Self::Rfc2822 => timezone::utc_datetime(epoch_ms).to_rfc2822(),
+        }
+    }
+
+    /// The timestamp as a JSON value: a number for `Unix`, a string otherwise.
+    pub fn render_json(self, epoch_ms: i64) -> String {
+        match self {
+            Self::Unix => self.render(epoch_ms),
+            _ => format!(r#""{}""#, self.render(epoch_ms)),
+        }
+    }
+}
+
+/// One cached binary `/time` body, valid for `epoch_ms` and staleness `stale`.
+struct EncodedEntry {
+    epoch_ms: i64,
+    stale: bool,
+    body: Arc<Vec<u8>>,
+}
+
+/// One cached rendering, valid for every epoch in `unit`.
+struct FormattedEntry {
+    unit: i64,
+    text: Arc<String>,
+    json: Arc<String>,
+}
+
 /// Zero-copy time cache - pre-serialized JSON responses
 /// Updates are lock-free using arc-swap
 pub struct TimeCache {
@@ -20,9 +82,16 @@ pub struct TimeCache {
     // Anchor for the monotonic millis counter above.
     start_instant: std::time::Instant,
 
+    // Latest rendering per `TimestampFormat`, indexed by its position in `ALL`.
+    formatted: [ArcSwap<FormattedEntry>; 3],
+
+    // Latest binary body per encoding, indexed by position in `Encoding::BINARY`.
+    encoded: [ArcSwap<EncodedEntry>; 3],
+
     // Configuration
     message_ok: String,
     message_ok_cache: String,
+    epoch_as_string: bool,
 }
 
 impl TimeCache {
@@ -35,11 +104,32 @@ impl TimeCache {
             json_stale: Arc::new(ArcSwap::from_pointee((*initial_json).clone())),
             last_update: AtomicI64::new(0),
             start_instant: std::time::Instant::now(),
+            formatted: TimestampFormat::ALL.map(|format| {
+                ArcSwap::from_pointee(FormattedEntry {
+                    unit: i64::MIN,
+                    text: Arc::new(format.render(0)),
+                    json: Arc::new(format.render_json(0)),
+                })
+            }),
+            encoded: Encoding::BINARY.map(|_| {
+                ArcSwap::from_pointee(EncodedEntry {
+                    epoch_ms: i64::MIN,
+                    stale: false,
+                    body: Arc::new(Vec::new()),
+                })
+            }),
             message_ok,
             message_ok_cache,
+            epoch_as_string: false,
         }
     }
 
+    /// Serialize `data` as a JSON string instead of a number (`EPOCH_AS_STRING`).
+    pub fn with_epoch_as_string(mut self, epoch_as_string: bool) -> Self {
+        self.epoch_as_string = epoch_as_string;
+        self
+    }
+
     /// Update cache with new time (lock-free, atomic).
     ///
     /// Always builds both JSON variants (fresh and stale) so that
@@ -62,13 +152,18 @@ impl TimeCache {
 
         // Pre-serialize both variants. They are tiny and only run during
         // NTP sync, not on the hot /time path.
+        let data = if self.epoch_as_string {
+            format!(r#""{epoch_ms}""#)
+        } else {
+            epoch_ms.to_string()
+        };
         let fresh_json = format!(
             r#"{{"data":{},"message":"{}","status":200}}"#,
-            epoch_ms, self.message_ok
+            data, self.message_ok
         );
         let stale_json = format!(
             r#"{{"data":{},"message":"{}","status":200}}"#,
-            epoch_ms, self.message_ok_cache
+            data, self.message_ok_cache
         );
 
         // Lock-free atomic store — each slot always holds the correct variant.
@@ -85,6 +180,73 @@ impl TimeCache {
             self.json_fresh.load_full()
         }
     }
+
+    /// The `/time` body for `epoch_ms` in a binary `encoding` (zero-copy
+    /// when the same epoch was served last).  MessagePack and CBOR mirror
+    /// the JSON body; protobuf is a `TimeResponse` whose `data` is always
+    /// an int64.
+    ///
+    /// # Panics
+    /// If `encoding` is JSON; use [`get_json`](Self::get_json) for that.
+    pub fn get_encoded(&self, encoding: Encoding, epoch_ms: i64, is_stale: bool) -> Arc<Vec<u8>> {
+        let index = Encoding::BINARY
+            .iter()
+            .position(|e| *e == encoding)
+            .expect("get_encoded takes a binary encoding");
+        let slot = &self.encoded[index];
+        let entry = slot.load();
+        if entry.epoch_ms == epoch_ms && entry.stale == is_stale {
+            return entry.body.clone();
+        }
+        let message = if is_stale {
+            &self.message_ok_cache
+        } else {
+            &self.message_ok
+        };
+        let body = Arc::new(match encoding {
+            Encoding::Protobuf => protobuf::time_response(epoch_ms, message, 200),
+            _ => {
+                let data = if self.epoch_as_string {
+                    serde_json::Value::String(epoch_ms.to_string())
+                } else {
+                    serde_json::Value::from(epoch_ms)
+                };
+                encoding.encode(&serde_json::json!({
+                    "data": data,
+                    "message": message,
+                    "status": 200,
+                }))
+            }
+        });
+        slot.store(Arc::new(EncodedEntry {
+            epoch_ms,
+            stale: is_stale,
+            body: body.clone(),
+        }));
+        body
+    }
+
+    /// `epoch_ms` rendered as `format`, bare or as JSON (zero-copy on a hit).
+    /// Renders only when the epoch has moved into a new unit of the format's
+    /// resolution, so `/time/unix` and `/time/rfc2822` format once a second.
+    pub fn get_formatted(&self, format: TimestampFormat, epoch_ms: i64, json: bool) -> Arc<String> {
+        let slot = &self.formatted[format as usize];
+        let unit = epoch_ms.div_euclid(format.resolution_ms());
+        let mut entry = slot.load_full();
+        if entry.unit != unit {
+            entry = Arc::new(FormattedEntry {
+                unit,
+                text: Arc::new(format.render(epoch_ms)),
+                json: Arc::new(format.render_json(epoch_ms)),
+            });
+            slot.store(entry.clone());
+        }
+        if json {
+            entry.json.clone()
+        } else {
+            entry.text.clone()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +275,9 @@ pub struct LockFreeMetrics {
 
     // Cache metrics
     pub cache_hits: AtomicU64,
+
+    // WebSocket stream QoS
+    pub stream: StreamMetrics,
 }
 
 impl LockFreeMetrics {
@@ -125,6 +290,7 @@ impl LockFreeMetrics {
             min_latency_us: AtomicU64::new(u64::MAX),
             max_latency_us: AtomicU64::new(0),
             cache_hits: AtomicU64::new(0),
+            stream: StreamMetrics::default(),
         }
     }
 
@@ -198,6 +364,64 @@ impl LockFreeMetrics {
     }
 }
 
+/// WebSocket stream QoS aggregated over all connections (lock-free).
+#[derive(Default)]
+pub struct StreamMetrics {
+    pub active_connections: AtomicU64,
+    pub ticks_sent: AtomicU64,
+    pub ticks_dropped: AtomicU64,
+    /// Messages currently queued, summed over open connections.
+    pub queue_depth: AtomicU64,
+    /// Deepest single-connection queue seen since start.
+    pub max_queue_depth: AtomicU64,
+    /// Enqueue-to-written latency of delivered ticks (microseconds).
+    pub total_send_latency_us: AtomicU64,
+    pub max_send_latency_us: AtomicU64,
+}
+
+impl StreamMetrics {
+    pub fn connection_opened(&self) {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn connection_closed(&self) {
+        self.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn record_sent(&self, latency_us: u64) {
+        self.ticks_sent.fetch_add(1, Ordering::Relaxed);
+        self.total_send_latency_us
+            .fetch_add(latency_us, Ordering::Relaxed);
+        self.max_send_latency_us
+            .fetch_max(latency_us, Ordering::Relaxed);
+    }
+
+    pub fn record_dropped(&self, count: u64) {
+        self.ticks_dropped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Apply one connection's queue-depth change from `previous` to `current`.
+    pub fn adjust_queue_depth(&self, previous: u64, current: u64) {
+        if current >= previous {
+            self.queue_depth
+                .fetch_add(current - previous, Ordering::Relaxed);
+        } else {
+            self.queue_depth
+                .fetch_sub(previous - current, Ordering::Relaxed);
+        }
+        self.max_queue_depth.fetch_max(current, Ordering::Relaxed);
+    }
+
+    pub fn avg_send_latency_us(&self) -> f64 {
+        let sent = self.ticks_sent.load(Ordering::Relaxed);
+        if sent > 0 {
+            self.total_send_latency_us.load(Ordering::Relaxed) as f64 / sent as f64
+        } else {
+            0.0
+        }
+    }
+}
+
 impl Default for LockFreeMetrics {
     fn default() -> Self {
         Self::new()
@@ -241,6 +465,65 @@ impl LockFreeMetrics {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_time_cache_epoch_as_string() {
+        let cache =
+            TimeCache::new("done".to_string(), "done".to_string()).with_epoch_as_string(true);
+        cache.update(1234567890000, false);
+        assert_eq!(
+            *cache.get_json(false),
+            r#"{"data":"1234567890000","message":"done","status":200}"#
+        );
+    }
+
+    #[test]
+    fn test_time_cache_formats() {
+        let cache = TimeCache::new("done".to_string(), "done".to_string());
+        let epoch_ms = 1_705_320_000_123; // 2024-01-15T12:00:00.123Z
+        let get = |format, json| (*cache.get_formatted(format, epoch_ms, json)).clone();
+        assert_eq!(get(TimestampFormat::Iso, false), "2024-01-15T12:00:00.123Z");
+        assert_eq!(
+            get(TimestampFormat::Iso, true),
+            r#""2024-01-15T12:00:00.123Z""#
+        );
+        assert_eq!(get(TimestampFormat::Unix, false), "1705320000");
+        assert_eq!(get(TimestampFormat::Unix, true), "1705320000");
+        assert_eq!(
+            get(TimestampFormat::Rfc2822, true),
+            r#""Mon, 15 Jan 2024 12:00:00 +0000""#
+        );
+
+        // Same second: the cached rendering is reused.
+        let first = cache.get_formatted(TimestampFormat::Rfc2822, epoch_ms, false);
+        let again = cache.get_formatted(TimestampFormat::Rfc2822, epoch_ms + 500, false);
+        assert!(Arc::ptr_eq(&first, &again));
+        let next = cache.get_formatted(TimestampFormat::Rfc2822, epoch_ms + 1000, false);
+        assert_eq!(*next, "Mon, 15 Jan 2024 12:00:01 +0000");
+    }
+
+    #[test]
+    fn test_time_cache_binary_encodings() {
+        let cache = TimeCache::new("done".to_string(), "done (cached)".to_string());
+        let msgpack = cache.get_encoded(Encoding::MsgPack, 1, false);
+        assert_eq!(
+            *msgpack,
+            crate::encoding::to_msgpack(&serde_json::json!({
+                "data": 1, "message": "done", "status": 200
+            }))
+        );
+        assert!(Arc::ptr_eq(
+            &msgpack,
+            &cache.get_encoded(Encoding::MsgPack, 1, false)
+        ));
+
+        let stale = cache.get_encoded(Encoding::Cbor, 1, true);
+        assert!(stale.windows(13).any(|w| w == b"done (cached)"));
+        assert_eq!(
+            *cache.get_encoded(Encoding::Protobuf, 150, false),
+            protobuf::time_response(150, "done", 200)
+        );
+    }
+
     #[test]
     fn test_time_cache_update() {
         let cache = TimeCache::new("done".to_string(), "done (cached)".to_string());
@@ -332,4 +615,25 @@ mod tests {
 
         assert_eq!(metrics.cache_hit_rate(), 2.0 / 3.0);
     }
+
+    #[test]
+    fn test_stream_metrics_aggregate_connections() {
+        let stream = StreamMetrics::default();
+        stream.connection_opened();
+        stream.connection_opened();
+        stream.adjust_queue_depth(0, 3);
+        stream.adjust_queue_depth(0, 1);
+        stream.adjust_queue_depth(3, 0);
+        assert_eq!(stream.queue_depth.load(Ordering::Relaxed), 1);
+        assert_eq!(stream.max_queue_depth.load(Ordering::Relaxed), 3);
+
+        stream.record_sent(1_000);
+        stream.record_sent(3_000);
+        stream.record_dropped(2);
+        stream.connection_closed();
+        assert_eq!(stream.avg_send_latency_us(), 2_000.0);
+        assert_eq!(stream.max_send_latency_us.load(Ordering::Relaxed), 3_000);
+        assert_eq!(stream.ticks_dropped.load(Ordering::Relaxed), 2);
+        assert_eq!(stream.active_connections.load(Ordering::Relaxed), 1);
+    }
 }
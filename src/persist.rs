@@ -22,6 +22,7 @@ mod tests {
             selected_server: Some("pool.ntp.org".to_string()),
             selected_provider: None,
             last_successful_ntp_sync_unix_ms: Some(saved_at_unix_ms),
+            last_served_ms: Some(epoch_ms + 250),
         }
     }
 
@@ -43,6 +44,7 @@ mod tests {
         assert_eq!(loaded.saved_epoch_ms, epoch_ms);
         assert_eq!(loaded.saved_at_unix_ms, now_ms);
         assert_eq!(loaded.selected_server.as_deref(), Some("pool.ntp.org"));
+        assert_eq!(loaded.last_served_ms, Some(epoch_ms + 250));
         // The effective epoch after elapsed adjustment should be ≥ epoch_ms
         let elapsed = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -86,6 +88,19 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn load_state_accepts_files_without_last_served_ms() {
+        let path = tmp_path("legacy");
+        let mut legacy = serde_json::to_value(make_state(1_700_000_000_000, 0)).unwrap();
+        legacy.as_object_mut().unwrap().remove("last_served_ms");
+        std::fs::write(&path, legacy.to_string()).expect("write");
+
+        let loaded = load_state(&path).expect("load").expect("some");
+        assert_eq!(loaded.last_served_ms, None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn load_state_returns_none_for_missing_file() {
         let result = load_state("/tmp/ntp_time_json_api_nonexistent_9182736.json")
@@ -137,6 +152,10 @@ pub struct PersistedState {
     /// Unix epoch ms of the last successful NTP sync (same as saved_at_unix_ms
     /// when saved immediately after sync).
     pub last_successful_ntp_sync_unix_ms: Option<i64>,
+    /// Highest epoch_ms served when this snapshot was taken; restored as the
+    /// monotonic floor on startup.  Absent in files from older releases.
+    #[serde(default)]
+    pub last_served_ms: Option<i64>,
 }
 
 /// Write a `PersistedState` to `path` atomically (write-then-rename).
@@ -0,0 +1,408 @@
+//! Tick generation shared by every streaming API (`/stream` and the gRPC
+//! `StreamTime*` RPCs).
+//!
+//! [`StreamHub`] runs one ticker per interval and format.  A ticker wakes on
+//! NTP-time boundaries, reads the time base and evaluates quality and
+//! staleness once, renders the `/stream` JSON body once, and broadcasts the
+//! result to every subscriber.  Transports only adapt a [`Tick`] to their
+//! wire format and flow control, so their timing, numbering and staleness
+//! reporting cannot diverge.
+
+use crate::config::WsConfig;
+use crate::http::handlers::epoch_json;
+use crate::http::state::{AppState, TimeQuality};
+use crate::timebase::NANOS_PER_MS;
+use axum::extract::ws::Utf8Bytes;
+use parking_lot::Mutex;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::sleep;
+use tracing::debug;
+
+/// Longest tick interval a client may request, in milliseconds.
+pub const MAX_STREAM_INTERVAL_MS: u64 = 60_000;
+
+/// Check a client-requested tick interval against the allowed range.
+pub(crate) fn validate_interval(interval_ms: u64, ws: &WsConfig) -> Result<u64, String> {
+    if !(ws.min_update_interval_ms..=MAX_STREAM_INTERVAL_MS).contains(&interval_ms) {
+        return Err(format!(
+            "interval_ms must be between {} and {MAX_STREAM_INTERVAL_MS}",
+            ws.min_update_interval_ms
+        ));
+    }
+    Ok(interval_ms)
+}
+
+/// Body of the stream ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamFormat {
+    /// Time, quality and sync provenance.
+    Full,
+    /// Just `epoch_ms` and `sequence`.
+    Epoch,
+    /// Just `iso8601` and `sequence`.
+    Iso,
+    /// Just `epoch_ns` and `sequence`.
+    UnixNs,
+}
+
+impl StreamFormat {
+    pub(crate) fn parse(raw: &str) -> Result<Self, String> {
+        match raw.to_lowercase().as_str() {
+            "full" => Ok(StreamFormat::Full),
+            "epoch" => Ok(StreamFormat::Epoch),
+            "iso" => Ok(StreamFormat::Iso),
+            "unix_ns" => Ok(StreamFormat::UnixNs),
+            _ => Err("format must be one of full, epoch, iso, unix_ns".to_string()),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamFormat::Full => "full",
+            StreamFormat::Epoch => "epoch",
+            StreamFormat::Iso => "iso",
+            StreamFormat::UnixNs => "unix_ns",
+        }
+    }
+}
+
+/// Ticks buffered per subscriber of the shared broadcast; a subscriber
+/// further behind skips ahead and counts the gap as dropped.
+const TICK_BROADCAST_CAPACITY: usize = 16;
+
+/// One tick, as broadcast to every subscriber of a ticker.
+#[derive(Debug, Clone)]
+pub(crate) struct Tick {
+    /// Boundary index since the epoch while synced, see [`run_ticker`].
+    pub(crate) sequence: u64,
+    /// Time base reading at emission, in nanoseconds; `None` while unsynced.
+    pub(crate) epoch_ns: Option<i64>,
+    /// Quality (serve state, staleness) at emission.
+    pub(crate) quality: Arc<TimeQuality>,
+    /// The `/stream` JSON body in the ticker's format.
+    pub(crate) json: Utf8Bytes,
+}
+
+/// Schedule of one shared ticker: subscribers asking for the same interval
+/// and format share its ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct TickerKey {
+    pub(crate) interval_ms: u64,
+    pub(crate) format: StreamFormat,
+}
+
+/// Tick schedules shared by all stream subscribers: each tick is produced
+/// once per interval and format and broadcast, instead of every stream
+/// running its own timer and serializer.  A ticker runs while anyone is
+/// subscribed to it.
+#[derive(Default)]
+pub struct StreamHub {
+    /// Running tickers; an entry exists exactly while its task runs, which
+    /// guards start/stop against a subscriber arriving just as the last one
+    /// leaves.
+    tickers: Mutex<HashMap<TickerKey, broadcast::Sender<Tick>>>,
+}
+
+impl StreamHub {
+    /// Receive every tick of `key` from now on, starting its ticker if needed.
+    pub(crate) fn subscribe(
+        &self,
+        state: &Arc<AppState>,
+        key: TickerKey,
+    ) -> broadcast::Receiver<Tick> {
+        self.tickers
+            .lock()
+            .entry(key)
+            .or_insert_with(|| {
+                tokio::spawn(run_ticker(state.clone(), key));
+                broadcast::channel(TICK_BROADCAST_CAPACITY).0
+            })
+            .subscribe()
+    }
+
+    /// Broadcast `tick` to `key`'s subscribers; `false` (and the ticker
+    /// removed) once nobody is subscribed.
+    fn publish(&self, key: TickerKey, tick: Tick) -> bool {
+        let mut tickers = self.tickers.lock();
+        let Some(sender) = tickers.get(&key) else {
+            return false;
+        };
+        if sender.receiver_count() == 0 {
+            tickers.remove(&key);
+            return false;
+        }
+        let _ = sender.send(tick);
+        true
+    }
+
+    #[cfg(test)]
+    fn running(&self) -> usize {
+        self.tickers.lock().len()
+    }
+}
+
+/// Next tick of a subscription; never resolves while paused (`None`).
+pub(crate) async fn next_tick(
+    ticks: &mut Option<broadcast::Receiver<Tick>>,
+) -> Result<Tick, RecvError> {
+    match ticks {
+        Some(ticks) => ticks.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Produce ticks for `key`'s [`StreamHub`] subscribers until none are left.
+async fn run_ticker(state: Arc<AppState>, key: TickerKey) {
+    let update_interval_ms = key.interval_ms;
+    let mut next_sequence = 0u64;
+    let mut last_scheduled_ms = i64::MIN;
+
+    loop {
+        // Wake on the next NTP-time multiple of the interval rather than
+        // on a free-running timer, recomputing the target every tick so
+        // a late wake-up skips to the next boundary instead of
+        // accumulating drift against the timestamps being sent.
+        let scheduled_ms = match state.timebase.now_ms() {
+            Some(now_ms) => {
+                let target = next_boundary_ms(now_ms.max(last_scheduled_ms), update_interval_ms);
+                sleep(Duration::from_millis(target.saturating_sub(now_ms) as u64)).await;
+                last_scheduled_ms = target;
+                Some(target)
+            }
+            None => {
+                sleep(Duration::from_millis(update_interval_ms)).await;
+                None
+            }
+        };
+
+        // While synced a tick's sequence is its boundary's index since the
+        // epoch (`scheduled_ms / interval`), so it does not restart with the
+        // ticker and agrees across replicas; unsynced ticks just count on.
+        let sequence = match scheduled_ms {
+            Some(scheduled) => next_sequence
+                .max(u64::try_from(scheduled.div_euclid(update_interval_ms as i64)).unwrap_or(0)),
+            None => next_sequence,
+        };
+
+        let epoch_ns = state.timebase.now_ns();
+        let quality = Arc::new(state.compute_quality());
+        let json = render_json(
+            &state,
+            key.format,
+            sequence,
+            scheduled_ms,
+            epoch_ns,
+            &quality,
+        );
+        let tick = Tick {
+            sequence,
+            epoch_ns,
+            quality,
+            json,
+        };
+        if !state.stream_hub.publish(key, tick) {
+            debug!(
+                interval_ms = key.interval_ms,
+                format = key.format.as_str(),
+                "No stream subscribers left, stopping ticker"
+            );
+            return;
+        }
+        next_sequence = sequence + 1;
+    }
+}
+
+/// The `/stream` JSON body of a tick.
+fn render_json(
+    state: &AppState,
+    format: StreamFormat,
+    sequence: u64,
+    scheduled_ms: Option<i64>,
+    epoch_ns: Option<i64>,
+    quality: &TimeQuality,
+) -> Utf8Bytes {
+    let epoch_as_string = state.config.http.epoch_as_string;
+    let epoch_ms = epoch_ns.map(|ns| ns.div_euclid(NANOS_PER_MS));
+    let message = match (epoch_ms, format) {
+        (Some(epoch_ms), StreamFormat::Epoch) => json!({
+            "type": "tick",
+            "epoch_ms": epoch_json(epoch_ms, epoch_as_string),
+            "sequence": sequence,
+        }),
+        (Some(epoch_ms), StreamFormat::Iso) => json!({
+            "type": "tick",
+            "iso8601": format_epoch_ms_to_iso8601(epoch_ms),
+            "sequence": sequence,
+        }),
+        (Some(_), StreamFormat::UnixNs) => json!({
+            "type": "tick",
+            "epoch_ns": epoch_ns.map(|ns| epoch_json(ns, epoch_as_string)),
+            "sequence": sequence,
+        }),
+        (Some(epoch_ms), StreamFormat::Full) => {
+            let is_stale = quality.serve_state != "ok";
+            let staleness_secs = quality.staleness_ms.unwrap_or(0) / 1000;
+
+            json!({
+                "type": "tick",
+                "epoch_ms": epoch_json(epoch_ms, epoch_as_string),
+                "iso8601": format_epoch_ms_to_iso8601(epoch_ms),
+                "is_stale": is_stale,
+                "staleness_secs": staleness_secs,
+                "message": if is_stale {
+                    &state.config.messages.ok_cache
+                } else {
+                    &state.config.messages.ok
+                },
+                "sequence": sequence,
+                "scheduled_ms": scheduled_ms
+                    .map(|scheduled| epoch_json(scheduled, epoch_as_string)),
+                "emit_skew_ms": scheduled_ms.map(|scheduled| epoch_ms - scheduled),
+                // P0-4 quality fields
+                "source": quality.source,
+                "serve_state": quality.serve_state,
+                "uncertainty_ms": quality.uncertainty_ms,
+                "staleness_ms": quality.staleness_ms,
+                // Sync provenance
+                "age_ms": quality.staleness_ms,
+                "stratum": quality.stratum,
+                "selected_server": quality.selected_server,
+            })
+        }
+        (None, _) => {
+            json!({
+                "type": "error",
+                "message": &state.config.messages.error_no_sync,
+                "sequence": sequence,
+                "source": "unsynced",
+                "serve_state": "unsynced",
+            })
+        }
+    };
+    serde_json::to_string(&message).unwrap().into()
+}
+
+/// First multiple of `interval_ms` strictly after `now_ms` (epoch ms).
+pub(crate) fn next_boundary_ms(now_ms: i64, interval_ms: u64) -> i64 {
+    let interval = i64::try_from(interval_ms).unwrap_or(i64::MAX);
+    now_ms
+        .div_euclid(interval)
+        .saturating_add(1)
+        .saturating_mul(interval)
+}
+
+/// Format epoch milliseconds to ISO 8601 string
+pub(crate) fn format_epoch_ms_to_iso8601(epoch_ms: i64) -> String {
+    use chrono::DateTime;
+
+    let secs = epoch_ms / 1000;
+    let nsecs = ((epoch_ms % 1000) * 1_000_000) as u32;
+
+    match DateTime::from_timestamp(secs, nsecs) {
+        Some(dt) => dt.to_rfc3339(),
+        None => "invalid".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso8601_formatting() {
+        let epoch_ms = 1735459200000; // Recent timestamp
+        let iso = format_epoch_ms_to_iso8601(epoch_ms);
+        // Verify it's not invalid and contains a date and time
+        assert_ne!(iso, "invalid");
+        assert!(iso.contains("T")); // ISO8601 has T separator
+        assert!(iso.len() > 10); // Should be full date-time
+    }
+
+    #[test]
+    fn test_next_boundary_aligns_to_interval() {
+        assert_eq!(next_boundary_ms(1_705_320_000_400, 1000), 1_705_320_001_000);
+        // Exactly on a boundary: the next one, never the same instant twice
+        assert_eq!(next_boundary_ms(1_705_320_001_000, 1000), 1_705_320_002_000);
+        assert_eq!(next_boundary_ms(1_705_320_000_049, 250), 1_705_320_000_250);
+    }
+
+    #[test]
+    fn test_unix_ns_ticks_carry_nanoseconds() {
+        let state = make_state();
+        let quality = state.compute_quality();
+        let render = |epoch_ns| {
+            let json = render_json(&state, StreamFormat::UnixNs, 3, None, epoch_ns, &quality);
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()
+        };
+        let tick = render(Some(1_705_320_000_000_123_456));
+        assert_eq!(tick["type"], "tick");
+        assert_eq!(tick["epoch_ns"], 1_705_320_000_000_123_456i64);
+        assert_eq!(tick["sequence"], 3);
+        assert!(tick.get("epoch_ms").is_none());
+        assert_eq!(render(None)["type"], "error");
+        assert_eq!(StreamFormat::parse("UNIX_NS"), Ok(StreamFormat::UnixNs));
+    }
+
+    fn make_state() -> Arc<AppState> {
+        use crate::config::Config;
+        use crate::metrics::Metrics;
+        use crate::performance::{LockFreeMetrics, TimeCache};
+        use crate::timebase::TimeBase;
+
+        let time_cache = Arc::new(TimeCache::new("done".into(), "done".into()));
+        Arc::new(AppState::new(
+            Arc::new(Config::default()),
+            TimeBase::new(true).with_cache(time_cache.clone()),
+            Arc::new(Metrics::new()),
+            time_cache,
+            Arc::new(LockFreeMetrics::new()),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_hub_produces_each_tick_once_for_all_subscribers() {
+        let state = make_state();
+        let key = TickerKey {
+            interval_ms: 10,
+            format: StreamFormat::Full,
+        };
+        let mut first = state.stream_hub.subscribe(&state, key);
+        let mut second = state.stream_hub.subscribe(&state, key);
+        let a = first.recv().await.unwrap();
+        let b = second.recv().await.unwrap();
+        assert_eq!(a.json, b.json);
+        assert!(Arc::ptr_eq(&a.quality, &b.quality));
+        assert_eq!(state.stream_hub.running(), 1);
+
+        // Another interval or format gets a ticker of its own.
+        let iso = TickerKey {
+            interval_ms: 20,
+            format: StreamFormat::Iso,
+        };
+        state.timebase.set_manual(1_705_320_000_000, 60); // 2024-01-15T12:00:00Z
+        let mut third = state.stream_hub.subscribe(&state, iso);
+        assert_eq!(state.stream_hub.running(), 2);
+        let next = third.recv().await.unwrap();
+        let tick: serde_json::Value = serde_json::from_str(&next.json).unwrap();
+        assert_eq!(tick["type"], "tick");
+        assert!(tick["iso8601"].is_string());
+        assert!(tick.get("epoch_ms").is_none());
+        // Synced ticks are numbered by their boundary since the epoch
+        assert_eq!(tick["sequence"], next.sequence);
+        assert!(next.sequence >= 1_705_320_000_000 / 20);
+        assert!(next.epoch_ns.unwrap() >= next.sequence as i64 * 20 * NANOS_PER_MS);
+
+        // A ticker stops once its last subscriber leaves.
+        drop((first, second));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(state.stream_hub.running(), 1);
+
+        // ...and starts again for the next one.
+        let mut fourth = state.stream_hub.subscribe(&state, key);
+        assert!(fourth.recv().await.is_ok());
+    }
+}
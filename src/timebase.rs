@@ -1,15 +1,88 @@
+use crate::config::MonotonicScope;
 use crate::ntp::SyncResult;
 use crate::performance::TimeCache;
 use once_cell::sync::Lazy;
+use parking_lot::RwLock;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::time::Instant;
-use tracing::debug;
+use tracing::{debug, warn};
 
 // Global reference instant for lock-free time calculations
 // This is created once at program startup and never changes
 static REFERENCE_INSTANT: Lazy<Instant> = Lazy::new(Instant::now);
 
+/// Upper bound on last-served shards in `MonotonicScope::Shard`.
+const MAX_MONOTONIC_SHARDS: usize = 256;
+
+/// Hands out a distinct shard seed to each thread on first use.
+static NEXT_SHARD_SEED: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static SHARD_SEED: usize = NEXT_SHARD_SEED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Shortest sync-to-sync span used for a frequency estimate; shorter spans
+/// are dominated by millisecond offset noise.
+const DRIFT_MIN_SPAN_NANOS: u64 = 600 * 1_000_000_000;
+
+/// Frequency estimates beyond this (ppb) are treated as steps, not drift.
+const DRIFT_MAX_PPB: i64 = 500_000;
+
+/// Nanoseconds per millisecond.
+pub const NANOS_PER_MS: i64 = 1_000_000;
+
+/// The sync result the time base was last updated from, kept for handlers
+/// (`/status`, verbose `/time`, the `/stream` welcome) rather than only logs.
+#[derive(Debug, Clone)]
+pub struct LastSync {
+    /// Server the result came from (`manual` or the persisted server for
+    /// seeds).
+    pub server: String,
+    /// Measured offset of the local clock (ms).
+    pub offset_ms: i64,
+    /// Round trip of the exchange (ms).
+    pub rtt_ms: f64,
+    /// NTP epoch_ms at the sync.
+    pub synced_at_ms: i64,
+    /// Monotonic instant of the sync.
+    pub instant: Instant,
+}
+
+impl LastSync {
+    fn from_result(sync_result: &SyncResult) -> Self {
+        Self {
+            server: sync_result.server.clone(),
+            offset_ms: sync_result.offset_ms,
+            rtt_ms: sync_result.rtt.as_secs_f64() * 1000.0,
+            synced_at_ms: sync_result.epoch_ms,
+            instant: sync_result.instant,
+        }
+    }
+
+    /// Milliseconds since the sync.
+    pub fn age_ms(&self) -> u64 {
+        self.instant.elapsed().as_millis() as u64
+    }
+}
+
+/// How [`TimeBase::update`] applied a sync result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Correction {
+    /// First sync or seed: the base was set outright.
+    Initial,
+    /// `step_ms` is being slewed into served time at `SLEW_RATE_PPM`.
+    Slewed { step_ms: i64 },
+    /// Served time jumped by `step_ms`.
+    Stepped { step_ms: i64 },
+    /// `step_ms` exceeds `MAX_STEP_MS`; the time base is unchanged.
+    Rejected { step_ms: i64 },
+}
+
+/// A last-served slot on its own cache line, so shards never false-share.
+#[repr(align(128))]
+struct PaddedLastServed(AtomicI64);
+
 /// Monotonic time base that avoids OS wall clock authority
 /// Uses NTP-synced epoch time + monotonic clock progression
 #[derive(Clone)]
@@ -27,12 +100,53 @@ pub struct TimeBase {
     /// Whether monotonic output clamping is enabled
     monotonic_output: bool,
 
+    /// Per-shard last served epoch_ms (`MonotonicScope::Shard`); empty in
+    /// global scope, where `last_served_ms` is used instead.
+    last_served_shards: Arc<[PaddedLastServed]>,
+
+    /// Last epoch_ns served by `now_ns` (one slot in either scope)
+    last_served_ns: Arc<AtomicI64>,
+
     /// Whether we've had at least one successful sync
     has_synced: Arc<AtomicBool>,
 
     /// Optional zero-copy JSON cache
     time_cache: Option<Arc<TimeCache>>,
 
+    /// Slew rate in ppm (ns of correction per ms of elapsed time); 0 steps
+    /// every update.
+    slew_rate_ppm: u64,
+    /// Corrections larger than this (ms) are stepped even in slew mode.
+    slew_max_offset_ms: u64,
+    /// Correction still being slewed in from `base_instant_nanos`, in ms.
+    slew_pending_ms: Arc<AtomicI64>,
+    /// Steps larger than this (ms) are rejected; 0 accepts any step.
+    max_step_ms: u64,
+    /// Consecutive agreeing rejections after which the step is accepted
+    /// anyway (ntpd's stepout); 0 rejects forever.
+    max_step_stepout: u32,
+    /// Rejections in a row whose steps agree to within `max_step_ms`.
+    rejected_streak: Arc<AtomicU32>,
+    /// Step of the most recent rejection, in ms.
+    last_rejected_step_ms: Arc<AtomicI64>,
+    /// Whether the base came from an NTP sync.  A persisted or manual seed
+    /// (or a base a clock jump invalidated) is stepped from by the next
+    /// sync, never slewed or held to `max_step_ms`.
+    verified: Arc<AtomicBool>,
+
+    /// Last sync result passed to `update`; read off the hot path only.
+    last_sync: Arc<RwLock<Option<LastSync>>>,
+
+    /// Whether the estimated frequency error is applied to projections.
+    drift_compensation: bool,
+    /// Smoothed frequency error of the local monotonic clock, in ppb
+    /// (positive = local clock runs slow).
+    drift_ppb: Arc<AtomicI64>,
+    /// Sync result the next frequency estimate is measured from:
+    /// raw epoch_ms and monotonic nanos since REFERENCE_INSTANT.
+    drift_anchor_epoch_ms: Arc<AtomicI64>,
+    drift_anchor_nanos: Arc<AtomicU64>,
+
     // ── Manual override atomics (P1-7) ────────────────────────────────────────
     /// True while a manual time override is active and not expired.
     manual_active: Arc<AtomicBool>,
@@ -51,8 +165,23 @@ impl TimeBase {
             base_instant_nanos: Arc::new(AtomicU64::new(0)),
             last_served_ms: Arc::new(AtomicI64::new(0)),
             monotonic_output,
+            last_served_shards: Arc::new([]),
+            last_served_ns: Arc::new(AtomicI64::new(0)),
             has_synced: Arc::new(AtomicBool::new(false)),
             time_cache: None,
+            slew_rate_ppm: 0,
+            slew_max_offset_ms: 0,
+            slew_pending_ms: Arc::new(AtomicI64::new(0)),
+            max_step_ms: 0,
+            max_step_stepout: 0,
+            rejected_streak: Arc::new(AtomicU32::new(0)),
+            last_rejected_step_ms: Arc::new(AtomicI64::new(0)),
+            verified: Arc::new(AtomicBool::new(false)),
+            last_sync: Arc::new(RwLock::new(None)),
+            drift_compensation: false,
+            drift_ppb: Arc::new(AtomicI64::new(0)),
+            drift_anchor_epoch_ms: Arc::new(AtomicI64::new(0)),
+            drift_anchor_nanos: Arc::new(AtomicU64::new(0)),
             manual_active: Arc::new(AtomicBool::new(false)),
             manual_base_epoch_ms: Arc::new(AtomicI64::new(0)),
             manual_base_instant_nanos: Arc::new(AtomicU64::new(0)),
@@ -65,8 +194,284 @@ impl TimeBase {
         self
     }
 
-    /// Update the time base with a new NTP sync result
-    pub fn update(&self, sync_result: &SyncResult) {
+    /// Select how widely monotonic clamping applies.  `Shard` allocates one
+    /// slot per available core (rounded up to a power of two) so concurrent
+    /// requests stop contending on a single `last_served_ms` cache line.
+    pub fn with_monotonic_scope(mut self, scope: MonotonicScope) -> Self {
+        self.last_served_shards = match scope {
+            MonotonicScope::Global => Arc::new([]),
+            MonotonicScope::Shard => {
+                let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+                let shards = cores.next_power_of_two().min(MAX_MONOTONIC_SHARDS);
+                (0..shards)
+                    .map(|_| PaddedLastServed(AtomicI64::new(0)))
+                    .collect()
+            }
+        };
+        self
+    }
+
+    /// Amortize corrections up to `max_offset_ms` at `rate_ppm` (500 ppm =
+    /// 0.5 ms per second) instead of stepping the base.  `rate_ppm` of 0
+    /// keeps stepping.
+    pub fn with_slew(mut self, rate_ppm: u64, max_offset_ms: u64) -> Self {
+        self.slew_rate_ppm = rate_ppm;
+        self.slew_max_offset_ms = max_offset_ms;
+        self
+    }
+
+    /// Reject sync results that would move the time base by more than
+    /// `max_step_ms` (the `MAX_STEP_MS` panic threshold); 0 accepts any.
+    /// After `stepout` rejections in a row that agree with each other the
+    /// step is taken anyway, so a wrong base cannot lock out every later
+    /// sync; 0 keeps rejecting.
+    pub fn with_max_step(mut self, max_step_ms: u64, stepout: u32) -> Self {
+        self.max_step_ms = max_step_ms;
+        self.max_step_stepout = stepout;
+        self
+    }
+
+    /// Estimate the local clock's frequency error from successive syncs and
+    /// apply it to every projection, so holdover through an NTP outage does
+    /// not accumulate the host's raw drift.
+    pub fn with_drift_compensation(mut self, enabled: bool) -> Self {
+        self.drift_compensation = enabled;
+        self
+    }
+
+    /// The sync result the time base was last updated from, or `None`
+    /// before the first sync or seed.
+    pub fn last_sync(&self) -> Option<LastSync> {
+        self.last_sync.read().clone()
+    }
+
+    /// Current frequency error estimate in ppm (0 until estimated or when
+    /// compensation is disabled).
+    pub fn drift_ppm(&self) -> f64 {
+        self.drift_ppb.load(Ordering::Acquire) as f64 / 1000.0
+    }
+
+    /// Fold the frequency error between the drift anchor and `sync_result`
+    /// into the estimate once enough time has passed, then re-anchor.
+    fn estimate_drift(&self, sync_result: &SyncResult, instant_nanos: u64) {
+        let anchor_nanos = self.drift_anchor_nanos.load(Ordering::Acquire);
+        let anchored = self.verified.load(Ordering::Acquire);
+        let span_nanos = instant_nanos.saturating_sub(anchor_nanos);
+        if anchored && span_nanos < DRIFT_MIN_SPAN_NANOS {
+            return;
+        }
+        if anchored {
+            let anchor_epoch_ms = self.drift_anchor_epoch_ms.load(Ordering::Acquire);
+            let error_nanos =
+                (sync_result.epoch_ms - anchor_epoch_ms) as i128 * 1_000_000 - span_nanos as i128;
+            let sample_ppb = (error_nanos * 1_000_000_000 / span_nanos as i128) as i64;
+            if sample_ppb.abs() <= DRIFT_MAX_PPB {
+                let drift = self.drift_ppb.load(Ordering::Acquire);
+                let smoothed = if drift == 0 {
+                    sample_ppb
+                } else {
+                    drift + (sample_ppb - drift) / 4
+                };
+                self.drift_ppb.store(smoothed, Ordering::Release);
+            } else {
+                debug!(sample_ppb, "Ignoring implausible clock frequency estimate");
+            }
+        }
+        self.drift_anchor_epoch_ms
+            .store(sync_result.epoch_ms, Ordering::Release);
+        self.drift_anchor_nanos
+            .store(instant_nanos, Ordering::Release);
+    }
+
+    /// NTP-derived epoch_ns at `at_nanos` (since REFERENCE_INSTANT),
+    /// including the part of any pending correction slewed in by then.
+    #[inline]
+    fn ntp_ns_at(&self, at_nanos: u64) -> i64 {
+        let base_instant_nanos = self.base_instant_nanos.load(Ordering::Acquire);
+        let base_epoch_ms = self.base_epoch_ms.load(Ordering::Acquire);
+        let elapsed_nanos = at_nanos.saturating_sub(base_instant_nanos);
+        let mut current_ns = base_epoch_ms * NANOS_PER_MS + elapsed_nanos as i64;
+        let drift_ppb = self.drift_ppb.load(Ordering::Relaxed);
+        if drift_ppb != 0 {
+            current_ns += (elapsed_nanos as i128 * drift_ppb as i128 / 1_000_000_000) as i64;
+        }
+        let pending_ms = self.slew_pending_ms.load(Ordering::Acquire);
+        if pending_ms != 0 {
+            current_ns += pending_ms.signum() * self.slewed_ns(pending_ms, elapsed_nanos) as i64;
+        }
+        current_ns
+    }
+
+    /// Part of the pending correction (ms) not yet slewed in at `at`: how
+    /// far the projection served then may still be off by design, on top of
+    /// the sync's own dispersion.
+    pub fn unslewed_ms_at(&self, at: Instant) -> f64 {
+        let at_nanos = at.duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
+        let elapsed_nanos =
+            at_nanos.saturating_sub(self.base_instant_nanos.load(Ordering::Acquire));
+        let pending_ms = self.slew_pending_ms.load(Ordering::Acquire);
+        let unslewed_ns = pending_ms.unsigned_abs() * NANOS_PER_MS as u64
+            - self.slewed_ns(pending_ms, elapsed_nanos);
+        unslewed_ns as f64 / NANOS_PER_MS as f64
+    }
+
+    /// Slewed-in part of a pending correction at `elapsed_nanos` since the
+    /// base, in ns.
+    #[inline]
+    fn slewed_ns(&self, pending_ms: i64, elapsed_nanos: u64) -> u64 {
+        let slewed_ns = elapsed_nanos.saturating_mul(self.slew_rate_ppm) / 1_000_000;
+        slewed_ns.min(pending_ms.unsigned_abs() * NANOS_PER_MS as u64)
+    }
+
+    /// [`Self::ntp_ns_at`] truncated to milliseconds.
+    #[inline]
+    fn ntp_ms_at(&self, at_nanos: u64) -> i64 {
+        self.ntp_ns_at(at_nanos).div_euclid(NANOS_PER_MS)
+    }
+
+    /// The last-served slot `clamp_monotonic` uses on this thread.
+    #[inline]
+    fn last_served_slot(&self) -> &AtomicI64 {
+        if self.last_served_shards.is_empty() {
+            &self.last_served_ms
+        } else {
+            let mask = self.last_served_shards.len() - 1;
+            &self.last_served_shards[SHARD_SEED.with(|seed| *seed & mask)].0
+        }
+    }
+
+    /// Apply monotonic clamping (if enabled) to a freshly computed time.
+    /// Within a millisecond successive calls on the same slot advance by 1 ms.
+    #[inline]
+    fn clamp_monotonic(&self, mut current_ms: i64) -> i64 {
+        if !self.monotonic_output {
+            return current_ms;
+        }
+        let slot = self.last_served_slot();
+        let last_served = slot.load(Ordering::Acquire);
+        if current_ms <= last_served {
+            current_ms = last_served + 1;
+        }
+        slot.store(current_ms, Ordering::Release);
+        current_ms
+    }
+
+    /// [`Self::clamp_monotonic`] at nanosecond resolution: successive calls
+    /// advance by at least 1 ns, and never fall behind a millisecond reading
+    /// already served (a clamped `now_ms` may run ahead of the clock).
+    #[inline]
+    fn clamp_monotonic_ns(&self, mut current_ns: i64) -> i64 {
+        if !self.monotonic_output {
+            return current_ns;
+        }
+        let served_ms_floor = self
+            .last_served_slot()
+            .load(Ordering::Acquire)
+            .saturating_mul(NANOS_PER_MS);
+        let last_served = self
+            .last_served_ns
+            .load(Ordering::Acquire)
+            .max(served_ms_floor - 1);
+        if current_ns <= last_served {
+            current_ns = last_served + 1;
+        }
+        self.last_served_ns.store(current_ns, Ordering::Release);
+        current_ns
+    }
+
+    /// Highest epoch_ms handed out so far, across every last-served slot
+    /// (0 before the first reading).  Persisted so a restart can resume
+    /// monotonic output from it.
+    pub fn last_served_ms(&self) -> i64 {
+        let shards = self.last_served_shards.iter().map(|shard| &shard.0);
+        std::iter::once(&*self.last_served_ms)
+            .chain(shards)
+            .map(|slot| slot.load(Ordering::Acquire))
+            .chain(std::iter::once(
+                self.last_served_ns
+                    .load(Ordering::Acquire)
+                    .div_euclid(NANOS_PER_MS),
+            ))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Raise the monotonic floor to `floor_ms`, e.g. the last epoch_ms a
+    /// previous process served: every later reading (ms or ns) is above it.
+    /// Never lowers a floor already reached.
+    pub fn restore_last_served_ms(&self, floor_ms: i64) {
+        let shards = self.last_served_shards.iter().map(|shard| &shard.0);
+        for slot in std::iter::once(&*self.last_served_ms).chain(shards) {
+            slot.fetch_max(floor_ms, Ordering::AcqRel);
+        }
+    }
+
+    /// Update the time base with a new NTP sync result, applying the
+    /// correction policy by its step size: up to `SLEW_MAX_OFFSET_MS` is
+    /// slewed (when slewing is on), larger steps are stepped, and steps
+    /// beyond `MAX_STEP_MS` are rejected, leaving the time base untouched,
+    /// until `MAX_STEP_STEPOUT` agreeing rejections in a row.  A base that
+    /// is not verified (see [`Self::seed`]) is always stepped from.
+    pub fn update(&self, sync_result: &SyncResult) -> Correction {
+        let verified = self.verified.load(Ordering::Acquire);
+        let step_ms = self.step_ms(sync_result);
+        if let Some(step_ms) = step_ms.filter(|step| {
+            verified && self.max_step_ms > 0 && step.unsigned_abs() > self.max_step_ms
+        }) {
+            if !self.step_out(step_ms) {
+                return Correction::Rejected { step_ms };
+            }
+            warn!(
+                step_ms,
+                rejections = self.max_step_stepout,
+                "Accepting time step beyond MAX_STEP_MS after consistent rejections"
+            );
+        }
+        self.rejected_streak.store(0, Ordering::Release);
+        let correction = self.apply(sync_result, step_ms, verified);
+        self.verified.store(true, Ordering::Release);
+        correction
+    }
+
+    /// Set the base outright from a result no NTP server verified (a
+    /// persisted or manual seed): it serves time, but the next sync steps
+    /// from it whatever the step size.
+    pub fn seed(&self, seed: &SyncResult) {
+        self.verified.store(false, Ordering::Release);
+        self.apply(seed, None, false);
+    }
+
+    /// Mark the current base unverified, e.g. after the monotonic clock
+    /// stopped across a suspend: the next sync steps from it, whatever the
+    /// step size.
+    pub fn invalidate(&self) {
+        self.verified.store(false, Ordering::Release);
+    }
+
+    /// Whether the base came from an NTP sync rather than a seed, and has
+    /// not been invalidated since.
+    pub fn is_verified(&self) -> bool {
+        self.verified.load(Ordering::Acquire)
+    }
+
+    /// Count a step beyond `max_step_ms`; true once `max_step_stepout`
+    /// rejections in a row agree to within `max_step_ms`.
+    fn step_out(&self, step_ms: i64) -> bool {
+        let previous_ms = self.last_rejected_step_ms.swap(step_ms, Ordering::AcqRel);
+        let streak = if self.rejected_streak.load(Ordering::Acquire) > 0
+            && step_ms.abs_diff(previous_ms) <= self.max_step_ms
+        {
+            self.rejected_streak.fetch_add(1, Ordering::AcqRel) + 1
+        } else {
+            self.rejected_streak.store(1, Ordering::Release);
+            1
+        };
+        self.max_step_stepout > 0 && streak >= self.max_step_stepout
+    }
+
+    /// Move the base to `sync_result`: slewed when `slew` allows and the
+    /// step is small enough, stepped otherwise.
+    fn apply(&self, sync_result: &SyncResult, step_ms: Option<i64>, slew: bool) -> Correction {
         // CRITICAL: Use the instant from when epoch_ms was calculated, not current time
         // This prevents timing mismatches between epoch_ms and the monotonic clock
 
@@ -76,19 +481,53 @@ impl TimeBase {
             .duration_since(*REFERENCE_INSTANT)
             .as_nanos() as u64;
 
+        if self.drift_compensation {
+            self.estimate_drift(sync_result, instant_nanos);
+        }
+
+        // Slew mode: keep serving the current projection and amortize the
+        // correction from here instead of jumping to the new epoch.
+        let slew_ms = self.slew_correction_ms(sync_result).filter(|_| slew);
+        let (base_epoch_ms, pending_ms, correction) = match (slew_ms, step_ms) {
+            (Some(correction_ms), _) => (
+                sync_result.epoch_ms - correction_ms,
+                correction_ms,
+                Correction::Slewed {
+                    step_ms: correction_ms,
+                },
+            ),
+            (None, Some(step_ms)) => (sync_result.epoch_ms, 0, Correction::Stepped { step_ms }),
+            (None, None) => (sync_result.epoch_ms, 0, Correction::Initial),
+        };
+
         // PERFORMANCE: Use Release ordering - ensures all prior writes are visible
         // before this update becomes visible to other threads
-        self.base_epoch_ms
-            .store(sync_result.epoch_ms, Ordering::Release);
+        self.base_epoch_ms.store(base_epoch_ms, Ordering::Release);
         self.base_instant_nanos
             .store(instant_nanos, Ordering::Release);
+        self.slew_pending_ms.store(pending_ms, Ordering::Release);
+        *self.last_sync.write() = Some(LastSync::from_result(sync_result));
         self.has_synced.store(true, Ordering::Release);
 
         debug!(
-            base_epoch_ms = sync_result.epoch_ms,
+            base_epoch_ms,
+            slew_pending_ms = pending_ms,
+            ?correction,
             server = %sync_result.server,
             "Updated time base"
         );
+        correction
+    }
+
+    /// Correction to slew in for `sync_result`, or `None` to step: slewing
+    /// is disabled, this is the first sync, or the step exceeds the slew
+    /// limit.
+    fn slew_correction_ms(&self, sync_result: &SyncResult) -> Option<i64> {
+        if self.slew_rate_ppm == 0 {
+            return None;
+        }
+        self.step_ms(sync_result)
+            .filter(|step| step.unsigned_abs() <= self.slew_max_offset_ms)
     }
 
     /// Get current epoch time in milliseconds.
@@ -99,23 +538,43 @@ impl TimeBase {
     ///
     /// PERFORMANCE: This is the hot path - fully lock-free using atomics.
     pub fn now_ms(&self) -> Option<i64> {
+        self.now_ms_at(Instant::now())
+    }
+
+    /// [`Self::now_ms`] as of `at`, for a reading that must match other
+    /// values taken at the same instant (e.g. its uncertainty).
+    pub fn now_ms_at(&self, at: Instant) -> Option<i64> {
+        let current_ns = self.source_now_ns(at)?;
+        Some(self.clamp_monotonic(current_ns.div_euclid(NANOS_PER_MS)))
+    }
+
+    /// Get current epoch time in nanoseconds, with the same sources and
+    /// precedence as [`Self::now_ms`].  The monotonic clock already counts
+    /// nanoseconds; only the sync base is millisecond-grained, so readings
+    /// are precise relative to each other rather than more accurate.
+    pub fn now_ns(&self) -> Option<i64> {
+        self.now_ns_at(Instant::now())
+    }
+
+    /// [`Self::now_ns`] as of `at`.
+    pub fn now_ns_at(&self, at: Instant) -> Option<i64> {
+        let current_ns = self.source_now_ns(at)?;
+        Some(self.clamp_monotonic_ns(current_ns))
+    }
+
+    /// Unclamped epoch_ns of the active source at `at`: manual override (if
+    /// active and not expired) → NTP synced → None.
+    #[inline]
+    fn source_now_ns(&self, at: Instant) -> Option<i64> {
+        let now_nanos = at.saturating_duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
         // ── Manual override path ─────────────────────────────────────────────
         if self.manual_active.load(Ordering::Acquire) {
-            let now_nanos = Instant::now().duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
             let expires_nanos = self.manual_expires_at_nanos.load(Ordering::Acquire);
             if now_nanos < expires_nanos {
                 let base_nanos = self.manual_base_instant_nanos.load(Ordering::Acquire);
                 let base_epoch = self.manual_base_epoch_ms.load(Ordering::Acquire);
-                let elapsed_ms = (now_nanos.saturating_sub(base_nanos) / 1_000_000) as i64;
-                let mut current_ms = base_epoch + elapsed_ms;
-                if self.monotonic_output {
-                    let last = self.last_served_ms.load(Ordering::Acquire);
-                    if current_ms <= last {
-                        current_ms = last + 1;
-                    }
-                    self.last_served_ms.store(current_ms, Ordering::Release);
-                }
-                return Some(current_ms);
+                let elapsed_nanos = now_nanos.saturating_sub(base_nanos) as i64;
+                return Some(base_epoch * NANOS_PER_MS + elapsed_nanos);
             }
             // Lazy expiry: silently clear (background task emits the audit log)
             self.manual_active.store(false, Ordering::Release);
@@ -125,20 +584,7 @@ impl TimeBase {
         if !self.has_synced.load(Ordering::Acquire) {
             return None;
         }
-        let base_instant_nanos = self.base_instant_nanos.load(Ordering::Acquire);
-        let base_epoch_ms = self.base_epoch_ms.load(Ordering::Acquire);
-        let now_nanos = Instant::now().duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
-        let elapsed_nanos = now_nanos.saturating_sub(base_instant_nanos);
-        let elapsed_ms = (elapsed_nanos / 1_000_000) as i64;
-        let mut current_ms = base_epoch_ms + elapsed_ms;
-        if self.monotonic_output {
-            let last_served = self.last_served_ms.load(Ordering::Acquire);
-            if current_ms <= last_served {
-                current_ms = last_served + 1;
-            }
-            self.last_served_ms.store(current_ms, Ordering::Release);
-        }
-        Some(current_ms)
+        Some(self.ntp_ns_at(now_nanos))
     }
 
     /// Check if we've had at least one successful sync
@@ -189,11 +635,33 @@ impl TimeBase {
         if !self.has_synced.load(Ordering::Acquire) {
             return None;
         }
-        let base_nanos = self.base_instant_nanos.load(Ordering::Acquire);
-        let base_epoch = self.base_epoch_ms.load(Ordering::Acquire);
         let now_nanos = Instant::now().duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
-        let elapsed_ms = (now_nanos.saturating_sub(base_nanos) / 1_000_000) as i64;
-        Some(base_epoch + elapsed_ms)
+        Some(self.ntp_ms_at(now_nanos))
+    }
+
+    /// How far `sync_result` would move the NTP time base (ms): its epoch
+    /// minus the current base projected to the result's instant.  Ignores any
+    /// manual override.  `None` until the first sync.
+    pub fn step_ms(&self, sync_result: &SyncResult) -> Option<i64> {
+        if !self.has_synced.load(Ordering::Acquire) {
+            return None;
+        }
+        let at_nanos = sync_result
+            .instant
+            .duration_since(*REFERENCE_INSTANT)
+            .as_nanos() as u64;
+        Some(sync_result.epoch_ms - self.ntp_ms_at(at_nanos))
+    }
+
+    /// Host clock minus NTP-derived time (ms); positive = host clock ahead.
+    /// Ignores any manual override.  `None` until the first sync.
+    pub fn system_clock_divergence_ms(&self) -> Option<i64> {
+        let ntp_ms = self.ntp_base_now_ms()?;
+        let system_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        Some(system_ms - ntp_ms)
     }
 
     /// Returns milliseconds elapsed since `set_manual()` was called.
@@ -254,6 +722,26 @@ mod tests {
         assert!(diff < 100);
     }
 
+    #[test]
+    fn test_last_sync_records_latest_result() {
+        let tb = TimeBase::new(true);
+        assert!(tb.last_sync().is_none());
+
+        let first = create_test_sync_result(1_000_000);
+        tb.update(&first);
+        tb.update(&SyncResult {
+            epoch_ms: 1_060_000,
+            server: "other:123".to_string(),
+            offset_ms: -7,
+            ..first.clone()
+        });
+        let last = tb.last_sync().expect("recorded after update");
+        assert_eq!(last.server, "other:123");
+        assert_eq!(last.offset_ms, -7);
+        assert_eq!(last.rtt_ms, 10.0);
+        assert_eq!(last.synced_at_ms, 1_060_000);
+    }
+
     #[test]
     fn test_monotonic_progression() {
         let tb = TimeBase::new(true);
@@ -287,6 +775,84 @@ mod tests {
         assert!(t2 > t1 + 1000);
     }
 
+    #[test]
+    fn test_shard_scope_is_monotonic_per_thread() {
+        let tb = TimeBase::new(true).with_monotonic_scope(MonotonicScope::Shard);
+        assert!(tb.last_served_shards.len().is_power_of_two());
+        tb.update(&create_test_sync_result(1000000));
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let tb = tb.clone();
+                std::thread::spawn(move || {
+                    let mut last = i64::MIN;
+                    for _ in 0..1000 {
+                        let now = tb.now_ms().unwrap();
+                        assert!(now > last, "shard clamp went backwards");
+                        last = now;
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        // The global slot is untouched in shard scope
+        assert_eq!(tb.last_served_ms.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_now_ns_resolves_below_a_millisecond() {
+        let tb = TimeBase::new(true);
+        assert!(tb.now_ns().is_none());
+        tb.update(&create_test_sync_result(1000000));
+
+        let ms = tb.now_ms().unwrap();
+        let first = tb.now_ns().unwrap();
+        let second = tb.now_ns().unwrap();
+        // Never behind a served millisecond, and strictly increasing by
+        // far less than the millisecond clamp would step.
+        assert!(first >= ms * NANOS_PER_MS);
+        assert!(second > first);
+        assert!(second - first < NANOS_PER_MS);
+        assert!((first / NANOS_PER_MS - 1000000).abs() < 100);
+
+        // A millisecond reading clamped ahead of the clock lifts the floor.
+        tb.last_served_ms.store(ms + 1000, Ordering::SeqCst);
+        assert!(tb.now_ns().unwrap() >= (ms + 1000) * NANOS_PER_MS);
+    }
+
+    #[test]
+    fn test_now_ns_follows_manual_override() {
+        let tb = TimeBase::new(false);
+        tb.set_manual(2_000_000, 60);
+        let ns = tb.now_ns().unwrap();
+        assert!((2_000_000 * NANOS_PER_MS..2_000_100 * NANOS_PER_MS).contains(&ns));
+        tb.clear_manual();
+        assert!(tb.now_ns().is_none());
+    }
+
+    #[test]
+    fn test_restored_floor_survives_restart() {
+        let previous = TimeBase::new(true).with_monotonic_scope(MonotonicScope::Shard);
+        assert_eq!(previous.last_served_ms(), 0);
+        previous.update(&create_test_sync_result(1_000_000));
+        let served = previous.now_ms().unwrap();
+        assert!(previous.last_served_ms() >= served);
+
+        // The next process syncs to a time behind what was already served.
+        let restarted = TimeBase::new(true).with_monotonic_scope(MonotonicScope::Shard);
+        restarted.restore_last_served_ms(served + 5_000);
+        restarted.update(&create_test_sync_result(1_000_000));
+        assert_eq!(restarted.now_ms(), Some(served + 5_001));
+        assert!(restarted.now_ns().unwrap() >= (served + 5_001) * NANOS_PER_MS);
+        assert_eq!(restarted.last_served_ms(), served + 5_001);
+
+        // Restoring never lowers the floor.
+        restarted.restore_last_served_ms(served);
+        assert_eq!(restarted.now_ms(), Some(served + 5_002));
+    }
+
     #[test]
     fn test_no_monotonic_clamping() {
         let tb = TimeBase::new(false);
@@ -301,4 +867,266 @@ mod tests {
         // Should still progress (based on Instant)
         assert!(t2 > t1);
     }
+
+    #[test]
+    fn test_step_ms_measures_jump_from_current_base() {
+        let tb = TimeBase::new(true);
+        let first = create_test_sync_result(1_000_000);
+        assert_eq!(tb.step_ms(&first), None);
+        tb.update(&first);
+
+        let later = SyncResult {
+            epoch_ms: 1_000_000 + 3_600_000 + 50,
+            instant: first.instant + Duration::from_millis(50),
+            ..first.clone()
+        };
+        assert_eq!(tb.step_ms(&later), Some(3_600_000));
+        let behind = SyncResult {
+            epoch_ms: 1_000_000 - 20,
+            ..first
+        };
+        assert_eq!(tb.step_ms(&behind), Some(-20));
+    }
+
+    #[test]
+    fn test_slew_amortizes_small_corrections() {
+        // 1000 ppm = 1 ms of correction per second of served time.
+        let tb = TimeBase::new(false).with_slew(1_000, 100);
+        let first = create_test_sync_result(1_000_000);
+        tb.update(&first);
+        let at = |ms: u64| {
+            (first.instant + Duration::from_millis(ms))
+                .duration_since(*REFERENCE_INSTANT)
+                .as_nanos() as u64
+        };
+
+        tb.update(&SyncResult {
+            epoch_ms: 1_000_000 + 50,
+            ..first.clone()
+        });
+        // Served time does not jump; the 50 ms arrive over 50 s.
+        assert_eq!(tb.ntp_ms_at(at(0)), 1_000_000);
+        assert_eq!(tb.ntp_ms_at(at(10_000)), 1_000_000 + 10_000 + 10);
+        assert_eq!(tb.ntp_ms_at(at(50_000)), 1_000_000 + 50_000 + 50);
+        assert_eq!(tb.ntp_ms_at(at(90_000)), 1_000_000 + 90_000 + 50);
+        // Nanosecond projections slew continuously: 1 µs per ms here.
+        assert_eq!(tb.ntp_ns_at(at(1)), (1_000_000 + 1) * NANOS_PER_MS + 1_000);
+
+        // Negative corrections slow the clock down rather than reversing it.
+        tb.update(&SyncResult {
+            epoch_ms: 1_000_000 - 20,
+            ..first.clone()
+        });
+        assert_eq!(tb.ntp_ms_at(at(0)), 1_000_000);
+        assert_eq!(tb.ntp_ms_at(at(10_000)), 1_000_000 + 10_000 - 10);
+        assert_eq!(tb.ntp_ms_at(at(30_000)), 1_000_000 + 30_000 - 20);
+    }
+
+    #[test]
+    fn test_unslewed_correction_counts_until_slewed_in() {
+        let tb = TimeBase::new(false).with_slew(1_000, 100);
+        let first = create_test_sync_result(1_000_000);
+        tb.update(&first);
+        let at = |ms: u64| first.instant + Duration::from_millis(ms);
+        assert_eq!(tb.unslewed_ms_at(at(0)), 0.0);
+
+        // 1000 ppm: 1 ms slewed in per second.
+        tb.update(&SyncResult {
+            epoch_ms: 1_000_000 + 50,
+            ..first.clone()
+        });
+        assert_eq!(tb.unslewed_ms_at(at(0)), 50.0);
+        assert_eq!(tb.unslewed_ms_at(at(20_000)), 30.0);
+        assert_eq!(tb.unslewed_ms_at(at(100_000)), 0.0);
+    }
+
+    #[test]
+    fn test_now_at_reads_the_given_instant() {
+        let tb = TimeBase::new(false);
+        let first = create_test_sync_result(1_000_000);
+        tb.update(&first);
+        let later = first.instant + Duration::from_millis(1_500);
+        assert_eq!(tb.now_ms_at(later), Some(1_001_500));
+        assert_eq!(tb.now_ns_at(later), Some(1_001_500 * NANOS_PER_MS));
+    }
+
+    #[test]
+    fn test_update_applies_step_policy_by_magnitude() {
+        // Slew up to 100 ms, step up to 1 s, reject beyond.
+        let tb = TimeBase::new(false)
+            .with_slew(1_000, 100)
+            .with_max_step(1_000, 0);
+        let first = create_test_sync_result(1_000_000);
+        let at = first.instant.duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
+        assert_eq!(tb.update(&first), Correction::Initial);
+
+        let sync = |epoch_ms| SyncResult {
+            epoch_ms,
+            ..first.clone()
+        };
+        assert_eq!(
+            tb.update(&sync(1_000_000 + 50)),
+            Correction::Slewed { step_ms: 50 }
+        );
+        assert_eq!(tb.ntp_ms_at(at), 1_000_000);
+
+        assert_eq!(
+            tb.update(&sync(1_000_000 + 500)),
+            Correction::Stepped { step_ms: 500 }
+        );
+        assert_eq!(tb.ntp_ms_at(at), 1_000_500);
+
+        assert_eq!(
+            tb.update(&sync(1_000_500 - 5_000)),
+            Correction::Rejected { step_ms: -5_000 }
+        );
+        assert_eq!(tb.ntp_ms_at(at), 1_000_500, "rejected steps leave the base");
+        assert_eq!(tb.last_sync().unwrap().synced_at_ms, 1_000_500);
+    }
+
+    #[test]
+    fn test_seeded_base_is_stepped_from_whatever_the_step() {
+        let tb = TimeBase::new(false)
+            .with_slew(1_000, 100)
+            .with_max_step(1_000, 0);
+        let seed = create_test_sync_result(1_000_000);
+        let at = seed.instant.duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
+        tb.seed(&seed);
+        assert!(tb.has_synced());
+        assert!(!tb.is_verified());
+
+        // A seed hours off is corrected by the first sync, not rejected.
+        let sync = |epoch_ms| SyncResult {
+            epoch_ms,
+            ..seed.clone()
+        };
+        let hours_ms = 3 * 3_600_000;
+        assert_eq!(
+            tb.update(&sync(1_000_000 + hours_ms)),
+            Correction::Stepped { step_ms: hours_ms }
+        );
+        assert!(tb.is_verified());
+        assert_eq!(tb.ntp_ms_at(at), 1_000_000 + hours_ms);
+
+        // Verified from here on: the panic threshold applies again.
+        assert_eq!(
+            tb.update(&sync(1_000_000)),
+            Correction::Rejected { step_ms: -hours_ms }
+        );
+
+        // Invalidating the base steps the next sync, even a small one.
+        tb.invalidate();
+        assert_eq!(
+            tb.update(&sync(1_000_000 + hours_ms + 50)),
+            Correction::Stepped { step_ms: 50 }
+        );
+    }
+
+    #[test]
+    fn test_max_step_steps_out_after_consistent_rejections() {
+        let tb = TimeBase::new(false).with_max_step(1_000, 3);
+        let first = create_test_sync_result(1_000_000);
+        let at = first.instant.duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
+        tb.update(&first);
+        let sync = |epoch_ms| SyncResult {
+            epoch_ms,
+            ..first.clone()
+        };
+
+        // An outlier in between restarts the count.
+        assert!(matches!(
+            tb.update(&sync(1_060_000)),
+            Correction::Rejected { .. }
+        ));
+        assert!(matches!(
+            tb.update(&sync(1_060_200)),
+            Correction::Rejected { .. }
+        ));
+        assert!(matches!(
+            tb.update(&sync(900_000)),
+            Correction::Rejected { .. }
+        ));
+        assert!(matches!(
+            tb.update(&sync(1_060_100)),
+            Correction::Rejected { .. }
+        ));
+        assert!(matches!(
+            tb.update(&sync(1_060_300)),
+            Correction::Rejected { .. }
+        ));
+        assert_eq!(tb.ntp_ms_at(at), 1_000_000);
+
+        // The third agreeing result in a row is accepted.
+        assert_eq!(
+            tb.update(&sync(1_060_000)),
+            Correction::Stepped { step_ms: 60_000 }
+        );
+        assert_eq!(tb.ntp_ms_at(at), 1_060_000);
+    }
+
+    #[test]
+    fn test_slew_steps_large_corrections() {
+        let tb = TimeBase::new(false).with_slew(1_000, 100);
+        let first = create_test_sync_result(1_000_000);
+        tb.update(&first);
+        tb.update(&SyncResult {
+            epoch_ms: 1_000_000 + 500,
+            ..first.clone()
+        });
+        let at = first.instant.duration_since(*REFERENCE_INSTANT).as_nanos() as u64;
+        assert_eq!(tb.ntp_ms_at(at), 1_000_500);
+        assert_eq!(tb.step_ms(&first), Some(-500));
+    }
+
+    #[test]
+    fn test_drift_compensation_projects_estimated_frequency() {
+        let tb = TimeBase::new(false).with_drift_compensation(true);
+        let first = create_test_sync_result(1_000_000);
+        tb.update(&first);
+
+        // Too soon after the anchor to estimate.
+        tb.update(&SyncResult {
+            epoch_ms: 1_000_000 + 30_001,
+            instant: first.instant + Duration::from_secs(30),
+            ..first.clone()
+        });
+        assert_eq!(tb.drift_ppm(), 0.0);
+
+        // The local clock lost 10 ms over 1000 s: 10 ppm slow.
+        let second = SyncResult {
+            epoch_ms: 1_000_000 + 1_000_010,
+            instant: first.instant + Duration::from_secs(1000),
+            ..first.clone()
+        };
+        tb.update(&second);
+        assert_eq!(tb.drift_ppm(), 10.0);
+
+        let at = (second.instant + Duration::from_secs(1000))
+            .duration_since(*REFERENCE_INSTANT)
+            .as_nanos() as u64;
+        assert_eq!(tb.ntp_ms_at(at), second.epoch_ms + 1_000_000 + 10);
+    }
+
+    #[test]
+    fn test_drift_compensation_ignores_implausible_estimates() {
+        let tb = TimeBase::new(false).with_drift_compensation(true);
+        let first = create_test_sync_result(1_000_000);
+        tb.update(&first);
+        // A 10 s jump over 1000 s is a step, not drift.
+        tb.update(&SyncResult {
+            epoch_ms: 1_000_000 + 1_010_000,
+            instant: first.instant + Duration::from_secs(1000),
+            ..first.clone()
+        });
+        assert_eq!(tb.drift_ppm(), 0.0);
+
+        let disabled = TimeBase::new(false);
+        disabled.update(&first);
+        disabled.update(&SyncResult {
+            epoch_ms: 1_000_000 + 1_000_010,
+            instant: first.instant + Duration::from_secs(1000),
+            ..first
+        });
+        assert_eq!(disabled.drift_ppm(), 0.0);
+    }
 }
@@ -0,0 +1,336 @@
+//! IANA timezone lookups evaluated at NTP-derived time.
+//!
+//! Backs `GET /timezones`, `GET /timezones/{zone}`, `GET /time?tz=...` and
+//! the worldtimeapi.org-compatible `GET /api/timezone/{zone}`.
+//! Offsets come from the tz database compiled into `chrono-tz`; "now" is
+//! always supplied by the caller (the `TimeBase`), never read from the host
+//! clock, so the reported offsets and upcoming transitions agree with the
+//! time this service serves.
+
+use crate::format::TimeFormat;
+use chrono::{DateTime, Datelike, Offset, SecondsFormat, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, TZ_VARIANTS, Tz};
+use serde::Serialize;
+
+/// How far ahead `next_transition` searches for a UTC-offset change.
+const TRANSITION_HORIZON_DAYS: i64 = 400;
+
+const DAY_MS: i64 = 86_400_000;
+
+/// A zone's offset at one instant plus its next transition.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoneInfo {
+    /// IANA name, e.g. `America/New_York`.
+    pub zone: &'static str,
+    /// Total UTC offset (standard + DST) in seconds.
+    pub utc_offset_seconds: i32,
+    /// Zone abbreviation (e.g. `EST`); `None` where the database has only a numeric name.
+    pub abbreviation: Option<String>,
+    pub is_dst: bool,
+    /// Next UTC-offset change within ~13 months; `None` for zones without DST.
+    pub next_transition: Option<Transition>,
+}
+
+/// A change of a zone's UTC offset.
+#[derive(Debug, Clone, Serialize)]
+pub struct Transition {
+    /// First instant (unix-epoch ms) at which the new offset applies.
+    pub at_ms: i64,
+    pub utc_offset_seconds_after: i32,
+    pub is_dst_after: bool,
+}
+
+/// Upper bound on zones accepted in one `?tz=` list.
+pub const MAX_ZONES_PER_REQUEST: usize = 32;
+
+/// Wall-clock representation of one instant in one zone.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalTime {
+    pub zone: &'static str,
+    /// RFC 3339 with millisecond precision and the zone's offset.
+    pub local_time: String,
+    pub utc_offset_seconds: i32,
+    pub abbreviation: Option<String>,
+    pub is_dst: bool,
+    /// Local time rendered with the request's `?pattern=` / `?locale=`, when given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+}
+
+/// Parse an IANA zone name (case-sensitive, as in the tz database).
+pub fn parse_zone(name: &str) -> Option<Tz> {
+    name.parse().ok()
+}
+
+/// Parse a comma-separated `?tz=` list such as `UTC,Asia/Tehran`.
+///
+/// Blank entries are skipped; duplicates are kept so the output order always
+/// matches the request.  Errors name the first unknown zone, or report an
+/// empty list or one longer than [`MAX_ZONES_PER_REQUEST`].
+pub fn parse_zone_list(raw: &str) -> Result<Vec<Tz>, String> {
+    let zones = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| parse_zone(name).ok_or_else(|| format!("Unknown timezone: {name}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    if zones.is_empty() {
+        return Err("tz must name at least one IANA timezone".to_string());
+    }
+    if zones.len() > MAX_ZONES_PER_REQUEST {
+        return Err(format!(
+            "tz accepts at most {MAX_ZONES_PER_REQUEST} timezones"
+        ));
+    }
+    Ok(zones)
+}
+
+/// `epoch_ms` rendered in `tz`, plus `format` output when given.
+pub fn localize(tz: Tz, epoch_ms: i64, format: Option<&TimeFormat>) -> LocalTime {
+    let local = utc_datetime(epoch_ms).with_timezone(&tz);
+    let offset = local.offset();
+    LocalTime {
+        zone: tz.name(),
+        local_time: local.to_rfc3339_opts(SecondsFormat::Millis, false),
+        utc_offset_seconds: offset.fix().local_minus_utc(),
+        abbreviation: offset.abbreviation().map(str::to_string),
+        is_dst: !offset.dst_offset().is_zero(),
+        formatted: format.map(|f| f.render(&local)),
+    }
+}
+
+/// Every zone in the compiled tz database, in canonical order.
+pub fn all_zones() -> &'static [Tz] {
+    &TZ_VARIANTS
+}
+
+/// `epoch_ms` as a UTC datetime; out-of-range values clamp to the epoch.
+pub fn utc_datetime(epoch_ms: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(epoch_ms).unwrap_or_default()
+}
+
+/// Offset and DST flag of `tz` at `epoch_ms`.
+fn offset_at(tz: Tz, epoch_ms: i64) -> (i32, bool) {
+    let offset = tz.offset_from_utc_datetime(&utc_datetime(epoch_ms).naive_utc());
+    (
+        offset.fix().local_minus_utc(),
+        !offset.dst_offset().is_zero(),
+    )
+}
+
+/// Offset, abbreviation and next transition of `tz` at `epoch_ms`.
+pub fn zone_info(tz: Tz, epoch_ms: i64) -> ZoneInfo {
+    let offset = tz.offset_from_utc_datetime(&utc_datetime(epoch_ms).naive_utc());
+    ZoneInfo {
+        zone: tz.name(),
+        utc_offset_seconds: offset.fix().local_minus_utc(),
+        abbreviation: offset.abbreviation().map(str::to_string),
+        is_dst: !offset.dst_offset().is_zero(),
+        next_transition: next_transition(tz, epoch_ms),
+    }
+}
+
+/// First UTC-offset change of `tz` after `epoch_ms`.
+///
+/// Steps forward a day at a time (transitions are at least weeks apart), then
+/// bisects the day containing the change down to the second.
+pub fn next_transition(tz: Tz, epoch_ms: i64) -> Option<Transition> {
+    let (current_offset, _) = offset_at(tz, epoch_ms);
+    let mut low = epoch_ms;
+    for _ in 0..TRANSITION_HORIZON_DAYS {
+        let high = low + DAY_MS;
+        if offset_at(tz, high).0 == current_offset {
+            low = high;
+            continue;
+        }
+
+        let at_ms = bisect_change(tz, low, high);
+        let (utc_offset_seconds_after, is_dst_after) = offset_at(tz, at_ms);
+        return Some(Transition {
+            at_ms,
+            utc_offset_seconds_after,
+            is_dst_after,
+        });
+    }
+    None
+}
+
+/// Most recent UTC-offset change of `tz` at or before `epoch_ms` (unix-epoch
+/// ms), searched as far back as `next_transition` searches ahead.
+pub fn previous_transition(tz: Tz, epoch_ms: i64) -> Option<i64> {
+    let (current_offset, _) = offset_at(tz, epoch_ms);
+    let mut high = epoch_ms;
+    for _ in 0..TRANSITION_HORIZON_DAYS {
+        let low = high - DAY_MS;
+        if offset_at(tz, low).0 == current_offset {
+            high = low;
+            continue;
+        }
+        return Some(bisect_change(tz, low, high));
+    }
+    None
+}
+
+/// First whole second in `(low_ms, high_ms]` whose offset differs from the
+/// offset at `low_ms`; the two ends must have different offsets.
+fn bisect_change(tz: Tz, low_ms: i64, high_ms: i64) -> i64 {
+    // Transitions fall on second boundaries.
+    // Invariant: offset(low_s) == before, offset(high_s) != before.
+    let (before, _) = offset_at(tz, low_ms);
+    let mut low_s = low_ms.div_euclid(1000);
+    let mut high_s = high_ms.div_euclid(1000);
+    while high_s - low_s > 1 {
+        let mid_s = low_s + (high_s - low_s) / 2;
+        if offset_at(tz, mid_s * 1000).0 == before {
+            low_s = mid_s;
+        } else {
+            high_s = mid_s;
+        }
+    }
+    high_s * 1000
+}
+
+/// The body of worldtimeapi.org's `/api/timezone/{zone}`, for clients of
+/// that API pointed at this service.  `client_ip` is added by the handler.
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldTime {
+    pub abbreviation: String,
+    /// Local time, RFC 3339 with microseconds.
+    pub datetime: String,
+    /// 0 = Sunday.
+    pub day_of_week: u32,
+    pub day_of_year: u32,
+    pub dst: bool,
+    /// Start and end of the current DST period (UTC); `null` outside DST.
+    pub dst_from: Option<String>,
+    pub dst_offset: i64,
+    pub dst_until: Option<String>,
+    /// Standard (non-DST) offset in seconds.
+    pub raw_offset: i64,
+    pub timezone: &'static str,
+    pub unixtime: i64,
+    pub utc_datetime: String,
+    /// Total offset as `±HH:MM`.
+    pub utc_offset: String,
+    /// ISO 8601 week number.
+    pub week_number: u32,
+}
+
+/// `epoch_ms` in `tz`, shaped like worldtimeapi.org's response.
+pub fn world_time(tz: Tz, epoch_ms: i64) -> WorldTime {
+    let utc = utc_datetime(epoch_ms);
+    let local = utc.with_timezone(&tz);
+    let offset = local.offset();
+    let dst = !offset.dst_offset().is_zero();
+    let utc_rfc3339 = |ms: i64| utc_datetime(ms).to_rfc3339_opts(SecondsFormat::Micros, false);
+    WorldTime {
+        // Zones without a letter abbreviation are named by their offset
+        // (`+0330`), as worldtimeapi.org does.
+        abbreviation: offset
+            .abbreviation()
+            .map(str::to_string)
+            .unwrap_or_else(|| local.format("%z").to_string()),
+        datetime: local.to_rfc3339_opts(SecondsFormat::Micros, false),
+        day_of_week: local.weekday().num_days_from_sunday(),
+        day_of_year: local.ordinal(),
+        dst,
+        dst_from: dst
+            .then(|| previous_transition(tz, epoch_ms).map(utc_rfc3339))
+            .flatten(),
+        dst_offset: offset.dst_offset().num_seconds(),
+        dst_until: dst
+            .then(|| next_transition(tz, epoch_ms).map(|t| utc_rfc3339(t.at_ms)))
+            .flatten(),
+        raw_offset: offset.base_utc_offset().num_seconds(),
+        timezone: tz.name(),
+        unixtime: utc.timestamp(),
+        utc_datetime: utc.to_rfc3339_opts(SecondsFormat::Micros, false),
+        utc_offset: local.format("%:z").to_string(),
+        week_number: local.iso_week().week(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-15T12:00:00Z
+    const JAN_2024_MS: i64 = 1_705_320_000_000;
+
+    #[test]
+    fn finds_us_spring_forward() {
+        let tz = parse_zone("America/New_York").unwrap();
+        let info = zone_info(tz, JAN_2024_MS);
+        assert_eq!(info.utc_offset_seconds, -5 * 3600);
+        assert!(!info.is_dst);
+        assert_eq!(info.abbreviation.as_deref(), Some("EST"));
+
+        // 2024-03-10T07:00:00Z (02:00 EST → 03:00 EDT)
+        let next = info.next_transition.unwrap();
+        assert_eq!(next.at_ms, 1_710_054_000_000);
+        assert_eq!(next.utc_offset_seconds_after, -4 * 3600);
+        assert!(next.is_dst_after);
+    }
+
+    #[test]
+    fn zone_without_dst_has_no_transition() {
+        let tz = parse_zone("Asia/Tehran").unwrap();
+        let info = zone_info(tz, JAN_2024_MS);
+        assert_eq!(info.utc_offset_seconds, 3 * 3600 + 1800);
+        assert!(info.next_transition.is_none());
+        assert!(next_transition(Tz::UTC, JAN_2024_MS).is_none());
+    }
+
+    #[test]
+    fn zone_list_keeps_request_order() {
+        let zones = parse_zone_list("UTC, Asia/Tehran,,America/New_York").unwrap();
+        let names: Vec<&str> = zones.iter().map(|tz| tz.name()).collect();
+        assert_eq!(names, ["UTC", "Asia/Tehran", "America/New_York"]);
+
+        let tehran = localize(zones[1], JAN_2024_MS, None);
+        assert_eq!(tehran.local_time, "2024-01-15T15:30:00.000+03:30");
+        assert!(!tehran.is_dst);
+
+        assert_eq!(
+            parse_zone_list("UTC,Nowhere/City").unwrap_err(),
+            "Unknown timezone: Nowhere/City"
+        );
+        assert!(parse_zone_list(" , ").is_err());
+        assert!(parse_zone_list(&vec!["UTC"; MAX_ZONES_PER_REQUEST + 1].join(",")).is_err());
+    }
+
+    #[test]
+    fn world_time_matches_worldtimeapi_shape() {
+        // 2024-07-01T12:00:00Z, a Monday in CEST.
+        let july = 1_719_835_200_000;
+        let amsterdam = world_time(parse_zone("Europe/Amsterdam").unwrap(), july);
+        assert_eq!(amsterdam.datetime, "2024-07-01T14:00:00.000000+02:00");
+        assert_eq!(amsterdam.utc_datetime, "2024-07-01T12:00:00.000000+00:00");
+        assert_eq!(amsterdam.utc_offset, "+02:00");
+        assert_eq!((amsterdam.raw_offset, amsterdam.dst_offset), (3600, 3600));
+        assert_eq!((amsterdam.day_of_week, amsterdam.day_of_year), (1, 183));
+        assert_eq!(amsterdam.week_number, 27);
+        assert_eq!(amsterdam.unixtime, 1_719_835_200);
+        assert!(amsterdam.dst);
+        assert_eq!(
+            amsterdam.dst_from.as_deref(),
+            Some("2024-03-31T01:00:00.000000+00:00")
+        );
+        assert_eq!(
+            amsterdam.dst_until.as_deref(),
+            Some("2024-10-27T01:00:00.000000+00:00")
+        );
+
+        let tehran = world_time(parse_zone("Asia/Tehran").unwrap(), JAN_2024_MS);
+        assert_eq!(tehran.abbreviation, "+0330");
+        assert!(!tehran.dst && tehran.dst_from.is_none() && tehran.dst_until.is_none());
+        assert_eq!(tehran.day_of_week, 1);
+    }
+
+    #[test]
+    fn rejects_unknown_zone() {
+        assert!(parse_zone("Mars/Olympus_Mons").is_none());
+        assert!(parse_zone("").is_none());
+        assert!(all_zones().contains(&Tz::UTC));
+    }
+}
@@ -6,8 +6,12 @@ use std::sync::atomic::Ordering;
 use std::time::Instant;
 
 use ntp_time_json_api::{
-    config::Config,
-    http::{create_router, create_router_for_test, state::AppState},
+    config::{Config, GrpcConfig, ServerConfig, WsConfig},
+    grpc_service::GrpcTimeService,
+    http::{
+        create_router, create_router_for_test,
+        state::{AppState, ForcedSyncReport, SyncRequest},
+    },
     metrics::{Metrics, ReplicaLabel},
     ntp::{
         NtpServer, NtpSyncer, SyncOutcome, SyncQuality,
@@ -87,20 +91,23 @@ pub async fn start_mock_ntp_upstream(epoch_ms: i64) -> MockNtpUpstream {
 // ── Build helpers ─────────────────────────────────────────────────────────────
 
 pub fn build_state(config: Arc<Config>) -> Arc<AppState> {
+    Arc::new(new_state(config))
+}
+
+fn new_state(config: Arc<Config>) -> AppState {
     let time_cache = Arc::new(TimeCache::new(
         config.messages.ok.clone(),
         config.messages.ok_cache.clone(),
     ));
     let perf_metrics = Arc::new(LockFreeMetrics::new());
-    let timebase = TimeBase::new(config.ntp.monotonic_output).with_cache(time_cache.clone());
+    let timebase = TimeBase::new(config.ntp.monotonic_output)
+        .with_monotonic_scope(config.ntp.monotonic_scope)
+        .with_slew(config.ntp.slew_rate_ppm, config.ntp.slew_max_offset_ms)
+        .with_max_step(config.ntp.max_step_ms, config.ntp.max_step_stepout)
+        .with_drift_compensation(config.ntp.drift_compensation)
+        .with_cache(time_cache.clone());
     let metrics = Arc::new(Metrics::new());
-    Arc::new(AppState::new(
-        config,
-        timebase,
-        metrics,
-        time_cache,
-        perf_metrics,
-    ))
+    AppState::new(config, timebase, metrics, time_cache, perf_metrics)
 }
 
 /// Apply one sync outcome to AppState — same bookkeeping sync_loop does in main.rs.
@@ -228,6 +235,7 @@ pub fn apply_sync_to_state(state: &AppState, outcome: &SyncOutcome) {
         last_sync_instant: Instant::now(),
         selected_server: result.server.clone(),
     });
+    state.update_quality_metrics();
 }
 
 async fn start_http_server(state: Arc<AppState>) -> TestServer {
@@ -260,21 +268,17 @@ async fn start_http_server(state: Arc<AppState>) -> TestServer {
 /// matching the production `main.rs` serve path exactly.
 async fn start_http_server_rate_limited(state: Arc<AppState>) -> TestServer {
     let app = create_router(state.clone()); // rate limiting enabled (disable_rate_limiting=false)
+    let state_config = state.config.clone();
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
 
     let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
 
     tokio::spawn(async move {
-        axum::serve(
-            listener,
-            app.into_make_service_with_connect_info::<SocketAddr>(),
-        )
-        .with_graceful_shutdown(async {
+        ntp_time_json_api::http::server::serve(listener, app, &state_config.http, async {
             let _ = shutdown_rx.await;
         })
-        .await
-        .ok();
+        .await;
     });
 
     tokio::time::sleep(std::time::Duration::from_millis(10)).await;
@@ -293,20 +297,38 @@ async fn start_http_server_rate_limited(state: Arc<AppState>) -> TestServer {
 /// `/time` will return 503 (REQUIRE_SYNC=true).
 pub async fn spawn_server_unsynced() -> TestServer {
     let mut config = Config::default();
-    config.ntp.servers = vec!["127.0.0.1:1".to_string()]; // unreachable; won't be contacted
+    config.ntp.servers = vec![ServerConfig::new("127.0.0.1:1")]; // unreachable; won't be contacted
     config.ntp.require_sync = true;
     config.ws.update_interval_ms = 100;
     start_http_server(build_state(Arc::new(config))).await
 }
 
+/// Spawn an unsynced HTTP server with `/stream` settings adjusted by `configure`.
+pub async fn spawn_server_with_ws(configure: impl FnOnce(&mut WsConfig)) -> TestServer {
+    let mut config = Config::default();
+    config.ntp.servers = vec![ServerConfig::new("127.0.0.1:1")]; // unreachable; won't be contacted
+    config.ws.update_interval_ms = 100;
+    configure(&mut config.ws);
+    start_http_server(build_state(Arc::new(config))).await
+}
+
 /// Spawn an HTTP server that has completed one NTP sync against `upstream`.
 pub async fn spawn_server_synced(upstream: &MockNtpUpstream) -> TestServer {
+    spawn_server_synced_with_grpc(upstream, |_| {}).await
+}
+
+/// Like [`spawn_server_synced`], with gRPC settings adjusted by `configure`.
+pub async fn spawn_server_synced_with_grpc(
+    upstream: &MockNtpUpstream,
+    configure: impl FnOnce(&mut GrpcConfig),
+) -> TestServer {
     let mut config = Config::default();
-    config.ntp.servers = vec![upstream.addr.to_string()];
-    config.ntp.timeout_secs = 5;
+    config.ntp.servers = vec![ServerConfig::new(&upstream.addr.to_string())];
+    config.ntp.timeout_ms = 5000;
     config.ntp.require_sync = true;
     config.ntp.selection.min_quorum = 1; // single upstream in tests
     config.ws.update_interval_ms = 100;
+    configure(&mut config.grpc);
     let config = Arc::new(config);
 
     let state = build_state(config.clone());
@@ -325,8 +347,8 @@ pub async fn spawn_server_synced(upstream: &MockNtpUpstream) -> TestServer {
 /// the client IP — the same path as `main.rs`.
 pub async fn spawn_server_synced_rate_limited(upstream: &MockNtpUpstream) -> TestServer {
     let mut config = Config::default();
-    config.ntp.servers = vec![upstream.addr.to_string()];
-    config.ntp.timeout_secs = 5;
+    config.ntp.servers = vec![ServerConfig::new(&upstream.addr.to_string())];
+    config.ntp.timeout_ms = 5000;
     config.ntp.require_sync = true;
     config.ntp.selection.min_quorum = 1;
     config.ws.update_interval_ms = 100;
@@ -348,8 +370,8 @@ pub async fn spawn_server_synced_rate_limited(upstream: &MockNtpUpstream) -> Tes
 /// Returns `(TestServer, ntp_udp_addr)`.
 pub async fn spawn_server_with_ntp_server(upstream: &MockNtpUpstream) -> (TestServer, SocketAddr) {
     let mut config = Config::default();
-    config.ntp.servers = vec![upstream.addr.to_string()];
-    config.ntp.timeout_secs = 5;
+    config.ntp.servers = vec![ServerConfig::new(&upstream.addr.to_string())];
+    config.ntp.timeout_ms = 5000;
     config.ntp.require_sync = true;
     config.ntp.selection.min_quorum = 1;
     config.ws.update_interval_ms = 100;
@@ -366,15 +388,16 @@ pub async fn spawn_server_with_ntp_server(upstream: &MockNtpUpstream) -> (TestSe
 }
 
 /// Spawn an HTTP server with admin API enabled.
-/// The server has completed one NTP sync against `upstream`.
+/// The server has completed one NTP sync against `upstream`, and its syncer
+/// backs `/admin/servers`.
 pub async fn spawn_server_with_admin(
     upstream: &MockNtpUpstream,
     admin_token: &str,
     max_jump_ms: u64,
 ) -> TestServer {
     let mut config = Config::default();
-    config.ntp.servers = vec![upstream.addr.to_string()];
-    config.ntp.timeout_secs = 5;
+    config.ntp.servers = vec![ServerConfig::new(&upstream.addr.to_string())];
+    config.ntp.timeout_ms = 5000;
     config.ntp.require_sync = true;
     config.ntp.selection.min_quorum = 1;
     config.ws.update_interval_ms = 100;
@@ -385,14 +408,34 @@ pub async fn spawn_server_with_admin(
     config.admin.dispersion_ms = 1000;
     let config = Arc::new(config);
 
-    let state = build_state(config.clone());
-    let syncer = NtpSyncer::new(Arc::new(config.ntp.clone()));
+    let syncer = Arc::new(NtpSyncer::new(Arc::new(config.ntp.clone())));
+    let (sync_requests_tx, mut sync_requests_rx) = tokio::sync::mpsc::channel::<SyncRequest>(8);
+    let state = Arc::new(
+        new_state(config.clone())
+            .with_ntp_syncer(syncer.clone())
+            .with_sync_requests(sync_requests_tx),
+    );
     let outcome = syncer
         .sync()
         .await
         .expect("initial sync against mock NTP upstream should succeed");
     apply_sync_to_state(&state, &outcome);
 
+    // Answer POST /admin/sync the way sync_loop does in main.rs
+    let (loop_state, loop_syncer) = (state.clone(), syncer.clone());
+    tokio::spawn(async move {
+        while let Some(request) = sync_requests_rx.recv().await {
+            let report = match loop_syncer.sync().await {
+                Ok(outcome) => {
+                    apply_sync_to_state(&loop_state, &outcome);
+                    Ok(ForcedSyncReport::from_outcome(&outcome))
+                }
+                Err(e) => Err(format!("{e:#}")),
+            };
+            let _ = request.send(report);
+        }
+    });
+
     start_http_server(state).await
 }
 
@@ -404,8 +447,8 @@ pub async fn spawn_server_with_admin_force_allowed(
     max_jump_ms: u64,
 ) -> TestServer {
     let mut config = Config::default();
-    config.ntp.servers = vec![upstream.addr.to_string()];
-    config.ntp.timeout_secs = 5;
+    config.ntp.servers = vec![ServerConfig::new(&upstream.addr.to_string())];
+    config.ntp.timeout_ms = 5000;
     config.ntp.require_sync = true;
     config.ntp.selection.min_quorum = 1;
     config.ws.update_interval_ms = 100;
@@ -436,8 +479,8 @@ pub async fn spawn_server_with_admin_and_ntp_server(
     max_jump_ms: u64,
 ) -> (TestServer, std::net::SocketAddr) {
     let mut config = Config::default();
-    config.ntp.servers = vec![upstream.addr.to_string()];
-    config.ntp.timeout_secs = 5;
+    config.ntp.servers = vec![ServerConfig::new(&upstream.addr.to_string())];
+    config.ntp.timeout_ms = 5000;
     config.ntp.require_sync = true;
     config.ntp.selection.min_quorum = 1;
     config.ws.update_interval_ms = 100;
@@ -467,7 +510,7 @@ pub async fn spawn_server_with_admin_and_ntp_server(
 pub async fn spawn_server_holdover_seeded(epoch_ms: i64) -> TestServer {
     use ntp_time_json_api::ntp::{SyncResult, selection::TimingSource};
     let mut config = Config::default();
-    config.ntp.servers = vec!["127.0.0.1:1".to_string()]; // unreachable
+    config.ntp.servers = vec![ServerConfig::new("127.0.0.1:1")]; // unreachable
     config.ntp.require_sync = true;
     config.ws.update_interval_ms = 100;
     let config = Arc::new(config);
@@ -499,8 +542,8 @@ pub async fn spawn_server_holdover_seeded(epoch_ms: i64) -> TestServer {
 /// High uncertainty will cause `/time` to return 503.
 pub async fn spawn_server_synced_strict(upstream: &MockNtpUpstream) -> TestServer {
     let mut config = Config::default();
-    config.ntp.servers = vec![upstream.addr.to_string()];
-    config.ntp.timeout_secs = 5;
+    config.ntp.servers = vec![ServerConfig::new(&upstream.addr.to_string())];
+    config.ntp.timeout_ms = 5000;
     config.ntp.require_sync = true;
     config.ntp.selection.min_quorum = 1;
     config.ws.update_interval_ms = 100;
@@ -522,7 +565,7 @@ pub async fn spawn_server_synced_strict(upstream: &MockNtpUpstream) -> TestServe
 /// Spawn an HTTP server with admin API enabled but unsynced (no initial NTP sync).
 pub async fn spawn_server_admin_unsynced(admin_token: &str) -> TestServer {
     let mut config = Config::default();
-    config.ntp.servers = vec!["127.0.0.1:1".to_string()]; // unreachable
+    config.ntp.servers = vec![ServerConfig::new("127.0.0.1:1")]; // unreachable
     config.ntp.require_sync = true;
     config.ntp.selection.min_quorum = 1;
     config.ws.update_interval_ms = 100;
@@ -554,6 +597,21 @@ pub async fn start_ntp_server_component(state: &Arc<AppState>, config: &Config)
     ready_rx.await.expect("NTP server should bind and notify")
 }
 
+/// Start only the gRPC server component for `state` on an ephemeral port.
+/// Returns the actual bound address.
+pub async fn start_grpc_server_component(state: &Arc<AppState>) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = GrpcTimeService::new(state.clone()).router().unwrap();
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(tonic::transport::server::TcpIncoming::from(listener))
+            .await
+            .ok();
+    });
+    addr
+}
+
 // ── NTP UDP helpers ───────────────────────────────────────────────────────────
 
 /// Send a Mode 3 NTP request to `server_addr` and return the parsed response packet.
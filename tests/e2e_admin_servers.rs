@@ -0,0 +1,192 @@
+mod common;
+use common::{spawn_server_admin_unsynced, spawn_server_with_admin, start_mock_ntp_upstream};
+use serde_json::json;
+
+const TOKEN: &str = "test-secret-token-abc123";
+const FIXED_EPOCH_MS: i64 = 1_704_067_200_000; // 2024-01-01T00:00:00Z
+
+fn client() -> reqwest::Client {
+    reqwest::Client::new()
+}
+
+async fn list_servers(base_url: &str) -> Vec<String> {
+    let resp = client()
+        .get(format!("{base_url}/admin/servers"))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .expect("GET /admin/servers failed");
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    serde_json::from_value(body["servers"].clone()).unwrap()
+}
+
+async fn add_server(base_url: &str, entry: &str) -> reqwest::Response {
+    client()
+        .post(format!("{base_url}/admin/servers"))
+        .bearer_auth(TOKEN)
+        .json(&json!({ "server": entry, "operator": "ops" }))
+        .send()
+        .await
+        .expect("POST /admin/servers failed")
+}
+
+async fn remove_server(base_url: &str, addr: &str) -> reqwest::Response {
+    client()
+        .delete(format!("{base_url}/admin/servers/{addr}"))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .expect("DELETE /admin/servers failed")
+}
+
+#[tokio::test]
+async fn servers_can_be_added_and_removed_at_runtime() {
+    let upstream = start_mock_ntp_upstream(FIXED_EPOCH_MS).await;
+    let server = spawn_server_with_admin(&upstream, TOKEN, 60_000).await;
+    let upstream_addr = upstream.addr.to_string();
+
+    assert_eq!(
+        list_servers(&server.base_url).await,
+        vec![upstream_addr.clone()]
+    );
+
+    let resp = add_server(&server.base_url, "127.0.0.1:9;weight=2").await;
+    assert_eq!(resp.status(), 201);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["servers"], json!([upstream_addr, "127.0.0.1:9"]));
+
+    assert_eq!(
+        add_server(&server.base_url, "127.0.0.1:9").await.status(),
+        409
+    );
+
+    assert_eq!(
+        remove_server(&server.base_url, "127.0.0.1:9")
+            .await
+            .status(),
+        200
+    );
+    assert_eq!(
+        list_servers(&server.base_url).await,
+        vec![upstream_addr.clone()]
+    );
+    assert_eq!(
+        remove_server(&server.base_url, "127.0.0.1:9")
+            .await
+            .status(),
+        404
+    );
+}
+
+#[tokio::test]
+async fn invalid_keyed_and_last_server_changes_are_rejected() {
+    let upstream = start_mock_ntp_upstream(FIXED_EPOCH_MS).await;
+    let server = spawn_server_with_admin(&upstream, TOKEN, 60_000).await;
+
+    assert_eq!(
+        add_server(&server.base_url, "a:123;weight=0")
+            .await
+            .status(),
+        400
+    );
+    assert_eq!(
+        add_server(&server.base_url, "a:123;key=1").await.status(),
+        400
+    );
+
+    let resp = remove_server(&server.base_url, &upstream.addr.to_string()).await;
+    assert_eq!(resp.status(), 409);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"], "LastServer");
+}
+
+#[tokio::test]
+async fn server_admin_requires_token() {
+    let upstream = start_mock_ntp_upstream(FIXED_EPOCH_MS).await;
+    let server = spawn_server_with_admin(&upstream, TOKEN, 60_000).await;
+
+    let resp = client()
+        .post(format!("{}/admin/servers", server.base_url))
+        .json(&json!({ "server": "127.0.0.1:9" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let resp = client()
+        .delete(format!("{}/admin/servers/127.0.0.1:9", server.base_url))
+        .bearer_auth("wrong-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+}
+
+async fn force_sync(base_url: &str) -> reqwest::Response {
+    client()
+        .post(format!("{base_url}/admin/sync"))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .expect("POST /admin/sync failed")
+}
+
+#[tokio::test]
+async fn forced_sync_reports_the_outcome() {
+    let upstream = start_mock_ntp_upstream(FIXED_EPOCH_MS).await;
+    let server = spawn_server_with_admin(&upstream, TOKEN, 60_000).await;
+
+    let resp = force_sync(&server.base_url).await;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["sync"]["server"], upstream.addr.to_string());
+    assert_eq!(body["sync"]["stratum"], 1);
+    assert!(body["sync"]["offset_ms"].is_i64());
+    assert!(body["sync"]["rtt_ms"].is_u64());
+
+    // With the only upstream gone the sync fails and says why.
+    drop(upstream);
+    let resp = force_sync(&server.base_url).await;
+    assert_eq!(resp.status(), 502);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"], "SyncFailed");
+    assert!(body["message"].as_str().is_some_and(|m| !m.is_empty()));
+
+    let resp = client()
+        .post(format!("{}/admin/sync", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+}
+
+#[tokio::test]
+async fn forced_sync_without_a_sync_loop_is_unavailable() {
+    let server = spawn_server_admin_unsynced(TOKEN).await;
+    let resp = force_sync(&server.base_url).await;
+    assert_eq!(resp.status(), 503);
+}
+
+#[tokio::test]
+async fn effective_config_redacts_the_admin_token() {
+    let server = spawn_server_admin_unsynced(TOKEN).await;
+    let resp = client()
+        .get(format!("{}/admin/config", server.base_url))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .expect("GET /admin/config failed");
+    assert_eq!(resp.status(), 200);
+    let text = resp.text().await.unwrap();
+    assert!(!text.contains(TOKEN));
+    let body: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(body["admin"]["enabled"], true);
+    assert_eq!(body["admin"]["token"], "<redacted>");
+
+    let resp = client()
+        .get(format!("{}/admin/config", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+}
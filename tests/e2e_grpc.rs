@@ -0,0 +1,394 @@
+mod common;
+
+use futures_util::StreamExt;
+use ntp_time_json_api::grpc_service::proto::stream_control::Op;
+use ntp_time_json_api::grpc_service::proto::stream_event::Event;
+use ntp_time_json_api::grpc_service::proto::time_service_client::TimeServiceClient;
+use ntp_time_json_api::grpc_service::proto::{
+    GetTimeIntervalRequest, GetTimeRequest, Pause, Ping, Resume, SetInterval, StreamControl,
+    StreamEvent, StreamTimeRequest, Subscribe, TimeFormat,
+};
+use std::time::Duration;
+
+/// GetTime answers the synced time with its quality, like `/time`.
+#[tokio::test]
+async fn grpc_get_time_returns_synced_time() {
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced(&upstream).await;
+    let addr = common::start_grpc_server_component(&server.state).await;
+
+    let mut client = TimeServiceClient::connect(format!("http://{addr}"))
+        .await
+        .expect("gRPC connect failed");
+    let reply = client
+        .get_time(GetTimeRequest {})
+        .await
+        .expect("GetTime failed")
+        .into_inner();
+
+    assert!(reply.epoch_ms > 0);
+    assert_eq!(reply.epoch_ns.div_euclid(1_000_000), reply.epoch_ms);
+    assert!(!reply.iso8601.is_empty());
+    assert_eq!(reply.source, "ntp");
+    assert!(reply.stratum.is_some());
+}
+
+/// GetTimeInterval bounds the served time by its uncertainty.
+#[tokio::test]
+async fn grpc_get_time_interval_brackets_the_time() {
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced(&upstream).await;
+    let addr = common::start_grpc_server_component(&server.state).await;
+
+    let mut client = TimeServiceClient::connect(format!("http://{addr}"))
+        .await
+        .expect("gRPC connect failed");
+    let reply = client
+        .get_time_interval(GetTimeIntervalRequest {})
+        .await
+        .expect("GetTimeInterval failed")
+        .into_inner();
+
+    assert!(reply.uncertainty_ms > 0.0);
+    assert!(reply.earliest_ms < reply.epoch_ms && reply.epoch_ms < reply.latest_ms);
+    assert_eq!(
+        reply.latest_ms - reply.epoch_ms,
+        reply.uncertainty_ms.ceil() as i64
+    );
+    assert_eq!(reply.source, "ntp");
+}
+
+/// GetTime follows the serve policy: UNAVAILABLE before the first sync.
+#[tokio::test]
+async fn grpc_get_time_unavailable_when_unsynced() {
+    let server = common::spawn_server_unsynced().await;
+    let addr = common::start_grpc_server_component(&server.state).await;
+
+    let mut client = TimeServiceClient::connect(format!("http://{addr}"))
+        .await
+        .expect("gRPC connect failed");
+    let status = client.get_time(GetTimeRequest {}).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+    let status = client
+        .get_time_interval(GetTimeIntervalRequest {})
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+}
+
+/// StreamTime ticks on interval boundaries and ends on shutdown.
+#[tokio::test]
+async fn grpc_stream_time_ticks_until_shutdown() {
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced(&upstream).await;
+    let addr = common::start_grpc_server_component(&server.state).await;
+
+    let mut client = TimeServiceClient::connect(format!("http://{addr}"))
+        .await
+        .expect("gRPC connect failed");
+
+    let status = client
+        .stream_time(StreamTimeRequest { interval_ms: 1 })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let mut ticks = client
+        .stream_time(StreamTimeRequest { interval_ms: 100 })
+        .await
+        .expect("StreamTime failed")
+        .into_inner();
+    let mut next = async || {
+        tokio::time::timeout(Duration::from_secs(2), ticks.next())
+            .await
+            .expect("timed out waiting for tick")
+            .expect("stream ended")
+    };
+    let first = next().await.expect("tick");
+    let second = next().await.expect("tick");
+    assert!(second.sequence > first.sequence);
+    assert!(second.epoch_ms >= first.epoch_ms);
+
+    server.state.begin_shutdown();
+    let status = next().await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+}
+
+/// Every RPC shows up in /metrics: per-code counters, latency and the
+/// active-stream gauge, which drops back to 0 when a stream is cancelled.
+#[tokio::test]
+async fn grpc_calls_are_recorded_in_metrics() {
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced(&upstream).await;
+    let addr = common::start_grpc_server_component(&server.state).await;
+
+    let mut client = TimeServiceClient::connect(format!("http://{addr}"))
+        .await
+        .expect("gRPC connect failed");
+    client
+        .get_time(GetTimeRequest {})
+        .await
+        .expect("GetTime failed");
+    client
+        .stream_time(StreamTimeRequest { interval_ms: 1 })
+        .await
+        .unwrap_err();
+
+    let mut ticks = client
+        .stream_time(StreamTimeRequest { interval_ms: 100 })
+        .await
+        .expect("StreamTime failed")
+        .into_inner();
+    ticks.next().await.expect("stream ended").expect("tick");
+    let body = reqwest::get(format!("{}/metrics", server.base_url))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains(r#"grpc_requests_total{method="GetTime",code="OK"} 1"#));
+    assert!(body.contains(r#"grpc_requests_total{method="StreamTime",code="InvalidArgument"} 1"#));
+    assert!(body.contains(r#"grpc_request_duration_seconds_count{method="GetTime",code="OK"} 1"#));
+    assert!(body.contains(r#"grpc_active_streams{method="StreamTime"} 1"#));
+
+    // Dropping the stream cancels it on the server.
+    drop(ticks);
+    let cancelled = r#"grpc_requests_total{method="StreamTime",code="Cancelled"} 1"#;
+    for _ in 0..50 {
+        if server.state.metrics.encode().contains(cancelled) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let body = server.state.metrics.encode();
+    assert!(body.contains(cancelled), "{body}");
+    assert!(body.contains(r#"grpc_active_streams{method="StreamTime"} 0"#));
+}
+
+/// StreamTimeControlled follows the /stream control protocol: subscribe,
+/// set_interval, pause, resume and ping are acked, nacked or ponged, and the
+/// ticks follow the current settings.
+#[tokio::test]
+async fn grpc_stream_time_controlled_applies_controls() {
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced(&upstream).await;
+    let addr = common::start_grpc_server_component(&server.state).await;
+
+    let mut client = TimeServiceClient::connect(format!("http://{addr}"))
+        .await
+        .expect("gRPC connect failed");
+    let (controls, rx) = tokio::sync::mpsc::channel::<StreamControl>(4);
+    let requests = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|control| (control, rx))
+    });
+    let mut events = client
+        .stream_time_controlled(requests)
+        .await
+        .expect("StreamTimeControlled failed")
+        .into_inner();
+    let send = async |op: Op| {
+        controls
+            .send(StreamControl { op: Some(op) })
+            .await
+            .expect("control channel closed");
+    };
+    let mut next = async || -> Result<Event, tonic::Status> {
+        let event: StreamEvent = tokio::time::timeout(Duration::from_secs(3), events.next())
+            .await
+            .expect("timed out waiting for event")
+            .expect("stream ended")?;
+        Ok(event.event.expect("empty event"))
+    };
+    // The first control reply, skipping ticks sent before it.
+    async fn reply(next: &mut impl AsyncFnMut() -> Result<Event, tonic::Status>) -> Event {
+        loop {
+            match next().await.expect("stream failed") {
+                Event::Tick(_) => continue,
+                event => return event,
+            }
+        }
+    }
+
+    send(Op::Subscribe(Subscribe {
+        interval_ms: Some(100),
+        format: TimeFormat::Epoch.into(),
+    }))
+    .await;
+    let Event::Ack(ack) = reply(&mut next).await else {
+        panic!("expected ack");
+    };
+    assert_eq!(ack.op, "subscribe");
+    assert_eq!(ack.update_interval_ms, 100);
+    assert_eq!(ack.format(), TimeFormat::Epoch);
+    assert!(!ack.paused);
+    let Event::Tick(tick) = next().await.unwrap() else {
+        panic!("expected tick");
+    };
+    assert!(tick.epoch_ms > 0);
+    assert!(tick.iso8601.is_empty());
+    assert!(tick.sequence > 0);
+
+    send(Op::SetInterval(SetInterval { ms: 1 })).await;
+    let Event::Nack(nack) = reply(&mut next).await else {
+        panic!("expected nack");
+    };
+    assert_eq!(nack.op, "set_interval");
+
+    send(Op::Ping(Ping {})).await;
+    let Event::Pong(pong) = reply(&mut next).await else {
+        panic!("expected pong");
+    };
+    assert!(pong.epoch_ms.is_some());
+
+    send(Op::Pause(Pause {})).await;
+    let Event::Ack(ack) = reply(&mut next).await else {
+        panic!("expected ack");
+    };
+    assert!(ack.paused);
+    assert!(
+        tokio::time::timeout(Duration::from_millis(300), next())
+            .await
+            .is_err(),
+        "paused stream kept ticking"
+    );
+
+    send(Op::Resume(Resume {})).await;
+    let Event::Ack(ack) = reply(&mut next).await else {
+        panic!("expected ack");
+    };
+    assert_eq!(ack.op, "resume");
+    assert!(matches!(next().await.unwrap(), Event::Tick(_)));
+
+    server.state.begin_shutdown();
+    let status = loop {
+        match next().await {
+            Ok(_) => continue,
+            Err(status) => break status,
+        }
+    };
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+}
+
+/// With `GRPC_API_KEYS` set, the streams need a key (as `x-api-key` or a
+/// bearer token) while GetTime stays open under the default `streams` scope.
+#[tokio::test]
+async fn grpc_streams_require_an_api_key() {
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced_with_grpc(&upstream, |grpc| {
+        grpc.api_keys = vec!["k3y".into()];
+    })
+    .await;
+    let addr = common::start_grpc_server_component(&server.state).await;
+
+    let mut client = TimeServiceClient::connect(format!("http://{addr}"))
+        .await
+        .expect("gRPC connect failed");
+    client
+        .get_time(GetTimeRequest {})
+        .await
+        .expect("GetTime should not need a key");
+
+    let stream_request = |header: Option<(&'static str, &'static str)>| {
+        let mut request = tonic::Request::new(StreamTimeRequest { interval_ms: 100 });
+        if let Some((key, value)) = header {
+            request.metadata_mut().insert(key, value.parse().unwrap());
+        }
+        request
+    };
+    for header in [None, Some(("x-api-key", "wrong"))] {
+        let status = client
+            .stream_time(stream_request(header))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+    for header in [("x-api-key", "k3y"), ("authorization", "Bearer k3y")] {
+        let mut ticks = client
+            .stream_time(stream_request(Some(header)))
+            .await
+            .expect("StreamTime failed")
+            .into_inner();
+        ticks.next().await.expect("stream ended").expect("tick");
+    }
+
+    let body = server.state.metrics.encode();
+    assert!(
+        body.contains(r#"grpc_requests_total{method="StreamTime",code="Unauthenticated"} 2"#),
+        "{body}"
+    );
+}
+
+/// With `GRPC_TLS_CLIENT_CA_FILE`, a client certificate signed by that CA
+/// authenticates the streams; a TLS client without one is refused.
+#[tokio::test]
+async fn grpc_streams_accept_mtls_client_certificates() {
+    use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+
+    let dir = std::env::temp_dir().join(format!("grpc-mtls-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut ca_params = rcgen::CertificateParams::new(Vec::new()).unwrap();
+    ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    let ca = rcgen::CertifiedIssuer::self_signed(ca_params, rcgen::KeyPair::generate().unwrap())
+        .unwrap();
+    let issue = |name: &str| {
+        let key = rcgen::KeyPair::generate().unwrap();
+        let cert = rcgen::CertificateParams::new(vec![name.to_string()])
+            .unwrap()
+            .signed_by(&key, &ca)
+            .unwrap();
+        (cert.pem(), key.serialize_pem())
+    };
+    let (server_cert, server_key) = issue("localhost");
+    let (client_cert, client_key) = issue("client.example");
+    let write = |name: &str, pem: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, pem).unwrap();
+        Some(path.to_str().unwrap().to_string())
+    };
+
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced_with_grpc(&upstream, |grpc| {
+        grpc.tls_cert_file = write("cert.pem", &server_cert);
+        grpc.tls_key_file = write("key.pem", &server_key);
+        grpc.tls_client_ca_file = write("ca.pem", &ca.pem());
+    })
+    .await;
+    let addr = common::start_grpc_server_component(&server.state).await;
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let connect = async |identity: Option<Identity>| {
+        let mut tls = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(ca.pem()))
+            .domain_name("localhost");
+        if let Some(identity) = identity {
+            tls = tls.identity(identity);
+        }
+        let channel = Channel::from_shared(format!("https://{addr}"))
+            .unwrap()
+            .tls_config(tls)
+            .unwrap()
+            .connect()
+            .await
+            .expect("gRPC TLS connect failed");
+        TimeServiceClient::new(channel)
+    };
+
+    let mut anonymous = connect(None).await;
+    anonymous
+        .get_time(GetTimeRequest {})
+        .await
+        .expect("GetTime should not need a certificate");
+    let status = anonymous
+        .stream_time(StreamTimeRequest { interval_ms: 100 })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    let mut client = connect(Some(Identity::from_pem(client_cert, client_key))).await;
+    let mut ticks = client
+        .stream_time(StreamTimeRequest { interval_ms: 100 })
+        .await
+        .expect("StreamTime failed")
+        .into_inner();
+    ticks.next().await.expect("stream ended").expect("tick");
+}
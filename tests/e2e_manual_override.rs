@@ -575,6 +575,51 @@ async fn metrics_show_manual_override_active_gauge() {
     );
 }
 
+/// `time_quality_score` value in a `/metrics` body.
+async fn quality_score_gauge(base_url: &str) -> f64 {
+    let text = reqwest::get(format!("{base_url}/metrics"))
+        .await
+        .expect("GET /metrics failed")
+        .text()
+        .await
+        .unwrap();
+    text.lines()
+        .find_map(|line| line.strip_prefix("time_quality_score "))
+        .expect("time_quality_score exported")
+        .parse()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn quality_score_gauge_resets_under_manual_override() {
+    let upstream = start_mock_ntp_upstream(FIXED_EPOCH_MS).await;
+    let server = spawn_server_with_admin(&upstream, TOKEN, 100_000).await;
+    assert!(quality_score_gauge(&server.base_url).await > 0.0);
+
+    post_override(
+        &server.base_url,
+        TOKEN,
+        &serde_json::json!({
+            "epoch_ms": FIXED_EPOCH_MS,
+            "reason": "quality score test",
+            "ttl_seconds": 60
+        }),
+    )
+    .await;
+    // /status reports no score under an override; the gauge must not keep the NTP one
+    let status: serde_json::Value = reqwest::get(format!("{}/status", server.base_url))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(status["quality_score"].is_null());
+    assert_eq!(quality_score_gauge(&server.base_url).await, 0.0);
+
+    delete_override(&server.base_url, TOKEN).await;
+    assert!(quality_score_gauge(&server.base_url).await > 0.0);
+}
+
 #[tokio::test]
 async fn force_true_allowed_when_allow_force_true() {
     let upstream = start_mock_ntp_upstream(FIXED_EPOCH_MS).await;
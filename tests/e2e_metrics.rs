@@ -231,9 +231,12 @@ async fn metrics_replica_source_mode_reflects_manual_override() {
             min_quorum: 1,
             weighted_median_offset_ms: Some(0.0),
             candidate_lambdas: vec![(upstream.addr.to_string(), 5.0)],
+            candidates: vec![],
+            sticky: None,
             intersection: IntersectionDiagnostics::disabled(),
         },
         jitter_ms: 0,
+        fallback_tier: false,
     };
     common::apply_sync_to_state(&server.state, &outcome);
 
@@ -115,12 +115,12 @@ async fn ntp_server_echoes_origin_timestamp() {
 /// to signal "I am not authoritative".
 #[tokio::test]
 async fn ntp_server_unsynced_response() {
-    use ntp_time_json_api::config::Config;
+    use ntp_time_json_api::config::{Config, ServerConfig};
     use std::sync::Arc;
 
     // Build state without any NTP sync.
     let mut config = Config::default();
-    config.ntp.servers = vec!["127.0.0.1:1".into()]; // unreachable
+    config.ntp.servers = vec![ServerConfig::new("127.0.0.1:1")]; // unreachable
     let config = Arc::new(config);
 
     use ntp_time_json_api::{
@@ -133,7 +133,12 @@ async fn ntp_server_unsynced_response() {
         config.messages.ok.clone(),
         config.messages.ok_cache.clone(),
     ));
-    let timebase = TimeBase::new(config.ntp.monotonic_output).with_cache(time_cache.clone());
+    let timebase = TimeBase::new(config.ntp.monotonic_output)
+        .with_monotonic_scope(config.ntp.monotonic_scope)
+        .with_slew(config.ntp.slew_rate_ppm, config.ntp.slew_max_offset_ms)
+        .with_max_step(config.ntp.max_step_ms, config.ntp.max_step_stepout)
+        .with_drift_compensation(config.ntp.drift_compensation)
+        .with_cache(time_cache.clone());
     let metrics = Arc::new(Metrics::new());
     let state = Arc::new(AppState::new(
         config.clone(),
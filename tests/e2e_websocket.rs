@@ -33,6 +33,11 @@ async fn websocket_delivers_welcome_and_tick() {
         "first message must be type=welcome"
     );
     assert!(welcome["update_interval_ms"].is_number());
+    assert!(
+        welcome["last_sync"]["server"].is_string(),
+        "welcome must describe the last sync"
+    );
+    assert!(welcome["last_sync"]["synced_at_ms"].is_number());
 
     // Second message must be a "tick" (interval is 100 ms in the test config)
     let msg = tokio::time::timeout(Duration::from_secs(2), read.next())
@@ -101,3 +106,228 @@ async fn websocket_ticks_are_monotonic() {
         prev_epoch = epoch;
     }
 }
+
+/// Query parameters pick the tick interval and body per connection.
+#[tokio::test]
+async fn websocket_honours_stream_query_parameters() {
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced(&upstream).await;
+
+    let ws_url = format!(
+        "ws://{}/stream?interval_ms=200&format=epoch&max_duration=60",
+        server.http_addr
+    );
+    let (ws_stream, _) = connect_async(&ws_url)
+        .await
+        .expect("WebSocket connection failed");
+    let (_, mut read) = ws_stream.split();
+
+    let msg = tokio::time::timeout(Duration::from_secs(2), read.next())
+        .await
+        .expect("timed out waiting for welcome")
+        .expect("stream ended")
+        .expect("WS error");
+    let welcome: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+    assert_eq!(welcome["update_interval_ms"], 200);
+    assert_eq!(welcome["max_duration_secs"], 60);
+    assert_eq!(welcome["format"], "epoch");
+
+    let msg = tokio::time::timeout(Duration::from_secs(2), read.next())
+        .await
+        .expect("timed out waiting for tick")
+        .expect("stream ended")
+        .expect("WS error");
+    let tick: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+    assert_eq!(tick["type"], "tick");
+    assert!(tick["epoch_ms"].as_i64().unwrap_or(0) > 0);
+    assert!(tick.get("iso8601").is_none(), "epoch format is lean");
+
+    // An interval below WS_MIN_UPDATE_INTERVAL_MS is refused before the upgrade
+    let bad_url = format!("ws://{}/stream?interval_ms=1", server.http_addr);
+    match connect_async(&bad_url).await {
+        Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+            assert_eq!(response.status(), 400);
+        }
+        other => panic!("expected 400, got {:?}", other.map(|_| ())),
+    }
+}
+
+/// Handshakes beyond WS_MAX_CONNECTIONS are refused with 429 until a slot frees up.
+#[tokio::test]
+async fn websocket_connection_limit_rejects_with_429() {
+    let server = common::spawn_server_with_ws(|ws| ws.max_connections = 1).await;
+    let ws_url = format!("ws://{}/stream", server.http_addr);
+
+    let (first, _) = connect_async(&ws_url)
+        .await
+        .expect("first connection must be accepted");
+
+    match connect_async(&ws_url).await {
+        Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+            assert_eq!(response.status(), 429);
+        }
+        other => panic!("expected 429, got {:?}", other.map(|_| ())),
+    }
+    assert_eq!(server.state.metrics.websocket_rejected_total.get(), 1);
+
+    // Closing the first connection frees its slot
+    drop(first);
+    let mut accepted = false;
+    for _ in 0..20 {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        if connect_async(&ws_url).await.is_ok() {
+            accepted = true;
+            break;
+        }
+    }
+    assert!(accepted, "slot was not released after the client left");
+}
+
+/// A client that sends nothing for WS_IDLE_TIMEOUT_SECS is sent a Close frame.
+#[tokio::test]
+async fn websocket_idle_client_is_closed() {
+    let server = common::spawn_server_with_ws(|ws| {
+        ws.ping_interval_secs = 0;
+        ws.idle_timeout_secs = 1;
+    })
+    .await;
+    let ws_url = format!("ws://{}/stream", server.http_addr);
+    let (ws_stream, _) = connect_async(&ws_url)
+        .await
+        .expect("WebSocket connection failed");
+    let (_, mut read) = ws_stream.split();
+
+    let close = tokio::time::timeout(Duration::from_secs(5), async {
+        while let Some(Ok(msg)) = read.next().await {
+            if let tokio_tungstenite::tungstenite::Message::Close(frame) = msg {
+                return frame;
+            }
+        }
+        None
+    })
+    .await
+    .expect("idle connection was not closed");
+    assert_eq!(close.expect("close frame").reason, "Idle timeout");
+}
+
+/// On shutdown every open stream is sent Close 1001 "Server shutting down".
+#[tokio::test]
+async fn websocket_is_closed_on_shutdown() {
+    let server = common::spawn_server_with_ws(|_| {}).await;
+    let ws_url = format!("ws://{}/stream", server.http_addr);
+    let (ws_stream, _) = connect_async(&ws_url)
+        .await
+        .expect("WebSocket connection failed");
+    let (_, mut read) = ws_stream.split();
+
+    // Skip welcome
+    tokio::time::timeout(Duration::from_secs(2), read.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    server.state.begin_shutdown();
+    let close = tokio::time::timeout(Duration::from_secs(2), async {
+        while let Some(Ok(msg)) = read.next().await {
+            if let tokio_tungstenite::tungstenite::Message::Close(frame) = msg {
+                return frame;
+            }
+        }
+        None
+    })
+    .await
+    .expect("stream was not closed on shutdown")
+    .expect("close frame");
+    assert_eq!(u16::from(close.code), 1001);
+    assert_eq!(close.reason, "Server shutting down");
+}
+
+/// `?resume_from=` opens the stream with the number of ticks missed since.
+#[tokio::test]
+async fn websocket_resume_reports_missed_ticks() {
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced(&upstream).await;
+
+    let ws_url = format!("ws://{}/stream?resume_from=5", server.http_addr);
+    let (ws_stream, _) = connect_async(&ws_url)
+        .await
+        .expect("WebSocket connection failed");
+    let (_, mut read) = ws_stream.split();
+
+    let mut next = async || {
+        let msg = tokio::time::timeout(Duration::from_secs(2), read.next())
+            .await
+            .expect("timed out")
+            .expect("stream ended")
+            .expect("WS error");
+        serde_json::from_str::<serde_json::Value>(msg.to_text().unwrap()).unwrap()
+    };
+    assert_eq!(next().await["type"], "welcome");
+
+    let resume = next().await;
+    assert_eq!(resume["type"], "resume");
+    assert_eq!(resume["resume_from"], 5);
+    let sequence = resume["sequence"].as_u64().unwrap();
+    assert_eq!(resume["missed"].as_u64().unwrap(), sequence - 6);
+
+    // The stream continues with the announced sequence
+    let tick = next().await;
+    assert_eq!(tick["type"], "tick");
+    assert_eq!(tick["sequence"].as_u64(), Some(sequence));
+}
+
+/// Control messages change the stream without reconnecting.
+#[tokio::test]
+async fn websocket_control_messages() {
+    use futures_util::SinkExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let upstream = common::start_mock_ntp_upstream(1_704_067_200_000).await;
+    let server = common::spawn_server_synced(&upstream).await;
+    let ws_url = format!("ws://{}/stream", server.http_addr);
+    let (ws_stream, _) = connect_async(&ws_url)
+        .await
+        .expect("WebSocket connection failed");
+    let (mut write, mut read) = ws_stream.split();
+
+    // Next message of `kind`, skipping ticks
+    let mut next_of = async |kind: &str| loop {
+        let msg = tokio::time::timeout(Duration::from_secs(2), read.next())
+            .await
+            .expect("timed out")
+            .expect("stream ended")
+            .expect("WS error");
+        let value: serde_json::Value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
+        if value["type"] == kind {
+            return value;
+        }
+    };
+    next_of("welcome").await;
+
+    write.send(Message::text(r#"{"op":"ping"}"#)).await.unwrap();
+    assert!(next_of("pong").await["epoch_ms"].as_i64().unwrap() > 0);
+
+    write
+        .send(Message::text(r#"{"op":"set_interval","ms":200}"#))
+        .await
+        .unwrap();
+    let ack = next_of("ack").await;
+    assert_eq!(ack["op"], "set_interval");
+    assert_eq!(ack["update_interval_ms"], 200);
+    assert_eq!(ack["paused"], false);
+
+    write
+        .send(Message::text(r#"{"op":"pause"}"#))
+        .await
+        .unwrap();
+    assert_eq!(next_of("ack").await["paused"], true);
+
+    write
+        .send(Message::text(r#"{"op":"set_interval","ms":1}"#))
+        .await
+        .unwrap();
+    let nack = next_of("nack").await;
+    assert_eq!(nack["op"], "set_interval");
+    assert!(nack["error"].as_str().unwrap().contains("interval_ms"));
+}